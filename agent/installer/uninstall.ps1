@@ -0,0 +1,28 @@
+#Requires -RunAsAdministrator
+
+Write-Host "=======================================================" -ForegroundColor Cyan
+Write-Host "  AssetScan Agent - Desinstalacao" -ForegroundColor Cyan
+Write-Host "=======================================================" -ForegroundColor Cyan
+Write-Host ""
+
+$InstallPath = "C:\Program Files\AssetScan"
+$TaskName = "AssetScanAgent"
+
+# 1. Parar e remover a tarefa agendada
+Write-Host "[1/3] Removendo Tarefa Agendada..." -ForegroundColor Yellow
+Stop-ScheduledTask -TaskName $TaskName -ErrorAction SilentlyContinue
+Unregister-ScheduledTask -TaskName $TaskName -Confirm:$false -ErrorAction SilentlyContinue
+Stop-Process -Name "assetscan-agent" -Force -ErrorAction SilentlyContinue
+
+# 2. Reverter alteracoes do agente (bloco do ficheiro hosts, etc.)
+Write-Host "[2/3] Revertendo alteracoes do sistema..." -ForegroundColor Yellow
+if (Test-Path "$InstallPath\assetscan-agent.exe") {
+    & "$InstallPath\assetscan-agent.exe" --cleanup
+}
+
+# 3. Remover ficheiros e configuracao
+Write-Host "[3/3] Removendo ficheiros..." -ForegroundColor Yellow
+Remove-Item -Path $InstallPath -Recurse -Force -ErrorAction SilentlyContinue
+Remove-Item -Path "HKLM:\SOFTWARE\AssetScan" -Recurse -Force -ErrorAction SilentlyContinue
+
+Write-Host "`n[SUCESSO] Agente desinstalado." -ForegroundColor Green
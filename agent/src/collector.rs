@@ -1,31 +1,54 @@
 use anyhow::Result;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use sysinfo::{Disks, System, ProcessesToUpdate};
-use winreg::enums::*;
-use winreg::RegKey;
 use chrono::Utc;
+use crate::device_control;
+use crate::dns_filter;
+use crate::enforcer;
 use crate::hardware_collector;
+use crate::monitor;
 use crate::network_collector;
+use crate::platform;
 use crate::security_collector;
 use crate::screen_time_tracker::ScreenTimeEntry;
-use crate::wmi_collector; // NOVO!
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SystemReport {
     pub agent_version: String,
     pub hostname: String,
     pub machine_id: String,
-    pub local_ip: String,
+    pub machine_identity: Option<MachineIdentity>, // Âncoras de hardware para detectar clones
+    pub local_ip: Option<String>,
     pub collected_at: String,
     pub hardware: HardwareInfo,
     pub hardware_details: hardware_collector::HardwareDetails, // NOVO!
     pub network_details: network_collector::NetworkDetails, // NOVO!
     pub security_status: security_collector::SecurityStatus, // NOVO!
     pub software: Vec<SoftwareEntry>,
+    pub services: Vec<platform::Service>,
     pub processes: Vec<ProcessInfo>,
-    pub network: Vec<network_collector::NetworkConnection>,
+    pub network_connections: Vec<network_collector::NetworkConnection>,
+    pub neighbors: Option<Vec<network_collector::NetworkNeighbor>>, // Só com report_neighbors
     pub screen_time: Vec<ScreenTimeEntry>,
+    pub current_user: Option<String>, // Utilizador com sessão na consola
     pub os: OsInfo,
+    pub policy_revisions: Vec<enforcer::PolicyRevisionState>,
+    pub enforcement: Vec<enforcer::EnforcementResult>,
+    pub app_violations: Vec<enforcer::AppViolation>,
+    pub policy_matches: Vec<monitor::PolicyMatch>,
+    pub dns_stats: Vec<dns_filter::DnsQueryStat>,
+    pub usb_devices: Vec<hardware_collector::UsbDevice>,
+    pub device_events: Vec<device_control::DeviceEvent>,
+}
+
+/// Âncoras de hardware e a sua impressão digital; o servidor compara-a com a que já
+/// conhece para o machine_id (clone) e com as de outras máquinas (reinstalação)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MachineIdentity {
+    #[serde(flatten)]
+    pub anchors: platform::HardwareAnchors,
+    pub fingerprint: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -83,32 +106,73 @@ pub fn collect_full_report(config: &crate::config::Config) -> Result<SystemRepor
     
     // CORREÇÃO: Usar os nomes exatos das tuas funções originais
     let os_info = collect_os(&sys); 
-    let software = collect_software().unwrap_or_default(); 
+    let software = collect_software().unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao listar software instalado: {}", e);
+        vec![]
+    });
+    let services = platform::current().services().unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao listar serviços: {}", e);
+        vec![]
+    });
     let processes = collect_processes(&sys);
     
-    // Coleta avançada via WMI
-    let advanced_info = wmi_collector::collect_wmi_data();
-    
-    // Obtém IP (tentativa via UDP socket, placeholder no network_collector)
+    // Domínio e utilizador da consola (WMI no Windows, sessões systemd no Linux)
+    let identity = platform::current().computer_identity().unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao ler domínio e utilizador: {}", e);
+        platform::ComputerIdentity::default()
+    });
+
+    // IP de saída (o SO escolhe a interface da rota para fora)
     let hostname = System::host_name().unwrap_or_else(|| "Unknown".to_string());
-    let local_ip = network_collector::get_local_ip()
-        .unwrap_or_else(|_| "127.0.0.1".to_string());
-    // MAC Address temporário (pode ser refinado depois)
-    let mac_address = "00:00:00:00:00:00".to_string(); 
+    let local_ip = network_collector::get_local_ip();
 
-    let hardware_details = hardware_collector::collect_hardware_details()
-        .unwrap_or_else(|_| default_hardware_details());
-    
-    let network_details = network_collector::collect_network_details()
-        .unwrap_or_else(|_| default_network_details());
+    let hardware_details = hardware_collector::collect_hardware_details().unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao ler hardware: {}", e);
+        default_hardware_details()
+    });
+
+    let network_details = network_collector::collect_network_details(&identity).unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao ler configuração de rede: {}", e);
+        network_collector::NetworkDetails::default()
+    });
     
     let security_status = security_collector::collect_security_status()
         .unwrap_or_else(|_| default_security_status());
 
+    let process_names: HashMap<u32, String> = sys
+        .processes()
+        .iter()
+        .map(|(pid, p)| (pid.as_u32(), p.name().to_string_lossy().to_string()))
+        .collect();
+    let network_connections = network_collector::collect_connections(&process_names)
+        .unwrap_or_else(|e| {
+            eprintln!("[Coleta] Erro ao listar ligações de rede: {}", e);
+            vec![]
+        });
+
+    let machine_identity = match platform::current().hardware_anchors() {
+        Ok(anchors) => Some(MachineIdentity {
+            fingerprint: platform::identity::fingerprint(&anchors),
+            anchors,
+        }),
+        Err(e) => {
+            eprintln!("[Coleta] Erro ao ler as âncoras de hardware: {}", e);
+            None
+        }
+    };
+
+    let neighbors = config.report_neighbors.then(|| {
+        network_collector::collect_neighbors().unwrap_or_else(|e| {
+            eprintln!("[Coleta] Erro ao ler a tabela de vizinhos: {}", e);
+            vec![]
+        })
+    });
+
     Ok(SystemReport {
         agent_version: "3.0.0".to_string(),
         hostname,
         machine_id,
+        machine_identity,
         local_ip,
         collected_at: Utc::now().to_rfc3339(),
         hardware: collect_hardware(&sys),
@@ -116,31 +180,23 @@ pub fn collect_full_report(config: &crate::config::Config) -> Result<SystemRepor
         network_details,
         security_status,
         os: collect_os(&sys),
-        software: collect_software()?,
+        software,
+        services,
         processes: collect_processes(&sys),
-        network: vec![], // Mantém vazio por agora
+        network_connections,
+        neighbors,
         screen_time: vec![], // Preenchido no main.rs
+        current_user: identity.console_user,
+        policy_revisions: vec![], // Preenchido no main.rs
+        enforcement: vec![], // Preenchido no main.rs
+        app_violations: vec![], // Preenchido no main.rs
+        policy_matches: vec![], // Preenchido no main.rs
+        dns_stats: vec![], // Preenchido no main.rs
+        usb_devices: vec![], // Preenchido no main.rs
+        device_events: vec![], // Preenchido no main.rs
     })
 }
 
-fn default_network_details() -> network_collector::NetworkDetails {
-    network_collector::NetworkDetails {
-        local_ip: "127.0.0.1".to_string(),
-        subnet_mask: "255.255.255.0".to_string(),
-        gateway: "192.168.1.1".to_string(),
-        dns_primary: "8.8.8.8".to_string(),
-        dns_secondary: None,
-        dhcp_enabled: true,
-        domain_name: "WORKGROUP".to_string(),
-        is_domain_joined: false,
-        mac_address: "00-00-00-00-00-00".to_string(),
-        adapter_name: "Unknown".to_string(),
-        connection_speed_mbps: 0,
-        wifi_ssid: None,
-        wifi_security: None,
-    }
-}
-
 fn default_security_status() -> security_collector::SecurityStatus {
     security_collector::SecurityStatus {
         windows_defender_enabled: false,
@@ -163,6 +219,11 @@ fn default_hardware_details() -> hardware_collector::HardwareDetails {
         total_ram_slots: 0,
         used_ram_slots: 0,
         ram_type: "Unknown".to_string(),
+        memory_modules: None,
+        gpus: None,
+        monitors: None,
+        batteries: None,
+        physical_disks: None,
     }
 }
 
@@ -217,37 +278,17 @@ fn collect_os(_sys: &System) -> OsInfo {
     }
 }
 
-fn collect_software() -> Result<Vec<SoftwareEntry>> {
-    let mut list: Vec<SoftwareEntry> = Vec::new();
-
-    let paths = [
-        (HKEY_LOCAL_MACHINE, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
-        (HKEY_LOCAL_MACHINE, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall"),
-        (HKEY_CURRENT_USER, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
-    ];
-
-    for (hive, path) in &paths {
-        let root = RegKey::predef(*hive);
-        let Ok(key) = root.open_subkey(path) else { continue };
-
-        for subkey_name in key.enum_keys().flatten() {
-            let Ok(sub) = key.open_subkey(&subkey_name) else { continue };
-
-            let Ok(name): Result<String, _> = sub.get_value("DisplayName") else { continue };
-            if name.is_empty() { continue; }
-
-            let version: String = sub.get_value("DisplayVersion").unwrap_or_default();
-            let publisher: String = sub.get_value("Publisher").unwrap_or_default();
-            let install_date: String = sub.get_value("InstallDate").unwrap_or_default();
-
-            list.push(SoftwareEntry {
-                name,
-                version,
-                publisher,
-                install_date,
-            });
-        }
-    }
+fn collect_software() -> platform::Result<Vec<SoftwareEntry>> {
+    let mut list: Vec<SoftwareEntry> = platform::current()
+        .installed_software()?
+        .into_iter()
+        .map(|s| SoftwareEntry {
+            name: s.name,
+            version: s.version,
+            publisher: s.publisher,
+            install_date: s.install_date,
+        })
+        .collect();
 
     list.sort_by(|a, b| a.name.cmp(&b.name));
     list.dedup_by(|a, b| a.name == b.name);
@@ -276,19 +317,6 @@ fn collect_processes(sys: &System) -> Vec<ProcessInfo> {
         .collect()
 }
 
-fn get_or_create_machine_id() -> Result<String> {
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    
-    if let Ok(key) = hklm.open_subkey(r"SOFTWARE\AssetScan") {
-        if let Ok(id) = key.get_value::<String, _>("MachineID") {
-            return Ok(id);
-        }
-    }
-
-    let new_id = uuid::Uuid::new_v4().to_string();
-    
-    let (key, _) = hklm.create_subkey(r"SOFTWARE\AssetScan")?;
-    key.set_value("MachineID", &new_id)?;
-
-    Ok(new_id)
-}
\ No newline at end of file
+pub fn get_or_create_machine_id() -> Result<String> {
+    Ok(platform::current().machine_id()?)
+}
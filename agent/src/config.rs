@@ -1,54 +1,73 @@
 // ============================================================
 // config.rs — Gestão de Configuração do Agente
-// Lê do Registry e de variáveis de ambiente
+// Lê da configuração do sistema (Registry no Windows,
+// /etc/assetscan/agent.toml no Linux) e de variáveis de ambiente
 // ============================================================
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use winreg::enums::*;
-use winreg::RegKey;
 use std::env;
+use std::path::PathBuf;
+
+use crate::hosts_file::HostsFile;
+use crate::platform;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)] // No agent.toml só server_url e api_key são obrigatórios
 pub struct Config {
     pub server_url: String,
     pub api_key: String,
     pub interval_minutes: u64,
     pub enforcement_enabled: bool,
+    /// Caminho alternativo do ficheiro hosts (por omissão, o do sistema)
+    #[serde(default)]
+    pub hosts_path: Option<String>,
+    /// Resolver DNS local com filtragem em 127.0.0.1:53. No Windows o agente aponta o DNS
+    /// dos adaptadores para ele; no Linux isso fica a cargo do administrador
+    #[serde(default)]
+    pub dns_filter_enabled: bool,
+    /// Servidores DNS para onde o resolver local reencaminha. Vazio (por omissão): os
+    /// servidores que o sistema usava antes do filtro, para os nomes internos e do AD
+    /// continuarem a resolver; DNS públicos só se configurados aqui
+    #[serde(default)]
+    pub dns_upstream: Vec<String>,
+    /// Long-poll ao servidor para acções imediatas (sem ele, polling periódico)
+    #[serde(default = "default_push_enabled")]
+    pub push_enabled: bool,
+    /// Envia a tabela ARP/NDP para o inventário de equipamentos descobertos
+    #[serde(default)]
+    pub report_neighbors: bool,
+    /// Chave pública (hex) com que o servidor assina os scripts aprovados. Sem ela,
+    /// o agente fixa a chave que o servidor apresentar no primeiro script
+    #[serde(default)]
+    pub script_public_key: Option<String>,
+}
+
+fn default_push_enabled() -> bool {
+    true
+}
+
+pub(crate) fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 impl Config {
-    /// Carrega configuração do Registry ou de variáveis de ambiente
+    /// Carrega a configuração do sistema ou de variáveis de ambiente
     pub fn load() -> Result<Self> {
-        // Tenta carregar do Registry primeiro
-        if let Ok(config) = Self::load_from_registry() {
-            return Ok(config);
+        match platform::current().load_config() {
+            Ok(Some(config)) => return Ok(config),
+            Ok(None) => {}
+            Err(e) => eprintln!("[Config] {}; a usar variáveis de ambiente", e),
         }
 
         // Fallback para variáveis de ambiente
         Self::load_from_env()
     }
 
-    fn load_from_registry() -> Result<Self> {
-        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-        let key = hklm.open_subkey(r"SOFTWARE\AssetScan\Config")
-            .context("Chave de configuração não encontrada no Registry")?;
-
-        let server_url: String = key.get_value("ServerURL")?;
-        let api_key: String = key.get_value("APIKey")?;
-        let interval_minutes: u64 = key.get_value::<u32, _>("IntervalMinutes")
-            .unwrap_or(60) as u64;
-        let enforcement_enabled: u32 = key.get_value("EnforcementEnabled")
-            .unwrap_or(1);
-
-        Ok(Config {
-            server_url,
-            api_key,
-            interval_minutes,
-            enforcement_enabled: enforcement_enabled != 0,
-        })
-    }
-
     fn load_from_env() -> Result<Self> {
         Ok(Config {
             server_url: env::var("ASSETSCAN_SERVER")
@@ -62,20 +81,89 @@ impl Config {
             enforcement_enabled: env::var("ASSETSCAN_ENFORCEMENT")
                 .map(|s| s == "1" || s.to_lowercase() == "true")
                 .unwrap_or(true),
+            hosts_path: env::var("ASSETSCAN_HOSTS_PATH").ok(),
+            dns_filter_enabled: env::var("ASSETSCAN_DNS_FILTER")
+                .map(|s| s == "1" || s.to_lowercase() == "true")
+                .unwrap_or(false),
+            dns_upstream: env::var("ASSETSCAN_DNS_UPSTREAM")
+                .map(|v| parse_list(&v))
+                .unwrap_or_default(),
+            push_enabled: env::var("ASSETSCAN_PUSH")
+                .map(|s| s == "1" || s.to_lowercase() == "true")
+                .unwrap_or(true),
+            report_neighbors: env::var("ASSETSCAN_REPORT_NEIGHBORS")
+                .map(|s| s == "1" || s.to_lowercase() == "true")
+                .unwrap_or(false),
+            script_public_key: env::var("ASSETSCAN_SCRIPT_KEY").ok(),
         })
     }
+}
 
-    /// Salva configuração no Registry
-    pub fn save(&self) -> Result<()> {
-        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-        let (key, _) = hklm.create_subkey(r"SOFTWARE\AssetScan\Config")?;
+impl Config {
+    /// Hostname do servidor (nunca filtrado pelo resolver DNS local)
+    pub fn server_host(&self) -> Option<String> {
+        let without_scheme = self.server_url.split("://").last()?;
+        let host = without_scheme.split(['/', ':']).next()?;
+        (!host.is_empty()).then(|| host.to_string())
+    }
+
+    /// Porta do servidor (explícita no URL ou a do esquema)
+    pub fn server_port(&self) -> u16 {
+        let without_scheme = self.server_url.split("://").last().unwrap_or_default();
+        let authority = without_scheme.split('/').next().unwrap_or_default();
+        match authority.rsplit_once(':').and_then(|(_, port)| port.parse().ok()) {
+            Some(port) => port,
+            None if self.server_url.starts_with("https://") => 443,
+            None => 80,
+        }
+    }
 
-        key.set_value("ServerURL", &self.server_url)?;
-        key.set_value("APIKey", &self.api_key)?;
-        key.set_value("IntervalMinutes", &(self.interval_minutes as u32))?;
-        key.set_value("EnforcementEnabled", &(if self.enforcement_enabled { 1u32 } else { 0u32 }))?;
+    /// Ficheiro hosts gerido pelo enforcer
+    pub fn hosts_file(&self) -> HostsFile {
+        match &self.hosts_path {
+            Some(path) => HostsFile::new(path),
+            None => HostsFile::new(HostsFile::system_path()),
+        }
+    }
+}
+
+/// Pasta de dados do agente (cache de políticas, estado local)
+pub fn data_dir() -> PathBuf {
+    #[cfg(windows)]
+    let dir = {
+        let base = env::var("ProgramData").unwrap_or_else(|_| r"C:\ProgramData".to_string());
+        PathBuf::from(base).join("AssetScan")
+    };
+    #[cfg(not(windows))]
+    let dir = PathBuf::from("/var/lib/assetscan");
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+/// Credencial emitida pelo servidor a esta máquina (no primeiro relatório)
+pub fn agent_token() -> Option<String> {
+    std::fs::read_to_string(data_dir().join("agent_token"))
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+pub fn save_agent_token(token: &str) -> Result<()> {
+    let path = data_dir().join("agent_token");
+    std::fs::write(&path, token).with_context(|| format!("Falha ao gravar {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
 
-        Ok(())
+/// Acrescenta a credencial da máquina aos pedidos feitos em nome dela
+pub fn with_agent_token(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match agent_token() {
+        Some(token) => request.header("X-Agent-Token", token),
+        None => request,
     }
 }
 
@@ -86,6 +174,12 @@ impl Default for Config {
             api_key: String::new(),
             interval_minutes: 60,
             enforcement_enabled: true,
+            hosts_path: None,
+            dns_filter_enabled: false,
+            dns_upstream: Vec::new(),
+            push_enabled: default_push_enabled(),
+            report_neighbors: false,
+            script_public_key: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,427 @@
+// ============================================================
+// device_control.rs — Inventário e controlo de dispositivos USB
+// Deteta ligações/desligações entre ciclos e aplica as políticas
+// "device": bloqueio (desactiva o dispositivo no PnP; no Linux,
+// retira a autorização no sysfs), excepções por número de série e
+// modo só de leitura para discos removíveis (só Windows)
+// ============================================================
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Mutex;
+
+use crate::config;
+use crate::enforcer::Policy;
+use crate::hardware_collector::{self, UsbDevice};
+use crate::monitor;
+
+// Política de grupo "Removable Disks: Deny write access"
+#[cfg(windows)]
+const REMOVABLE_DISKS_KEY: &str =
+    r"SOFTWARE\Policies\Microsoft\Windows\RemovableStorageDevices\{53f5630d-b6bf-11d0-94f2-00a0c91efb8b}";
+
+// Teclados e ratos nunca são desactivados, mesmo com uma regra "*"
+const PROTECTED_CLASSES: &[&str] = &["hidclass", "keyboard", "mouse"];
+
+// Sem enforcement activo, o inventário é lido no máximo a cada 30s
+const INVENTORY_MAX_AGE_SECS: i64 = 30;
+
+// Com políticas activas, a leitura (uma consulta WMI completa no Windows) é feita no
+// máximo a cada 15s; entre leituras as políticas aplicam-se ao último inventário
+const ENFORCE_SCAN_INTERVAL_SECS: i64 = 15;
+
+lazy_static::lazy_static! {
+    static ref DEVICE_STATE: Mutex<DeviceState> = Mutex::new(DeviceState::load());
+}
+
+/// Ligação ou desligação de um dispositivo USB
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeviceEvent {
+    pub device_id: String,
+    pub name: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    pub serial: Option<String>,
+    pub is_mass_storage: bool,
+    pub event: String, // "connected" | "disconnected"
+    pub timestamp: String,
+}
+
+// Config da política: {"access": "block" | "read_only", "allowed_serials": [...]}
+#[derive(Deserialize, Debug, Default)]
+struct DeviceConfig {
+    access: Option<String>,
+    #[serde(default)]
+    allowed_serials: Vec<String>,
+    // Opção do dashboard ("block_all" | "whitelist_only" | "read_only")
+    device_action: Option<String>,
+}
+
+fn device_config(policy: &Policy) -> DeviceConfig {
+    serde_json::from_str(&policy.config_json).unwrap_or_default()
+}
+
+struct DeviceState {
+    devices: Vec<UsbDevice>,
+    last_scan: Option<DateTime<Utc>>,
+    events: Vec<DeviceEvent>,
+    // Dispositivos desactivados pelo agente (persistido para os reactivar depois de um reinício)
+    disabled: HashSet<String>,
+}
+
+impl DeviceState {
+    fn load() -> Self {
+        let disabled = fs::read_to_string(config::data_dir().join("disabled_devices.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        DeviceState { devices: vec![], last_scan: None, events: vec![], disabled }
+    }
+
+    fn save_disabled(&self) {
+        let path = config::data_dir().join("disabled_devices.json");
+        if let Ok(json) = serde_json::to_string(&self.disabled) {
+            if let Err(e) = fs::write(&path, json) {
+                eprintln!("[Devices] Erro ao guardar dispositivos bloqueados: {}", e);
+            }
+        }
+    }
+
+    /// Lê os dispositivos ligados e regista as diferenças para o inventário anterior.
+    /// Devolve os IDs dos dispositivos que apareceram desde a última leitura
+    fn scan(&mut self) -> Result<HashSet<String>> {
+        let mut current = hardware_collector::get_usb_devices()?;
+        let now = Utc::now();
+        let timestamp = now.to_rfc3339();
+
+        let previous: HashSet<&str> = self.devices.iter().map(|d| d.device_id.as_str()).collect();
+        let current_ids: HashSet<&str> = current.iter().map(|d| d.device_id.as_str()).collect();
+
+        // A primeira leitura serve de base: o que já estava ligado não gera eventos
+        let first_scan = self.last_scan.is_none();
+        let mut connected = HashSet::new();
+
+        for device in current.iter().filter(|d| !previous.contains(d.device_id.as_str())) {
+            connected.insert(device.device_id.clone());
+            if !first_scan {
+                println!("[Devices] Ligado: {} ({}:{})", device.name, device.vendor_id, device.product_id);
+                self.events.push(event(device, "connected", &timestamp));
+            }
+        }
+
+        for device in self.devices.iter().filter(|d| !current_ids.contains(d.device_id.as_str())) {
+            println!("[Devices] Desligado: {}", device.name);
+            self.events.push(event(device, "disconnected", &timestamp));
+        }
+
+        let write_protected = write_protection_enabled();
+        for device in &mut current {
+            device.status = if self.disabled.contains(&device.device_id) {
+                "blocked"
+            } else if write_protected && device.is_mass_storage {
+                "read_only"
+            } else {
+                "allowed"
+            }
+            .to_string();
+        }
+
+        self.devices = current;
+        self.last_scan = Some(now);
+        Ok(connected)
+    }
+}
+
+fn event(device: &UsbDevice, kind: &str, timestamp: &str) -> DeviceEvent {
+    DeviceEvent {
+        device_id: device.device_id.clone(),
+        name: device.name.clone(),
+        vendor_id: device.vendor_id.clone(),
+        product_id: device.product_id.clone(),
+        serial: device.serial.clone(),
+        is_mass_storage: device.is_mass_storage,
+        event: kind.to_string(),
+        timestamp: timestamp.to_string(),
+    }
+}
+
+/// Aplica as políticas "device" aos dispositivos ligados (chamado pelo enforcer a cada ciclo).
+/// Sem políticas, reactiva o que o agente tiver desactivado
+pub fn enforce(policies: &[&Policy]) -> Result<()> {
+    let mut state = DEVICE_STATE.lock().unwrap();
+
+    // Sem políticas nem dispositivos por reactivar não há nada a ler
+    if policies.is_empty() && state.disabled.is_empty() {
+        return set_write_protection(false);
+    }
+
+    let due = state
+        .last_scan
+        .map(|t| (Utc::now() - t).num_seconds() >= ENFORCE_SCAN_INTERVAL_SECS)
+        .unwrap_or(true);
+    let connected = if due { state.scan()? } else { HashSet::new() };
+
+    let mut ordered: Vec<&Policy> = policies.to_vec();
+    ordered.sort_by_key(|p| device_precedence(p));
+
+    let mut errors = Vec::new();
+    let mut changed = false;
+
+    // Só de leitura: a política de grupo vale para todos os discos removíveis
+    let read_only = ordered
+        .iter()
+        .any(|p| p.action == "block" && device_config(p).access.as_deref() == Some("read_only"));
+    if let Err(e) = set_write_protection(read_only) {
+        errors.push(format!("{:#}", e));
+    }
+
+    let devices = state.devices.clone();
+    for device in &devices {
+        let winner = ordered.iter().find(|p| device_matches(p, device));
+        let block = winner.map(|p| should_disable(p, device)).unwrap_or(false);
+
+        if let Some(policy) = winner.filter(|p| p.action == "alert") {
+            if connected.contains(&device.device_id) {
+                monitor::record_device_match(policy, &device.name, device.serial.as_deref());
+            }
+        }
+
+        let disabled = state.disabled.contains(&device.device_id);
+        if block && !disabled {
+            println!("[Devices] A bloquear dispositivo: {} ({})", device.name, device.device_id);
+            match set_device_enabled(&device.device_id, false) {
+                Ok(()) => {
+                    state.disabled.insert(device.device_id.clone());
+                    changed = true;
+                }
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        } else if !block && disabled {
+            println!("[Devices] A reactivar dispositivo: {}", device.name);
+            match set_device_enabled(&device.device_id, true) {
+                Ok(()) => {
+                    state.disabled.remove(&device.device_id);
+                    changed = true;
+                }
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+    }
+
+    if changed {
+        state.save_disabled();
+        // Actualiza o estado reportado no inventário
+        let disabled = state.disabled.clone();
+        for device in &mut state.devices {
+            if disabled.contains(&device.device_id) {
+                device.status = "blocked".to_string();
+            } else if device.status == "blocked" {
+                device.status = "allowed".to_string();
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!(errors.join("; "))
+    }
+}
+
+/// "block" com acesso total (não só de leitura), excepto números de série permitidos
+/// e classes essenciais (teclado/rato)
+fn should_disable(policy: &Policy, device: &UsbDevice) -> bool {
+    if policy.action != "block" {
+        return false;
+    }
+
+    let class = device.device_class.to_lowercase();
+    if PROTECTED_CLASSES.contains(&class.as_str()) {
+        return false;
+    }
+
+    let config = device_config(policy);
+    if config.access.as_deref() == Some("read_only") {
+        return false;
+    }
+
+    let allowed = device.serial.as_ref().is_some_and(|serial| {
+        config.allowed_serials.iter().any(|s| s.trim().eq_ignore_ascii_case(serial))
+    });
+    !allowed
+}
+
+/// Alvos suportados: "*"/"all", "mass_storage", "VID:PID" (PID pode ser "*"),
+/// uma classe PnP ("WPD", "Image") ou um número de série
+fn device_matches(policy: &Policy, device: &UsbDevice) -> bool {
+    // Políticas antigas do dashboard tinham um alvo descritivo e aplicavam-se ao armazenamento USB
+    if device_config(policy).device_action.is_some() && !policy.target.contains(':') {
+        return device.is_mass_storage;
+    }
+
+    let target = policy.target.trim().to_uppercase();
+
+    match target.as_str() {
+        "*" | "ALL" | "USB" => true,
+        "MASS_STORAGE" | "STORAGE" => device.is_mass_storage,
+        _ => {
+            if let Some((vid, pid)) = target.split_once(':') {
+                return vid == device.vendor_id && (pid == "*" || pid == device.product_id);
+            }
+            device.serial.as_ref().is_some_and(|s| s.eq_ignore_ascii_case(&target))
+                || device.device_class.eq_ignore_ascii_case(&target)
+        }
+    }
+}
+
+// Mesma ordem do enforcer, mas a especificidade segue o tipo de alvo
+// (série > VID:PID > classe > mass_storage > todos) e não o comprimento do texto
+fn device_precedence(policy: &Policy) -> (i32, u8, u8, u8) {
+    let target = policy.target.trim().to_lowercase();
+    let specificity = match target.as_str() {
+        "*" | "all" | "usb" => 4,
+        "mass_storage" | "storage" => 3,
+        t if t.contains(':') => 1,
+        t if t.len() > 8 => 0, // Números de série
+        _ => 2,
+    };
+
+    (
+        policy.priority,
+        if policy.machine_id.is_some() { 0 } else { 1 },
+        specificity,
+        if policy.action == "allow" { 0 } else { 1 },
+    )
+}
+
+#[cfg(windows)]
+fn set_device_enabled(device_id: &str, enabled: bool) -> Result<()> {
+    let action = if enabled { "/enable-device" } else { "/disable-device" };
+    let output = std::process::Command::new("pnputil")
+        .args([action, device_id])
+        .output()
+        .context("Falha ao executar pnputil")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "pnputil {} {} falhou: {}",
+            action,
+            device_id,
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    Ok(())
+}
+
+// Sem autorização, o kernel desliga os drivers do dispositivo até voltar a ser autorizado
+#[cfg(not(windows))]
+fn set_device_enabled(device_id: &str, enabled: bool) -> Result<()> {
+    let path = format!("/sys/bus/usb/devices/{}/authorized", device_id);
+    fs::write(&path, if enabled { "1" } else { "0" })
+        .with_context(|| format!("Falha ao escrever {}", path))
+}
+
+#[cfg(windows)]
+fn write_protection_enabled() -> bool {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey(REMOVABLE_DISKS_KEY)
+        .and_then(|key| key.get_value::<u32, _>("Deny_Write"))
+        .map(|v| v == 1)
+        .unwrap_or(false)
+}
+
+// Aplica-se a discos montados a seguir; os já ligados mantêm o acesso até serem religados
+#[cfg(windows)]
+fn set_write_protection(enabled: bool) -> Result<()> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    if write_protection_enabled() == enabled {
+        return Ok(());
+    }
+
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    if enabled {
+        let (key, _) = hklm
+            .create_subkey(REMOVABLE_DISKS_KEY)
+            .context("Sem permissão para configurar discos removíveis")?;
+        key.set_value("Deny_Write", &1u32)?;
+        println!("[Devices] Discos removíveis em modo só de leitura");
+    } else if let Ok(key) = hklm.open_subkey_with_flags(REMOVABLE_DISKS_KEY, KEY_SET_VALUE) {
+        key.delete_value("Deny_Write")?;
+        println!("[Devices] Escrita em discos removíveis reposta");
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn write_protection_enabled() -> bool {
+    false
+}
+
+#[cfg(not(windows))]
+fn set_write_protection(enabled: bool) -> Result<()> {
+    if enabled {
+        anyhow::bail!("Modo só de leitura para discos removíveis não suportado neste sistema");
+    }
+    Ok(())
+}
+
+/// Inventário actual; sem enforcement, lê os dispositivos se a última leitura for antiga
+pub fn inventory() -> Vec<UsbDevice> {
+    let mut state = DEVICE_STATE.lock().unwrap();
+    let stale = state
+        .last_scan
+        .map(|t| (Utc::now() - t).num_seconds() >= INVENTORY_MAX_AGE_SECS)
+        .unwrap_or(true);
+
+    if stale {
+        if let Err(e) = state.scan() {
+            eprintln!("[Devices] Erro ao listar dispositivos USB: {}", e);
+        }
+    }
+    state.devices.clone()
+}
+
+/// Eventos de ligação/desligação para enviar no próximo relatório
+pub fn event_snapshot() -> Vec<DeviceEvent> {
+    DEVICE_STATE.lock().unwrap().events.clone()
+}
+
+/// Depois de um envio com sucesso, remove os eventos já reportados
+pub fn acknowledge_events(sent: &[DeviceEvent]) {
+    DEVICE_STATE.lock().unwrap().events.retain(|e| !sent.contains(e));
+}
+
+/// Desinstalação: reactiva os dispositivos bloqueados e repõe a escrita em discos removíveis
+pub fn cleanup() -> Result<()> {
+    let mut state = DEVICE_STATE.lock().unwrap();
+    let mut errors = Vec::new();
+
+    for device_id in state.disabled.clone() {
+        match set_device_enabled(&device_id, true) {
+            Ok(()) => {
+                state.disabled.remove(&device_id);
+            }
+            Err(e) => errors.push(format!("{:#}", e)),
+        }
+    }
+    state.save_disabled();
+
+    if let Err(e) = set_write_protection(false) {
+        errors.push(format!("{:#}", e));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!(errors.join("; "))
+    }
+}
@@ -0,0 +1,761 @@
+// ============================================================
+// dns_filter.rs — Resolver DNS local com filtragem (opcional)
+// Escuta em 127.0.0.1:53, aplica as políticas "web" (wildcards,
+// categorias e whitelist) e reencaminha o resto para o upstream:
+// por omissão, os servidores DNS que o sistema usava antes do filtro
+// ============================================================
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::time::{timeout, timeout_at, Duration, Instant};
+
+use crate::enforcer::{self, Policy};
+use crate::monitor;
+
+const LISTEN_ADDR: &str = "127.0.0.1:53";
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(3);
+const BLOCK_TTL: u32 = 60;
+
+// Domínio "canário": responder NXDOMAIN desactiva o DNS-over-HTTPS do Firefox
+const DOH_CANARY: &str = "use-application-dns.net";
+
+// Resolvers DoH conhecidos; com "block_doh" numa política web são bloqueados
+// para os browsers voltarem ao DNS do sistema
+const DOH_PROVIDERS: &[&str] = &[
+    "dns.google",
+    "cloudflare-dns.com",
+    "mozilla.cloudflare-dns.com",
+    "dns.quad9.net",
+    "doh.opendns.com",
+    "dns.nextdns.io",
+];
+
+// Categorias suportadas em políticas com target_type "category"
+const CATEGORIES: &[(&str, &[&str])] = &[
+    ("social", &["facebook.com", "instagram.com", "tiktok.com", "twitter.com", "x.com", "snapchat.com", "linkedin.com", "pinterest.com", "reddit.com"]),
+    ("streaming", &["youtube.com", "netflix.com", "twitch.tv", "primevideo.com", "disneyplus.com", "hbomax.com", "spotify.com"]),
+    ("gaming", &["steampowered.com", "steamcommunity.com", "epicgames.com", "roblox.com", "ea.com", "battle.net", "riotgames.com", "miniclip.com"]),
+    ("gambling", &["bet365.com", "betclic.pt", "placard.pt", "pokerstars.com", "casino.com", "betano.pt", "solverde.pt"]),
+    ("adult", &["pornhub.com", "xvideos.com", "xnxx.com", "onlyfans.com", "xhamster.com"]),
+    ("messaging", &["whatsapp.com", "web.whatsapp.com", "telegram.org", "discord.com", "discord.gg", "messenger.com"]),
+];
+
+lazy_static::lazy_static! {
+    // Políticas web em vigor, ordenadas por precedência (actualizadas pelo enforcer)
+    static ref WEB_POLICIES: RwLock<Vec<Policy>> = RwLock::new(Vec::new());
+    static ref QUERY_STATS: Mutex<HashMap<String, DnsQueryStat>> = Mutex::new(HashMap::new());
+    // Domínios nunca filtrados (ex: o servidor AssetScan, para o agente não se isolar)
+    static ref EXEMPT_DOMAINS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+    // Domínios consultados desde a última leitura do monitor; `None` com o filtro parado
+    static ref RECENT_DOMAINS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+}
+
+// Limite do registo de domínios recentes entre leituras do monitor
+const MAX_RECENT_DOMAINS: usize = 10_000;
+
+/// Estatísticas por domínio desde o último relatório
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DnsQueryStat {
+    pub domain: String,
+    pub queries: u64,
+    pub blocked: u64,
+    pub policy_id: Option<String>,
+    pub last_query: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct WebFilterConfig {
+    domains: Option<Vec<String>>,
+    target_type: Option<String>,
+    block_doh: Option<bool>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Forward,
+    Alert,
+    Block,
+}
+
+/// Domínios de uma categoria conhecida
+pub fn category_domains(category: &str) -> &'static [&'static str] {
+    let category = category.trim().to_lowercase();
+    CATEGORIES
+        .iter()
+        .find(|(name, _)| *name == category)
+        .map(|(_, domains)| *domains)
+        .unwrap_or(&[])
+}
+
+/// Chamado em cada ciclo do enforcer com as políticas web activas e por ordem de precedência
+pub fn update_policies(policies: &[&Policy]) {
+    *WEB_POLICIES.write().unwrap() = policies.iter().map(|p| (*p).clone()).collect();
+}
+
+/// Arranca os listeners UDP e TCP em 127.0.0.1:53. Sem `upstreams` configurados,
+/// reencaminha para os servidores DNS do sistema
+pub async fn start(upstreams: Vec<String>, exempt_domains: Vec<String>) {
+    *EXEMPT_DOMAINS.write().unwrap() = exempt_domains.iter().map(|d| d.to_lowercase()).collect();
+
+    let upstreams = if upstreams.is_empty() {
+        match tokio::task::spawn_blocking(system_upstreams).await {
+            Ok(Ok(servers)) => servers,
+            Ok(Err(e)) => {
+                eprintln!("[DNS] Erro ao ler os servidores DNS do sistema: {:#}", e);
+                vec![]
+            }
+            Err(e) => {
+                eprintln!("[DNS] Erro ao ler os servidores DNS do sistema: {}", e);
+                vec![]
+            }
+        }
+    } else {
+        upstreams
+    };
+
+    let upstreams: Vec<SocketAddr> = upstreams
+        .iter()
+        .filter_map(|u| {
+            u.parse()
+                .ok()
+                .or_else(|| u.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53)))
+        })
+        .collect();
+    if upstreams.is_empty() {
+        // Sem para onde reencaminhar, o resolver do sistema não é apontado para o filtro
+        eprintln!("[DNS] Sem servidores DNS do sistema nem dns_upstream configurado; filtro não iniciado");
+        return;
+    }
+
+    let udp = match UdpSocket::bind(LISTEN_ADDR).await {
+        Ok(socket) => std::sync::Arc::new(socket),
+        Err(e) => {
+            eprintln!("[DNS] Não foi possível escutar em {}: {}", LISTEN_ADDR, e);
+            return;
+        }
+    };
+    println!("[DNS] Filtro DNS activo em {} (upstream: {:?})", LISTEN_ADDR, upstreams);
+    *RECENT_DOMAINS.lock().unwrap() = Some(HashSet::new());
+
+    match tokio::task::spawn_blocking(point_system_resolver).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("[DNS] Não foi possível configurar o resolver do sistema: {:#}", e),
+        Err(e) => eprintln!("[DNS] Não foi possível configurar o resolver do sistema: {}", e),
+    }
+
+    match TcpListener::bind(LISTEN_ADDR).await {
+        Ok(listener) => {
+            let upstreams = upstreams.clone();
+            tokio::spawn(async move { serve_tcp(listener, upstreams).await });
+        }
+        Err(e) => eprintln!("[DNS] TCP indisponível em {}: {}", LISTEN_ADDR, e),
+    }
+
+    let mut buf = [0u8; 4096];
+    loop {
+        let (len, client) = match udp.recv_from(&mut buf).await {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("[DNS] Erro a receber consulta: {}", e);
+                continue;
+            }
+        };
+
+        let query = buf[..len].to_vec();
+        let socket = udp.clone();
+        let upstreams = upstreams.clone();
+        tokio::spawn(async move {
+            if let Some(response) = handle_udp_query(&query, &upstreams).await {
+                let _ = socket.send_to(&response, client).await;
+            }
+        });
+    }
+}
+
+// -------------------------------------------------
+// Resolver do sistema
+// -------------------------------------------------
+
+// Servidores DNS IPv4 de um adaptador antes de o apontarmos para o filtro local
+#[cfg(windows)]
+#[derive(Serialize, Deserialize, Debug)]
+struct AdapterDns {
+    #[serde(rename = "InterfaceIndex")]
+    interface_index: u32,
+    #[serde(rename = "ServerAddresses", default)]
+    server_addresses: Vec<String>,
+}
+
+#[cfg(windows)]
+fn resolver_backup_path() -> std::path::PathBuf {
+    crate::config::data_dir().join("dns_resolvers_backup.json")
+}
+
+/// Servidores DNS dos adaptadores antes do filtro. Se já estiverem apontados para
+/// 127.0.0.1 (filtro activo numa execução anterior), valem os guardados na cópia
+#[cfg(windows)]
+fn system_upstreams() -> anyhow::Result<Vec<String>> {
+    let adapters: Vec<AdapterDns> = match std::fs::read_to_string(resolver_backup_path()) {
+        Ok(content) => serde_json::from_str(&content)?,
+        Err(_) => adapter_dns()?,
+    };
+    Ok(unique_resolvers(adapters.into_iter().flat_map(|a| a.server_addresses)))
+}
+
+/// Servidores do resolv.conf. Com systemd-resolved, /etc/resolv.conf só tem o stub
+/// 127.0.0.53 e os servidores reais estão no ficheiro em /run
+#[cfg(not(windows))]
+fn system_upstreams() -> anyhow::Result<Vec<String>> {
+    for path in ["/run/systemd/resolve/resolv.conf", "/etc/resolv.conf"] {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let servers = unique_resolvers(parse_resolv_conf(&content));
+            if !servers.is_empty() {
+                return Ok(servers);
+            }
+        }
+    }
+    Ok(vec![])
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+fn parse_resolv_conf(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            (fields.next() == Some("nameserver")).then(|| fields.next()).flatten()
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Endereços válidos e sem repetidos; loopback fica de fora (seria o próprio filtro)
+fn unique_resolvers(servers: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for server in servers {
+        let valid = server
+            .trim()
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| !ip.is_loopback() && !ip.is_unspecified());
+        if valid && !unique.contains(&server) {
+            unique.push(server);
+        }
+    }
+    unique
+}
+
+/// Aponta o DNS IPv4 de cada adaptador para 127.0.0.1. Os servidores anteriores ficam
+/// guardados na primeira vez, para `restore_system_resolver` os repor
+#[cfg(windows)]
+fn point_system_resolver() -> anyhow::Result<()> {
+    let backup = resolver_backup_path();
+    if !backup.exists() {
+        let adapters: Vec<AdapterDns> = adapter_dns()?
+            .into_iter()
+            .filter(|a| !a.server_addresses.iter().any(|s| s == "127.0.0.1"))
+            .collect();
+        std::fs::write(&backup, serde_json::to_string(&adapters)?)?;
+    }
+
+    powershell(
+        "Get-DnsClientServerAddress -AddressFamily IPv4 | \
+         Where-Object { $_.InterfaceAlias -notlike 'Loopback*' } | \
+         Set-DnsClientServerAddress -ServerAddresses 127.0.0.1",
+    )?;
+    println!("[DNS] Resolver do sistema apontado para 127.0.0.1");
+    Ok(())
+}
+
+// No Linux depende da distribuição (resolv.conf, systemd-resolved, NetworkManager):
+// o administrador tem de apontar o resolver para 127.0.0.1
+#[cfg(not(windows))]
+fn point_system_resolver() -> anyhow::Result<()> {
+    println!("[DNS] Configure o resolver do sistema para usar {} (nameserver 127.0.0.1)", LISTEN_ADDR);
+    Ok(())
+}
+
+/// Repõe os servidores DNS guardados (desinstalação ou filtro desactivado).
+/// Adaptadores sem registo voltam aos servidores do DHCP
+#[cfg(windows)]
+pub fn restore_system_resolver() -> anyhow::Result<bool> {
+    let backup = resolver_backup_path();
+    let Ok(content) = std::fs::read_to_string(&backup) else {
+        return Ok(false);
+    };
+    let saved: Vec<AdapterDns> = serde_json::from_str(&content).unwrap_or_default();
+
+    for adapter in adapter_dns()?.iter().filter(|a| a.server_addresses.iter().any(|s| s == "127.0.0.1")) {
+        let previous = saved.iter().find(|s| s.interface_index == adapter.interface_index);
+        let script = match previous.filter(|p| !p.server_addresses.is_empty()) {
+            Some(p) => format!(
+                "Set-DnsClientServerAddress -InterfaceIndex {} -ServerAddresses {}",
+                adapter.interface_index,
+                p.server_addresses.iter().map(|a| format!("'{}'", a)).collect::<Vec<_>>().join(",")
+            ),
+            None => format!("Set-DnsClientServerAddress -InterfaceIndex {} -ResetServerAddresses", adapter.interface_index),
+        };
+        powershell(&script)?;
+    }
+
+    std::fs::remove_file(&backup)?;
+    Ok(true)
+}
+
+#[cfg(not(windows))]
+pub fn restore_system_resolver() -> anyhow::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(windows)]
+fn adapter_dns() -> anyhow::Result<Vec<AdapterDns>> {
+    let output = powershell(
+        "Get-DnsClientServerAddress -AddressFamily IPv4 | \
+         Where-Object { $_.InterfaceAlias -notlike 'Loopback*' } | \
+         Select-Object InterfaceIndex, ServerAddresses | ConvertTo-Json -Compress",
+    )?;
+    if output.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    // ConvertTo-Json devolve um objecto (e não uma lista) quando só há um adaptador
+    let value: serde_json::Value = serde_json::from_str(output.trim())?;
+    let list = match value {
+        serde_json::Value::Array(_) => value,
+        other => serde_json::Value::Array(vec![other]),
+    };
+    Ok(serde_json::from_value(list)?)
+}
+
+#[cfg(windows)]
+fn powershell(script: &str) -> anyhow::Result<String> {
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn handle_udp_query(query: &[u8], upstreams: &[SocketAddr]) -> Option<Vec<u8>> {
+    if let Some(response) = filter_query(query) {
+        return Some(response);
+    }
+
+    for upstream in upstreams {
+        let bind = if upstream.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let Ok(socket) = UdpSocket::bind(bind).await else { continue };
+        // Socket ligado ao upstream: o sistema descarta datagramas de outras origens
+        if socket.connect(upstream).await.is_err() || socket.send(query).await.is_err() {
+            continue;
+        }
+
+        // Respostas com outro ID ou outra pergunta são ignoradas até ao fim do prazo
+        let deadline = Instant::now() + UPSTREAM_TIMEOUT;
+        let mut buf = [0u8; 4096];
+        while let Ok(Ok(len)) = timeout_at(deadline, socket.recv(&mut buf)).await {
+            if is_reply_to(query, &buf[..len]) {
+                return Some(buf[..len].to_vec());
+            }
+        }
+    }
+
+    // Sem upstream disponível: SERVFAIL
+    build_response(query, 2, None)
+}
+
+async fn serve_tcp(listener: TcpListener, upstreams: Vec<SocketAddr>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else { continue };
+        let upstreams = upstreams.clone();
+        tokio::spawn(async move {
+            let _ = timeout(Duration::from_secs(10), handle_tcp_client(stream, &upstreams)).await;
+        });
+    }
+}
+
+async fn handle_tcp_client(mut stream: TcpStream, upstreams: &[SocketAddr]) -> std::io::Result<()> {
+    let query = read_tcp_message(&mut stream).await?;
+
+    let response = match filter_query(&query) {
+        Some(response) => response,
+        None => {
+            let mut response = None;
+            for upstream in upstreams {
+                if let Ok(mut up) = TcpStream::connect(upstream).await {
+                    if write_tcp_message(&mut up, &query).await.is_ok() {
+                        if let Ok(r) = read_tcp_message(&mut up).await {
+                            if is_reply_to(&query, &r) {
+                                response = Some(r);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            match response.or_else(|| build_response(&query, 2, None)) {
+                Some(r) => r,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    write_tcp_message(&mut stream, &response).await
+}
+
+async fn read_tcp_message(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let len = stream.read_u16().await? as usize;
+    let mut message = vec![0u8; len];
+    stream.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+async fn write_tcp_message(stream: &mut TcpStream, message: &[u8]) -> std::io::Result<()> {
+    stream.write_u16(message.len() as u16).await?;
+    stream.write_all(message).await
+}
+
+// -------------------------------------------------
+// Decisão
+// -------------------------------------------------
+
+/// Devolve a resposta de bloqueio, ou `None` se a consulta deve ser reencaminhada
+fn filter_query(query: &[u8]) -> Option<Vec<u8>> {
+    let (domain, qtype) = parse_question(query)?;
+    let policies = WEB_POLICIES.read().unwrap();
+
+    if is_doh_domain(&domain) && policies.iter().any(blocks_doh) {
+        return build_response(query, 3, None);
+    }
+
+    if EXEMPT_DOMAINS.read().unwrap().contains(&domain) {
+        return None;
+    }
+
+    let winner = policies.iter().find_map(|p| policy_verdict(p, &domain).map(|v| (p, v)));
+
+    let blocked = matches!(winner, Some((_, Verdict::Block)));
+    record_query(&domain, blocked, winner.as_ref().map(|(p, _)| p.id.clone()));
+
+    match winner {
+        Some((_, Verdict::Block)) => {
+            println!("[DNS] Bloqueado: {}", domain);
+            match qtype {
+                1 => build_response(query, 0, Some(&[0, 0, 0, 0])),
+                28 => build_response(query, 0, Some(&[0u8; 16])),
+                _ => build_response(query, 3, None),
+            }
+        }
+        Some((policy, Verdict::Alert)) => {
+            monitor::record_domain_match(policy, &domain, None);
+            None
+        }
+        Some((_, Verdict::Forward)) | None => None,
+    }
+}
+
+/// Veredicto da política para o domínio, se a política se aplicar
+fn policy_verdict(policy: &Policy, domain: &str) -> Option<Verdict> {
+    let config: WebFilterConfig = serde_json::from_str(&policy.config_json).unwrap_or_default();
+    let entries: Vec<String> = config
+        .domains
+        .unwrap_or_default()
+        .iter()
+        .map(|d| d.trim().to_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect();
+
+    match config.target_type.as_deref() {
+        Some("category") => {
+            let covered = entries
+                .iter()
+                .flat_map(|c| category_domains(c).iter())
+                .any(|rule| enforcer::domain_covers(rule, domain));
+            covered.then(|| action_verdict(policy))
+        }
+        // Whitelist: os domínios listados passam, a acção da política vale para tudo o resto
+        Some("whitelist") => {
+            let listed = entries.iter().any(|rule| enforcer::domain_covers(rule, domain));
+            Some(if listed { Verdict::Forward } else { action_verdict(policy) })
+        }
+        _ => entries
+            .iter()
+            .any(|rule| enforcer::domain_covers(rule, domain))
+            .then(|| action_verdict(policy)),
+    }
+}
+
+fn is_doh_domain(domain: &str) -> bool {
+    domain == DOH_CANARY || DOH_PROVIDERS.iter().any(|p| enforcer::domain_covers(p, domain))
+}
+
+fn blocks_doh(policy: &Policy) -> bool {
+    serde_json::from_str::<WebFilterConfig>(&policy.config_json)
+        .ok()
+        .and_then(|c| c.block_doh)
+        .unwrap_or(false)
+        && policy.action == "block"
+}
+
+fn action_verdict(policy: &Policy) -> Verdict {
+    match policy.action.as_str() {
+        "block" => Verdict::Block,
+        "alert" => Verdict::Alert,
+        _ => Verdict::Forward,
+    }
+}
+
+fn record_query(domain: &str, blocked: bool, policy_id: Option<String>) {
+    let mut stats = QUERY_STATS.lock().unwrap();
+    let entry = stats.entry(domain.to_string()).or_insert_with(|| DnsQueryStat {
+        domain: domain.to_string(),
+        queries: 0,
+        blocked: 0,
+        policy_id: None,
+        last_query: String::new(),
+    });
+
+    entry.queries += 1;
+    if blocked {
+        entry.blocked += 1;
+    }
+    if policy_id.is_some() {
+        entry.policy_id = policy_id;
+    }
+    entry.last_query = Utc::now().to_rfc3339();
+    drop(stats);
+
+    if let Some(recent) = RECENT_DOMAINS.lock().unwrap().as_mut() {
+        if recent.len() < MAX_RECENT_DOMAINS {
+            recent.insert(domain.to_string());
+        }
+    }
+}
+
+/// Domínios consultados desde a última chamada, se o filtro estiver a correr.
+/// Permite ao monitor dispensar a leitura da cache DNS do sistema
+pub fn take_recent_domains() -> Option<Vec<String>> {
+    let mut recent = RECENT_DOMAINS.lock().unwrap();
+    recent.as_mut().map(|domains| domains.drain().collect())
+}
+
+/// Estatísticas acumuladas para enviar no próximo relatório
+pub fn stats_snapshot() -> Vec<DnsQueryStat> {
+    QUERY_STATS.lock().unwrap().values().cloned().collect()
+}
+
+/// Depois de um envio com sucesso, desconta as contagens já reportadas
+pub fn acknowledge_stats(sent: &[DnsQueryStat]) {
+    let mut stats = QUERY_STATS.lock().unwrap();
+    for stat in sent {
+        let done = match stats.get_mut(&stat.domain) {
+            Some(current) => {
+                current.queries = current.queries.saturating_sub(stat.queries);
+                current.blocked = current.blocked.saturating_sub(stat.blocked);
+                current.queries == 0
+            }
+            None => false,
+        };
+        if done {
+            stats.remove(&stat.domain);
+        }
+    }
+}
+
+// -------------------------------------------------
+// Formato DNS (RFC 1035), apenas o necessário
+// -------------------------------------------------
+
+/// Nome e tipo da primeira pergunta
+fn parse_question(packet: &[u8]) -> Option<(String, u16)> {
+    if packet.len() < 12 || u16::from_be_bytes([packet[4], packet[5]]) == 0 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut pos = 12;
+    loop {
+        let len = *packet.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        if len > 63 {
+            return None; // Ponteiros de compressão não são usados em perguntas
+        }
+        let label = packet.get(pos..pos + len)?;
+        labels.push(String::from_utf8_lossy(label).to_lowercase());
+        pos += len;
+    }
+
+    let qtype = u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]);
+    Some((labels.join("."), qtype))
+}
+
+/// Resposta do upstream à consulta: mesmo ID, bit QR e a mesma pergunta
+fn is_reply_to(query: &[u8], reply: &[u8]) -> bool {
+    reply.len() >= 12
+        && query.len() >= 2
+        && reply[..2] == query[..2]
+        && reply[2] & 0x80 != 0
+        && parse_question(reply).is_some()
+        && parse_question(reply) == parse_question(query)
+}
+
+/// Resposta à consulta com o `rcode` indicado e, opcionalmente, um registo A/AAAA
+fn build_response(query: &[u8], rcode: u8, address: Option<&[u8]>) -> Option<Vec<u8>> {
+    let (_, qtype) = parse_question(query)?;
+
+    // Fim da primeira pergunta (nome + QTYPE + QCLASS)
+    let mut end = 12;
+    while query[end] != 0 {
+        end += query[end] as usize + 1;
+    }
+    end += 5;
+    if query.len() < end {
+        return None;
+    }
+
+    let mut response = query[..end].to_vec();
+    response[2] = 0x80 | (query[2] & 0x79); // QR=1, mantém opcode e RD
+    response[3] = 0x80 | (rcode & 0x0F); // RA=1
+    response[4..6].copy_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    response[6..8].copy_from_slice(&(address.is_some() as u16).to_be_bytes()); // ANCOUNT
+    response[8..12].fill(0); // NSCOUNT, ARCOUNT
+
+    if let Some(address) = address {
+        response.extend_from_slice(&[0xC0, 0x0C]); // Ponteiro para o nome da pergunta
+        response.extend_from_slice(&qtype.to_be_bytes());
+        response.extend_from_slice(&1u16.to_be_bytes()); // IN
+        response.extend_from_slice(&BLOCK_TTL.to_be_bytes());
+        response.extend_from_slice(&(address.len() as u16).to_be_bytes());
+        response.extend_from_slice(address);
+    }
+
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Consulta com ID 0x1234, RD=1 e uma pergunta IN
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut packet = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&qtype.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+        packet
+    }
+
+    fn policy(action: &str, config: &str) -> Policy {
+        Policy {
+            id: "p1".to_string(),
+            machine_id: None,
+            priority: 1,
+            revision: 1,
+            policy_type: "web".to_string(),
+            target: "*".to_string(),
+            action: action.to_string(),
+            reason: String::new(),
+            config_json: config.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_question_name_and_type() {
+        assert_eq!(parse_question(&query("WWW.Example.com", 28)), Some(("www.example.com".to_string(), 28)));
+        assert_eq!(parse_question(&query("example.com", 1)[..14]), None); // Truncado
+        assert_eq!(parse_question(&[0u8; 12]), None); // QDCOUNT = 0
+
+        let mut compressed = query("example.com", 1);
+        compressed[12] = 0xC0;
+        assert_eq!(parse_question(&compressed), None);
+    }
+
+    #[test]
+    fn builds_blocking_answer() {
+        let q = query("ads.example.com", 1);
+        let response = build_response(&q, 0, Some(&[0, 0, 0, 0])).unwrap();
+
+        assert_eq!(&response[..2], &[0x12, 0x34]);
+        assert_eq!(response[2], 0x81); // QR + RD
+        assert_eq!(response[3], 0x80); // RA, NOERROR
+        assert_eq!(&response[6..8], &[0, 1]); // ANCOUNT
+        assert_eq!(&response[..q.len()][12..], &q[12..]); // Pergunta copiada
+
+        let answer = &response[q.len()..];
+        assert_eq!(&answer[..2], &[0xC0, 0x0C]);
+        assert_eq!(&answer[2..4], &1u16.to_be_bytes());
+        assert_eq!(&answer[6..10], &BLOCK_TTL.to_be_bytes());
+        assert_eq!(&answer[10..], &[0, 4, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn builds_nxdomain_without_answers() {
+        let q = query("example.com", 16);
+        let response = build_response(&q, 3, None).unwrap();
+        assert_eq!(response.len(), q.len());
+        assert_eq!(response[3] & 0x0F, 3);
+        assert_eq!(&response[6..8], &[0, 0]);
+    }
+
+    #[test]
+    fn policy_verdicts() {
+        let block = policy("block", r#"{"domains": ["*.example.com"]}"#);
+        assert_eq!(policy_verdict(&block, "ads.example.com"), Some(Verdict::Block));
+        assert_eq!(policy_verdict(&block, "other.org"), None);
+
+        let whitelist = policy("block", r#"{"target_type": "whitelist", "domains": ["school.pt"]}"#);
+        assert_eq!(policy_verdict(&whitelist, "school.pt"), Some(Verdict::Forward));
+        assert_eq!(policy_verdict(&whitelist, "youtube.com"), Some(Verdict::Block));
+
+        let category = policy("alert", r#"{"target_type": "category", "domains": ["social"]}"#);
+        assert_eq!(policy_verdict(&category, "instagram.com"), Some(Verdict::Alert));
+
+        // Whitelist em modo alerta: o resto passa, mas fica registado
+        let audit = policy("alert", r#"{"target_type": "whitelist", "domains": ["school.pt"]}"#);
+        assert_eq!(policy_verdict(&audit, "school.pt"), Some(Verdict::Forward));
+        assert_eq!(policy_verdict(&audit, "youtube.com"), Some(Verdict::Alert));
+    }
+
+    #[test]
+    fn upstream_reply_must_match_query() {
+        let q = query("example.com", 1);
+        let reply = build_response(&q, 0, Some(&[10, 0, 0, 1])).unwrap();
+        assert!(is_reply_to(&q, &reply));
+
+        let mut wrong_id = reply.clone();
+        wrong_id[1] ^= 0xFF;
+        assert!(!is_reply_to(&q, &wrong_id));
+
+        assert!(!is_reply_to(&q, &q)); // Sem QR: é uma consulta
+        assert!(!is_reply_to(&q, &build_response(&query("other.com", 1), 0, None).unwrap()));
+    }
+
+    #[test]
+    fn system_resolvers_skip_loopback() {
+        let conf = "# resolv.conf\nnameserver 127.0.0.53\nnameserver 10.0.0.2\noptions edns0\nnameserver 10.0.0.2\nnameserver fd00::53\n";
+        assert_eq!(unique_resolvers(parse_resolv_conf(conf)), vec!["10.0.0.2", "fd00::53"]);
+    }
+
+    #[test]
+    fn doh_blocking_is_opt_in() {
+        assert!(is_doh_domain("use-application-dns.net"));
+        assert!(is_doh_domain("dns.google"));
+        assert!(!is_doh_domain("google.com"));
+
+        assert!(!blocks_doh(&policy("block", r#"{"domains": ["x.com"]}"#)));
+        assert!(blocks_doh(&policy("block", r#"{"domains": ["x.com"], "block_doh": true}"#)));
+        assert!(!blocks_doh(&policy("alert", r#"{"block_doh": true}"#)));
+    }
+}
@@ -1,11 +1,41 @@
 use anyhow::Result;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use sysinfo::{System, ProcessesToUpdate};
-use std::fs::{self, OpenOptions};
-use std::io::{Read, Write};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::config;
+use crate::device_control;
+use crate::dns_filter;
+use crate::file_identity;
+use crate::firewall;
+use crate::hosts_file::{self, HostsFile};
+use crate::monitor;
+use crate::platform;
+use crate::schedule;
+use crate::screen_time_tracker;
+
+lazy_static::lazy_static! {
+    // Resultado da aplicação de cada política desde o último relatório
+    static ref ENFORCEMENT_STATE: Mutex<HashMap<String, EnforcementResult>> = Mutex::new(HashMap::new());
+
+    // Executáveis fora da allowlist (bloqueados ou, em modo aprendizagem, apenas registados)
+    static ref APP_VIOLATIONS: Mutex<HashMap<(String, String), AppViolation>> = Mutex::new(HashMap::new());
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Policy {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub machine_id: Option<String>,
+    #[serde(default = "default_priority")]
+    pub priority: i32,
+    #[serde(default)]
+    pub revision: i64,
     pub policy_type: String,
     pub target: String,
     pub action: String,
@@ -13,89 +43,492 @@ pub struct Policy {
     pub config_json: String,
 }
 
+fn default_priority() -> i32 {
+    1
+}
+
+/// Revisão de uma política que o agente está a aplicar (reportada ao servidor)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyRevisionState {
+    pub policy_id: String,
+    pub revision: i64,
+}
+
+/// Resultado da aplicação de uma política neste endpoint
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnforcementResult {
+    pub policy_id: String,
+    pub revision: i64,
+    pub status: String, // "applied" | "failed"
+    pub error: Option<String>,
+    pub kill_count: u64, // Processos terminados desde o último relatório
+    pub last_hit: Option<String>,
+}
+
+/// Executável que não corresponde a nenhuma regra "allow" com a allowlist activa
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppViolation {
+    pub policy_id: String,
+    pub process_name: String,
+    pub exe_path: String,
+    pub sha256: Option<String>,
+    pub publisher: Option<String>,
+    pub original_filename: Option<String>,
+    pub action: String, // "blocked" | "audited" (modo aprendizagem)
+    pub count: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+pub fn revision_states(policies: &[Policy]) -> Vec<PolicyRevisionState> {
+    policies
+        .iter()
+        .filter(|p| !p.id.is_empty())
+        .map(|p| PolicyRevisionState {
+            policy_id: p.id.clone(),
+            revision: p.revision,
+        })
+        .collect()
+}
+
 #[derive(Deserialize, Debug)]
 struct AppConfig {
     match_type: Option<String>,
+    hash_value: Option<String>,
+}
+
+// Política de allowlist: {"mode": "allowlist", "learning": true, "exempt_paths": [...]}
+#[derive(Deserialize, Debug, Default)]
+struct AllowlistConfig {
+    mode: Option<String>,
+    #[serde(default)]
+    learning: bool,
+    #[serde(default)]
+    exempt_paths: Vec<String>,
+}
+
+fn allowlist_config(policy: &Policy) -> Option<AllowlistConfig> {
+    serde_json::from_str::<AllowlistConfig>(&policy.config_json)
+        .ok()
+        .filter(|c| c.mode.as_deref() == Some("allowlist"))
 }
 
 #[derive(Deserialize, Debug)]
 struct WebConfig {
     domains: Option<Vec<String>>,
+    subdomains: Option<Vec<String>>,
+    target_type: Option<String>,
 }
 
-pub fn enforce_active_policies(policies: &[Policy]) {
+pub fn enforce_active_policies(policies: &[Policy], hosts: &HostsFile) {
     let mut sys = System::new();
-    sys.refresh_processes(ProcessesToUpdate::All);
+    // O utilizador dono do processo é necessário para registar correspondências "alert"
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        ProcessRefreshKind::new()
+            .with_exe(UpdateKind::OnlyIfNotSet)
+            .with_user(UpdateKind::OnlyIfNotSet),
+    );
 
-    // 1. App Control (Bloqueio de Aplicações)
-    let app_policies: Vec<&Policy> = policies.iter().filter(|p| p.policy_type == "app").collect();
-    for policy in app_policies {
-        if policy.action != "block" { continue; }
-        
-        let config: AppConfig = serde_json::from_str(&policy.config_json)
-            .unwrap_or(AppConfig { match_type: Some("name".to_string()) });
-            
-        let target = policy.target.to_lowercase();
-        let match_type = config.match_type.unwrap_or_else(|| "name".to_string());
-
-        for (_pid, process) in sys.processes() {
-            let name = process.name().to_string_lossy().to_lowercase();
-            let path = process.exe().unwrap_or(std::path::Path::new("")).to_string_lossy().to_lowercase();
-
-            let is_match = match match_type.as_str() {
-                "path" => path.contains(&target),
-                _ => name.contains(&target) || path.contains(&target), // Apanha "fraps.exe" ou "fraps"
-            };
-
-            if is_match {
-                println!("[Enforcer] A terminar aplicação proibida: {}", name);
-                process.kill(); // Termina imediatamente
+    // Ordena por precedência (igual ao servidor): prioridade, âmbito, especificidade, allow > block.
+    // O sort é estável, por isso o desempate final segue a ordem enviada pelo servidor.
+    // Só entram as políticas dentro do horário e, se tiverem quota, com a quota esgotada
+    let now = Utc::now();
+    let mut ordered: Vec<&Policy> = policies
+        .iter()
+        .filter(|p| schedule::is_active(&p.config_json, now) && quota_exhausted(p))
+        .collect();
+    ordered.sort_by_key(|p| precedence_key(p));
+
+    // 1. App Control (Bloqueio de Aplicações) — a primeira regra que coincide ganha.
+    // Com uma política de allowlist activa, o que não coincidir com nenhuma regra é bloqueado
+    let app_policies: Vec<&Policy> = ordered.iter().copied().filter(|p| p.policy_type == "app").collect();
+    let allowlist = app_policies
+        .iter()
+        .find_map(|p| allowlist_config(p).map(|config| (*p, config)));
+    let app_rules: Vec<&Policy> = app_policies
+        .iter()
+        .copied()
+        .filter(|p| allowlist_config(p).is_none())
+        .collect();
+
+    let mut app_errors: HashMap<&str, String> = HashMap::new();
+    let mut app_hits: HashMap<&str, u64> = HashMap::new();
+    let mut users: Option<Users> = None;
+
+    for (pid, process) in sys.processes() {
+        let name = process.name().to_string_lossy().to_lowercase();
+        let exe = process.exe();
+
+        // (política responsável, terminar o processo?)
+        let decision = match app_rules.iter().find(|policy| app_policy_matches(policy, &name, exe)) {
+            Some(policy) => Some((*policy, policy.action == "block")),
+            None => match &allowlist {
+                Some((policy, config)) if !is_exempt(pid.as_u32(), exe, &config.exempt_paths) => {
+                    let exe_path = exe.unwrap_or(Path::new("")).to_string_lossy().to_string();
+                    record_violation(policy, &name, &exe_path, config.learning);
+                    Some((*policy, !config.learning))
+                }
+                _ => None,
+            },
+        };
+
+        // "alert": apenas regista o que seria afectado
+        if let Some((policy, false)) = decision {
+            if policy.action == "alert" {
+                let users = users.get_or_insert_with(Users::new_with_refreshed_list);
+                monitor::record_app_match(policy, &sys, users, *pid, &name);
             }
         }
+
+        if let Some((policy, true)) = decision {
+            println!("[Enforcer] A terminar aplicação proibida: {}", name);
+            if process.kill() {
+                *app_hits.entry(policy.id.as_str()).or_insert(0) += 1;
+            } else {
+                app_errors.insert(
+                    policy.id.as_str(),
+                    format!("Falha ao terminar {} (PID {})", name, pid.as_u32()),
+                );
+            }
+        }
+    }
+
+    for policy in &app_policies {
+        let error = app_errors.get(policy.id.as_str()).cloned();
+        let kills = app_hits.get(policy.id.as_str()).copied().unwrap_or(0);
+        record_result(policy, error, kills);
     }
 
     // 2. Web Filtering (Bloqueio de Sites via Hosts File)
-    let web_policies: Vec<&Policy> = policies.iter().filter(|p| p.policy_type == "web").collect();
-    let _ = enforce_hosts_file(&web_policies);
+    let web_policies: Vec<&Policy> = ordered.iter().copied().filter(|p| p.policy_type == "web").collect();
+    let web_error = enforce_hosts_file(&web_policies, hosts).err().map(|e| format!("{:#}", e));
+    for policy in &web_policies {
+        record_result(policy, web_error.clone(), 0);
+    }
+    monitor::check_web_alerts(&web_policies);
+    dns_filter::update_policies(&web_policies);
+
+    // 3. Regras IP/CIDR e de portas (firewall do sistema; "alert" apenas monitoriza ligações)
+    let ip_policies: Vec<&Policy> = ordered.iter().copied().filter(|p| p.policy_type == "ip").collect();
+    let network_policies: Vec<&Policy> = ordered
+        .iter()
+        .copied()
+        .filter(|p| p.policy_type == "ip" || p.policy_type == "network")
+        .collect();
+    let firewall_error = firewall::enforce(&network_policies).err().map(|e| format!("{:#}", e));
+    if let Some(error) = &firewall_error {
+        eprintln!("[Enforcer] {}", error);
+    }
+    for policy in network_policies.iter().filter(|p| p.action != "alert") {
+        record_result(policy, firewall_error.clone(), 0);
+    }
+    monitor::check_ip_alerts(&ip_policies, &sys);
+
+    // 4. Dispositivos USB (bloqueio, excepções por número de série, só de leitura)
+    let device_policies: Vec<&Policy> = ordered.iter().copied().filter(|p| p.policy_type == "device").collect();
+    let device_error = device_control::enforce(&device_policies).err().map(|e| format!("{:#}", e));
+    if let Some(error) = &device_error {
+        eprintln!("[Enforcer] {}", error);
+    }
+    for policy in device_policies.iter().filter(|p| p.action != "alert") {
+        record_result(policy, device_error.clone(), 0);
+    }
+
+    // Descarta resultados de políticas que já não estão activas
+    let active_ids: Vec<&str> = policies.iter().map(|p| p.id.as_str()).collect();
+    ENFORCEMENT_STATE
+        .lock()
+        .unwrap()
+        .retain(|id, _| active_ids.contains(&id.as_str()));
+}
+
+fn record_result(policy: &Policy, error: Option<String>, kills: u64) {
+    if policy.id.is_empty() {
+        return;
+    }
+
+    let mut state = ENFORCEMENT_STATE.lock().unwrap();
+    let entry = state.entry(policy.id.clone()).or_insert_with(|| EnforcementResult {
+        policy_id: policy.id.clone(),
+        revision: policy.revision,
+        status: "applied".to_string(),
+        error: None,
+        kill_count: 0,
+        last_hit: None,
+    });
+
+    entry.revision = policy.revision;
+    entry.status = if error.is_some() { "failed" } else { "applied" }.to_string();
+    entry.error = error;
+    if kills > 0 {
+        entry.kill_count += kills;
+        entry.last_hit = Some(Utc::now().to_rfc3339());
+    }
+}
+
+/// Resultados acumulados para enviar no próximo relatório
+pub fn enforcement_snapshot() -> Vec<EnforcementResult> {
+    ENFORCEMENT_STATE.lock().unwrap().values().cloned().collect()
+}
+
+/// Depois de um envio com sucesso, desconta as contagens já reportadas
+pub fn acknowledge_enforcement(sent: &[EnforcementResult]) {
+    let mut state = ENFORCEMENT_STATE.lock().unwrap();
+    for result in sent {
+        if let Some(entry) = state.get_mut(&result.policy_id) {
+            entry.kill_count = entry.kill_count.saturating_sub(result.kill_count);
+        }
+    }
 }
 
-// Manipula o ficheiro hosts do Windows de forma segura
-fn enforce_hosts_file(policies: &[&Policy]) -> Result<()> {
-    let hosts_path = r"C:\Windows\System32\drivers\etc\hosts";
-    let mut current_content = String::new();
-    
-    if let Ok(mut file) = fs::File::open(hosts_path) {
-        file.read_to_string(&mut current_content).ok();
+/// Processos que a allowlist nunca termina: sem executável acessível (kernel,
+/// processos protegidos), executáveis e serviços do sistema (indicados pela plataforma),
+/// o próprio agente e exclusões da política
+fn is_exempt(pid: u32, exe: Option<&Path>, exempt_paths: &[String]) -> bool {
+    let Some(exe) = exe else {
+        return true;
+    };
+
+    let platform = platform::current();
+    if platform.system_exe_dirs().iter().any(|dir| is_inside(exe, dir)) || platform.is_system_service(pid) {
+        return true;
     }
 
-    // Remove apenas as regras antigas do AssetScan para limpar a cache
-    let mut lines: Vec<&str> = current_content.lines().filter(|l| !l.contains("# ASSETSCAN")).collect();
-    let mut new_lines = Vec::new();
+    if std::env::current_exe().map(|own| own == exe).unwrap_or(false) {
+        return true;
+    }
+
+    exempt_paths.iter().any(|pattern| path_matches(&pattern.to_lowercase(), exe))
+}
+
+/// `exe` dentro de `dir` (sem distinguir maiúsculas no Windows)
+fn is_inside(exe: &Path, dir: &Path) -> bool {
+    if cfg!(windows) {
+        let dir = dir.to_string_lossy().trim_end_matches('\\').to_lowercase();
+        exe.to_string_lossy().to_lowercase().starts_with(&format!("{}\\", dir))
+    } else {
+        exe.starts_with(dir)
+    }
+}
+
+fn record_violation(policy: &Policy, name: &str, exe_path: &str, learning: bool) {
+    let action = if learning { "audited" } else { "blocked" };
+    let now = Utc::now().to_rfc3339();
+    let key = (policy.id.clone(), exe_path.to_lowercase());
+
+    let mut violations = APP_VIOLATIONS.lock().unwrap();
+    if let Some(existing) = violations.get_mut(&key) {
+        // Em modo aprendizagem o processo continua vivo: conta uma vez por relatório
+        if !learning || existing.action != action {
+            existing.count += 1;
+        }
+        existing.action = action.to_string();
+        existing.last_seen = now;
+        return;
+    }
+
+    if learning {
+        println!("[Enforcer] (aprendizagem) Seria bloqueado: {}", name);
+    }
+
+    let exe = Path::new(exe_path);
+    violations.insert(
+        key,
+        AppViolation {
+            policy_id: policy.id.clone(),
+            process_name: name.to_string(),
+            exe_path: exe_path.to_string(),
+            sha256: file_identity::sha256(exe),
+            publisher: file_identity::publisher(exe),
+            original_filename: file_identity::original_filename(exe),
+            action: action.to_string(),
+            count: 1,
+            first_seen: now.clone(),
+            last_seen: now,
+        },
+    );
+}
+
+/// Violações da allowlist acumuladas para o próximo relatório
+pub fn app_violation_snapshot() -> Vec<AppViolation> {
+    APP_VIOLATIONS.lock().unwrap().values().cloned().collect()
+}
+
+/// Depois de um envio com sucesso, remove as violações já reportadas
+pub fn acknowledge_app_violations(sent: &[AppViolation]) {
+    let mut violations = APP_VIOLATIONS.lock().unwrap();
+    for violation in sent {
+        let key = (violation.policy_id.clone(), violation.exe_path.to_lowercase());
+        let reported = violations
+            .get(&key)
+            .map(|v| v.count <= violation.count && v.action == violation.action)
+            .unwrap_or(false);
+        if reported {
+            violations.remove(&key);
+        } else if let Some(v) = violations.get_mut(&key) {
+            v.count = v.count.saturating_sub(violation.count);
+        }
+    }
+}
+
+/// Políticas sem quota aplicam-se sempre; com quota, só depois de esgotada.
+/// O tempo de utilização só é medido por executável, por isso quotas noutros alvos
+/// (web, categorias, caminhos, hashes) são ignoradas e a regra aplica-se sempre
+fn quota_exhausted(policy: &Policy) -> bool {
+    match schedule::daily_quota_seconds(&policy.config_json) {
+        Some(quota) if quota_measurable(policy) => screen_time_tracker::seconds_today(&policy.target) >= quota,
+        Some(_) => true,
+        None => true,
+    }
+}
+
+fn quota_measurable(policy: &Policy) -> bool {
+    let match_type = serde_json::from_str::<AppConfig>(&policy.config_json)
+        .ok()
+        .and_then(|c| c.match_type)
+        .unwrap_or_else(|| "name".to_string());
+    policy.policy_type == "app" && match_type == "name"
+}
+
+/// Guarda as últimas políticas recebidas para continuar a aplicá-las offline
+pub fn save_policy_cache(policies: &[Policy]) {
+    let path = config::data_dir().join("policies_cache.json");
+    if let Ok(json) = serde_json::to_string(policies) {
+        if let Err(e) = fs::write(&path, json) {
+            eprintln!("[Enforcer] Erro ao guardar cache de políticas: {}", e);
+        }
+    }
+}
+
+pub fn load_policy_cache() -> Vec<Policy> {
+    let path = config::data_dir().join("policies_cache.json");
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Chave de ordenação: valores menores têm maior precedência
+fn precedence_key(policy: &Policy) -> (i32, u8, std::cmp::Reverse<usize>, u8) {
+    let target = policy.target.trim();
+    let specificity = if target == "*" { 0 } else { target.trim_start_matches("*.").len() };
+
+    (
+        policy.priority,
+        if policy.machine_id.is_some() { 0 } else { 1 },
+        std::cmp::Reverse(specificity),
+        if policy.action == "allow" { 0 } else { 1 },
+    )
+}
+
+fn app_policy_matches(policy: &Policy, name: &str, exe: Option<&Path>) -> bool {
+    let config: AppConfig = serde_json::from_str(&policy.config_json)
+        .unwrap_or(AppConfig { match_type: Some("name".to_string()), hash_value: None });
+
+    let target = policy.target.trim().to_lowercase();
+    let match_type = config.match_type.unwrap_or_else(|| "name".to_string());
+
+    match match_type.as_str() {
+        // Caminho exacto ou glob (ex: "C:\\Users\\*\\AppData\\*\\fraps.exe")
+        "path" | "path_glob" => exe.map(|exe| path_matches(&target, exe)).unwrap_or(false),
+        "hash" => {
+            let expected = config.hash_value.unwrap_or_else(|| target.clone()).trim().to_lowercase();
+            !expected.is_empty()
+                && exe.and_then(file_identity::sha256).as_deref() == Some(expected.as_str())
+        }
+        // Nome original do recurso de versão: sobrevive a renomear o executável
+        "original_filename" => exe
+            .and_then(file_identity::original_filename)
+            .map(|original| exe_name_matches(&target, &original.to_lowercase()))
+            .unwrap_or(false),
+        "publisher" => exe
+            .and_then(file_identity::publisher)
+            .map(|publisher| publisher.trim().to_lowercase() == target)
+            .unwrap_or(false),
+        // Nome exacto do processo: "fraps" ou "fraps.exe" apanham fraps.exe, mas não fraps_helper.exe
+        _ => exe_name_matches(&target, name),
+    }
+}
+
+fn exe_name_matches(target: &str, name: &str) -> bool {
+    name == target || name.strip_suffix(".exe") == Some(target)
+}
+
+fn path_matches(pattern: &str, exe: &Path) -> bool {
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    glob::Pattern::new(pattern)
+        .map(|p| p.matches_path_with(exe, options))
+        .unwrap_or(false)
+}
+
+/// Domínios abrangidos por uma política web; categorias são expandidas.
+/// Políticas "whitelist" não têm lista de bloqueio (só o filtro DNS as aplica)
+pub(crate) fn web_domains(policy: &Policy) -> Vec<String> {
+    let Ok(config) = serde_json::from_str::<WebConfig>(&policy.config_json) else {
+        return vec![];
+    };
+    let entries = config.domains.unwrap_or_default();
+
+    let domains: Vec<String> = match config.target_type.as_deref() {
+        Some("whitelist") => vec![],
+        Some("category") => entries
+            .iter()
+            .flat_map(|c| dns_filter::category_domains(c).iter().map(|d| d.to_string()))
+            .collect(),
+        _ => entries,
+    };
+
+    domains
+        .iter()
+        .map(|d| d.trim().to_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+// Subdomínios adicionados no hosts para cada domínio (por omissão, só "www")
+fn web_subdomains(policy: &Policy) -> Vec<String> {
+    serde_json::from_str::<WebConfig>(&policy.config_json)
+        .ok()
+        .and_then(|c| c.subdomains)
+        .unwrap_or_else(|| vec!["www".to_string()])
+}
+
+pub(crate) fn domain_covers(rule: &str, domain: &str) -> bool {
+    let rule = rule.trim_start_matches("*.");
+    domain == rule || domain.ends_with(&format!(".{}", rule))
+}
+
+/// Domínios a bloquear no hosts: regras "block" vencedoras, expandidas com os subdomínios da política
+fn enforce_hosts_file(policies: &[&Policy], hosts: &HostsFile) -> Result<()> {
+    let mut entries: Vec<String> = Vec::new();
 
     for policy in policies {
         if policy.action != "block" { continue; }
-        if let Ok(config) = serde_json::from_str::<WebConfig>(&policy.config_json) {
-            if let Some(domains) = config.domains {
-                for domain in domains {
-                    let clean_domain = domain.trim();
-                    if !clean_domain.is_empty() {
-                        new_lines.push(format!("127.0.0.1 {} # ASSETSCAN", clean_domain));
-                        new_lines.push(format!("127.0.0.1 www.{} # ASSETSCAN", clean_domain));
-                    }
-                }
+        let subdomains = web_subdomains(policy);
+        for domain in web_domains(policy) {
+            // Um "allow" com maior precedência sobre o mesmo domínio anula o bloqueio
+            let winner = policies
+                .iter()
+                .find(|p| web_domains(p).iter().any(|rule| domain_covers(rule, &domain)));
+            if winner.map(|p| p.action != "block").unwrap_or(false) {
+                continue;
             }
+
+            entries.extend(hosts_file::expand_domain(&domain, &subdomains));
         }
     }
 
-    if !new_lines.is_empty() {
-        lines.push("# ASSETSCAN AUTOGENERATED");
-        lines.extend(new_lines.iter().map(|s| s.as_str()));
-    }
+    entries.sort();
+    entries.dedup();
 
-    let final_content = lines.join("\n") + "\n";
-    if let Ok(mut file) = OpenOptions::new().write(true).truncate(true).open(hosts_path) {
-        file.write_all(final_content.as_bytes()).ok();
+    if hosts.apply(&entries)? {
+        println!("[Enforcer] Ficheiro hosts actualizado ({} entradas)", entries.len());
     }
-
     Ok(())
-}
\ No newline at end of file
+}
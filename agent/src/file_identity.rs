@@ -0,0 +1,245 @@
+// ============================================================
+// file_identity.rs — Identidade de executáveis (hash, nome original, publisher)
+// Resultados em cache por caminho + mtime + tamanho para manter
+// o ciclo de enforcement (5s) barato
+// ============================================================
+
+use sha2::{Digest, Sha256};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+// Limite simples para a cache não crescer indefinidamente
+const MAX_CACHE_ENTRIES: usize = 4096;
+
+lazy_static::lazy_static! {
+    static ref IDENTITY_CACHE: Mutex<HashMap<PathBuf, CachedIdentity>> = Mutex::new(HashMap::new());
+}
+
+// Cada campo só é calculado quando uma política precisa dele
+struct CachedIdentity {
+    modified: SystemTime,
+    size: u64,
+    sha256: OnceCell<Option<String>>,
+    original_filename: OnceCell<Option<String>>,
+    publisher: OnceCell<Option<String>>,
+}
+
+fn with_identity<T>(path: &Path, f: impl FnOnce(&CachedIdentity) -> T) -> Option<T> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?;
+    let size = metadata.len();
+
+    let mut cache = IDENTITY_CACHE.lock().unwrap();
+
+    let stale = cache
+        .get(path)
+        .map(|c| c.modified != modified || c.size != size)
+        .unwrap_or(true);
+
+    if stale {
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(
+            path.to_path_buf(),
+            CachedIdentity {
+                modified,
+                size,
+                sha256: OnceCell::new(),
+                original_filename: OnceCell::new(),
+                publisher: OnceCell::new(),
+            },
+        );
+    }
+
+    cache.get(path).map(f)
+}
+
+/// SHA-256 do ficheiro em hexadecimal minúsculo
+pub fn sha256(path: &Path) -> Option<String> {
+    with_identity(path, |c| c.sha256.get_or_init(|| compute_sha256(path)).clone()).flatten()
+}
+
+/// Nome original do executável (recurso de versão "OriginalFilename"),
+/// não muda quando o ficheiro é renomeado
+pub fn original_filename(path: &Path) -> Option<String> {
+    with_identity(path, |c| {
+        c.original_filename.get_or_init(|| read_original_filename(path)).clone()
+    })
+    .flatten()
+}
+
+/// Nome do signatário da assinatura Authenticode, apenas se a assinatura for válida
+pub fn publisher(path: &Path) -> Option<String> {
+    with_identity(path, |c| c.publisher.get_or_init(|| read_publisher(path)).clone()).flatten()
+}
+
+fn compute_sha256(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Some(hex::encode(hasher.finalize()))
+}
+
+// -------------------------------------------------
+// Windows: recursos de versão e Authenticode
+// -------------------------------------------------
+
+#[cfg(windows)]
+fn read_original_filename(path: &Path) -> Option<String> {
+    use std::ffi::c_void;
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
+    };
+
+    let wide_path = HSTRING::from(path.as_os_str());
+
+    unsafe {
+        let size = GetFileVersionInfoSizeW(&wide_path, None);
+        if size == 0 {
+            return None;
+        }
+
+        let mut data = vec![0u8; size as usize];
+        GetFileVersionInfoW(&wide_path, 0, size, data.as_mut_ptr() as *mut c_void).ok()?;
+
+        // Primeira tradução disponível (idioma + codepage)
+        let mut translation: *mut c_void = std::ptr::null_mut();
+        let mut len = 0u32;
+        if !VerQueryValueW(
+            data.as_ptr() as *const c_void,
+            &HSTRING::from("\\VarFileInfo\\Translation"),
+            &mut translation,
+            &mut len,
+        )
+        .as_bool()
+            || len < 4
+        {
+            return None;
+        }
+
+        let lang = *(translation as *const u16);
+        let codepage = *(translation as *const u16).add(1);
+        let sub_block = format!("\\StringFileInfo\\{:04x}{:04x}\\OriginalFilename", lang, codepage);
+
+        let mut value: *mut c_void = std::ptr::null_mut();
+        let mut value_len = 0u32;
+        if !VerQueryValueW(
+            data.as_ptr() as *const c_void,
+            &HSTRING::from(sub_block),
+            &mut value,
+            &mut value_len,
+        )
+        .as_bool()
+            || value_len == 0
+        {
+            return None;
+        }
+
+        let chars = std::slice::from_raw_parts(value as *const u16, value_len as usize);
+        let name = String::from_utf16_lossy(chars).trim_end_matches('\0').trim().to_string();
+        (!name.is_empty()).then_some(name)
+    }
+}
+
+#[cfg(windows)]
+fn read_publisher(path: &Path) -> Option<String> {
+    use std::ffi::c_void;
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{HANDLE, HWND};
+    use windows::Win32::Security::Cryptography::{CertGetNameStringW, CERT_NAME_SIMPLE_DISPLAY_TYPE};
+    use windows::Win32::Security::WinTrust::{
+        WTHelperGetProvCertFromChain, WTHelperGetProvSignerFromChain,
+        WTHelperProvDataFromStateData, WinVerifyTrust, WINTRUST_ACTION_GENERIC_VERIFY_V2,
+        WINTRUST_DATA, WINTRUST_DATA_0, WINTRUST_FILE_INFO, WTD_CHOICE_FILE, WTD_REVOKE_NONE,
+        WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+    };
+
+    let wide_path = HSTRING::from(path.as_os_str());
+
+    unsafe {
+        let mut file_info = WINTRUST_FILE_INFO {
+            cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+            pcwszFilePath: PCWSTR(wide_path.as_ptr()),
+            hFile: HANDLE::default(),
+            pgKnownSubject: std::ptr::null_mut(),
+        };
+
+        let mut trust_data = WINTRUST_DATA {
+            cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+            dwUIChoice: WTD_UI_NONE,
+            fdwRevocationChecks: WTD_REVOKE_NONE,
+            dwUnionChoice: WTD_CHOICE_FILE,
+            Anonymous: WINTRUST_DATA_0 { pFile: &mut file_info },
+            dwStateAction: WTD_STATEACTION_VERIFY,
+            ..Default::default()
+        };
+
+        let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+        let status = WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut trust_data as *mut _ as *mut c_void,
+        );
+
+        // Só aceitamos o publisher de assinaturas válidas (ficheiros assinados
+        // apenas por catálogo, como alguns binários do sistema, ficam sem publisher)
+        let mut name = None;
+        if status == 0 {
+            let provider = WTHelperProvDataFromStateData(trust_data.hWVTStateData);
+            if !provider.is_null() {
+                let signer = WTHelperGetProvSignerFromChain(provider, 0, false, 0);
+                if !signer.is_null() {
+                    let cert = WTHelperGetProvCertFromChain(signer, 0);
+                    if !cert.is_null() && !(*cert).pCert.is_null() {
+                        let mut buffer = [0u16; 256];
+                        let len = CertGetNameStringW(
+                            (*cert).pCert,
+                            CERT_NAME_SIMPLE_DISPLAY_TYPE,
+                            0,
+                            None,
+                            Some(&mut buffer),
+                        );
+                        if len > 1 {
+                            name = Some(String::from_utf16_lossy(&buffer[..(len - 1) as usize]));
+                        }
+                    }
+                }
+            }
+        }
+
+        trust_data.dwStateAction = WTD_STATEACTION_CLOSE;
+        WinVerifyTrust(
+            HWND::default(),
+            &mut action,
+            &mut trust_data as *mut _ as *mut c_void,
+        );
+
+        name
+    }
+}
+
+#[cfg(not(windows))]
+fn read_original_filename(_path: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(not(windows))]
+fn read_publisher(_path: &Path) -> Option<String> {
+    None
+}
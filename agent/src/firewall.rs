@@ -0,0 +1,1136 @@
+// ============================================================
+// firewall.rs — Regras IP/CIDR e de portas na firewall do sistema
+// Windows Firewall (netsh) no Windows; nftables ou iptables no Linux.
+// O conjunto de regras geridas é reconciliado a cada ciclo: só se
+// adiciona/remove o que mudou, e tudo é removido na desinstalação.
+// ============================================================
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::process::Command;
+use std::sync::{Mutex, RwLock};
+
+use crate::enforcer::Policy;
+
+/// Prefixo de todas as regras criadas pelo agente
+pub const RULE_PREFIX: &str = "AssetScan";
+
+lazy_static::lazy_static! {
+    static ref FIREWALL: Mutex<Firewall> = Mutex::new(Firewall::new(default_backend()));
+    // Servidor AssetScan: nunca bloqueado, para o agente não se isolar
+    static ref SERVER_ADDRS: RwLock<Vec<SocketAddr>> = RwLock::new(Vec::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Any,
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FirewallRule {
+    /// Nome estável: prefixo + política + hash do conteúdo (muda quando a regra muda)
+    pub name: String,
+    pub policy_id: String,
+    pub allow: bool,
+    pub direction: Direction,
+    pub protocol: Protocol,
+    /// IP ou CIDR remoto; `None` significa qualquer endereço
+    pub remote: Option<String>,
+    /// Portas ou intervalos ("443", "8000-8100"); vazio significa todas
+    pub ports: Vec<String>,
+    /// Endereços do servidor retirados de um bloqueio. Só o netsh precisa deles: nas
+    /// cadeias do Linux o "allow" do servidor vem primeiro e ganha
+    pub exclude: Vec<IpAddr>,
+}
+
+/// Operações mínimas que cada firewall tem de suportar
+pub trait FirewallBackend: Send {
+    fn name(&self) -> &'static str;
+    /// Nomes das regras do AssetScan actualmente instaladas
+    fn installed_rules(&self) -> Result<Vec<String>>;
+    fn add_rule(&mut self, rule: &FirewallRule) -> Result<()>;
+    fn remove_rule(&mut self, name: &str) -> Result<()>;
+    /// Remove todas as regras do AssetScan
+    fn clear(&mut self) -> Result<()>;
+
+    /// A primeira regra que coincide ganha (nftables, iptables): a ordem das regras
+    /// instaladas tem de seguir a precedência das políticas
+    fn ordered(&self) -> bool {
+        false
+    }
+
+    /// Substitui todas as regras do AssetScan pelas indicadas, por esta ordem
+    fn replace_all(&mut self, rules: &[FirewallRule]) -> Result<()> {
+        self.clear()?;
+        for rule in rules {
+            self.add_rule(rule)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Firewall {
+    backend: Box<dyn FirewallBackend>,
+    // Nomes aplicados no último ciclo, por ordem; evita consultar a firewall quando nada mudou
+    applied: Option<Vec<String>>,
+}
+
+impl Firewall {
+    pub fn new(backend: Box<dyn FirewallBackend>) -> Self {
+        Firewall { backend, applied: None }
+    }
+
+    /// Deixa instaladas exactamente as regras indicadas
+    pub fn reconcile(&mut self, desired: &[FirewallRule]) -> Result<()> {
+        let order: Vec<String> = desired.iter().map(|r| r.name.clone()).collect();
+        if self.applied.as_ref() == Some(&order) {
+            return Ok(());
+        }
+
+        if self.backend.ordered() {
+            // Acrescentar só as regras novas deixava-as no fim da cadeia, fora da ordem
+            // de precedência: a cadeia é reconstruída por inteiro
+            if let Err(e) = self.backend.replace_all(desired) {
+                self.applied = None;
+                return Err(e).context("Falha ao aplicar regras de firewall");
+            }
+            println!("[Firewall] {} regra(s) aplicada(s) ({})", desired.len(), self.backend.name());
+            self.applied = Some(order);
+            return Ok(());
+        }
+
+        let wanted: HashSet<String> = order.iter().cloned().collect();
+        let installed: HashSet<String> = self.backend.installed_rules()?.into_iter().collect();
+        let mut errors = Vec::new();
+
+        for name in installed.difference(&wanted) {
+            if let Err(e) = self.backend.remove_rule(name) {
+                errors.push(format!("{}: {:#}", name, e));
+            }
+        }
+        for rule in desired.iter().filter(|r| !installed.contains(&r.name)) {
+            match self.backend.add_rule(rule) {
+                Ok(()) => println!("[Firewall] Regra {} aplicada ({})", rule.name, self.backend.name()),
+                Err(e) => errors.push(format!("{}: {:#}", rule.name, e)),
+            }
+        }
+
+        if !errors.is_empty() {
+            // Na próxima execução volta a comparar com o estado real
+            self.applied = None;
+            bail!("Falha ao aplicar regras de firewall: {}", errors.join("; "));
+        }
+
+        self.applied = Some(order);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.applied = None;
+        self.backend.clear()
+    }
+}
+
+/// Aplica as políticas "ip" e "network" (ordenadas por precedência) na firewall do sistema
+pub fn enforce(policies: &[&Policy]) -> Result<()> {
+    let servers = SERVER_ADDRS.read().unwrap().clone();
+    let rules = rules_from_policies(policies, &servers);
+    FIREWALL.lock().unwrap().reconcile(&rules)
+}
+
+/// Endereços do servidor AssetScan, isentos de qualquer bloqueio (como no filtro DNS)
+pub fn exempt_server(addrs: Vec<SocketAddr>) {
+    *SERVER_ADDRS.write().unwrap() = addrs;
+}
+
+/// Remove todas as regras do AssetScan (desinstalação)
+pub fn cleanup() -> Result<()> {
+    FIREWALL.lock().unwrap().clear()
+}
+
+// -------------------------------------------------
+// Políticas → regras
+// -------------------------------------------------
+
+#[derive(serde::Deserialize, Debug, Default)]
+struct NetworkConfig {
+    ports: Option<PortList>,
+    blocked_ports: Option<PortList>,
+    protocol: Option<String>,
+    direction: Option<String>,
+}
+
+// O dashboard envia as portas como texto ("80, 443") ou lista
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum PortList {
+    Text(String),
+    List(Vec<serde_json::Value>),
+}
+
+impl PortList {
+    fn values(&self) -> Vec<String> {
+        let raw: Vec<String> = match self {
+            PortList::Text(text) => text.split([',', ';', ' ', '\n']).map(str::to_string).collect(),
+            PortList::List(items) => items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                .collect(),
+        };
+        raw.into_iter().filter_map(|p| normalize_port(&p)).collect()
+    }
+}
+
+fn normalize_port(port: &str) -> Option<String> {
+    let port = port.trim();
+    match port.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (start.trim().parse::<u16>().ok()?, end.trim().parse::<u16>().ok()?);
+            (start <= end).then(|| format!("{}-{}", start, end))
+        }
+        None => port.parse::<u16>().ok().map(|p| p.to_string()),
+    }
+}
+
+/// Valida e normaliza um IP ou CIDR ("10.0.0.1", "10.0.0.0/8", "2001:db8::/32")
+pub fn normalize_cidr(value: &str) -> Option<String> {
+    let value = value.trim();
+    let (ip, prefix) = match value.split_once('/') {
+        Some((ip, prefix)) => (ip, Some(prefix.parse::<u8>().ok()?)),
+        None => (value, None),
+    };
+    let ip: IpAddr = ip.parse().ok()?;
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+
+    match prefix {
+        Some(p) if p > max => None,
+        Some(p) if p < max => Some(format!("{}/{}", ip, p)),
+        _ => Some(ip.to_string()),
+    }
+}
+
+/// Regras pretendidas, pela ordem de precedência das políticas. A primeira é sempre a
+/// excepção do servidor AssetScan
+pub fn rules_from_policies(policies: &[&Policy], servers: &[SocketAddr]) -> Vec<FirewallRule> {
+    let mut rules: Vec<FirewallRule> = servers.iter().map(server_rule).collect();
+    // "allow" vistos até aqui, para anular os bloqueios de menor precedência que cobrem
+    let mut allows: Vec<FirewallRule> = rules.clone();
+
+    for policy in policies {
+        if policy.action != "block" && policy.action != "allow" {
+            continue; // "alert" é tratado pelo monitor
+        }
+
+        let config: NetworkConfig = serde_json::from_str(&policy.config_json).unwrap_or_default();
+        let (remote, ports) = match policy.policy_type.as_str() {
+            "ip" => {
+                let Some(remote) = normalize_cidr(&policy.target) else {
+                    eprintln!("[Firewall] Endereço inválido na política {}: {}", policy.id, policy.target);
+                    continue;
+                };
+                (Some(remote), config.ports.as_ref().map(PortList::values).unwrap_or_default())
+            }
+            "network" => {
+                let ports = config
+                    .blocked_ports
+                    .as_ref()
+                    .or(config.ports.as_ref())
+                    .map(PortList::values)
+                    .unwrap_or_default();
+                if ports.is_empty() {
+                    continue;
+                }
+                (None, ports)
+            }
+            _ => continue,
+        };
+
+        // Portas exigem protocolo: "any" gera uma regra TCP e outra UDP
+        let protocols = match (config.protocol.as_deref(), ports.is_empty()) {
+            (Some("tcp"), _) => vec![Protocol::Tcp],
+            (Some("udp"), _) => vec![Protocol::Udp],
+            (_, true) => vec![Protocol::Any],
+            (_, false) => vec![Protocol::Tcp, Protocol::Udp],
+        };
+        let directions = match config.direction.as_deref() {
+            Some("in") => vec![Direction::In],
+            Some("out") => vec![Direction::Out],
+            _ => vec![Direction::Out, Direction::In],
+        };
+
+        for direction in &directions {
+            for protocol in &protocols {
+                let mut rule = FirewallRule {
+                    name: String::new(),
+                    policy_id: policy.id.clone(),
+                    allow: policy.action == "allow",
+                    direction: *direction,
+                    protocol: *protocol,
+                    remote: remote.clone(),
+                    ports: ports.clone(),
+                    exclude: Vec::new(),
+                };
+
+                if rule.allow {
+                    // Um "allow" não abre tráfego de entrada: só anula os bloqueios que cobre.
+                    // Na saída fica como excepção a bloqueios mais largos nas cadeias do Linux
+                    rule.name = rule_name(&rule);
+                    if rule.direction == Direction::Out {
+                        rules.push(rule.clone());
+                    }
+                    allows.push(rule);
+                    continue;
+                }
+
+                // A Windows Firewall dá sempre prioridade ao bloqueio; um "allow" com maior
+                // precedência que cubra o mesmo tráfego anula o bloqueio antes de chegar à firewall.
+                // Se só cobrir parte das portas, o bloqueio fica com as restantes
+                let covering: Vec<&FirewallRule> = allows.iter().filter(|allow| allow_covers(allow, &rule)).collect();
+                if !covering.is_empty() {
+                    rule.ports = uncovered_ports(&rule.ports, &covering);
+                    if rule.ports.is_empty() {
+                        continue;
+                    }
+                }
+
+                // Pela mesma razão, o servidor é retirado dos bloqueios que o abrangem
+                rule.exclude = servers.iter().filter(|s| blocks_server(&rule, s)).map(|s| s.ip()).collect();
+                if remote_ranges(rule.remote.as_deref(), &rule.exclude).as_deref() == Some("") {
+                    continue; // O bloqueio visava apenas o próprio servidor
+                }
+                rule.name = rule_name(&rule);
+                rules.push(rule);
+            }
+        }
+    }
+
+    rules
+}
+
+fn server_rule(addr: &SocketAddr) -> FirewallRule {
+    let mut rule = FirewallRule {
+        name: String::new(),
+        policy_id: "server".to_string(),
+        allow: true,
+        direction: Direction::Out,
+        protocol: Protocol::Tcp,
+        remote: Some(addr.ip().to_string()),
+        ports: vec![addr.port().to_string()],
+        exclude: Vec::new(),
+    };
+    rule.name = rule_name(&rule);
+    rule
+}
+
+/// O bloqueio apanharia as ligações do agente ao servidor
+fn blocks_server(rule: &FirewallRule, server: &SocketAddr) -> bool {
+    let port_covered = rule.ports.is_empty() || rule.ports.iter().any(|p| port_in(p, server.port()));
+    let remote_covered = match &rule.remote {
+        None => true,
+        Some(remote) => cidr_contains(remote, &server.ip().to_string()),
+    };
+
+    rule.direction == Direction::Out && rule.protocol != Protocol::Udp && port_covered && remote_covered
+}
+
+fn port_in(spec: &str, port: u16) -> bool {
+    port_range(spec).is_some_and(|(start, end)| (start..=end).contains(&port))
+}
+
+fn port_range(spec: &str) -> Option<(u16, u16)> {
+    match spec.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (start.trim().parse::<u16>().ok()?, end.trim().parse::<u16>().ok()?);
+            (start <= end).then_some((start, end))
+        }
+        None => spec.trim().parse::<u16>().ok().map(|p| (p, p)),
+    }
+}
+
+/// Portas de um bloqueio que os "allow" indicados não cobrem (vazio: nenhuma).
+/// Ex: bloqueio "1-1024" com "allow" em "443" fica "1-442", "444-1024"
+fn uncovered_ports(block: &[String], allows: &[&FirewallRule]) -> Vec<String> {
+    if allows.iter().any(|allow| allow.ports.is_empty() || allow.ports == block) {
+        return Vec::new();
+    }
+
+    // Sem portas, o bloqueio abrange todas
+    let ranges: Option<Vec<(u16, u16)>> = if block.is_empty() {
+        Some(vec![(1, u16::MAX)])
+    } else {
+        block.iter().map(|p| port_range(p)).collect()
+    };
+    let Some(mut remaining) = ranges else {
+        return block.to_vec(); // Formato desconhecido: só a cobertura exacta acima
+    };
+
+    let mut changed = false;
+    for (start, end) in allows.iter().flat_map(|allow| allow.ports.iter().filter_map(|p| port_range(p))) {
+        remaining = remaining
+            .into_iter()
+            .flat_map(|(from, to)| {
+                if end < from || start > to {
+                    return vec![(from, to)];
+                }
+                changed = true;
+                let mut parts = Vec::new();
+                if start > from {
+                    parts.push((from, start - 1));
+                }
+                if end < to {
+                    parts.push((end + 1, to));
+                }
+                parts
+            })
+            .collect();
+    }
+
+    if !changed {
+        return block.to_vec();
+    }
+    remaining
+        .into_iter()
+        .map(|(from, to)| if from == to { from.to_string() } else { format!("{}-{}", from, to) })
+        .collect()
+}
+
+/// Endereços remotos para o netsh: o IP/CIDR da regra (ou todos) sem os excluídos,
+/// como lista de intervalos ("10.0.0.0-10.0.0.4,10.0.0.6-10.255.255.255")
+fn remote_ranges(remote: Option<&str>, exclude: &[IpAddr]) -> Option<String> {
+    if exclude.is_empty() {
+        return remote.map(str::to_string);
+    }
+
+    // (IPv6, início, fim)
+    let mut ranges: Vec<(bool, u128, u128)> = match remote.and_then(parse_cidr) {
+        Some((ip, len)) => {
+            let (v6, bits) = (ip.is_ipv6(), if ip.is_ipv6() { 128 } else { 32 });
+            let host_bits = bits - len as u32;
+            let size = if host_bits == 128 { u128::MAX } else { (1u128 << host_bits) - 1 };
+            let start = ip_value(ip) & !size;
+            vec![(v6, start, start | size)]
+        }
+        None => vec![(false, 0, u32::MAX as u128), (true, 0, u128::MAX)],
+    };
+
+    for ip in exclude {
+        let (v6, value) = (ip.is_ipv6(), ip_value(*ip));
+        ranges = ranges
+            .into_iter()
+            .flat_map(|(family, start, end)| {
+                if family != v6 || value < start || value > end {
+                    return vec![(family, start, end)];
+                }
+                let mut parts = Vec::new();
+                if value > start {
+                    parts.push((family, start, value - 1));
+                }
+                if value < end {
+                    parts.push((family, value + 1, end));
+                }
+                parts
+            })
+            .collect();
+    }
+
+    let format = |v6: bool, value: u128| {
+        if v6 {
+            Ipv6Addr::from(value).to_string()
+        } else {
+            Ipv4Addr::from(value as u32).to_string()
+        }
+    };
+    let list: Vec<String> = ranges
+        .iter()
+        .map(|&(v6, start, end)| {
+            if start == end {
+                format(v6, start)
+            } else {
+                format!("{}-{}", format(v6, start), format(v6, end))
+            }
+        })
+        .collect();
+    Some(list.join(","))
+}
+
+fn ip_value(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(v4) => u32::from(v4) as u128,
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+/// O "allow" abrange o tráfego do bloqueio, pelo menos em parte das portas (ver `uncovered_ports`)
+fn allow_covers(allow: &FirewallRule, block: &FirewallRule) -> bool {
+    let remote_covered = match (&allow.remote, &block.remote) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(outer), Some(inner)) => cidr_contains(outer, inner),
+    };
+
+    allow.direction == block.direction
+        && (allow.protocol == Protocol::Any || allow.protocol == block.protocol)
+        && remote_covered
+}
+
+fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    match value.split_once('/') {
+        Some((ip, prefix)) => Some((ip.parse().ok()?, prefix.parse().ok()?)),
+        None => {
+            let ip: IpAddr = value.parse().ok()?;
+            Some((ip, if ip.is_ipv4() { 32 } else { 128 }))
+        }
+    }
+}
+
+/// `outer` contém todos os endereços de `inner`
+fn cidr_contains(outer: &str, inner: &str) -> bool {
+    let (Some((outer_ip, outer_len)), Some((inner_ip, inner_len))) = (parse_cidr(outer), parse_cidr(inner)) else {
+        return false;
+    };
+    if outer_len > inner_len {
+        return false;
+    }
+
+    match (outer_ip, inner_ip) {
+        (IpAddr::V4(o), IpAddr::V4(i)) => {
+            let mask = if outer_len == 0 { 0 } else { u32::MAX << (32 - outer_len as u32) };
+            (u32::from(o) & mask) == (u32::from(i) & mask)
+        }
+        (IpAddr::V6(o), IpAddr::V6(i)) => {
+            let mask = if outer_len == 0 { 0 } else { u128::MAX << (128 - outer_len as u32) };
+            (u128::from(o) & mask) == (u128::from(i) & mask)
+        }
+        _ => false,
+    }
+}
+
+fn rule_name(rule: &FirewallRule) -> String {
+    let digest = Sha256::digest(format!(
+        "{:?}",
+        (rule.allow, rule.direction, rule.protocol, &rule.remote, &rule.ports, &rule.exclude)
+    ));
+    let short_id: String = rule.policy_id.chars().take(8).collect();
+    format!("{}-{}-{}", RULE_PREFIX, short_id, &hex::encode(digest)[..10])
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Falha ao executar {}", program))?;
+
+    if !output.status.success() {
+        bail!(
+            "{} terminou com erro: {}{}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim(),
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// -------------------------------------------------
+// Windows Firewall (netsh advfirewall)
+// -------------------------------------------------
+
+pub struct NetshBackend;
+
+impl FirewallBackend for NetshBackend {
+    fn name(&self) -> &'static str {
+        "Windows Firewall"
+    }
+
+    fn installed_rules(&self) -> Result<Vec<String>> {
+        // A saída do netsh é traduzida; o PowerShell devolve só os nomes
+        let script = format!(
+            "Get-NetFirewallRule -DisplayName '{}-*' -ErrorAction SilentlyContinue | Select-Object -ExpandProperty DisplayName",
+            RULE_PREFIX
+        );
+        let output = run("powershell", &["-NoProfile", "-Command", &script])?;
+
+        let mut names: Vec<String> = output.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn add_rule(&mut self, rule: &FirewallRule) -> Result<()> {
+        let mut args = vec![
+            "advfirewall".to_string(),
+            "firewall".to_string(),
+            "add".to_string(),
+            "rule".to_string(),
+            format!("name={}", rule.name),
+            format!("dir={}", if rule.direction == Direction::In { "in" } else { "out" }),
+            format!("action={}", if rule.allow { "allow" } else { "block" }),
+            format!(
+                "protocol={}",
+                match rule.protocol {
+                    Protocol::Any => "any",
+                    Protocol::Tcp => "TCP",
+                    Protocol::Udp => "UDP",
+                }
+            ),
+            "enable=yes".to_string(),
+        ];
+        if let Some(remote) = remote_ranges(rule.remote.as_deref(), &rule.exclude) {
+            args.push(format!("remoteip={}", remote));
+        }
+        if !rule.ports.is_empty() {
+            // Entrada: porta local do serviço; saída: porta do destino
+            let key = if rule.direction == Direction::In { "localport" } else { "remoteport" };
+            args.push(format!("{}={}", key, rule.ports.join(",")));
+        }
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run("netsh", &args).map(|_| ())
+    }
+
+    fn remove_rule(&mut self, name: &str) -> Result<()> {
+        let name_arg = format!("name={}", name);
+        run("netsh", &["advfirewall", "firewall", "delete", "rule", &name_arg]).map(|_| ())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        for name in self.installed_rules()? {
+            self.remove_rule(&name)?;
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------
+// Linux: nftables (tabela própria) ou iptables (cadeia própria)
+// -------------------------------------------------
+
+const NFT_TABLE: &str = "assetscan";
+const IPT_CHAIN_OUT: &str = "ASSETSCAN-OUT";
+const IPT_CHAIN_IN: &str = "ASSETSCAN-IN";
+
+/// Executa dentro de um network namespace quando indicado (testes e contentores)
+fn run_in_netns(netns: &Option<String>, program: &str, args: &[&str]) -> Result<String> {
+    match netns {
+        Some(ns) => {
+            let mut full = vec!["netns", "exec", ns.as_str(), program];
+            full.extend_from_slice(args);
+            run("ip", &full)
+        }
+        None => run(program, args),
+    }
+}
+
+pub struct NftablesBackend {
+    netns: Option<String>,
+}
+
+impl NftablesBackend {
+    pub fn new(netns: Option<String>) -> Self {
+        NftablesBackend { netns }
+    }
+
+    fn nft(&self, args: &[&str]) -> Result<String> {
+        run_in_netns(&self.netns, "nft", args)
+    }
+
+    fn ensure_table(&self) -> Result<()> {
+        // Declarar uma tabela/cadeia que já existe não tem efeito
+        self.nft(&["add", "table", "inet", NFT_TABLE])?;
+        self.nft(&[
+            "add", "chain", "inet", NFT_TABLE, "output",
+            "{ type filter hook output priority 0 ; policy accept ; }",
+        ])?;
+        self.nft(&[
+            "add", "chain", "inet", NFT_TABLE, "input",
+            "{ type filter hook input priority 0 ; policy accept ; }",
+        ])?;
+        Ok(())
+    }
+
+    // (cadeia, handle, comentário) de cada regra da tabela
+    fn rules_with_handles(&self) -> Result<Vec<(String, u64, String)>> {
+        let output = match self.nft(&["-j", "list", "table", "inet", NFT_TABLE]) {
+            Ok(out) => out,
+            Err(_) => return Ok(vec![]), // Tabela ainda não existe
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&output).context("Saída JSON do nft inválida")?;
+        let rules = json["nftables"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let rule = item.get("rule")?;
+                        Some((
+                            rule["chain"].as_str()?.to_string(),
+                            rule["handle"].as_u64()?,
+                            rule["comment"].as_str()?.to_string(),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(rules)
+    }
+}
+
+impl FirewallBackend for NftablesBackend {
+    fn name(&self) -> &'static str {
+        "nftables"
+    }
+
+    fn installed_rules(&self) -> Result<Vec<String>> {
+        Ok(self.rules_with_handles()?.into_iter().map(|(_, _, name)| name).collect())
+    }
+
+    fn add_rule(&mut self, rule: &FirewallRule) -> Result<()> {
+        self.ensure_table()?;
+        let (chain, statement) = nft_statement(rule);
+        self.nft(&["add", "rule", "inet", NFT_TABLE, chain, &statement]).map(|_| ())
+    }
+
+    fn ordered(&self) -> bool {
+        true
+    }
+
+    // Um só comando nft é aplicado numa transacção: as cadeias nunca ficam a meio
+    fn replace_all(&mut self, rules: &[FirewallRule]) -> Result<()> {
+        self.ensure_table()?;
+
+        let mut script = vec![
+            format!("flush chain inet {} output", NFT_TABLE),
+            format!("flush chain inet {} input", NFT_TABLE),
+        ];
+        for rule in rules {
+            let (chain, statement) = nft_statement(rule);
+            script.push(format!("add rule inet {} {} {}", NFT_TABLE, chain, statement));
+        }
+        self.nft(&[&script.join(" ; ")]).map(|_| ())
+    }
+
+    fn remove_rule(&mut self, name: &str) -> Result<()> {
+        for (chain, handle, comment) in self.rules_with_handles()? {
+            if comment == name {
+                let handle = handle.to_string();
+                self.nft(&["delete", "rule", "inet", NFT_TABLE, &chain, "handle", &handle])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        if self.nft(&["list", "table", "inet", NFT_TABLE]).is_ok() {
+            self.nft(&["delete", "table", "inet", NFT_TABLE])?;
+        }
+        Ok(())
+    }
+}
+
+// (cadeia, expressão) de uma regra na tabela do AssetScan
+fn nft_statement(rule: &FirewallRule) -> (&'static str, String) {
+    let chain = if rule.direction == Direction::In { "input" } else { "output" };
+    let mut expr: Vec<String> = Vec::new();
+
+    if let Some(remote) = &rule.remote {
+        let family = if remote.contains(':') { "ip6" } else { "ip" };
+        let field = if rule.direction == Direction::In { "saddr" } else { "daddr" };
+        expr.push(format!("{} {} {}", family, field, remote));
+    }
+    match rule.protocol {
+        Protocol::Any => {}
+        Protocol::Tcp | Protocol::Udp => {
+            let proto = if rule.protocol == Protocol::Tcp { "tcp" } else { "udp" };
+            if rule.ports.is_empty() {
+                expr.push(format!("meta l4proto {}", proto));
+            } else {
+                expr.push(format!("{} dport {{ {} }}", proto, rule.ports.join(", ")));
+            }
+        }
+    }
+    expr.push(format!("counter {}", if rule.allow { "accept" } else { "drop" }));
+    expr.push(format!("comment \"{}\"", rule.name));
+
+    (chain, expr.join(" "))
+}
+
+pub struct IptablesBackend {
+    netns: Option<String>,
+}
+
+impl IptablesBackend {
+    pub fn new(netns: Option<String>) -> Self {
+        IptablesBackend { netns }
+    }
+
+    fn iptables(&self, ipv6: bool, args: &[&str]) -> Result<String> {
+        run_in_netns(&self.netns, if ipv6 { "ip6tables" } else { "iptables" }, args)
+    }
+
+    fn ensure_chains(&self, ipv6: bool) -> Result<()> {
+        for (chain, parent) in [(IPT_CHAIN_OUT, "OUTPUT"), (IPT_CHAIN_IN, "INPUT")] {
+            if self.iptables(ipv6, &["-nL", chain]).is_err() {
+                self.iptables(ipv6, &["-N", chain])?;
+            }
+            if self.iptables(ipv6, &["-C", parent, "-j", chain]).is_err() {
+                self.iptables(ipv6, &["-I", parent, "1", "-j", chain])?;
+            }
+        }
+        Ok(())
+    }
+
+    // Linhas "-A CADEIA ... --comment NOME ..." das cadeias do AssetScan
+    fn managed_lines(&self, ipv6: bool) -> Vec<String> {
+        [IPT_CHAIN_OUT, IPT_CHAIN_IN]
+            .iter()
+            .filter_map(|chain| self.iptables(ipv6, &["-S", chain]).ok())
+            .flat_map(|out| out.lines().map(str::to_string).collect::<Vec<_>>())
+            .filter(|l| l.starts_with("-A ") && l.contains(RULE_PREFIX))
+            .collect()
+    }
+}
+
+fn comment_of(line: &str) -> Option<String> {
+    let rest = line.split("--comment ").nth(1)?;
+    Some(rest.split_whitespace().next()?.trim_matches('"').to_string())
+}
+
+impl FirewallBackend for IptablesBackend {
+    fn name(&self) -> &'static str {
+        "iptables"
+    }
+
+    fn installed_rules(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = [false, true]
+            .iter()
+            .flat_map(|v6| self.managed_lines(*v6))
+            .filter_map(|l| comment_of(&l))
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    fn add_rule(&mut self, rule: &FirewallRule) -> Result<()> {
+        let ipv6 = rule.remote.as_deref().map(|r| r.contains(':')).unwrap_or(false);
+        self.ensure_chains(ipv6)?;
+
+        let chain = if rule.direction == Direction::In { IPT_CHAIN_IN } else { IPT_CHAIN_OUT };
+        let mut args: Vec<String> = vec!["-A".into(), chain.into()];
+
+        if let Some(remote) = &rule.remote {
+            args.push(if rule.direction == Direction::In { "-s" } else { "-d" }.into());
+            args.push(remote.clone());
+        }
+        if rule.protocol != Protocol::Any {
+            let proto = if rule.protocol == Protocol::Tcp { "tcp" } else { "udp" };
+            args.extend(["-p".into(), proto.into()]);
+            if !rule.ports.is_empty() {
+                let ports = rule.ports.join(",").replace('-', ":");
+                args.extend(["-m".into(), "multiport".into(), "--dports".into(), ports]);
+            }
+        }
+        args.extend(["-m".into(), "comment".into(), "--comment".into(), rule.name.clone()]);
+        args.extend(["-j".into(), if rule.allow { "ACCEPT" } else { "DROP" }.into()]);
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let result = self.iptables(ipv6, &args).map(|_| ());
+
+        // Sem endereço, a regra vale também para IPv6
+        if result.is_ok() && rule.remote.is_none() && self.ensure_chains(true).is_ok() {
+            let _ = self.iptables(true, &args);
+        }
+        result
+    }
+
+    fn ordered(&self) -> bool {
+        true
+    }
+
+    fn remove_rule(&mut self, name: &str) -> Result<()> {
+        for ipv6 in [false, true] {
+            for line in self.managed_lines(ipv6) {
+                if comment_of(&line).as_deref() == Some(name) {
+                    // "-A CADEIA spec" → "-D CADEIA spec"
+                    let spec: Vec<String> = shell_words(&line.replacen("-A ", "-D ", 1));
+                    let args: Vec<&str> = spec.iter().map(String::as_str).collect();
+                    self.iptables(ipv6, &args)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        for ipv6 in [false, true] {
+            for (chain, parent) in [(IPT_CHAIN_OUT, "OUTPUT"), (IPT_CHAIN_IN, "INPUT")] {
+                let _ = self.iptables(ipv6, &["-D", parent, "-j", chain]);
+                let _ = self.iptables(ipv6, &["-F", chain]);
+                let _ = self.iptables(ipv6, &["-X", chain]);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Divide uma linha do "iptables -S" respeitando aspas
+fn shell_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ' ' if !quoted => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn default_backend() -> Box<dyn FirewallBackend> {
+    if cfg!(windows) {
+        return Box::new(NetshBackend);
+    }
+
+    let netns = std::env::var("ASSETSCAN_NETNS").ok();
+    if run_in_netns(&netns, "nft", &["--version"]).is_ok() {
+        Box::new(NftablesBackend::new(netns))
+    } else {
+        Box::new(IptablesBackend::new(netns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(id: &str, policy_type: &str, target: &str, action: &str, config: &str) -> Policy {
+        Policy {
+            id: id.to_string(),
+            machine_id: None,
+            priority: 1,
+            revision: 1,
+            policy_type: policy_type.to_string(),
+            target: target.to_string(),
+            action: action.to_string(),
+            reason: String::new(),
+            config_json: config.to_string(),
+        }
+    }
+
+    // Backend em memória para testar a reconciliação
+    #[derive(Default)]
+    struct FakeBackend {
+        rules: std::sync::Arc<Mutex<Vec<String>>>,
+        ordered: bool,
+    }
+
+    impl FirewallBackend for FakeBackend {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+        fn installed_rules(&self) -> Result<Vec<String>> {
+            Ok(self.rules.lock().unwrap().clone())
+        }
+        fn add_rule(&mut self, rule: &FirewallRule) -> Result<()> {
+            self.rules.lock().unwrap().push(rule.name.clone());
+            Ok(())
+        }
+        fn remove_rule(&mut self, name: &str) -> Result<()> {
+            self.rules.lock().unwrap().retain(|r| r != name);
+            Ok(())
+        }
+        fn clear(&mut self) -> Result<()> {
+            self.rules.lock().unwrap().clear();
+            Ok(())
+        }
+        fn ordered(&self) -> bool {
+            self.ordered
+        }
+    }
+
+    #[test]
+    fn builds_rules_from_ip_and_port_policies() {
+        let ip = policy("p1", "ip", "10.0.0.0/8", "block", r#"{"ports":"443","direction":"out"}"#);
+        let ports = policy("p2", "network", "Portas", "block", r#"{"blocked_ports":"21, 23, 6000-6010"}"#);
+        let alert = policy("p3", "ip", "1.2.3.4", "alert", "{}");
+
+        let rules = rules_from_policies(&[&ip, &ports, &alert], &[]);
+
+        // p1: só saída, TCP+UDP; p2: entrada+saída, TCP+UDP; p3: nenhuma
+        assert_eq!(rules.len(), 6);
+        assert_eq!(rules[0].remote.as_deref(), Some("10.0.0.0/8"));
+        assert_eq!(rules[2].ports, vec!["21", "23", "6000-6010"]);
+        assert!(rules.iter().all(|r| r.name.starts_with("AssetScan-")));
+    }
+
+    #[test]
+    fn reconcile_is_idempotent_and_removes_stale_rules() {
+        let backend = FakeBackend::default();
+        let installed = backend.rules.clone();
+        installed.lock().unwrap().push("AssetScan-old-0000000000".to_string());
+        let mut firewall = Firewall::new(Box::new(backend));
+
+        let p = policy("p1", "ip", "192.168.1.10", "block", "{}");
+        let rules = rules_from_policies(&[&p], &[]);
+
+        firewall.reconcile(&rules).unwrap();
+        firewall.reconcile(&rules).unwrap();
+        let mut names: Vec<String> = rules.iter().map(|r| r.name.clone()).collect();
+        names.sort();
+        let mut current = installed.lock().unwrap().clone();
+        current.sort();
+        assert_eq!(current, names);
+
+        firewall.reconcile(&[]).unwrap();
+        assert!(installed.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn higher_precedence_allow_removes_covered_block() {
+        let allow = policy("p1", "ip", "10.1.0.0/16", "allow", "{}");
+        let block = policy("p2", "ip", "10.1.2.3", "block", "{}");
+
+        let rules = rules_from_policies(&[&allow, &block], &[]);
+        assert!(rules.iter().all(|r| r.allow));
+
+        let rules = rules_from_policies(&[&block, &allow], &[]);
+        assert_eq!(rules.iter().filter(|r| !r.allow).count(), 2);
+    }
+
+    #[test]
+    fn partial_port_allow_splits_the_block() {
+        let allow = policy("p1", "ip", "10.1.0.0/16", "allow", r#"{"ports":"443, 8000-8080","protocol":"tcp"}"#);
+        let block = policy("p2", "ip", "10.1.2.3", "block", r#"{"ports":"1-1024, 8080-9000","protocol":"tcp"}"#);
+
+        let rules = rules_from_policies(&[&allow, &block], &[]);
+        let out = rules.iter().find(|r| !r.allow && r.direction == Direction::Out).unwrap();
+        assert_eq!(out.ports, vec!["1-442", "444-1024", "8081-9000"]);
+
+        let covered = policy("p3", "ip", "10.1.2.3", "block", r#"{"ports":"8000-8010","protocol":"tcp"}"#);
+        assert!(rules_from_policies(&[&allow, &covered], &[]).iter().all(|r| r.allow));
+
+        // Noutro protocolo, o bloqueio fica intacto
+        let udp = policy("p4", "ip", "10.1.2.3", "block", r#"{"ports":"443","protocol":"udp"}"#);
+        let rules = rules_from_policies(&[&allow, &udp], &[]);
+        assert!(rules.iter().filter(|r| !r.allow).all(|r| r.ports == vec!["443"]));
+    }
+
+    #[test]
+    fn allow_never_opens_inbound_traffic() {
+        let allow = policy("p1", "ip", "10.1.0.0/16", "allow", "{}");
+        let block_in = policy("p2", "ip", "10.1.2.3", "block", r#"{"direction":"in"}"#);
+
+        let rules = rules_from_policies(&[&allow, &block_in], &[]);
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].allow && rules[0].direction == Direction::Out);
+
+        let inbound_only = policy("p3", "ip", "10.1.0.0/16", "allow", r#"{"direction":"in"}"#);
+        assert!(rules_from_policies(&[&inbound_only], &[]).is_empty());
+    }
+
+    #[test]
+    fn server_is_exempt_from_blocks() {
+        let server: SocketAddr = "10.0.0.5:7474".parse().unwrap();
+        let block = policy("p1", "ip", "10.0.0.0/8", "block", "{}");
+        let udp = policy("p2", "ip", "10.0.0.0/8", "block", r#"{"protocol":"udp","ports":"53"}"#);
+
+        let rules = rules_from_policies(&[&block, &udp], &[server]);
+        assert_eq!(rules[0].policy_id, "server");
+        assert!(rules[0].allow);
+        assert_eq!(rules[0].ports, vec!["7474"]);
+
+        // Só a saída TCP para o servidor é retirada do bloqueio
+        let out = rules.iter().find(|r| r.policy_id == "p1" && r.direction == Direction::Out).unwrap();
+        assert_eq!(out.exclude, vec![server.ip()]);
+        assert!(rules.iter().filter(|r| r.policy_id == "p1" && r.direction == Direction::In).all(|r| r.exclude.is_empty()));
+        assert!(rules.iter().filter(|r| r.policy_id == "p2").all(|r| r.exclude.is_empty()));
+
+        let only_server = policy("p3", "ip", "10.0.0.5", "block", r#"{"direction":"out"}"#);
+        assert_eq!(rules_from_policies(&[&only_server], &[server]).len(), 1);
+    }
+
+    #[test]
+    fn remote_ranges_exclude_addresses() {
+        let server: IpAddr = "10.0.0.5".parse().unwrap();
+        assert_eq!(remote_ranges(Some("10.0.0.0/8"), &[]).as_deref(), Some("10.0.0.0/8"));
+        assert_eq!(
+            remote_ranges(Some("10.0.0.0/8"), &[server]).as_deref(),
+            Some("10.0.0.0-10.0.0.4,10.0.0.6-10.255.255.255")
+        );
+        assert_eq!(remote_ranges(Some("10.0.0.5"), &[server]).as_deref(), Some(""));
+        assert_eq!(
+            remote_ranges(None, &[server]).as_deref(),
+            Some("0.0.0.0-10.0.0.4,10.0.0.6-255.255.255.255,::-ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")
+        );
+    }
+
+    #[test]
+    fn ordered_backends_follow_precedence() {
+        let backend = FakeBackend { ordered: true, ..Default::default() };
+        let installed = backend.rules.clone();
+        let mut firewall = Firewall::new(Box::new(backend));
+
+        let a = policy("a", "ip", "10.0.0.1", "block", r#"{"direction":"out"}"#);
+        let b = policy("b", "ip", "10.0.0.2", "block", r#"{"direction":"out"}"#);
+
+        firewall.reconcile(&rules_from_policies(&[&a], &[])).unwrap();
+        let rules = rules_from_policies(&[&b, &a], &[]);
+        firewall.reconcile(&rules).unwrap();
+
+        let names: Vec<String> = rules.iter().map(|r| r.name.clone()).collect();
+        assert_eq!(*installed.lock().unwrap(), names);
+    }
+
+    #[test]
+    fn normalizes_cidrs() {
+        assert_eq!(normalize_cidr("10.0.0.1/32").as_deref(), Some("10.0.0.1"));
+        assert_eq!(normalize_cidr(" 2001:db8::/32 ").as_deref(), Some("2001:db8::/32"));
+        assert_eq!(normalize_cidr("10.0.0.0/33"), None);
+        assert_eq!(normalize_cidr("example.com"), None);
+    }
+
+    // Requer root e nftables: cria um namespace de rede descartável
+    //   cargo test --package assetscan-agent nftables_in_netns -- --ignored
+    #[test]
+    #[ignore]
+    fn nftables_in_netns() {
+        let ns = format!("assetscan-test-{}", std::process::id());
+        run("ip", &["netns", "add", &ns]).unwrap();
+
+        let mut firewall = Firewall::new(Box::new(NftablesBackend::new(Some(ns.clone()))));
+        let p = policy("p1", "ip", "203.0.113.0/24", "block", r#"{"ports":"80","protocol":"tcp"}"#);
+        let rules = rules_from_policies(&[&p], &[]);
+
+        let result = firewall.reconcile(&rules);
+        let listed = run_in_netns(&Some(ns.clone()), "nft", &["list", "table", "inet", NFT_TABLE]);
+        let cleared = firewall.clear();
+        run("ip", &["netns", "delete", &ns]).unwrap();
+
+        result.unwrap();
+        cleared.unwrap();
+        let listed = listed.unwrap();
+        assert!(listed.contains("203.0.113.0/24"));
+        assert!(listed.contains(&rules[0].name));
+    }
+}
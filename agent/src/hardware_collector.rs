@@ -1,6 +1,6 @@
-use anyhow::Result;
 use serde::{Serialize, Deserialize};
-use std::process::Command;
+
+use crate::platform;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HardwareDetails {
@@ -13,162 +13,128 @@ pub struct HardwareDetails {
     pub total_ram_slots: i64,
     pub used_ram_slots: i64,
     pub ram_type: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct BatteryInfo {
-    pub status: u16,
-    pub estimated_charge_remaining: u16,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MonitorInfo {
-    pub name: String,
-    pub resolution: String,
+    // Inventário por componente; `None` se a recolha falhou (o servidor mantém o anterior)
+    pub memory_modules: Option<Vec<platform::MemoryModule>>,
+    pub gpus: Option<Vec<platform::Gpu>>,
+    pub monitors: Option<Vec<platform::Monitor>>,
+    pub batteries: Option<Vec<platform::Battery>>,
+    pub physical_disks: Option<Vec<platform::PhysicalDisk>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UsbDevice {
     pub name: String,
-    pub device_id: String,
-}
+    pub device_id: String, // Instance ID do PnP (ex: USB\VID_0781&PID_5567\4C5300012304) ou nome no sysfs (ex: 1-2.1)
+    pub vendor_id: String,
+    pub product_id: String,
+    pub serial: Option<String>, // Só quando o dispositivo tem número de série próprio
+    pub device_class: String,
+    pub manufacturer: Option<String>,
+    pub is_mass_storage: bool,
+    pub status: String, // "allowed" | "blocked" | "read_only" (preenchido pelo device_control)
+}
+
+pub fn collect_hardware_details() -> platform::Result<HardwareDetails> {
+    let platform = platform::current();
+    let inventory = platform.hardware()?;
+    let unknown = || "Unknown".to_string();
+
+    let gpus = component("placas gráficas", platform.gpus());
+    // Com gráfica integrada e dedicada, fica a que tem mais memória
+    let main_gpu = gpus
+        .as_ref()
+        .and_then(|gpus| gpus.iter().max_by_key(|g| g.vram_mb.unwrap_or(0)).cloned());
 
-pub fn collect_hardware_details() -> Result<HardwareDetails> {
     Ok(HardwareDetails {
-        serial_number: get_serial_number()?,
-        motherboard_manufacturer: get_motherboard_manufacturer()?,
-        motherboard_model: get_motherboard_model()?,
-        bios_version: get_bios_version()?,
-        gpu_name: get_gpu_name()?,
-        gpu_vram_mb: get_gpu_vram()?,
-        total_ram_slots: get_total_ram_slots()?,
-        used_ram_slots: get_used_ram_slots()?,
-        ram_type: get_ram_type()?,
+        serial_number: inventory.serial_number.unwrap_or_else(|| "Not Available".to_string()),
+        motherboard_manufacturer: inventory.motherboard_manufacturer.unwrap_or_else(unknown),
+        motherboard_model: inventory.motherboard_model.unwrap_or_else(unknown),
+        bios_version: inventory.bios_version.unwrap_or_else(unknown),
+        gpu_name: main_gpu
+            .as_ref()
+            .map(|g| g.name.clone())
+            .unwrap_or_else(|| "Integrated Graphics".to_string()),
+        gpu_vram_mb: main_gpu.and_then(|g| g.vram_mb).unwrap_or(0),
+        total_ram_slots: inventory.total_ram_slots,
+        used_ram_slots: inventory.used_ram_slots,
+        ram_type: inventory.ram_type.unwrap_or_else(unknown),
+        memory_modules: component("módulos de memória", platform.memory_modules()),
+        gpus,
+        monitors: component("monitores", platform.monitors()),
+        batteries: component("baterias", platform.batteries()),
+        physical_disks: component("discos físicos", platform.physical_disks()),
     })
 }
 
-fn get_serial_number() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["bios", "get", "serialnumber"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let serial = result
-        .lines()
-        .nth(1)
-        .unwrap_or("Unknown")
-        .trim()
-        .to_string();
-    
-    Ok(if serial.is_empty() { "Not Available".to_string() } else { serial })
-}
-
-fn get_motherboard_manufacturer() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["baseboard", "get", "manufacturer"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    Ok(result.lines().nth(1).unwrap_or("Unknown").trim().to_string())
-}
-
-fn get_motherboard_model() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["baseboard", "get", "product"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    Ok(result.lines().nth(1).unwrap_or("Unknown").trim().to_string())
-}
-
-fn get_bios_version() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["bios", "get", "version"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    Ok(result.lines().nth(1).unwrap_or("Unknown").trim().to_string())
+/// Cada componente falha por si (ex.: SMBIOS sem root) sem perder o resto do inventário
+fn component<T>(what: &str, result: platform::Result<Vec<T>>) -> Option<Vec<T>> {
+    result
+        .map_err(|e| eprintln!("[Coleta] Erro ao ler {}: {}", what, e))
+        .ok()
+}
+
+/// Dispositivos USB ligados (sem hubs nem interfaces de dispositivos compostos)
+pub fn get_usb_devices() -> platform::Result<Vec<UsbDevice>> {
+    platform::current().usb_devices()
+}
+
+/// "USB\VID_0781&PID_5567\4C530001230405118385" -> ("0781", "5567", Some(serial)).
+/// Interfaces de dispositivos compostos (&MI_xx) são ignoradas; um último segmento com "&"
+/// é um ID gerado pelo Windows, não um número de série
+#[cfg(any(windows, test))]
+pub(crate) fn parse_usb_instance_id(device_id: &str) -> Option<(String, String, Option<String>)> {
+    let mut parts = device_id.split('\\');
+    if !parts.next()?.eq_ignore_ascii_case("usb") {
+        return None;
+    }
+
+    let hardware = parts.next()?.to_uppercase();
+    if hardware.contains("&MI_") {
+        return None;
+    }
+
+    let mut vendor_id = None;
+    let mut product_id = None;
+    for field in hardware.split('&') {
+        if let Some(vid) = field.strip_prefix("VID_") {
+            vendor_id = Some(vid.to_string());
+        } else if let Some(pid) = field.strip_prefix("PID_") {
+            product_id = Some(pid.to_string());
+        }
+    }
+
+    let serial = parts
+        .next()
+        .filter(|s| !s.is_empty() && !s.contains('&'))
+        .map(|s| s.to_string());
+
+    Some((vendor_id?, product_id?, serial))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usb_instance_id_with_serial() {
+        assert_eq!(
+            parse_usb_instance_id(r"USB\VID_0781&PID_5567\4C530001230405118385"),
+            Some(("0781".to_string(), "5567".to_string(), Some("4C530001230405118385".to_string())))
+        );
+    }
+
+    #[test]
+    fn usb_instance_id_generated_by_windows_has_no_serial() {
+        assert_eq!(
+            parse_usb_instance_id(r"USB\VID_046D&PID_C52B\5&2B3F1A0&0&2"),
+            Some(("046D".to_string(), "C52B".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn usb_interfaces_and_other_buses_are_skipped() {
+        assert_eq!(parse_usb_instance_id(r"USB\VID_046D&PID_C52B&MI_00\6&1A2B3C&0&0000"), None);
+        assert_eq!(parse_usb_instance_id(r"PCI\VEN_8086&DEV_A370\3&11583659&0&A3"), None);
+        assert_eq!(parse_usb_instance_id(r"USB\ROOT_HUB30\4&2A1B3C&0&0"), None);
+    }
 }
-
-fn get_gpu_name() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["path", "win32_VideoController", "get", "name"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let gpu = result.lines().nth(1).unwrap_or("Unknown").trim();
-    Ok(if gpu.is_empty() { "Integrated Graphics".to_string() } else { gpu.to_string() })
-}
-
-fn get_gpu_vram() -> Result<i64> {
-    let output = Command::new("wmic")
-        .args(&["path", "win32_VideoController", "get", "AdapterRAM"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let vram_str = result.lines().nth(1).unwrap_or("0").trim();
-    let vram_bytes: i64 = vram_str.parse().unwrap_or(0);
-    
-    Ok(vram_bytes / 1024 / 1024) // Convert to MB
-}
-
-fn get_total_ram_slots() -> Result<i64> {
-    let output = Command::new("wmic")
-        .args(&["memphysical", "get", "MemoryDevices"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let slots_str = result.lines().nth(1).unwrap_or("0").trim();
-    Ok(slots_str.parse().unwrap_or(0))
-}
-
-fn get_used_ram_slots() -> Result<i64> {
-    let output = Command::new("wmic")
-        .args(&["memorychip", "get", "capacity"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let count = result.lines().skip(1).filter(|line| !line.trim().is_empty()).count();
-    Ok(count as i64)
-}
-
-fn get_ram_type() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["memorychip", "get", "MemoryType"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let mem_type_code: i32 = result
-        .lines()
-        .nth(1)
-        .unwrap_or("0")
-        .trim()
-        .parse()
-        .unwrap_or(0);
-    
-    // Memory type codes: https://docs.microsoft.com/en-us/windows/win32/cimwin32prov/win32-physicalmemory
-    let ram_type = match mem_type_code {
-        24 => "DDR3",
-        26 => "DDR4",
-        27 => "DDR5",
-        _ => "Unknown",
-    };
-    
-    Ok(ram_type.to_string())
-}
-
-pub fn get_battery_info() -> Result<BatteryInfo> {
-    // Implementação pendente...
-    Ok(BatteryInfo {
-        status: 2, // 2 = AC Power (Ligado à corrente)
-        estimated_charge_remaining: 100,
-    })
-}
-
-pub fn get_monitors() -> Result<Vec<MonitorInfo>> {
-    Ok(vec![]) // Retorna lista vazia por agora
-}
-
-pub fn get_usb_devices() -> Result<Vec<UsbDevice>> {
-    Ok(vec![]) // Retorna lista vazia por agora
-}
\ No newline at end of file
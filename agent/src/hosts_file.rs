@@ -0,0 +1,237 @@
+// ============================================================
+// hosts_file.rs — Bloco gerido pelo AssetScan no ficheiro hosts
+// Só toca nas linhas entre os marcadores; escreve apenas quando
+// o conteúdo muda, com backup e substituição atómica
+// ============================================================
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const BEGIN_MARKER: &str = "# BEGIN ASSETSCAN (bloco gerido automaticamente, não editar)";
+const END_MARKER: &str = "# END ASSETSCAN";
+
+// Formato antigo (linhas soltas terminadas em "# ASSETSCAN")
+const LEGACY_MARKER: &str = "# ASSETSCAN";
+
+#[derive(Debug, Clone)]
+pub struct HostsFile {
+    path: PathBuf,
+}
+
+impl HostsFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        HostsFile { path: path.into() }
+    }
+
+    /// Ficheiro hosts do sistema operativo
+    pub fn system_path() -> PathBuf {
+        if cfg!(windows) {
+            let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+            PathBuf::from(root).join(r"System32\drivers\etc\hosts")
+        } else {
+            PathBuf::from("/etc/hosts")
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        self.path.with_extension("assetscan.bak")
+    }
+
+    // Lock exclusivo num ficheiro ao lado do hosts: serializa as escritas do enforcer
+    // e do `--cleanup`, que corre noutro processo. Liberta-se quando o ficheiro fecha
+    fn lock(&self) -> Result<fs::File> {
+        let path = self.path().with_extension("assetscan.lock");
+        let file = fs::File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Falha ao abrir {}", path.display()))?;
+        file.lock()
+            .with_context(|| format!("Falha ao obter o lock {}", path.display()))?;
+        Ok(file)
+    }
+
+    /// Substitui o bloco gerido pelos domínios indicados (lista vazia remove o bloco).
+    /// Devolve `true` se o ficheiro foi alterado.
+    pub fn apply(&self, domains: &[String]) -> Result<bool> {
+        let _lock = self.lock()?;
+
+        let current = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Falha ao ler {}", self.path.display()))
+            }
+        };
+
+        let updated = render(&current, domains);
+        if updated == current {
+            return Ok(false);
+        }
+
+        // Backup do ficheiro original, antes da primeira alteração do AssetScan
+        let backup = self.backup_path();
+        if !backup.exists() && !current.is_empty() {
+            fs::write(&backup, &current)
+                .with_context(|| format!("Falha ao criar backup em {}", backup.display()))?;
+        }
+
+        self.write_atomic(&updated)?;
+        flush_dns_cache();
+        Ok(true)
+    }
+
+    /// Remove o bloco gerido (desinstalação ou todas as políticas web removidas)
+    pub fn restore(&self) -> Result<bool> {
+        self.apply(&[])
+    }
+
+    // Escreve num ficheiro temporário na mesma pasta e substitui com rename
+    fn write_atomic(&self, content: &str) -> Result<()> {
+        let tmp = self.path.with_extension("assetscan.tmp");
+
+        let mut file = fs::File::create(&tmp)
+            .with_context(|| format!("Sem permissão para escrever em {}", tmp.display()))?;
+        file.write_all(content.as_bytes())
+            .context("Falha ao escrever no ficheiro hosts temporário")?;
+        file.sync_all().ok();
+        drop(file);
+
+        if let Err(e) = fs::rename(&tmp, &self.path) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e).with_context(|| format!("Falha ao substituir {}", self.path.display()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Conteúdo final: o ficheiro sem o bloco antigo, seguido do bloco novo
+fn render(current: &str, domains: &[String]) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_block = false;
+
+    for line in current.lines() {
+        let trimmed = line.trim();
+        if trimmed == BEGIN_MARKER {
+            in_block = true;
+            continue;
+        }
+        if trimmed == END_MARKER {
+            in_block = false;
+            continue;
+        }
+        if in_block || trimmed.ends_with(LEGACY_MARKER) || trimmed == "# ASSETSCAN AUTOGENERATED" {
+            continue;
+        }
+        lines.push(line);
+    }
+
+    // Remove linhas vazias deixadas no fim pelo bloco anterior
+    while lines.last().map(|l| l.trim().is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+
+    let mut output = lines.join("\n");
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    if !domains.is_empty() {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(BEGIN_MARKER);
+        output.push('\n');
+        for domain in domains {
+            output.push_str(&format!("0.0.0.0 {}\n", domain));
+        }
+        output.push_str(END_MARKER);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Expande um domínio de política para as entradas do hosts (o hosts não suporta wildcards):
+/// "*.exemplo.com" ou "exemplo.com" + subdomínios configurados ("www", "m", ...)
+pub fn expand_domain(domain: &str, subdomains: &[String]) -> Vec<String> {
+    let base = domain.trim().trim_start_matches("*.").trim_end_matches('.').to_lowercase();
+    if base.is_empty() {
+        return vec![];
+    }
+
+    let mut entries = vec![base.clone()];
+    for sub in subdomains {
+        let sub = sub.trim().trim_matches('.').to_lowercase();
+        if !sub.is_empty() && sub != "*" {
+            entries.push(format!("{}.{}", sub, base));
+        }
+    }
+    entries
+}
+
+fn flush_dns_cache() {
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("ipconfig").arg("/flushdns").output();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_hosts(name: &str, content: &str) -> HostsFile {
+        let dir = std::env::temp_dir().join(format!("assetscan-hosts-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hosts");
+        fs::write(&path, content).unwrap();
+        HostsFile::new(path)
+    }
+
+    #[test]
+    fn apply_is_idempotent_and_keeps_user_lines() {
+        let hosts = temp_hosts("idempotent", "127.0.0.1 localhost\n10.0.0.1 intranet\n");
+        let domains = expand_domain("facebook.com", &["www".to_string()]);
+
+        assert!(hosts.apply(&domains).unwrap());
+        assert!(!hosts.apply(&domains).unwrap());
+
+        let content = fs::read_to_string(hosts.path()).unwrap();
+        assert!(content.starts_with("127.0.0.1 localhost\n10.0.0.1 intranet\n"));
+        assert!(content.contains("0.0.0.0 www.facebook.com"));
+        assert_eq!(content.matches(BEGIN_MARKER).count(), 1);
+        assert!(hosts.backup_path().exists());
+    }
+
+    #[test]
+    fn restore_removes_block_and_legacy_lines() {
+        let original = "127.0.0.1 localhost\n";
+        let hosts = temp_hosts(
+            "restore",
+            "127.0.0.1 localhost\n# ASSETSCAN AUTOGENERATED\n127.0.0.1 youtube.com # ASSETSCAN\n",
+        );
+
+        hosts.apply(&["tiktok.com".to_string()]).unwrap();
+        assert!(hosts.restore().unwrap());
+        assert_eq!(fs::read_to_string(hosts.path()).unwrap(), original);
+    }
+
+    #[test]
+    fn expand_domain_handles_wildcards_and_subdomains() {
+        let subs = vec!["www".to_string(), "m".to_string()];
+        assert_eq!(
+            expand_domain("*.Reddit.com", &subs),
+            vec!["reddit.com", "www.reddit.com", "m.reddit.com"]
+        );
+        assert!(expand_domain("  ", &subs).is_empty());
+    }
+}
@@ -2,14 +2,24 @@
 
 mod collector;
 mod enforcer;
+mod device_control;
+mod dns_filter;
+mod file_identity;
+mod firewall;
+mod hosts_file;
+mod monitor;
+mod push_channel;
+mod remote_commands;
 mod config;
 mod notifications;
 mod screenshot;
 mod network_collector;
+mod platform;
 mod screen_time_tracker;
-mod wmi_collector;
 mod hardware_collector;
 mod security_collector;
+mod schedule;
+mod session_helper;
 
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -24,10 +34,22 @@ lazy_static::lazy_static! {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Helper lançado pelo agente na sessão do utilizador (capturas, avisos). Antes de
+    // qualquer output: o stdout do helper é o pipe com a resposta para o agente
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(pos) = args.iter().position(|a| a == session_helper::HELPER_ARG) {
+        return session_helper::run(&args[pos + 1..]);
+    }
+
     println!("╔═══════════════════════════════════════════════════════╗");
     println!("║         AssetScan Agent v4.0.0 BETA                   ║");
     println!("╚═══════════════════════════════════════════════════════╝\n");
 
+    // Desinstalação: remove tudo o que o agente alterou no sistema e termina
+    if std::env::args().any(|a| a == "--cleanup") {
+        return cleanup(&config::Config::load().unwrap_or_default());
+    }
+
     let config = config::Config::load()
         .context("Falha ao carregar configuração")?;
 
@@ -41,21 +63,72 @@ async fn main() -> Result<()> {
     let interval_minutes = config.interval_minutes.min(5);
     let mut interval = time::interval(Duration::from_secs(interval_minutes * 60));
 
+    if config.enforcement_enabled && config.dns_filter_enabled {
+        let exempt: Vec<String> = config.server_host().into_iter().collect();
+        tokio::spawn(dns_filter::start(config.dns_upstream.clone(), exempt));
+    } else if let Err(e) = dns_filter::restore_system_resolver() {
+        // Filtro desligado depois de ter estado activo: o DNS não pode ficar em 127.0.0.1
+        eprintln!("[DNS] ERRO ao repor o resolver do sistema: {:#}", e);
+    }
+
     if config.enforcement_enabled {
+        // Nenhuma regra de firewall pode cortar a ligação ao servidor
+        if let Some(host) = config.server_host() {
+            match tokio::net::lookup_host((host.as_str(), config.server_port())).await {
+                Ok(addrs) => firewall::exempt_server(addrs.collect()),
+                Err(e) => eprintln!("[Firewall] Não foi possível resolver o servidor {}: {}", host, e),
+            }
+        }
+
+        // Políticas da última sessão: horários e quotas continuam a valer sem servidor
+        *ACTIVE_POLICIES.lock().unwrap() = enforcer::load_policy_cache();
+        let hosts = config.hosts_file();
+
         tokio::spawn(async move {
             let mut ticker = time::interval(Duration::from_secs(5));
+            // Uma passagem lenta atrasa a seguinte em vez de as acumular
+            ticker.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
             loop {
                 ticker.tick().await;
+                // Corre mesmo sem políticas, para limpar o que foi aplicado por políticas removidas
                 let policies = ACTIVE_POLICIES.lock().unwrap().clone();
-                if !policies.is_empty() {
-                    enforcer::enforce_active_policies(&policies);
+                let hosts = hosts.clone();
+                // Processos, firewall e ficheiro hosts bloqueiam: fora dos workers do runtime
+                let pass = tokio::task::spawn_blocking(move || enforcer::enforce_active_policies(&policies, &hosts));
+                if let Err(e) = pass.await {
+                    eprintln!("[Enforcer] Passagem interrompida: {}", e);
+                }
+            }
+        });
+    }
+
+    // Comandos do dashboard entre relatórios: long-poll, com polling periódico de reserva
+    if let Ok(machine_id) = collector::get_or_create_machine_id() {
+        if config.push_enabled {
+            tokio::spawn(push_channel::run(config.clone(), machine_id.clone()));
+        }
+
+        let config = config.clone();
+        tokio::spawn(async move {
+            let mut ticker = time::interval(Duration::from_secs(30));
+            loop {
+                ticker.tick().await;
+                if push_channel::is_connected() {
+                    continue;
+                }
+                if let Err(e) = remote_commands::poll(&config, &machine_id).await {
+                    eprintln!("[Commands] Erro ao obter comandos: {}", e);
                 }
             }
         });
     }
 
     loop {
-        interval.tick().await;
+        // Ciclo normal, ou antecipado por um comando "collect_now"
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = remote_commands::COLLECT_NOW.notified() => {}
+        }
 
         if let Err(e) = run_cycle(&config).await {
             eprintln!("[Agent] ERRO: {}", e);
@@ -63,48 +136,112 @@ async fn main() -> Result<()> {
     }
 }
 
+fn cleanup(config: &config::Config) -> Result<()> {
+    println!("[Agent] A remover alterações do AssetScan...");
+
+    match config.hosts_file().restore() {
+        Ok(true) => println!("[Agent] ✓ Bloco do ficheiro hosts removido"),
+        Ok(false) => println!("[Agent] ✓ Ficheiro hosts sem alterações do AssetScan"),
+        Err(e) => eprintln!("[Agent] ERRO ao restaurar ficheiro hosts: {:#}", e),
+    }
+
+    match firewall::cleanup() {
+        Ok(()) => println!("[Agent] ✓ Regras de firewall do AssetScan removidas"),
+        Err(e) => eprintln!("[Agent] ERRO ao remover regras de firewall: {:#}", e),
+    }
+
+    match dns_filter::restore_system_resolver() {
+        Ok(true) => println!("[Agent] ✓ Servidores DNS dos adaptadores repostos"),
+        Ok(false) => {}
+        Err(e) => eprintln!("[Agent] ERRO ao repor servidores DNS: {:#}", e),
+    }
+
+    match device_control::cleanup() {
+        Ok(()) => println!("[Agent] ✓ Dispositivos USB reactivados"),
+        Err(e) => eprintln!("[Agent] ERRO ao reactivar dispositivos: {:#}", e),
+    }
+
+    enforcer::save_policy_cache(&[]);
+    Ok(())
+}
+
 async fn run_cycle(config: &config::Config) -> Result<()> {
     println!("\n[{}] ═══ Ciclo Iniciado ═══", Utc::now().format("%H:%M:%S"));
 
     // 1. Coleta dados
-    let mut report = collector::collect_full_report(config)?;
+    // A recolha é síncrona (WMI, comandos do sistema): corre fora das threads do runtime
+    let collect_config = config.clone();
+    let mut report = tokio::task::spawn_blocking(move || collector::collect_full_report(&collect_config)).await??;
+    network_collector::resolve_remote_hosts(&mut report.network_connections).await;
     
-    // 2. Adiciona screen time (atualizado a cada 5 min)
+    // 2. Adiciona screen time (dia corrente e dias anteriores ainda por enviar)
     report.screen_time = screen_time_tracker::get_daily_stats();
+
+    // 3. Revisões das políticas actualmente em aplicação
+    report.policy_revisions = enforcer::revision_states(&ACTIVE_POLICIES.lock().unwrap());
+    report.enforcement = enforcer::enforcement_snapshot();
+    report.app_violations = enforcer::app_violation_snapshot();
+    report.policy_matches = monitor::match_snapshot();
+    report.dns_stats = dns_filter::stats_snapshot();
+    report.usb_devices = device_control::inventory();
+    report.device_events = device_control::event_snapshot();
     
     println!("[Coleta] ✓ {} processos | {} apps | {} conexões", 
         report.processes.len(), 
         report.software.len(),
-        report.network.len()
+        report.network_connections.len()
     );
 
-    // 3. Envia ao servidor e recebe políticas
-    let policies = send_report_and_get_policies(&report, config).await?;
+    // 4. Envia ao servidor e recebe políticas
+    let response = send_report(&report, config).await?;
+    if let Some(token) = &response.agent_token {
+        match config::save_agent_token(token) {
+            Ok(()) => println!("[Server] ✓ Credencial da máquina recebida"),
+            Err(e) => eprintln!("[Server] Erro ao guardar credencial da máquina: {}", e),
+        }
+    }
+    let policies = response.policies;
     println!("[Server] ✓ {} políticas recebidas", policies.len());
-
-    // Atualiza a memória partilhada para o Enforcer rápido ler
-    if config.enforcement_enabled {
-        *ACTIVE_POLICIES.lock().unwrap() = policies;
+    remote_commands::dispatch(response.commands, config, &report.machine_id);
+    if let Err(e) = remote_commands::flush_results(config).await {
+        eprintln!("[Commands] Erro ao enviar resultados: {}", e);
     }
+    enforcer::acknowledge_enforcement(&report.enforcement);
+    enforcer::acknowledge_app_violations(&report.app_violations);
+    monitor::acknowledge_matches(&report.policy_matches);
+    dns_filter::acknowledge_stats(&report.dns_stats);
+    device_control::acknowledge_events(&report.device_events);
+    screen_time_tracker::acknowledge_daily_stats(&report.screen_time);
+
+    apply_policies(config, policies);
 
     println!("[Completo] Ciclo OK\n");
     Ok(())
 }
 
-async fn send_report_and_get_policies(
+/// Atualiza a memória partilhada para o Enforcer rápido ler
+pub(crate) fn apply_policies(config: &config::Config, policies: Vec<enforcer::Policy>) {
+    if config.enforcement_enabled {
+        enforcer::save_policy_cache(&policies);
+        *ACTIVE_POLICIES.lock().unwrap() = policies;
+    }
+}
+
+async fn send_report(
     report: &collector::SystemReport,
     config: &config::Config,
-) -> Result<Vec<enforcer::Policy>> {
+) -> Result<ServerResponse> {
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(30))
         .build()?;
 
     let url = format!("{}/api/v2/report", config.server_url);
 
-    let response = client
+    let request = client
         .post(&url)
         .header("X-API-Key", &config.api_key)
-        .header("X-Agent-Version", "4.0.0")
+        .header("X-Agent-Version", "4.0.0");
+    let response = config::with_agent_token(request)
         .json(report)
         .send()
         .await
@@ -114,8 +251,7 @@ async fn send_report_and_get_policies(
         anyhow::bail!("Servidor erro: {}", response.status());
     }
 
-    let response_data: ServerResponse = response.json().await?;
-    Ok(response_data.policies)
+    Ok(response.json().await?)
 }
 
 #[derive(serde::Deserialize)]
@@ -123,4 +259,9 @@ struct ServerResponse {
     status: String,
     message: String,
     policies: Vec<enforcer::Policy>,
+    #[serde(default)]
+    commands: Vec<remote_commands::AgentCommand>,
+    /// Só no primeiro relatório da máquina
+    #[serde(default)]
+    agent_token: Option<String>,
 }
\ No newline at end of file
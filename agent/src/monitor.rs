@@ -0,0 +1,302 @@
+// ============================================================
+// monitor.rs — Acção "alert" (modo auditoria)
+// Regista o que cada política teria afectado, sem bloquear nada
+// ============================================================
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::process::Command;
+use std::sync::Mutex;
+use sysinfo::{Pid, System, Users};
+
+use crate::dns_filter;
+use crate::enforcer::{self, Policy};
+use crate::platform;
+
+// A cache DNS do Windows só é consultada de minuto a minuto (PowerShell é lento)
+const DNS_CACHE_INTERVAL_SECS: i64 = 60;
+const CONSOLE_USER_TTL_SECS: i64 = 60;
+
+lazy_static::lazy_static! {
+    // Correspondências desde o último relatório, por (política, alvo)
+    static ref POLICY_MATCHES: Mutex<HashMap<(String, String), PolicyMatch>> = Mutex::new(HashMap::new());
+    static ref LAST_DNS_CHECK: Mutex<Option<DateTime<Utc>>> = Mutex::new(None);
+    static ref CONSOLE_USER: Mutex<Option<(DateTime<Utc>, Option<String>)>> = Mutex::new(None);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyMatch {
+    pub policy_id: String,
+    pub policy_type: String,
+    pub subject: String, // Processo, domínio ou IP remoto que coincidiu
+    pub process_name: Option<String>,
+    pub pid: Option<u32>,
+    pub user: Option<String>,
+    pub domain: Option<String>,
+    pub remote_ip: Option<String>,
+    pub count: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+struct MatchDetails {
+    subject: String,
+    process_name: Option<String>,
+    pid: Option<u32>,
+    user: Option<String>,
+    domain: Option<String>,
+    remote_ip: Option<String>,
+}
+
+// O mesmo alvo a correr entre ciclos conta uma vez por relatório; um novo PID conta de novo
+fn record(policy: &Policy, details: MatchDetails) {
+    if policy.id.is_empty() {
+        return;
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let key = (policy.id.clone(), details.subject.to_lowercase());
+    let mut matches = POLICY_MATCHES.lock().unwrap();
+
+    if let Some(existing) = matches.get_mut(&key) {
+        if details.pid.is_some() && existing.pid != details.pid {
+            existing.count += 1;
+            existing.pid = details.pid;
+        }
+        existing.last_seen = now;
+        return;
+    }
+
+    println!("[Monitor] Política '{}' ({}) coincidiu com {}", policy.target, policy.policy_type, details.subject);
+    matches.insert(
+        key,
+        PolicyMatch {
+            policy_id: policy.id.clone(),
+            policy_type: policy.policy_type.clone(),
+            subject: details.subject,
+            process_name: details.process_name,
+            pid: details.pid,
+            user: details.user,
+            domain: details.domain,
+            remote_ip: details.remote_ip,
+            count: 1,
+            first_seen: now.clone(),
+            last_seen: now,
+        },
+    );
+}
+
+fn process_user(sys: &System, users: &Users, pid: Pid) -> Option<String> {
+    let uid = sys.process(pid)?.user_id()?;
+    users.get_user_by_id(uid).map(|u| u.name().to_string())
+}
+
+/// Processo que coincidiu com uma regra de aplicação "alert"
+pub fn record_app_match(policy: &Policy, sys: &System, users: &Users, pid: Pid, name: &str) {
+    record(
+        policy,
+        MatchDetails {
+            subject: name.to_string(),
+            process_name: Some(name.to_string()),
+            pid: Some(pid.as_u32()),
+            user: process_user(sys, users, pid),
+            domain: None,
+            remote_ip: None,
+        },
+    );
+}
+
+/// Domínio consultado que coincidiu com uma regra web "alert" (usado também pelo resolver DNS)
+pub fn record_domain_match(policy: &Policy, domain: &str, user: Option<String>) {
+    record(
+        policy,
+        MatchDetails {
+            subject: domain.to_string(),
+            process_name: None,
+            pid: None,
+            user,
+            domain: Some(domain.to_string()),
+            remote_ip: None,
+        },
+    );
+}
+
+/// Dispositivo USB ligado que coincidiu com uma regra de dispositivo "alert"
+pub fn record_device_match(policy: &Policy, device_name: &str, serial: Option<&str>) {
+    record(
+        policy,
+        MatchDetails {
+            subject: serial.map(|s| format!("{} ({})", device_name, s)).unwrap_or_else(|| device_name.to_string()),
+            process_name: None,
+            pid: None,
+            user: current_console_user(),
+            domain: None,
+            remote_ip: None,
+        },
+    );
+}
+
+/// Regras web: compara os domínios consultados com as regras em que "alert" é a acção vencedora.
+/// Com o filtro DNS local a correr usa o seu registo; senão, lê a cache DNS do sistema
+pub fn check_web_alerts(web_policies: &[&Policy]) {
+    let recent = dns_filter::take_recent_domains();
+    if !web_policies.iter().any(|p| p.action == "alert") {
+        return;
+    }
+
+    let domains = match recent {
+        Some(domains) => domains,
+        None => {
+            let mut last = LAST_DNS_CHECK.lock().unwrap();
+            let now = Utc::now();
+            if last.map(|t| (now - t).num_seconds() < DNS_CACHE_INTERVAL_SECS).unwrap_or(false) {
+                return;
+            }
+            *last = Some(now);
+            drop(last);
+            dns_cache_entries()
+        }
+    };
+
+    let user = current_console_user();
+    for domain in domains {
+        let winner = web_policies
+            .iter()
+            .find(|p| enforcer::web_domains(p).iter().any(|rule| enforcer::domain_covers(rule, &domain)));
+        if let Some(policy) = winner.filter(|p| p.action == "alert") {
+            record_domain_match(policy, &domain, user.clone());
+        }
+    }
+}
+
+/// Regras IP: compara as ligações TCP activas com as regras em que "alert" é a acção vencedora
+pub fn check_ip_alerts(ip_policies: &[&Policy], sys: &System) {
+    if !ip_policies.iter().any(|p| p.action == "alert") {
+        return;
+    }
+
+    let sockets = match platform::current().sockets() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[Monitor] Erro ao listar ligações: {}", e);
+            return;
+        }
+    };
+
+    let users = Users::new_with_refreshed_list();
+    for socket in sockets.into_iter().filter(|s| s.protocol == "tcp") {
+        let Some(remote) = socket.remote_addr else {
+            continue;
+        };
+        if remote.is_unspecified() || remote.is_loopback() {
+            continue;
+        }
+
+        let winner = ip_policies.iter().find(|p| ip_matches(&p.target, remote));
+        if let Some(policy) = winner.filter(|p| p.action == "alert") {
+            let pid = socket.pid.map(Pid::from_u32);
+            let process_name = pid
+                .and_then(|pid| sys.process(pid))
+                .map(|p| p.name().to_string_lossy().to_string());
+            record(
+                policy,
+                MatchDetails {
+                    subject: remote.to_string(),
+                    process_name,
+                    pid: socket.pid,
+                    user: pid.and_then(|pid| process_user(sys, &users, pid)),
+                    domain: None,
+                    remote_ip: Some(remote.to_string()),
+                },
+            );
+        }
+    }
+}
+
+/// IP exacto ou bloco CIDR ("10.0.0.0/8", "2001:db8::/32")
+pub fn ip_matches(rule: &str, ip: IpAddr) -> bool {
+    let rule = rule.trim();
+    let (base, prefix) = match rule.split_once('/') {
+        Some((base, prefix)) => (base, prefix.parse::<u32>().ok()),
+        None => (rule, None),
+    };
+
+    let Ok(base) = base.parse::<IpAddr>() else {
+        return false;
+    };
+
+    match (base, ip) {
+        (IpAddr::V4(b), IpAddr::V4(i)) => {
+            let bits = prefix.unwrap_or(32).min(32);
+            let mask = if bits == 0 { 0 } else { u32::MAX << (32 - bits) };
+            (u32::from(b) & mask) == (u32::from(i) & mask)
+        }
+        (IpAddr::V6(b), IpAddr::V6(i)) => {
+            let bits = prefix.unwrap_or(128).min(128);
+            let mask = if bits == 0 { 0 } else { u128::MAX << (128 - bits) };
+            (u128::from(b) & mask) == (u128::from(i) & mask)
+        }
+        _ => false,
+    }
+}
+
+fn dns_cache_entries() -> Vec<String> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-DnsClientCache | Select-Object -ExpandProperty Entry",
+        ])
+        .output();
+
+    match output {
+        Ok(out) => {
+            let mut entries: Vec<String> = String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .map(|l| l.trim().trim_end_matches('.').to_lowercase())
+                .filter(|l| !l.is_empty())
+                .collect();
+            entries.sort();
+            entries.dedup();
+            entries
+        }
+        Err(_) => vec![],
+    }
+}
+
+// A cache DNS é global: atribuímos a consulta ao utilizador com sessão iniciada.
+// A consulta (WMI no Windows) é guardada durante um minuto
+fn current_console_user() -> Option<String> {
+    let mut cached = CONSOLE_USER.lock().unwrap();
+    let now = Utc::now();
+    if let Some((at, user)) = cached.as_ref() {
+        if (now - *at).num_seconds() < CONSOLE_USER_TTL_SECS {
+            return user.clone();
+        }
+    }
+
+    let user = platform::current().computer_identity().ok().and_then(|id| id.console_user);
+    *cached = Some((now, user.clone()));
+    user
+}
+
+/// Correspondências acumuladas para enviar no próximo relatório
+pub fn match_snapshot() -> Vec<PolicyMatch> {
+    POLICY_MATCHES.lock().unwrap().values().cloned().collect()
+}
+
+/// Depois de um envio com sucesso, remove as correspondências já reportadas
+pub fn acknowledge_matches(sent: &[PolicyMatch]) {
+    let mut matches = POLICY_MATCHES.lock().unwrap();
+    for m in sent {
+        let key = (m.policy_id.clone(), m.subject.to_lowercase());
+        let reported = matches.get(&key).map(|c| c.count <= m.count).unwrap_or(false);
+        if reported {
+            matches.remove(&key);
+        } else if let Some(current) = matches.get_mut(&key) {
+            current.count = current.count.saturating_sub(m.count);
+        }
+    }
+}
@@ -1,200 +1,452 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::platform::{self, prefix_mask, ComputerIdentity, Neighbor, NetworkAdapter, Route};
+
+/// Resumo do adaptador principal (o do IP de saída) e inventário de todas as interfaces.
+/// O que não se conseguiu ler fica a `None`, nunca com um valor inventado
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct NetworkDetails {
-    pub local_ip: String,
-    pub subnet_mask: String,
-    pub gateway: String,
-    pub dns_primary: String,
+    pub local_ip: Option<String>,
+    pub subnet_mask: Option<String>,
+    pub gateway: Option<String>,
+    pub dns_primary: Option<String>,
     pub dns_secondary: Option<String>,
-    pub dhcp_enabled: bool,
-    pub domain_name: String,
+    pub dhcp_enabled: Option<bool>,
+    pub domain_name: Option<String>,
     pub is_domain_joined: bool,
-    pub mac_address: String,
-    pub adapter_name: String,
-    pub connection_speed_mbps: i64,
+    pub mac_address: Option<String>,
+    pub adapter_name: Option<String>,
+    pub connection_speed_mbps: Option<i64>,
     pub wifi_ssid: Option<String>,
     pub wifi_security: Option<String>,
+    pub adapters: Vec<AdapterInfo>,
+    pub dns_servers: Vec<String>, // Todos, sem repetidos, pela ordem dos adaptadores
+    pub routes: Vec<RouteInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub description: String,
+    pub kind: String, // "physical" | "virtual" | "vpn"
+    pub mac_address: Option<String>,
+    pub addresses: Vec<AdapterAddress>,
+    pub gateways: Vec<String>,
+    /// MAC do primeiro gateway (tabela ARP/NDP): distingue redes privadas com o mesmo endereçamento
+    pub gateway_mac: Option<String>,
+    pub dns_servers: Vec<String>,
+    pub dhcp_enabled: Option<bool>,
+    pub speed_mbps: Option<i64>,
+    pub is_up: bool,
+    pub is_wireless: bool,
+    pub is_primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AdapterAddress {
+    pub family: String, // "ipv4" | "ipv6"
+    pub address: String,
+    pub prefix_length: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RouteInfo {
+    pub interface: String,
+    pub destination: String,
+    pub prefix_length: u8,
+    pub gateway: Option<String>,
+    pub metric: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NetworkConnection {
+    pub protocol: String, // "tcp" | "udp"
     pub pid: u32,
+    pub process_name: Option<String>,
     pub local_ip: String,
-    pub remote_ip: String,
+    pub local_port: u16,
+    pub remote_ip: String, // Vazio em sockets à escuta e UDP
+    pub remote_port: u16,
+    pub remote_host: Option<String>, // DNS inverso do remote_ip
     pub state: String,
 }
 
-pub fn collect_network_details() -> Result<NetworkDetails> {
-    Ok(NetworkDetails {
-        local_ip: get_local_ip()?,
-        subnet_mask: get_subnet_mask()?,
-        gateway: get_gateway()?,
-        dns_primary: get_dns_primary()?,
-        dns_secondary: get_dns_secondary(),
-        dhcp_enabled: is_dhcp_enabled()?,
-        domain_name: get_domain_name()?,
-        is_domain_joined: check_domain_joined()?,
-        mac_address: get_mac_address()?,
-        adapter_name: get_adapter_name()?,
-        connection_speed_mbps: get_connection_speed()?,
-        wifi_ssid: get_wifi_ssid(),
-        wifi_security: get_wifi_security(),
-    })
-}
-
-pub fn get_local_ip() -> Result<String> {
-    // Já implementado anteriormente
-    if let Ok(socket) = std::net::UdpSocket::bind("0.0.0.0:0") {
-        if socket.connect("8.8.8.8:80").is_ok() {
-            if let Ok(addr) = socket.local_addr() {
-                return Ok(addr.ip().to_string());
+/// Vizinho da tabela ARP/NDP: outro equipamento na mesma rede, com ou sem agente
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetworkNeighbor {
+    pub ip: String,
+    pub mac_address: String,
+    pub interface: String,
+}
+
+// Resoluções inversas novas por recolha (cada uma pode demorar segundos)
+const MAX_REVERSE_LOOKUPS: usize = 32;
+const REVERSE_DNS_TTL_SECS: u64 = 6 * 3600;
+// Tempo máximo que o ciclo espera pelas resoluções; as que acabarem depois ficam
+// em cache para a recolha seguinte
+const REVERSE_DNS_WAIT: Duration = Duration::from_secs(3);
+
+lazy_static::lazy_static! {
+    // IP → (nome, instante da resolução); falhas também ficam guardadas
+    static ref REVERSE_DNS: Mutex<HashMap<IpAddr, (Option<String>, Instant)>> = Mutex::new(HashMap::new());
+}
+
+/// Sockets TCP/UDP abertos, com o nome do processo dono e o DNS inverso do destino já em cache
+/// (os restantes são resolvidos por `resolve_remote_hosts`)
+pub fn collect_connections(processes: &HashMap<u32, String>) -> platform::Result<Vec<NetworkConnection>> {
+    let sockets = platform::current().sockets()?;
+
+    Ok(sockets
+        .into_iter()
+        .map(|socket| {
+            let remote_host = socket.remote_addr.and_then(cached_reverse_dns);
+            let pid = socket.pid.unwrap_or(0);
+
+            NetworkConnection {
+                protocol: socket.protocol.to_string(),
+                pid,
+                process_name: processes.get(&pid).cloned(),
+                local_ip: socket.local_addr.to_string(),
+                local_port: socket.local_port,
+                remote_ip: socket.remote_addr.map(|a| a.to_string()).unwrap_or_default(),
+                remote_port: socket.remote_port,
+                remote_host,
+                state: socket.state,
             }
-        }
+        })
+        .collect())
+}
+
+fn cached_reverse_dns(addr: IpAddr) -> Option<String> {
+    let cache = REVERSE_DNS.lock().unwrap();
+    let (name, at) = cache.get(&addr)?;
+    (at.elapsed().as_secs() < REVERSE_DNS_TTL_SECS).then(|| name.clone()).flatten()
+}
+
+fn needs_reverse_dns(addr: &IpAddr) -> bool {
+    if addr.is_loopback() || addr.is_unspecified() {
+        return false;
+    }
+    match REVERSE_DNS.lock().unwrap().get(addr) {
+        Some((_, at)) => at.elapsed().as_secs() >= REVERSE_DNS_TTL_SECS,
+        None => true,
     }
-    Ok("127.0.0.1".to_string())
 }
 
-fn get_subnet_mask() -> Result<String> {
-    let output = Command::new("ipconfig").output()?;
-    let result = String::from_utf8_lossy(&output.stdout);
-    
-    for line in result.lines() {
-        if line.contains("Subnet Mask") || line.contains("Máscara de Sub-rede") {
-            if let Some(mask) = line.split(':').nth(1) {
-                return Ok(mask.trim().to_string());
-            }
+/// Resolve em paralelo (fora das threads do runtime) os destinos ainda sem nome em cache,
+/// até `MAX_REVERSE_LOOKUPS` por recolha e esperando no máximo `REVERSE_DNS_WAIT`
+pub async fn resolve_remote_hosts(connections: &mut [NetworkConnection]) {
+    let pending: HashSet<IpAddr> = connections
+        .iter()
+        .filter(|c| c.remote_host.is_none())
+        .filter_map(|c| c.remote_ip.parse().ok())
+        .filter(needs_reverse_dns)
+        .take(MAX_REVERSE_LOOKUPS)
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+
+    let lookups: Vec<_> = pending
+        .into_iter()
+        .map(|addr| {
+            // Marca já a entrada: uma resolução lenta não é repetida na recolha seguinte
+            REVERSE_DNS.lock().unwrap().insert(addr, (None, Instant::now()));
+            tokio::task::spawn_blocking(move || {
+                let name = dns_lookup::lookup_addr(&addr).ok().filter(|n| *n != addr.to_string());
+                REVERSE_DNS.lock().unwrap().insert(addr, (name, Instant::now()));
+            })
+        })
+        .collect();
+
+    let deadline = tokio::time::Instant::now() + REVERSE_DNS_WAIT;
+    for lookup in lookups {
+        let _ = tokio::time::timeout_at(deadline, lookup).await;
+    }
+
+    for connection in connections.iter_mut().filter(|c| c.remote_host.is_none()) {
+        if let Ok(addr) = connection.remote_ip.parse() {
+            connection.remote_host = cached_reverse_dns(addr);
         }
     }
-    Ok("255.255.255.0".to_string())
 }
 
-fn get_gateway() -> Result<String> {
-    let output = Command::new("ipconfig").output()?;
-    let result = String::from_utf8_lossy(&output.stdout);
-    
-    for line in result.lines() {
-        if line.contains("Default Gateway") || line.contains("Gateway Predefinido") {
-            if let Some(gateway) = line.split(':').nth(1) {
-                let gw = gateway.trim();
-                if !gw.is_empty() {
-                    return Ok(gw.to_string());
-                }
-            }
+/// Tabela de vizinhos sem as entradas de multicast e broadcast, que não são equipamentos
+pub fn collect_neighbors() -> platform::Result<Vec<NetworkNeighbor>> {
+    let mut neighbors: Vec<NetworkNeighbor> = Vec::new();
+    for neighbor in platform::current().neighbors()?.into_iter().filter(is_reportable_neighbor) {
+        let entry = NetworkNeighbor {
+            ip: neighbor.ip.to_string(),
+            mac_address: neighbor.mac_address,
+            interface: neighbor.interface,
+        };
+        if !neighbors.contains(&entry) {
+            neighbors.push(entry);
         }
     }
-    Ok("192.168.1.1".to_string())
+    Ok(neighbors)
 }
 
-fn get_dns_primary() -> Result<String> {
-    // Usa nslookup para obter DNS
-    let output = Command::new("nslookup")
-        .arg("google.com")
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    for line in result.lines() {
-        if line.contains("Server:") {
-            if let Some(dns) = line.split(':').nth(1) {
-                return Ok(dns.trim().to_string());
-            }
+fn is_reportable_neighbor(neighbor: &Neighbor) -> bool {
+    let ip = neighbor.ip;
+    let ip_ok = !ip.is_multicast() && !ip.is_loopback() && !ip.is_unspecified()
+        && !matches!(ip, IpAddr::V4(v4) if v4.is_broadcast());
+
+    // Bit menos significativo do primeiro octeto: MAC de grupo (multicast, FF-FF-FF-FF-FF-FF)
+    let first_octet = neighbor.mac_address.get(0..2).and_then(|o| u8::from_str_radix(o, 16).ok());
+    let mac_ok = first_octet.is_some_and(|o| o & 0x01 == 0);
+
+    ip_ok && mac_ok
+}
+
+/// Todas as interfaces e rotas, com o resumo do adaptador por onde sai o tráfego
+pub fn collect_network_details(identity: &ComputerIdentity) -> platform::Result<NetworkDetails> {
+    let platform = platform::current();
+    let adapters = platform.network_adapters()?;
+    let routes = platform.routes().unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao ler a tabela de rotas: {}", e);
+        vec![]
+    });
+    let neighbors = platform.neighbors().unwrap_or_else(|e| {
+        eprintln!("[Coleta] Erro ao ler a tabela de vizinhos: {}", e);
+        vec![]
+    });
+
+    let local_ip = get_local_ip().and_then(|ip| ip.parse::<Ipv4Addr>().ok());
+    let primary = primary_adapter(&adapters, local_ip);
+
+    let mut details = NetworkDetails {
+        domain_name: identity.domain.clone(),
+        is_domain_joined: identity.part_of_domain,
+        adapters: adapters
+            .iter()
+            .map(|a| adapter_info(a, primary.is_some_and(|p| p.name == a.name), &neighbors))
+            .collect(),
+        dns_servers: unique_dns_servers(&adapters),
+        routes: routes.iter().filter(|r| is_reportable_route(r)).map(route_info).collect(),
+        ..Default::default()
+    };
+
+    // Sem adaptador activo (cabo desligado, só loopback): o resumo fica vazio
+    let Some(adapter) = primary else {
+        return Ok(details);
+    };
+
+    let address = local_ip
+        .and_then(|ip| adapter.ipv4.iter().find(|(addr, _)| *addr == ip))
+        .or(adapter.ipv4.first());
+
+    let wifi = if adapter.is_wireless {
+        platform.wifi().unwrap_or_else(|e| {
+            eprintln!("[Coleta] Erro ao ler a ligação Wi-Fi: {}", e);
+            None
+        })
+    } else {
+        None
+    };
+
+    details.local_ip = address.map(|(ip, _)| ip.to_string());
+    details.subnet_mask = address.map(|(_, prefix)| prefix_mask(*prefix).to_string());
+    details.gateway = adapter.gateways.first().map(|g| g.to_string());
+    details.dns_primary = adapter.dns_servers.first().map(|d| d.to_string());
+    details.dns_secondary = adapter.dns_servers.get(1).map(|d| d.to_string());
+    details.dhcp_enabled = adapter.dhcp_enabled;
+    details.mac_address = adapter.mac_address.clone();
+    details.adapter_name = Some(adapter.description.clone());
+    details.connection_speed_mbps = adapter.speed_mbps;
+    details.wifi_ssid = wifi.as_ref().map(|w| w.ssid.clone());
+    details.wifi_security = wifi.map(|w| w.security);
+    Ok(details)
+}
+
+fn adapter_info(adapter: &NetworkAdapter, is_primary: bool, neighbors: &[Neighbor]) -> AdapterInfo {
+    let ipv4 = adapter.ipv4.iter().map(|(ip, prefix)| AdapterAddress {
+        family: "ipv4".to_string(),
+        address: ip.to_string(),
+        prefix_length: *prefix,
+    });
+    let ipv6 = adapter.ipv6.iter().map(|(ip, prefix)| AdapterAddress {
+        family: "ipv6".to_string(),
+        address: ip.to_string(),
+        prefix_length: *prefix,
+    });
+
+    AdapterInfo {
+        name: adapter.name.clone(),
+        description: adapter.description.clone(),
+        kind: adapter.kind.to_string(),
+        mac_address: adapter.mac_address.clone(),
+        addresses: ipv4.chain(ipv6).collect(),
+        gateways: adapter.gateways.iter().map(|g| g.to_string()).collect(),
+        gateway_mac: gateway_mac(adapter, neighbors),
+        dns_servers: adapter.dns_servers.iter().map(|d| d.to_string()).collect(),
+        dhcp_enabled: adapter.dhcp_enabled,
+        speed_mbps: adapter.speed_mbps,
+        is_up: adapter.is_up,
+        is_wireless: adapter.is_wireless,
+        is_primary,
+    }
+}
+
+/// MAC do gateway visto pela interface do adaptador
+fn gateway_mac(adapter: &NetworkAdapter, neighbors: &[Neighbor]) -> Option<String> {
+    let gateway = adapter.gateways.first()?;
+    neighbors
+        .iter()
+        .filter(|n| is_reportable_neighbor(n))
+        .find(|n| n.ip == *gateway && n.interface == adapter.name)
+        .map(|n| n.mac_address.clone())
+}
+
+fn unique_dns_servers(adapters: &[NetworkAdapter]) -> Vec<String> {
+    let mut servers: Vec<String> = Vec::new();
+    for server in adapters.iter().flat_map(|a| &a.dns_servers) {
+        let server = server.to_string();
+        if !servers.contains(&server) {
+            servers.push(server);
         }
     }
-    Ok("8.8.8.8".to_string())
+    servers
 }
 
-fn get_dns_secondary() -> Option<String> {
-    // Placeholder - pode ser expandido
-    None
+/// Rotas que interessam ao inventário: sem loopback, multicast e broadcast, que o SO cria sozinho
+fn is_reportable_route(route: &Route) -> bool {
+    let broadcast = match route.destination {
+        IpAddr::V4(ip) => ip.is_broadcast() || (route.prefix == 32 && ip.octets()[3] == 255),
+        IpAddr::V6(_) => false,
+    };
+    route.interface != "lo" && !route.destination.is_loopback() && !route.destination.is_multicast() && !broadcast
 }
 
-fn is_dhcp_enabled() -> Result<bool> {
-    let output = Command::new("ipconfig").arg("/all").output()?;
-    let result = String::from_utf8_lossy(&output.stdout);
-    
-    Ok(result.contains("DHCP Enabled") && result.contains("Yes"))
+fn route_info(route: &Route) -> RouteInfo {
+    RouteInfo {
+        interface: route.interface.clone(),
+        destination: route.destination.to_string(),
+        prefix_length: route.prefix,
+        gateway: route.gateway.map(|g| g.to_string()),
+        metric: route.metric,
+    }
 }
 
-fn get_domain_name() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["computersystem", "get", "domain"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let domain = result.lines().nth(1).unwrap_or("WORKGROUP").trim();
-    Ok(domain.to_string())
+/// IP de saída: o SO escolhe a interface da rota para fora sem enviar nada
+pub fn get_local_ip() -> Option<String> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip().to_string())
 }
 
-fn check_domain_joined() -> Result<bool> {
-    let domain = get_domain_name()?;
-    Ok(domain.to_uppercase() != "WORKGROUP")
+/// O adaptador que tem o IP de saída; sem ele, o primeiro activo com gateway
+fn primary_adapter(adapters: &[NetworkAdapter], local_ip: Option<Ipv4Addr>) -> Option<&NetworkAdapter> {
+    local_ip
+        .and_then(|ip| adapters.iter().find(|a| a.ipv4.iter().any(|(addr, _)| *addr == ip)))
+        .or_else(|| adapters.iter().find(|a| a.is_up && !a.gateways.is_empty() && !a.ipv4.is_empty()))
+        .or_else(|| adapters.iter().find(|a| a.is_up && !a.ipv4.is_empty()))
 }
 
-fn get_mac_address() -> Result<String> {
-    let output = Command::new("getmac").output()?;
-    let result = String::from_utf8_lossy(&output.stdout);
-    
-    for line in result.lines().skip(3) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() > 0 && parts[0].contains('-') {
-            return Ok(parts[0].to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, ipv4: &[(&str, u8)], gateway: Option<&str>, is_up: bool) -> NetworkAdapter {
+        NetworkAdapter {
+            name: name.to_string(),
+            description: name.to_string(),
+            ipv4: ipv4.iter().map(|(ip, prefix)| (ip.parse().unwrap(), *prefix)).collect(),
+            gateways: gateway.iter().map(|g| g.parse().unwrap()).collect(),
+            is_up,
+            ..Default::default()
         }
     }
-    Ok("00-00-00-00-00-00".to_string())
-}
-
-fn get_adapter_name() -> Result<String> {
-    let output = Command::new("wmic")
-        .args(&["nic", "where", "NetEnabled=true", "get", "name"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    Ok(result.lines().nth(1).unwrap_or("Unknown Adapter").trim().to_string())
-}
-
-fn get_connection_speed() -> Result<i64> {
-    let output = Command::new("wmic")
-        .args(&["nic", "where", "NetEnabled=true", "get", "speed"])
-        .output()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    let speed_str = result.lines().nth(1).unwrap_or("0").trim();
-    let speed_bps: i64 = speed_str.parse().unwrap_or(0);
-    Ok(speed_bps / 1_000_000) // Convert to Mbps
-}
-
-fn get_wifi_ssid() -> Option<String> {
-    let output = Command::new("netsh")
-        .args(&["wlan", "show", "interfaces"])
-        .output()
-        .ok()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    for line in result.lines() {
-        if line.contains("SSID") && !line.contains("BSSID") {
-            if let Some(ssid) = line.split(':').nth(1) {
-                return Some(ssid.trim().to_string());
-            }
+
+    fn route(interface: &str, destination: &str, prefix: u8) -> Route {
+        Route {
+            interface: interface.to_string(),
+            destination: destination.parse().unwrap(),
+            prefix,
+            gateway: None,
+            metric: 0,
         }
     }
-    None
-}
 
-fn get_wifi_security() -> Option<String> {
-    let output = Command::new("netsh")
-        .args(&["wlan", "show", "interfaces"])
-        .output()
-        .ok()?;
-    
-    let result = String::from_utf8_lossy(&output.stdout);
-    for line in result.lines() {
-        if line.contains("Authentication") {
-            if let Some(auth) = line.split(':').nth(1) {
-                return Some(auth.trim().to_string());
-            }
-        }
+    #[test]
+    fn system_routes_are_not_reported() {
+        assert!(is_reportable_route(&route("eth0", "0.0.0.0", 0)));
+        assert!(is_reportable_route(&route("eth0", "192.168.1.0", 24)));
+        assert!(is_reportable_route(&route("eth0", "fd00::", 64)));
+
+        assert!(!is_reportable_route(&route("lo", "::1", 128)));
+        assert!(!is_reportable_route(&route("Ethernet", "127.0.0.0", 8)));
+        assert!(!is_reportable_route(&route("Ethernet", "224.0.0.0", 4)));
+        assert!(!is_reportable_route(&route("Ethernet", "ff00::", 8)));
+        assert!(!is_reportable_route(&route("Ethernet", "255.255.255.255", 32)));
+        assert!(!is_reportable_route(&route("Ethernet", "192.168.1.255", 32)));
+    }
+
+    #[test]
+    fn adapter_info_lists_both_families() {
+        let mut eth = adapter("Ethernet", &[("10.0.0.15", 24)], Some("10.0.0.1"), true);
+        eth.ipv6 = vec![("fe80::1".parse().unwrap(), 64)];
+        eth.dns_servers = vec!["10.0.0.2".parse().unwrap(), "1.1.1.1".parse().unwrap()];
+
+        let neighbors = vec![
+            Neighbor { interface: "Wi-Fi".into(), ip: "10.0.0.1".parse().unwrap(), mac_address: "AA-AA-AA-AA-AA-AA".into() },
+            Neighbor { interface: "Ethernet".into(), ip: "10.0.0.1".parse().unwrap(), mac_address: "00-11-22-33-44-55".into() },
+        ];
+        let info = adapter_info(&eth, true, &neighbors);
+        assert_eq!(info.addresses.len(), 2);
+        assert_eq!(info.addresses[0].family, "ipv4");
+        assert_eq!(info.addresses[1].address, "fe80::1");
+        assert_eq!(info.gateways, vec!["10.0.0.1"]);
+        assert_eq!(info.gateway_mac.as_deref(), Some("00-11-22-33-44-55"));
+        assert_eq!(info.speed_mbps, None);
+        assert!(info.is_primary);
+
+        let mut wifi = adapter("Wi-Fi", &[], None, false);
+        wifi.dns_servers = vec!["1.1.1.1".parse().unwrap(), "9.9.9.9".parse().unwrap()];
+        assert_eq!(unique_dns_servers(&[eth, wifi]), vec!["10.0.0.2", "1.1.1.1", "9.9.9.9"]);
+    }
+
+    #[test]
+    fn primary_adapter_holds_the_outgoing_ip() {
+        let adapters = vec![
+            adapter("vEthernet (WSL)", &[("172.20.16.1", 20)], None, true),
+            adapter("Ethernet", &[("10.0.0.15", 24)], Some("10.0.0.1"), true),
+            adapter("Wi-Fi", &[("192.168.1.40", 24)], Some("192.168.1.1"), true),
+        ];
+
+        let primary = primary_adapter(&adapters, Some(Ipv4Addr::new(192, 168, 1, 40))).unwrap();
+        assert_eq!(primary.name, "Wi-Fi");
+
+        // Sem rota para fora: o primeiro activo com gateway
+        let primary = primary_adapter(&adapters, None).unwrap();
+        assert_eq!(primary.name, "Ethernet");
     }
-    None
-}
\ No newline at end of file
+
+    #[test]
+    fn no_primary_adapter_when_all_down() {
+        let adapters = vec![adapter("Ethernet", &[("10.0.0.15", 24)], Some("10.0.0.1"), false)];
+        assert!(primary_adapter(&adapters, None).is_none());
+        assert!(primary_adapter(&[], Some(Ipv4Addr::new(10, 0, 0, 15))).is_none());
+    }
+
+    #[test]
+    fn group_neighbors_are_not_reported() {
+        let neighbor = |ip: &str, mac: &str| Neighbor {
+            interface: "Ethernet".to_string(),
+            ip: ip.parse().unwrap(),
+            mac_address: mac.to_string(),
+        };
+
+        assert!(is_reportable_neighbor(&neighbor("192.168.1.1", "A4-91-B1-0C-22-10")));
+        assert!(is_reportable_neighbor(&neighbor("fe80::1", "A4-91-B1-0C-22-10")));
+
+        assert!(!is_reportable_neighbor(&neighbor("224.0.0.22", "01-00-5E-00-00-16")));
+        assert!(!is_reportable_neighbor(&neighbor("ff02::1", "33-33-00-00-00-01")));
+        assert!(!is_reportable_neighbor(&neighbor("255.255.255.255", "FF-FF-FF-FF-FF-FF")));
+        assert!(!is_reportable_neighbor(&neighbor("192.168.1.255", "FF-FF-FF-FF-FF-FF")));
+    }
+}
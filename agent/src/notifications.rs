@@ -1,10 +1,14 @@
 // ============================================================
 // notifications.rs — Notificações Windows para o Utilizador
+// Noutros sistemas (servidores Linux sem sessão gráfica) a
+// mensagem fica apenas no registo do agente
 // ============================================================
 
+#[cfg(windows)]
 use windows::UI::Notifications::{
     ToastNotification, ToastNotificationManager, ToastTemplateType,
 };
+#[cfg(windows)]
 use windows::Data::Xml::Dom::XmlDocument;
 
 /// Mostra notificação de app bloqueada
@@ -32,7 +36,17 @@ pub fn notify_blocked_apps(count: usize) {
     let _ = show_toast("🛡️ AssetScan", &message);
 }
 
+/// Avisa o utilizador de que o administrador pediu uma captura do ecrã
+pub fn show_screenshot_notification() {
+    let message = "O administrador pediu uma captura do seu ecrã.";
+
+    if let Err(e) = show_toast("📷 AssetScan", message) {
+        eprintln!("[Notificação] Erro ao mostrar: {}", e);
+    }
+}
+
 /// Mostra toast notification genérica
+#[cfg(windows)]
 fn show_toast(title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
     unsafe {
         let toast_xml = XmlDocument::new()?;
@@ -66,6 +80,13 @@ fn show_toast(title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+#[cfg(not(windows))]
+fn show_toast(title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("[Notificação] {}: {}", title, body.replace('\n', " "));
+    Ok(())
+}
+
+#[cfg(windows)]
 fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
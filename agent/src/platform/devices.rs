@@ -0,0 +1,192 @@
+// ============================================================
+// platform/devices.rs — Dispositivos no Linux
+// Parsers do uevent das fontes de alimentação, da base de dados
+// do udev (/run/udev/data), do pci.ids e da saída JSON do
+// smartctl.
+// ============================================================
+
+use std::collections::HashMap;
+
+use super::{firmware_string, Battery};
+
+/// Linhas "CHAVE=valor" (uevent do sysfs; no udev as propriedades têm o prefixo "E:")
+pub fn key_values(content: &str) -> HashMap<&str, &str> {
+    content
+        .lines()
+        .map(|line| line.strip_prefix("E:").unwrap_or(line))
+        .filter_map(|line| line.split_once('='))
+        .collect()
+}
+
+/// /sys/class/power_supply/<nome>/uevent → bateria do sistema. As de periféricos
+/// (ratos, teclados: POWER_SUPPLY_SCOPE=Device) e os carregadores ficam de fora
+pub fn battery(uevent: &str) -> Option<Battery> {
+    let values = key_values(uevent);
+    let get = |key: &str| values.get(format!("POWER_SUPPLY_{}", key).as_str()).copied();
+    let number = |key: &str| get(key).and_then(|v| v.parse::<i64>().ok()).filter(|v| *v > 0);
+
+    if get("TYPE") != Some("Battery") || get("SCOPE") == Some("Device") {
+        return None;
+    }
+
+    // energy_* em µWh; alguns firmwares só dão charge_* em µAh, que se converte
+    // com a tensão nominal (µV)
+    let capacity = |energy: &str, charge: &str| {
+        number(energy).map(|uwh| uwh / 1000).or_else(|| {
+            let uah = number(charge)?;
+            let uv = number("VOLTAGE_MIN_DESIGN")?;
+            Some(uah * uv / 1_000_000_000)
+        })
+    };
+
+    Some(Battery {
+        name: get("MODEL_NAME").and_then(firmware_string),
+        manufacturer: get("MANUFACTURER").and_then(firmware_string),
+        serial_number: get("SERIAL_NUMBER").and_then(firmware_string),
+        chemistry: get("TECHNOLOGY").and_then(firmware_string).filter(|t| t != "Unknown"),
+        design_capacity_mwh: capacity("ENERGY_FULL_DESIGN", "CHARGE_FULL_DESIGN"),
+        full_charge_capacity_mwh: capacity("ENERGY_FULL", "CHARGE_FULL"),
+        cycle_count: number("CYCLE_COUNT"),
+    })
+}
+
+/// Nome do fabricante da placa gráfica pelo ID PCI
+pub fn pci_vendor(vendor_id: &str) -> Option<&'static str> {
+    match vendor_id.trim_start_matches("0x").to_lowercase().as_str() {
+        "8086" => Some("Intel"),
+        "10de" => Some("NVIDIA"),
+        "1002" => Some("AMD"),
+        "1af4" => Some("Red Hat (virtio)"),
+        "15ad" => Some("VMware"),
+        "80ee" => Some("VirtualBox"),
+        "1414" => Some("Microsoft"),
+        _ => None,
+    }
+}
+
+/// Nome do dispositivo em pci.ids: linhas de fabricante sem indentação e os
+/// dispositivos desse fabricante com um tab
+pub fn pci_device_name(pci_ids: &str, vendor_id: &str, device_id: &str) -> Option<String> {
+    let vendor_id = vendor_id.trim_start_matches("0x").to_lowercase();
+    let device_id = device_id.trim_start_matches("0x").to_lowercase();
+    let mut in_vendor = false;
+
+    for line in pci_ids.lines() {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if !line.starts_with('\t') {
+            if in_vendor {
+                return None;
+            }
+            in_vendor = line.starts_with(&vendor_id);
+        } else if in_vendor && !line.starts_with("\t\t") {
+            if let Some((id, name)) = line.trim_start().split_once("  ") {
+                if id == device_id {
+                    return Some(name.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `smartctl --json -H`: "healthy" se o autoteste passa, "failing" se não, e
+/// "warning" quando um NVMe reporta um aviso crítico (reserva, temperatura, ...)
+pub fn smart_health(json: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let passed = value.get("smart_status")?.get("passed")?.as_bool()?;
+    let critical_warning = value
+        .pointer("/nvme_smart_health_information_log/critical_warning")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    Some(
+        match (passed, critical_warning) {
+            (false, _) => "failing",
+            (true, 0) => "healthy",
+            (true, _) => "warning",
+        }
+        .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAPTOP_BATTERY: &str = "POWER_SUPPLY_NAME=BAT0
+POWER_SUPPLY_TYPE=Battery
+POWER_SUPPLY_STATUS=Discharging
+POWER_SUPPLY_TECHNOLOGY=Li-poly
+POWER_SUPPLY_CYCLE_COUNT=312
+POWER_SUPPLY_ENERGY_FULL_DESIGN=57000000
+POWER_SUPPLY_ENERGY_FULL=48450000
+POWER_SUPPLY_MODEL_NAME=5B10W13975
+POWER_SUPPLY_MANUFACTURER=SMP
+POWER_SUPPLY_SERIAL_NUMBER= 1234
+";
+
+    #[test]
+    fn parses_system_battery_wear() {
+        assert_eq!(
+            battery(LAPTOP_BATTERY),
+            Some(Battery {
+                name: Some("5B10W13975".to_string()),
+                manufacturer: Some("SMP".to_string()),
+                serial_number: Some("1234".to_string()),
+                chemistry: Some("Li-poly".to_string()),
+                design_capacity_mwh: Some(57000),
+                full_charge_capacity_mwh: Some(48450),
+                cycle_count: Some(312),
+            })
+        );
+    }
+
+    #[test]
+    fn charge_based_batteries_and_peripherals() {
+        let charge = "POWER_SUPPLY_TYPE=Battery
+POWER_SUPPLY_CHARGE_FULL_DESIGN=4000000
+POWER_SUPPLY_CHARGE_FULL=3600000
+POWER_SUPPLY_VOLTAGE_MIN_DESIGN=11400000
+POWER_SUPPLY_CYCLE_COUNT=0
+";
+        let battery_info = battery(charge).unwrap();
+        assert_eq!(battery_info.design_capacity_mwh, Some(45600));
+        assert_eq!(battery_info.full_charge_capacity_mwh, Some(41040));
+        assert_eq!(battery_info.cycle_count, None);
+
+        assert_eq!(battery("POWER_SUPPLY_TYPE=Battery\nPOWER_SUPPLY_SCOPE=Device\n"), None);
+        assert_eq!(battery("POWER_SUPPLY_TYPE=Mains\nPOWER_SUPPLY_ONLINE=1\n"), None);
+    }
+
+    #[test]
+    fn udev_properties() {
+        let data = "S:disk/by-id/ata-Samsung_SSD_870_EVO_1TB_S6PTNX0T123456\nE:ID_BUS=ata\nE:ID_SERIAL_SHORT=S6PTNX0T123456\n";
+        let values = key_values(data);
+        assert_eq!(values.get("ID_SERIAL_SHORT"), Some(&"S6PTNX0T123456"));
+        assert_eq!(values.get("ID_BUS"), Some(&"ata"));
+    }
+
+    #[test]
+    fn finds_pci_device_names() {
+        let pci_ids = "# pci.ids\n10de  NVIDIA Corporation\n\t2484  GA104 [GeForce RTX 3070]\n\t\t1043 87b8  subsistema\n1002  Advanced Micro Devices, Inc. [AMD/ATI]\n\t2484  Outro\n8086  Intel Corporation\n\t9a49  TigerLake-LP GT2 [Iris Xe Graphics]\n";
+        assert_eq!(pci_device_name(pci_ids, "0x10de", "0x2484"), Some("GA104 [GeForce RTX 3070]".to_string()));
+        assert_eq!(pci_device_name(pci_ids, "0x8086", "0x9A49"), Some("TigerLake-LP GT2 [Iris Xe Graphics]".to_string()));
+        assert_eq!(pci_device_name(pci_ids, "0x10de", "0x1111"), None);
+        assert_eq!(pci_vendor("0x10DE"), Some("NVIDIA"));
+    }
+
+    #[test]
+    fn smart_status_from_smartctl() {
+        assert_eq!(smart_health(r#"{"smart_status":{"passed":true}}"#).as_deref(), Some("healthy"));
+        assert_eq!(smart_health(r#"{"smart_status":{"passed":false}}"#).as_deref(), Some("failing"));
+        assert_eq!(
+            smart_health(r#"{"smart_status":{"passed":true},"nvme_smart_health_information_log":{"critical_warning":4}}"#)
+                .as_deref(),
+            Some("warning")
+        );
+        // Sem permissões ou disco sem SMART: não há smart_status
+        assert_eq!(smart_health(r#"{"smartctl":{"exit_status":2}}"#), None);
+    }
+}
@@ -0,0 +1,161 @@
+// ============================================================
+// platform/edid.rs — Identificação de monitores pelo EDID
+// Bloco base de 128 bytes: fabricante (código PnP), descritores
+// de nome e número de série e a temporização preferida, que dá a
+// resolução nativa do painel.
+// ============================================================
+
+use super::Monitor;
+
+const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+const DESCRIPTOR_SERIAL: u8 = 0xFF;
+const DESCRIPTOR_NAME: u8 = 0xFC;
+
+/// Códigos PnP mais comuns → nome do fabricante
+fn vendor_name(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "ACR" => "Acer",
+        "AOC" => "AOC",
+        "APP" => "Apple",
+        "AUO" => "AU Optronics",
+        "AUS" => "ASUS",
+        "BNQ" => "BenQ",
+        "BOE" => "BOE",
+        "CMN" => "Innolux",
+        "DEL" => "Dell",
+        "EIZ" => "EIZO",
+        "GSM" => "LG",
+        "HPN" | "HWP" => "HP",
+        "IVM" => "iiyama",
+        "LEN" => "Lenovo",
+        "LGD" => "LG Display",
+        "MSI" => "MSI",
+        "NEC" => "NEC",
+        "PHL" => "Philips",
+        "SAM" => "Samsung",
+        "SDC" => "Samsung Display",
+        "SHP" => "Sharp",
+        "SNY" => "Sony",
+        "VSC" => "ViewSonic",
+        _ => return None,
+    })
+}
+
+/// Nome do fabricante pelo código PnP; códigos desconhecidos ficam como estão
+pub fn manufacturer_name(code: &str) -> String {
+    vendor_name(code).map(|n| n.to_string()).unwrap_or_else(|| code.to_string())
+}
+
+/// Texto de um descritor de monitor (13 bytes, termina em 0x0A e é preenchido com espaços)
+fn descriptor_text(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.split('\n').next()?.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn parse(edid: &[u8]) -> Option<Monitor> {
+    if edid.len() < 128 || edid[0..8] != HEADER {
+        return None;
+    }
+
+    // Três letras de 5 bits (1 = 'A'), big-endian
+    let id = u16::from_be_bytes([edid[8], edid[9]]);
+    let code: String = [10, 5, 0]
+        .iter()
+        .map(|shift| (b'A' - 1 + ((id >> shift) & 0x1F) as u8) as char)
+        .collect();
+
+    let mut monitor = Monitor {
+        manufacturer: Some(manufacturer_name(&code)),
+        ..Default::default()
+    };
+
+    for block in edid[54..126].chunks_exact(18) {
+        let pixel_clock = u16::from_le_bytes([block[0], block[1]]);
+        if pixel_clock != 0 {
+            // O primeiro descritor de temporização é o modo preferido
+            if monitor.width.is_none() {
+                monitor.width = Some(block[2] as u32 | ((block[4] as u32 & 0xF0) << 4));
+                monitor.height = Some(block[5] as u32 | ((block[7] as u32 & 0xF0) << 4));
+            }
+            continue;
+        }
+        match block[3] {
+            DESCRIPTOR_NAME => monitor.model = descriptor_text(&block[5..18]),
+            DESCRIPTOR_SERIAL => monitor.serial_number = descriptor_text(&block[5..18]),
+            _ => {}
+        }
+    }
+
+    // Sem descritor de série fica o número de 32 bits do cabeçalho (0 = não preenchido)
+    if monitor.serial_number.is_none() {
+        let serial = u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]);
+        monitor.serial_number = (serial != 0).then(|| serial.to_string());
+    }
+
+    Some(monitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display_descriptor(tag: u8, text: &str) -> [u8; 18] {
+        let mut block = [0x20u8; 18];
+        block[0..5].copy_from_slice(&[0, 0, 0, tag, 0]);
+        let bytes = text.as_bytes();
+        block[5..5 + bytes.len()].copy_from_slice(bytes);
+        if bytes.len() < 13 {
+            block[5 + bytes.len()] = 0x0A;
+        }
+        block
+    }
+
+    fn sample_edid() -> Vec<u8> {
+        let mut edid = vec![0u8; 128];
+        edid[0..8].copy_from_slice(&HEADER);
+        edid[8..10].copy_from_slice(&[0x10, 0xAC]); // DEL
+        // 1920x1080: 0x780 e 0x438
+        edid[54..72].copy_from_slice(&[
+            0x02, 0x3A, 0x80, 0x18, 0x71, 0x38, 0x2D, 0x40, 0x58, 0x2C, 0x45, 0x00, 0, 0, 0, 0, 0, 0x1E,
+        ]);
+        edid[72..90].copy_from_slice(&display_descriptor(DESCRIPTOR_SERIAL, "CN0ABC123"));
+        edid[90..108].copy_from_slice(&display_descriptor(DESCRIPTOR_NAME, "DELL P2419H"));
+        edid[108..126].copy_from_slice(&display_descriptor(0xFD, ""));
+        edid
+    }
+
+    #[test]
+    fn parses_identity_and_native_resolution() {
+        assert_eq!(
+            parse(&sample_edid()),
+            Some(Monitor {
+                manufacturer: Some("Dell".to_string()),
+                model: Some("DELL P2419H".to_string()),
+                serial_number: Some("CN0ABC123".to_string()),
+                width: Some(1920),
+                height: Some(1080),
+            })
+        );
+    }
+
+    #[test]
+    fn numeric_serial_and_unknown_vendor() {
+        let mut edid = sample_edid();
+        edid[8..10].copy_from_slice(&[0x4D, 0x10]); // SHP
+        edid[72..90].copy_from_slice(&display_descriptor(0xFE, "LQ156M1"));
+        edid[12..16].copy_from_slice(&42u32.to_le_bytes());
+        let monitor = parse(&edid).unwrap();
+        assert_eq!(monitor.manufacturer.as_deref(), Some("Sharp"));
+        assert_eq!(monitor.serial_number.as_deref(), Some("42"));
+
+        edid[8..10].copy_from_slice(&[0x59, 0x6B]); // VKK: sem nome conhecido
+        assert_eq!(parse(&edid).unwrap().manufacturer.as_deref(), Some("VKK"));
+    }
+
+    #[test]
+    fn rejects_invalid_blocks() {
+        assert_eq!(parse(&[0u8; 128]), None);
+        assert_eq!(parse(&sample_edid()[..100]), None);
+    }
+}
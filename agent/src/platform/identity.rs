@@ -0,0 +1,151 @@
+// ============================================================
+// platform/identity.rs — Identidade da máquina pelo hardware
+// UUID do SMBIOS, números de série da BIOS e da motherboard e
+// MAC da placa principal. Sobrevivem a uma reinstalação do SO
+// e mudam quando uma VM é clonada, ao contrário do ID guardado.
+// ============================================================
+
+use sha2::{Digest, Sha256};
+
+use super::{firmware_string, HardwareAnchors, NetworkAdapter};
+
+/// UUIDs que os fabricantes deixam por preencher (ou que o Windows devolve quando a
+/// BIOS não tem UUID); o último é o exemplo da especificação SMBIOS, copiado por muitas
+pub fn system_uuid(value: &str) -> Option<String> {
+    let value = firmware_string(value)?.to_uppercase();
+    let hex: String = value.chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    if hex.chars().all(|c| c == '0') || hex.chars().all(|c| c == 'F') || value == "03000200-0400-0500-0006-000700080009" {
+        return None;
+    }
+    Some(value)
+}
+
+/// MAC administrado localmente (bit 1 do primeiro octeto): gerado por software,
+/// como os das VMs, contentores e da aleatorização de Wi-Fi
+fn locally_administered(mac: &str) -> bool {
+    u8::from_str_radix(mac.get(0..2).unwrap_or("00"), 16).map(|b| b & 0x02 != 0).unwrap_or(true)
+}
+
+/// MAC da placa física principal: prefere as com fio, e entre iguais o menor MAC,
+/// para o resultado não depender da ordem em que o SO lista as interfaces
+pub fn primary_mac(adapters: &[NetworkAdapter]) -> Option<String> {
+    adapters
+        .iter()
+        .filter(|a| a.kind == "physical")
+        .filter_map(|a| {
+            let mac = a.mac_address.as_deref()?.to_uppercase().replace(':', "-");
+            (mac.len() == 17 && !locally_administered(&mac)).then_some((a.is_wireless, mac))
+        })
+        .min()
+        .map(|(_, mac)| mac)
+}
+
+fn digest(anchors: &[(&str, &Option<String>)]) -> Option<[u8; 32]> {
+    let parts: Vec<String> = anchors
+        .iter()
+        .filter_map(|(name, value)| value.as_ref().map(|v| format!("{}={}", name, v)))
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(Sha256::digest(format!("assetscan:{}", parts.join(";")).as_bytes()).into())
+}
+
+/// Impressão digital de todas as âncoras conhecidas; é o que o servidor compara
+/// para encontrar clones (mesmo ID, outra impressão) e reinstalações (o contrário)
+pub fn fingerprint(anchors: &HardwareAnchors) -> Option<String> {
+    digest(&[
+        ("uuid", &anchors.system_uuid),
+        ("bios", &anchors.bios_serial),
+        ("board", &anchors.board_serial),
+        ("mac", &anchors.primary_mac),
+    ])
+    .map(hex::encode)
+}
+
+/// ID da máquina derivado do firmware, para quando não há nenhum guardado: uma
+/// reinstalação volta a dar o mesmo. O MAC só entra se o firmware não tiver nada
+pub fn derived_machine_id(anchors: &HardwareAnchors) -> Option<String> {
+    let firmware = [
+        ("uuid", &anchors.system_uuid),
+        ("bios", &anchors.bios_serial),
+        ("board", &anchors.board_serial),
+    ];
+    let digest = digest(&firmware).or_else(|| digest(&[("mac", &anchors.primary_mac)]))?;
+    Some(uuid_format(&digest))
+}
+
+/// Primeiros 16 bytes de um digest no formato UUID (o mesmo dos IDs gerados)
+pub fn uuid_format(digest: &[u8]) -> String {
+    let hex = hex::encode(&digest[..16]);
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, kind: &'static str, mac: &str, wireless: bool) -> NetworkAdapter {
+        NetworkAdapter {
+            name: name.to_string(),
+            description: name.to_string(),
+            kind,
+            mac_address: Some(mac.to_string()),
+            ipv4: vec![],
+            ipv6: vec![],
+            gateways: vec![],
+            dns_servers: vec![],
+            dhcp_enabled: None,
+            speed_mbps: None,
+            is_wireless: wireless,
+            is_up: true,
+        }
+    }
+
+    #[test]
+    fn bogus_uuids_are_dropped() {
+        assert_eq!(
+            system_uuid("4c4c4544-0042-3510-8052-b4c04f4e4d32"),
+            Some("4C4C4544-0042-3510-8052-B4C04F4E4D32".to_string())
+        );
+        assert_eq!(system_uuid("00000000-0000-0000-0000-000000000000"), None);
+        assert_eq!(system_uuid("FFFFFFFF-FFFF-FFFF-FFFF-FFFFFFFFFFFF"), None);
+        assert_eq!(system_uuid("03000200-0400-0500-0006-000700080009"), None);
+        assert_eq!(system_uuid("Not Specified"), None);
+    }
+
+    #[test]
+    fn primary_mac_prefers_wired_physical_adapters() {
+        let adapters = vec![
+            adapter("wlan0", "physical", "10-00-00-00-00-01", true),
+            adapter("docker0", "virtual", "02-42-AC-11-00-02", false),
+            adapter("eth1", "physical", "B0-00-00-00-00-02", false),
+            adapter("eth0", "physical", "a0:00:00:00:00:03", false),
+        ];
+        assert_eq!(primary_mac(&adapters), Some("A0-00-00-00-00-03".to_string()));
+
+        // Wi-Fi com MAC aleatório não conta
+        let adapters = vec![adapter("wlan0", "physical", "DA-A1-19-00-00-01", true)];
+        assert_eq!(primary_mac(&adapters), None);
+    }
+
+    #[test]
+    fn derived_id_ignores_mac_when_firmware_is_known() {
+        let anchors = HardwareAnchors {
+            system_uuid: Some("4C4C4544-0042-3510-8052-B4C04F4E4D32".to_string()),
+            bios_serial: Some("5CG1234XYZ".to_string()),
+            board_serial: None,
+            primary_mac: Some("A0-00-00-00-00-03".to_string()),
+        };
+        let other_nic = HardwareAnchors { primary_mac: Some("B0-00-00-00-00-02".to_string()), ..anchors.clone() };
+
+        assert_eq!(derived_machine_id(&anchors), derived_machine_id(&other_nic));
+        assert_ne!(fingerprint(&anchors), fingerprint(&other_nic));
+        assert_eq!(derived_machine_id(&anchors).unwrap().len(), 36);
+        assert_eq!(derived_machine_id(&HardwareAnchors::default()), None);
+        assert_eq!(fingerprint(&HardwareAnchors::default()), None);
+    }
+}
@@ -0,0 +1,587 @@
+// ============================================================
+// platform/linux.rs — Implementação Linux (/etc, /proc, /sys,
+// systemd e gestores de pacotes)
+// ============================================================
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{
+    adapter_kind, devices, edid, firmware_string, identity, packages, prefix_mask, proc_net, resolv_conf, smbios, systemd,
+    Battery, ComputerIdentity, Gpu, HardwareAnchors, HardwareInventory, InstalledSoftware, MemoryModule, Monitor, Neighbor,
+    NetworkAdapter, PhysicalDisk, Platform, PlatformError, Result, Route, Service, Socket, WifiConnection,
+};
+use crate::config::{self, Config};
+use crate::hardware_collector::UsbDevice;
+
+const CONFIG_PATH: &str = "/etc/assetscan/agent.toml";
+const PCI_IDS_PATHS: [&str; 2] = ["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids"];
+// Daemons e utilitários do sistema (sshd, journald, cron, dbus...)
+const SYSTEM_EXE_DIRS: [&str; 6] = ["/usr/lib/systemd", "/lib/systemd", "/usr/sbin", "/sbin", "/usr/libexec", "/usr/lib/openssh"];
+
+pub struct LinuxPlatform;
+
+impl Platform for LinuxPlatform {
+    fn load_config(&self) -> Result<Option<Config>> {
+        match fs::read_to_string(CONFIG_PATH) {
+            Ok(content) => config_from_toml(&content).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn system_exe_dirs(&self) -> Vec<PathBuf> {
+        SYSTEM_EXE_DIRS.iter().map(PathBuf::from).collect()
+    }
+
+    fn is_system_service(&self, pid: u32) -> bool {
+        let proc_dir = Path::new("/proc").join(pid.to_string());
+        match (fs::read_to_string(proc_dir.join("status")), fs::read_to_string(proc_dir.join("cgroup"))) {
+            (Ok(status), Ok(cgroup)) => is_root_system_unit(&status, &cgroup),
+            _ => false,
+        }
+    }
+
+    fn machine_id(&self) -> Result<String> {
+        let path = config::data_dir().join("machine_id");
+        if let Some(id) = fs::read_to_string(&path).ok().map(|c| c.trim().to_string()).filter(|c| !c.is_empty()) {
+            return Ok(id);
+        }
+
+        // /var/lib/dbus/machine-id existe em sistemas antigos ou sem systemd. Contentores e
+        // imagens podem vir sem machine-id: fica o derivado do hardware ou um gerado
+        let new_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .into_iter()
+            .find_map(|p| fs::read_to_string(p).ok().and_then(|c| systemd::machine_uuid(&c)))
+            .or_else(|| self.hardware_anchors().ok().and_then(|a| identity::derived_machine_id(&a)))
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        // Guardado para não mudar se o machine-id for regenerado depois
+        fs::write(&path, &new_id)?;
+        Ok(new_id)
+    }
+
+    fn firmware_anchors(&self) -> Result<HardwareAnchors> {
+        let dmi = Path::new("/sys/class/dmi/id");
+        let read = |name: &str| sysfs_value(&dmi.join(name)).and_then(|v| firmware_string(&v));
+
+        // Todos só legíveis por root
+        Ok(HardwareAnchors {
+            system_uuid: sysfs_value(&dmi.join("product_uuid")).and_then(|v| identity::system_uuid(&v)),
+            bios_serial: read("product_serial"),
+            board_serial: read("board_serial"),
+            primary_mac: None,
+        })
+    }
+
+    fn installed_software(&self) -> Result<Vec<InstalledSoftware>> {
+        let mut list = Vec::new();
+
+        if let Ok(status) = fs::read_to_string("/var/lib/dpkg/status") {
+            list.extend(packages::parse_dpkg_status(&status));
+        }
+
+        if Path::new("/var/lib/rpm").exists() {
+            let output = Command::new("rpm")
+                .args(["-qa", "--queryformat", packages::RPM_QUERY_FORMAT])
+                .output()?;
+            list.extend(packages::parse_rpm_query(&String::from_utf8_lossy(&output.stdout)));
+        }
+
+        // Apps instaladas para o sistema; as de cada utilizador ficam em ~/.local/share/flatpak
+        if let Ok(apps) = fs::read_dir("/var/lib/flatpak/app") {
+            for app in apps.flatten() {
+                if let Ok(current) = fs::read_link(app.path().join("current")) {
+                    list.push(packages::flatpak_app(
+                        &app.file_name().to_string_lossy(),
+                        &current.to_string_lossy(),
+                    ));
+                }
+            }
+        }
+
+        if let Ok(snaps) = fs::read_dir("/snap") {
+            for snap in snaps.flatten() {
+                let meta = snap.path().join("current/meta/snap.yaml");
+                if let Some(package) = fs::read_to_string(meta).ok().and_then(|c| packages::parse_snap_yaml(&c)) {
+                    list.push(package);
+                }
+            }
+        }
+
+        Ok(list)
+    }
+
+    fn services(&self) -> Result<Vec<Service>> {
+        let units = systemctl(&["list-units", "--type=service", "--all"])?;
+        let unit_files = systemctl(&["list-unit-files", "--type=service"])?;
+        Ok(systemd::services(&units, &unit_files))
+    }
+
+    fn usb_devices(&self) -> Result<Vec<UsbDevice>> {
+        let mut devices = Vec::new();
+
+        for entry in fs::read_dir("/sys/bus/usb/devices")?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // "1-2:1.0" são interfaces e "usb1" os hubs raiz do controlador
+            if name.contains(':') || name.starts_with("usb") {
+                continue;
+            }
+            let dir = entry.path();
+            let (Some(vendor_id), Some(product_id)) =
+                (sysfs_value(&dir.join("idVendor")), sysfs_value(&dir.join("idProduct")))
+            else {
+                continue;
+            };
+
+            let interface_classes: Vec<String> = fs::read_dir(&dir)?
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with(&format!("{}:", name)))
+                .filter_map(|e| sysfs_value(&e.path().join("bInterfaceClass")))
+                .collect();
+            // Classe 00: definida por interface
+            let class_code = sysfs_value(&dir.join("bDeviceClass"))
+                .filter(|c| c != "00")
+                .or_else(|| interface_classes.first().cloned())
+                .unwrap_or_default();
+            if class_code == "09" {
+                continue; // Hubs não são dispositivos que o utilizador liga
+            }
+
+            devices.push(UsbDevice {
+                name: sysfs_value(&dir.join("product")).unwrap_or_else(|| "Dispositivo USB".to_string()),
+                device_id: name,
+                vendor_id: vendor_id.to_uppercase(),
+                product_id: product_id.to_uppercase(),
+                serial: sysfs_value(&dir.join("serial")).filter(|s| !s.is_empty()),
+                device_class: usb_class(&class_code).to_string(),
+                manufacturer: sysfs_value(&dir.join("manufacturer")),
+                is_mass_storage: interface_classes.iter().any(|c| c == "08"),
+                status: "allowed".to_string(),
+            });
+        }
+        Ok(devices)
+    }
+
+    fn sockets(&self) -> Result<Vec<Socket>> {
+        let mut sockets = Vec::new();
+        for (file, protocol) in [("tcp", "tcp"), ("tcp6", "tcp"), ("udp", "udp"), ("udp6", "udp")] {
+            // tcp6/udp6 não existem com IPv6 desactivado
+            if let Ok(content) = fs::read_to_string(format!("/proc/net/{}", file)) {
+                sockets.extend(proc_net::parse_table(&content, protocol));
+            }
+        }
+
+        let owners = socket_owners();
+        Ok(sockets
+            .into_iter()
+            .map(|(mut socket, inode)| {
+                socket.pid = owners.get(&inode).copied();
+                socket
+            })
+            .collect())
+    }
+
+    fn network_adapters(&self) -> Result<Vec<NetworkAdapter>> {
+        let routes = ipv4_routes();
+        let local_addresses = fs::read_to_string("/proc/net/fib_trie")
+            .map(|c| proc_net::parse_local_addresses(&c))
+            .unwrap_or_default();
+        // Sem IPv6 no kernel o ficheiro não existe
+        let ipv6_addresses = fs::read_to_string("/proc/net/if_inet6")
+            .map(|c| proc_net::parse_if_inet6(&c))
+            .unwrap_or_default();
+        let dns_servers = fs::read_to_string("/etc/resolv.conf")
+            .map(|c| resolv_conf::nameservers(&c))
+            .unwrap_or_default();
+
+        let mut adapters = Vec::new();
+        for entry in fs::read_dir("/sys/class/net")?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "lo" {
+                continue;
+            }
+            let dir = entry.path();
+
+            // O fib_trie não diz a interface: cada endereço fica na que tem a rota da sua sub-rede
+            let ipv4 = local_addresses
+                .iter()
+                .filter_map(|addr| {
+                    routes
+                        .iter()
+                        .filter(|r| r.interface == name && r.gateway.is_none())
+                        .find(|r| {
+                            let mask = u32::from(prefix_mask(r.prefix));
+                            IpAddr::V4(Ipv4Addr::from(u32::from(*addr) & mask)) == r.destination
+                        })
+                        .map(|r| (*addr, r.prefix))
+                })
+                .collect();
+
+            let ipv6 = ipv6_addresses
+                .iter()
+                .filter(|(interface, _, _)| *interface == name)
+                .map(|(_, addr, prefix)| (*addr, *prefix))
+                .collect();
+
+            let gateways = routes
+                .iter()
+                .filter(|r| r.interface == name && r.prefix == 0)
+                .filter_map(|r| r.gateway)
+                .collect();
+
+            adapters.push(NetworkAdapter {
+                description: name.clone(),
+                kind: adapter_kind(&name, &name, dir.join("device").exists()),
+                mac_address: sysfs_value(&dir.join("address"))
+                    .filter(|mac| mac != "00:00:00:00:00:00")
+                    .map(|mac| mac.to_uppercase().replace(':', "-")),
+                ipv4,
+                ipv6,
+                gateways,
+                dns_servers: dns_servers.clone(),
+                dhcp_enabled: None, // Depende do gestor de rede; não há fonte única no kernel
+                // "-1" quando a ligação está em baixo; interfaces virtuais não têm velocidade
+                speed_mbps: sysfs_value(&dir.join("speed"))
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .filter(|s| *s > 0),
+                is_wireless: dir.join("wireless").exists(),
+                is_up: sysfs_value(&dir.join("operstate")).as_deref() == Some("up"),
+                name,
+            });
+        }
+        Ok(adapters)
+    }
+
+    fn routes(&self) -> Result<Vec<Route>> {
+        let mut routes = ipv4_routes();
+        if let Ok(content) = fs::read_to_string("/proc/net/ipv6_route") {
+            routes.extend(proc_net::parse_ipv6_routes(&content));
+        }
+        Ok(routes)
+    }
+
+    fn neighbors(&self) -> Result<Vec<Neighbor>> {
+        // Os vizinhos IPv6 (NDP) só estão disponíveis via netlink, ainda não suportado
+        Ok(proc_net::parse_arp(&fs::read_to_string("/proc/net/arp")?))
+    }
+
+    fn wifi(&self) -> Result<Option<WifiConnection>> {
+        // O SSID só está disponível via nl80211, ainda não suportado
+        Ok(None)
+    }
+
+    fn computer_identity(&self) -> Result<ComputerIdentity> {
+        let sessions: Vec<String> = fs::read_dir("/run/systemd/sessions")
+            .map(|dir| {
+                dir.flatten()
+                    .filter(|e| !e.file_name().to_string_lossy().ends_with(".ref"))
+                    .filter_map(|e| fs::read_to_string(e.path()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ComputerIdentity {
+            console_user: systemd::console_user(&sessions),
+            ..Default::default()
+        })
+    }
+
+    fn hardware(&self) -> Result<HardwareInventory> {
+        let dmi = Path::new("/sys/class/dmi/id");
+        let read = |name: &str| sysfs_value(&dmi.join(name)).and_then(|v| firmware_string(&v));
+
+        // Sem root as entradas SMBIOS não são legíveis: os slots ficam por saber
+        let slots = memory_devices().unwrap_or_default();
+
+        Ok(HardwareInventory {
+            serial_number: read("product_serial"), // Só legível por root
+            motherboard_manufacturer: read("board_vendor"),
+            motherboard_model: read("board_name"),
+            bios_version: read("bios_version"),
+            total_ram_slots: slots.len() as i64,
+            used_ram_slots: slots.iter().filter(|m| m.size_mb > 0).count() as i64,
+            ram_type: slots.iter().find_map(|m| m.memory_type.clone()),
+        })
+    }
+
+    fn memory_modules(&self) -> Result<Vec<MemoryModule>> {
+        Ok(memory_devices()?.into_iter().filter(|m| m.size_mb > 0).collect())
+    }
+
+    fn gpus(&self) -> Result<Vec<Gpu>> {
+        let pci_ids = PCI_IDS_PATHS.iter().find_map(|p| fs::read_to_string(p).ok()).unwrap_or_default();
+
+        let mut gpus = Vec::new();
+        for entry in fs::read_dir("/sys/class/drm")?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // "card0" é a placa; "card0-HDMI-A-1" são as saídas
+            if !name.starts_with("card") || name.contains('-') {
+                continue;
+            }
+            let device = entry.path().join("device");
+            let (Some(vendor_id), Some(device_id)) =
+                (sysfs_value(&device.join("vendor")), sysfs_value(&device.join("device")))
+            else {
+                continue; // simpledrm e afins não são placas PCI
+            };
+            let driver = fs::read_link(device.join("driver"))
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()));
+            let vendor = devices::pci_vendor(&vendor_id);
+
+            gpus.push(Gpu {
+                name: devices::pci_device_name(&pci_ids, &vendor_id, &device_id).unwrap_or_else(|| {
+                    format!("{} {}", vendor.unwrap_or("GPU"), device_id.trim_start_matches("0x"))
+                }),
+                vendor: vendor.map(|v| v.to_string()),
+                // Só o amdgpu expõe a memória dedicada no sysfs
+                vram_mb: sysfs_value(&device.join("mem_info_vram_total"))
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .map(|bytes| bytes / 1024 / 1024),
+                driver_version: driver
+                    .as_ref()
+                    .and_then(|d| sysfs_value(&Path::new("/sys/module").join(d).join("version")))
+                    .or(driver),
+            });
+        }
+        Ok(gpus)
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        let mut monitors = Vec::new();
+        for entry in fs::read_dir("/sys/class/drm")?.flatten() {
+            let dir = entry.path();
+            if sysfs_value(&dir.join("status")).as_deref() != Some("connected") {
+                continue;
+            }
+            if let Some(monitor) = fs::read(dir.join("edid")).ok().and_then(|e| edid::parse(&e)) {
+                monitors.push(monitor);
+            }
+        }
+        Ok(monitors)
+    }
+
+    fn batteries(&self) -> Result<Vec<Battery>> {
+        let Ok(dir) = fs::read_dir("/sys/class/power_supply") else {
+            return Ok(Vec::new()); // Sem a classe power_supply não há baterias
+        };
+        Ok(dir
+            .flatten()
+            .filter_map(|e| fs::read_to_string(e.path().join("uevent")).ok())
+            .filter_map(|uevent| devices::battery(&uevent))
+            .collect())
+    }
+
+    fn physical_disks(&self) -> Result<Vec<PhysicalDisk>> {
+        const VIRTUAL: [&str; 8] = ["loop", "ram", "zram", "dm-", "md", "sr", "fd", "nbd"];
+
+        let mut disks = Vec::new();
+        for entry in fs::read_dir("/sys/block")?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if VIRTUAL.iter().any(|prefix| name.starts_with(prefix)) {
+                continue;
+            }
+            let dir = entry.path();
+            // Tamanho em setores de 512 bytes; 0 em leitores de cartões sem cartão
+            let sectors: u64 = sysfs_value(&dir.join("size")).and_then(|s| s.parse().ok()).unwrap_or(0);
+            if sectors == 0 {
+                continue;
+            }
+
+            let udev = sysfs_value(&dir.join("dev"))
+                .and_then(|dev| fs::read_to_string(format!("/run/udev/data/b{}", dev)).ok())
+                .unwrap_or_default();
+            let udev = devices::key_values(&udev);
+
+            let bus_type = if name.starts_with("nvme") {
+                Some("NVMe")
+            } else if name.starts_with("vd") {
+                Some("Virtio")
+            } else {
+                match udev.get("ID_BUS").copied() {
+                    Some("ata") => Some("SATA"),
+                    Some("usb") => Some("USB"),
+                    Some("scsi") => Some("SCSI"),
+                    _ => None,
+                }
+            };
+
+            disks.push(PhysicalDisk {
+                model: sysfs_value(&dir.join("device/model"))
+                    .filter(|m| !m.is_empty())
+                    .unwrap_or_else(|| name.clone()),
+                serial_number: udev
+                    .get("ID_SERIAL_SHORT")
+                    .map(|s| s.to_string())
+                    .or_else(|| sysfs_value(&dir.join("device/serial")))
+                    .filter(|s| !s.is_empty()),
+                media_type: match sysfs_value(&dir.join("queue/rotational")).as_deref() {
+                    Some("1") => "hdd",
+                    Some("0") => "ssd",
+                    _ => "unknown",
+                }
+                .to_string(),
+                bus_type: bus_type.map(|b| b.to_string()),
+                size_gb: (sectors * 512) as f64 / 1_073_741_824.0,
+                health: smart_health(&name),
+            });
+        }
+        Ok(disks)
+    }
+}
+
+/// Entradas SMBIOS tipo 17, incluindo os slots vazios
+fn memory_devices() -> Result<Vec<MemoryModule>> {
+    let mut modules = Vec::new();
+    for entry in fs::read_dir("/sys/firmware/dmi/entries")?.flatten() {
+        if entry.file_name().to_string_lossy().starts_with("17-") {
+            if let Some(module) = smbios::memory_device(&fs::read(entry.path().join("raw"))?) {
+                modules.push(module);
+            }
+        }
+    }
+    Ok(modules)
+}
+
+/// Estado SMART via smartctl (smartmontools), quando instalado
+fn smart_health(device: &str) -> Option<String> {
+    let output = Command::new("smartctl")
+        .args(["--json", "-H", &format!("/dev/{}", device)])
+        .output()
+        .ok()?;
+    // O código de saída é uma máscara de bits que também marca discos a falhar: conta o JSON
+    devices::smart_health(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// agent.toml: os campos têm os nomes de `Config`; os que faltarem ficam com o valor por omissão
+/// Corre como root (UID real) dentro de uma unidade de system.slice (não de uma sessão de utilizador)
+fn is_root_system_unit(status: &str, cgroup: &str) -> bool {
+    let root = status
+        .lines()
+        .find_map(|l| l.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_whitespace().next())
+        == Some("0");
+    root && cgroup.lines().any(|l| l.contains("/system.slice/"))
+}
+
+fn config_from_toml(content: &str) -> Result<Config> {
+    let config: Config = toml::from_str(content).map_err(|e| PlatformError::InvalidConfig(e.to_string()))?;
+    if config.api_key.trim().is_empty() {
+        return Err(PlatformError::InvalidConfig(format!("api_key em falta em {}", CONFIG_PATH)));
+    }
+    Ok(config)
+}
+
+fn systemctl(args: &[&str]) -> Result<String> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .args(["--plain", "--no-legend", "--no-pager"])
+        .output()?;
+    if !output.status.success() {
+        return Err(PlatformError::Api { api: "systemctl", code: output.status.code().unwrap_or(-1) as u32 });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Classe USB → nome da classe PnP equivalente no Windows, para as políticas valerem nos dois sistemas
+fn usb_class(code: &str) -> &'static str {
+    match code {
+        "01" => "MEDIA",
+        "02" | "0a" => "Ports",
+        "03" => "HIDClass",
+        "06" => "Image",
+        "07" => "Printer",
+        "0b" => "SmartCardReader",
+        "0e" => "Camera",
+        "e0" => "Bluetooth",
+        _ => "USB", // Armazenamento e classes do fabricante
+    }
+}
+
+fn ipv4_routes() -> Vec<Route> {
+    fs::read_to_string("/proc/net/route")
+        .map(|c| proc_net::parse_routes(&c))
+        .unwrap_or_default()
+}
+
+fn sysfs_value(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|v| v.trim().to_string())
+}
+
+/// inode do socket → PID, a partir de /proc/<pid>/fd (sem root só os processos próprios)
+fn socket_owners() -> HashMap<u64, u32> {
+    let mut owners = HashMap::new();
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return owners;
+    };
+
+    for entry in procs.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            if let Some(inode) = fs::read_link(fd.path())
+                .ok()
+                .and_then(|link| proc_net::parse_socket_inode(&link.to_string_lossy()))
+            {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+    owners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_system_units() {
+        let root = "Name:\tsshd\nUid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\n";
+        let user = "Name:\tbash\nUid:\t1000\t1000\t1000\t1000\n";
+        let service = "0::/system.slice/ssh.service\n";
+        let session = "0::/user.slice/user-1000.slice/session-2.scope\n";
+
+        assert!(is_root_system_unit(root, service));
+        assert!(!is_root_system_unit(root, session));
+        assert!(!is_root_system_unit(user, service));
+    }
+
+    #[test]
+    fn agent_toml_with_defaults() {
+        let config = config_from_toml(
+            "server_url = \"https://assetscan.empresa.local\"\napi_key = \"abc123\"\ndns_filter_enabled = true\n",
+        )
+        .unwrap();
+        assert_eq!(config.server_url, "https://assetscan.empresa.local");
+        assert_eq!(config.interval_minutes, 60);
+        assert!(config.dns_filter_enabled);
+        assert!(config.push_enabled);
+        assert!(config.dns_upstream.is_empty());
+    }
+
+    #[test]
+    fn agent_toml_requires_api_key() {
+        assert!(matches!(
+            config_from_toml("server_url = \"http://srv:7474\"\n"),
+            Err(PlatformError::InvalidConfig(_))
+        ));
+        assert!(config_from_toml("interval_minutes = \"x\"").is_err());
+    }
+
+    #[test]
+    fn saved_config_round_trips() {
+        let config = Config { api_key: "abc123".to_string(), hosts_path: Some("/etc/hosts".to_string()), ..Default::default() };
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let loaded = config_from_toml(&saved).unwrap();
+        assert_eq!(loaded.api_key, "abc123");
+        assert_eq!(loaded.hosts_path.as_deref(), Some("/etc/hosts"));
+    }
+}
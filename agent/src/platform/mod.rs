@@ -0,0 +1,400 @@
+// ============================================================
+// platform/mod.rs — Recolha dependente do sistema operativo
+// Cada sistema implementa `Platform` com APIs nativas (Registry,
+// IP Helper e WMI no Windows; /etc, /proc, /sys e systemd no
+// Linux); o resto do agente só usa `platform::current()`.
+// ============================================================
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::hardware_collector::UsbDevice;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(windows)]
+mod windows;
+
+// Parsers puros, testados em qualquer sistema
+#[cfg(any(target_os = "linux", test))]
+mod devices;
+mod edid;
+pub mod identity;
+#[cfg(any(target_os = "linux", test))]
+mod packages;
+#[cfg(any(target_os = "linux", test))]
+mod proc_net;
+#[cfg(any(target_os = "linux", test))]
+mod resolv_conf;
+#[cfg(any(target_os = "linux", test))]
+mod smbios;
+#[cfg(any(target_os = "linux", test))]
+mod systemd;
+#[cfg(any(windows, test))]
+mod win_decode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlatformError {
+    #[error("{api} falhou com o código {code}")]
+    Api { api: &'static str, code: u32 },
+    #[cfg(windows)]
+    #[error("Consulta WMI falhou: {0}")]
+    Wmi(#[from] wmi::WMIError),
+    #[error("Erro de leitura: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(windows)]
+    #[error("{0} não encontrado")]
+    NotFound(&'static str),
+    #[error("Configuração inválida: {0}")]
+    InvalidConfig(String),
+}
+
+pub type Result<T> = std::result::Result<T, PlatformError>;
+
+/// Socket aberto no sistema, tal como o SO o reporta
+#[derive(Debug, Clone, PartialEq)]
+pub struct Socket {
+    pub protocol: &'static str, // "tcp" | "udp"
+    pub local_addr: IpAddr,
+    pub local_port: u16,
+    pub remote_addr: Option<IpAddr>, // UDP e sockets em escuta não têm destino
+    pub remote_port: u16,
+    pub state: String, // "ESTABLISHED", "LISTEN", ... (UDP: "OPEN")
+    pub pid: Option<u32>,
+}
+
+/// Interface de rede com a configuração IP actual
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkAdapter {
+    pub name: String,
+    pub description: String,
+    pub kind: &'static str,          // "physical" | "virtual" | "vpn" (ver `adapter_kind`)
+    pub mac_address: Option<String>, // "AA-BB-CC-DD-EE-FF"
+    pub ipv4: Vec<(Ipv4Addr, u8)>,   // Endereço e comprimento do prefixo
+    pub ipv6: Vec<(Ipv6Addr, u8)>,
+    pub gateways: Vec<IpAddr>,
+    pub dns_servers: Vec<IpAddr>,
+    pub dhcp_enabled: Option<bool>, // No Linux depende do gestor de rede: desconhecido
+    pub speed_mbps: Option<i64>,
+    pub is_wireless: bool,
+    pub is_up: bool,
+}
+
+/// Entrada da tabela de encaminhamento (IPv4 e IPv6)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub interface: String,
+    pub destination: IpAddr,
+    pub prefix: u8,
+    pub gateway: Option<IpAddr>, // Sem gateway: rede ligada directamente
+    pub metric: u32,
+}
+
+/// Entrada da tabela de vizinhos (ARP em IPv4, NDP em IPv6)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbor {
+    pub interface: String,
+    pub ip: IpAddr,
+    pub mac_address: String, // "AA-BB-CC-DD-EE-FF"
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WifiConnection {
+    pub ssid: String,
+    pub security: String, // "WPA2-Personal", "Open", ...
+}
+
+/// Domínio e utilizador com sessão na consola
+#[derive(Debug, Clone, Default)]
+pub struct ComputerIdentity {
+    pub domain: Option<String>,
+    pub part_of_domain: bool,
+    pub console_user: Option<String>,
+}
+
+/// Dados do firmware (SMBIOS); `None` quando o fabricante não os preenche
+#[derive(Debug, Clone, Default)]
+pub struct HardwareInventory {
+    pub serial_number: Option<String>,
+    pub motherboard_manufacturer: Option<String>,
+    pub motherboard_model: Option<String>,
+    pub bios_version: Option<String>,
+    pub total_ram_slots: i64,
+    pub used_ram_slots: i64,
+    pub ram_type: Option<String>,
+}
+
+/// Módulo de memória instalado (SMBIOS tipo 17); slots vazios ficam de fora
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MemoryModule {
+    pub slot: String, // "DIMM A1", "ChannelA-DIMM0", ...
+    pub size_mb: i64,
+    pub speed_mts: Option<u32>,
+    pub memory_type: Option<String>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Gpu {
+    pub name: String,
+    pub vendor: Option<String>,
+    pub vram_mb: Option<i64>,
+    pub driver_version: Option<String>,
+}
+
+/// Monitor ligado, identificado pelo EDID; a resolução é a nativa (preferida)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Monitor {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Bateria do sistema; o desgaste é a capacidade actual face à de fábrica
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Battery {
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub serial_number: Option<String>,
+    pub chemistry: Option<String>,
+    pub design_capacity_mwh: Option<i64>,
+    pub full_charge_capacity_mwh: Option<i64>,
+    pub cycle_count: Option<i64>,
+}
+
+/// Disco físico (não as partições); `health` vem do SMART quando o SO o expõe
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PhysicalDisk {
+    pub model: String,
+    pub serial_number: Option<String>,
+    pub media_type: String, // "ssd" | "hdd" | "unknown"
+    pub bus_type: Option<String>, // "NVMe", "SATA", "SAS", "USB", ...
+    pub size_gb: f64,
+    pub health: Option<String>, // "healthy" | "warning" | "failing"
+}
+
+/// Âncoras de hardware para a identidade da máquina; `None` = o firmware não as tem
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HardwareAnchors {
+    pub system_uuid: Option<String>, // UUID do SMBIOS (tipo 1)
+    pub bios_serial: Option<String>,
+    pub board_serial: Option<String>,
+    pub primary_mac: Option<String>, // Ver `identity::primary_mac`
+}
+
+/// Programa instalado (Registry no Windows; dpkg, rpm, flatpak e snap no Linux)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstalledSoftware {
+    pub name: String,
+    pub version: String,
+    pub publisher: String,
+    pub install_date: String, // YYYYMMDD, como no Registry; vazio se desconhecida
+}
+
+/// Serviço do sistema (serviços Windows, unidades systemd)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Service {
+    pub name: String,
+    pub display_name: String,
+    pub state: String,      // "running" | "stopped" | "failed" | ...
+    pub start_mode: String, // "auto" | "manual" | "disabled"
+}
+
+pub trait Platform: Send + Sync {
+    /// Configuração gravada pelo instalador; `Ok(None)` se não existir
+    fn load_config(&self) -> Result<Option<Config>>;
+
+    /// Pastas de executáveis do sistema que uma allowlist nunca termina
+    fn system_exe_dirs(&self) -> Vec<PathBuf>;
+
+    /// Processo de um serviço do sistema (no Linux, unidade systemd a correr como root)
+    fn is_system_service(&self, _pid: u32) -> bool {
+        false
+    }
+
+    /// Identificador estável da máquina: o guardado; na primeira execução, derivado
+    /// das âncoras de hardware (para sobreviver a reinstalações) ou gerado
+    fn machine_id(&self) -> Result<String>;
+
+    /// UUID do sistema e números de série da BIOS e da motherboard
+    fn firmware_anchors(&self) -> Result<HardwareAnchors>;
+
+    /// Âncoras do firmware mais o MAC da placa de rede principal
+    fn hardware_anchors(&self) -> Result<HardwareAnchors> {
+        let mut anchors = self.firmware_anchors()?;
+        anchors.primary_mac = identity::primary_mac(&self.network_adapters().unwrap_or_default());
+        Ok(anchors)
+    }
+
+    fn installed_software(&self) -> Result<Vec<InstalledSoftware>>;
+
+    fn services(&self) -> Result<Vec<Service>>;
+
+    /// Dispositivos USB ligados (sem hubs nem interfaces de dispositivos compostos)
+    fn usb_devices(&self) -> Result<Vec<UsbDevice>>;
+
+    /// Sockets TCP/UDP (IPv4 e IPv6) com o processo dono, quando conhecido
+    fn sockets(&self) -> Result<Vec<Socket>>;
+
+    /// Interfaces de rede, sem a de loopback
+    fn network_adapters(&self) -> Result<Vec<NetworkAdapter>>;
+
+    /// Rotas IPv4 e IPv6, tal como o SO as tem (o filtro fica para quem as reporta)
+    fn routes(&self) -> Result<Vec<Route>>;
+
+    /// Vizinhos já resolvidos (com MAC); as entradas incompletas ficam de fora
+    fn neighbors(&self) -> Result<Vec<Neighbor>>;
+
+    /// Rede Wi-Fi a que a máquina está ligada, se alguma
+    fn wifi(&self) -> Result<Option<WifiConnection>>;
+
+    fn computer_identity(&self) -> Result<ComputerIdentity>;
+
+    fn hardware(&self) -> Result<HardwareInventory>;
+
+    fn memory_modules(&self) -> Result<Vec<MemoryModule>>;
+
+    /// Placas gráficas, integradas e dedicadas
+    fn gpus(&self) -> Result<Vec<Gpu>>;
+
+    /// Monitores ligados neste momento (os já desligados ficam de fora)
+    fn monitors(&self) -> Result<Vec<Monitor>>;
+
+    /// Baterias do sistema; lista vazia em desktops (as de periféricos não contam)
+    fn batteries(&self) -> Result<Vec<Battery>>;
+
+    /// Discos físicos, sem os virtuais (loop, RAM, mapeamentos)
+    fn physical_disks(&self) -> Result<Vec<PhysicalDisk>>;
+}
+
+/// Implementação do sistema em que o agente está a correr
+pub fn current() -> &'static dyn Platform {
+    #[cfg(windows)]
+    {
+        &windows::WindowsPlatform
+    }
+    #[cfg(target_os = "linux")]
+    {
+        &linux::LinuxPlatform
+    }
+}
+
+/// Valores de firmware que os fabricantes deixam por preencher
+pub fn firmware_string(value: &str) -> Option<String> {
+    const PLACEHOLDERS: [&str; 7] = [
+        "to be filled by o.e.m.",
+        "default string",
+        "system serial number",
+        "not applicable",
+        "not specified",
+        "none",
+        "0",
+    ];
+
+    let value = value.trim().trim_matches('\0').trim();
+    if value.is_empty() || PLACEHOLDERS.contains(&value.to_lowercase().as_str()) {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/// Tipo de memória pelo código SMBIOS (tipo 17, campo Memory Type; o mesmo que o
+/// SMBIOSMemoryType do WMI)
+pub fn smbios_memory_type(code: u32) -> Option<&'static str> {
+    match code {
+        18 => Some("DDR"),
+        19 => Some("DDR2"),
+        24 => Some("DDR3"),
+        26 => Some("DDR4"),
+        27 => Some("LPDDR"),
+        28 => Some("LPDDR2"),
+        29 => Some("LPDDR3"),
+        30 => Some("LPDDR4"),
+        34 => Some("DDR5"),
+        35 => Some("LPDDR5"),
+        _ => None,
+    }
+}
+
+/// Comprimento do prefixo → máscara ("24" → 255.255.255.0)
+pub fn prefix_mask(prefix: u8) -> Ipv4Addr {
+    let bits = prefix.min(32) as u32;
+    let mask = if bits == 0 { 0 } else { u32::MAX << (32 - bits) };
+    Ipv4Addr::from(mask)
+}
+
+/// Classifica a interface pelo nome e descrição. `has_hardware`: o SO associa-a a um
+/// dispositivo (Linux: /sys/class/net/<nome>/device; Windows: tipo Ethernet ou 802.11),
+/// o que ainda inclui placas virtuais de hipervisores com nome reconhecível
+pub fn adapter_kind(name: &str, description: &str, has_hardware: bool) -> &'static str {
+    // Prefixos de nome; os que têm mais de 3 letras também valem dentro da descrição
+    const VPN: &[&str] = &[
+        "tun", "tap", "wg", "ppp", "tailscale", "zt", "ipsec", "wireguard", "wintun", "tap-windows", "openvpn",
+        "anyconnect", "forti", "vpn",
+    ];
+    const VIRTUAL: &[&str] = &[
+        "docker", "veth", "br-", "virbr", "vethernet", "vmnet", "vboxnet", "hyper-v", "vmware", "virtualbox",
+        "virtual",
+    ];
+
+    let name = name.to_lowercase();
+    let description = description.to_lowercase();
+    let matches = |hints: &[&str]| {
+        hints.iter().any(|h| name.starts_with(h) || (h.len() > 3 && description.contains(h)))
+    };
+
+    if matches(VPN) {
+        "vpn"
+    } else if matches(VIRTUAL) {
+        "virtual"
+    } else if has_hardware {
+        "physical"
+    } else {
+        "virtual"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_to_mask() {
+        assert_eq!(prefix_mask(24), Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(prefix_mask(20), Ipv4Addr::new(255, 255, 240, 0));
+        assert_eq!(prefix_mask(32), Ipv4Addr::new(255, 255, 255, 255));
+        assert_eq!(prefix_mask(0), Ipv4Addr::UNSPECIFIED);
+    }
+
+    #[test]
+    fn adapter_kinds() {
+        assert_eq!(adapter_kind("eth0", "eth0", true), "physical");
+        assert_eq!(adapter_kind("Ethernet", "Intel(R) Ethernet Connection I219-LM", true), "physical");
+        assert_eq!(adapter_kind("Wi-Fi", "Intel(R) Wi-Fi 6 AX201 160MHz", true), "physical");
+
+        assert_eq!(adapter_kind("docker0", "docker0", false), "virtual");
+        assert_eq!(adapter_kind("vEthernet (WSL)", "Hyper-V Virtual Ethernet Adapter", true), "virtual");
+        assert_eq!(adapter_kind("Ethernet 2", "VMware Virtual Ethernet Adapter for VMnet8", true), "virtual");
+        assert_eq!(adapter_kind("dummy0", "dummy0", false), "virtual");
+
+        assert_eq!(adapter_kind("wg0", "wg0", false), "vpn");
+        assert_eq!(adapter_kind("tailscale0", "tailscale0", false), "vpn");
+        assert_eq!(adapter_kind("Ethernet 3", "TAP-Windows Adapter V9", false), "vpn");
+        assert_eq!(adapter_kind("Local Area Connection", "Cisco AnyConnect Secure Mobility Client Virtual Miniport Adapter for Windows x64", true), "vpn");
+    }
+
+    #[test]
+    fn firmware_placeholders_are_dropped() {
+        assert_eq!(firmware_string("  5CG1234XYZ \n"), Some("5CG1234XYZ".to_string()));
+        assert_eq!(firmware_string("To be filled by O.E.M."), None);
+        assert_eq!(firmware_string("Default string"), None);
+        assert_eq!(firmware_string("\0\0"), None);
+        assert_eq!(firmware_string(""), None);
+    }
+}
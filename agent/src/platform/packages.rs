@@ -0,0 +1,179 @@
+// ============================================================
+// platform/packages.rs — Inventário de pacotes no Linux
+// dpkg (/var/lib/dpkg/status), rpm (saída de `rpm -qa`), snap
+// (meta/snap.yaml) e flatpak (ligação `current` de cada app).
+// ============================================================
+
+use super::InstalledSoftware;
+
+// Formato pedido ao rpm; uma linha por pacote, campos separados por tabs
+pub const RPM_QUERY_FORMAT: &str = "%{NAME}\\t%{VERSION}-%{RELEASE}\\t%{VENDOR}\\t%{INSTALLTIME}\\n";
+
+/// Parágrafos de /var/lib/dpkg/status com "Status: install ok installed"
+pub fn parse_dpkg_status(content: &str) -> Vec<InstalledSoftware> {
+    content
+        .split("\n\n")
+        .filter_map(|paragraph| {
+            let mut package = InstalledSoftware::default();
+            let mut installed = false;
+
+            // Linhas começadas por espaço continuam o campo anterior (ex: Description)
+            for line in paragraph.lines().filter(|l| !l.starts_with([' ', '\t'])) {
+                let Some((field, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim();
+                match field {
+                    "Package" => package.name = value.to_string(),
+                    "Version" => package.version = value.to_string(),
+                    "Maintainer" => package.publisher = without_email(value),
+                    "Status" => installed = value.ends_with(" installed"),
+                    _ => {}
+                }
+            }
+
+            (installed && !package.name.is_empty()).then_some(package)
+        })
+        .collect()
+}
+
+/// "Nome <email@dominio>" → "Nome"
+fn without_email(value: &str) -> String {
+    value.split('<').next().unwrap_or(value).trim().to_string()
+}
+
+/// Saída de `rpm -qa --queryformat RPM_QUERY_FORMAT`
+pub fn parse_rpm_query(output: &str) -> Vec<InstalledSoftware> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, version, vendor, installed_at] = fields.as_slice() else {
+                return None;
+            };
+            if name.trim().is_empty() || name.starts_with("gpg-pubkey") {
+                return None; // Chaves GPG importadas aparecem como pacotes
+            }
+
+            Some(InstalledSoftware {
+                name: name.trim().to_string(),
+                version: version.trim().to_string(),
+                publisher: if *vendor == "(none)" { String::new() } else { vendor.trim().to_string() },
+                install_date: installed_at
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map(|date| date.format("%Y%m%d").to_string())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// meta/snap.yaml: só os campos de topo "name" e "version" interessam
+pub fn parse_snap_yaml(content: &str) -> Option<InstalledSoftware> {
+    let mut name = None;
+    let mut version = None;
+
+    for line in content.lines().filter(|l| !l.starts_with([' ', '\t'])) {
+        let Some((field, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().trim_matches(['\'', '"']).to_string();
+        match field {
+            "name" => name = Some(value),
+            "version" => version = Some(value),
+            _ => {}
+        }
+    }
+
+    Some(InstalledSoftware {
+        name: name.filter(|n| !n.is_empty())?,
+        version: version.unwrap_or_default(),
+        publisher: "snap".to_string(),
+        install_date: String::new(),
+    })
+}
+
+/// App flatpak: o destino de `current` é "<arquitectura>/<ramo>" (ex: "x86_64/stable")
+pub fn flatpak_app(app_id: &str, current_link: &str) -> InstalledSoftware {
+    InstalledSoftware {
+        name: app_id.to_string(),
+        version: current_link.rsplit('/').next().unwrap_or_default().to_string(),
+        publisher: "flatpak".to_string(),
+        install_date: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DPKG_STATUS: &str = "Package: bash
+Essential: yes
+Status: install ok installed
+Priority: required
+Maintainer: Matthias Klose <doko@debian.org>
+Architecture: amd64
+Version: 5.2.15-2+b2
+Description: GNU Bourne Again SHell
+ Bash is an sh-compatible command language interpreter.
+ Version: not a real field
+
+Package: old-lib
+Status: deinstall ok config-files
+Version: 1.0
+
+Package: openssh-server
+Status: install ok installed
+Maintainer: Debian OpenSSH Maintainers <debian-ssh@lists.debian.org>
+Version: 1:9.2p1-2+deb12u3
+";
+
+    #[test]
+    fn dpkg_lists_only_installed_packages() {
+        let packages = parse_dpkg_status(DPKG_STATUS);
+        assert_eq!(packages.len(), 2);
+
+        assert_eq!(packages[0].name, "bash");
+        assert_eq!(packages[0].version, "5.2.15-2+b2");
+        assert_eq!(packages[0].publisher, "Matthias Klose");
+
+        assert_eq!(packages[1].name, "openssh-server");
+        assert_eq!(packages[1].version, "1:9.2p1-2+deb12u3");
+    }
+
+    #[test]
+    fn rpm_query_lines() {
+        let output = "bash\t5.2.26-3.fc40\tFedora Project\t1714000000\n\
+                      gpg-pubkey\tabc-def\t(none)\t1714000000\n\
+                      custom-tool\t1.0-1\t(none)\tnot-a-date\n\
+                      broken line\n";
+        let packages = parse_rpm_query(output);
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "bash");
+        assert_eq!(packages[0].publisher, "Fedora Project");
+        assert_eq!(packages[0].install_date, "20240424");
+        assert_eq!(packages[1].publisher, "");
+        assert_eq!(packages[1].install_date, "");
+    }
+
+    #[test]
+    fn snap_yaml_top_level_fields() {
+        let yaml = "name: firefox\nversion: '125.0.2-1'\nsummary: Mozilla Firefox\napps:\n  firefox:\n    name: nested\n";
+        let snap = parse_snap_yaml(yaml).unwrap();
+        assert_eq!(snap.name, "firefox");
+        assert_eq!(snap.version, "125.0.2-1");
+
+        assert!(parse_snap_yaml("version: 1\n").is_none());
+    }
+
+    #[test]
+    fn flatpak_version_is_the_branch() {
+        let app = flatpak_app("org.mozilla.firefox", "x86_64/stable");
+        assert_eq!(app.name, "org.mozilla.firefox");
+        assert_eq!(app.version, "stable");
+    }
+}
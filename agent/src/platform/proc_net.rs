@@ -0,0 +1,380 @@
+// ============================================================
+// platform/proc_net.rs — Leitura de /proc/net/{tcp,tcp6,udp,udp6}
+// Endereços em hexadecimal, por palavras de 32 bits na ordem do
+// host; o inode liga o socket ao processo via /proc/<pid>/fd.
+// Também /proc/net/route, /proc/net/ipv6_route, /proc/net/fib_trie
+// (endereços IPv4), /proc/net/if_inet6 (endereços IPv6) e
+// /proc/net/arp (vizinhos IPv4).
+// ============================================================
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::{Neighbor, Route, Socket};
+
+/// Linhas de um ficheiro /proc/net/*: (socket sem PID, inode)
+pub fn parse_table(content: &str, protocol: &'static str) -> Vec<(Socket, u64)> {
+    content
+        .lines()
+        .skip(1) // cabeçalho
+        .filter_map(|line| parse_line(line, protocol))
+        .collect()
+}
+
+fn parse_line(line: &str, protocol: &'static str) -> Option<(Socket, u64)> {
+    // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 10 {
+        return None;
+    }
+
+    let (local_addr, local_port) = parse_endpoint(fields[1])?;
+    let (remote_addr, remote_port) = parse_endpoint(fields[2])?;
+    let state_code = u8::from_str_radix(fields[3], 16).ok()?;
+    let inode: u64 = fields[9].parse().ok()?;
+
+    let state = if protocol == "tcp" { tcp_state(state_code) } else { "OPEN" };
+    let remote_addr = (!remote_addr.is_unspecified() || remote_port != 0).then_some(remote_addr);
+
+    Some((
+        Socket {
+            protocol,
+            local_addr,
+            local_port,
+            remote_addr,
+            remote_port,
+            state: state.to_string(),
+            pid: None,
+        },
+        inode,
+    ))
+}
+
+/// "0100007F:0035" → 127.0.0.1:53; aceita IPv4 (8 dígitos) e IPv6 (32 dígitos)
+pub fn parse_endpoint(value: &str) -> Option<(IpAddr, u16)> {
+    let (addr, port) = value.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let words: Vec<u32> = (0..addr.len() / 8)
+        .map(|i| u32::from_str_radix(addr.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+
+    let ip = match words.as_slice() {
+        [v4] => IpAddr::V4(Ipv4Addr::from(v4.to_ne_bytes())),
+        [a, b, c, d] => {
+            let mut bytes = [0u8; 16];
+            for (i, word) in [a, b, c, d].iter().enumerate() {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        }
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+fn tcp_state(code: u8) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Destino de /proc/<pid>/fd/<n>: "socket:[12345]" → 12345
+pub fn parse_socket_inode(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Tabela de encaminhamento IPv4; destino 0.0.0.0/0 é a rota por omissão
+pub fn parse_routes(content: &str) -> Vec<Route> {
+    content
+        .lines()
+        .skip(1) // cabeçalho
+        .filter_map(|line| {
+            // Iface Destination Gateway Flags RefCnt Use Metric Mask MTU Window IRTT
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 8 {
+                return None;
+            }
+            let gateway = hex_ipv4(fields[2])?;
+            Some(Route {
+                interface: fields[0].to_string(),
+                destination: IpAddr::V4(hex_ipv4(fields[1])?),
+                prefix: u32::from(hex_ipv4(fields[7])?).count_ones() as u8,
+                gateway: (!gateway.is_unspecified()).then_some(IpAddr::V4(gateway)),
+                metric: fields[6].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// /proc/net/ipv6_route: destino, prefixo, origem, prefixo, próximo salto, métrica,
+/// refcnt, use, flags, interface (endereços em 32 dígitos hex, pela ordem da rede)
+pub fn parse_ipv6_routes(content: &str) -> Vec<Route> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return None;
+            }
+            let gateway = hex_ipv6(fields[4])?;
+            Some(Route {
+                interface: fields[9].to_string(),
+                destination: IpAddr::V6(hex_ipv6(fields[0])?),
+                prefix: u8::from_str_radix(fields[1], 16).ok()?,
+                gateway: (!gateway.is_unspecified()).then_some(IpAddr::V6(gateway)),
+                metric: u32::from_str_radix(fields[5], 16).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// /proc/net/if_inet6: endereço, índice, prefixo, âmbito, flags, interface
+pub fn parse_if_inet6(content: &str) -> Vec<(String, Ipv6Addr, u8)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let addr = hex_ipv6(fields[0])?;
+            let prefix = u8::from_str_radix(fields[2], 16).ok()?;
+            Some((fields[5].to_string(), addr, prefix))
+        })
+        .collect()
+}
+
+fn hex_ipv6(value: &str) -> Option<Ipv6Addr> {
+    if value.len() != 32 {
+        return None;
+    }
+    u128::from_str_radix(value, 16).ok().map(Ipv6Addr::from)
+}
+
+fn hex_ipv4(value: &str) -> Option<Ipv4Addr> {
+    let word = u32::from_str_radix(value, 16).ok()?;
+    Some(Ipv4Addr::from(word.to_ne_bytes()))
+}
+
+/// Endereços IPv4 atribuídos à máquina: folhas "/32 host LOCAL" de /proc/net/fib_trie
+pub fn parse_local_addresses(content: &str) -> Vec<Ipv4Addr> {
+    let mut addresses = Vec::new();
+    let mut leaf: Option<Ipv4Addr> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(addr) = line.strip_prefix("|-- ") {
+            leaf = addr.parse().ok();
+        } else if line.starts_with("/32 host LOCAL") {
+            if let Some(addr) = leaf.filter(|a| !a.is_loopback() && !addresses.contains(a)) {
+                addresses.push(addr);
+            }
+        }
+    }
+    addresses
+}
+
+/// /proc/net/arp: "IP address  HW type  Flags  HW address  Mask  Device". Flags 0x0 é
+/// uma entrada ainda por resolver (sem MAC)
+pub fn parse_arp(content: &str) -> Vec<Neighbor> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let flags = u32::from_str_radix(fields[2].trim_start_matches("0x"), 16).ok()?;
+            if flags & 0x2 == 0 {
+                return None; // ATF_COM: resolvida
+            }
+
+            Some(Neighbor {
+                interface: fields[5].to_string(),
+                ip: IpAddr::V4(fields[0].parse().ok()?),
+                mac_address: fields[3].to_uppercase().replace(':', "-"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0035 00000000:0000 0A 00000000:00000000 00:00000000 00000000   101        0 21735 1 0000000000000000 100 0 0 10 0
+   1: 0F02000A:C8D2 5DB8D822:01BB 01 00000000:00000000 02:000A4F2E 00000000  1000        0 389211 2 0000000000000000 20 4 30 10 -1
+";
+
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 23150 1 0000000000000000 100 0 0 10 0
+   1: 00000000000000000000000001000000:0277 00000000000000000000000001000000:A1B2 01 00000000:00000000 00:00000000 00000000     0        0 24001 1 0000000000000000 20 4 0 10 -1
+";
+
+    const UDP: &str = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  221: 3500007F:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 21734 2 0000000000000000 0
+";
+
+    #[test]
+    fn parses_ipv4_tcp_listener_and_connection() {
+        let sockets = parse_table(TCP, "tcp");
+        assert_eq!(sockets.len(), 2);
+
+        let (listener, inode) = &sockets[0];
+        assert_eq!(listener.local_addr, "127.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(listener.local_port, 53);
+        assert_eq!(listener.remote_addr, None);
+        assert_eq!(listener.state, "LISTEN");
+        assert_eq!(*inode, 21735);
+
+        let (conn, _) = &sockets[1];
+        assert_eq!(conn.local_addr, "10.0.2.15".parse::<IpAddr>().unwrap());
+        assert_eq!(conn.local_port, 51410);
+        assert_eq!(conn.remote_addr, Some("34.216.184.93".parse().unwrap()));
+        assert_eq!(conn.remote_port, 443);
+        assert_eq!(conn.state, "ESTABLISHED");
+    }
+
+    #[test]
+    fn parses_ipv6_tcp() {
+        let sockets = parse_table(TCP6, "tcp");
+        assert_eq!(sockets[0].0.local_addr, "::".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[0].0.local_port, 22);
+        assert_eq!(sockets[1].0.local_addr, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[1].0.remote_addr, Some("::1".parse().unwrap()));
+        assert_eq!(sockets[1].0.remote_port, 0xA1B2);
+    }
+
+    #[test]
+    fn udp_sockets_are_open_without_remote() {
+        let sockets = parse_table(UDP, "udp");
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].0.local_addr, "127.0.0.53".parse::<IpAddr>().unwrap());
+        assert_eq!(sockets[0].0.state, "OPEN");
+        assert_eq!(sockets[0].0.remote_addr, None);
+    }
+
+    #[test]
+    fn rejects_malformed_lines_and_links() {
+        assert!(parse_table("header\n  0: garbage\n", "tcp").is_empty());
+        assert_eq!(parse_endpoint("0100007F"), None);
+        assert_eq!(parse_socket_inode("socket:[4242]"), Some(4242));
+        assert_eq!(parse_socket_inode("pipe:[4242]"), None);
+        assert_eq!(parse_socket_inode("/dev/null"), None);
+    }
+
+    const ROUTE: &str = "Iface	Destination	Gateway 	Flags	RefCnt	Use	Metric	Mask		MTU	Window	IRTT
+eth0	00000000	0102A8C0	0003	0	0	100	00000000	0	0	0
+eth0	0002A8C0	00000000	0001	0	0	100	00FFFFFF	0	0	0
+docker0	000011AC	00000000	0001	0	0	0	0000FFFF	0	0	0
+";
+
+    const FIB_TRIE: &str = "Main:
+  +-- 0.0.0.0/0 3 0 5
+     |-- 0.0.0.0
+        /0 universe UNICAST
+     +-- 127.0.0.0/8 2 0 2
+        +-- 127.0.0.0/31 1 0 0
+           |-- 127.0.0.0
+              /8 host LOCAL
+           |-- 127.0.0.1
+              /32 host LOCAL
+     +-- 192.168.2.0/24 2 0 2
+        |-- 192.168.2.0
+           /24 link UNICAST
+        |-- 192.168.2.37
+           /32 host LOCAL
+        |-- 192.168.2.255
+           /32 link BROADCAST
+Local:
+  +-- 0.0.0.0/0 3 0 5
+     |-- 192.168.2.37
+        /32 host LOCAL
+     |-- 172.17.0.1
+        /32 host LOCAL
+";
+
+    const IPV6_ROUTE: &str = "\
+fd000000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000002 00000000 00000003     eth0
+00000000000000000000000000000001 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000003 00000000 80200001       lo
+";
+
+    const IF_INET6: &str = "\
+00000000000000000000000000000001 01 80 10 80       lo
+fd000000000000000000000000000025 02 40 00 80     eth0
+fe80000000000000021122fffe334455 02 40 20 80     eth0
+";
+
+    #[test]
+    fn parses_routes_with_default_gateway() {
+        let routes = parse_routes(ROUTE);
+        assert_eq!(routes.len(), 3);
+
+        assert_eq!(routes[0].interface, "eth0");
+        assert_eq!(routes[0].destination, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(routes[0].prefix, 0);
+        assert_eq!(routes[0].gateway, Some("192.168.2.1".parse().unwrap()));
+        assert_eq!(routes[0].metric, 100);
+
+        assert_eq!(routes[1].destination, IpAddr::V4(Ipv4Addr::new(192, 168, 2, 0)));
+        assert_eq!(routes[1].prefix, 24);
+        assert_eq!(routes[1].gateway, None);
+        assert_eq!(routes[2].prefix, 16);
+    }
+
+    #[test]
+    fn parses_ipv6_routes_and_addresses() {
+        let routes = parse_ipv6_routes(IPV6_ROUTE);
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0].destination, "fd00::".parse::<IpAddr>().unwrap());
+        assert_eq!(routes[0].prefix, 64);
+        assert_eq!(routes[0].gateway, None);
+        assert_eq!(routes[0].metric, 256);
+        assert_eq!(routes[1].prefix, 0);
+        assert_eq!(routes[1].gateway, Some("fe80::1".parse().unwrap()));
+        assert_eq!(routes[2].interface, "lo");
+
+        let addresses = parse_if_inet6(IF_INET6);
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(addresses[1], ("eth0".to_string(), "fd00::25".parse().unwrap(), 64));
+        assert_eq!(addresses[2].1, "fe80::211:22ff:fe33:4455".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(addresses[0].2, 128);
+    }
+
+    #[test]
+    fn local_addresses_skip_loopback_and_duplicates() {
+        assert_eq!(
+            parse_local_addresses(FIB_TRIE),
+            vec![Ipv4Addr::new(192, 168, 2, 37), Ipv4Addr::new(172, 17, 0, 1)]
+        );
+    }
+
+    #[test]
+    fn parses_resolved_arp_entries() {
+        let arp = "IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         a4:91:b1:0c:22:10     *        wlp2s0
+192.168.1.57     0x1         0x0         00:00:00:00:00:00     *        wlp2s0
+10.0.0.20        0x1         0x6         52:54:00:12:34:56     *        eth0
+";
+        let neighbors = parse_arp(arp);
+        assert_eq!(neighbors.len(), 2);
+        assert_eq!(neighbors[0].ip, IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(neighbors[0].mac_address, "A4-91-B1-0C-22-10");
+        assert_eq!(neighbors[0].interface, "wlp2s0");
+        assert_eq!(neighbors[1].interface, "eth0");
+    }
+}
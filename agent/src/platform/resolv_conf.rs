@@ -0,0 +1,56 @@
+// ============================================================
+// platform/resolv_conf.rs — Servidores DNS de /etc/resolv.conf
+// ============================================================
+
+use std::net::IpAddr;
+
+/// Linhas "nameserver <ip>", pela ordem do ficheiro (o primeiro é o primário)
+pub fn nameservers(content: &str) -> Vec<IpAddr> {
+    content
+        .lines()
+        .map(|line| line.split(['#', ';']).next().unwrap_or(""))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            if fields.next()? != "nameserver" {
+                return None;
+            }
+            // IPv6 de ligação local pode vir com a zona: "fe80::1%eth0"
+            let addr = fields.next()?.split('%').next()?;
+            addr.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESOLV_CONF: &str = "# Generated by NetworkManager
+search empresa.local
+nameserver 10.0.0.2
+nameserver 1.1.1.1 # secundário
+; nameserver 9.9.9.9
+nameserver fe80::1%eth0
+nameserver not-an-ip
+options edns0 trust-ad
+";
+
+    #[test]
+    fn reads_nameservers_in_order() {
+        let servers = nameservers(RESOLV_CONF);
+        assert_eq!(
+            servers,
+            vec![
+                "10.0.0.2".parse::<IpAddr>().unwrap(),
+                "1.1.1.1".parse().unwrap(),
+                "fe80::1".parse().unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn systemd_resolved_stub() {
+        let servers = nameservers("nameserver 127.0.0.53\noptions edns0\n");
+        assert_eq!(servers, vec!["127.0.0.53".parse::<IpAddr>().unwrap()]);
+    }
+}
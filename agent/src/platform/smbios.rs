@@ -0,0 +1,131 @@
+// ============================================================
+// platform/smbios.rs — Estruturas SMBIOS em bruto
+// No Linux vêm de /sys/firmware/dmi/entries/<tipo>-<n>/raw (só
+// legíveis por root): área formatada seguida das strings, cada
+// uma terminada em NUL e numerada a partir de 1.
+// ============================================================
+
+use super::{firmware_string, smbios_memory_type, MemoryModule};
+
+const MEMORY_DEVICE: u8 = 17;
+
+/// Texto n.º `index` da estrutura (0 = sem texto)
+fn string_at(raw: &[u8], index: u8) -> Option<String> {
+    if index == 0 {
+        return None;
+    }
+    let formatted_len = *raw.get(1)? as usize;
+    raw.get(formatted_len..)?
+        .split(|b| *b == 0)
+        .nth(index as usize - 1)
+        .and_then(|s| firmware_string(&String::from_utf8_lossy(s)))
+}
+
+fn word(raw: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(raw.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn dword(raw: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(raw.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Memory Device (tipo 17) → módulo; `Some` com `size_mb == 0` é um slot vazio
+pub fn memory_device(raw: &[u8]) -> Option<MemoryModule> {
+    if *raw.first()? != MEMORY_DEVICE || raw.len() < 0x15 {
+        return None;
+    }
+
+    // 0xFFFF: tamanho desconhecido; 0x7FFF: o valor real (em MB) está no Extended Size;
+    // com o bit 15 ligado o valor vem em KB
+    let size_mb = match word(raw, 0x0C)? {
+        0xFFFF => 0,
+        0x7FFF => (dword(raw, 0x1C).unwrap_or(0) & 0x7FFF_FFFF) as i64,
+        size if size & 0x8000 != 0 => ((size & 0x7FFF) / 1024) as i64,
+        size => size as i64,
+    };
+
+    // Velocidade configurada (0x20) é a real; a 0x15 é a máxima do módulo
+    let speed_mts = [0x20, 0x15]
+        .into_iter()
+        .filter_map(|offset| word(raw, offset))
+        .find(|speed| *speed != 0 && *speed != 0xFFFF)
+        .map(|speed| speed as u32);
+
+    let slot = string_at(raw, raw[0x10])
+        .or_else(|| string_at(raw, raw[0x11]))
+        .unwrap_or_default();
+
+    Some(MemoryModule {
+        slot,
+        size_mb,
+        speed_mts,
+        memory_type: raw.get(0x12).and_then(|t| smbios_memory_type(*t as u32)).map(|t| t.to_string()),
+        manufacturer: raw.get(0x17).and_then(|i| string_at(raw, *i)),
+        serial_number: raw.get(0x18).and_then(|i| string_at(raw, *i)),
+        part_number: raw.get(0x1A).and_then(|i| string_at(raw, *i)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tipo 17 do SMBIOS 3.x com os campos até à velocidade configurada
+    fn memory_device_raw(size: u16, strings: &[&str]) -> Vec<u8> {
+        let mut raw = vec![0u8; 0x28];
+        raw[0] = MEMORY_DEVICE;
+        raw[1] = 0x28;
+        raw[0x0C..0x0E].copy_from_slice(&size.to_le_bytes());
+        raw[0x10] = 1; // Device Locator
+        raw[0x11] = 2; // Bank Locator
+        raw[0x12] = 26; // DDR4
+        raw[0x15..0x17].copy_from_slice(&3200u16.to_le_bytes());
+        raw[0x17] = 3; // Manufacturer
+        raw[0x18] = 4; // Serial Number
+        raw[0x1A] = 5; // Part Number
+        raw[0x20..0x22].copy_from_slice(&2933u16.to_le_bytes());
+        for s in strings {
+            raw.extend_from_slice(s.as_bytes());
+            raw.push(0);
+        }
+        raw.push(0);
+        raw
+    }
+
+    #[test]
+    fn parses_installed_module() {
+        let raw = memory_device_raw(16384, &["DIMM A1", "BANK 0", "Samsung", "12345678", "M471A2K43DB1-CWE    "]);
+        assert_eq!(
+            memory_device(&raw),
+            Some(MemoryModule {
+                slot: "DIMM A1".to_string(),
+                size_mb: 16384,
+                speed_mts: Some(2933),
+                memory_type: Some("DDR4".to_string()),
+                manufacturer: Some("Samsung".to_string()),
+                part_number: Some("M471A2K43DB1-CWE".to_string()),
+                serial_number: Some("12345678".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn empty_slot_and_placeholders() {
+        let raw = memory_device_raw(0, &["DIMM B1", "BANK 1", "Not Specified", "Not Specified", "Not Specified"]);
+        let module = memory_device(&raw).unwrap();
+        assert_eq!(module.size_mb, 0);
+        assert_eq!(module.slot, "DIMM B1");
+        assert_eq!(module.manufacturer, None);
+        assert_eq!(module.part_number, None);
+    }
+
+    #[test]
+    fn extended_size_and_other_types() {
+        let mut raw = memory_device_raw(0x7FFF, &["DIMM 0", "", "", "", ""]);
+        raw[0x1C..0x20].copy_from_slice(&65536u32.to_le_bytes());
+        assert_eq!(memory_device(&raw).unwrap().size_mb, 65536);
+
+        raw[0] = 16; // Physical Memory Array
+        assert_eq!(memory_device(&raw), None);
+    }
+}
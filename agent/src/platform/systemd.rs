@@ -0,0 +1,167 @@
+// ============================================================
+// platform/systemd.rs — /etc/machine-id, unidades e sessões
+// Saída de `systemctl list-units` / `list-unit-files` (com
+// --plain --no-legend) e ficheiros de /run/systemd/sessions.
+// ============================================================
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use super::{identity, Service};
+
+/// ID da máquina para o AssetScan a partir de /etc/machine-id. O valor original não sai
+/// da máquina (a documentação do systemd pede um derivado por aplicação); o resultado
+/// tem o formato UUID, como o ID gerado no Windows
+pub fn machine_uuid(machine_id: &str) -> Option<String> {
+    let machine_id = machine_id.trim();
+    if machine_id.len() != 32 || !machine_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digest = Sha256::digest(format!("assetscan:{}", machine_id.to_lowercase()).as_bytes());
+    Some(identity::uuid_format(&digest))
+}
+
+/// `systemctl list-units --type=service --all`: nome → (estado, descrição)
+pub fn parse_list_units(output: &str) -> Vec<(String, String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // "● " marca unidades falhadas ou em falta, mesmo com --plain
+            let line = line.trim_start().trim_start_matches('●').trim_start();
+            let mut fields = line.split_whitespace();
+            let (unit, load, active, sub) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+            if load == "not-found" {
+                return None;
+            }
+            let description = fields.collect::<Vec<_>>().join(" ");
+
+            let state = match (active, sub) {
+                (_, "running") => "running",
+                ("failed", _) => "failed",
+                ("inactive", _) => "stopped",
+                (_, sub) => sub,
+            };
+            Some((service_name(unit), state.to_string(), description))
+        })
+        .collect()
+}
+
+/// `systemctl list-unit-files --type=service`: nome → modo de arranque
+pub fn parse_unit_files(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (unit, state) = (fields.next()?, fields.next()?);
+            if unit.contains("@.") {
+                return None; // Modelos, não unidades reais
+            }
+            let start_mode = match state {
+                "enabled" | "enabled-runtime" | "alias" => "auto",
+                "disabled" | "masked" | "masked-runtime" => "disabled",
+                _ => "manual", // static, indirect, generated: arrancam por dependência
+            };
+            Some((service_name(unit), start_mode.to_string()))
+        })
+        .collect()
+}
+
+fn service_name(unit: &str) -> String {
+    unit.strip_suffix(".service").unwrap_or(unit).to_string()
+}
+
+/// Junta o estado em execução com o modo de arranque de cada unidade
+pub fn services(units: &str, unit_files: &str) -> Vec<Service> {
+    let start_modes = parse_unit_files(unit_files);
+
+    parse_list_units(units)
+        .into_iter()
+        .map(|(name, state, description)| Service {
+            start_mode: start_modes.get(&name).cloned().unwrap_or_else(|| "manual".to_string()),
+            display_name: if description.is_empty() { name.clone() } else { description },
+            name,
+            state,
+        })
+        .collect()
+}
+
+/// Utilizador da sessão activa num posto (seat); sessões SSH não contam como consola
+pub fn console_user(sessions: &[String]) -> Option<String> {
+    sessions.iter().find_map(|content| {
+        let fields: HashMap<&str, &str> = content.lines().filter_map(|l| l.split_once('=')).collect();
+        let active = fields.get("ACTIVE") == Some(&"1");
+        let on_seat = fields.get("SEAT").is_some_and(|s| !s.is_empty());
+        let is_user = fields.get("CLASS").copied().unwrap_or("user") == "user";
+
+        (active && on_seat && is_user)
+            .then(|| fields.get("USER").map(|u| u.to_string()))
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST_UNITS: &str = "  accounts-daemon.service   loaded    active   running Accounts Service
+  apparmor.service          loaded    active   exited  Load AppArmor profiles
+  bluetooth.service         loaded    inactive dead    Bluetooth service
+● nginx.service             loaded    failed   failed  A high performance web server
+● ghost.service             not-found inactive dead    ghost.service
+";
+
+    const UNIT_FILES: &str = "accounts-daemon.service                    enabled         enabled
+apparmor.service                           enabled         enabled
+autovt@.service                            alias           -
+bluetooth.service                          disabled        enabled
+nginx.service                              masked          enabled
+systemd-journald.service                   static          -
+";
+
+    #[test]
+    fn machine_id_is_derived_not_copied() {
+        let id = machine_uuid("b08dfa6083e7567a1921a715000001fb\n").unwrap();
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.matches('-').count(), 4);
+        assert!(!id.replace('-', "").contains("b08dfa6083e7567a"));
+        assert_eq!(machine_uuid("B08DFA6083E7567A1921A715000001FB"), Some(id));
+
+        assert_eq!(machine_uuid("uninitialized"), None);
+        assert_eq!(machine_uuid(""), None);
+    }
+
+    #[test]
+    fn units_with_state_and_start_mode() {
+        let services = services(LIST_UNITS, UNIT_FILES);
+        assert_eq!(services.len(), 4);
+
+        let by_name = |name: &str| services.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(by_name("accounts-daemon").state, "running");
+        assert_eq!(by_name("accounts-daemon").start_mode, "auto");
+        assert_eq!(by_name("accounts-daemon").display_name, "Accounts Service");
+        assert_eq!(by_name("apparmor").state, "exited");
+        assert_eq!(by_name("bluetooth").state, "stopped");
+        assert_eq!(by_name("bluetooth").start_mode, "disabled");
+        assert_eq!(by_name("nginx").state, "failed");
+        assert!(services.iter().all(|s| s.name != "ghost"));
+    }
+
+    #[test]
+    fn unit_files_skip_templates() {
+        let modes = parse_unit_files(UNIT_FILES);
+        assert_eq!(modes.get("systemd-journald").map(String::as_str), Some("manual"));
+        assert!(!modes.contains_key("autovt@"));
+    }
+
+    #[test]
+    fn console_user_ignores_remote_sessions() {
+        let ssh = "# This is private data. Do not parse.\nUID=1001\nUSER=admin\nACTIVE=1\nREMOTE=1\nCLASS=user\n";
+        let greeter = "UID=120\nUSER=gdm\nACTIVE=0\nSEAT=seat0\nCLASS=greeter\n";
+        let desktop = "UID=1000\nUSER=maria\nACTIVE=1\nSEAT=seat0\nCLASS=user\nTYPE=wayland\n";
+
+        let sessions = vec![ssh.to_string(), greeter.to_string(), desktop.to_string()];
+        assert_eq!(console_user(&sessions), Some("maria".to_string()));
+        assert_eq!(console_user(&sessions[..2]), None);
+    }
+}
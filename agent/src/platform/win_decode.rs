@@ -0,0 +1,220 @@
+// ============================================================
+// platform/win_decode.rs — Descodificação das estruturas Win32
+// Funções puras sobre bytes e códigos devolvidos pelo IP Helper,
+// WLAN API e WMI, para poderem ser testadas fora do Windows.
+// ============================================================
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 23;
+
+/// SOCKADDR_IN / SOCKADDR_IN6 em bytes → IP (família nos 2 primeiros bytes)
+pub fn sockaddr_ip(raw: &[u8]) -> Option<IpAddr> {
+    let family = u16::from_le_bytes(raw.get(0..2)?.try_into().ok()?);
+    match family {
+        AF_INET => {
+            let octets: [u8; 4] = raw.get(4..8)?.try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        AF_INET6 => {
+            let octets: [u8; 16] = raw.get(8..24)?.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Endereço físico no formato do `getmac` ("AA-BB-CC-DD-EE-FF"); vazio ou só zeros → None
+pub fn mac_address(bytes: &[u8]) -> Option<String> {
+    if bytes.is_empty() || bytes.iter().all(|b| *b == 0) {
+        return None;
+    }
+    Some(bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join("-"))
+}
+
+/// TransmitLinkSpeed vem em bits/s; u64::MAX (ou 0, em placas desligadas) significa desconhecida
+pub fn link_speed_mbps(bits_per_second: u64) -> Option<i64> {
+    if bits_per_second == u64::MAX || bits_per_second == 0 {
+        return None;
+    }
+    Some((bits_per_second / 1_000_000) as i64)
+}
+
+/// Tipo de memória de Win32_PhysicalMemory. SMBIOSMemoryType é o fiável; MemoryType
+/// fica a 0 ("Unknown") na maioria das máquinas com DDR4 ou mais recente
+pub fn ram_type(smbios_memory_type: Option<u32>, memory_type: Option<u16>) -> Option<&'static str> {
+    let by_smbios = smbios_memory_type.and_then(super::smbios_memory_type);
+
+    by_smbios.or_else(|| match memory_type? {
+        20 => Some("DDR"),
+        21 => Some("DDR2"),
+        24 => Some("DDR3"),
+        26 => Some("DDR4"),
+        _ => None,
+    })
+}
+
+/// DOT11_AUTH_ALGORITHM → nome como o `netsh wlan` o mostra
+pub fn wifi_auth_name(algorithm: i32) -> &'static str {
+    match algorithm {
+        1 => "Open",
+        2 => "Shared",
+        3 => "WPA-Enterprise",
+        4 => "WPA-Personal",
+        6 => "WPA2-Enterprise",
+        7 => "WPA2-Personal",
+        8 | 11 => "WPA3-Enterprise",
+        9 => "WPA3-Personal",
+        10 => "OWE",
+        _ => "Unknown",
+    }
+}
+
+/// DOT11_SSID: o comprimento vem à parte e o nome não tem de ser UTF-8
+pub fn ssid(bytes: &[u8], length: u32) -> String {
+    let length = (length as usize).min(bytes.len());
+    String::from_utf8_lossy(&bytes[..length]).to_string()
+}
+
+/// Strings de WmiMonitorID: arrays de u16, um carácter por elemento, preenchidos com zeros
+pub fn wmi_text(chars: &[u16]) -> Option<String> {
+    let end = chars.iter().position(|c| *c == 0).unwrap_or(chars.len());
+    super::firmware_string(&String::from_utf16_lossy(&chars[..end]))
+}
+
+/// InstanceName do WmiMonitorID ("DISPLAY\DEL4101\5&1a2b&0&UID4352_0") → chave em
+/// HKLM\SYSTEM\CurrentControlSet\Enum (sem o sufixo "_0")
+pub fn monitor_device_key(instance_name: &str) -> Option<String> {
+    let (key, suffix) = instance_name.rsplit_once('_')?;
+    suffix.chars().all(|c| c.is_ascii_digit()).then(|| key.to_string())
+}
+
+/// BatteryStaticData.Chemistry: quatro letras ASCII num u32 little-endian ("LION")
+pub fn battery_chemistry(code: u32) -> Option<String> {
+    let text = String::from_utf8_lossy(&code.to_le_bytes()).trim_matches(char::from(0)).trim().to_uppercase();
+    match text.as_str() {
+        "" => None,
+        "LION" | "LI-I" => Some("Li-ion".to_string()),
+        "LIP" | "LIPO" => Some("Li-poly".to_string()),
+        "NIMH" => Some("NiMH".to_string()),
+        "NICD" => Some("NiCd".to_string()),
+        "PBAC" => Some("Lead acid".to_string()),
+        _ => Some(text),
+    }
+}
+
+/// MSFT_PhysicalDisk.MediaType (SCM, memória persistente, conta como SSD)
+pub fn disk_media_type(media_type: u16) -> &'static str {
+    match media_type {
+        3 => "hdd",
+        4 | 5 => "ssd",
+        _ => "unknown",
+    }
+}
+
+/// MSFT_PhysicalDisk.BusType
+pub fn disk_bus_type(bus_type: u16) -> Option<&'static str> {
+    match bus_type {
+        1 => Some("SCSI"),
+        3 => Some("ATA"),
+        6 => Some("Fibre Channel"),
+        7 => Some("USB"),
+        8 => Some("RAID"),
+        10 => Some("SAS"),
+        11 => Some("SATA"),
+        12 => Some("SD"),
+        13 => Some("MMC"),
+        16 => Some("Storage Spaces"),
+        17 => Some("NVMe"),
+        _ => None,
+    }
+}
+
+/// MSFT_PhysicalDisk.HealthStatus: 0 Healthy, 1 Warning, 2 Unhealthy, 5 Unknown
+pub fn disk_health(status: u16) -> Option<&'static str> {
+    match status {
+        0 => Some("healthy"),
+        1 => Some("warning"),
+        2 => Some("failing"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_ipv4_and_ipv6_sockaddrs() {
+        // SOCKADDR_IN: família, porta, 192.168.1.20, sin_zero
+        let v4 = [2, 0, 0, 0, 192, 168, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(sockaddr_ip(&v4), Some("192.168.1.20".parse().unwrap()));
+
+        // SOCKADDR_IN6: família, porta, flowinfo, fe80::1, scope id
+        let mut v6 = vec![23, 0, 0, 0, 0, 0, 0, 0, 0xfe, 0x80];
+        v6.extend_from_slice(&[0; 13]);
+        v6.push(1);
+        v6.extend_from_slice(&[0; 4]);
+        assert_eq!(sockaddr_ip(&v6), Some("fe80::1".parse().unwrap()));
+
+        assert_eq!(sockaddr_ip(&[2, 0, 0]), None);
+        assert_eq!(sockaddr_ip(&[17, 0, 0, 0, 1, 2, 3, 4]), None);
+    }
+
+    #[test]
+    fn formats_mac_like_getmac() {
+        assert_eq!(
+            mac_address(&[0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]),
+            Some("00-1A-2B-3C-4D-5E".to_string())
+        );
+        assert_eq!(mac_address(&[0; 6]), None);
+        assert_eq!(mac_address(&[]), None);
+    }
+
+    #[test]
+    fn link_speed_handles_unknown() {
+        assert_eq!(link_speed_mbps(1_000_000_000), Some(1000));
+        assert_eq!(link_speed_mbps(u64::MAX), None);
+        assert_eq!(link_speed_mbps(0), None);
+    }
+
+    #[test]
+    fn ram_type_prefers_smbios_code() {
+        assert_eq!(ram_type(Some(26), Some(0)), Some("DDR4"));
+        assert_eq!(ram_type(Some(34), None), Some("DDR5"));
+        assert_eq!(ram_type(Some(2), Some(24)), Some("DDR3"));
+        assert_eq!(ram_type(None, None), None);
+    }
+
+    #[test]
+    fn wifi_names_match_netsh() {
+        assert_eq!(wifi_auth_name(7), "WPA2-Personal");
+        assert_eq!(wifi_auth_name(9), "WPA3-Personal");
+        assert_eq!(ssid(b"Escritorio\0\0\0", 10), "Escritorio");
+        assert_eq!(ssid(b"abc", 40), "abc");
+    }
+
+    #[test]
+    fn decodes_monitor_ids() {
+        let name: Vec<u16> = "DELL P2419H".encode_utf16().chain([0, 0, 0]).collect();
+        assert_eq!(wmi_text(&name), Some("DELL P2419H".to_string()));
+        assert_eq!(wmi_text(&[0, 0]), None);
+        assert_eq!(
+            monitor_device_key(r"DISPLAY\DEL4101\5&1a2b3c4d&0&UID4352_0"),
+            Some(r"DISPLAY\DEL4101\5&1a2b3c4d&0&UID4352".to_string())
+        );
+    }
+
+    #[test]
+    fn decodes_battery_and_disk_codes() {
+        assert_eq!(battery_chemistry(u32::from_le_bytes(*b"LION")), Some("Li-ion".to_string()));
+        assert_eq!(battery_chemistry(u32::from_le_bytes(*b"LiP\0")), Some("Li-poly".to_string()));
+        assert_eq!(battery_chemistry(0), None);
+        assert_eq!(disk_media_type(4), "ssd");
+        assert_eq!(disk_media_type(0), "unknown");
+        assert_eq!(disk_bus_type(17), Some("NVMe"));
+        assert_eq!(disk_health(2), Some("failing"));
+        assert_eq!(disk_health(5), None);
+    }
+}
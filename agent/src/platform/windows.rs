@@ -0,0 +1,977 @@
+// ============================================================
+// platform/windows.rs — Implementação Windows (Registry, IP Helper,
+// WLAN, WMI)
+// ============================================================
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use windows::Win32::Foundation::{BOOL, ERROR_BUFFER_OVERFLOW, ERROR_INSUFFICIENT_BUFFER, HANDLE, NO_ERROR};
+use windows::Win32::NetworkManagement::IpHelper::{
+    ConvertInterfaceLuidToAlias, FreeMibTable, GetAdaptersAddresses, GetExtendedTcpTable, GetIpForwardTable2,
+    GetIpNetTable2, MIB_IPFORWARD_TABLE2, MIB_IPNET_TABLE2, GetExtendedUdpTable, GAA_FLAG_INCLUDE_GATEWAYS,
+    GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_MULTICAST, IP_ADAPTER_ADDRESSES_LH, MIB_TCP6ROW_OWNER_PID,
+    MIB_TCP6TABLE_OWNER_PID, MIB_TCPROW_OWNER_PID, MIB_TCPTABLE_OWNER_PID, MIB_UDP6ROW_OWNER_PID,
+    MIB_UDP6TABLE_OWNER_PID, MIB_UDPROW_OWNER_PID, MIB_UDPTABLE_OWNER_PID, TCP_TABLE_OWNER_PID_ALL,
+    UDP_TABLE_OWNER_PID,
+};
+use windows::Win32::NetworkManagement::Ndis::{IfOperStatusUp, NET_LUID_LH};
+use windows::Win32::NetworkManagement::WiFi::{
+    wlan_interface_state_connected, wlan_intf_opcode_current_connection, WlanCloseHandle, WlanEnumInterfaces,
+    WlanFreeMemory, WlanOpenHandle, WlanQueryInterface, WLAN_CONNECTION_ATTRIBUTES, WLAN_INTERFACE_INFO_LIST,
+};
+use windows::Win32::Networking::WinSock::{ADDRESS_FAMILY, SOCKADDR_INET, SOCKET_ADDRESS};
+use winreg::enums::*;
+use winreg::RegKey;
+use wmi::{COMLibrary, WMIConnection};
+
+use super::{
+    adapter_kind, edid, firmware_string, identity, win_decode, Battery, ComputerIdentity, Gpu, HardwareAnchors,
+    HardwareInventory, InstalledSoftware, MemoryModule, Monitor, Neighbor, NetworkAdapter, PhysicalDisk, Platform, PlatformError, Result,
+    Route, Service, Socket, WifiConnection,
+};
+use crate::config::{self, Config};
+use crate::hardware_collector::{self, UsbDevice};
+
+const CONFIG_KEY: &str = r"SOFTWARE\AssetScan\Config";
+const DISPLAY_CLASS_KEY: &str = r"SYSTEM\CurrentControlSet\Control\Class\{4d36e968-e325-11ce-bfc1-08002be10318}";
+const MACHINE_ID_KEY: &str = r"SOFTWARE\AssetScan";
+
+const AF_UNSPEC: u32 = 0;
+// NL_NEIGHBOR_STATE: 0 Unreachable, 1 Incomplete; daí para cima o MAC é conhecido
+const NL_NEIGHBOR_STATE_INCOMPLETE: i32 = 1;
+const AF_INET: u32 = 2;
+const AF_INET6: u32 = 23;
+
+const IF_TYPE_ETHERNET_CSMACD: u32 = 6;
+const IF_TYPE_PPP: u32 = 23;
+const IF_TYPE_SOFTWARE_LOOPBACK: u32 = 24;
+const IF_TYPE_IEEE80211: u32 = 71;
+const IP_ADAPTER_DHCP_ENABLED: u32 = 0x4;
+// MSFT_PhysicalDisk.BusType dos VHD/VHDX montados
+const BUS_TYPE_FILE_BACKED_VIRTUAL: u16 = 15;
+
+// Serviço WLAN parado ou inexistente (desktops sem placa Wi-Fi)
+const ERROR_SERVICE_NOT_ACTIVE: u32 = 1062;
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_BIOS {
+    serial_number: Option<String>,
+    #[serde(rename = "SMBIOSBIOSVersion")]
+    smbios_bios_version: Option<String>,
+    version: Option<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_BaseBoard {
+    manufacturer: Option<String>,
+    product: Option<String>,
+    serial_number: Option<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_ComputerSystemProduct {
+    #[serde(rename = "UUID")]
+    uuid: Option<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_VideoController {
+    name: Option<String>,
+    #[serde(rename = "AdapterRAM")]
+    adapter_ram: Option<u32>,
+    adapter_compatibility: Option<String>,
+    driver_version: Option<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_PhysicalMemoryArray {
+    memory_devices: Option<u16>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_PhysicalMemory {
+    device_locator: Option<String>,
+    bank_label: Option<String>,
+    capacity: Option<u64>,
+    speed: Option<u32>,
+    configured_clock_speed: Option<u32>,
+    manufacturer: Option<String>,
+    part_number: Option<String>,
+    serial_number: Option<String>,
+    #[serde(rename = "SMBIOSMemoryType")]
+    smbios_memory_type: Option<u32>,
+    memory_type: Option<u16>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_Battery {
+    #[serde(rename = "DeviceID")]
+    device_id: Option<String>,
+}
+
+// root\WMI: uma instância por bateria, ligadas pelo InstanceName
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct BatteryStaticData {
+    instance_name: String,
+    device_name: Option<String>,
+    manufacture_name: Option<String>,
+    serial_number: Option<String>,
+    chemistry: Option<u32>,
+    designed_capacity: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct BatteryFullChargedCapacity {
+    instance_name: String,
+    full_charged_capacity: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct BatteryCycleCount {
+    instance_name: String,
+    cycle_count: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct WmiMonitorId {
+    instance_name: String,
+    active: Option<bool>,
+    manufacturer_name: Option<Vec<u16>>,
+    user_friendly_name: Option<Vec<u16>>,
+    #[serde(rename = "SerialNumberID")]
+    serial_number_id: Option<Vec<u16>>,
+}
+
+// root\Microsoft\Windows\Storage (Windows 8 / Server 2012 ou mais recente)
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct MsftPhysicalDisk {
+    friendly_name: Option<String>,
+    model: Option<String>,
+    serial_number: Option<String>,
+    media_type: Option<u16>,
+    bus_type: Option<u16>,
+    size: Option<u64>,
+    health_status: Option<u16>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_ComputerSystem {
+    domain: Option<String>,
+    part_of_domain: Option<bool>,
+    user_name: Option<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Win32_Service {
+    name: String,
+    display_name: Option<String>,
+    state: Option<String>,
+    start_mode: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct PnpEntity {
+    #[serde(rename = "DeviceID")]
+    device_id: String,
+    name: Option<String>,
+    #[serde(rename = "PNPClass")]
+    pnp_class: Option<String>,
+    manufacturer: Option<String>,
+    service: Option<String>,
+}
+
+fn wmi_connection() -> Result<WMIConnection> {
+    Ok(WMIConnection::new(COMLibrary::new()?)?)
+}
+
+fn wmi_namespace(path: &str) -> Result<WMIConnection> {
+    Ok(WMIConnection::with_namespace_path(path, COMLibrary::new()?)?)
+}
+
+pub struct WindowsPlatform;
+
+impl Platform for WindowsPlatform {
+    fn load_config(&self) -> Result<Option<Config>> {
+        let Ok(key) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(CONFIG_KEY) else {
+            return Ok(None);
+        };
+
+        let server_url: String = key
+            .get_value("ServerURL")
+            .map_err(|_| PlatformError::InvalidConfig("ServerURL em falta no Registry".to_string()))?;
+        let api_key: String = key
+            .get_value("APIKey")
+            .map_err(|_| PlatformError::InvalidConfig("APIKey em falta no Registry".to_string()))?;
+        let interval_minutes: u64 = key.get_value::<u32, _>("IntervalMinutes")
+            .unwrap_or(60) as u64;
+        let enforcement_enabled: u32 = key.get_value("EnforcementEnabled")
+            .unwrap_or(1);
+        let hosts_path: Option<String> = key.get_value("HostsPath").ok();
+        let dns_filter_enabled: u32 = key.get_value("DnsFilterEnabled").unwrap_or(0);
+        let dns_upstream = key
+            .get_value::<String, _>("DnsUpstream")
+            .map(|v| config::parse_list(&v))
+            .unwrap_or_default();
+        let push_enabled: u32 = key.get_value("PushEnabled").unwrap_or(1);
+        let report_neighbors: u32 = key.get_value("ReportNeighbors").unwrap_or(0);
+        let script_public_key: Option<String> = key.get_value("ScriptPublicKey").ok();
+
+        Ok(Some(Config {
+            server_url,
+            api_key,
+            interval_minutes,
+            enforcement_enabled: enforcement_enabled != 0,
+            hosts_path,
+            dns_filter_enabled: dns_filter_enabled != 0,
+            dns_upstream,
+            push_enabled: push_enabled != 0,
+            report_neighbors: report_neighbors != 0,
+            script_public_key,
+        }))
+    }
+
+    fn system_exe_dirs(&self) -> Vec<PathBuf> {
+        let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+        vec![PathBuf::from(system_root)]
+    }
+
+    fn machine_id(&self) -> Result<String> {
+        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+
+        if let Ok(key) = hklm.open_subkey(MACHINE_ID_KEY) {
+            if let Ok(id) = key.get_value::<String, _>("MachineID") {
+                return Ok(id);
+            }
+        }
+
+        // Sem ID guardado (primeira instalação ou SO reinstalado): o derivado do hardware
+        // volta a dar o da instalação anterior
+        let new_id = self
+            .hardware_anchors()
+            .ok()
+            .and_then(|a| identity::derived_machine_id(&a))
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let (key, _) = hklm.create_subkey(MACHINE_ID_KEY)?;
+        key.set_value("MachineID", &new_id)?;
+
+        Ok(new_id)
+    }
+
+    fn firmware_anchors(&self) -> Result<HardwareAnchors> {
+        let wmi = wmi_connection()?;
+        let mut anchors = HardwareAnchors::default();
+
+        let products: Vec<Win32_ComputerSystemProduct> =
+            wmi.raw_query("SELECT UUID FROM Win32_ComputerSystemProduct")?;
+        anchors.system_uuid = products.into_iter().next().and_then(|p| p.uuid).as_deref().and_then(identity::system_uuid);
+
+        let bios: Vec<Win32_BIOS> = wmi.raw_query("SELECT SerialNumber, SMBIOSBIOSVersion, Version FROM Win32_BIOS")?;
+        anchors.bios_serial = bios.into_iter().next().and_then(|b| b.serial_number).as_deref().and_then(firmware_string);
+
+        let boards: Vec<Win32_BaseBoard> =
+            wmi.raw_query("SELECT Manufacturer, Product, SerialNumber FROM Win32_BaseBoard")?;
+        anchors.board_serial = boards.into_iter().next().and_then(|b| b.serial_number).as_deref().and_then(firmware_string);
+
+        Ok(anchors)
+    }
+
+    fn installed_software(&self) -> Result<Vec<InstalledSoftware>> {
+        let mut list = Vec::new();
+
+        let paths = [
+            (HKEY_LOCAL_MACHINE, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
+            (HKEY_LOCAL_MACHINE, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall"),
+            (HKEY_CURRENT_USER, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
+        ];
+
+        for (hive, path) in &paths {
+            let root = RegKey::predef(*hive);
+            let Ok(key) = root.open_subkey(path) else { continue };
+
+            for subkey_name in key.enum_keys().flatten() {
+                let Ok(sub) = key.open_subkey(&subkey_name) else { continue };
+
+                let Ok(name) = sub.get_value::<String, _>("DisplayName") else { continue };
+                if name.is_empty() { continue; }
+
+                list.push(InstalledSoftware {
+                    name,
+                    version: sub.get_value("DisplayVersion").unwrap_or_default(),
+                    publisher: sub.get_value("Publisher").unwrap_or_default(),
+                    install_date: sub.get_value("InstallDate").unwrap_or_default(),
+                });
+            }
+        }
+        Ok(list)
+    }
+
+    fn services(&self) -> Result<Vec<Service>> {
+        let services: Vec<Win32_Service> =
+            wmi_connection()?.raw_query("SELECT Name, DisplayName, State, StartMode FROM Win32_Service")?;
+
+        Ok(services
+            .into_iter()
+            .map(|s| Service {
+                display_name: s.display_name.unwrap_or_else(|| s.name.clone()),
+                name: s.name,
+                // "Running" / "Stopped" / "Start Pending" → "running" / "stopped" / "start pending"
+                state: s.state.unwrap_or_default().to_lowercase(),
+                // Win32_Service usa "Auto" / "Manual" / "Disabled" (e "Boot" / "System" nos drivers)
+                start_mode: s.start_mode.unwrap_or_default().to_lowercase(),
+            })
+            .collect())
+    }
+
+    fn usb_devices(&self) -> Result<Vec<UsbDevice>> {
+        let entities: Vec<PnpEntity> = wmi_connection()?.raw_query(
+            "SELECT DeviceID, Name, PNPClass, Manufacturer, Service FROM Win32_PnPEntity WHERE DeviceID LIKE 'USB\\\\VID_%'",
+        )?;
+
+        Ok(entities.into_iter().filter_map(usb_device_from_entity).collect())
+    }
+
+    fn sockets(&self) -> Result<Vec<Socket>> {
+        let mut sockets = Vec::new();
+
+        let table = extended_table("GetExtendedTcpTable", AF_INET, |buf, size, af| unsafe {
+            GetExtendedTcpTable(Some(buf), size, BOOL::from(false), af, TCP_TABLE_OWNER_PID_ALL, 0)
+        })?;
+        for row in unsafe { rows::<MIB_TCPTABLE_OWNER_PID, MIB_TCPROW_OWNER_PID>(&table) } {
+            sockets.push(tcp_socket(
+                IpAddr::V4(Ipv4Addr::from(row.dwLocalAddr.to_ne_bytes())),
+                row.dwLocalPort,
+                IpAddr::V4(Ipv4Addr::from(row.dwRemoteAddr.to_ne_bytes())),
+                row.dwRemotePort,
+                row.dwState,
+                row.dwOwningPid,
+            ));
+        }
+
+        let table = extended_table("GetExtendedTcpTable", AF_INET6, |buf, size, af| unsafe {
+            GetExtendedTcpTable(Some(buf), size, BOOL::from(false), af, TCP_TABLE_OWNER_PID_ALL, 0)
+        })?;
+        for row in unsafe { rows::<MIB_TCP6TABLE_OWNER_PID, MIB_TCP6ROW_OWNER_PID>(&table) } {
+            sockets.push(tcp_socket(
+                IpAddr::V6(Ipv6Addr::from(row.ucLocalAddr)),
+                row.dwLocalPort,
+                IpAddr::V6(Ipv6Addr::from(row.ucRemoteAddr)),
+                row.dwRemotePort,
+                row.dwState,
+                row.dwOwningPid,
+            ));
+        }
+
+        let table = extended_table("GetExtendedUdpTable", AF_INET, |buf, size, af| unsafe {
+            GetExtendedUdpTable(Some(buf), size, BOOL::from(false), af, UDP_TABLE_OWNER_PID, 0)
+        })?;
+        for row in unsafe { rows::<MIB_UDPTABLE_OWNER_PID, MIB_UDPROW_OWNER_PID>(&table) } {
+            sockets.push(udp_socket(
+                IpAddr::V4(Ipv4Addr::from(row.dwLocalAddr.to_ne_bytes())),
+                row.dwLocalPort,
+                row.dwOwningPid,
+            ));
+        }
+
+        let table = extended_table("GetExtendedUdpTable", AF_INET6, |buf, size, af| unsafe {
+            GetExtendedUdpTable(Some(buf), size, BOOL::from(false), af, UDP_TABLE_OWNER_PID, 0)
+        })?;
+        for row in unsafe { rows::<MIB_UDP6TABLE_OWNER_PID, MIB_UDP6ROW_OWNER_PID>(&table) } {
+            sockets.push(udp_socket(
+                IpAddr::V6(Ipv6Addr::from(row.ucLocalAddr)),
+                row.dwLocalPort,
+                row.dwOwningPid,
+            ));
+        }
+
+        Ok(sockets)
+    }
+
+    fn network_adapters(&self) -> Result<Vec<NetworkAdapter>> {
+        let flags = GAA_FLAG_INCLUDE_GATEWAYS | GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST;
+        // Tamanho inicial recomendado pela documentação, para evitar a segunda chamada
+        let mut size = 15_000u32;
+        let mut buffer: Vec<u64> = Vec::new();
+
+        for _ in 0..3 {
+            buffer = vec![0u64; (size as usize).div_ceil(8)];
+            let status = unsafe {
+                GetAdaptersAddresses(
+                    AF_UNSPEC,
+                    flags,
+                    None,
+                    Some(buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH),
+                    &mut size,
+                )
+            };
+            if status == NO_ERROR.0 {
+                return Ok(unsafe { adapters_from_list(buffer.as_ptr() as *const IP_ADAPTER_ADDRESSES_LH) });
+            }
+            if status != ERROR_BUFFER_OVERFLOW.0 {
+                return Err(PlatformError::Api { api: "GetAdaptersAddresses", code: status });
+            }
+        }
+        Err(PlatformError::Api { api: "GetAdaptersAddresses", code: ERROR_BUFFER_OVERFLOW.0 })
+    }
+
+    fn routes(&self) -> Result<Vec<Route>> {
+        let mut table: *mut MIB_IPFORWARD_TABLE2 = std::ptr::null_mut();
+        unsafe {
+            let status = GetIpForwardTable2(ADDRESS_FAMILY(AF_UNSPEC as u16), &mut table);
+            if status.is_err() {
+                return Err(PlatformError::Api { api: "GetIpForwardTable2", code: status.0 });
+            }
+
+            let rows = std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+            let mut aliases: HashMap<u64, Option<String>> = HashMap::new();
+            let routes = rows
+                .iter()
+                .filter(|row| !row.Loopback.as_bool())
+                .filter_map(|row| {
+                    let destination = sockaddr_inet_ip(&row.DestinationPrefix.Prefix)?;
+                    let gateway = sockaddr_inet_ip(&row.NextHop).filter(|g| !g.is_unspecified());
+                    let interface = aliases
+                        .entry(row.InterfaceLuid.Value)
+                        .or_insert_with(|| interface_alias(&row.InterfaceLuid))
+                        .clone()
+                        .unwrap_or_else(|| row.InterfaceIndex.to_string());
+
+                    Some(Route {
+                        interface,
+                        destination,
+                        prefix: row.DestinationPrefix.PrefixLength,
+                        gateway,
+                        metric: row.Metric,
+                    })
+                })
+                .collect();
+
+            FreeMibTable(table as *const c_void);
+            Ok(routes)
+        }
+    }
+
+    fn neighbors(&self) -> Result<Vec<Neighbor>> {
+        let mut table: *mut MIB_IPNET_TABLE2 = std::ptr::null_mut();
+        unsafe {
+            let status = GetIpNetTable2(ADDRESS_FAMILY(AF_UNSPEC as u16), &mut table);
+            if status.is_err() {
+                return Err(PlatformError::Api { api: "GetIpNetTable2", code: status.0 });
+            }
+
+            let rows = std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+            let mut aliases: HashMap<u64, Option<String>> = HashMap::new();
+            let neighbors = rows
+                .iter()
+                .filter(|row| row.State.0 > NL_NEIGHBOR_STATE_INCOMPLETE)
+                .filter_map(|row| {
+                    let length = (row.PhysicalAddressLength as usize).min(row.PhysicalAddress.len());
+                    let mac_address = win_decode::mac_address(&row.PhysicalAddress[..length])?;
+                    let ip = sockaddr_inet_ip(&row.Address)?;
+                    let interface = aliases
+                        .entry(row.InterfaceLuid.Value)
+                        .or_insert_with(|| interface_alias(&row.InterfaceLuid))
+                        .clone()
+                        .unwrap_or_else(|| row.InterfaceIndex.to_string());
+
+                    Some(Neighbor { interface, ip, mac_address })
+                })
+                .collect();
+
+            FreeMibTable(table as *const c_void);
+            Ok(neighbors)
+        }
+    }
+
+    fn wifi(&self) -> Result<Option<WifiConnection>> {
+        unsafe {
+            let mut version = 0u32;
+            let mut client = HANDLE::default();
+            match WlanOpenHandle(2, None, &mut version, &mut client) {
+                0 => {}
+                ERROR_SERVICE_NOT_ACTIVE => return Ok(None),
+                code => return Err(PlatformError::Api { api: "WlanOpenHandle", code }),
+            }
+
+            let result = current_wifi_connection(client);
+            WlanCloseHandle(client, None);
+            result
+        }
+    }
+
+    fn computer_identity(&self) -> Result<ComputerIdentity> {
+        let wmi = wmi_connection()?;
+        let systems: Vec<Win32_ComputerSystem> =
+            wmi.raw_query("SELECT Domain, PartOfDomain, UserName FROM Win32_ComputerSystem")?;
+        let Some(system) = systems.into_iter().next() else {
+            return Err(PlatformError::NotFound("Win32_ComputerSystem"));
+        };
+
+        Ok(ComputerIdentity {
+            domain: system.domain.filter(|d| !d.is_empty()),
+            part_of_domain: system.part_of_domain.unwrap_or(false),
+            console_user: system.user_name.filter(|u| !u.is_empty()),
+        })
+    }
+
+    fn hardware(&self) -> Result<HardwareInventory> {
+        let wmi = wmi_connection()?;
+        let mut inventory = HardwareInventory::default();
+
+        let bios: Vec<Win32_BIOS> = wmi.raw_query("SELECT SerialNumber, SMBIOSBIOSVersion, Version FROM Win32_BIOS")?;
+        if let Some(bios) = bios.into_iter().next() {
+            inventory.serial_number = bios.serial_number.as_deref().and_then(firmware_string);
+            inventory.bios_version = bios
+                .smbios_bios_version
+                .or(bios.version)
+                .as_deref()
+                .and_then(firmware_string);
+        }
+
+        let boards: Vec<Win32_BaseBoard> = wmi.raw_query("SELECT Manufacturer, Product, SerialNumber FROM Win32_BaseBoard")?;
+        if let Some(board) = boards.into_iter().next() {
+            inventory.motherboard_manufacturer = board.manufacturer.as_deref().and_then(firmware_string);
+            inventory.motherboard_model = board.product.as_deref().and_then(firmware_string);
+        }
+
+        let arrays: Vec<Win32_PhysicalMemoryArray> =
+            wmi.raw_query("SELECT MemoryDevices FROM Win32_PhysicalMemoryArray")?;
+        inventory.total_ram_slots = arrays.iter().map(|a| a.memory_devices.unwrap_or(0) as i64).sum();
+
+        let modules: Vec<Win32_PhysicalMemory> =
+            wmi.raw_query("SELECT SMBIOSMemoryType, MemoryType FROM Win32_PhysicalMemory")?;
+        inventory.used_ram_slots = modules.len() as i64;
+        inventory.ram_type = modules
+            .iter()
+            .find_map(|m| win_decode::ram_type(m.smbios_memory_type, m.memory_type))
+            .map(|t| t.to_string());
+
+        Ok(inventory)
+    }
+
+    fn memory_modules(&self) -> Result<Vec<MemoryModule>> {
+        let modules: Vec<Win32_PhysicalMemory> = wmi_connection()?.raw_query(
+            "SELECT DeviceLocator, BankLabel, Capacity, Speed, ConfiguredClockSpeed, Manufacturer, PartNumber, \
+             SerialNumber, SMBIOSMemoryType, MemoryType FROM Win32_PhysicalMemory",
+        )?;
+
+        Ok(modules
+            .into_iter()
+            .map(|m| MemoryModule {
+                memory_type: win_decode::ram_type(m.smbios_memory_type, m.memory_type).map(|t| t.to_string()),
+                slot: m
+                    .device_locator
+                    .or(m.bank_label)
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_default(),
+                size_mb: (m.capacity.unwrap_or(0) / 1024 / 1024) as i64,
+                // ConfiguredClockSpeed é a velocidade a que o módulo está a trabalhar
+                speed_mts: m.configured_clock_speed.or(m.speed).filter(|s| *s > 0),
+                manufacturer: m.manufacturer.as_deref().and_then(firmware_string),
+                part_number: m.part_number.as_deref().and_then(firmware_string),
+                serial_number: m.serial_number.as_deref().and_then(firmware_string),
+            })
+            .collect())
+    }
+
+    fn gpus(&self) -> Result<Vec<Gpu>> {
+        let controllers: Vec<Win32_VideoController> = wmi_connection()?
+            .raw_query("SELECT Name, AdapterRAM, AdapterCompatibility, DriverVersion FROM Win32_VideoController")?;
+        let registry_vram = registry_vram_mb();
+
+        Ok(controllers
+            .into_iter()
+            .filter_map(|c| {
+                let name = c.name.filter(|n| !n.is_empty())?;
+                // AdapterRAM é de 32 bits: acima de 4 GB só o valor do driver no Registry está certo
+                let wmi_vram = c.adapter_ram.map(|b| (b / 1024 / 1024) as i64).filter(|mb| *mb > 0);
+                Some(Gpu {
+                    vram_mb: registry_vram.get(&name).copied().or(wmi_vram),
+                    vendor: c.adapter_compatibility.filter(|v| !v.is_empty()),
+                    driver_version: c.driver_version.filter(|v| !v.is_empty()),
+                    name,
+                })
+            })
+            .collect())
+    }
+
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        let ids: Vec<WmiMonitorId> = wmi_namespace(r"root\WMI")?.raw_query(
+            "SELECT InstanceName, Active, ManufacturerName, UserFriendlyName, SerialNumberID FROM WmiMonitorID",
+        )?;
+        let enum_key = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(r"SYSTEM\CurrentControlSet\Enum").ok();
+
+        Ok(ids
+            .into_iter()
+            .filter(|id| id.active.unwrap_or(true))
+            .map(|id| {
+                // O EDID gravado pelo driver do monitor tem a resolução nativa
+                let from_edid = win_decode::monitor_device_key(&id.instance_name)
+                    .and_then(|key| enum_key.as_ref()?.open_subkey(format!(r"{}\Device Parameters", key)).ok())
+                    .and_then(|params| params.get_raw_value("EDID").ok())
+                    .and_then(|value| edid::parse(&value.bytes));
+
+                from_edid.unwrap_or_else(|| Monitor {
+                    manufacturer: id
+                        .manufacturer_name
+                        .as_deref()
+                        .and_then(win_decode::wmi_text)
+                        .map(|code| edid::manufacturer_name(&code)),
+                    model: id.user_friendly_name.as_deref().and_then(win_decode::wmi_text),
+                    serial_number: id.serial_number_id.as_deref().and_then(win_decode::wmi_text),
+                    ..Default::default()
+                })
+            })
+            .collect())
+    }
+
+    fn batteries(&self) -> Result<Vec<Battery>> {
+        // Sem bateria as classes de root\WMI nem respondem
+        let present: Vec<Win32_Battery> = wmi_connection()?.raw_query("SELECT DeviceID FROM Win32_Battery")?;
+        if present.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let wmi = wmi_namespace(r"root\WMI")?;
+        let batteries: Vec<BatteryStaticData> = wmi.raw_query(
+            "SELECT InstanceName, DeviceName, ManufactureName, SerialNumber, Chemistry, DesignedCapacity \
+             FROM BatteryStaticData",
+        )?;
+        // Capacidade actual e ciclos são opcionais no firmware: na falta ficam por saber
+        let full_charge: HashMap<String, u32> = wmi
+            .raw_query::<BatteryFullChargedCapacity>("SELECT InstanceName, FullChargedCapacity FROM BatteryFullChargedCapacity")
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|b| Some((b.instance_name, b.full_charged_capacity?)))
+            .collect();
+        let cycles: HashMap<String, u32> = wmi
+            .raw_query::<BatteryCycleCount>("SELECT InstanceName, CycleCount FROM BatteryCycleCount")
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|b| Some((b.instance_name, b.cycle_count?)))
+            .collect();
+
+        Ok(batteries
+            .into_iter()
+            .map(|b| Battery {
+                name: b.device_name.as_deref().and_then(firmware_string),
+                manufacturer: b.manufacture_name.as_deref().and_then(firmware_string),
+                serial_number: b.serial_number.as_deref().and_then(firmware_string),
+                chemistry: b.chemistry.and_then(win_decode::battery_chemistry),
+                design_capacity_mwh: b.designed_capacity.filter(|c| *c > 0).map(|c| c as i64),
+                full_charge_capacity_mwh: full_charge.get(&b.instance_name).filter(|c| **c > 0).map(|c| *c as i64),
+                cycle_count: cycles.get(&b.instance_name).filter(|c| **c > 0).map(|c| *c as i64),
+            })
+            .collect())
+    }
+
+    fn physical_disks(&self) -> Result<Vec<PhysicalDisk>> {
+        let disks: Vec<MsftPhysicalDisk> = wmi_namespace(r"root\Microsoft\Windows\Storage")?.raw_query(
+            "SELECT FriendlyName, Model, SerialNumber, MediaType, BusType, Size, HealthStatus FROM MSFT_PhysicalDisk",
+        )?;
+
+        Ok(disks
+            .into_iter()
+            .filter(|d| d.bus_type != Some(BUS_TYPE_FILE_BACKED_VIRTUAL)) // VHDs montados
+            .map(|d| PhysicalDisk {
+                model: d
+                    .model
+                    .or(d.friendly_name)
+                    .map(|m| m.trim().to_string())
+                    .unwrap_or_default(),
+                serial_number: d.serial_number.as_deref().and_then(firmware_string),
+                media_type: win_decode::disk_media_type(d.media_type.unwrap_or(0)).to_string(),
+                bus_type: d.bus_type.and_then(win_decode::disk_bus_type).map(|b| b.to_string()),
+                size_gb: d.size.unwrap_or(0) as f64 / 1_073_741_824.0,
+                // HealthStatus já reflecte a previsão de falha do SMART
+                health: d.health_status.and_then(win_decode::disk_health).map(|h| h.to_string()),
+            })
+            .collect())
+    }
+}
+
+/// Memória dedicada de cada placa gráfica, pelo DriverDesc, tal como o driver a regista
+fn registry_vram_mb() -> HashMap<String, i64> {
+    let Ok(class) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(DISPLAY_CLASS_KEY) else {
+        return HashMap::new();
+    };
+
+    class
+        .enum_keys()
+        .flatten()
+        .filter_map(|name| class.open_subkey(name).ok())
+        .filter_map(|key| {
+            let description: String = key.get_value("DriverDesc").ok()?;
+            let size = key.get_raw_value("HardwareInformation.qwMemorySize").ok()?;
+            let bytes: [u8; 8] = size.bytes.get(0..8)?.try_into().ok()?;
+            Some((description, (u64::from_le_bytes(bytes) / 1024 / 1024) as i64))
+        })
+        .filter(|(_, mb)| *mb > 0)
+        .collect()
+}
+
+fn usb_device_from_entity(entity: PnpEntity) -> Option<UsbDevice> {
+    let (vendor_id, product_id, serial) = hardware_collector::parse_usb_instance_id(&entity.device_id)?;
+    let device_class = entity.pnp_class.unwrap_or_default();
+    let service = entity.service.unwrap_or_default().to_lowercase();
+
+    // Hubs não são dispositivos que o utilizador liga
+    if service.starts_with("usbhub") {
+        return None;
+    }
+
+    Some(UsbDevice {
+        name: entity.name.unwrap_or_else(|| "Dispositivo USB".to_string()),
+        device_id: entity.device_id,
+        vendor_id,
+        product_id,
+        serial,
+        is_mass_storage: service == "usbstor" || service == "uaspstor",
+        device_class,
+        manufacturer: entity.manufacturer,
+        status: "allowed".to_string(),
+    })
+}
+
+/// Percorre a lista ligada devolvida por GetAdaptersAddresses
+unsafe fn adapters_from_list(mut node: *const IP_ADAPTER_ADDRESSES_LH) -> Vec<NetworkAdapter> {
+    let mut adapters = Vec::new();
+
+    while let Some(adapter) = node.as_ref() {
+        node = adapter.Next;
+        if adapter.IfType == IF_TYPE_SOFTWARE_LOOPBACK {
+            continue;
+        }
+
+        let mut ipv4 = Vec::new();
+        let mut ipv6 = Vec::new();
+        let mut unicast = adapter.FirstUnicastAddress;
+        while let Some(address) = unicast.as_ref() {
+            match socket_address_ip(&address.Address) {
+                Some(IpAddr::V4(ip)) => ipv4.push((ip, address.OnLinkPrefixLength)),
+                Some(IpAddr::V6(ip)) => ipv6.push((ip, address.OnLinkPrefixLength)),
+                None => {}
+            }
+            unicast = address.Next;
+        }
+
+        let mut gateways = Vec::new();
+        let mut gateway = adapter.FirstGatewayAddress;
+        while let Some(address) = gateway.as_ref() {
+            gateways.extend(socket_address_ip(&address.Address));
+            gateway = address.Next;
+        }
+        // IPv4 primeiro: é o que o dashboard mostra como gateway
+        gateways.sort_by_key(|ip: &IpAddr| ip.is_ipv6());
+
+        let mut dns_servers = Vec::new();
+        let mut dns = adapter.FirstDnsServerAddress;
+        while let Some(address) = dns.as_ref() {
+            dns_servers.extend(socket_address_ip(&address.Address));
+            dns = address.Next;
+        }
+
+        let mac_length = (adapter.PhysicalAddressLength as usize).min(adapter.PhysicalAddress.len());
+
+        let name = adapter.FriendlyName.to_string().unwrap_or_default();
+        let description = adapter.Description.to_string().unwrap_or_default();
+        // Ligações VPN do próprio Windows (RAS) aparecem como PPP
+        let kind = if adapter.IfType == IF_TYPE_PPP {
+            "vpn"
+        } else {
+            let has_hardware = matches!(adapter.IfType, IF_TYPE_ETHERNET_CSMACD | IF_TYPE_IEEE80211);
+            adapter_kind(&name, &description, has_hardware)
+        };
+
+        adapters.push(NetworkAdapter {
+            name,
+            description,
+            kind,
+            mac_address: win_decode::mac_address(&adapter.PhysicalAddress[..mac_length]),
+            ipv4,
+            ipv6,
+            gateways,
+            dns_servers,
+            dhcp_enabled: Some(adapter.Anonymous2.Flags & IP_ADAPTER_DHCP_ENABLED != 0),
+            speed_mbps: win_decode::link_speed_mbps(adapter.TransmitLinkSpeed),
+            is_wireless: adapter.IfType == IF_TYPE_IEEE80211,
+            is_up: adapter.OperStatus == IfOperStatusUp,
+        });
+    }
+    adapters
+}
+
+unsafe fn sockaddr_inet_ip(address: &SOCKADDR_INET) -> Option<IpAddr> {
+    let raw = std::slice::from_raw_parts(address as *const SOCKADDR_INET as *const u8, std::mem::size_of::<SOCKADDR_INET>());
+    win_decode::sockaddr_ip(raw)
+}
+
+/// Nome amigável da interface ("Ethernet", "Wi-Fi"), como em GetAdaptersAddresses
+unsafe fn interface_alias(luid: &NET_LUID_LH) -> Option<String> {
+    let mut alias = [0u16; 257]; // NDIS_IF_MAX_STRING_SIZE + 1
+    if ConvertInterfaceLuidToAlias(luid, &mut alias).is_err() {
+        return None;
+    }
+    let length = alias.iter().position(|c| *c == 0).unwrap_or(alias.len());
+    Some(String::from_utf16_lossy(&alias[..length]))
+}
+
+unsafe fn socket_address_ip(address: &SOCKET_ADDRESS) -> Option<IpAddr> {
+    if address.lpSockaddr.is_null() || address.iSockaddrLength <= 0 {
+        return None;
+    }
+    let raw = std::slice::from_raw_parts(address.lpSockaddr as *const u8, address.iSockaddrLength as usize);
+    win_decode::sockaddr_ip(raw)
+}
+
+/// Primeira interface Wi-Fi ligada: SSID e autenticação da ligação actual
+unsafe fn current_wifi_connection(client: HANDLE) -> Result<Option<WifiConnection>> {
+    let mut list: *mut WLAN_INTERFACE_INFO_LIST = std::ptr::null_mut();
+    let status = WlanEnumInterfaces(client, None, &mut list);
+    if status != 0 {
+        return Err(PlatformError::Api { api: "WlanEnumInterfaces", code: status });
+    }
+
+    let interfaces = std::slice::from_raw_parts((*list).InterfaceInfo.as_ptr(), (*list).dwNumberOfItems as usize);
+    let mut connection = None;
+
+    for interface in interfaces.iter().filter(|i| i.isState == wlan_interface_state_connected) {
+        let mut size = 0u32;
+        let mut data: *mut c_void = std::ptr::null_mut();
+        let status = WlanQueryInterface(
+            client,
+            &interface.InterfaceGuid,
+            wlan_intf_opcode_current_connection,
+            None,
+            &mut size,
+            &mut data,
+            None,
+        );
+        if status != 0 || data.is_null() {
+            continue;
+        }
+
+        let attributes = &*(data as *const WLAN_CONNECTION_ATTRIBUTES);
+        let ssid = &attributes.wlanAssociationAttributes.dot11Ssid;
+        connection = Some(WifiConnection {
+            ssid: win_decode::ssid(&ssid.ucSSID, ssid.uSSIDLength),
+            security: win_decode::wifi_auth_name(attributes.wlanSecurityAttributes.dot11AuthAlgorithm.0).to_string(),
+        });
+        WlanFreeMemory(data);
+        break;
+    }
+
+    WlanFreeMemory(list as *const c_void);
+    Ok(connection)
+}
+
+/// Chama a API duas vezes: a primeira só para saber o tamanho da tabela
+fn extended_table(
+    api: &'static str,
+    family: u32,
+    call: impl Fn(*mut std::ffi::c_void, *mut u32, u32) -> u32,
+) -> Result<Vec<u64>> {
+    let mut size = 0u32;
+    let mut buffer: Vec<u64> = Vec::new();
+
+    for _ in 0..3 {
+        let status = call(buffer.as_mut_ptr() as *mut _, &mut size, family);
+        if status == NO_ERROR.0 {
+            return Ok(buffer);
+        }
+        if status != ERROR_INSUFFICIENT_BUFFER.0 {
+            return Err(PlatformError::Api { api, code: status });
+        }
+        // u64 para o alinhamento das estruturas; a tabela pode crescer entre chamadas
+        buffer = vec![0u64; (size as usize).div_ceil(8) + 1];
+    }
+    // A tabela continua a crescer entre chamadas
+    Err(PlatformError::Api { api, code: ERROR_INSUFFICIENT_BUFFER.0 })
+}
+
+/// Vista das linhas de uma tabela MIB_*TABLE_OWNER_PID (dwNumEntries seguido das linhas)
+unsafe fn rows<Table, Row>(buffer: &[u64]) -> &[Row] {
+    if buffer.is_empty() {
+        return &[];
+    }
+    let count = *(buffer.as_ptr() as *const u32) as usize;
+    let offset = std::mem::size_of::<Table>() - std::mem::size_of::<Row>();
+    let first = (buffer.as_ptr() as *const u8).add(offset) as *const Row;
+    std::slice::from_raw_parts(first, count)
+}
+
+/// As portas vêm em ordem de rede nos 16 bits baixos
+fn port(raw: u32) -> u16 {
+    u16::from_be(raw as u16)
+}
+
+fn tcp_socket(local: IpAddr, local_port: u32, remote: IpAddr, remote_port: u32, state: u32, pid: u32) -> Socket {
+    let remote_port = port(remote_port);
+    Socket {
+        protocol: "tcp",
+        local_addr: local,
+        local_port: port(local_port),
+        remote_addr: (!remote.is_unspecified() || remote_port != 0).then_some(remote),
+        remote_port,
+        state: tcp_state(state).to_string(),
+        pid: (pid != 0).then_some(pid),
+    }
+}
+
+fn udp_socket(local: IpAddr, local_port: u32, pid: u32) -> Socket {
+    Socket {
+        protocol: "udp",
+        local_addr: local,
+        local_port: port(local_port),
+        remote_addr: None,
+        remote_port: 0,
+        state: "OPEN".to_string(),
+        pid: (pid != 0).then_some(pid),
+    }
+}
+
+/// MIB_TCP_STATE
+fn tcp_state(state: u32) -> &'static str {
+    match state {
+        1 => "CLOSE",
+        2 => "LISTEN",
+        3 => "SYN_SENT",
+        4 => "SYN_RECV",
+        5 => "ESTABLISHED",
+        6 => "FIN_WAIT1",
+        7 => "FIN_WAIT2",
+        8 => "CLOSE_WAIT",
+        9 => "CLOSING",
+        10 => "LAST_ACK",
+        11 => "TIME_WAIT",
+        12 => "DELETE_TCB",
+        _ => "UNKNOWN",
+    }
+}
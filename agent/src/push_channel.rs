@@ -0,0 +1,158 @@
+// ============================================================
+// push_channel.rs — Ligação persistente ao dashboard
+// Long-poll em /api/v2/agent/events: o servidor responde assim
+// que há uma política nova, um comando na fila ou um pedido de
+// recolha. Se falhar, volta o polling periódico dos comandos.
+// Políticas novas vêm de /api/v2/policies, sem recolha completa.
+// ============================================================
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::config::{self, Config};
+use crate::enforcer::Policy;
+use crate::remote_commands;
+
+// Tempo que o servidor segura o pedido sem eventos
+const LONG_POLL_SECS: u64 = 50;
+const MIN_BACKOFF_SECS: u64 = 5;
+const MAX_BACKOFF_SECS: u64 = 300;
+// Uma política global acorda todos os agentes ao mesmo tempo: cada um espera
+// um tempo aleatório até este limite antes de a ir buscar
+const POLICY_FETCH_SPREAD_SECS: u64 = 30;
+
+/// Verdadeiro enquanto o long-poll estiver a responder
+static PUSH_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Deserialize, Debug)]
+struct EventsResponse {
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+pub fn is_connected() -> bool {
+    PUSH_CONNECTED.load(Ordering::Relaxed)
+}
+
+/// Mantém o long-poll aberto para sempre, religando com backoff e jitter
+pub async fn run(config: Config, machine_id: String) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(LONG_POLL_SECS + 20))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("[Push] Erro ao criar cliente HTTP: {}", e);
+            return;
+        }
+    };
+
+    // Espalha as ligações quando muitos agentes arrancam ao mesmo tempo
+    tokio::time::sleep(jitter(MIN_BACKOFF_SECS)).await;
+
+    let mut failures: u32 = 0;
+    loop {
+        match wait_events(&client, &config, &machine_id).await {
+            Ok(events) => {
+                if failures > 0 || !is_connected() {
+                    println!("[Push] Ligado ao servidor");
+                }
+                failures = 0;
+                PUSH_CONNECTED.store(true, Ordering::Relaxed);
+                handle_events(&events, &config, &machine_id).await;
+            }
+            Err(e) => {
+                PUSH_CONNECTED.store(false, Ordering::Relaxed);
+                failures = failures.saturating_add(1);
+                let delay = backoff(failures);
+                eprintln!("[Push] {} — nova tentativa em {}s", e, delay.as_secs());
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+async fn wait_events(client: &reqwest::Client, config: &Config, machine_id: &str) -> Result<Vec<String>> {
+    let url = format!("{}/api/v2/agent/events", config.server_url);
+    let wait = LONG_POLL_SECS.to_string();
+
+    let request = client.get(&url).header("X-API-Key", &config.api_key);
+    let response = config::with_agent_token(request)
+        .query(&[("machine_id", machine_id), ("wait", wait.as_str())])
+        .send()
+        .await
+        .context("Falha ao contactar servidor")?;
+
+    if !response.status().is_success() {
+        // 404: servidor antigo sem long-poll, fica o polling periódico
+        bail!("Servidor erro: {}", response.status());
+    }
+
+    let body: EventsResponse = response.json().await?;
+    Ok(body.events)
+}
+
+async fn handle_events(events: &[String], config: &Config, machine_id: &str) {
+    for event in events {
+        match event.as_str() {
+            "policy_changed" => {
+                let config = config.clone();
+                let machine_id = machine_id.to_string();
+                tokio::spawn(async move {
+                    tokio::time::sleep(jitter(POLICY_FETCH_SPREAD_SECS)).await;
+                    match fetch_policies(&config, &machine_id).await {
+                        Ok(policies) => {
+                            println!("[Push] ✓ {} políticas actualizadas", policies.len());
+                            crate::apply_policies(&config, policies);
+                        }
+                        Err(e) => {
+                            // Servidor sem o endpoint: as políticas vêm com o relatório
+                            eprintln!("[Push] {}; a antecipar o ciclo", e);
+                            remote_commands::COLLECT_NOW.notify_one();
+                        }
+                    }
+                });
+            }
+            "collect_now" => remote_commands::COLLECT_NOW.notify_one(),
+            "command_queued" => {
+                if let Err(e) = remote_commands::poll(config, machine_id).await {
+                    eprintln!("[Push] Erro ao obter comandos: {}", e);
+                }
+            }
+            other => println!("[Push] Evento desconhecido ignorado: {}", other),
+        }
+    }
+}
+
+/// Só as políticas da máquina, sem recolha nem relatório
+async fn fetch_policies(config: &Config, machine_id: &str) -> Result<Vec<Policy>> {
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+    let url = format!("{}/api/v2/policies", config.server_url);
+
+    let request = client.get(&url).header("X-API-Key", &config.api_key);
+    let response = config::with_agent_token(request)
+        .query(&[("machine_id", machine_id)])
+        .send()
+        .await
+        .context("Falha ao obter políticas")?;
+
+    if !response.status().is_success() {
+        bail!("Servidor erro ao obter políticas: {}", response.status());
+    }
+    Ok(response.json().await?)
+}
+
+/// Backoff exponencial (5s, 10s, 20s... até 5min); metade do valor é aleatória
+fn backoff(failures: u32) -> Duration {
+    let exp = failures.saturating_sub(1).min(16);
+    let base = (MIN_BACKOFF_SECS << exp).min(MAX_BACKOFF_SECS);
+    Duration::from_secs(base / 2) + jitter(base / 2 + 1)
+}
+
+fn jitter(max_secs: u64) -> Duration {
+    let max_ms = max_secs.max(1) * 1000;
+    let random = uuid::Uuid::new_v4().as_u128() as u64;
+    Duration::from_millis(random % max_ms)
+}
@@ -0,0 +1,514 @@
+// ============================================================
+// remote_commands.rs — Comandos enviados pelo dashboard
+// Chegam na resposta ao relatório ou pelo pedido periódico a
+// /api/v2/commands; cada resultado é devolvido ao servidor
+// ============================================================
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tokio::sync::Notify;
+
+use crate::config::{self, Config};
+use crate::screenshot;
+
+// Saída guardada por comando (o resto é cortado)
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+const SCRIPT_TIMEOUT_SECS: u64 = 300;
+const UNINSTALL_TIMEOUT_SECS: u64 = 900;
+// Limite de IDs lembrados, mesmo que o servidor envie prazos longos
+const MAX_SEEN_COMMANDS: usize = 1000;
+
+// Scripts sem interpretador indicado (dashboards anteriores ao suporte a Linux)
+#[cfg(windows)]
+const DEFAULT_INTERPRETER: &str = "powershell";
+#[cfg(not(windows))]
+const DEFAULT_INTERPRETER: &str = "sh";
+
+lazy_static::lazy_static! {
+    // Comandos já aceites, até ao prazo de cada um: o servidor pode voltar a enviá-los
+    // se o resultado se perder (depois do prazo já não são executados)
+    static ref SEEN_COMMANDS: Mutex<HashMap<String, DateTime<Utc>>> = Mutex::new(HashMap::new());
+    // Resultados ainda não confirmados pelo servidor
+    static ref PENDING_RESULTS: Mutex<Vec<CommandResult>> = Mutex::new(Vec::new());
+    /// Acorda o ciclo principal para uma recolha imediata
+    pub static ref COLLECT_NOW: Notify = Notify::new();
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AgentCommand {
+    pub id: String,
+    pub command_type: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    pub expires_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommandResult {
+    pub command_id: String,
+    pub machine_id: String,
+    pub status: String, // "succeeded" | "failed" | "expired"
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub completed_at: String,
+}
+
+/// Executa em background os comandos ainda não vistos e envia os resultados
+pub fn dispatch(commands: Vec<AgentCommand>, config: &Config, machine_id: &str) {
+    for command in commands {
+        if !remember_command(&command) {
+            continue;
+        }
+
+        let config = config.clone();
+        let machine_id = machine_id.to_string();
+        tokio::spawn(async move {
+            println!("[Commands] A executar {} ({})", command.command_type, command.id);
+            let result = run(&command, &config, &machine_id).await;
+            println!("[Commands] {} terminou: {}", command.id, result.status);
+
+            PENDING_RESULTS.lock().unwrap().push(result);
+            if let Err(e) = flush_results(&config).await {
+                eprintln!("[Commands] Resultado fica para o próximo envio: {}", e);
+            }
+        });
+    }
+}
+
+/// Regista o comando; devolve `false` se já tinha sido aceite
+fn remember_command(command: &AgentCommand) -> bool {
+    let now = Utc::now();
+    let expires_at = DateTime::parse_from_rfc3339(&command.expires_at)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now);
+
+    let mut seen = SEEN_COMMANDS.lock().unwrap();
+    seen.retain(|_, expires| *expires >= now);
+    if seen.contains_key(&command.id) {
+        return false;
+    }
+    if seen.len() >= MAX_SEEN_COMMANDS {
+        if let Some(oldest) = seen.iter().min_by_key(|(_, expires)| **expires).map(|(id, _)| id.clone()) {
+            seen.remove(&oldest);
+        }
+    }
+    seen.insert(command.id.clone(), expires_at);
+    true
+}
+
+async fn run(command: &AgentCommand, config: &Config, machine_id: &str) -> CommandResult {
+    let expired = DateTime::parse_from_rfc3339(&command.expires_at)
+        .map(|t| t.with_timezone(&Utc) < Utc::now())
+        .unwrap_or(false);
+
+    let (status, output, error) = if expired {
+        ("expired", None, Some("Comando expirou antes de ser executado".to_string()))
+    } else {
+        match execute(command, config, machine_id).await {
+            Ok(output) => ("succeeded", Some(truncate(output)), None),
+            Err(e) => ("failed", None, Some(format!("{:#}", e))),
+        }
+    };
+
+    CommandResult {
+        command_id: command.id.clone(),
+        machine_id: machine_id.to_string(),
+        status: status.to_string(),
+        output,
+        error,
+        completed_at: Utc::now().to_rfc3339(),
+    }
+}
+
+async fn execute(command: &AgentCommand, config: &Config, machine_id: &str) -> Result<String> {
+    let payload = &command.payload;
+
+    match command.command_type.as_str() {
+        "kill_process" => kill_process(payload),
+        "restart_service" => restart_service(payload).await,
+        "collect_now" => {
+            COLLECT_NOW.notify_one();
+            Ok("Recolha agendada".to_string())
+        }
+        "run_script" => run_script(&command.id, payload, config).await,
+        "screenshot" => screenshot::capture_and_upload(config, machine_id, &command.id).await,
+        "uninstall_app" => uninstall_app(payload).await,
+        other => bail!("Tipo de comando desconhecido: {}", other),
+    }
+}
+
+fn payload_str<'a>(payload: &'a serde_json::Value, field: &str) -> Result<&'a str> {
+    payload
+        .get(field)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+        .with_context(|| format!("Campo '{}' em falta", field))
+}
+
+// -------------------------------------------------
+// Tipos de comando
+// -------------------------------------------------
+
+/// Termina um processo por PID; com `process_name`, confirma que o PID ainda é o mesmo processo.
+/// Só com `process_name`, termina todos os processos com esse nome
+fn kill_process(payload: &serde_json::Value) -> Result<String> {
+    let pid = payload.get("pid").and_then(|v| v.as_u64());
+    let name = payload.get("process_name").and_then(|v| v.as_str()).map(|n| n.to_lowercase());
+
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All);
+
+    let targets: Vec<(Pid, &sysinfo::Process)> = match (pid, &name) {
+        (Some(pid), _) => {
+            let pid = Pid::from_u32(pid as u32);
+            let process = sys.process(pid).with_context(|| format!("PID {} não existe", pid))?;
+            let actual = process.name().to_string_lossy().to_lowercase();
+            if let Some(expected) = &name {
+                if &actual != expected {
+                    bail!("PID {} é agora {}, não {}", pid, actual, expected);
+                }
+            }
+            vec![(pid, process)]
+        }
+        (None, Some(name)) => sys
+            .processes()
+            .iter()
+            .filter(|(_, p)| p.name().to_string_lossy().to_lowercase() == *name)
+            .map(|(pid, p)| (*pid, p))
+            .collect(),
+        (None, None) => bail!("Indique 'pid' ou 'process_name'"),
+    };
+
+    if targets.is_empty() {
+        bail!("Nenhum processo encontrado");
+    }
+
+    let mut killed = 0;
+    for (pid, process) in &targets {
+        if process.kill() {
+            killed += 1;
+        } else {
+            bail!("Falha ao terminar PID {}", pid);
+        }
+    }
+    Ok(format!("{} processo(s) terminado(s)", killed))
+}
+
+async fn restart_service(payload: &serde_json::Value) -> Result<String> {
+    let service = payload_str(payload, "service_name")?;
+    // O nome vai dentro de aspas simples no PowerShell
+    if !service.chars().all(|c| c.is_ascii_alphanumeric() || "-_. $".contains(c)) {
+        bail!("Nome de serviço inválido: {}", service);
+    }
+
+    #[cfg(windows)]
+    {
+        let script = format!("Restart-Service -Name '{}' -Force -ErrorAction Stop", service);
+        run_process(
+            "powershell",
+            &["-NoProfile", "-NonInteractive", "-Command", &script],
+            Duration::from_secs(120),
+        )
+        .await?;
+    }
+    #[cfg(not(windows))]
+    run_process("systemctl", &["restart", "--", service], Duration::from_secs(120)).await?;
+
+    Ok(format!("Serviço {} reiniciado", service))
+}
+
+/// Scripts aprovados no dashboard: só corre o que vier assinado pela chave do servidor
+async fn run_script(command_id: &str, payload: &serde_json::Value, config: &Config) -> Result<String> {
+    let script_id = payload_str(payload, "script_id")?;
+    let content = payload_str(payload, "content")?;
+    let signature = payload_str(payload, "signature")?;
+    let interpreter = payload.get("interpreter").and_then(|v| v.as_str()).unwrap_or(DEFAULT_INTERPRETER);
+
+    let public_key = script_public_key(config).await?;
+    verify_script(&public_key, script_id, interpreter, content, signature)?;
+
+    let dir = config::data_dir().join("scripts");
+    std::fs::create_dir_all(&dir)?;
+
+    let (path, program, args): (_, &str, Vec<String>) = match interpreter {
+        "powershell" => {
+            let path = dir.join(format!("{}.ps1", command_id));
+            let args = ["-NoProfile", "-NonInteractive", "-ExecutionPolicy", "Bypass", "-File"]
+                .iter()
+                .map(|s| s.to_string())
+                .chain([path.to_string_lossy().to_string()])
+                .collect();
+            (path, "powershell", args)
+        }
+        "cmd" => {
+            let path = dir.join(format!("{}.cmd", command_id));
+            let args = vec!["/C".to_string(), path.to_string_lossy().to_string()];
+            (path, "cmd", args)
+        }
+        "sh" => {
+            let path = dir.join(format!("{}.sh", command_id));
+            let args = vec![path.to_string_lossy().to_string()];
+            (path, "sh", args)
+        }
+        other => bail!("Interpretador não suportado: {}", other),
+    };
+
+    std::fs::write(&path, content).context("Falha ao gravar script")?;
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let result = run_process(program, &args, Duration::from_secs(SCRIPT_TIMEOUT_SECS)).await;
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Valida a assinatura Ed25519 feita pelo servidor sobre o ID, o interpretador e o hash do conteúdo
+fn verify_script(public_key: &[u8], script_id: &str, interpreter: &str, content: &str, signature: &str) -> Result<()> {
+    let signature = hex::decode(signature.trim()).context("Assinatura do script mal formada")?;
+    let sha256 = hex::encode(Sha256::digest(content.as_bytes()));
+    let message = format!("assetscan-script\n{}\n{}\n{}", script_id, interpreter, sha256);
+
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(message.as_bytes(), &signature)
+        .map_err(|_| anyhow!("Assinatura do script inválida"))
+}
+
+/// Chave de assinatura dos scripts: a configurada ou, sem ela, a que o servidor
+/// apresentou no primeiro script (fica fixada em script_key.pub)
+async fn script_public_key(config: &Config) -> Result<Vec<u8>> {
+    if let Some(key) = &config.script_public_key {
+        return parse_public_key(key).context("script_public_key inválida");
+    }
+
+    let path = config::data_dir().join("script_key.pub");
+    if let Ok(key) = std::fs::read_to_string(&path) {
+        return parse_public_key(&key).with_context(|| format!("{:?} inválido", path));
+    }
+
+    #[derive(Deserialize)]
+    struct ScriptKey {
+        public_key: String,
+    }
+
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+    let response = client
+        .get(format!("{}/api/v2/script-key", config.server_url))
+        .header("X-API-Key", &config.api_key)
+        .send()
+        .await
+        .context("Falha ao obter a chave de scripts do servidor")?;
+    if !response.status().is_success() {
+        bail!("Servidor erro: {}", response.status());
+    }
+
+    let key: ScriptKey = response.json().await?;
+    let bytes = parse_public_key(&key.public_key).context("Chave de scripts do servidor inválida")?;
+    std::fs::write(&path, &key.public_key).context("Falha ao gravar a chave de scripts")?;
+    println!("[Commands] Chave de scripts do servidor fixada: {}", key.public_key);
+    Ok(bytes)
+}
+
+fn parse_public_key(key: &str) -> Result<Vec<u8>> {
+    let bytes = hex::decode(key.trim())?;
+    if bytes.len() != 32 {
+        bail!("Chave Ed25519 com {} bytes", bytes.len());
+    }
+    Ok(bytes)
+}
+
+/// Desinstala pelo nome apresentado em "Programas e Funcionalidades" (apenas em modo silencioso)
+async fn uninstall_app(payload: &serde_json::Value) -> Result<String> {
+    let name = payload_str(payload, "name")?;
+    let command = quiet_uninstall_command(name)
+        .with_context(|| format!("{} não tem desinstalação silenciosa disponível", name))?;
+
+    run_process("cmd", &["/C", &command], Duration::from_secs(UNINSTALL_TIMEOUT_SECS)).await?;
+    Ok(format!("{} desinstalado", name))
+}
+
+#[cfg(windows)]
+fn quiet_uninstall_command(name: &str) -> Option<String> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let paths = [
+        r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+        r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+    ];
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+
+    for path in paths {
+        let Ok(key) = hklm.open_subkey(path) else { continue };
+        for subkey_name in key.enum_keys().flatten() {
+            let Ok(sub) = key.open_subkey(&subkey_name) else { continue };
+            let display: String = sub.get_value("DisplayName").unwrap_or_default();
+            if !display.eq_ignore_ascii_case(name) {
+                continue;
+            }
+
+            if let Ok(quiet) = sub.get_value::<String, _>("QuietUninstallString") {
+                return Some(quiet);
+            }
+
+            // Instalações MSI: o código de produto é o nome da subchave
+            let uninstall: String = sub.get_value("UninstallString").unwrap_or_default();
+            if uninstall.to_lowercase().contains("msiexec") && subkey_name.starts_with('{') {
+                return Some(format!("msiexec /x {} /qn /norestart", subkey_name));
+            }
+        }
+    }
+    None
+}
+
+// Pacotes Linux são removidos pelo gestor de pacotes, não por comandos do dashboard
+#[cfg(not(windows))]
+fn quiet_uninstall_command(_name: &str) -> Option<String> {
+    None
+}
+
+async fn run_process(program: &str, args: &[&str], timeout: Duration) -> Result<String> {
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output();
+
+    let output = tokio::time::timeout(timeout, child)
+        .await
+        .with_context(|| format!("Tempo limite de {}s excedido", timeout.as_secs()))?
+        .with_context(|| format!("Falha ao executar {}", program))?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        text.push('\n');
+        text.push_str(&stderr);
+    }
+
+    if !output.status.success() {
+        bail!("Código de saída {}: {}", output.status.code().unwrap_or(-1), truncate(text));
+    }
+    Ok(text)
+}
+
+fn truncate(mut text: String) -> String {
+    if text.len() > MAX_OUTPUT_BYTES {
+        let mut cut = MAX_OUTPUT_BYTES;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+        text.push_str("\n[...]");
+    }
+    text
+}
+
+// -------------------------------------------------
+// Comunicação com o servidor
+// -------------------------------------------------
+
+/// Pede ao servidor os comandos pendentes desta máquina (entre relatórios)
+pub async fn poll(config: &Config, machine_id: &str) -> Result<()> {
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+    let url = format!("{}/api/v2/commands", config.server_url);
+
+    let request = client.get(&url).header("X-API-Key", &config.api_key);
+    let response = config::with_agent_token(request)
+        .query(&[("machine_id", machine_id)])
+        .send()
+        .await
+        .context("Falha ao contactar servidor")?;
+
+    if !response.status().is_success() {
+        bail!("Servidor erro: {}", response.status());
+    }
+
+    let commands: Vec<AgentCommand> = response.json().await?;
+    dispatch(commands, config, machine_id);
+    flush_results(config).await
+}
+
+/// Envia os resultados pendentes; os que falharem voltam a ser enviados mais tarde
+pub async fn flush_results(config: &Config) -> Result<()> {
+    let results: Vec<CommandResult> = PENDING_RESULTS.lock().unwrap().clone();
+    if results.is_empty() {
+        return Ok(());
+    }
+
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+    let url = format!("{}/api/v2/commands/result", config.server_url);
+
+    let request = client.post(&url).header("X-API-Key", &config.api_key);
+    let response = config::with_agent_token(request)
+        .json(&results)
+        .send()
+        .await
+        .context("Falha ao enviar resultados")?;
+
+    if !response.status().is_success() {
+        bail!("Servidor erro: {}", response.status());
+    }
+
+    let sent: HashSet<&str> = results.iter().map(|r| r.command_id.as_str()).collect();
+    PENDING_RESULTS.lock().unwrap().retain(|r| !sent.contains(r.command_id.as_str()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn sign(key: &Ed25519KeyPair, script_id: &str, interpreter: &str, content: &str) -> String {
+        let sha256 = hex::encode(Sha256::digest(content.as_bytes()));
+        let message = format!("assetscan-script\n{}\n{}\n{}", script_id, interpreter, sha256);
+        hex::encode(key.sign(message.as_bytes()).as_ref())
+    }
+
+    #[test]
+    fn verifies_server_signature() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public_key = key.public_key().as_ref().to_vec();
+        let signature = sign(&key, "s1", "sh", "echo ok");
+
+        assert!(verify_script(&public_key, "s1", "sh", "echo ok", &signature).is_ok());
+        // Conteúdo, interpretador ou script trocados não passam
+        assert!(verify_script(&public_key, "s1", "sh", "rm -rf /", &signature).is_err());
+        assert!(verify_script(&public_key, "s1", "powershell", "echo ok", &signature).is_err());
+        assert!(verify_script(&public_key, "s2", "sh", "echo ok", &signature).is_err());
+
+        // Assinado por outra chave
+        let other = Ed25519KeyPair::from_pkcs8(
+            Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap().as_ref(),
+        )
+        .unwrap();
+        let forged = sign(&other, "s1", "sh", "echo ok");
+        assert!(verify_script(&public_key, "s1", "sh", "echo ok", &forged).is_err());
+    }
+
+    #[test]
+    fn seen_commands_expire() {
+        let command = |id: &str, expires_at: DateTime<Utc>| AgentCommand {
+            id: id.to_string(),
+            command_type: "collect_now".to_string(),
+            payload: serde_json::json!({}),
+            expires_at: expires_at.to_rfc3339(),
+        };
+        let later = Utc::now() + chrono::Duration::minutes(5);
+
+        assert!(remember_command(&command("seen-a", later)));
+        assert!(!remember_command(&command("seen-a", later)));
+
+        // Já expirado: é esquecido na próxima passagem
+        let past = Utc::now() - chrono::Duration::minutes(5);
+        assert!(remember_command(&command("seen-b", past)));
+        remember_command(&command("seen-c", later));
+        assert!(!SEEN_COMMANDS.lock().unwrap().contains_key("seen-b"));
+    }
+}
@@ -0,0 +1,321 @@
+// ============================================================
+// schedule.rs — Horários e quotas diárias das políticas
+// Avaliado localmente para continuar a funcionar offline
+// ============================================================
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    // Configurações inválidas já registadas (o enforcer avalia-as a cada passagem)
+    static ref REPORTED_INVALID: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Campo "schedule" do config_json: predefinição ("always", "work_hours")
+/// ou um horário detalhado
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Schedule {
+    Preset(String),
+    Custom(CustomSchedule),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CustomSchedule {
+    /// Fuso horário IANA (ex: "Europe/Lisbon"); por omissão, a hora local
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub windows: Vec<TimeWindow>,
+    /// Dias em que a política não se aplica: "YYYY-MM-DD" ou "MM-DD" (anual)
+    #[serde(default)]
+    pub holidays: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimeWindow {
+    /// "mon".."sun"; vazio significa todos os dias
+    #[serde(default)]
+    pub days: Vec<String>,
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM" (pode passar a meia-noite, ex: 22:00-06:00)
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ScheduleConfig {
+    schedule: Option<Schedule>,
+    daily_quota_minutes: Option<u64>,
+}
+
+/// Uma configuração inválida não desactiva a política (fica sempre activa),
+/// mas fica registada para não passar despercebida
+fn parse_config(config_json: &str) -> ScheduleConfig {
+    if config_json.trim().is_empty() {
+        return ScheduleConfig::default();
+    }
+    match serde_json::from_str::<ScheduleConfig>(config_json) {
+        Ok(config) => {
+            if let Some(Err(e)) = config.schedule.as_ref().map(Schedule::validate) {
+                report_invalid(config_json, &e);
+            }
+            config
+        }
+        Err(e) => {
+            report_invalid(config_json, &e.to_string());
+            ScheduleConfig::default()
+        }
+    }
+}
+
+fn report_invalid(config_json: &str, error: &str) {
+    if REPORTED_INVALID.lock().unwrap().insert(config_json.to_string()) {
+        eprintln!("[Schedule] Horário inválido ({}), política sempre activa: {}", error, config_json);
+    }
+}
+
+/// Quota diária em segundos, se a política a tiver
+pub fn daily_quota_seconds(config_json: &str) -> Option<u64> {
+    parse_config(config_json).daily_quota_minutes.map(|m| m * 60)
+}
+
+/// Indica se a política está dentro do seu horário no instante `now`
+pub fn is_active(config_json: &str, now: DateTime<Utc>) -> bool {
+    match parse_config(config_json).schedule {
+        None => true,
+        Some(Schedule::Preset(preset)) => match preset.as_str() {
+            "work_hours" => {
+                let local = now.with_timezone(&chrono::Local).naive_local();
+                is_weekday(local.weekday()) && in_range(local.time(), "09:00", "18:00")
+            }
+            _ => true, // "always" ou valores desconhecidos
+        },
+        Some(Schedule::Custom(custom)) => custom.is_active(now),
+    }
+}
+
+impl Schedule {
+    /// Erros que fariam o horário ser ignorado ou nunca coincidir
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Schedule::Preset(preset) => match preset.as_str() {
+                "always" | "work_hours" => Ok(()),
+                other => Err(format!("predefinição desconhecida '{}'", other)),
+            },
+            Schedule::Custom(custom) => custom.validate(),
+        }
+    }
+}
+
+impl CustomSchedule {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(tz) = &self.timezone {
+            tz.parse::<Tz>().map_err(|_| format!("fuso horário desconhecido '{}'", tz))?;
+        }
+        for w in &self.windows {
+            for time in [&w.start, &w.end] {
+                NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                    .map_err(|_| format!("hora inválida '{}' (esperado HH:MM)", time))?;
+            }
+            if let Some(day) = w.days.iter().find(|d| parse_weekday(d).is_none()) {
+                return Err(format!("dia da semana desconhecido '{}'", day));
+            }
+        }
+        if let Some(h) = self.holidays.iter().find(|h| !is_holiday_date(h)) {
+            return Err(format!("feriado inválido '{}' (esperado AAAA-MM-DD ou MM-DD)", h));
+        }
+        Ok(())
+    }
+
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        let local = match self.timezone.as_deref().and_then(|tz| tz.parse::<Tz>().ok()) {
+            Some(tz) => tz.from_utc_datetime(&now.naive_utc()).naive_local(),
+            None => now.with_timezone(&chrono::Local).naive_local(),
+        };
+
+        if self.windows.is_empty() {
+            return !self.is_holiday(local.date());
+        }
+
+        // O feriado conta para o dia em que a janela começou: "22:00-06:00" na
+        // véspera de um feriado fica activa até às 06:00 desse dia
+        self.windows
+            .iter()
+            .filter_map(|w| w.start_date(local))
+            .any(|start| !self.is_holiday(start))
+    }
+
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        let full = date.format("%Y-%m-%d").to_string();
+        let yearly = date.format("%m-%d").to_string();
+        self.holidays.iter().any(|h| h.trim() == full || h.trim() == yearly)
+    }
+}
+
+impl TimeWindow {
+    /// Data em que começou a janela que contém `local`, se os dias coincidirem.
+    /// Os dias referem-se ao início da janela: "sex 22:00-06:00" inclui sábado às 02:00
+    fn start_date(&self, local: NaiveDateTime) -> Option<NaiveDate> {
+        let start = window_start_date(local, &self.start, &self.end)?;
+        let start_day = start.weekday();
+        (self.days.is_empty() || self.days.iter().any(|d| parse_weekday(d) == Some(start_day))).then_some(start)
+    }
+}
+
+/// Data em que começou a janela que contém `local`; `None` se estiver fora dela
+fn window_start_date(local: NaiveDateTime, start: &str, end: &str) -> Option<NaiveDate> {
+    let (Ok(start), Ok(end)) = (
+        NaiveTime::parse_from_str(start.trim(), "%H:%M"),
+        NaiveTime::parse_from_str(end.trim(), "%H:%M"),
+    ) else {
+        return None;
+    };
+
+    let (date, time) = (local.date(), local.time());
+    if start <= end {
+        (time >= start && time < end).then_some(date)
+    } else if time >= start {
+        Some(date)
+    } else if time < end {
+        // Parte depois da meia-noite: a janela começou na véspera
+        date.checked_sub_days(Days::new(1))
+    } else {
+        None
+    }
+}
+
+fn is_holiday_date(holiday: &str) -> bool {
+    let holiday = holiday.trim();
+    NaiveDate::parse_from_str(holiday, "%Y-%m-%d").is_ok()
+        // Ano bissexto para aceitar "02-29"
+        || NaiveDate::parse_from_str(&format!("2000-{}", holiday), "%Y-%m-%d").is_ok()
+}
+
+fn is_weekday(day: Weekday) -> bool {
+    !matches!(day, Weekday::Sat | Weekday::Sun)
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    let prefix: String = day.trim().to_lowercase().chars().take(3).collect();
+    match prefix.as_str() {
+        "mon" | "seg" => Some(Weekday::Mon),
+        "tue" | "ter" => Some(Weekday::Tue),
+        "wed" | "qua" => Some(Weekday::Wed),
+        "thu" | "qui" => Some(Weekday::Thu),
+        "fri" | "sex" => Some(Weekday::Fri),
+        "sat" | "sáb" | "sab" => Some(Weekday::Sat),
+        "sun" | "dom" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn in_range(time: NaiveTime, start: &str, end: &str) -> bool {
+    // O dia é indiferente aqui: só interessa se a hora cai na janela
+    window_start_date(NaiveDate::default().and_time(time), start, end).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    // 2026-10-16 é uma sexta-feira
+    fn on(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_time(at(h, m))
+    }
+
+    fn window(days: &[&str], start: &str, end: &str) -> TimeWindow {
+        TimeWindow {
+            days: days.iter().map(|d| d.to_string()).collect(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn ranges_within_a_day() {
+        assert!(in_range(at(9, 0), "09:00", "18:00"));
+        assert!(in_range(at(17, 59), "09:00", "18:00"));
+        assert!(!in_range(at(18, 0), "09:00", "18:00"));
+        assert!(!in_range(at(8, 59), "09:00", "18:00"));
+        assert!(!in_range(at(12, 0), "9h", "18:00"));
+    }
+
+    #[test]
+    fn ranges_across_midnight() {
+        assert!(in_range(at(23, 0), "22:00", "06:00"));
+        assert!(in_range(at(2, 0), "22:00", "06:00"));
+        assert!(!in_range(at(6, 0), "22:00", "06:00"));
+        assert!(!in_range(at(12, 0), "22:00", "06:00"));
+    }
+
+    #[test]
+    fn overnight_window_uses_start_day() {
+        let friday_night = window(&["fri"], "22:00", "06:00");
+        let friday = NaiveDate::from_ymd_opt(2026, 10, 16);
+        assert_eq!(friday_night.start_date(on(16, 23, 0)), friday);
+        assert_eq!(friday_night.start_date(on(17, 2, 0)), friday);
+        assert_eq!(friday_night.start_date(on(16, 2, 0)), None); // Pertence à janela de quinta
+        assert_eq!(friday_night.start_date(on(17, 23, 0)), None);
+
+        let sunday_night = window(&["dom"], "22:00", "06:00");
+        assert!(sunday_night.start_date(on(19, 5, 59)).is_some());
+    }
+
+    #[test]
+    fn days_filter_and_weekday_names() {
+        let weekdays = window(&["seg", "ter", "qua", "qui", "sex"], "09:00", "18:00");
+        assert!(weekdays.start_date(on(14, 10, 0)).is_some());
+        assert!(weekdays.start_date(on(17, 10, 0)).is_none());
+        assert!(window(&[], "09:00", "18:00").start_date(on(18, 10, 0)).is_some());
+
+        assert_eq!(parse_weekday("Sábado"), Some(Weekday::Sat));
+        assert_eq!(parse_weekday("monday"), Some(Weekday::Mon));
+        assert_eq!(parse_weekday("x"), None);
+    }
+
+    #[test]
+    fn holidays_full_and_yearly() {
+        let schedule = CustomSchedule {
+            timezone: None,
+            windows: vec![],
+            holidays: vec!["2026-04-03".to_string(), "12-25".to_string()],
+        };
+        assert!(schedule.is_holiday(NaiveDate::from_ymd_opt(2026, 4, 3).unwrap()));
+        assert!(schedule.is_holiday(NaiveDate::from_ymd_opt(2031, 12, 25).unwrap()));
+        assert!(!schedule.is_holiday(NaiveDate::from_ymd_opt(2027, 4, 3).unwrap()));
+    }
+
+    #[test]
+    fn holidays_apply_to_the_window_start_day() {
+        let schedule = CustomSchedule {
+            timezone: Some("UTC".to_string()),
+            windows: vec![window(&[], "22:00", "06:00")],
+            holidays: vec!["2026-10-17".to_string()],
+        };
+        let utc = |dt: NaiveDateTime| Utc.from_utc_datetime(&dt);
+        assert!(schedule.is_active(utc(on(17, 2, 0)))); // Janela de sexta, não é feriado
+        assert!(!schedule.is_active(utc(on(17, 23, 0)))); // Começa no feriado
+        assert!(!schedule.is_active(utc(on(18, 2, 0))));
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        let parse = |json: &str| serde_json::from_str::<ScheduleConfig>(json).unwrap().schedule.unwrap();
+        assert!(parse(r#"{"schedule": "work_hours"}"#).validate().is_ok());
+        assert!(parse(r#"{"schedule": "office"}"#).validate().is_err());
+        assert!(parse(r#"{"schedule": {"timezone": "Europe/Lisbon", "windows": [{"days": ["seg"], "start": "09:00", "end": "18:00"}], "holidays": ["12-25", "02-29"]}}"#)
+            .validate()
+            .is_ok());
+        assert!(parse(r#"{"schedule": {"timezone": "Europe/Lisboa"}}"#).validate().is_err());
+        assert!(parse(r#"{"schedule": {"windows": [{"start": "9h", "end": "18:00"}]}}"#).validate().is_err());
+        assert!(parse(r#"{"schedule": {"windows": [{"days": ["xyz"], "start": "09:00", "end": "18:00"}]}}"#)
+            .validate()
+            .is_err());
+        assert!(parse(r#"{"schedule": {"holidays": ["25/12"]}}"#).validate().is_err());
+    }
+}
@@ -1,80 +1,359 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use chrono::Utc;
-use serde::{Serialize, Deserialize};
-use sysinfo::{System, ProcessesToUpdate};
+// ============================================================
+// screen_time_tracker.rs — Tempo de utilização por aplicação
+// Conta apenas a janela em primeiro plano e pára quando o
+// utilizador está inactivo (medidos pelo helper na sessão do
+// utilizador; o serviço na sessão 0 não os vê). Totais por dia (hora local) com
+// blocos por hora, guardados em disco entre reinícios.
+// ============================================================
+
+use chrono::{Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+use sysinfo::{Pid, ProcessesToUpdate, System};
 use tokio::time::{interval, Duration};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+use crate::config;
+use crate::session_helper;
+
+const TICK_SECS: u64 = session_helper::SAMPLE_INTERVAL.as_secs();
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const CATALOG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Dias terminados ainda por enviar que ficam guardados (agente sem servidor)
+const MAX_PENDING_DAYS: usize = 7;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ScreenTimeEntry {
     pub app_name: String,
+    pub category: String,
     pub total_seconds: u64,
-    pub date: String, // YYYY-MM-DD
+    pub date: String, // YYYY-MM-DD (hora local)
+    pub hourly_seconds: Vec<u64>, // 24 posições, hora local
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct AppUsage {
+    category: String,
+    total_seconds: u64,
+    hourly: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct DayUsage {
+    date: String,
+    apps: BTreeMap<String, AppUsage>,
+}
+
+impl DayUsage {
+    fn entries(&self) -> impl Iterator<Item = ScreenTimeEntry> + '_ {
+        self.apps.iter().map(|(app, usage)| ScreenTimeEntry {
+            app_name: app.clone(),
+            category: usage.category.clone(),
+            total_seconds: usage.total_seconds,
+            date: self.date.clone(),
+            hourly_seconds: usage.hourly.clone(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct TrackerState {
+    today: DayUsage,
+    // Dias anteriores ainda não confirmados pelo servidor
+    finished: Vec<DayUsage>,
+}
+
+impl TrackerState {
+    fn load() -> Self {
+        let path = config::data_dir().join("screen_time.json");
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let path = config::data_dir().join("screen_time.json");
+        if let Ok(json) = serde_json::to_string(self) {
+            if let Err(e) = fs::write(&path, json) {
+                eprintln!("[ScreenTime] Erro ao guardar tempos de utilização: {}", e);
+            }
+        }
+    }
+
+    /// À meia-noite (ou ao arrancar noutro dia) o dia corrente passa para os terminados
+    fn roll_over(&mut self, date: &str) {
+        if self.today.date == date {
+            return;
+        }
+
+        let previous = std::mem::replace(&mut self.today, DayUsage { date: date.to_string(), apps: BTreeMap::new() });
+        if !previous.date.is_empty() && !previous.apps.is_empty() {
+            self.finished.push(previous);
+            if self.finished.len() > MAX_PENDING_DAYS {
+                let excess = self.finished.len() - MAX_PENDING_DAYS;
+                self.finished.drain(..excess);
+            }
+        }
+    }
+
+    fn add(&mut self, app: String, category: &str, hour: usize, seconds: u64) {
+        let usage = self.today.apps.entry(app).or_insert_with(|| AppUsage {
+            category: category.to_string(),
+            total_seconds: 0,
+            hourly: vec![0; 24],
+        });
+        usage.category = category.to_string();
+        usage.total_seconds += seconds;
+        if usage.hourly.len() != 24 {
+            usage.hourly.resize(24, 0);
+        }
+        usage.hourly[hour.min(23)] += seconds;
+    }
 }
 
 lazy_static::lazy_static! {
-    static ref SCREEN_TIME_DATA: Arc<Mutex<HashMap<String, u64>>> = Arc::new(Mutex::new(HashMap::new()));
+    static ref SCREEN_TIME_STATE: Mutex<TrackerState> = Mutex::new(TrackerState::load());
+}
+
+// -------------------------------------------------
+// Catálogo de aplicações (data_dir/screen_time_catalog.json)
+// -------------------------------------------------
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CatalogEntry {
+    /// Parte do nome do executável, sem distinguir maiúsculas (ex: "chrome")
+    pattern: String,
+    /// Categoria da app; "ignore" não conta tempo (ecrã de bloqueio, etc.)
+    category: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct AppCatalog {
+    /// Sem teclado/rato durante este tempo, o utilizador conta como inactivo
+    #[serde(default = "default_idle_threshold")]
+    idle_threshold_secs: u64,
+    /// Conta também apps fora do catálogo, na categoria "other"
+    #[serde(default = "default_track_unlisted")]
+    track_unlisted: bool,
+    #[serde(default)]
+    apps: Vec<CatalogEntry>,
+}
+
+fn default_idle_threshold() -> u64 {
+    300
+}
+
+fn default_track_unlisted() -> bool {
+    true
 }
 
+impl Default for AppCatalog {
+    fn default() -> Self {
+        let entry = |pattern: &str, category: &str| CatalogEntry {
+            pattern: pattern.to_string(),
+            category: category.to_string(),
+        };
+
+        AppCatalog {
+            idle_threshold_secs: default_idle_threshold(),
+            track_unlisted: default_track_unlisted(),
+            apps: vec![
+                entry("lockapp", "ignore"),
+                entry("logonui", "ignore"),
+                entry("scrnsave", "ignore"),
+                entry("chrome", "browser"),
+                entry("firefox", "browser"),
+                entry("msedge", "browser"),
+                entry("brave", "browser"),
+                entry("opera", "browser"),
+                entry("code", "development"),
+                entry("devenv", "development"),
+                entry("sublime", "development"),
+                entry("notepad++", "development"),
+                entry("winword", "office"),
+                entry("excel", "office"),
+                entry("powerpnt", "office"),
+                entry("outlook", "communication"),
+                entry("teams", "communication"),
+                entry("slack", "communication"),
+                entry("zoom", "communication"),
+                entry("discord", "social"),
+                entry("spotify", "entertainment"),
+                entry("steam", "games"),
+                entry("obs", "media"),
+                entry("photoshop", "design"),
+                entry("illustrator", "design"),
+                entry("premiere", "design"),
+                entry("afterfx", "design"),
+            ],
+        }
+    }
+}
+
+impl AppCatalog {
+    fn path() -> std::path::PathBuf {
+        config::data_dir().join("screen_time_catalog.json")
+    }
+
+    fn modified() -> Option<SystemTime> {
+        fs::metadata(Self::path()).and_then(|m| m.modified()).ok()
+    }
+
+    /// Lê o catálogo; se não existir, escreve o de omissão para o administrador editar
+    fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("[ScreenTime] Catálogo inválido, a usar o de omissão: {}", e);
+                Self::default()
+            }),
+            Err(_) => {
+                let catalog = Self::default();
+                if let Ok(json) = serde_json::to_string_pretty(&catalog) {
+                    let _ = fs::write(Self::path(), json);
+                }
+                catalog
+            }
+        }
+    }
+
+    /// Categoria do executável, ou `None` se não deve contar
+    fn categorize(&self, exe_name: &str) -> Option<String> {
+        let lower = exe_name.to_lowercase();
+        match self.apps.iter().find(|e| !e.pattern.is_empty() && lower.contains(&e.pattern.to_lowercase())) {
+            Some(entry) if entry.category == "ignore" => None,
+            Some(entry) => Some(entry.category.clone()),
+            None if self.track_unlisted => Some("other".to_string()),
+            None => None,
+        }
+    }
+}
+
+// -------------------------------------------------
+// Ciclo de medição
+// -------------------------------------------------
+
 pub async fn start_tracking() {
-    let mut ticker = interval(Duration::from_secs(10)); // Check cada 10s
-    let mut sys = System::new_all();
+    let mut ticker = interval(Duration::from_secs(TICK_SECS));
+    let mut sys = System::new();
+    let mut catalog = AppCatalog::load();
+    let mut catalog_modified = AppCatalog::modified();
+    let mut last_tick = Instant::now();
+    let mut last_save = Instant::now();
+    let mut last_catalog_check = Instant::now();
+    session_helper::start_sampler();
 
     loop {
         ticker.tick().await;
-        
-        // CORREÇÃO: Removido o argumento `true`
-        sys.refresh_processes(ProcessesToUpdate::All);
-        
-        // Detecta processos activos
-        let active_processes: Vec<String> = sys.processes()
-            .values()
-            .filter(|p| {
-                let name = p.name().to_string_lossy().to_string();
-                is_relevant_app(&name)
-            })
-            .map(|p| clean_process_name(p.name().to_string_lossy().to_string()))
-            .collect();
-
-        // Incrementa tempo para cada app activa
-        let mut data = SCREEN_TIME_DATA.lock().unwrap();
-        for app in active_processes {
-            *data.entry(app).or_insert(0) += 10; // +10 segundos
+
+        // Tempo real desde a última medição; limitado para não contar suspensões do sistema
+        let elapsed_ms = last_tick.elapsed().as_millis() as u64;
+        last_tick = Instant::now();
+        let seconds = ((elapsed_ms + 500) / 1000).min(TICK_SECS * 2);
+
+        if last_catalog_check.elapsed() >= CATALOG_CHECK_INTERVAL {
+            last_catalog_check = Instant::now();
+            let modified = AppCatalog::modified();
+            if modified != catalog_modified {
+                catalog = AppCatalog::load();
+                catalog_modified = modified;
+                println!("[ScreenTime] Catálogo de aplicações recarregado");
+            }
+        }
+
+        let now = Local::now();
+        let active = match session_helper::latest_sample() {
+            Some(sample) if sample.idle_secs < catalog.idle_threshold_secs => {
+                sample.foreground_pid.and_then(|pid| process_name(&mut sys, pid))
+            }
+            _ => None,
+        };
+
+        {
+            let mut state = SCREEN_TIME_STATE.lock().unwrap();
+            state.roll_over(&now.format("%Y-%m-%d").to_string());
+
+            if let Some(exe) = active {
+                if let Some(category) = catalog.categorize(&exe) {
+                    state.add(clean_process_name(exe), &category, now.hour() as usize, seconds);
+                }
+            }
+
+            if last_save.elapsed() >= SAVE_INTERVAL {
+                last_save = Instant::now();
+                state.save();
+            }
         }
     }
 }
 
+/// Dia corrente e dias terminados ainda por confirmar
 pub fn get_daily_stats() -> Vec<ScreenTimeEntry> {
-    let data = SCREEN_TIME_DATA.lock().unwrap();
-    let today = Utc::now().format("%Y-%m-%d").to_string();
-    
-    data.iter()
-        // CORREÇÃO: Declarado explicitamente o tipo (&String, &u64)
-        .map(|(app, seconds): (&String, &u64)| ScreenTimeEntry {
-            app_name: app.clone(),
-            total_seconds: *seconds,
-            date: today.clone(),
-        })
+    let state = SCREEN_TIME_STATE.lock().unwrap();
+    state
+        .finished
+        .iter()
+        .chain(std::iter::once(&state.today))
+        .flat_map(|day| day.entries())
         .collect()
 }
 
-fn is_relevant_app(name: &str) -> bool {
-    let name_lower = name.to_lowercase();
-    
-    // Lista de apps relevantes para tracking
-    let relevant = [
-        "chrome", "firefox", "edge", "brave", "opera",
-        "code", "visual studio", "sublime", "notepad++",
-        "word", "excel", "powerpoint", "outlook", "teams", "slack", "discord", "zoom",
-        "obs", "spotify", "photoshop", "illustrator", "premiere", "aftereffects"
-    ];
-    
-    relevant.iter().any(|&app| name_lower.contains(app))
+/// Depois de um envio com sucesso, esquece os dias terminados já reportados
+pub fn acknowledge_daily_stats(sent: &[ScreenTimeEntry]) {
+    let mut state = SCREEN_TIME_STATE.lock().unwrap();
+    state.finished.retain(|day| !sent.iter().any(|e| e.date == day.date));
+    state.save();
+}
+
+/// Segundos de utilização hoje do executável `target` (usado pelas quotas)
+pub fn seconds_today(target: &str) -> u64 {
+    let state = SCREEN_TIME_STATE.lock().unwrap();
+    if state.today.date != Local::now().format("%Y-%m-%d").to_string() {
+        return 0;
+    }
+    state.today.seconds_for(target)
+}
+
+impl DayUsage {
+    /// Tempo do executável com este nome exacto ("code" não conta "vscode-helper")
+    fn seconds_for(&self, target: &str) -> u64 {
+        let key = clean_process_name(target.to_string());
+        self.apps.get(&key).map(|usage| usage.total_seconds).unwrap_or(0)
+    }
 }
 
+fn process_name(sys: &mut System, pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]));
+    sys.process(pid).map(|p| p.name().to_string_lossy().to_string())
+}
+
+/// Nome do executável sem extensão, em maiúsculas (chave dos totais por app)
 fn clean_process_name(name: String) -> String {
-    name.replace(".exe", "")
-        .replace(".bin", "")
-        .replace(".app", "")
-        .to_uppercase()
-}
\ No newline at end of file
+    let lower = name.trim().to_lowercase();
+    let stem = [".exe", ".bin", ".app"]
+        .iter()
+        .find_map(|ext| lower.strip_suffix(ext))
+        .unwrap_or(&lower);
+    stem.to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quota_counts_exact_executable_only() {
+        let mut state = TrackerState::default();
+        state.roll_over("2026-10-19");
+        state.add(clean_process_name("Code.exe".to_string()), "development", 9, 600);
+        state.add(clean_process_name("vscode-helper.exe".to_string()), "development", 9, 300);
+
+        assert_eq!(state.today.seconds_for("code.exe"), 600);
+        assert_eq!(state.today.seconds_for("CODE"), 600);
+        assert_eq!(state.today.seconds_for("vscode-helper"), 300);
+        assert_eq!(state.today.seconds_for("helper"), 0);
+    }
+}
@@ -1,9 +1,193 @@
-// agent/src/screenshot.rs
-pub fn capture_screenshot() -> Result<Vec<u8>, String> {
-    // Implementação simulada (Dummy)
-    // No futuro, a captura real de ecrã será implementada aqui.
-    println!("[Agent] Captura de ecrã simulada com sucesso.");
-    
-    // Retorna um dummy JPEG buffer para compilação (Assinatura JPEG: FF D8 FF E0)
-    Ok(vec![0xFF, 0xD8, 0xFF, 0xE0]) 
-}
\ No newline at end of file
+// ============================================================
+// screenshot.rs — Captura do ambiente de trabalho a pedido
+// Só com uma política "monitoring/screenshot" em "allow"; o
+// utilizador é avisado e a imagem vai em JPEG para o servidor.
+// A captura corre no helper da sessão do utilizador.
+// ============================================================
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::config::{self, Config};
+use crate::enforcer;
+use crate::session_helper;
+
+// Capturas maiores (vários monitores) são reduzidas a esta largura
+const MAX_WIDTH: u32 = 2560;
+const JPEG_QUALITY: u8 = 70;
+
+pub struct Screenshot {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Deserialize, Debug)]
+struct ScreenshotConfig {
+    #[serde(default = "default_notify_user")]
+    notify_user: bool,
+}
+
+fn default_notify_user() -> bool {
+    true
+}
+
+/// Política de capturas em vigor nesta máquina: `None` se estiverem desactivadas.
+/// A cache já vem ordenada por precedência, por isso a primeira que coincide ganha
+fn screenshot_policy() -> Option<ScreenshotConfig> {
+    let policies = enforcer::load_policy_cache();
+    let policy = policies
+        .iter()
+        .find(|p| p.policy_type == "monitoring" && p.target.eq_ignore_ascii_case("screenshot"))?;
+
+    if policy.action != "allow" {
+        return None;
+    }
+    Some(serde_json::from_str(&policy.config_json).unwrap_or(ScreenshotConfig { notify_user: true }))
+}
+
+/// Executa o comando "screenshot": verifica a política, avisa o utilizador, captura e envia
+pub async fn capture_and_upload(config: &Config, machine_id: &str, command_id: &str) -> Result<String> {
+    let Some(policy) = screenshot_policy() else {
+        bail!("Capturas de ecrã desactivadas por política nesta máquina");
+    };
+
+    // O aviso e a captura são feitos na sessão do utilizador, não na sessão 0 do serviço
+    let notify_user = policy.notify_user;
+    let shot = tokio::task::spawn_blocking(move || session_helper::capture_screenshot(notify_user)).await??;
+    // No agente e não na captura: no helper, o stdout é a resposta binária
+    println!("[Agent] Captura de ecrã: {}x{} ({} KB)", shot.width, shot.height, shot.jpeg.len() / 1024);
+
+    upload(config, machine_id, command_id, &shot).await?;
+    Ok(format!("Captura enviada ({}x{}, {} KB)", shot.width, shot.height, shot.jpeg.len() / 1024))
+}
+
+async fn upload(config: &Config, machine_id: &str, command_id: &str, shot: &Screenshot) -> Result<()> {
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(60)).build()?;
+    let url = format!("{}/api/v2/screenshots", config.server_url);
+
+    let request = client.post(&url).header("X-API-Key", &config.api_key);
+    let response = config::with_agent_token(request)
+        .header("Content-Type", "image/jpeg")
+        .query(&[
+            ("machine_id", machine_id.to_string()),
+            ("command_id", command_id.to_string()),
+            ("width", shot.width.to_string()),
+            ("height", shot.height.to_string()),
+            ("captured_at", chrono::Utc::now().to_rfc3339()),
+        ])
+        .body(shot.jpeg.clone())
+        .send()
+        .await
+        .context("Falha ao enviar captura")?;
+
+    if !response.status().is_success() {
+        bail!("Servidor erro: {}", response.status());
+    }
+    Ok(())
+}
+
+/// Captura todos os monitores (ecrã virtual) e comprime em JPEG; corre no helper da sessão do utilizador
+pub fn capture_screenshot() -> Result<Screenshot, String> {
+    let (width, height, bgra) = capture_bgra()?;
+
+    // Numa sessão sem ambiente de trabalho (ex: serviço na sessão 0) a imagem sai toda preta
+    if bgra.chunks_exact(4).all(|px| px[0] == 0 && px[1] == 0 && px[2] == 0) {
+        return Err("Sem ambiente de trabalho interactivo para capturar".to_string());
+    }
+
+    let rgb: Vec<u8> = bgra.chunks_exact(4).flat_map(|px| [px[2], px[1], px[0]]).collect();
+    let mut image = image::RgbImage::from_raw(width, height, rgb)
+        .ok_or_else(|| "Buffer da captura com tamanho inválido".to_string())?;
+
+    if width > MAX_WIDTH {
+        let new_height = (height as u64 * MAX_WIDTH as u64 / width as u64).max(1) as u32;
+        image = image::imageops::resize(&image, MAX_WIDTH, new_height, image::imageops::FilterType::Triangle);
+    }
+
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(&image)
+        .map_err(|e| format!("Erro ao comprimir captura: {}", e))?;
+
+    Ok(Screenshot { jpeg, width: image.width(), height: image.height() })
+}
+
+/// Copia o ecrã virtual via GDI e devolve (largura, altura, pixels BGRA de cima para baixo)
+#[cfg(windows)]
+fn capture_bgra() -> Result<(u32, u32, Vec<u8>), String> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+        ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT, DIB_RGB_COLORS, SRCCOPY,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+    };
+
+    unsafe {
+        let x = GetSystemMetrics(SM_XVIRTUALSCREEN);
+        let y = GetSystemMetrics(SM_YVIRTUALSCREEN);
+        let width = GetSystemMetrics(SM_CXVIRTUALSCREEN);
+        let height = GetSystemMetrics(SM_CYVIRTUALSCREEN);
+        if width <= 0 || height <= 0 {
+            return Err("Nenhum monitor disponível".to_string());
+        }
+
+        let screen_dc = GetDC(HWND::default());
+        if screen_dc.is_invalid() {
+            return Err("GetDC falhou".to_string());
+        }
+        let mem_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+        let previous = SelectObject(mem_dc, bitmap);
+
+        let blit = BitBlt(mem_dc, 0, 0, width, height, screen_dc, x, y, SRCCOPY | CAPTUREBLT);
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, // negativo: linhas de cima para baixo
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let lines = if blit.is_ok() {
+            GetDIBits(
+                mem_dc,
+                bitmap,
+                0,
+                height as u32,
+                Some(pixels.as_mut_ptr() as *mut _),
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        } else {
+            0
+        };
+
+        SelectObject(mem_dc, previous);
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(mem_dc);
+        ReleaseDC(HWND::default(), screen_dc);
+
+        if let Err(e) = blit {
+            return Err(format!("BitBlt falhou: {}", e));
+        }
+        if lines == 0 {
+            return Err("GetDIBits falhou".to_string());
+        }
+        Ok((width as u32, height as u32, pixels))
+    }
+}
+
+#[cfg(not(windows))]
+fn capture_bgra() -> Result<(u32, u32, Vec<u8>), String> {
+    Err("Captura de ecrã só suportada em Windows".to_string())
+}
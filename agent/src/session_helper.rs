@@ -0,0 +1,405 @@
+// ============================================================
+// session_helper.rs — Tarefas na sessão do utilizador
+// O agente corre como SYSTEM na sessão 0, sem ambiente de
+// trabalho: capturas e avisos ao utilizador são feitos por uma
+// cópia do agente lançada na sessão da consola
+// (`--session-helper <tarefa>`), que responde pelo stdout. O mesmo
+// helper mede a janela em primeiro plano e a inactividade.
+// ============================================================
+
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+#[cfg(windows)]
+use std::io::{BufRead, BufReader, Read};
+#[cfg(windows)]
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(windows)]
+use std::time::Instant;
+
+use crate::notifications;
+use crate::screenshot::{self, Screenshot};
+
+pub const HELPER_ARG: &str = "--session-helper";
+
+// Uma captura demora poucos segundos; depois disto o helper é terminado
+#[cfg(windows)]
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(30);
+// Intervalo entre amostras do helper de medição
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+// Sem sessão ou com o helper terminado, nova tentativa ao fim de
+#[cfg(windows)]
+const RESPAWN_DELAY: Duration = Duration::from_secs(10);
+
+/// Janela em primeiro plano e inactividade na sessão do utilizador
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionSample {
+    pub foreground_pid: Option<u32>,
+    pub idle_secs: u64,
+}
+
+#[cfg(windows)]
+lazy_static::lazy_static! {
+    // Última amostra recebida do helper e quando chegou
+    static ref LATEST_SAMPLE: Mutex<Option<(SessionSample, Instant)>> = Mutex::new(None);
+}
+
+// -------------------------------------------------
+// Lado do helper (processo na sessão do utilizador)
+// -------------------------------------------------
+
+/// Ponto de entrada do helper: `--session-helper screenshot [--notify]` ou `sample`
+pub fn run(args: &[String]) -> Result<()> {
+    let task = args.first().map(String::as_str).unwrap_or_default();
+    let mut stdout = std::io::stdout().lock();
+
+    match task {
+        "screenshot" => {
+            if args.iter().any(|a| a == "--notify") {
+                notifications::show_screenshot_notification();
+            }
+            match screenshot::capture_screenshot() {
+                Ok(shot) => {
+                    writeln!(stdout, "OK {} {} {}", shot.width, shot.height, shot.jpeg.len())?;
+                    stdout.write_all(&shot.jpeg)?;
+                }
+                Err(e) => writeln!(stdout, "ERR {}", e)?,
+            }
+        }
+        // Uma linha "<pid> <segundos inactivo>" por intervalo, até o agente fechar o pipe
+        "sample" => loop {
+            writeln!(stdout, "{} {}", foreground_pid().unwrap_or(0), idle_seconds())?;
+            stdout.flush()?;
+            std::thread::sleep(SAMPLE_INTERVAL);
+        },
+        other => bail!("Tarefa desconhecida: {}", other),
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+// -------------------------------------------------
+// Lado do agente
+// -------------------------------------------------
+
+/// Avisa (se pedido) e captura o ecrã na sessão da consola
+#[cfg(windows)]
+pub fn capture_screenshot(notify_user: bool) -> Result<Screenshot> {
+    let mut args = vec!["screenshot"];
+    if notify_user {
+        args.push("--notify");
+    }
+
+    let mut helper = SessionProcess::spawn(&args)?;
+    helper.kill_after(CAPTURE_TIMEOUT);
+
+    let mut output = Vec::new();
+    helper.stdout.read_to_end(&mut output).context("Falha ao ler a resposta do helper")?;
+    parse_screenshot(&output)
+}
+
+#[cfg(not(windows))]
+pub fn capture_screenshot(_notify_user: bool) -> Result<Screenshot> {
+    bail!("Captura de ecrã só suportada em Windows")
+}
+
+/// Resposta do helper: "OK <largura> <altura> <bytes>\n" seguido do JPEG, ou "ERR <motivo>\n"
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_screenshot(output: &[u8]) -> Result<Screenshot> {
+    let newline = output.iter().position(|b| *b == b'\n').context("Helper terminou sem resposta")?;
+    let header = String::from_utf8_lossy(&output[..newline]);
+    let body = &output[newline + 1..];
+
+    if let Some(error) = header.strip_prefix("ERR ") {
+        bail!("{}", error.trim());
+    }
+
+    let fields: Vec<&str> = header.trim().split(' ').collect();
+    let ["OK", width, height, len] = fields.as_slice() else {
+        bail!("Resposta do helper inválida");
+    };
+    let len: usize = len.parse()?;
+    if body.len() != len {
+        bail!("Captura incompleta ({} de {} bytes)", body.len(), len);
+    }
+
+    Ok(Screenshot { jpeg: body.to_vec(), width: width.parse()?, height: height.parse()? })
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_sample(line: &str) -> Option<SessionSample> {
+    let (pid, idle) = line.trim().split_once(' ')?;
+    let pid: u32 = pid.parse().ok()?;
+    Some(SessionSample {
+        foreground_pid: (pid != 0).then_some(pid),
+        idle_secs: idle.parse().ok()?,
+    })
+}
+
+/// Lança o helper de medição e mantém-no vivo na sessão activa da consola
+#[cfg(windows)]
+pub fn start_sampler() {
+    std::thread::spawn(|| {
+        let mut last_error = String::new();
+        loop {
+            match SessionProcess::spawn(&["sample"]) {
+                Ok(helper) => {
+                    last_error.clear();
+                    for line in BufReader::new(&helper.stdout).lines() {
+                        let Ok(line) = line else { break };
+                        if let Some(sample) = parse_sample(&line) {
+                            *LATEST_SAMPLE.lock().unwrap() = Some((sample, Instant::now()));
+                        }
+                        // Troca de utilizador: o helper fica na sessão antiga
+                        if SessionProcess::active_session() != Some(helper.session_id) {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    // Sem sessão (ecrã de login) a falha repete-se: só se regista quando muda
+                    let error = format!("{:#}", e);
+                    if error != last_error {
+                        eprintln!("[ScreenTime] Helper de sessão indisponível: {}", error);
+                        last_error = error;
+                    }
+                }
+            }
+            *LATEST_SAMPLE.lock().unwrap() = None;
+            std::thread::sleep(RESPAWN_DELAY);
+        }
+    });
+}
+
+#[cfg(not(windows))]
+pub fn start_sampler() {}
+
+/// Amostra recente do helper (`None` sem sessão de utilizador ou com o helper parado)
+#[cfg(windows)]
+pub fn latest_sample() -> Option<SessionSample> {
+    let latest = LATEST_SAMPLE.lock().unwrap();
+    let (sample, at) = latest.as_ref()?;
+    (at.elapsed() < SAMPLE_INTERVAL * 3).then_some(*sample)
+}
+
+#[cfg(not(windows))]
+pub fn latest_sample() -> Option<SessionSample> {
+    None
+}
+
+#[cfg(windows)]
+fn foreground_pid() -> Option<u32> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        (pid != 0).then_some(pid)
+    }
+}
+
+#[cfg(not(windows))]
+fn foreground_pid() -> Option<u32> {
+    None
+}
+
+/// Segundos desde a última entrada de teclado/rato na sessão
+#[cfg(windows)]
+fn idle_seconds() -> u64 {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    unsafe {
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return 0;
+        }
+        (GetTickCount().wrapping_sub(info.dwTime) / 1000) as u64
+    }
+}
+
+#[cfg(not(windows))]
+fn idle_seconds() -> u64 {
+    0
+}
+
+/// Processo lançado na sessão activa da consola, com o stdout ligado a um pipe
+#[cfg(windows)]
+pub struct SessionProcess {
+    pub stdout: std::fs::File,
+    pub session_id: u32,
+    process: windows::Win32::Foundation::HANDLE,
+}
+
+// O HANDLE do processo só é usado para o terminar e fechar
+#[cfg(windows)]
+unsafe impl Send for SessionProcess {}
+
+#[cfg(windows)]
+impl SessionProcess {
+    /// Sessão com utilizador na consola (`None` no ecrã de login ou sem sessão)
+    pub fn active_session() -> Option<u32> {
+        use windows::Win32::System::RemoteDesktop::WTSGetActiveConsoleSessionId;
+
+        let session = unsafe { WTSGetActiveConsoleSessionId() };
+        (session != u32::MAX).then_some(session)
+    }
+
+    pub fn spawn(args: &[&str]) -> Result<Self> {
+        use std::os::windows::io::FromRawHandle;
+        use windows::core::{PCWSTR, PWSTR};
+        use windows::Win32::Foundation::{
+            CloseHandle, SetHandleInformation, BOOL, HANDLE, HANDLE_FLAGS, HANDLE_FLAG_INHERIT,
+        };
+        use windows::Win32::Security::SECURITY_ATTRIBUTES;
+        use windows::Win32::System::Pipes::CreatePipe;
+        use windows::Win32::System::RemoteDesktop::WTSQueryUserToken;
+        use windows::Win32::System::Threading::{
+            CreateProcessAsUserW, CREATE_NO_WINDOW, PROCESS_INFORMATION, STARTF_USESTDHANDLES, STARTUPINFOW,
+        };
+
+        let session_id = Self::active_session().context("Nenhum utilizador com sessão na consola")?;
+        let exe = std::env::current_exe()?;
+        let mut command_line: Vec<u16> = format!("\"{}\" {} {}", exe.display(), HELPER_ARG, args.join(" "))
+            .encode_utf16()
+            .chain([0])
+            .collect();
+        let mut desktop: Vec<u16> = "winsta0\\default".encode_utf16().chain([0]).collect();
+
+        unsafe {
+            let mut token = HANDLE::default();
+            WTSQueryUserToken(session_id, &mut token).context("WTSQueryUserToken falhou")?;
+
+            // Só a ponta de escrita é herdada pelo helper
+            let attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: std::ptr::null_mut(),
+                bInheritHandle: BOOL(1),
+            };
+            let (mut read, mut write) = (HANDLE::default(), HANDLE::default());
+            if let Err(e) = CreatePipe(&mut read, &mut write, Some(&attributes as *const _), 0) {
+                let _ = CloseHandle(token);
+                bail!("CreatePipe falhou: {}", e);
+            }
+            let _ = SetHandleInformation(read, HANDLE_FLAG_INHERIT.0, HANDLE_FLAGS(0));
+
+            let startup = STARTUPINFOW {
+                cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+                lpDesktop: PWSTR(desktop.as_mut_ptr()),
+                dwFlags: STARTF_USESTDHANDLES,
+                hStdOutput: write,
+                hStdError: write,
+                ..Default::default()
+            };
+            let mut info = PROCESS_INFORMATION::default();
+            let created = CreateProcessAsUserW(
+                token,
+                PCWSTR::null(),
+                PWSTR(command_line.as_mut_ptr()),
+                None,
+                None,
+                BOOL(1),
+                CREATE_NO_WINDOW,
+                None,
+                PCWSTR::null(),
+                &startup,
+                &mut info,
+            );
+
+            let _ = CloseHandle(write);
+            let _ = CloseHandle(token);
+            if let Err(e) = created {
+                let _ = CloseHandle(read);
+                bail!("CreateProcessAsUser falhou: {}", e);
+            }
+            let _ = CloseHandle(info.hThread);
+
+            Ok(SessionProcess {
+                stdout: std::fs::File::from_raw_handle(read.0),
+                session_id,
+                process: info.hProcess,
+            })
+        }
+    }
+
+    /// Termina o helper se ainda estiver a correr ao fim de `timeout` (a leitura do pipe acaba aí)
+    pub fn kill_after(&self, timeout: Duration) {
+        use windows::Win32::Foundation::{DuplicateHandle, BOOL, DUPLICATE_SAME_ACCESS, HANDLE};
+        use windows::Win32::System::Threading::{GetCurrentProcess, TerminateProcess, WaitForSingleObject};
+
+        let mut process = HANDLE::default();
+        let duplicated = unsafe {
+            DuplicateHandle(
+                GetCurrentProcess(),
+                self.process,
+                GetCurrentProcess(),
+                &mut process,
+                0,
+                BOOL(0),
+                DUPLICATE_SAME_ACCESS,
+            )
+        };
+        if duplicated.is_err() {
+            return;
+        }
+
+        let raw = process.0 as usize;
+        std::thread::spawn(move || unsafe {
+            use windows::Win32::Foundation::{CloseHandle, WAIT_TIMEOUT};
+
+            let process = HANDLE(raw as *mut _);
+            if WaitForSingleObject(process, timeout.as_millis() as u32) == WAIT_TIMEOUT {
+                let _ = TerminateProcess(process, 1);
+            }
+            let _ = CloseHandle(process);
+        });
+    }
+}
+
+#[cfg(windows)]
+impl Drop for SessionProcess {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Threading::TerminateProcess;
+
+        unsafe {
+            let _ = TerminateProcess(self.process, 0);
+            let _ = CloseHandle(self.process);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_helper_screenshot() {
+        let mut output = b"OK 1920 1080 4\n".to_vec();
+        output.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xD9]);
+        let shot = parse_screenshot(&output).unwrap();
+        assert_eq!((shot.width, shot.height, shot.jpeg.len()), (1920, 1080, 4));
+
+        let error = parse_screenshot(b"ERR Sem ambiente de trabalho\n").err().unwrap();
+        assert_eq!(error.to_string(), "Sem ambiente de trabalho");
+
+        // Helper terminado a meio do envio
+        assert!(parse_screenshot(b"OK 1920 1080 4000\n\xFF\xD8").is_err());
+        assert!(parse_screenshot(b"").is_err());
+    }
+
+    #[test]
+    fn parses_helper_samples() {
+        assert_eq!(parse_sample("4242 12\n"), Some(SessionSample { foreground_pid: Some(4242), idle_secs: 12 }));
+        // Sem janela em primeiro plano (ambiente de trabalho, ecrã bloqueado)
+        assert_eq!(parse_sample("0 300"), Some(SessionSample { foreground_pid: None, idle_secs: 300 }));
+        assert_eq!(parse_sample("lixo"), None);
+    }
+}
@@ -0,0 +1,99 @@
+// ============================================================
+// update-oui.mjs — Gera src-tauri/data/oui.tsv a partir do
+// registo MA-L do IEEE (todos os OUIs de 24 bits atribuídos)
+// ============================================================
+//
+// Uso: npm run update:oui            (descarrega do IEEE)
+//      npm run update:oui -- oui.csv (ficheiro já descarregado)
+//
+// O CSV do IEEE tem as colunas Registry, Assignment,
+// Organization Name e Organization Address; só as duas do meio
+// interessam. O ficheiro gerado é embebido no binário do dashboard
+// (discovery.rs), por isso tem de ser voltado a commitar.
+
+import { readFile, writeFile } from "node:fs/promises";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const SOURCE_URL = "https://standards-oui.ieee.org/oui/oui.csv";
+const OUTPUT = join(dirname(fileURLToPath(import.meta.url)), "../src-tauri/data/oui.tsv");
+// Um registo parcial (download cortado) não pode substituir a tabela
+const MIN_ENTRIES = 20000;
+
+async function loadCsv(path) {
+    if (path) return readFile(path, "utf8");
+
+    const response = await fetch(SOURCE_URL, { headers: { "User-Agent": "assetscan-update-oui" } });
+    if (!response.ok) throw new Error(`${SOURCE_URL}: HTTP ${response.status}`);
+    return response.text();
+}
+
+// CSV com campos entre aspas (nomes e moradas levam vírgulas e aspas duplicadas)
+function parseCsv(text) {
+    const rows = [];
+    let row = [];
+    let field = "";
+    let quoted = false;
+
+    for (let i = 0; i < text.length; i++) {
+        const c = text[i];
+        if (quoted) {
+            if (c === '"' && text[i + 1] === '"') {
+                field += '"';
+                i++;
+            } else if (c === '"') {
+                quoted = false;
+            } else {
+                field += c;
+            }
+        } else if (c === '"') {
+            quoted = true;
+        } else if (c === ",") {
+            row.push(field);
+            field = "";
+        } else if (c === "\n" || c === "\r") {
+            if (c === "\r" && text[i + 1] === "\n") i++;
+            row.push(field);
+            rows.push(row);
+            row = [];
+            field = "";
+        } else {
+            field += c;
+        }
+    }
+    if (field || row.length) {
+        row.push(field);
+        rows.push(row);
+    }
+    return rows;
+}
+
+const csv = await loadCsv(process.argv[2]);
+const [header, ...records] = parseCsv(csv);
+const assignment = header.indexOf("Assignment");
+const organization = header.indexOf("Organization Name");
+if (assignment < 0 || organization < 0) {
+    throw new Error(`Cabeçalho inesperado: ${header.join(",")}`);
+}
+
+const vendors = new Map();
+for (const record of records) {
+    const prefix = (record[assignment] ?? "").trim().toUpperCase();
+    // Tabs e quebras de linha no nome partiriam o formato do ficheiro
+    const vendor = (record[organization] ?? "").replace(/\s+/g, " ").trim();
+    if (/^[0-9A-F]{6}$/.test(prefix) && vendor && !vendors.has(prefix)) {
+        vendors.set(prefix, vendor);
+    }
+}
+if (vendors.size < MIN_ENTRIES) {
+    throw new Error(`Só ${vendors.size} OUIs no registo; oui.tsv não foi alterado`);
+}
+
+const lines = [
+    "# Fabricantes por OUI (primeiros 3 octetos do MAC), usado no inventário de",
+    "# equipamentos descobertos. Registo MA-L completo do IEEE, gerado por",
+    `# scripts/update-oui.mjs em ${new Date().toISOString().slice(0, 10)}; não editar à mão.`,
+    ...[...vendors].sort(([a], [b]) => a.localeCompare(b)).map(([prefix, vendor]) => `${prefix}\t${vendor}`),
+];
+await writeFile(OUTPUT, lines.join("\n") + "\n");
+console.log(`${vendors.size} OUIs escritos em ${OUTPUT}`);
@@ -0,0 +1,120 @@
+// ============================================================
+// agent_hub.rs — Notificações imediatas para os agentes
+// Cada agente mantém um long-poll aberto em /api/v2/agent/events;
+// o dashboard acorda-o quando há políticas novas ou comandos.
+// Um agente à espera custa apenas um Notify e uma entrada no mapa.
+// ============================================================
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+pub const POLICY_CHANGED: &str = "policy_changed";
+pub const COMMAND_QUEUED: &str = "command_queued";
+
+// Canais de agentes que não voltam a ligar são descartados ao fim de 1h
+const IDLE_CHANNEL: Duration = Duration::from_secs(3600);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(300);
+
+struct Channel {
+    notify: Notify,
+    state: Mutex<ChannelState>,
+}
+
+struct ChannelState {
+    pending: HashSet<&'static str>,
+    last_poll: Instant,
+}
+
+struct AgentHub {
+    channels: Mutex<HashMap<String, Arc<Channel>>>,
+    last_prune: Mutex<Instant>,
+}
+
+fn hub() -> &'static AgentHub {
+    static HUB: OnceLock<AgentHub> = OnceLock::new();
+    HUB.get_or_init(|| AgentHub {
+        channels: Mutex::new(HashMap::new()),
+        last_prune: Mutex::new(Instant::now()),
+    })
+}
+
+fn channel(machine_id: &str) -> Arc<Channel> {
+    let mut channels = hub().channels.lock().unwrap();
+    channels
+        .entry(machine_id.to_string())
+        .or_insert_with(|| {
+            Arc::new(Channel {
+                notify: Notify::new(),
+                state: Mutex::new(ChannelState { pending: HashSet::new(), last_poll: Instant::now() }),
+            })
+        })
+        .clone()
+}
+
+fn push(channel: &Channel, event: &'static str) {
+    channel.state.lock().unwrap().pending.insert(event);
+    // Sem ninguém à espera, o Notify guarda a permissão para o próximo long-poll
+    channel.notify.notify_one();
+}
+
+/// Evento para uma máquina; fica guardado se o agente estiver a religar
+pub fn notify_machine(machine_id: &str, event: &'static str) {
+    push(&channel(machine_id), event);
+}
+
+/// Evento para todos os agentes com long-poll (os restantes recebem no próximo relatório)
+pub fn notify_all(event: &'static str) {
+    let channels: Vec<Arc<Channel>> = hub().channels.lock().unwrap().values().cloned().collect();
+    for channel in channels {
+        push(&channel, event);
+    }
+}
+
+/// Política criada/alterada: global acorda todos, de máquina só essa. Os agentes vão
+/// buscar só as políticas (/api/v2/policies), espalhados por uma espera aleatória
+pub fn notify_policy_change(machine_id: Option<&str>) {
+    match machine_id {
+        Some(id) => notify_machine(id, POLICY_CHANGED),
+        None => notify_all(POLICY_CHANGED),
+    }
+}
+
+/// Espera até haver eventos para a máquina ou até `timeout` (devolve lista vazia)
+pub async fn wait(machine_id: &str, timeout: Duration) -> Vec<String> {
+    prune_idle();
+
+    let channel = channel(machine_id);
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        {
+            let mut state = channel.state.lock().unwrap();
+            state.last_poll = Instant::now();
+            if !state.pending.is_empty() {
+                return state.pending.drain().map(|e| e.to_string()).collect();
+            }
+        }
+
+        // Uma permissão antiga pode acordar-nos sem eventos: volta a esperar até ao prazo
+        if tokio::time::timeout_at(deadline, channel.notify.notified()).await.is_err() {
+            return vec![];
+        }
+    }
+}
+
+fn prune_idle() {
+    {
+        let mut last = hub().last_prune.lock().unwrap();
+        if last.elapsed() < PRUNE_INTERVAL {
+            return;
+        }
+        *last = Instant::now();
+    }
+
+    hub().channels.lock().unwrap().retain(|_, channel| {
+        // Ainda com um pedido em curso (referência fora do mapa) ou activo recentemente
+        Arc::strong_count(channel) > 1 || channel.state.lock().unwrap().last_poll.elapsed() < IDLE_CHANNEL
+    });
+}
@@ -1,11 +1,14 @@
 // ============================================================
-// auth.rs — Autenticação com API Keys
+// auth.rs — Autenticação com API Keys, credenciais por máquina
+// e assinatura dos scripts aprovados
 // ============================================================
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use hex;
 use rand::Rng;
-use rusqlite::{params, Connection};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use rusqlite::{params, Connection, OptionalExtension};
 use sha2::{Digest, Sha256};
 
 use crate::database::DbPool;
@@ -91,3 +94,99 @@ pub struct ApiKeyInfo {
     pub last_used: Option<String>,
     pub enabled: bool,
 }
+
+// -------------------------------------------------
+// Credenciais por máquina
+// -------------------------------------------------
+
+/// Emite o token de uma máquina que ainda não tem credencial. O token só é devolvido
+/// nesse momento; o servidor guarda apenas o hash
+pub fn issue_machine_token(pool: &DbPool, machine_id: &str) -> Result<Option<String>> {
+    let token = generate_api_key().replacen("ask_", "amt_", 1);
+    let conn = pool.lock().unwrap();
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO machine_credentials (machine_id, token_hash, created_at) VALUES (?1, ?2, ?3)",
+        params![machine_id, hash_api_key(&token), chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok((inserted > 0).then_some(token))
+}
+
+/// Confirma que o pedido vem do agente a quem foi emitida a credencial da máquina
+pub fn verify_machine_token(pool: &DbPool, machine_id: &str, token: Option<&str>) -> Result<bool> {
+    let Some(token) = token else { return Ok(false) };
+    let conn = pool.lock().unwrap();
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT token_hash FROM machine_credentials WHERE machine_id = ?1",
+            params![machine_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(stored.is_some_and(|hash| hash == hash_api_key(token)))
+}
+
+/// Apaga a credencial (máquina reinstalada, token perdido); o próximo relatório emite outra
+pub fn reset_machine_token(pool: &DbPool, machine_id: &str) -> Result<bool> {
+    let conn = pool.lock().unwrap();
+    let deleted = conn.execute("DELETE FROM machine_credentials WHERE machine_id = ?1", params![machine_id])?;
+    Ok(deleted > 0)
+}
+
+// -------------------------------------------------
+// Assinatura de scripts aprovados (Ed25519)
+// -------------------------------------------------
+
+/// Chave de assinatura do servidor, gerada na primeira utilização
+fn script_signing_key(conn: &Connection) -> Result<Ed25519KeyPair> {
+    let stored: Option<Vec<u8>> = conn
+        .query_row("SELECT pkcs8 FROM server_keys WHERE name = 'script_signing'", [], |row| row.get(0))
+        .optional()?;
+
+    let pkcs8 = match stored {
+        Some(pkcs8) => pkcs8,
+        None => {
+            let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| anyhow!("Falha ao gerar a chave de assinatura de scripts"))?;
+            conn.execute(
+                "INSERT INTO server_keys (name, pkcs8, created_at) VALUES ('script_signing', ?1, ?2)",
+                params![document.as_ref(), chrono::Utc::now().to_rfc3339()],
+            )?;
+            document.as_ref().to_vec()
+        }
+    };
+    Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|_| anyhow!("Chave de assinatura de scripts inválida"))
+}
+
+/// Mensagem assinada: o agente recalcula o hash do conteúdo que recebe
+fn script_signing_message(script_id: &str, interpreter: &str, sha256: &str) -> String {
+    format!("assetscan-script\n{}\n{}\n{}", script_id, interpreter, sha256)
+}
+
+/// Chave pública (hex) que os agentes fixam para validar scripts
+pub fn script_public_key(pool: &DbPool) -> Result<String> {
+    let conn = pool.lock().unwrap();
+    let key = script_signing_key(&conn)?;
+    Ok(hex::encode(key.public_key().as_ref()))
+}
+
+/// Assina um script no momento da aprovação
+pub fn sign_script(pool: &DbPool, script_id: &str, interpreter: &str, sha256: &str) -> Result<String> {
+    let conn = pool.lock().unwrap();
+    let key = script_signing_key(&conn)?;
+    let signature = key.sign(script_signing_message(script_id, interpreter, sha256).as_bytes());
+    Ok(hex::encode(signature.as_ref()))
+}
+
+/// Scripts aprovados antes da assinatura passam a ir assinados
+pub fn sign_unsigned_scripts(pool: &DbPool) -> Result<usize> {
+    let unsigned = crate::database::list_approved_scripts(pool)?
+        .into_iter()
+        .filter(|s| s.signature.is_empty())
+        .collect::<Vec<_>>();
+
+    for script in &unsigned {
+        let signature = sign_script(pool, &script.id, &script.interpreter, &script.sha256)?;
+        crate::database::set_script_signature(pool, &script.id, &signature)?;
+    }
+    Ok(unsigned.len())
+}
@@ -1,4 +1,8 @@
+use crate::agent_hub;
 use crate::database::{self, DbPool};
+use crate::hardware_changes;
+use crate::hardware_inventory;
+use crate::machine_identity;
 use crate::models::*;
 use tauri::State;
 
@@ -23,6 +27,157 @@ pub async fn get_hardware_details(
     database::get_hardware_details(&pool, &machine_id).map_err(|e| e.to_string())
 }
 
+/// Memória, gráficas, monitores, baterias e discos; `include_removed` junta os que já saíram
+#[tauri::command]
+pub async fn get_hardware_inventory(
+    machine_id: String,
+    include_removed: Option<bool>,
+    pool: State<'_, DbPool>,
+) -> Result<HardwareInventory, String> {
+    hardware_inventory::load(&pool, &machine_id, include_removed.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_battery_history(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<BatteryReading>, String> {
+    database::get_battery_readings(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_disk_health_history(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<DiskHealthChange>, String> {
+    database::get_disk_health_history(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+/// Alterações de hardware (de uma máquina ou de todas), as mais recentes primeiro
+#[tauri::command]
+pub async fn get_hardware_changes(
+    machine_id: Option<String>,
+    suspicious_only: Option<bool>,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<HardwareChange>, String> {
+    database::list_hardware_changes(
+        &pool,
+        machine_id.as_deref(),
+        suspicious_only.unwrap_or(false),
+        limit.unwrap_or(100),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_hardware_change_rules(pool: State<'_, DbPool>) -> Result<Vec<HardwareChangeRule>, String> {
+    database::list_hardware_change_rules(&pool).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_hardware_change_rule(
+    component: String,
+    suspicious: bool,
+    pool: State<'_, DbPool>,
+) -> Result<(), String> {
+    if !hardware_changes::COMPONENTS.contains(&component.as_str()) {
+        return Err(format!("Componente desconhecido: {}", component));
+    }
+    database::set_hardware_change_rule(&pool, &component, suspicious).map_err(|e| e.to_string())?;
+    database::log_audit(
+        &pool,
+        "set_hardware_change_rule",
+        "hardware_change_rule",
+        &component,
+        "admin",
+        if suspicious { "suspicious" } else { "normal" },
+    )
+    .ok();
+    Ok(())
+}
+
+/// Impressões digitais de hardware vistas com o machine_id, a mais recente primeiro
+#[tauri::command]
+pub async fn get_machine_fingerprints(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<MachineFingerprint>, String> {
+    database::list_machine_fingerprints(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+/// Clones e duplicados por resolver (ou todos, com `include_resolved`)
+#[tauri::command]
+pub async fn list_identity_conflicts(
+    include_resolved: Option<bool>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<IdentityConflict>, String> {
+    database::list_identity_conflicts(&pool, include_resolved.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+/// Une o registo `source_id` ao `target_id`, que fica com o histórico dos dois.
+/// Devolve quantas linhas de histórico foram somadas a linhas do destino
+#[tauri::command]
+pub async fn merge_machines(
+    source_id: String,
+    target_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<usize, String> {
+    machine_identity::merge(&pool, &source_id, &target_id, "admin")
+}
+
+#[tauri::command]
+pub async fn dismiss_identity_conflict(id: i64, pool: State<'_, DbPool>) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    database::resolve_identity_conflict(&pool, id, "dismissed", &now).map_err(|e| e.to_string())?;
+    database::log_audit(&pool, "dismiss_identity_conflict", "identity_conflict", &id.to_string(), "admin", "").ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_usb_devices(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<UsbDevice>, String> {
+    database::get_usb_devices(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_network_connections(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<NetworkConnection>, String> {
+    database::get_network_connections(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+/// Que máquinas (e processos) falam com um IP ou nome remoto
+#[tauri::command]
+pub async fn find_connections_to(
+    remote: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<NetworkConnection>, String> {
+    let remote = remote.trim();
+    if remote.is_empty() {
+        return Err("Indique um IP ou nome".to_string());
+    }
+    database::find_connections_to(&pool, remote).map_err(|e| e.to_string())
+}
+
+/// Que processos estão à escuta numa porta, em todas as máquinas
+#[tauri::command]
+pub async fn find_listeners(port: u16, pool: State<'_, DbPool>) -> Result<Vec<NetworkConnection>, String> {
+    database::find_listeners(&pool, port).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_device_events(
+    machine_id: String,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<DeviceEvent>, String> {
+    database::list_device_events(&pool, &machine_id, limit.unwrap_or(50)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_network_details(
     machine_id: String,
@@ -31,6 +186,22 @@ pub async fn get_network_details(
     database::get_network_details(&pool, &machine_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_network_adapters(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<NetworkAdapterEntry>, String> {
+    database::get_network_adapters(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_network_routes(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<NetworkRoute>, String> {
+    database::get_network_routes(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_security_status(
     machine_id: String,
@@ -44,33 +215,15 @@ pub async fn kill_process_remote(
     machine_id: String,
     process_name: String,
     pool: State<'_, DbPool>,
-) -> Result<(), String> {
-    // Cria política temporária para matar processo
-    let _id = database::create_policy(
-        &pool,
-        Some(&machine_id),
-        &format!("Bloqueio de Processo: {}", process_name), // name
-        "Criado via Dashboard",                             // description
-        "app",                                              // policy_type
-        1,                                                  // priority
-        &process_name,                                      // target
-        "block",                                            // action
-        "{}",                                               // config_json
-        "Terminado remotamente pelo administrador",         // reason
-        "admin",                                            // created_by
-    ).map_err(|e| e.to_string())?;
-
-    database::log_audit(
+) -> Result<String, String> {
+    // Termina as instâncias actuais; para impedir que volte a abrir, usar block_software
+    super::remote::enqueue(
         &pool,
-        "kill_process_remote",
-        "process",
-        &process_name,
-        "admin",
-        &format!("Machine: {}", machine_id),
+        &machine_id,
+        "kill_process",
+        serde_json::json!({ "process_name": process_name }),
+        None,
     )
-    .ok();
-
-    Ok(())
 }
 
 #[tauri::command]
@@ -104,6 +257,7 @@ pub async fn block_software(
         &format!("Blocked {} on machine {}", software_name, machine_id),
     ).ok();
     
+    agent_hub::notify_machine(&machine_id, agent_hub::POLICY_CHANGED);
     Ok(policy_id)
 }
 
@@ -153,6 +307,14 @@ pub async fn get_software(
     database::get_software(&pool, &machine_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_services(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<ServiceEntry>, String> {
+    database::get_services(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn rename_machine(
     machine_id: String,
@@ -283,8 +445,41 @@ pub async fn create_ip_policy(
     reason: String,
     pool: State<'_, DbPool>,
 ) -> Result<String, String> {
-    database::create_ip_policy(&pool, machine_id.as_deref(), &ip_address, &action, &reason)
-        .map_err(|e| e.to_string())
+    let ip_address = ip_address.trim().to_string();
+    if !is_valid_ip_or_cidr(&ip_address) {
+        return Err(format!("Endereço IP/CIDR inválido: {}", ip_address));
+    }
+
+    let id = database::create_ip_policy(&pool, machine_id.as_deref(), &ip_address, &action, &reason)
+        .map_err(|e| e.to_string())?;
+
+    database::log_audit(
+        &pool,
+        "create_ip_policy",
+        "policy",
+        &id,
+        "admin",
+        &format!("{} {}", action, ip_address),
+    )
+    .ok();
+
+    agent_hub::notify_policy_change(machine_id.as_deref());
+    Ok(id)
+}
+
+fn is_valid_ip_or_cidr(value: &str) -> bool {
+    let (ip, prefix) = match value.split_once('/') {
+        Some((ip, prefix)) => (ip, Some(prefix)),
+        None => (value, None),
+    };
+
+    match ip.parse::<std::net::IpAddr>() {
+        Ok(addr) => {
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            prefix.map(|p| p.parse::<u8>().map(|p| p <= max).unwrap_or(false)).unwrap_or(true)
+        }
+        Err(_) => false,
+    }
 }
 
 #[tauri::command]
@@ -308,29 +503,23 @@ pub async fn block_software_for_machine(
         "admin",                                             // created_by
     ).map_err(|e| e.to_string())?;
 
+    agent_hub::notify_machine(&machine_id, agent_hub::POLICY_CHANGED);
     Ok(id)
 }
 
 #[tauri::command]
 pub async fn kill_process(
     machine_id: String,
-    process_name: String,
+    pid: u32,
+    process_name: Option<String>,
     pool: State<'_, DbPool>,
-) -> Result<(), String> {
-    // Cria política temporária para bloquear processo
-    let _id = database::create_policy(
+) -> Result<String, String> {
+    // Com o nome, o agente confirma que o PID não foi reutilizado por outro processo
+    super::remote::enqueue(
         &pool,
-        Some(&machine_id),
-        &format!("Regra para {}", process_name),  // name
-        "Criado via Dashboard",                   // description
-        "app",                                    // policy_type
-        1,                                        // priority
-        &process_name,                            // target
-        "block",                                  // action
-        "{}",                                     // config_json
-        "Terminado pelo admin",                   // reason
-        "admin",                                  // created_by
-    ).map_err(|e| e.to_string())?;
-
-    Ok(())
+        &machine_id,
+        "kill_process",
+        serde_json::json!({ "pid": pid, "process_name": process_name }),
+        Some(10),
+    )
 }
\ No newline at end of file
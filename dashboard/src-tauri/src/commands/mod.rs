@@ -1,8 +1,11 @@
 pub mod audit;
 pub mod chatbot;
 pub mod machines;
+pub mod network;
 pub mod policies;
 pub mod processes;
+pub mod remote;
+pub mod screen_time;
 pub mod screenshots;
 pub mod vulnerabilities;
 
@@ -10,7 +13,10 @@ pub mod vulnerabilities;
 pub use audit::*;
 pub use chatbot::*;
 pub use machines::*;
+pub use network::*;
 pub use policies::*;
 pub use processes::*;
+pub use remote::*;
+pub use screen_time::*;
 pub use screenshots::*;
 pub use vulnerabilities::*;
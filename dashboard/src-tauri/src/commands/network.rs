@@ -0,0 +1,68 @@
+use crate::database::{self, DbPool};
+use crate::models::*;
+use crate::network_map;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_network_map(pool: State<'_, DbPool>) -> Result<Vec<NetworkSubnet>, String> {
+    network_map::build_map(&pool).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_network_conflicts(pool: State<'_, DbPool>) -> Result<Vec<NetworkConflict>, String> {
+    network_map::find_conflicts(&pool).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_ip_changes(
+    machine_id: Option<String>,
+    unexpected_only: Option<bool>,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<IpChangeEvent>, String> {
+    database::list_ip_changes(
+        &pool,
+        machine_id.as_deref(),
+        unexpected_only.unwrap_or(false),
+        limit.unwrap_or(100),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_ip_history(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<IpAssignment>, String> {
+    database::get_ip_history(&pool, &machine_id).map_err(|e| e.to_string())
+}
+
+/// Quem tinha um IP ou MAC (em qualquer formato); `at` em RFC 3339 limita a esse instante
+#[tauri::command]
+pub async fn find_ip_assignments(
+    value: String,
+    at: Option<String>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<IpAssignment>, String> {
+    let value = value.trim();
+    let (ip, mac) = if value.parse::<std::net::IpAddr>().is_ok() {
+        (Some(value.to_string()), None)
+    } else {
+        let mac = network_map::normalize_mac(value)
+            .ok_or_else(|| format!("Não é um IP nem um MAC: {}", value))?;
+        (None, Some(mac))
+    };
+
+    database::find_ip_assignments(&pool, ip.as_deref(), mac.as_deref(), at.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Equipamentos vistos na rede pelos agentes; `unmanaged_only` esconde os que têm agente
+#[tauri::command]
+pub async fn list_discovered_devices(
+    unmanaged_only: Option<bool>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<DiscoveredDevice>, String> {
+    database::list_discovered_devices(&pool, unmanaged_only.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
@@ -1,5 +1,7 @@
+use crate::agent_hub;
 use crate::database::{self, DbPool};
 use crate::models::*;
+use crate::policy_engine::{self, EffectivePolicy, PolicyConflict, PolicyCreationResult};
 use tauri::State;
 
 #[tauri::command]
@@ -14,7 +16,10 @@ pub async fn create_policy(
     config_json: String,
     reason: String,
     pool: State<'_, DbPool>,
-) -> Result<String, String> {
+) -> Result<PolicyCreationResult, String> {
+    policy_engine::validate_quota(&policy_type, &config_json)?;
+    policy_engine::validate_schedule(&config_json)?;
+
     let id = database::create_policy(
         &pool,
         machine_id.as_deref(),
@@ -29,7 +34,66 @@ pub async fn create_policy(
         "admin",
     ).map_err(|e| e.to_string())?;
 
-    Ok(id)
+    let created = database::get_policy(&pool, &id).map_err(|e| e.to_string())?;
+    let existing = database::list_policies(&pool, None).map_err(|e| e.to_string())?;
+    let conflicts = policy_engine::find_conflicts(&created, &existing);
+
+    if !conflicts.is_empty() {
+        database::log_audit(
+            &pool,
+            "policy_conflict",
+            "policy",
+            &id,
+            "admin",
+            &format!("{} conflito(s) detectado(s) ao criar '{}'", conflicts.len(), name),
+        )
+        .ok();
+    }
+
+    agent_hub::notify_policy_change(machine_id.as_deref());
+    Ok(PolicyCreationResult { id, conflicts })
+}
+
+/// Verifica conflitos de uma política antes de a criar
+#[tauri::command]
+pub async fn check_policy_conflicts(
+    machine_id: Option<String>,
+    policy_type: String,
+    priority: i32,
+    target: String,
+    action: String,
+    config_json: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<PolicyConflict>, String> {
+    let candidate = Policy {
+        id: String::new(),
+        machine_id,
+        name: "Nova Política".to_string(),
+        description: String::new(),
+        policy_type,
+        priority,
+        target,
+        action,
+        config_json,
+        reason: String::new(),
+        created_by: "admin".to_string(),
+        created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        enabled: true,
+        revision: 0,
+    };
+
+    let existing = database::list_policies(&pool, None).map_err(|e| e.to_string())?;
+    Ok(policy_engine::find_conflicts(&candidate, &existing))
+}
+
+/// Mostra que regra ganha em cada máquina e porquê
+#[tauri::command]
+pub async fn preview_effective_policies(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<EffectivePolicy>, String> {
+    let policies = database::list_policies(&pool, Some(&machine_id)).map_err(|e| e.to_string())?;
+    Ok(policy_engine::resolve_effective(&policies))
 }
 
 #[tauri::command]
@@ -42,5 +106,297 @@ pub async fn list_policies(
 
 #[tauri::command]
 pub async fn delete_policy(id: String, pool: State<'_, DbPool>) -> Result<(), String> {
-    database::delete_policy(&pool, &id).map_err(|e| e.to_string())
-}
\ No newline at end of file
+    let policy = database::get_policy(&pool, &id).map_err(|e| e.to_string())?;
+    database::delete_policy(&pool, &id).map_err(|e| e.to_string())?;
+
+    agent_hub::notify_policy_change(policy.machine_id.as_deref());
+    Ok(())
+}
+
+/// Cria uma nova revisão da política. Com `canary_group` a alteração só chega
+/// às máquinas com essa tag até ser promovida.
+#[tauri::command]
+pub async fn update_policy(
+    id: String,
+    update: PolicyUpdate,
+    pool: State<'_, DbPool>,
+) -> Result<i64, String> {
+    let current = database::get_policy(&pool, &id).map_err(|e| e.to_string())?;
+    let machine_id = current.machine_id.clone();
+    policy_engine::validate_quota(&current.policy_type, &update.config_json)?;
+    policy_engine::validate_schedule(&update.config_json)?;
+    let canary_group = update.canary_group;
+    let updated = Policy {
+        name: update.name,
+        description: update.description,
+        priority: update.priority,
+        target: update.target,
+        action: update.action,
+        config_json: update.config_json,
+        reason: update.reason,
+        enabled: update.enabled,
+        ..current
+    };
+
+    let revision = database::update_policy(&pool, &updated, canary_group.as_deref(), "admin")
+        .map_err(|e| e.to_string())?;
+
+    let details = match &canary_group {
+        Some(group) => format!("Revisão {} em canary (grupo '{}')", revision, group),
+        None => format!("Revisão {} aplicada a todas as máquinas", revision),
+    };
+    database::log_audit(&pool, "update_policy", "policy", &id, "admin", &details).ok();
+
+    agent_hub::notify_policy_change(machine_id.as_deref());
+    Ok(revision)
+}
+
+#[tauri::command]
+pub async fn promote_policy_revision(id: String, pool: State<'_, DbPool>) -> Result<i64, String> {
+    let revision = database::promote_policy_revision(&pool, &id).map_err(|e| e.to_string())?;
+
+    database::log_audit(
+        &pool,
+        "promote_policy_revision",
+        "policy",
+        &id,
+        "admin",
+        &format!("Revisão {} promovida para todas as máquinas", revision),
+    )
+    .ok();
+
+    notify_policy_machines(&pool, &id);
+    Ok(revision)
+}
+
+#[tauri::command]
+pub async fn rollback_policy(
+    id: String,
+    revision: i64,
+    pool: State<'_, DbPool>,
+) -> Result<i64, String> {
+    let new_revision = database::rollback_policy(&pool, &id, revision, "admin")
+        .map_err(|e| e.to_string())?;
+
+    database::log_audit(
+        &pool,
+        "rollback_policy",
+        "policy",
+        &id,
+        "admin",
+        &format!("Rollback para revisão {} (nova revisão {})", revision, new_revision),
+    )
+    .ok();
+
+    notify_policy_machines(&pool, &id);
+    Ok(new_revision)
+}
+
+/// Acorda os agentes afectados por uma política já existente
+fn notify_policy_machines(pool: &DbPool, policy_id: &str) {
+    match database::get_policy(pool, policy_id) {
+        Ok(policy) => agent_hub::notify_policy_change(policy.machine_id.as_deref()),
+        Err(_) => agent_hub::notify_all(agent_hub::POLICY_CHANGED),
+    }
+}
+
+#[tauri::command]
+pub async fn list_policy_revisions(
+    id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<PolicyRevision>, String> {
+    database::list_policy_revisions(&pool, &id).map_err(|e| e.to_string())
+}
+
+/// Que revisão de cada política os agentes reportam estar a aplicar
+#[tauri::command]
+pub async fn get_policy_rollout_status(
+    machine_id: Option<String>,
+    policy_id: Option<String>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<MachinePolicyRevision>, String> {
+    database::get_machine_policy_revisions(&pool, machine_id.as_deref(), policy_id.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Resultado da aplicação das políticas numa máquina
+#[tauri::command]
+pub async fn get_machine_enforcement(
+    machine_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<PolicyEnforcementStatus>, String> {
+    database::get_policy_enforcement(&pool, Some(&machine_id), None).map_err(|e| e.to_string())
+}
+
+/// Conformidade de uma política em todas as máquinas abrangidas
+#[tauri::command]
+pub async fn get_policy_compliance(
+    policy_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<PolicyComplianceSummary, String> {
+    let policy = database::get_policy(&pool, &policy_id).map_err(|e| e.to_string())?;
+    let results = database::get_policy_enforcement(&pool, None, Some(&policy_id))
+        .map_err(|e| e.to_string())?;
+
+    let targeted_machines = match &policy.machine_id {
+        Some(_) => 1,
+        None => database::list_machines(&pool).map_err(|e| e.to_string())?.len() as i64,
+    };
+
+    let applied = results.iter().filter(|r| r.status == "applied").count() as i64;
+    let failed = results.iter().filter(|r| r.status == "failed").count() as i64;
+    let total_kills = results.iter().map(|r| r.kill_count).sum();
+    let last_hit = results.iter().filter_map(|r| r.last_hit.clone()).max();
+
+    Ok(PolicyComplianceSummary {
+        policy_id,
+        targeted_machines,
+        applied,
+        failed,
+        pending: (targeted_machines - applied - failed).max(0),
+        total_kills,
+        last_hit,
+        results,
+    })
+}
+
+/// Executáveis bloqueados (ou que seriam bloqueados) pela allowlist
+#[tauri::command]
+pub async fn list_app_violations(
+    policy_id: Option<String>,
+    machine_id: Option<String>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<AppViolation>, String> {
+    database::list_app_violations(&pool, policy_id.as_deref(), machine_id.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Agrega as violações do modo aprendizagem para construir a allowlist a partir do uso real
+#[tauri::command]
+pub async fn get_allowlist_candidates(
+    policy_id: String,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<AllowlistCandidate>, String> {
+    let violations = database::list_app_violations(&pool, Some(&policy_id), None)
+        .map_err(|e| e.to_string())?;
+
+    // Agrupa pelo hash (mesmo binário em caminhos diferentes) ou, sem hash, pelo caminho
+    let mut groups: std::collections::BTreeMap<String, Vec<&AppViolation>> = Default::default();
+    for v in &violations {
+        let key = v.sha256.clone().unwrap_or_else(|| v.exe_path.to_lowercase());
+        groups.entry(key).or_default().push(v);
+    }
+
+    let mut candidates: Vec<AllowlistCandidate> = groups
+        .into_values()
+        .map(|group| {
+            let first = group[0];
+            let mut machines: Vec<&str> = group.iter().map(|v| v.machine_id.as_str()).collect();
+            machines.sort();
+            machines.dedup();
+
+            // Publisher assinado cobre actualizações; sem assinatura, o hash é o mais seguro
+            let (suggested_match_type, suggested_target) = match (&first.publisher, &first.sha256) {
+                (Some(publisher), _) => ("publisher", publisher.clone()),
+                (None, Some(hash)) => ("hash", hash.clone()),
+                (None, None) => ("path", first.exe_path.clone()),
+            };
+
+            AllowlistCandidate {
+                process_name: first.process_name.clone(),
+                exe_path: first.exe_path.clone(),
+                sha256: first.sha256.clone(),
+                publisher: first.publisher.clone(),
+                original_filename: first.original_filename.clone(),
+                machine_count: machines.len() as i64,
+                hit_count: group.iter().map(|v| v.hit_count).sum(),
+                last_seen: group.iter().map(|v| v.last_seen.clone()).max().unwrap_or_default(),
+                suggested_match_type: suggested_match_type.to_string(),
+                suggested_target,
+            }
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.machine_count.cmp(&a.machine_count).then(b.hit_count.cmp(&a.hit_count)));
+    Ok(candidates)
+}
+
+/// Correspondências de uma política em modo "alert"
+#[tauri::command]
+pub async fn list_policy_matches(
+    policy_id: String,
+    machine_id: Option<String>,
+    days: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<PolicyMatch>, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days.unwrap_or(7))).to_rfc3339();
+    database::list_policy_matches(&pool, &policy_id, machine_id.as_deref(), &since)
+        .map_err(|e| e.to_string())
+}
+
+/// Raio de impacto de uma política: máquinas, processos e utilizadores afectados
+#[tauri::command]
+pub async fn get_policy_impact(
+    policy_id: String,
+    days: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<PolicyImpact, String> {
+    let days = days.unwrap_or(7);
+    let since = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    let matches = database::list_policy_matches(&pool, &policy_id, None, &since)
+        .map_err(|e| e.to_string())?;
+
+    let mut machines = std::collections::HashSet::new();
+    let mut users = std::collections::HashSet::new();
+    let mut subjects: std::collections::HashMap<&str, i64> = Default::default();
+    let mut affected_processes = 0;
+
+    for m in &matches {
+        machines.insert(m.machine_id.as_str());
+        if let Some(user) = &m.user {
+            users.insert(user.as_str());
+        }
+        if m.process_name.is_some() {
+            affected_processes += m.hit_count;
+        }
+        *subjects.entry(m.subject.as_str()).or_insert(0) += m.hit_count;
+    }
+
+    let mut top_subjects: Vec<(String, i64)> =
+        subjects.into_iter().map(|(s, n)| (s.to_string(), n)).collect();
+    top_subjects.sort_by_key(|s| std::cmp::Reverse(s.1));
+    top_subjects.truncate(10);
+
+    Ok(PolicyImpact {
+        policy_id,
+        days,
+        affected_machines: machines.len() as i64,
+        affected_processes,
+        affected_users: users.len() as i64,
+        total_matches: matches.iter().map(|m| m.hit_count).sum(),
+        top_subjects,
+    })
+}
+
+/// Domínios consultados através do filtro DNS dos agentes (mais bloqueados primeiro)
+#[tauri::command]
+pub async fn get_dns_stats(
+    machine_id: Option<String>,
+    days: Option<i64>,
+    blocked_only: Option<bool>,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<DnsDomainSummary>, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days.unwrap_or(7)))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    database::get_dns_summary(
+        &pool,
+        machine_id.as_deref(),
+        &since,
+        blocked_only.unwrap_or(false),
+        limit.unwrap_or(100),
+    )
+    .map_err(|e| e.to_string())
+}
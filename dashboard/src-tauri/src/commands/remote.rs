@@ -0,0 +1,182 @@
+use crate::agent_hub;
+use crate::auth;
+use crate::database::{self, DbPool};
+use crate::models::{AgentCommand, ApprovedScript};
+use tauri::State;
+
+// Validade por omissão de um comando que o agente ainda não recebeu
+const DEFAULT_TTL_MINUTES: i64 = 60;
+
+/// Coloca um comando na fila da máquina, depois de validar os parâmetros
+pub(crate) fn enqueue(
+    pool: &DbPool,
+    machine_id: &str,
+    command_type: &str,
+    payload: serde_json::Value,
+    ttl_minutes: Option<i64>,
+) -> Result<String, String> {
+    validate_command(pool, machine_id, command_type, &payload)?;
+
+    let ttl = ttl_minutes.unwrap_or(DEFAULT_TTL_MINUTES).clamp(1, 7 * 24 * 60);
+    let id = database::enqueue_agent_command(
+        pool,
+        machine_id,
+        command_type,
+        &payload.to_string(),
+        "admin",
+        ttl,
+    )
+    .map_err(|e| e.to_string())?;
+
+    database::log_audit(
+        pool,
+        "enqueue_command",
+        "agent_command",
+        &id,
+        "admin",
+        &format!("Machine: {} | {} {}", machine_id, command_type, payload),
+    )
+    .ok();
+
+    // O agente com long-poll vai buscar o comando de imediato
+    agent_hub::notify_machine(machine_id, agent_hub::COMMAND_QUEUED);
+    Ok(id)
+}
+
+fn validate_command(
+    pool: &DbPool,
+    machine_id: &str,
+    command_type: &str,
+    payload: &serde_json::Value,
+) -> Result<(), String> {
+    let has_str = |field: &str| {
+        payload
+            .get(field)
+            .and_then(|v| v.as_str())
+            .map(|v| !v.trim().is_empty())
+            .unwrap_or(false)
+    };
+
+    match command_type {
+        "kill_process" => {
+            if payload.get("pid").and_then(|v| v.as_u64()).is_none() && !has_str("process_name") {
+                return Err("kill_process requer 'pid' ou 'process_name'".to_string());
+            }
+        }
+        "restart_service" => {
+            if !has_str("service_name") {
+                return Err("restart_service requer 'service_name'".to_string());
+            }
+        }
+        "run_script" => {
+            let script_id = payload.get("script_id").and_then(|v| v.as_str()).unwrap_or_default();
+            let exists = database::get_approved_script(pool, script_id)
+                .map_err(|e| e.to_string())?
+                .is_some();
+            if !exists {
+                return Err("run_script requer o 'script_id' de um script aprovado".to_string());
+            }
+        }
+        "uninstall_app" => {
+            if !has_str("name") {
+                return Err("uninstall_app requer 'name'".to_string());
+            }
+        }
+        "screenshot" => {
+            if !super::screenshots::screenshots_allowed(pool, machine_id)? {
+                return Err("Capturas de ecrã não permitidas por política nesta máquina".to_string());
+            }
+        }
+        "collect_now" => {}
+        other => return Err(format!("Tipo de comando desconhecido: {}", other)),
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn enqueue_agent_command(
+    machine_id: String,
+    command_type: String,
+    payload: Option<serde_json::Value>,
+    ttl_minutes: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<String, String> {
+    let payload = payload.unwrap_or_else(|| serde_json::json!({}));
+    enqueue(&pool, &machine_id, &command_type, payload, ttl_minutes)
+}
+
+#[tauri::command]
+pub async fn list_agent_commands(
+    machine_id: Option<String>,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<AgentCommand>, String> {
+    database::list_agent_commands(&pool, machine_id.as_deref(), limit.unwrap_or(100))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn cancel_agent_command(command_id: String, pool: State<'_, DbPool>) -> Result<(), String> {
+    if !database::cancel_agent_command(&pool, &command_id).map_err(|e| e.to_string())? {
+        return Err("O comando já foi entregue ao agente ou terminou".to_string());
+    }
+
+    database::log_audit(&pool, "cancel_command", "agent_command", &command_id, "admin", "").ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_approved_script(
+    name: String,
+    description: Option<String>,
+    interpreter: String,
+    content: String,
+    pool: State<'_, DbPool>,
+) -> Result<String, String> {
+    if !matches!(interpreter.as_str(), "powershell" | "cmd" | "sh") {
+        return Err(format!("Interpretador não suportado: {}", interpreter));
+    }
+    if content.trim().is_empty() {
+        return Err("O script está vazio".to_string());
+    }
+
+    let id = database::create_approved_script(
+        &pool,
+        &name,
+        description.as_deref().unwrap_or(""),
+        &interpreter,
+        &content,
+        "admin",
+    )
+    .map_err(|e| e.to_string())?;
+
+    // O agente só executa scripts com assinatura válida da chave do servidor
+    let script = database::get_approved_script(&pool, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Script aprovado não encontrado")?;
+    let signature = auth::sign_script(&pool, &script.id, &script.interpreter, &script.sha256)
+        .map_err(|e| e.to_string())?;
+    database::set_script_signature(&pool, &id, &signature).map_err(|e| e.to_string())?;
+
+    database::log_audit(&pool, "approve_script", "script", &id, "admin", &name).ok();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn list_approved_scripts(pool: State<'_, DbPool>) -> Result<Vec<ApprovedScript>, String> {
+    database::list_approved_scripts(&pool).map_err(|e| e.to_string())
+}
+
+/// Chave pública a configurar nos agentes (ScriptPublicKey / script_public_key)
+#[tauri::command]
+pub async fn get_script_signing_key(pool: State<'_, DbPool>) -> Result<String, String> {
+    auth::script_public_key(&pool).map_err(|e| e.to_string())
+}
+
+/// Revoga o token do agente; o próximo relatório da máquina recebe um novo
+#[tauri::command]
+pub async fn reset_machine_credential(machine_id: String, pool: State<'_, DbPool>) -> Result<bool, String> {
+    let reset = auth::reset_machine_token(&pool, &machine_id).map_err(|e| e.to_string())?;
+    database::log_audit(&pool, "reset_machine_credential", "machine", &machine_id, "admin", "").ok();
+    Ok(reset)
+}
@@ -0,0 +1,158 @@
+use crate::database::{self, DbPool};
+use crate::models::*;
+use crate::policy_engine;
+use chrono::{Duration, Local};
+use tauri::State;
+
+const DEFAULT_RANGE_DAYS: i64 = 7;
+const DEFAULT_TREND_WEEKS: i64 = 8;
+
+/// Intervalo de datas (YYYY-MM-DD, inclusive); por omissão os últimos 7 dias
+fn date_range(from: Option<String>, to: Option<String>) -> (String, String) {
+    let today = Local::now().date_naive();
+    let from = from.unwrap_or_else(|| (today - Duration::days(DEFAULT_RANGE_DAYS - 1)).to_string());
+    let to = to.unwrap_or_else(|| today.to_string());
+    (from, to)
+}
+
+fn validate_group_by(group_by: Option<String>) -> Result<String, String> {
+    let group_by = group_by.unwrap_or_else(|| "user".to_string());
+    match group_by.as_str() {
+        "user" | "department" => Ok(group_by),
+        other => Err(format!("Agrupamento desconhecido: {}", other)),
+    }
+}
+
+/// Mesma normalização do agente (`clean_process_name`): nome exacto do executável sem
+/// extensão, em maiúsculas
+fn app_key(name: &str) -> String {
+    let lower = name.trim().to_lowercase();
+    let stem = [".exe", ".bin", ".app"]
+        .iter()
+        .find_map(|ext| lower.strip_suffix(ext))
+        .unwrap_or(&lower);
+    stem.to_uppercase()
+}
+
+#[tauri::command]
+pub async fn get_top_apps(
+    group_by: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<UsageTopApp>, String> {
+    let group_by = validate_group_by(group_by)?;
+    let (from, to) = date_range(from, to);
+    database::screen_time_top_apps(&pool, &group_by, &from, &to, limit.unwrap_or(5))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_category_totals(
+    group_by: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<UsageCategoryTotal>, String> {
+    let group_by = validate_group_by(group_by)?;
+    let (from, to) = date_range(from, to);
+    database::screen_time_category_totals(&pool, &group_by, &from, &to).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_weekly_usage_trend(
+    machine_id: Option<String>,
+    weeks: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<UsageWeeklyTrend>, String> {
+    let weeks = weeks.unwrap_or(DEFAULT_TREND_WEEKS).clamp(1, 52);
+    let from = (Local::now().date_naive() - Duration::weeks(weeks)).to_string();
+    database::screen_time_weekly_trend(&pool, machine_id.as_deref(), &from).map_err(|e| e.to_string())
+}
+
+/// Utilização de hoje nas políticas com `daily_quota_minutes`. Políticas globais só
+/// listam as máquinas que já usaram a app hoje
+#[tauri::command]
+pub async fn get_quota_usage(pool: State<'_, DbPool>) -> Result<Vec<QuotaUsage>, String> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let policies = database::list_policies(&pool, None).map_err(|e| e.to_string())?;
+    let machines = database::list_machines(&pool).map_err(|e| e.to_string())?;
+    let entries = database::get_screen_time_for_date(&pool, &today).map_err(|e| e.to_string())?;
+
+    let mut usage = Vec::new();
+    // Só as quotas que o agente aplica (regras de aplicação por nome do executável)
+    let enforced = policies
+        .iter()
+        .filter(|p| p.enabled && policy_engine::validate_quota(&p.policy_type, &p.config_json).is_ok());
+    for policy in enforced {
+        let quota_minutes = serde_json::from_str::<serde_json::Value>(&policy.config_json)
+            .ok()
+            .and_then(|c| c.get("daily_quota_minutes").and_then(|v| v.as_u64()));
+        let Some(quota_minutes) = quota_minutes else {
+            continue;
+        };
+
+        let key = app_key(&policy.target);
+        if key.is_empty() {
+            continue;
+        }
+
+        for machine in &machines {
+            if policy.machine_id.as_deref().is_some_and(|id| id != machine.machine_id) {
+                continue;
+            }
+
+            let used_seconds: u64 = entries
+                .iter()
+                .filter(|e| e.machine_id == machine.machine_id && e.app_name == key)
+                .map(|e| e.total_seconds)
+                .sum();
+            if policy.machine_id.is_none() && used_seconds == 0 {
+                continue;
+            }
+
+            let quota_seconds = quota_minutes * 60;
+            usage.push(QuotaUsage {
+                policy_id: policy.id.clone(),
+                policy_name: policy.name.clone(),
+                machine_id: machine.machine_id.clone(),
+                hostname: machine.custom_name.clone().unwrap_or_else(|| machine.hostname.clone()),
+                target: policy.target.clone(),
+                quota_seconds,
+                used_seconds,
+                exceeded: used_seconds >= quota_seconds,
+            });
+        }
+    }
+
+    usage.sort_by(|a, b| {
+        let ratio = |u: &QuotaUsage| u.used_seconds as f64 / u.quota_seconds.max(1) as f64;
+        ratio(b).total_cmp(&ratio(a))
+    });
+    Ok(usage)
+}
+
+#[tauri::command]
+pub async fn list_screen_time_categories(pool: State<'_, DbPool>) -> Result<Vec<ScreenTimeCategory>, String> {
+    database::list_screen_time_categories(&pool).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_screen_time_category(
+    category: String,
+    productivity: String,
+    pool: State<'_, DbPool>,
+) -> Result<(), String> {
+    if !matches!(productivity.as_str(), "productive" | "unproductive" | "neutral") {
+        return Err(format!("Classificação inválida: {}", productivity));
+    }
+    let category = category.trim().to_lowercase();
+    if category.is_empty() {
+        return Err("Categoria vazia".to_string());
+    }
+
+    database::set_screen_time_category(&pool, &category, &productivity).map_err(|e| e.to_string())?;
+    database::log_audit(&pool, "set_screen_time_category", "screen_time", &category, "admin", &productivity).ok();
+    Ok(())
+}
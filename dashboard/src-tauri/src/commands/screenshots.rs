@@ -1,11 +1,109 @@
-use crate::database::DbPool;
+use crate::database::{self, DbPool};
+use crate::models::Screenshot;
+use crate::policy_engine;
+use base64::Engine;
+use std::path::PathBuf;
 use tauri::State;
 
+// Retenção: por máquina ficam as mais recentes, e nenhuma além da idade máxima
+pub(crate) const MAX_SCREENSHOTS_PER_MACHINE: i64 = 50;
+pub(crate) const MAX_SCREENSHOT_AGE_DAYS: i64 = 30;
+
+fn screenshots_dir() -> PathBuf {
+    database::data_dir().join("screenshots")
+}
+
+fn screenshot_path(id: &str) -> PathBuf {
+    screenshots_dir().join(format!("{}.jpg", id))
+}
+
+/// Capturas só com uma política "monitoring/screenshot" em "allow" a vigorar na máquina
+pub(crate) fn screenshots_allowed(pool: &DbPool, machine_id: &str) -> Result<bool, String> {
+    let policies = database::list_policies_for_machine(pool, machine_id)
+        .map(policy_engine::policies_for_agent)
+        .map_err(|e| e.to_string())?;
+
+    Ok(policies
+        .iter()
+        .find(|p| p.policy_type == "monitoring" && p.target.eq_ignore_ascii_case("screenshot"))
+        .map(|p| p.action == "allow")
+        .unwrap_or(false))
+}
+
+/// Guarda a imagem enviada pelo agente e aplica a retenção da máquina
+pub(crate) fn store(pool: &DbPool, shot: &Screenshot, jpeg: &[u8]) -> Result<(), String> {
+    std::fs::create_dir_all(screenshots_dir()).map_err(|e| e.to_string())?;
+    std::fs::write(screenshot_path(&shot.id), jpeg).map_err(|e| e.to_string())?;
+
+    if let Err(e) = database::insert_screenshot(pool, shot) {
+        let _ = std::fs::remove_file(screenshot_path(&shot.id));
+        return Err(e.to_string());
+    }
+
+    let removed = database::prune_screenshots(
+        pool,
+        &shot.machine_id,
+        MAX_SCREENSHOTS_PER_MACHINE,
+        MAX_SCREENSHOT_AGE_DAYS,
+    )
+    .map_err(|e| e.to_string())?;
+    for id in removed {
+        let _ = std::fs::remove_file(screenshot_path(&id));
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn request_screenshot(
     machine_id: String,
     pool: State<'_, DbPool>,
 ) -> Result<String, String> {
-    // Placeholder - screenshot será capturado pelo agente na próxima conexão
-    Ok(format!("Screenshot requested for machine {}", machine_id))
+    // O agente recebe o comando na próxima ligação; devolve o ID para acompanhar o estado
+    super::remote::enqueue(&pool, &machine_id, "screenshot", serde_json::json!({}), Some(15))
+}
+
+#[tauri::command]
+pub async fn list_screenshots(
+    machine_id: String,
+    limit: Option<i64>,
+    pool: State<'_, DbPool>,
+) -> Result<Vec<Screenshot>, String> {
+    database::list_screenshots(&pool, &machine_id, limit.unwrap_or(MAX_SCREENSHOTS_PER_MACHINE))
+        .map_err(|e| e.to_string())
+}
+
+/// Devolve a imagem como data URL; cada visualização fica na auditoria
+#[tauri::command]
+pub async fn get_screenshot_image(id: String, pool: State<'_, DbPool>) -> Result<String, String> {
+    let shot = database::get_screenshot(&pool, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Captura não encontrada".to_string())?;
+
+    let jpeg = std::fs::read(screenshot_path(&id)).map_err(|e| format!("Ficheiro da captura em falta: {}", e))?;
+
+    database::log_audit(
+        &pool,
+        "view_screenshot",
+        "screenshot",
+        &id,
+        "admin",
+        &format!("Machine: {} | Capturada em {}", shot.machine_id, shot.captured_at),
+    )
+    .ok();
+
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(jpeg)
+    ))
+}
+
+#[tauri::command]
+pub async fn delete_screenshot(id: String, pool: State<'_, DbPool>) -> Result<(), String> {
+    if !database::delete_screenshot(&pool, &id).map_err(|e| e.to_string())? {
+        return Err("Captura não encontrada".to_string());
+    }
+    let _ = std::fs::remove_file(screenshot_path(&id));
+
+    database::log_audit(&pool, "delete_screenshot", "screenshot", &id, "admin", "").ok();
+    Ok(())
 }
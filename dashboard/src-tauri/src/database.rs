@@ -3,7 +3,9 @@
 // ============================================================
 
 use chrono::Utc;
-use rusqlite::{params, Connection, Result};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 use crate::models::*;
@@ -69,6 +71,283 @@ pub fn initialize(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_software_machine ON software(machine_id);
         CREATE INDEX IF NOT EXISTS idx_software_name ON software(name);
 
+        -- Serviços (Windows) / unidades systemd (Linux)
+        CREATE TABLE IF NOT EXISTS services (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id   TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            name         TEXT NOT NULL,
+            display_name TEXT NOT NULL DEFAULT '',
+            state        TEXT NOT NULL DEFAULT '',
+            start_mode   TEXT NOT NULL DEFAULT ''
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_services_machine ON services(machine_id);
+
+        -- Interfaces de rede, endereços e rotas (último relatório)
+        CREATE TABLE IF NOT EXISTS network_adapters (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id   TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            name         TEXT NOT NULL,
+            description  TEXT NOT NULL DEFAULT '',
+            kind         TEXT NOT NULL DEFAULT 'physical',
+            mac_address  TEXT,
+            gateways     TEXT NOT NULL DEFAULT '',
+            gateway_mac  TEXT,
+            dns_servers  TEXT NOT NULL DEFAULT '',
+            dhcp_enabled INTEGER,
+            speed_mbps   INTEGER,
+            is_up        INTEGER NOT NULL DEFAULT 0,
+            is_wireless  INTEGER NOT NULL DEFAULT 0,
+            is_primary   INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_network_adapters_machine ON network_adapters(machine_id);
+
+        CREATE TABLE IF NOT EXISTS network_addresses (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id    TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            adapter_name  TEXT NOT NULL,
+            family        TEXT NOT NULL,
+            address       TEXT NOT NULL,
+            prefix_length INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_network_addresses_machine ON network_addresses(machine_id);
+        CREATE INDEX IF NOT EXISTS idx_network_addresses_address ON network_addresses(address);
+
+        CREATE TABLE IF NOT EXISTS network_routes (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id    TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            interface     TEXT NOT NULL,
+            destination   TEXT NOT NULL,
+            prefix_length INTEGER NOT NULL,
+            gateway       TEXT,
+            metric        INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_network_routes_machine ON network_routes(machine_id);
+
+        -- Histórico de IP/MAC por máquina (ended_at NULL = atribuição actual)
+        CREATE TABLE IF NOT EXISTS ip_assignments (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id   TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            adapter_name TEXT NOT NULL,
+            ip_address   TEXT NOT NULL,
+            mac_address  TEXT,
+            first_seen   TEXT NOT NULL,
+            last_seen    TEXT NOT NULL,
+            ended_at     TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_ip_assignments_machine ON ip_assignments(machine_id, ended_at);
+        CREATE INDEX IF NOT EXISTS idx_ip_assignments_ip ON ip_assignments(ip_address);
+        CREATE INDEX IF NOT EXISTS idx_ip_assignments_mac ON ip_assignments(mac_address);
+
+        CREATE TABLE IF NOT EXISTS ip_change_events (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id   TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            adapter_name TEXT NOT NULL,
+            old_ip       TEXT NOT NULL,
+            new_ip       TEXT NOT NULL,
+            mac_address  TEXT,
+            reason       TEXT NOT NULL,
+            unexpected   INTEGER NOT NULL DEFAULT 0,
+            detected_at  TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_ip_change_events_machine ON ip_change_events(machine_id, detected_at);
+
+        -- Inventário de hardware por componente (removed_at NULL = instalado)
+        CREATE TABLE IF NOT EXISTS memory_modules (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id    TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            component_key TEXT NOT NULL,
+            slot          TEXT NOT NULL DEFAULT '',
+            size_mb       INTEGER NOT NULL DEFAULT 0,
+            speed_mts     INTEGER,
+            memory_type   TEXT,
+            manufacturer  TEXT,
+            part_number   TEXT,
+            serial_number TEXT,
+            first_seen    TEXT NOT NULL,
+            last_seen     TEXT NOT NULL,
+            removed_at    TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_memory_modules_machine ON memory_modules(machine_id, removed_at);
+
+        CREATE TABLE IF NOT EXISTS gpus (
+            id             INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id     TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            component_key  TEXT NOT NULL,
+            name           TEXT NOT NULL,
+            vendor         TEXT,
+            vram_mb        INTEGER,
+            driver_version TEXT,
+            first_seen     TEXT NOT NULL,
+            last_seen      TEXT NOT NULL,
+            removed_at     TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_gpus_machine ON gpus(machine_id, removed_at);
+
+        CREATE TABLE IF NOT EXISTS monitors (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id    TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            component_key TEXT NOT NULL,
+            manufacturer  TEXT,
+            model         TEXT,
+            serial_number TEXT,
+            width         INTEGER,
+            height        INTEGER,
+            first_seen    TEXT NOT NULL,
+            last_seen     TEXT NOT NULL,
+            removed_at    TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_monitors_machine ON monitors(machine_id, removed_at);
+        CREATE INDEX IF NOT EXISTS idx_monitors_serial ON monitors(serial_number);
+
+        CREATE TABLE IF NOT EXISTS batteries (
+            id                       INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id               TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            component_key            TEXT NOT NULL,
+            name                     TEXT,
+            manufacturer             TEXT,
+            serial_number            TEXT,
+            chemistry                TEXT,
+            design_capacity_mwh      INTEGER,
+            full_charge_capacity_mwh INTEGER,
+            cycle_count              INTEGER,
+            first_seen               TEXT NOT NULL,
+            last_seen                TEXT NOT NULL,
+            removed_at               TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_batteries_machine ON batteries(machine_id, removed_at);
+
+        CREATE TABLE IF NOT EXISTS battery_readings (
+            id                       INTEGER PRIMARY KEY AUTOINCREMENT,
+            battery_id               INTEGER NOT NULL REFERENCES batteries(id) ON DELETE CASCADE,
+            design_capacity_mwh      INTEGER,
+            full_charge_capacity_mwh INTEGER,
+            cycle_count              INTEGER,
+            recorded_at              TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_battery_readings_battery ON battery_readings(battery_id, recorded_at);
+
+        CREATE TABLE IF NOT EXISTS physical_disks (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id    TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            component_key TEXT NOT NULL,
+            model         TEXT NOT NULL,
+            serial_number TEXT,
+            media_type    TEXT NOT NULL DEFAULT 'unknown',
+            bus_type      TEXT,
+            size_gb       REAL NOT NULL DEFAULT 0,
+            health        TEXT,
+            first_seen    TEXT NOT NULL,
+            last_seen     TEXT NOT NULL,
+            removed_at    TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_physical_disks_machine ON physical_disks(machine_id, removed_at);
+        CREATE INDEX IF NOT EXISTS idx_physical_disks_serial ON physical_disks(serial_number);
+
+        CREATE TABLE IF NOT EXISTS disk_health_history (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            disk_id     INTEGER NOT NULL REFERENCES physical_disks(id) ON DELETE CASCADE,
+            health      TEXT,
+            recorded_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_disk_health_history_disk ON disk_health_history(disk_id, recorded_at);
+
+        -- Alterações de hardware detectadas entre relatórios
+        CREATE TABLE IF NOT EXISTS hardware_changes (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id  TEXT NOT NULL REFERENCES machines(machine_id) ON DELETE CASCADE,
+            component   TEXT NOT NULL,
+            change_type TEXT NOT NULL, -- 'changed' | 'added' | 'removed'
+            old_value   TEXT,
+            new_value   TEXT,
+            suspicious  INTEGER NOT NULL DEFAULT 0,
+            detected_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_hardware_changes_machine ON hardware_changes(machine_id, detected_at);
+
+        -- Componentes cujas mudanças contam como suspeitas
+        CREATE TABLE IF NOT EXISTS hardware_change_rules (
+            component  TEXT PRIMARY KEY,
+            suspicious INTEGER NOT NULL DEFAULT 0
+        );
+        INSERT OR IGNORE INTO hardware_change_rules (component, suspicious) VALUES
+            ('serial_number', 1),
+            ('motherboard', 1),
+            ('bios', 0),
+            ('disk', 0),
+            ('memory', 0),
+            ('gpu', 0);
+
+        -- Impressões digitais de hardware vistas em cada machine_id
+        CREATE TABLE IF NOT EXISTS machine_fingerprints (
+            machine_id   TEXT NOT NULL,
+            fingerprint  TEXT NOT NULL,
+            system_uuid  TEXT,
+            bios_serial  TEXT,
+            board_serial TEXT,
+            primary_mac  TEXT,
+            hostname     TEXT,
+            first_seen   TEXT NOT NULL,
+            last_seen    TEXT NOT NULL,
+            report_count INTEGER NOT NULL DEFAULT 1,
+            PRIMARY KEY (machine_id, fingerprint)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_machine_fingerprints_fingerprint ON machine_fingerprints(fingerprint);
+
+        -- Clones e registos duplicados encontrados pelas impressões digitais
+        CREATE TABLE IF NOT EXISTS identity_conflicts (
+            id                INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind              TEXT NOT NULL, -- 'clone' | 'duplicate'
+            machine_id        TEXT NOT NULL,
+            hostname          TEXT,
+            fingerprint       TEXT NOT NULL,
+            other_machine_id  TEXT NOT NULL,
+            other_hostname    TEXT,
+            other_fingerprint TEXT NOT NULL,
+            detected_at       TEXT NOT NULL,
+            last_seen         TEXT NOT NULL,
+            resolved_at       TEXT,
+            resolution        TEXT -- 'merged' | 'dismissed'
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_identity_conflicts_open ON identity_conflicts(resolved_at, last_seen);
+
+        -- machine_id de registos unidos a outro: os relatórios e comandos passam para o destino
+        CREATE TABLE IF NOT EXISTS machine_aliases (
+            alias_id   TEXT PRIMARY KEY,
+            machine_id TEXT NOT NULL,
+            merged_at  TEXT NOT NULL
+        );
+
+        -- Equipamentos vistos nas tabelas ARP/NDP dos agentes (um por MAC)
+        CREATE TABLE IF NOT EXISTS discovered_devices (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            mac_address  TEXT NOT NULL UNIQUE,
+            ip_address   TEXT NOT NULL,
+            vendor       TEXT,
+            random_mac   INTEGER NOT NULL DEFAULT 0,
+            interface    TEXT NOT NULL DEFAULT '',
+            reported_by  TEXT NOT NULL,
+            first_seen   TEXT NOT NULL,
+            last_seen    TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_discovered_devices_ip ON discovered_devices(ip_address);
+
         -- Processos activos (NOVO)
         CREATE TABLE IF NOT EXISTS processes (
             id          INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -327,8 +606,14 @@ pub fn initialize(conn: &Connection) -> Result<()> {
         "ALTER TABLE machines ADD COLUMN current_user TEXT DEFAULT ''",
         [],
     );
+    let _ = conn.execute(
+        "ALTER TABLE network_details ADD COLUMN dns_servers TEXT DEFAULT ''",
+        [],
+    );
     conn.execute("ALTER TABLE machines ADD COLUMN current_user TEXT", [])
         .ok();
+    conn.execute("ALTER TABLE network_adapters ADD COLUMN gateway_mac TEXT", [])
+        .ok();
     conn.execute("ALTER TABLE machines ADD COLUMN primary_user TEXT", [])
         .ok();
     conn.execute("ALTER TABLE machines ADD COLUMN department TEXT", [])
@@ -363,13 +648,244 @@ pub fn initialize(conn: &Connection) -> Result<()> {
         "CREATE INDEX IF NOT EXISTS idx_policies_machine ON policies(machine_id)",
         [],
     )?;
+
+    // Revisões imutáveis de políticas (rollout canary / rollback)
+    conn.execute("ALTER TABLE policies ADD COLUMN revision INTEGER NOT NULL DEFAULT 1", [])
+        .ok();
+    conn.execute_batch("
+        CREATE TABLE IF NOT EXISTS policy_revisions (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            policy_id    TEXT NOT NULL,
+            revision     INTEGER NOT NULL,
+            snapshot     TEXT NOT NULL,
+            state        TEXT NOT NULL, -- 'active', 'canary', 'superseded', 'rolled_back', 'deleted'
+            canary_group TEXT,
+            note         TEXT NOT NULL DEFAULT '',
+            created_by   TEXT NOT NULL DEFAULT 'admin',
+            created_at   TEXT NOT NULL,
+            UNIQUE(policy_id, revision)
+        );
+        CREATE INDEX IF NOT EXISTS idx_policy_revisions_state ON policy_revisions(state);
+
+        -- Resultado da aplicação de cada política por máquina
+        CREATE TABLE IF NOT EXISTS policy_enforcement (
+            machine_id  TEXT NOT NULL,
+            policy_id   TEXT NOT NULL,
+            revision    INTEGER NOT NULL DEFAULT 0,
+            status      TEXT NOT NULL, -- 'applied' | 'failed'
+            error       TEXT,
+            kill_count  INTEGER NOT NULL DEFAULT 0,
+            last_hit    TEXT,
+            updated_at  TEXT NOT NULL,
+            PRIMARY KEY (machine_id, policy_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_policy_enforcement_policy ON policy_enforcement(policy_id);
+
+        -- Revisão que cada agente reporta estar a aplicar
+        CREATE TABLE IF NOT EXISTS machine_policy_revisions (
+            machine_id  TEXT NOT NULL,
+            policy_id   TEXT NOT NULL,
+            revision    INTEGER NOT NULL,
+            reported_at TEXT NOT NULL,
+            PRIMARY KEY (machine_id, policy_id)
+        );
+
+        -- Executáveis fora da allowlist (bloqueados ou registados em modo aprendizagem)
+        CREATE TABLE IF NOT EXISTS app_violations (
+            machine_id        TEXT NOT NULL,
+            policy_id         TEXT NOT NULL,
+            exe_path          TEXT NOT NULL,
+            action            TEXT NOT NULL, -- 'blocked' | 'audited'
+            process_name      TEXT NOT NULL,
+            sha256            TEXT,
+            publisher         TEXT,
+            original_filename TEXT,
+            hit_count         INTEGER NOT NULL DEFAULT 0,
+            first_seen        TEXT NOT NULL,
+            last_seen         TEXT NOT NULL,
+            PRIMARY KEY (machine_id, policy_id, exe_path, action)
+        );
+        CREATE INDEX IF NOT EXISTS idx_app_violations_policy ON app_violations(policy_id);
+
+        -- Correspondências de políticas em modo 'alert' (uma linha por relatório)
+        CREATE TABLE IF NOT EXISTS policy_matches (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id   TEXT NOT NULL,
+            policy_id    TEXT NOT NULL,
+            policy_type  TEXT NOT NULL,
+            subject      TEXT NOT NULL,
+            process_name TEXT,
+            pid          INTEGER,
+            user         TEXT,
+            domain       TEXT,
+            remote_ip    TEXT,
+            hit_count    INTEGER NOT NULL DEFAULT 1,
+            first_seen   TEXT NOT NULL,
+            last_seen    TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_policy_matches_policy ON policy_matches(policy_id, last_seen);
+
+        -- Consultas DNS filtradas pelo resolver local do agente (agregadas por dia)
+        CREATE TABLE IF NOT EXISTS dns_query_stats (
+            machine_id  TEXT NOT NULL,
+            domain      TEXT NOT NULL,
+            date        TEXT NOT NULL,
+            queries     INTEGER NOT NULL DEFAULT 0,
+            blocked     INTEGER NOT NULL DEFAULT 0,
+            policy_id   TEXT,
+            last_query  TEXT NOT NULL,
+            PRIMARY KEY (machine_id, domain, date)
+        );
+        CREATE INDEX IF NOT EXISTS idx_dns_query_stats_date ON dns_query_stats(date);
+
+        -- Dispositivos USB ligados (substituídos a cada relatório)
+        CREATE TABLE IF NOT EXISTS usb_devices (
+            machine_id      TEXT NOT NULL,
+            device_id       TEXT NOT NULL,
+            name            TEXT NOT NULL,
+            vendor_id       TEXT NOT NULL,
+            product_id      TEXT NOT NULL,
+            serial          TEXT,
+            device_class    TEXT NOT NULL,
+            manufacturer    TEXT,
+            is_mass_storage INTEGER NOT NULL DEFAULT 0,
+            status          TEXT NOT NULL,
+            updated_at      TEXT NOT NULL,
+            PRIMARY KEY (machine_id, device_id)
+        );
+
+        -- Sockets abertos em cada máquina (substituídos a cada relatório)
+        CREATE TABLE IF NOT EXISTS network_connections (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id   TEXT NOT NULL,
+            protocol     TEXT NOT NULL,
+            pid          INTEGER NOT NULL DEFAULT 0,
+            process_name TEXT,
+            local_ip     TEXT NOT NULL,
+            local_port   INTEGER NOT NULL,
+            remote_ip    TEXT NOT NULL DEFAULT '',
+            remote_port  INTEGER NOT NULL DEFAULT 0,
+            remote_host  TEXT,
+            state        TEXT NOT NULL,
+            updated_at   TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_netconn_machine ON network_connections(machine_id);
+        CREATE INDEX IF NOT EXISTS idx_netconn_remote ON network_connections(remote_ip);
+        CREATE INDEX IF NOT EXISTS idx_netconn_local_port ON network_connections(local_port, state);
+
+        -- Histórico de ligações/desligações de dispositivos USB
+        CREATE TABLE IF NOT EXISTS device_events (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            machine_id      TEXT NOT NULL,
+            device_id       TEXT NOT NULL,
+            name            TEXT NOT NULL,
+            vendor_id       TEXT NOT NULL,
+            product_id      TEXT NOT NULL,
+            serial          TEXT,
+            is_mass_storage INTEGER NOT NULL DEFAULT 0,
+            event           TEXT NOT NULL,
+            timestamp       TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_device_events_machine ON device_events(machine_id, timestamp);
+
+        -- Fila de comandos para os agentes (entregues na resposta ao relatório ou por pedido)
+        CREATE TABLE IF NOT EXISTS agent_commands (
+            id            TEXT PRIMARY KEY,
+            machine_id    TEXT NOT NULL,
+            command_type  TEXT NOT NULL,
+            payload       TEXT NOT NULL DEFAULT '{}',
+            status        TEXT NOT NULL DEFAULT 'pending',
+            created_by    TEXT NOT NULL,
+            created_at    TEXT NOT NULL,
+            expires_at    TEXT NOT NULL,
+            delivered_at  TEXT,
+            completed_at  TEXT,
+            output        TEXT,
+            error         TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_agent_commands_machine ON agent_commands(machine_id, status);
+
+        -- Scripts aprovados para execução remota
+        CREATE TABLE IF NOT EXISTS approved_scripts (
+            id           TEXT PRIMARY KEY,
+            name         TEXT NOT NULL,
+            description  TEXT NOT NULL DEFAULT '',
+            interpreter  TEXT NOT NULL DEFAULT 'powershell',
+            content      TEXT NOT NULL,
+            sha256       TEXT NOT NULL,
+            created_by   TEXT NOT NULL,
+            created_at   TEXT NOT NULL
+        );
+
+        -- Chaves do servidor (assinatura dos scripts aprovados)
+        CREATE TABLE IF NOT EXISTS server_keys (
+            name         TEXT PRIMARY KEY,
+            pkcs8        BLOB NOT NULL,
+            created_at   TEXT NOT NULL
+        );
+
+        -- Credencial de cada agente (hash do token emitido no primeiro relatório)
+        CREATE TABLE IF NOT EXISTS machine_credentials (
+            machine_id   TEXT PRIMARY KEY,
+            token_hash   TEXT NOT NULL,
+            created_at   TEXT NOT NULL
+        );
+
+        -- Capturas de ecrã; a imagem fica em ficheiro, aqui só os metadados
+        CREATE TABLE IF NOT EXISTS screenshots (
+            id           TEXT PRIMARY KEY,
+            machine_id   TEXT NOT NULL,
+            command_id   TEXT,
+            captured_at  TEXT NOT NULL,
+            received_at  TEXT NOT NULL,
+            width        INTEGER NOT NULL,
+            height       INTEGER NOT NULL,
+            size_bytes   INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_screenshots_machine ON screenshots(machine_id, captured_at);
+    ")?;
+    seed_policy_revisions(conn)?;
+    // Assinatura Ed25519 do script, feita na aprovação
+    conn.execute("ALTER TABLE approved_scripts ADD COLUMN signature TEXT NOT NULL DEFAULT ''", [])
+        .ok();
+
+    // Screen time: categoria, blocos por hora e utilizador de cada linha
+    conn.execute("ALTER TABLE screen_time ADD COLUMN category TEXT NOT NULL DEFAULT 'other'", [])
+        .ok();
+    conn.execute("ALTER TABLE screen_time ADD COLUMN hourly_seconds TEXT NOT NULL DEFAULT '[]'", [])
+        .ok();
+    conn.execute("ALTER TABLE screen_time ADD COLUMN user_name TEXT NOT NULL DEFAULT ''", [])
+        .ok();
+    conn.execute_batch("
+        -- Classificação das categorias do catálogo do agente
+        CREATE TABLE IF NOT EXISTS screen_time_categories (
+            category     TEXT PRIMARY KEY,
+            productivity TEXT NOT NULL DEFAULT 'neutral' -- 'productive' | 'unproductive' | 'neutral'
+        );
+        INSERT OR IGNORE INTO screen_time_categories (category, productivity) VALUES
+            ('development', 'productive'),
+            ('office', 'productive'),
+            ('communication', 'productive'),
+            ('design', 'productive'),
+            ('social', 'unproductive'),
+            ('entertainment', 'unproductive'),
+            ('games', 'unproductive'),
+            ('browser', 'neutral'),
+            ('media', 'neutral'),
+            ('other', 'neutral');
+    ")?;
     Ok(())
 }
 
-pub fn open_database() -> Result<Connection> {
-    let data_dir = dirs::data_dir()
+/// Pasta de dados do dashboard (base de dados, capturas de ecrã)
+pub fn data_dir() -> std::path::PathBuf {
+    dirs::data_dir()
         .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("assetscan");
+        .join("assetscan")
+}
+
+pub fn open_database() -> Result<Connection> {
+    let data_dir = data_dir();
 
     std::fs::create_dir_all(&data_dir).expect("Não foi possível criar pasta de dados");
 
@@ -636,6 +1152,27 @@ pub fn get_processes(pool: &DbPool, machine_id: &str) -> Result<Vec<ProcessInfo>
 // Políticas (NOVO)
 // -------------------------------------------------
 
+const POLICY_COLUMNS: &str = "id, machine_id, name, description, policy_type, priority, target, action, config_json, reason, created_by, created_at, enabled, revision";
+
+fn map_policy_row(row: &rusqlite::Row) -> Result<Policy> {
+    Ok(Policy {
+        id: row.get(0)?,
+        machine_id: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        policy_type: row.get(4)?,
+        priority: row.get(5)?,
+        target: row.get(6)?,
+        action: row.get(7)?,
+        config_json: row.get(8)?,
+        reason: row.get(9)?,
+        created_by: row.get(10)?,
+        created_at: row.get(11)?,
+        enabled: row.get::<_, i32>(12)? != 0,
+        revision: row.get(13)?,
+    })
+}
+
 pub fn create_policy(
     pool: &DbPool,
     machine_id: Option<&str>,
@@ -649,15 +1186,24 @@ pub fn create_policy(
     reason: &str,
     created_by: &str,
 ) -> Result<String> {
-    let conn = pool.lock().unwrap();
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
     let id = uuid::Uuid::new_v4().to_string();
 
-    conn.execute(
+    tx.execute(
         "INSERT INTO policies (id, machine_id, name, description, policy_type, priority, target, action, config_json, reason, created_by)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
         params![id, machine_id, name, description, policy_type, priority, target, action, config_json, reason, created_by],
     )?;
 
+    let policy = tx.query_row(
+        &format!("SELECT {} FROM policies WHERE id = ?1", POLICY_COLUMNS),
+        params![id],
+        map_policy_row,
+    )?;
+    insert_policy_revision(&tx, &policy, "active", None, "Criação", created_by)?;
+
+    tx.commit()?;
     Ok(id)
 }
 
@@ -667,323 +1213,2467 @@ pub fn list_policies(
 ) -> Result<Vec<Policy>> {
     let conn = pool.lock().unwrap();
 
-    // 1. Atualizamos o SELECT para incluir as novas colunas
-    let query = if let Some(mid) = machine_id {
-        format!(
-            "SELECT id, machine_id, name, description, policy_type, priority, target, action, config_json, reason, created_by, created_at, enabled
-             FROM policies
-             WHERE machine_id = '{}' OR machine_id IS NULL
+    let policies = if let Some(mid) = machine_id {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM policies
+             WHERE machine_id = ?1 OR machine_id IS NULL
              ORDER BY priority ASC, created_at DESC",
-            mid
-        )
+            POLICY_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![mid], map_policy_row)?;
+        rows.collect::<Result<Vec<Policy>>>()?
     } else {
-        "SELECT id, machine_id, name, description, policy_type, priority, target, action, config_json, reason, created_by, created_at, enabled
-         FROM policies
-         ORDER BY priority ASC, created_at DESC".to_string()
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM policies ORDER BY priority ASC, created_at DESC",
+            POLICY_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], map_policy_row)?;
+        rows.collect::<Result<Vec<Policy>>>()?
     };
 
-    let mut stmt = conn.prepare(&query)?;
+    Ok(policies)
+}
 
-    // 2. Extraímos e mapeamos as colunas lidas para a estrutura
-    let policies = stmt.query_map([], |row| {
-        Ok(Policy {
-            id: row.get(0)?,
-            machine_id: row.get(1)?,
-            name: row.get(2)?,
-            description: row.get(3)?,
-            policy_type: row.get(4)?,
-            priority: row.get(5)?,
-            target: row.get(6)?,
-            action: row.get(7)?,
-            config_json: row.get(8)?,
-            reason: row.get(9)?,
-            created_by: row.get(10)?,
-            created_at: row.get(11)?,
-            enabled: row.get::<_, i32>(12)? != 0,
-        })
-    })?
-    .collect::<Result<Vec<Policy>>>()?;
+pub fn get_policy(pool: &DbPool, policy_id: &str) -> Result<Policy> {
+    let conn = pool.lock().unwrap();
 
-    Ok(policies)
+    conn.query_row(
+        &format!("SELECT {} FROM policies WHERE id = ?1", POLICY_COLUMNS),
+        params![policy_id],
+        map_policy_row,
+    )
 }
 
 pub fn delete_policy(pool: &DbPool, policy_id: &str) -> Result<()> {
-    let conn = pool.lock().unwrap();
-    conn.execute("DELETE FROM policies WHERE id = ?1", params![policy_id])?;
-    Ok(())
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+
+    // Mantém o histórico: a última revisão fica registada como "deleted"
+    let policy = tx
+        .query_row(
+            &format!("SELECT {} FROM policies WHERE id = ?1", POLICY_COLUMNS),
+            params![policy_id],
+            map_policy_row,
+        )
+        .optional()?;
+    if let Some(policy) = policy {
+        tx.execute(
+            "UPDATE policy_revisions SET state = 'superseded'
+             WHERE policy_id = ?1 AND state IN ('active', 'canary')",
+            params![policy_id],
+        )?;
+        let next = next_revision(&tx, policy_id)?;
+        let deleted = Policy { revision: next, ..policy };
+        insert_policy_revision(&tx, &deleted, "deleted", None, "Eliminação", "admin")?;
+    }
+
+    tx.execute("DELETE FROM policies WHERE id = ?1", params![policy_id])?;
+    tx.commit()
 }
 
 // -------------------------------------------------
-// Discos
+// Revisões de Políticas (rollout e rollback)
 // -------------------------------------------------
 
-pub fn update_disks(pool: &DbPool, machine_id: &str, disks: &[DiskInfo]) -> Result<()> {
-    let conn = pool.lock().unwrap();
+fn next_revision(conn: &Connection, policy_id: &str) -> Result<i64> {
+    // Políticas anteriores ao versionamento não têm linhas em policy_revisions
+    conn.query_row(
+        "SELECT MAX(
+            COALESCE((SELECT MAX(revision) FROM policy_revisions WHERE policy_id = ?1), 0),
+            COALESCE((SELECT revision FROM policies WHERE id = ?1), 0)
+         ) + 1",
+        params![policy_id],
+        |row| row.get(0),
+    )
+}
+
+fn insert_policy_revision(
+    conn: &Connection,
+    policy: &Policy,
+    state: &str,
+    canary_group: Option<&str>,
+    note: &str,
+    created_by: &str,
+) -> Result<()> {
+    let snapshot = serde_json::to_string(policy).unwrap_or_else(|_| "{}".to_string());
+
     conn.execute(
-        "DELETE FROM disks WHERE machine_id = ?1",
-        params![machine_id],
+        "INSERT INTO policy_revisions (policy_id, revision, snapshot, state, canary_group, note, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            policy.id,
+            policy.revision,
+            snapshot,
+            state,
+            canary_group,
+            note,
+            created_by,
+            Utc::now().to_rfc3339()
+        ],
     )?;
+    Ok(())
+}
 
-    for disk in disks {
-        conn.execute(
-            "INSERT INTO disks (machine_id, name, mount_point, total_gb, free_gb, fs_type)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                machine_id,
-                disk.name,
-                disk.mount_point,
-                disk.total_gb,
-                disk.free_gb,
-                disk.fs_type
-            ],
-        )?;
+/// Políticas criadas antes do versionamento não têm revisões: a versão actual fica
+/// registada como a revisão inicial, para o histórico e o rollback terem ponto de partida
+fn seed_policy_revisions(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM policies p
+         WHERE NOT EXISTS (SELECT 1 FROM policy_revisions r WHERE r.policy_id = p.id)",
+        POLICY_COLUMNS
+    ))?;
+    let policies = stmt.query_map([], map_policy_row)?.collect::<Result<Vec<Policy>>>()?;
+
+    for policy in policies {
+        let created_by = policy.created_by.clone();
+        insert_policy_revision(conn, &policy, "active", None, "Revisão inicial", &created_by)?;
     }
     Ok(())
 }
 
-pub fn get_disks(pool: &DbPool, machine_id: &str) -> Result<Vec<DiskInfo>> {
-    let conn = pool.lock().unwrap();
-    let mut stmt = conn.prepare(
-        "SELECT name, mount_point, total_gb, free_gb, fs_type
-         FROM disks WHERE machine_id = ?1 ORDER BY mount_point",
+/// Escreve o snapshot de uma revisão na linha "viva" da tabela policies.
+/// Devolve `false` se a política já não existir (eliminada)
+fn apply_policy_snapshot(conn: &Connection, policy: &Policy) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE policies SET
+            machine_id = ?1, name = ?2, description = ?3, policy_type = ?4, priority = ?5,
+            target = ?6, action = ?7, config_json = ?8, reason = ?9, enabled = ?10, revision = ?11
+         WHERE id = ?12",
+        params![
+            policy.machine_id,
+            policy.name,
+            policy.description,
+            policy.policy_type,
+            policy.priority,
+            policy.target,
+            policy.action,
+            policy.config_json,
+            policy.reason,
+            policy.enabled,
+            policy.revision,
+            policy.id
+        ],
     )?;
-
-    let disks = stmt
-        .query_map(params![machine_id], |row| {
-            Ok(DiskInfo {
-                name: row.get(0)?,
-                mount_point: row.get(1)?,
-                total_gb: row.get(2)?,
-                free_gb: row.get(3)?,
-                fs_type: row.get(4)?,
-            })
-        })?
-        .collect::<Result<Vec<DiskInfo>>>()?;
-
-    Ok(disks)
+    Ok(updated > 0)
 }
 
-pub fn insert_metric(
-    pool: &DbPool,
-    machine_id: &str,
-    cpu_percent: f32,
-    ram_used_mb: i64,
-    ram_total_mb: i64,
-) -> Result<()> {
-    let conn = pool.lock().unwrap();
+/// Volta a criar a linha de uma política eliminada a partir de uma revisão
+fn restore_policy_row(conn: &Connection, policy: &Policy) -> Result<()> {
     conn.execute(
-        "INSERT INTO metrics_history (machine_id, timestamp, cpu_percent, ram_used_mb, ram_total_mb)
-         VALUES (?1, CURRENT_TIMESTAMP, ?2, ?3, ?4)",
-        params![machine_id, cpu_percent, ram_used_mb, ram_total_mb],
+        "INSERT INTO policies
+            (id, machine_id, name, description, policy_type, priority, target, action,
+             config_json, reason, created_by, created_at, enabled, revision)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            policy.id,
+            policy.machine_id,
+            policy.name,
+            policy.description,
+            policy.policy_type,
+            policy.priority,
+            policy.target,
+            policy.action,
+            policy.config_json,
+            policy.reason,
+            policy.created_by,
+            policy.created_at,
+            policy.enabled,
+            policy.revision
+        ],
     )?;
     Ok(())
 }
 
-// -------------------------------------------------
-// Software
-// -------------------------------------------------
+fn map_revision_row(row: &rusqlite::Row) -> Result<PolicyRevision> {
+    let snapshot: String = row.get(3)?;
+    Ok(PolicyRevision {
+        id: row.get(0)?,
+        policy_id: row.get(1)?,
+        revision: row.get(2)?,
+        policy: serde_json::from_str(&snapshot).ok(),
+        state: row.get(4)?,
+        canary_group: row.get(5)?,
+        note: row.get(6)?,
+        created_by: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
 
-pub fn update_software(
+const REVISION_COLUMNS: &str = "id, policy_id, revision, snapshot, state, canary_group, note, created_by, created_at";
+
+/// Cria uma nova revisão imutável. Em modo canary a revisão só chega às
+/// máquinas do grupo até ser promovida; caso contrário é aplicada a todas.
+pub fn update_policy(
     pool: &DbPool,
-    machine_id: &str,
-    software_list: &[SoftwareEntry],
-) -> Result<()> {
-    let conn = pool.lock().unwrap();
-    conn.execute(
-        "DELETE FROM software WHERE machine_id = ?1",
-        params![machine_id],
+    policy: &Policy,
+    canary_group: Option<&str>,
+    updated_by: &str,
+) -> Result<i64> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    let revision = next_revision(&tx, &policy.id)?;
+    let new_policy = Policy { revision, ..policy.clone() };
+
+    // Uma nova alteração substitui qualquer canary pendente
+    tx.execute(
+        "UPDATE policy_revisions SET state = 'superseded' WHERE policy_id = ?1 AND state = 'canary'",
+        params![policy.id],
     )?;
 
-    for sw in software_list {
-        conn.execute(
-            "INSERT INTO software (machine_id, name, version, publisher, install_date)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                machine_id,
-                sw.name,
-                sw.version,
-                sw.publisher,
-                sw.install_date
-            ],
-        )?;
+    match canary_group {
+        Some(group) => {
+            insert_policy_revision(&tx, &new_policy, "canary", Some(group), "Rollout canary", updated_by)?;
+        }
+        None => {
+            tx.execute(
+                "UPDATE policy_revisions SET state = 'superseded' WHERE policy_id = ?1 AND state = 'active'",
+                params![policy.id],
+            )?;
+            insert_policy_revision(&tx, &new_policy, "active", None, "Alteração", updated_by)?;
+            apply_policy_snapshot(&tx, &new_policy)?;
+        }
     }
-    Ok(())
+
+    tx.commit()?;
+    Ok(revision)
 }
 
-pub fn get_software(pool: &DbPool, machine_id: &str) -> Result<Vec<SoftwareEntry>> {
-    let conn = pool.lock().unwrap();
-    let mut stmt = conn.prepare(
-        "SELECT name, version, publisher, install_date
-         FROM software WHERE machine_id = ?1 ORDER BY name",
+/// Promove a revisão canary pendente para todas as máquinas
+pub fn promote_policy_revision(pool: &DbPool, policy_id: &str) -> Result<i64> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+
+    let canary = tx.query_row(
+        &format!(
+            "SELECT {} FROM policy_revisions WHERE policy_id = ?1 AND state = 'canary'
+             ORDER BY revision DESC LIMIT 1",
+            REVISION_COLUMNS
+        ),
+        params![policy_id],
+        map_revision_row,
     )?;
+    let policy = canary.policy.ok_or(rusqlite::Error::InvalidQuery)?;
 
-    let software = stmt
-        .query_map(params![machine_id], |row| {
-            Ok(SoftwareEntry {
-                name: row.get(0)?,
-                version: row.get(1)?,
-                publisher: row.get(2)?,
-                install_date: row.get(3)?,
+    tx.execute(
+        "UPDATE policy_revisions SET state = 'superseded' WHERE policy_id = ?1 AND state = 'active'",
+        params![policy_id],
+    )?;
+    tx.execute(
+        "UPDATE policy_revisions SET state = 'active' WHERE id = ?1",
+        params![canary.id],
+    )?;
+    apply_policy_snapshot(&tx, &policy)?;
+
+    tx.commit()?;
+    Ok(canary.revision)
+}
+
+/// Repõe uma revisão anterior, criando uma nova revisão com o mesmo conteúdo.
+/// Numa política eliminada, o rollback volta a criá-la
+pub fn rollback_policy(pool: &DbPool, policy_id: &str, revision: i64, user: &str) -> Result<i64> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+
+    let target = tx.query_row(
+        &format!(
+            "SELECT {} FROM policy_revisions WHERE policy_id = ?1 AND revision = ?2",
+            REVISION_COLUMNS
+        ),
+        params![policy_id, revision],
+        map_revision_row,
+    )?;
+    let snapshot = target.policy.ok_or(rusqlite::Error::InvalidQuery)?;
+
+    let new_revision = next_revision(&tx, policy_id)?;
+    let restored = Policy { revision: new_revision, ..snapshot };
+
+    tx.execute(
+        "UPDATE policy_revisions SET state = 'superseded' WHERE policy_id = ?1 AND state = 'active'",
+        params![policy_id],
+    )?;
+    tx.execute(
+        "UPDATE policy_revisions SET state = 'rolled_back' WHERE policy_id = ?1 AND state = 'canary'",
+        params![policy_id],
+    )?;
+    insert_policy_revision(
+        &tx,
+        &restored,
+        "active",
+        None,
+        &format!("Rollback para revisão {}", revision),
+        user,
+    )?;
+    if !apply_policy_snapshot(&tx, &restored)? {
+        restore_policy_row(&tx, &restored)?;
+    }
+
+    tx.commit()?;
+    Ok(new_revision)
+}
+
+pub fn list_policy_revisions(pool: &DbPool, policy_id: &str) -> Result<Vec<PolicyRevision>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM policy_revisions WHERE policy_id = ?1 ORDER BY revision DESC",
+        REVISION_COLUMNS
+    ))?;
+
+    let revisions = stmt
+        .query_map(params![policy_id], map_revision_row)?
+        .collect::<Result<Vec<PolicyRevision>>>()?;
+
+    Ok(revisions)
+}
+
+/// Políticas para um agente, com as revisões canary aplicadas se a máquina
+/// pertencer ao grupo (tag) do rollout
+pub fn list_policies_for_machine(pool: &DbPool, machine_id: &str) -> Result<Vec<Policy>> {
+    let mut policies = list_policies(pool, Some(machine_id))?;
+
+    let conn = pool.lock().unwrap();
+    let tags_json: String = conn
+        .query_row(
+            "SELECT COALESCE(tags, '[]') FROM machines WHERE machine_id = ?1",
+            params![machine_id],
+            |row| row.get(0),
+        )
+        .unwrap_or_else(|_| "[]".to_string());
+    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM policy_revisions WHERE state = 'canary'",
+        REVISION_COLUMNS
+    ))?;
+    let canaries = stmt
+        .query_map([], map_revision_row)?
+        .collect::<Result<Vec<PolicyRevision>>>()?;
+
+    for canary in canaries {
+        let in_group = canary
+            .canary_group
+            .as_ref()
+            .map(|g| tags.iter().any(|t| t.eq_ignore_ascii_case(g)))
+            .unwrap_or(false);
+        if !in_group {
+            continue;
+        }
+        if let (Some(slot), Some(snapshot)) = (
+            policies.iter_mut().find(|p| p.id == canary.policy_id),
+            canary.policy,
+        ) {
+            *slot = snapshot;
+        }
+    }
+
+    Ok(policies)
+}
+
+/// Guarda as revisões que o agente reporta estar a aplicar
+pub fn update_machine_policy_revisions(
+    pool: &DbPool,
+    machine_id: &str,
+    revisions: &[(String, i64)],
+) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM machine_policy_revisions WHERE machine_id = ?1",
+        params![machine_id],
+    )?;
+
+    let now = Utc::now().to_rfc3339();
+    for (policy_id, revision) in revisions {
+        tx.execute(
+            "INSERT INTO machine_policy_revisions (machine_id, policy_id, revision, reported_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![machine_id, policy_id, revision, now],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn get_machine_policy_revisions(
+    pool: &DbPool,
+    machine_id: Option<&str>,
+    policy_id: Option<&str>,
+) -> Result<Vec<MachinePolicyRevision>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT machine_id, policy_id, revision, reported_at
+         FROM machine_policy_revisions
+         WHERE (?1 IS NULL OR machine_id = ?1) AND (?2 IS NULL OR policy_id = ?2)
+         ORDER BY machine_id, policy_id",
+    )?;
+
+    let rows = stmt
+        .query_map(params![machine_id, policy_id], |row| {
+            Ok(MachinePolicyRevision {
+                machine_id: row.get(0)?,
+                policy_id: row.get(1)?,
+                revision: row.get(2)?,
+                reported_at: row.get(3)?,
             })
         })?
-        .collect::<Result<Vec<SoftwareEntry>>>()?;
+        .collect::<Result<Vec<MachinePolicyRevision>>>()?;
 
-    Ok(software)
+    Ok(rows)
 }
 
 // -------------------------------------------------
-// Auditoria
+// Resultados de Enforcement (reportados pelo agente)
 // -------------------------------------------------
 
-pub fn log_audit(
+pub fn update_policy_enforcement(
     pool: &DbPool,
-    action: &str,
-    resource_type: &str,
-    resource_id: &str,
-    user: &str,
-    details: &str,
+    machine_id: &str,
+    results: &[PolicyEnforcementStatus],
 ) -> Result<()> {
     let conn = pool.lock().unwrap();
-    conn.execute(
-        "INSERT INTO audit_logs (action, resource_type, resource_id, user, details)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![action, resource_type, resource_id, user, details],
-    )?;
+
+    for r in results {
+        // kill_count chega como delta desde o último relatório
+        conn.execute(
+            "INSERT INTO policy_enforcement (machine_id, policy_id, revision, status, error, kill_count, last_hit, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(machine_id, policy_id) DO UPDATE SET
+                revision = excluded.revision,
+                status = excluded.status,
+                error = excluded.error,
+                kill_count = kill_count + excluded.kill_count,
+                last_hit = COALESCE(excluded.last_hit, last_hit),
+                updated_at = excluded.updated_at",
+            params![
+                machine_id,
+                r.policy_id,
+                r.revision,
+                r.status,
+                r.error,
+                r.kill_count,
+                r.last_hit,
+                r.updated_at
+            ],
+        )?;
+    }
     Ok(())
 }
 
+pub fn get_policy_enforcement(
+    pool: &DbPool,
+    machine_id: Option<&str>,
+    policy_id: Option<&str>,
+) -> Result<Vec<PolicyEnforcementStatus>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT machine_id, policy_id, revision, status, error, kill_count, last_hit, updated_at
+         FROM policy_enforcement
+         WHERE (?1 IS NULL OR machine_id = ?1) AND (?2 IS NULL OR policy_id = ?2)
+         ORDER BY updated_at DESC",
+    )?;
+
+    let rows = stmt
+        .query_map(params![machine_id, policy_id], |row| {
+            Ok(PolicyEnforcementStatus {
+                machine_id: row.get(0)?,
+                policy_id: row.get(1)?,
+                revision: row.get(2)?,
+                status: row.get(3)?,
+                error: row.get(4)?,
+                kill_count: row.get(5)?,
+                last_hit: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<PolicyEnforcementStatus>>>()?;
+
+    Ok(rows)
+}
+
 // -------------------------------------------------
-// Filtros Avançados (NOVO v3.0)
+// Allowlist de aplicações (violações reportadas pelo agente)
 // -------------------------------------------------
-pub fn list_machines_filtered(pool: &DbPool, filters: MachineFilters) -> Result<Vec<Machine>> {
+
+pub fn insert_app_violations(pool: &DbPool, machine_id: &str, violations: &[AppViolation]) -> Result<()> {
     let conn = pool.lock().unwrap();
-    let mut query = "
-        SELECT
-            m.id, m.machine_id, m.hostname, m.custom_name, m.tags, m.notes,
-            m.last_seen, m.cpu_name, m.cpu_cores, m.ram_total_mb, m.ram_used_mb,
-            m.os_name, m.os_version, m.uptime_hours,
-            COUNT(DISTINCT d.id) AS disk_count,
-            COUNT(DISTINCT s.id) AS software_count,
-            COUNT(DISTINCT p.id) AS process_count,
-            m.local_ip, m.mac_address, m.serial_number, m.motherboard_model,
-            m.gpu_name, m.is_bitlocker_active, m.domain_name, m.current_user
-        FROM machines m
-        LEFT JOIN disks d ON d.machine_id = m.machine_id
-        LEFT JOIN software s ON s.machine_id = m.machine_id
-        LEFT JOIN processes p ON p.machine_id = m.machine_id
-        WHERE 1=1
-    "
-    .to_string();
 
-    if let Some(search) = filters.search_term {
-        query.push_str(&format!(
-            " AND (m.hostname LIKE '%{}%' OR m.custom_name LIKE '%{}%')",
-            search, search
-        ));
+    for v in violations {
+        // hit_count chega como delta desde o último relatório
+        conn.execute(
+            "INSERT INTO app_violations (machine_id, policy_id, exe_path, action, process_name, sha256,
+                publisher, original_filename, hit_count, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(machine_id, policy_id, exe_path, action) DO UPDATE SET
+                process_name = excluded.process_name,
+                sha256 = COALESCE(excluded.sha256, sha256),
+                publisher = COALESCE(excluded.publisher, publisher),
+                original_filename = COALESCE(excluded.original_filename, original_filename),
+                hit_count = hit_count + excluded.hit_count,
+                last_seen = excluded.last_seen",
+            params![
+                machine_id,
+                v.policy_id,
+                v.exe_path,
+                v.action,
+                v.process_name,
+                v.sha256,
+                v.publisher,
+                v.original_filename,
+                v.hit_count,
+                v.first_seen,
+                v.last_seen
+            ],
+        )?;
     }
+    Ok(())
+}
 
-    query.push_str(" GROUP BY m.id ORDER BY m.last_seen DESC");
+pub fn list_app_violations(
+    pool: &DbPool,
+    policy_id: Option<&str>,
+    machine_id: Option<&str>,
+) -> Result<Vec<AppViolation>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT machine_id, policy_id, process_name, exe_path, sha256, publisher, original_filename,
+                action, hit_count, first_seen, last_seen
+         FROM app_violations
+         WHERE (?1 IS NULL OR policy_id = ?1) AND (?2 IS NULL OR machine_id = ?2)
+         ORDER BY last_seen DESC",
+    )?;
 
-    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt
+        .query_map(params![policy_id, machine_id], |row| {
+            Ok(AppViolation {
+                machine_id: row.get(0)?,
+                policy_id: row.get(1)?,
+                process_name: row.get(2)?,
+                exe_path: row.get(3)?,
+                sha256: row.get(4)?,
+                publisher: row.get(5)?,
+                original_filename: row.get(6)?,
+                action: row.get(7)?,
+                hit_count: row.get(8)?,
+                first_seen: row.get(9)?,
+                last_seen: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<AppViolation>>>()?;
 
-    let machines = stmt
-        .query_map([], |row| {
-            let last_seen: String = row.get(6)?;
-            let is_online = is_machine_online(&last_seen);
-            let tags_json: String = row.get(4).unwrap_or_else(|_| "[]".to_string());
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    Ok(rows)
+}
 
-            Ok(Machine {
-                id: row.get(0)?,
-                machine_id: row.get(1)?,
-                hostname: row.get(2)?,
-                custom_name: row.get(3)?,
-                tags,
-                notes: row.get(5)?,
-                last_seen,
-                cpu_name: row.get(7)?,
-                cpu_cores: row.get(8)?,
-                ram_total_mb: row.get(9)?,
-                ram_used_mb: row.get(10)?,
-                os_name: row.get(11)?,
-                os_version: row.get(12)?,
-                uptime_hours: row.get(13)?,
-                disk_count: row.get(14)?,
-                software_count: row.get(15)?,
-                process_count: row.get(16)?,
-                is_online,
-                local_ip: row.get(17).ok(),
-                mac_address: row.get(18).ok(),
-                serial_number: row.get(19).ok(),
-                motherboard_model: row.get(20).ok(),
-                gpu_name: row.get(21).ok(),
-                is_bitlocker_active: row.get(22).ok(),
-                domain_name: row.get(23).ok(),
-                current_user: row.get(24).ok(),
+// -------------------------------------------------
+// Correspondências de políticas em modo "alert"
+// -------------------------------------------------
+
+pub fn insert_policy_matches(pool: &DbPool, machine_id: &str, matches: &[PolicyMatch]) -> Result<()> {
+    let conn = pool.lock().unwrap();
+
+    for m in matches {
+        conn.execute(
+            "INSERT INTO policy_matches (machine_id, policy_id, policy_type, subject, process_name, pid,
+                user, domain, remote_ip, hit_count, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                machine_id,
+                m.policy_id,
+                m.policy_type,
+                m.subject,
+                m.process_name,
+                m.pid,
+                m.user,
+                m.domain,
+                m.remote_ip,
+                m.hit_count,
+                m.first_seen,
+                m.last_seen
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Correspondências de uma política desde `since` (RFC 3339)
+pub fn list_policy_matches(
+    pool: &DbPool,
+    policy_id: &str,
+    machine_id: Option<&str>,
+    since: &str,
+) -> Result<Vec<PolicyMatch>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT machine_id, policy_id, policy_type, subject, process_name, pid, user, domain,
+                remote_ip, hit_count, first_seen, last_seen
+         FROM policy_matches
+         WHERE policy_id = ?1 AND (?2 IS NULL OR machine_id = ?2) AND last_seen >= ?3
+         ORDER BY last_seen DESC",
+    )?;
+
+    let rows = stmt
+        .query_map(params![policy_id, machine_id, since], |row| {
+            Ok(PolicyMatch {
+                machine_id: row.get(0)?,
+                policy_id: row.get(1)?,
+                policy_type: row.get(2)?,
+                subject: row.get(3)?,
+                process_name: row.get(4)?,
+                pid: row.get(5)?,
+                user: row.get(6)?,
+                domain: row.get(7)?,
+                remote_ip: row.get(8)?,
+                hit_count: row.get(9)?,
+                first_seen: row.get(10)?,
+                last_seen: row.get(11)?,
             })
         })?
-        .collect::<Result<Vec<Machine>>>()?;
+        .collect::<Result<Vec<PolicyMatch>>>()?;
 
-    Ok(machines)
+    Ok(rows)
 }
 
-pub fn get_hardware_details(pool: &DbPool, machine_id: &str) -> Result<HardwareDetails> {
+// -------------------------------------------------
+// Estatísticas do filtro DNS
+// -------------------------------------------------
+
+pub fn insert_dns_stats(pool: &DbPool, machine_id: &str, stats: &[DnsQueryStat]) -> Result<()> {
     let conn = pool.lock().unwrap();
 
+    for s in stats {
+        // Contagens chegam como delta desde o último relatório
+        conn.execute(
+            "INSERT INTO dns_query_stats (machine_id, domain, date, queries, blocked, policy_id, last_query)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(machine_id, domain, date) DO UPDATE SET
+                queries = queries + excluded.queries,
+                blocked = blocked + excluded.blocked,
+                policy_id = COALESCE(excluded.policy_id, policy_id),
+                last_query = excluded.last_query",
+            params![machine_id, s.domain, s.date, s.queries, s.blocked, s.policy_id, s.last_query],
+        )?;
+    }
+    Ok(())
+}
+
+/// Totais por domínio desde `since` (YYYY-MM-DD), do mais bloqueado para o menos
+pub fn get_dns_summary(
+    pool: &DbPool,
+    machine_id: Option<&str>,
+    since: &str,
+    blocked_only: bool,
+    limit: i64,
+) -> Result<Vec<DnsDomainSummary>> {
+    let conn = pool.lock().unwrap();
     let mut stmt = conn.prepare(
-        "SELECT 
-            serial_number, motherboard_manufacturer, motherboard_model,
-            bios_version, gpu_name, gpu_vram_mb, total_ram_slots,
-            used_ram_slots, ram_type
-         FROM hardware_details
-         WHERE machine_id = ?1",
+        "SELECT domain, SUM(queries), SUM(blocked), COUNT(DISTINCT machine_id), MAX(policy_id), MAX(last_query)
+         FROM dns_query_stats
+         WHERE date >= ?1 AND (?2 IS NULL OR machine_id = ?2)
+         GROUP BY domain
+         HAVING (?3 = 0 OR SUM(blocked) > 0)
+         ORDER BY SUM(blocked) DESC, SUM(queries) DESC
+         LIMIT ?4",
     )?;
 
-    let details = stmt.query_row([machine_id], |row| {
-        Ok(HardwareDetails {
-            serial_number: row.get(0)?,
-            motherboard_manufacturer: row.get(1)?,
-            motherboard_model: row.get(2)?,
-            bios_version: row.get(3)?,
-            gpu_name: row.get(4)?,
-            gpu_vram_mb: row.get(5)?,
-            total_ram_slots: row.get(6)?,
-            used_ram_slots: row.get(7)?,
-            ram_type: row.get(8)?,
-        })
-    })?;
+    let rows = stmt
+        .query_map(params![since, machine_id, blocked_only as i32, limit], |row| {
+            Ok(DnsDomainSummary {
+                domain: row.get(0)?,
+                queries: row.get(1)?,
+                blocked: row.get(2)?,
+                machines: row.get(3)?,
+                policy_id: row.get(4)?,
+                last_query: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<DnsDomainSummary>>>()?;
+
+    Ok(rows)
+}
+
+// -------------------------------------------------
+// Ligações de rede
+// -------------------------------------------------
+
+const CONNECTION_COLUMNS: &str = "c.machine_id, m.hostname, c.protocol, c.pid, c.process_name, c.local_ip, c.local_port,
+     c.remote_ip, c.remote_port, c.remote_host, c.state, c.updated_at";
+
+fn map_connection_row(row: &rusqlite::Row) -> Result<NetworkConnection> {
+    Ok(NetworkConnection {
+        machine_id: row.get(0)?,
+        hostname: row.get(1)?,
+        protocol: row.get(2)?,
+        pid: row.get(3)?,
+        process_name: row.get(4)?,
+        local_ip: row.get(5)?,
+        local_port: row.get(6)?,
+        remote_ip: row.get(7)?,
+        remote_port: row.get(8)?,
+        remote_host: row.get(9)?,
+        state: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+pub fn update_network_connections(
+    pool: &DbPool,
+    machine_id: &str,
+    connections: &[NetworkConnection],
+) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM network_connections WHERE machine_id = ?1", params![machine_id])?;
+
+    for c in connections {
+        tx.execute(
+            "INSERT INTO network_connections
+                (machine_id, protocol, pid, process_name, local_ip, local_port,
+                 remote_ip, remote_port, remote_host, state, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                machine_id,
+                c.protocol,
+                c.pid,
+                c.process_name,
+                c.local_ip,
+                c.local_port,
+                c.remote_ip,
+                c.remote_port,
+                c.remote_host,
+                c.state,
+                c.updated_at
+            ],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn get_network_connections(pool: &DbPool, machine_id: &str) -> Result<Vec<NetworkConnection>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM network_connections c
+         LEFT JOIN machines m ON m.machine_id = c.machine_id
+         WHERE c.machine_id = ?1
+         ORDER BY c.state = 'LISTEN' DESC, c.protocol, c.local_port",
+        CONNECTION_COLUMNS
+    ))?;
+    let connections = stmt
+        .query_map(params![machine_id], map_connection_row)?
+        .collect::<Result<Vec<NetworkConnection>>>()?;
+
+    Ok(connections)
+}
+
+/// Ligações de qualquer máquina para um IP remoto (ou nome resolvido)
+pub fn find_connections_to(pool: &DbPool, remote: &str) -> Result<Vec<NetworkConnection>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM network_connections c
+         LEFT JOIN machines m ON m.machine_id = c.machine_id
+         WHERE c.remote_ip = ?1 OR c.remote_host = ?1 COLLATE NOCASE
+         ORDER BY m.hostname, c.process_name",
+        CONNECTION_COLUMNS
+    ))?;
+    let connections = stmt
+        .query_map(params![remote], map_connection_row)?
+        .collect::<Result<Vec<NetworkConnection>>>()?;
+
+    Ok(connections)
+}
+
+/// Processos à escuta numa porta (TCP em LISTEN, ou UDP aberto) em todas as máquinas
+pub fn find_listeners(pool: &DbPool, port: u16) -> Result<Vec<NetworkConnection>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM network_connections c
+         LEFT JOIN machines m ON m.machine_id = c.machine_id
+         WHERE c.local_port = ?1
+           AND ((c.protocol = 'tcp' AND c.state = 'LISTEN') OR c.protocol = 'udp')
+         ORDER BY m.hostname, c.protocol",
+        CONNECTION_COLUMNS
+    ))?;
+    let connections = stmt
+        .query_map(params![port], map_connection_row)?
+        .collect::<Result<Vec<NetworkConnection>>>()?;
+
+    Ok(connections)
+}
+
+// -------------------------------------------------
+// Dispositivos USB
+// -------------------------------------------------
+
+pub fn update_usb_devices(pool: &DbPool, machine_id: &str, devices: &[UsbDevice]) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM usb_devices WHERE machine_id = ?1", params![machine_id])?;
+
+    for d in devices {
+        tx.execute(
+            "INSERT OR REPLACE INTO usb_devices
+                (machine_id, device_id, name, vendor_id, product_id, serial, device_class,
+                 manufacturer, is_mass_storage, status, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                machine_id,
+                d.device_id,
+                d.name,
+                d.vendor_id,
+                d.product_id,
+                d.serial,
+                d.device_class,
+                d.manufacturer,
+                d.is_mass_storage,
+                d.status,
+                d.updated_at
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn get_usb_devices(pool: &DbPool, machine_id: &str) -> Result<Vec<UsbDevice>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT machine_id, device_id, name, vendor_id, product_id, serial, device_class,
+                manufacturer, is_mass_storage, status, updated_at
+         FROM usb_devices WHERE machine_id = ?1
+         ORDER BY is_mass_storage DESC, name",
+    )?;
+
+    let devices = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(UsbDevice {
+                machine_id: row.get(0)?,
+                device_id: row.get(1)?,
+                name: row.get(2)?,
+                vendor_id: row.get(3)?,
+                product_id: row.get(4)?,
+                serial: row.get(5)?,
+                device_class: row.get(6)?,
+                manufacturer: row.get(7)?,
+                is_mass_storage: row.get(8)?,
+                status: row.get(9)?,
+                updated_at: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<UsbDevice>>>()?;
+
+    Ok(devices)
+}
+
+pub fn insert_device_events(pool: &DbPool, machine_id: &str, events: &[DeviceEvent]) -> Result<()> {
+    let conn = pool.lock().unwrap();
+
+    for e in events {
+        conn.execute(
+            "INSERT INTO device_events
+                (machine_id, device_id, name, vendor_id, product_id, serial, is_mass_storage, event, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                machine_id,
+                e.device_id,
+                e.name,
+                e.vendor_id,
+                e.product_id,
+                e.serial,
+                e.is_mass_storage,
+                e.event,
+                e.timestamp
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Eventos mais recentes primeiro
+pub fn list_device_events(pool: &DbPool, machine_id: &str, limit: i64) -> Result<Vec<DeviceEvent>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, machine_id, device_id, name, vendor_id, product_id, serial, is_mass_storage, event, timestamp
+         FROM device_events WHERE machine_id = ?1
+         ORDER BY timestamp DESC LIMIT ?2",
+    )?;
+
+    let events = stmt
+        .query_map(params![machine_id, limit], |row| {
+            Ok(DeviceEvent {
+                id: row.get(0)?,
+                machine_id: row.get(1)?,
+                device_id: row.get(2)?,
+                name: row.get(3)?,
+                vendor_id: row.get(4)?,
+                product_id: row.get(5)?,
+                serial: row.get(6)?,
+                is_mass_storage: row.get(7)?,
+                event: row.get(8)?,
+                timestamp: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<DeviceEvent>>>()?;
+
+    Ok(events)
+}
+
+// -------------------------------------------------
+// Comandos remotos
+// -------------------------------------------------
+
+const COMMAND_COLUMNS: &str = "id, machine_id, command_type, payload, status, created_by, created_at, expires_at, delivered_at, completed_at, output, error";
+
+fn map_command_row(row: &rusqlite::Row) -> Result<AgentCommand> {
+    Ok(AgentCommand {
+        id: row.get(0)?,
+        machine_id: row.get(1)?,
+        command_type: row.get(2)?,
+        payload: row.get(3)?,
+        status: row.get(4)?,
+        created_by: row.get(5)?,
+        created_at: row.get(6)?,
+        expires_at: row.get(7)?,
+        delivered_at: row.get(8)?,
+        completed_at: row.get(9)?,
+        output: row.get(10)?,
+        error: row.get(11)?,
+    })
+}
+
+pub fn enqueue_agent_command(
+    pool: &DbPool,
+    machine_id: &str,
+    command_type: &str,
+    payload: &str,
+    created_by: &str,
+    ttl_minutes: i64,
+) -> Result<String> {
+    let conn = pool.lock().unwrap();
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::minutes(ttl_minutes);
+
+    conn.execute(
+        "INSERT INTO agent_commands (id, machine_id, command_type, payload, status, created_by, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, 'pending', ?5, ?6, ?7)",
+        params![id, machine_id, command_type, payload, created_by, now.to_rfc3339(), expires_at.to_rfc3339()],
+    )?;
+    Ok(id)
+}
+
+// Comandos por entregar ou sem resultado depois do prazo passam a "expired"
+fn expire_agent_commands(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "UPDATE agent_commands SET status = 'expired', completed_at = ?1
+         WHERE status IN ('pending', 'delivered') AND expires_at < ?1",
+        params![Utc::now().to_rfc3339()],
+    )
+}
+
+// Um comando entregue sem resultado ao fim deste tempo volta a ser enviado (o agente
+// pode ter reiniciado ou perdido o resultado); maior que o timeout mais longo do agente
+const COMMAND_REDELIVERY_MINUTES: i64 = 20;
+
+/// Comandos pendentes de uma máquina, marcados como entregues. Os entregues há
+/// mais de COMMAND_REDELIVERY_MINUTES sem resultado são reenviados: o agente
+/// ignora os IDs que já aceitou, por isso não os executa duas vezes
+pub fn take_pending_commands(pool: &DbPool, machine_id: &str) -> Result<Vec<AgentCommand>> {
+    let mut conn = pool.lock().unwrap();
+    expire_agent_commands(&conn)?;
+
+    // Dois pedidos em simultâneo não podem entregar o mesmo comando
+    let tx = conn.transaction()?;
+    let commands = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT {} FROM agent_commands
+             WHERE machine_id = ?1
+               AND (status = 'pending' OR (status = 'delivered' AND delivered_at < ?2))
+             ORDER BY created_at",
+            COMMAND_COLUMNS
+        ))?;
+        let redeliver_before = (Utc::now() - chrono::Duration::minutes(COMMAND_REDELIVERY_MINUTES)).to_rfc3339();
+        let commands = stmt
+            .query_map(params![machine_id, redeliver_before], map_command_row)?
+            .collect::<Result<Vec<AgentCommand>>>()?;
+        commands
+    };
+
+    let now = Utc::now().to_rfc3339();
+    for command in &commands {
+        tx.execute(
+            "UPDATE agent_commands SET status = 'delivered', delivered_at = ?1
+             WHERE id = ?2 AND status IN ('pending', 'delivered')",
+            params![now, command.id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(commands)
+}
+
+/// Regista o resultado enviado pelo agente; devolve `false` se o comando não estava à espera de resultado
+pub fn complete_agent_command(
+    pool: &DbPool,
+    command_id: &str,
+    machine_id: &str,
+    status: &str,
+    output: Option<&str>,
+    error: Option<&str>,
+    completed_at: &str,
+) -> Result<bool> {
+    let conn = pool.lock().unwrap();
+    let updated = conn.execute(
+        "UPDATE agent_commands SET status = ?1, output = ?2, error = ?3, completed_at = ?4
+         WHERE id = ?5 AND machine_id = ?6 AND status IN ('pending', 'delivered', 'expired')",
+        params![status, output, error, completed_at, command_id, machine_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Só comandos ainda não entregues podem ser cancelados
+pub fn cancel_agent_command(pool: &DbPool, command_id: &str) -> Result<bool> {
+    let conn = pool.lock().unwrap();
+    let updated = conn.execute(
+        "UPDATE agent_commands SET status = 'cancelled', completed_at = ?1 WHERE id = ?2 AND status = 'pending'",
+        params![Utc::now().to_rfc3339(), command_id],
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn list_agent_commands(pool: &DbPool, machine_id: Option<&str>, limit: i64) -> Result<Vec<AgentCommand>> {
+    let conn = pool.lock().unwrap();
+    expire_agent_commands(&conn)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM agent_commands WHERE (?1 IS NULL OR machine_id = ?1) ORDER BY created_at DESC LIMIT ?2",
+        COMMAND_COLUMNS
+    ))?;
+    let commands = stmt
+        .query_map(params![machine_id, limit], map_command_row)?
+        .collect::<Result<Vec<AgentCommand>>>()?;
+
+    Ok(commands)
+}
+
+pub fn create_approved_script(
+    pool: &DbPool,
+    name: &str,
+    description: &str,
+    interpreter: &str,
+    content: &str,
+    created_by: &str,
+) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let conn = pool.lock().unwrap();
+    let id = uuid::Uuid::new_v4().to_string();
+    let sha256 = hex::encode(Sha256::digest(content.as_bytes()));
+
+    conn.execute(
+        "INSERT INTO approved_scripts (id, name, description, interpreter, content, sha256, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![id, name, description, interpreter, content, sha256, created_by, Utc::now().to_rfc3339()],
+    )?;
+    Ok(id)
+}
+
+fn map_script_row(row: &rusqlite::Row) -> Result<ApprovedScript> {
+    Ok(ApprovedScript {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        interpreter: row.get(3)?,
+        content: row.get(4)?,
+        sha256: row.get(5)?,
+        created_by: row.get(6)?,
+        created_at: row.get(7)?,
+        signature: row.get(8)?,
+    })
+}
+
+pub fn set_script_signature(pool: &DbPool, script_id: &str, signature: &str) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "UPDATE approved_scripts SET signature = ?1 WHERE id = ?2",
+        params![signature, script_id],
+    )?;
+    Ok(())
+}
+
+pub fn list_approved_scripts(pool: &DbPool) -> Result<Vec<ApprovedScript>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description, interpreter, content, sha256, created_by, created_at, signature
+         FROM approved_scripts ORDER BY name",
+    )?;
+    let scripts = stmt
+        .query_map([], map_script_row)?
+        .collect::<Result<Vec<ApprovedScript>>>()?;
+
+    Ok(scripts)
+}
+
+pub fn get_approved_script(pool: &DbPool, script_id: &str) -> Result<Option<ApprovedScript>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description, interpreter, content, sha256, created_by, created_at, signature
+         FROM approved_scripts WHERE id = ?1",
+    )?;
+    let mut rows = stmt.query_map(params![script_id], map_script_row)?;
+    rows.next().transpose()
+}
+
+/// Comando ainda em curso (não concluído nem expirado) para a máquina e tipo indicados
+pub fn is_command_open(pool: &DbPool, command_id: &str, machine_id: &str, command_type: &str) -> Result<bool> {
+    let conn = pool.lock().unwrap();
+    expire_agent_commands(&conn)?;
+
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM agent_commands
+         WHERE id = ?1 AND machine_id = ?2 AND command_type = ?3 AND status IN ('pending', 'delivered')",
+        params![command_id, machine_id, command_type],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+// -------------------------------------------------
+// Capturas de ecrã
+// -------------------------------------------------
+
+const SCREENSHOT_COLUMNS: &str = "id, machine_id, command_id, captured_at, received_at, width, height, size_bytes";
+
+fn map_screenshot_row(row: &rusqlite::Row) -> Result<Screenshot> {
+    Ok(Screenshot {
+        id: row.get(0)?,
+        machine_id: row.get(1)?,
+        command_id: row.get(2)?,
+        captured_at: row.get(3)?,
+        received_at: row.get(4)?,
+        width: row.get(5)?,
+        height: row.get(6)?,
+        size_bytes: row.get(7)?,
+    })
+}
+
+pub fn insert_screenshot(pool: &DbPool, shot: &Screenshot) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        &format!("INSERT INTO screenshots ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", SCREENSHOT_COLUMNS),
+        params![
+            shot.id,
+            shot.machine_id,
+            shot.command_id,
+            shot.captured_at,
+            shot.received_at,
+            shot.width,
+            shot.height,
+            shot.size_bytes,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_screenshots(pool: &DbPool, machine_id: &str, limit: i64) -> Result<Vec<Screenshot>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM screenshots WHERE machine_id = ?1 ORDER BY captured_at DESC LIMIT ?2",
+        SCREENSHOT_COLUMNS
+    ))?;
+    let shots = stmt
+        .query_map(params![machine_id, limit], map_screenshot_row)?
+        .collect::<Result<Vec<Screenshot>>>()?;
+
+    Ok(shots)
+}
+
+pub fn get_screenshot(pool: &DbPool, id: &str) -> Result<Option<Screenshot>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM screenshots WHERE id = ?1", SCREENSHOT_COLUMNS))?;
+    let mut rows = stmt.query_map(params![id], map_screenshot_row)?;
+    rows.next().transpose()
+}
+
+pub fn delete_screenshot(pool: &DbPool, id: &str) -> Result<bool> {
+    let conn = pool.lock().unwrap();
+    Ok(conn.execute("DELETE FROM screenshots WHERE id = ?1", params![id])? > 0)
+}
+
+/// Aplica a retenção: mantém as `keep` mais recentes da máquina e nenhuma com mais de
+/// `max_age_days`. Devolve os IDs removidos, para apagar os ficheiros
+pub fn prune_screenshots(pool: &DbPool, machine_id: &str, keep: i64, max_age_days: i64) -> Result<Vec<String>> {
+    let conn = pool.lock().unwrap();
+    let cutoff = (Utc::now() - chrono::Duration::days(max_age_days)).to_rfc3339();
+
+    let mut stmt = conn.prepare(
+        "SELECT id FROM screenshots
+         WHERE captured_at < ?1
+            OR (machine_id = ?2 AND id NOT IN (
+                SELECT id FROM screenshots WHERE machine_id = ?2 ORDER BY captured_at DESC LIMIT ?3))",
+    )?;
+    let ids = stmt
+        .query_map(params![cutoff, machine_id, keep], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<String>>>()?;
+
+    for id in &ids {
+        conn.execute("DELETE FROM screenshots WHERE id = ?1", params![id])?;
+    }
+    Ok(ids)
+}
+
+// -------------------------------------------------
+// Discos
+// -------------------------------------------------
+
+pub fn update_disks(pool: &DbPool, machine_id: &str, disks: &[DiskInfo]) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "DELETE FROM disks WHERE machine_id = ?1",
+        params![machine_id],
+    )?;
+
+    for disk in disks {
+        conn.execute(
+            "INSERT INTO disks (machine_id, name, mount_point, total_gb, free_gb, fs_type)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                machine_id,
+                disk.name,
+                disk.mount_point,
+                disk.total_gb,
+                disk.free_gb,
+                disk.fs_type
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_disks(pool: &DbPool, machine_id: &str) -> Result<Vec<DiskInfo>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT name, mount_point, total_gb, free_gb, fs_type
+         FROM disks WHERE machine_id = ?1 ORDER BY mount_point",
+    )?;
+
+    let disks = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(DiskInfo {
+                name: row.get(0)?,
+                mount_point: row.get(1)?,
+                total_gb: row.get(2)?,
+                free_gb: row.get(3)?,
+                fs_type: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<DiskInfo>>>()?;
+
+    Ok(disks)
+}
+
+pub fn insert_metric(
+    pool: &DbPool,
+    machine_id: &str,
+    cpu_percent: f32,
+    ram_used_mb: i64,
+    ram_total_mb: i64,
+) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO metrics_history (machine_id, timestamp, cpu_percent, ram_used_mb, ram_total_mb)
+         VALUES (?1, CURRENT_TIMESTAMP, ?2, ?3, ?4)",
+        params![machine_id, cpu_percent, ram_used_mb, ram_total_mb],
+    )?;
+    Ok(())
+}
+
+// -------------------------------------------------
+// Software
+// -------------------------------------------------
+
+pub fn update_software(
+    pool: &DbPool,
+    machine_id: &str,
+    software_list: &[SoftwareEntry],
+) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "DELETE FROM software WHERE machine_id = ?1",
+        params![machine_id],
+    )?;
+
+    for sw in software_list {
+        conn.execute(
+            "INSERT INTO software (machine_id, name, version, publisher, install_date)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                machine_id,
+                sw.name,
+                sw.version,
+                sw.publisher,
+                sw.install_date
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_software(pool: &DbPool, machine_id: &str) -> Result<Vec<SoftwareEntry>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT name, version, publisher, install_date
+         FROM software WHERE machine_id = ?1 ORDER BY name",
+    )?;
+
+    let software = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(SoftwareEntry {
+                name: row.get(0)?,
+                version: row.get(1)?,
+                publisher: row.get(2)?,
+                install_date: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<SoftwareEntry>>>()?;
+
+    Ok(software)
+}
+
+// -------------------------------------------------
+// Serviços
+// -------------------------------------------------
+
+pub fn update_services(pool: &DbPool, machine_id: &str, services: &[ServiceEntry]) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM services WHERE machine_id = ?1", params![machine_id])?;
+
+    for s in services {
+        tx.execute(
+            "INSERT INTO services (machine_id, name, display_name, state, start_mode)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![machine_id, s.name, s.display_name, s.state, s.start_mode],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn get_services(pool: &DbPool, machine_id: &str) -> Result<Vec<ServiceEntry>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT name, display_name, state, start_mode
+         FROM services WHERE machine_id = ?1 ORDER BY name",
+    )?;
+
+    let services = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(ServiceEntry {
+                name: row.get(0)?,
+                display_name: row.get(1)?,
+                state: row.get(2)?,
+                start_mode: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<ServiceEntry>>>()?;
+
+    Ok(services)
+}
+
+// -------------------------------------------------
+// Auditoria
+// -------------------------------------------------
+
+pub fn log_audit(
+    pool: &DbPool,
+    action: &str,
+    resource_type: &str,
+    resource_id: &str,
+    user: &str,
+    details: &str,
+) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO audit_logs (action, resource_type, resource_id, user, details)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![action, resource_type, resource_id, user, details],
+    )?;
+    Ok(())
+}
+
+// -------------------------------------------------
+// Filtros Avançados (NOVO v3.0)
+// -------------------------------------------------
+pub fn list_machines_filtered(pool: &DbPool, filters: MachineFilters) -> Result<Vec<Machine>> {
+    let conn = pool.lock().unwrap();
+    let mut query = "
+        SELECT
+            m.id, m.machine_id, m.hostname, m.custom_name, m.tags, m.notes,
+            m.last_seen, m.cpu_name, m.cpu_cores, m.ram_total_mb, m.ram_used_mb,
+            m.os_name, m.os_version, m.uptime_hours,
+            COUNT(DISTINCT d.id) AS disk_count,
+            COUNT(DISTINCT s.id) AS software_count,
+            COUNT(DISTINCT p.id) AS process_count,
+            m.local_ip, m.mac_address, m.serial_number, m.motherboard_model,
+            m.gpu_name, m.is_bitlocker_active, m.domain_name, m.current_user
+        FROM machines m
+        LEFT JOIN disks d ON d.machine_id = m.machine_id
+        LEFT JOIN software s ON s.machine_id = m.machine_id
+        LEFT JOIN processes p ON p.machine_id = m.machine_id
+        WHERE 1=1
+    "
+    .to_string();
+
+    if let Some(search) = filters.search_term {
+        query.push_str(&format!(
+            " AND (m.hostname LIKE '%{}%' OR m.custom_name LIKE '%{}%')",
+            search, search
+        ));
+    }
+
+    query.push_str(" GROUP BY m.id ORDER BY m.last_seen DESC");
+
+    let mut stmt = conn.prepare(&query)?;
+
+    let machines = stmt
+        .query_map([], |row| {
+            let last_seen: String = row.get(6)?;
+            let is_online = is_machine_online(&last_seen);
+            let tags_json: String = row.get(4).unwrap_or_else(|_| "[]".to_string());
+            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+
+            Ok(Machine {
+                id: row.get(0)?,
+                machine_id: row.get(1)?,
+                hostname: row.get(2)?,
+                custom_name: row.get(3)?,
+                tags,
+                notes: row.get(5)?,
+                last_seen,
+                cpu_name: row.get(7)?,
+                cpu_cores: row.get(8)?,
+                ram_total_mb: row.get(9)?,
+                ram_used_mb: row.get(10)?,
+                os_name: row.get(11)?,
+                os_version: row.get(12)?,
+                uptime_hours: row.get(13)?,
+                disk_count: row.get(14)?,
+                software_count: row.get(15)?,
+                process_count: row.get(16)?,
+                is_online,
+                local_ip: row.get(17).ok(),
+                mac_address: row.get(18).ok(),
+                serial_number: row.get(19).ok(),
+                motherboard_model: row.get(20).ok(),
+                gpu_name: row.get(21).ok(),
+                is_bitlocker_active: row.get(22).ok(),
+                domain_name: row.get(23).ok(),
+                current_user: row.get(24).ok(),
+            })
+        })?
+        .collect::<Result<Vec<Machine>>>()?;
+
+    Ok(machines)
+}
+
+pub fn get_hardware_details(pool: &DbPool, machine_id: &str) -> Result<HardwareDetails> {
+    let conn = pool.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT 
+            serial_number, motherboard_manufacturer, motherboard_model,
+            bios_version, gpu_name, gpu_vram_mb, total_ram_slots,
+            used_ram_slots, ram_type
+         FROM hardware_details
+         WHERE machine_id = ?1",
+    )?;
+
+    let details = stmt.query_row([machine_id], |row| {
+        Ok(HardwareDetails {
+            serial_number: row.get(0)?,
+            motherboard_manufacturer: row.get(1)?,
+            motherboard_model: row.get(2)?,
+            bios_version: row.get(3)?,
+            gpu_name: row.get(4)?,
+            gpu_vram_mb: row.get(5)?,
+            total_ram_slots: row.get(6)?,
+            used_ram_slots: row.get(7)?,
+            ram_type: row.get(8)?,
+        })
+    })?;
+
+    Ok(details)
+}
+
+pub fn update_hardware_details(
+    pool: &DbPool,
+    machine_id: &str,
+    details: &HardwareDetails,
+) -> Result<()> {
+    let conn = pool.lock().unwrap();
+
+    conn.execute(
+        "INSERT OR REPLACE INTO hardware_details (
+            machine_id, serial_number, motherboard_manufacturer,
+            motherboard_model, bios_version, gpu_name, gpu_vram_mb,
+            total_ram_slots, used_ram_slots, ram_type, updated_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            machine_id,
+            &details.serial_number,
+            &details.motherboard_manufacturer,
+            &details.motherboard_model,
+            &details.bios_version,
+            &details.gpu_name,
+            details.gpu_vram_mb,
+            details.total_ram_slots,
+            details.used_ram_slots,
+            &details.ram_type,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Componente de hardware reportado: chave estável e os valores das colunas da tabela
+pub struct ComponentRow {
+    pub key: String,
+    pub values: Vec<Value>,
+}
+
+/// Acerta o inventário de um tipo de componente: os que continuam ficam com o novo
+/// last_seen, os novos entram e os que deixaram de aparecer ficam com removed_at.
+/// Devolve o id de cada chave reportada
+pub fn sync_components(
+    pool: &DbPool,
+    table: &str,
+    columns: &[&str],
+    machine_id: &str,
+    rows: &[ComponentRow],
+    now: &str,
+) -> Result<HashMap<String, i64>> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    let mut ids = HashMap::new();
+    {
+        let installed: HashMap<String, i64> = tx
+            .prepare(&format!(
+                "SELECT component_key, id FROM {} WHERE machine_id = ?1 AND removed_at IS NULL",
+                table
+            ))?
+            .query_map(params![machine_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        let assignments: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ?{}", column, i + 2))
+            .collect();
+        let mut update = tx.prepare(&format!(
+            "UPDATE {} SET last_seen = ?1, {} WHERE id = ?{}",
+            table,
+            assignments.join(", "),
+            columns.len() + 2
+        ))?;
+        let placeholders: Vec<String> = (0..columns.len()).map(|i| format!("?{}", i + 4)).collect();
+        let mut insert = tx.prepare(&format!(
+            "INSERT INTO {} (machine_id, component_key, first_seen, last_seen, {})
+             VALUES (?1, ?2, ?3, ?3, {})",
+            table,
+            columns.join(", "),
+            placeholders.join(", ")
+        ))?;
+
+        for row in rows {
+            let id = match installed.get(&row.key) {
+                Some(id) => {
+                    let mut values = vec![Value::from(now.to_string())];
+                    values.extend(row.values.iter().cloned());
+                    values.push(Value::from(*id));
+                    update.execute(params_from_iter(values))?;
+                    *id
+                }
+                None => {
+                    let mut values = vec![
+                        Value::from(machine_id.to_string()),
+                        Value::from(row.key.clone()),
+                        Value::from(now.to_string()),
+                    ];
+                    values.extend(row.values.iter().cloned());
+                    insert.execute(params_from_iter(values))?;
+                    tx.last_insert_rowid()
+                }
+            };
+            ids.insert(row.key.clone(), id);
+        }
+
+        for (key, id) in &installed {
+            if !ids.contains_key(key) {
+                tx.execute(
+                    &format!("UPDATE {} SET removed_at = ?1 WHERE id = ?2", table),
+                    params![now, id],
+                )?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(ids)
+}
+
+pub fn get_memory_modules(pool: &DbPool, machine_id: &str, include_removed: bool) -> Result<Vec<MemoryModule>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, slot, size_mb, speed_mts, memory_type, manufacturer, part_number, serial_number,
+                first_seen, last_seen, removed_at
+         FROM memory_modules
+         WHERE machine_id = ?1 AND (?2 = 1 OR removed_at IS NULL)
+         ORDER BY removed_at IS NOT NULL, slot",
+    )?;
+    let modules = stmt
+        .query_map(params![machine_id, include_removed], |row| {
+            Ok(MemoryModule {
+                id: row.get(0)?,
+                slot: row.get(1)?,
+                size_mb: row.get(2)?,
+                speed_mts: row.get(3)?,
+                memory_type: row.get(4)?,
+                manufacturer: row.get(5)?,
+                part_number: row.get(6)?,
+                serial_number: row.get(7)?,
+                first_seen: row.get(8)?,
+                last_seen: row.get(9)?,
+                removed_at: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(modules)
+}
+
+pub fn get_gpus(pool: &DbPool, machine_id: &str, include_removed: bool) -> Result<Vec<GpuInfo>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, name, vendor, vram_mb, driver_version, first_seen, last_seen, removed_at
+         FROM gpus
+         WHERE machine_id = ?1 AND (?2 = 1 OR removed_at IS NULL)
+         ORDER BY removed_at IS NOT NULL, vram_mb DESC",
+    )?;
+    let gpus = stmt
+        .query_map(params![machine_id, include_removed], |row| {
+            Ok(GpuInfo {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                vendor: row.get(2)?,
+                vram_mb: row.get(3)?,
+                driver_version: row.get(4)?,
+                first_seen: row.get(5)?,
+                last_seen: row.get(6)?,
+                removed_at: row.get(7)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(gpus)
+}
+
+pub fn get_monitors(pool: &DbPool, machine_id: &str, include_removed: bool) -> Result<Vec<MonitorInfo>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, manufacturer, model, serial_number, width, height, first_seen, last_seen, removed_at
+         FROM monitors
+         WHERE machine_id = ?1 AND (?2 = 1 OR removed_at IS NULL)
+         ORDER BY removed_at IS NOT NULL, first_seen",
+    )?;
+    let monitors = stmt
+        .query_map(params![machine_id, include_removed], |row| {
+            Ok(MonitorInfo {
+                id: row.get(0)?,
+                manufacturer: row.get(1)?,
+                model: row.get(2)?,
+                serial_number: row.get(3)?,
+                width: row.get(4)?,
+                height: row.get(5)?,
+                first_seen: row.get(6)?,
+                last_seen: row.get(7)?,
+                removed_at: row.get(8)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(monitors)
+}
+
+/// Desgaste em %: capacidade que a bateria já perdeu face à de fábrica
+fn battery_wear(design: Option<i64>, full_charge: Option<i64>) -> Option<f64> {
+    let (design, full_charge) = (design.filter(|d| *d > 0)?, full_charge?);
+    Some(((1.0 - full_charge as f64 / design as f64) * 100.0).max(0.0))
+}
+
+pub fn get_batteries(pool: &DbPool, machine_id: &str, include_removed: bool) -> Result<Vec<BatteryInfo>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, name, manufacturer, serial_number, chemistry, design_capacity_mwh,
+                full_charge_capacity_mwh, cycle_count, first_seen, last_seen, removed_at
+         FROM batteries
+         WHERE machine_id = ?1 AND (?2 = 1 OR removed_at IS NULL)
+         ORDER BY removed_at IS NOT NULL, first_seen",
+    )?;
+    let batteries = stmt
+        .query_map(params![machine_id, include_removed], |row| {
+            let design_capacity_mwh: Option<i64> = row.get(5)?;
+            let full_charge_capacity_mwh: Option<i64> = row.get(6)?;
+            Ok(BatteryInfo {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                manufacturer: row.get(2)?,
+                serial_number: row.get(3)?,
+                chemistry: row.get(4)?,
+                wear_percent: battery_wear(design_capacity_mwh, full_charge_capacity_mwh),
+                design_capacity_mwh,
+                full_charge_capacity_mwh,
+                cycle_count: row.get(7)?,
+                first_seen: row.get(8)?,
+                last_seen: row.get(9)?,
+                removed_at: row.get(10)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(batteries)
+}
+
+pub fn get_physical_disks(pool: &DbPool, machine_id: &str, include_removed: bool) -> Result<Vec<PhysicalDisk>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id, model, serial_number, media_type, bus_type, size_gb, health, first_seen, last_seen, removed_at
+         FROM physical_disks
+         WHERE machine_id = ?1 AND (?2 = 1 OR removed_at IS NULL)
+         ORDER BY removed_at IS NOT NULL, first_seen",
+    )?;
+    let disks = stmt
+        .query_map(params![machine_id, include_removed], |row| {
+            Ok(PhysicalDisk {
+                id: row.get(0)?,
+                model: row.get(1)?,
+                serial_number: row.get(2)?,
+                media_type: row.get(3)?,
+                bus_type: row.get(4)?,
+                size_gb: row.get(5)?,
+                health: row.get(6)?,
+                first_seen: row.get(7)?,
+                last_seen: row.get(8)?,
+                removed_at: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(disks)
+}
+
+/// Grava a capacidade da bateria se mudou desde a última leitura
+pub fn record_battery_reading(pool: &DbPool, reading: &BatteryReading) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    let last: Option<(Option<i64>, Option<i64>, Option<i64>)> = conn
+        .query_row(
+            "SELECT design_capacity_mwh, full_charge_capacity_mwh, cycle_count FROM battery_readings
+             WHERE battery_id = ?1 ORDER BY recorded_at DESC, id DESC LIMIT 1",
+            params![reading.battery_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+
+    let current = (reading.design_capacity_mwh, reading.full_charge_capacity_mwh, reading.cycle_count);
+    if last != Some(current) {
+        conn.execute(
+            "INSERT INTO battery_readings
+                (battery_id, design_capacity_mwh, full_charge_capacity_mwh, cycle_count, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                reading.battery_id,
+                reading.design_capacity_mwh,
+                reading.full_charge_capacity_mwh,
+                reading.cycle_count,
+                reading.recorded_at,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_battery_readings(pool: &DbPool, machine_id: &str) -> Result<Vec<BatteryReading>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT r.battery_id, r.design_capacity_mwh, r.full_charge_capacity_mwh, r.cycle_count, r.recorded_at
+         FROM battery_readings r
+         JOIN batteries b ON b.id = r.battery_id
+         WHERE b.machine_id = ?1
+         ORDER BY r.recorded_at",
+    )?;
+    let readings = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(BatteryReading {
+                battery_id: row.get(0)?,
+                design_capacity_mwh: row.get(1)?,
+                full_charge_capacity_mwh: row.get(2)?,
+                cycle_count: row.get(3)?,
+                recorded_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(readings)
+}
+
+/// Grava o estado SMART do disco se mudou (o primeiro também fica registado)
+pub fn record_disk_health(pool: &DbPool, change: &DiskHealthChange) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    let last: Option<Option<String>> = conn
+        .query_row(
+            "SELECT health FROM disk_health_history WHERE disk_id = ?1 ORDER BY recorded_at DESC, id DESC LIMIT 1",
+            params![change.disk_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if last.as_ref() != Some(&change.health) {
+        conn.execute(
+            "INSERT INTO disk_health_history (disk_id, health, recorded_at) VALUES (?1, ?2, ?3)",
+            params![change.disk_id, change.health, change.recorded_at],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_disk_health_history(pool: &DbPool, machine_id: &str) -> Result<Vec<DiskHealthChange>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT h.disk_id, h.health, h.recorded_at
+         FROM disk_health_history h
+         JOIN physical_disks d ON d.id = h.disk_id
+         WHERE d.machine_id = ?1
+         ORDER BY h.recorded_at",
+    )?;
+    let history = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(DiskHealthChange {
+                disk_id: row.get(0)?,
+                health: row.get(1)?,
+                recorded_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(history)
+}
+
+pub fn insert_hardware_change(pool: &DbPool, change: &HardwareChange) -> Result<i64> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO hardware_changes
+            (machine_id, component, change_type, old_value, new_value, suspicious, detected_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            change.machine_id,
+            change.component,
+            change.change_type,
+            change.old_value,
+            change.new_value,
+            change.suspicious,
+            change.detected_at,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn list_hardware_changes(
+    pool: &DbPool,
+    machine_id: Option<&str>,
+    suspicious_only: bool,
+    limit: i64,
+) -> Result<Vec<HardwareChange>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.machine_id, m.hostname, c.component, c.change_type, c.old_value, c.new_value,
+                c.suspicious, c.detected_at
+         FROM hardware_changes c
+         LEFT JOIN machines m ON m.machine_id = c.machine_id
+         WHERE (?1 IS NULL OR c.machine_id = ?1) AND (?2 = 0 OR c.suspicious = 1)
+         ORDER BY c.detected_at DESC, c.id DESC
+         LIMIT ?3",
+    )?;
+
+    let changes = stmt
+        .query_map(params![machine_id, suspicious_only, limit], |row| {
+            Ok(HardwareChange {
+                id: row.get(0)?,
+                machine_id: row.get(1)?,
+                hostname: row.get(2)?,
+                component: row.get(3)?,
+                change_type: row.get(4)?,
+                old_value: row.get(5)?,
+                new_value: row.get(6)?,
+                suspicious: row.get(7)?,
+                detected_at: row.get(8)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(changes)
+}
+
+pub fn list_hardware_change_rules(pool: &DbPool) -> Result<Vec<HardwareChangeRule>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare("SELECT component, suspicious FROM hardware_change_rules ORDER BY component")?;
+    let rules = stmt
+        .query_map([], |row| {
+            Ok(HardwareChangeRule {
+                component: row.get(0)?,
+                suspicious: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(rules)
+}
+
+pub fn set_hardware_change_rule(pool: &DbPool, component: &str, suspicious: bool) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO hardware_change_rules (component, suspicious) VALUES (?1, ?2)
+         ON CONFLICT(component) DO UPDATE SET suspicious = excluded.suspicious",
+        params![component, suspicious],
+    )?;
+    Ok(())
+}
+
+fn fingerprint_from_row(row: &rusqlite::Row) -> Result<MachineFingerprint> {
+    Ok(MachineFingerprint {
+        machine_id: row.get(0)?,
+        fingerprint: row.get(1)?,
+        system_uuid: row.get(2)?,
+        bios_serial: row.get(3)?,
+        board_serial: row.get(4)?,
+        primary_mac: row.get(5)?,
+        hostname: row.get(6)?,
+        first_seen: row.get(7)?,
+        last_seen: row.get(8)?,
+        report_count: row.get(9)?,
+    })
+}
+
+const FINGERPRINT_COLUMNS: &str = "machine_id, fingerprint, system_uuid, bios_serial, board_serial, primary_mac,
+     hostname, first_seen, last_seen, report_count";
+
+/// Regista mais um relatório com esta impressão digital (as âncoras e o hostname ficam os últimos)
+pub fn record_machine_fingerprint(pool: &DbPool, fp: &MachineFingerprint) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO machine_fingerprints
+            (machine_id, fingerprint, system_uuid, bios_serial, board_serial, primary_mac,
+             hostname, first_seen, last_seen, report_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, 1)
+         ON CONFLICT(machine_id, fingerprint) DO UPDATE SET
+            system_uuid = excluded.system_uuid,
+            bios_serial = excluded.bios_serial,
+            board_serial = excluded.board_serial,
+            primary_mac = excluded.primary_mac,
+            hostname = excluded.hostname,
+            last_seen = excluded.last_seen,
+            report_count = report_count + 1",
+        params![
+            fp.machine_id,
+            fp.fingerprint,
+            fp.system_uuid,
+            fp.bios_serial,
+            fp.board_serial,
+            fp.primary_mac,
+            fp.hostname,
+            fp.last_seen,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Impressões digitais vistas num machine_id, a mais recente primeiro
+pub fn list_machine_fingerprints(pool: &DbPool, machine_id: &str) -> Result<Vec<MachineFingerprint>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM machine_fingerprints WHERE machine_id = ?1 ORDER BY last_seen DESC",
+        FINGERPRINT_COLUMNS
+    ))?;
+    let list = stmt
+        .query_map(params![machine_id], fingerprint_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(list)
+}
+
+/// Outras máquinas (ainda registadas) que já reportaram esta impressão digital
+pub fn find_fingerprint_elsewhere(
+    pool: &DbPool,
+    fingerprint: &str,
+    machine_id: &str,
+) -> Result<Vec<MachineFingerprint>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM machine_fingerprints f
+         WHERE f.fingerprint = ?1 AND f.machine_id != ?2
+           AND EXISTS (SELECT 1 FROM machines m WHERE m.machine_id = f.machine_id)
+         ORDER BY f.last_seen DESC",
+        FINGERPRINT_COLUMNS
+    ))?;
+    let list = stmt
+        .query_map(params![fingerprint, machine_id], fingerprint_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(list)
+}
+
+/// Guarda o conflito, ou actualiza o já conhecido para o mesmo par (em qualquer ordem).
+/// Devolve `None` se o par já existia: só os novos geram alertas, e os dispensados não voltam
+pub fn upsert_identity_conflict(pool: &DbPool, conflict: &IdentityConflict) -> Result<Option<i64>> {
+    let conn = pool.lock().unwrap();
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM identity_conflicts
+             WHERE kind = ?1
+               AND ((machine_id = ?2 AND fingerprint = ?3 AND other_machine_id = ?4 AND other_fingerprint = ?5)
+                 OR (machine_id = ?4 AND fingerprint = ?5 AND other_machine_id = ?2 AND other_fingerprint = ?3))
+             ORDER BY id DESC LIMIT 1",
+            params![
+                conflict.kind,
+                conflict.machine_id,
+                conflict.fingerprint,
+                conflict.other_machine_id,
+                conflict.other_fingerprint,
+            ],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if let Some(id) = existing {
+        conn.execute(
+            "UPDATE identity_conflicts SET last_seen = ?1 WHERE id = ?2",
+            params![conflict.last_seen, id],
+        )?;
+        return Ok(None);
+    }
+
+    conn.execute(
+        "INSERT INTO identity_conflicts
+            (kind, machine_id, hostname, fingerprint, other_machine_id, other_hostname, other_fingerprint,
+             detected_at, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            conflict.kind,
+            conflict.machine_id,
+            conflict.hostname,
+            conflict.fingerprint,
+            conflict.other_machine_id,
+            conflict.other_hostname,
+            conflict.other_fingerprint,
+            conflict.detected_at,
+            conflict.last_seen,
+        ],
+    )?;
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+const CONFLICT_COLUMNS: &str = "id, kind, machine_id, hostname, fingerprint, other_machine_id, other_hostname,
+     other_fingerprint, detected_at, last_seen, resolved_at, resolution";
+
+fn conflict_from_row(row: &rusqlite::Row) -> Result<IdentityConflict> {
+    Ok(IdentityConflict {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        machine_id: row.get(2)?,
+        hostname: row.get(3)?,
+        fingerprint: row.get(4)?,
+        other_machine_id: row.get(5)?,
+        other_hostname: row.get(6)?,
+        other_fingerprint: row.get(7)?,
+        detected_at: row.get(8)?,
+        last_seen: row.get(9)?,
+        resolved_at: row.get(10)?,
+        resolution: row.get(11)?,
+    })
+}
+
+pub fn list_identity_conflicts(pool: &DbPool, include_resolved: bool) -> Result<Vec<IdentityConflict>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM identity_conflicts
+         WHERE ?1 = 1 OR resolved_at IS NULL
+         ORDER BY last_seen DESC, id DESC",
+        CONFLICT_COLUMNS
+    ))?;
+    let list = stmt
+        .query_map(params![include_resolved], conflict_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(list)
+}
+
+pub fn resolve_identity_conflict(pool: &DbPool, id: i64, resolution: &str, resolved_at: &str) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "UPDATE identity_conflicts SET resolution = ?1, resolved_at = ?2 WHERE id = ?3 AND resolved_at IS NULL",
+        params![resolution, resolved_at, id],
+    )?;
+    Ok(())
+}
+
+/// machine_id para onde foi unido, se este for de um registo já unido
+pub fn resolve_machine_alias(pool: &DbPool, machine_id: &str) -> Result<Option<String>> {
+    let conn = pool.lock().unwrap();
+    conn.query_row(
+        "SELECT machine_id FROM machine_aliases WHERE alias_id = ?1",
+        params![machine_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Estado actual, substituído a cada relatório: o do registo unido é descartado
+/// (o próximo relatório preenche o do destino) em vez de se misturar com o do destino
+const SNAPSHOT_TABLES: [&str; 14] = [
+    "disks",
+    "software",
+    "services",
+    "processes",
+    "network_adapters",
+    "network_addresses",
+    "network_routes",
+    "network_connections",
+    "usb_devices",
+    "hardware_details",
+    "network_details",
+    "security_status",
+    "policy_enforcement",
+    "machine_policy_revisions",
+];
+
+/// Histórico que passa do registo unido para o destino tal como está (sem chaves únicas
+/// por máquina, nada colide)
+const HISTORY_TABLES: [&str; 17] = [
+    "ip_assignments",
+    "ip_change_events",
+    "memory_modules",
+    "gpus",
+    "monitors",
+    "batteries",
+    "physical_disks",
+    "hardware_changes",
+    "metrics_history",
+    "metrics_aggregated",
+    "historical_metrics",
+    "vulnerabilities",
+    "license_assignments",
+    "policy_matches",
+    "device_events",
+    "agent_commands",
+    "screenshots",
+];
+
+/// Histórico com chave única por máquina: as linhas que colidem com as do destino são
+/// somadas (contadores) e ficam com os campos da mais recente
+const KEYED_HISTORY_MERGES: [(&str, &str, &str); 3] = [
+    (
+        "machine_fingerprints",
+        "fingerprint",
+        "INSERT INTO machine_fingerprints
+            (machine_id, fingerprint, system_uuid, bios_serial, board_serial, primary_mac, hostname,
+             first_seen, last_seen, report_count)
+         SELECT ?2, fingerprint, system_uuid, bios_serial, board_serial, primary_mac, hostname,
+                first_seen, last_seen, report_count
+         FROM machine_fingerprints WHERE machine_id = ?1
+         ON CONFLICT(machine_id, fingerprint) DO UPDATE SET
+            system_uuid  = CASE WHEN excluded.last_seen > last_seen THEN excluded.system_uuid ELSE system_uuid END,
+            bios_serial  = CASE WHEN excluded.last_seen > last_seen THEN excluded.bios_serial ELSE bios_serial END,
+            board_serial = CASE WHEN excluded.last_seen > last_seen THEN excluded.board_serial ELSE board_serial END,
+            primary_mac  = CASE WHEN excluded.last_seen > last_seen THEN excluded.primary_mac ELSE primary_mac END,
+            hostname     = CASE WHEN excluded.last_seen > last_seen THEN excluded.hostname ELSE hostname END,
+            first_seen   = MIN(first_seen, excluded.first_seen),
+            last_seen    = MAX(last_seen, excluded.last_seen),
+            report_count = report_count + excluded.report_count",
+    ),
+    (
+        "app_violations",
+        "policy_id, exe_path, action",
+        "INSERT INTO app_violations
+            (machine_id, policy_id, exe_path, action, process_name, sha256, publisher, original_filename,
+             hit_count, first_seen, last_seen)
+         SELECT ?2, policy_id, exe_path, action, process_name, sha256, publisher, original_filename,
+                hit_count, first_seen, last_seen
+         FROM app_violations WHERE machine_id = ?1
+         ON CONFLICT(machine_id, policy_id, exe_path, action) DO UPDATE SET
+            process_name      = CASE WHEN excluded.last_seen > last_seen THEN excluded.process_name ELSE process_name END,
+            sha256            = CASE WHEN excluded.last_seen > last_seen THEN excluded.sha256 ELSE sha256 END,
+            publisher         = CASE WHEN excluded.last_seen > last_seen THEN excluded.publisher ELSE publisher END,
+            original_filename = CASE WHEN excluded.last_seen > last_seen THEN excluded.original_filename ELSE original_filename END,
+            hit_count         = hit_count + excluded.hit_count,
+            first_seen        = MIN(first_seen, excluded.first_seen),
+            last_seen         = MAX(last_seen, excluded.last_seen)",
+    ),
+    (
+        "dns_query_stats",
+        "domain, date",
+        "INSERT INTO dns_query_stats (machine_id, domain, date, queries, blocked, policy_id, last_query)
+         SELECT ?2, domain, date, queries, blocked, policy_id, last_query
+         FROM dns_query_stats WHERE machine_id = ?1
+         ON CONFLICT(machine_id, domain, date) DO UPDATE SET
+            queries    = queries + excluded.queries,
+            blocked    = blocked + excluded.blocked,
+            policy_id  = CASE WHEN excluded.last_query > last_query THEN COALESCE(excluded.policy_id, policy_id)
+                              ELSE COALESCE(policy_id, excluded.policy_id) END,
+            last_query = MAX(last_query, excluded.last_query)",
+    ),
+];
+
+/// Tabelas com machine_id que a união não move: a própria máquina e a identidade (tratadas
+/// à parte), as políticas (configuração do administrador, não histórico) e a credencial,
+/// que pertence ao id com que o agente se apresenta e continua válida depois da união
+const NOT_MERGED_TABLES: [&str; 6] = [
+    "machines",
+    "machine_aliases",
+    "identity_conflicts",
+    "policies",
+    "machine_credentials",
+    "screen_time",
+];
+
+pub enum MergeOutcome {
+    /// Com o número de linhas da origem somadas a linhas do destino
+    Merged { combined_rows: usize },
+    /// Nada foi alterado
+    Refused(String),
+}
+
+/// Une o registo `source` ao `target`, tudo numa transacção: o histórico passa para o
+/// destino (linhas que colidem com as do destino são somadas), o estado actual de `source`
+/// é descartado, o nome, notas e tags em falta são copiados, `source` fica como alias do
+/// destino e a união fica na auditoria
+pub fn merge_machines(pool: &DbPool, source: &str, target: &str, user: &str, merged_at: &str) -> Result<MergeOutcome> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+
+    for id in [source, target] {
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM machines WHERE machine_id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(MergeOutcome::Refused(format!("Máquina {} não encontrada", id)));
+        }
+    }
+
+    // As políticas próprias da origem seriam apagadas com ela (ON DELETE CASCADE)
+    let own_policies: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM policies WHERE machine_id = ?1",
+        params![source],
+        |row| row.get(0),
+    )?;
+    if own_policies > 0 {
+        return Ok(MergeOutcome::Refused(format!(
+            "A máquina {} tem {} política(s) própria(s); mova-as ou apague-as antes de unir",
+            source, own_policies
+        )));
+    }
+
+    // Uma tabela nova com machine_id tem de ser classificada acima, senão perdia-se ao unir
+    let tables: Vec<String> = tx
+        .prepare(
+            "SELECT t.name FROM sqlite_master t
+             WHERE t.type = 'table'
+               AND EXISTS (SELECT 1 FROM pragma_table_info(t.name) c WHERE c.name = 'machine_id')",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>>>()?;
+    let known = |table: &str| {
+        SNAPSHOT_TABLES.contains(&table)
+            || HISTORY_TABLES.contains(&table)
+            || NOT_MERGED_TABLES.contains(&table)
+            || KEYED_HISTORY_MERGES.iter().any(|(t, _, _)| *t == table)
+    };
+    if let Some(table) = tables.iter().find(|t| !known(t)) {
+        return Ok(MergeOutcome::Refused(format!("Tabela {} sem regra de união de máquinas", table)));
+    }
+    let present = |table: &str| tables.iter().any(|t| t == table);
+
+    for table in SNAPSHOT_TABLES.iter().filter(|t| present(t)) {
+        tx.execute(&format!("DELETE FROM {} WHERE machine_id = ?1", table), params![source])?;
+    }
+    for table in HISTORY_TABLES.iter().filter(|t| present(t)) {
+        tx.execute(&format!("UPDATE {} SET machine_id = ?2 WHERE machine_id = ?1", table), params![source, target])?;
+    }
+
+    let mut combined = merge_screen_time(&tx, source, target)?;
+    for (table, key, upsert) in KEYED_HISTORY_MERGES {
+        let key_of = |alias: &str| key.split(", ").map(|c| format!("{}.{}", alias, c)).collect::<Vec<_>>().join(", ");
+        let colliding: i64 = tx.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {table} s WHERE s.machine_id = ?1
+                   AND EXISTS (SELECT 1 FROM {table} t WHERE t.machine_id = ?2 AND ({}) = ({}))",
+                key_of("t"),
+                key_of("s"),
+            ),
+            params![source, target],
+            |row| row.get(0),
+        )?;
+        combined += colliding as usize;
+        tx.execute(upsert, params![source, target])?;
+        tx.execute(&format!("DELETE FROM {} WHERE machine_id = ?1", table), params![source])?;
+    }
+
+    tx.execute(
+        "UPDATE machines SET
+            custom_name = COALESCE(custom_name, (SELECT custom_name FROM machines WHERE machine_id = ?1)),
+            notes = COALESCE(notes, (SELECT notes FROM machines WHERE machine_id = ?1)),
+            tags = CASE WHEN tags IS NULL OR tags = '[]'
+                        THEN COALESCE((SELECT tags FROM machines WHERE machine_id = ?1), tags)
+                        ELSE tags END
+         WHERE machine_id = ?2",
+        params![source, target],
+    )?;
+    tx.execute("DELETE FROM machines WHERE machine_id = ?1", params![source])?;
+
+    // Aliases antigos do registo unido passam a apontar para o destino
+    tx.execute("DELETE FROM machine_aliases WHERE alias_id = ?1", params![target])?;
+    tx.execute(
+        "UPDATE machine_aliases SET machine_id = ?2 WHERE machine_id = ?1",
+        params![source, target],
+    )?;
+    tx.execute(
+        "INSERT OR REPLACE INTO machine_aliases (alias_id, machine_id, merged_at) VALUES (?1, ?2, ?3)",
+        params![source, target, merged_at],
+    )?;
+
+    tx.execute(
+        "UPDATE identity_conflicts SET resolution = 'merged', resolved_at = ?3
+         WHERE resolved_at IS NULL
+           AND ((machine_id = ?1 AND other_machine_id = ?2) OR (machine_id = ?2 AND other_machine_id = ?1))",
+        params![source, target, merged_at],
+    )?;
+    tx.execute(
+        "UPDATE identity_conflicts SET machine_id = ?2 WHERE machine_id = ?1",
+        params![source, target],
+    )?;
+    tx.execute(
+        "UPDATE identity_conflicts SET other_machine_id = ?2 WHERE other_machine_id = ?1",
+        params![source, target],
+    )?;
+
+    tx.execute(
+        "INSERT INTO audit_logs (action, resource_type, resource_id, user, details)
+         VALUES ('merge_machines', 'machine', ?2, ?3, ?4)",
+        params![
+            source,
+            target,
+            user,
+            format!("{} → {} ({} linhas de histórico somadas às do destino)", source, target, combined),
+        ],
+    )?;
+
+    tx.commit()?;
+    Ok(MergeOutcome::Merged { combined_rows: combined })
+}
+
+/// Screen time da origem somado ao do destino no mesmo dia e app (tempo e blocos por hora)
+fn merge_screen_time(tx: &rusqlite::Transaction, source: &str, target: &str) -> Result<usize> {
+    let entries = tx
+        .prepare(&format!("SELECT {} FROM screen_time WHERE machine_id = ?1", SCREEN_TIME_COLUMNS))?
+        .query_map(params![source], map_screen_time_row)?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut combined = 0;
+    for entry in entries {
+        let existing = tx
+            .query_row(
+                &format!("SELECT {} FROM screen_time WHERE machine_id = ?1 AND app_name = ?2 AND date = ?3", SCREEN_TIME_COLUMNS),
+                params![target, entry.app_name, entry.date],
+                map_screen_time_row,
+            )
+            .optional()?;
+
+        let merged = match existing {
+            Some(existing) => {
+                combined += 1;
+                let hours = entry.hourly_seconds.len().max(existing.hourly_seconds.len());
+                let hour = |v: &[u64], h: usize| v.get(h).copied().unwrap_or(0);
+                ScreenTimeEntry {
+                    total_seconds: existing.total_seconds + entry.total_seconds,
+                    hourly_seconds: (0..hours)
+                        .map(|h| hour(&existing.hourly_seconds, h) + hour(&entry.hourly_seconds, h))
+                        .collect(),
+                    user_name: if existing.user_name.is_empty() { entry.user_name } else { existing.user_name },
+                    ..existing
+                }
+            }
+            None => ScreenTimeEntry { machine_id: target.to_string(), ..entry },
+        };
+
+        tx.execute(
+            "INSERT INTO screen_time (machine_id, app_name, total_seconds, date, category, hourly_seconds, user_name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(machine_id, app_name, date) DO UPDATE SET
+                total_seconds = excluded.total_seconds,
+                hourly_seconds = excluded.hourly_seconds,
+                user_name = excluded.user_name",
+            params![
+                merged.machine_id,
+                merged.app_name,
+                merged.total_seconds as i64,
+                merged.date,
+                merged.category,
+                serde_json::to_string(&merged.hourly_seconds).unwrap_or_else(|_| "[]".to_string()),
+                merged.user_name,
+            ],
+        )?;
+    }
+
+    tx.execute("DELETE FROM screen_time WHERE machine_id = ?1", params![source])?;
+    Ok(combined)
+}
+
+/// Webhook activo subscrito a um evento
+pub struct WebhookTarget {
+    pub id: String,
+    pub url: String,
+    pub secret: Option<String>,
+}
 
-    Ok(details)
+/// Webhooks activos com o evento na lista `events` (array JSON ou texto separado por vírgulas)
+pub fn list_webhooks_for_event(pool: &DbPool, event_type: &str) -> Result<Vec<WebhookTarget>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare("SELECT id, url, secret, events FROM webhooks WHERE enabled = 1")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                WebhookTarget {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    secret: row.get(2)?,
+                },
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(rows
+        .into_iter()
+        .filter(|(_, events)| {
+            serde_json::from_str::<Vec<String>>(events)
+                .unwrap_or_else(|_| events.split(',').map(|e| e.trim().to_string()).collect())
+                .iter()
+                .any(|e| e == event_type)
+        })
+        .map(|(target, _)| target)
+        .collect())
 }
 
-pub fn update_hardware_details(
+/// Regista um envio de webhook; `error` None = entregue
+pub fn log_webhook_delivery(
     pool: &DbPool,
-    machine_id: &str,
-    details: &HardwareDetails,
+    webhook_id: &str,
+    event_type: &str,
+    payload: &str,
+    error: Option<&str>,
 ) -> Result<()> {
     let conn = pool.lock().unwrap();
-
+    let now = Utc::now().to_rfc3339();
     conn.execute(
-        "INSERT OR REPLACE INTO hardware_details (
-            machine_id, serial_number, motherboard_manufacturer,
-            motherboard_model, bios_version, gpu_name, gpu_vram_mb,
-            total_ram_slots, used_ram_slots, ram_type, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-        params![
-            machine_id,
-            &details.serial_number,
-            &details.motherboard_manufacturer,
-            &details.motherboard_model,
-            &details.bios_version,
-            &details.gpu_name,
-            details.gpu_vram_mb,
-            details.total_ram_slots,
-            details.used_ram_slots,
-            &details.ram_type,
-            chrono::Utc::now().to_rfc3339(),
-        ],
+        "INSERT INTO webhook_logs (webhook_id, event_type, payload, response_body, triggered_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![webhook_id, event_type, payload, error, now],
+    )?;
+    conn.execute(
+        "UPDATE webhooks SET last_triggered = ?1 WHERE id = ?2",
+        params![now, webhook_id],
     )?;
-
     Ok(())
 }
 
@@ -994,7 +3684,7 @@ pub fn get_network_details(pool: &DbPool, machine_id: &str) -> Result<NetworkDet
         "SELECT 
             local_ip, subnet_mask, gateway, dns_primary, dns_secondary,
             dhcp_enabled, domain_name, is_domain_joined, mac_address,
-            adapter_name, connection_speed_mbps, wifi_ssid, wifi_security
+            adapter_name, connection_speed_mbps, wifi_ssid, wifi_security, dns_servers
          FROM network_details
          WHERE machine_id = ?1",
     )?;
@@ -1014,6 +3704,7 @@ pub fn get_network_details(pool: &DbPool, machine_id: &str) -> Result<NetworkDet
             connection_speed_mbps: row.get(10)?,
             wifi_ssid: row.get(11)?,
             wifi_security: row.get(12)?,
+            dns_servers: split_list(row.get::<_, Option<String>>(13)?),
         })
     })?;
 
@@ -1032,8 +3723,8 @@ pub fn update_network_details(
             machine_id, local_ip, subnet_mask, gateway, dns_primary,
             dns_secondary, dhcp_enabled, domain_name, is_domain_joined,
             mac_address, adapter_name, connection_speed_mbps,
-            wifi_ssid, wifi_security, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            wifi_ssid, wifi_security, updated_at, dns_servers
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
         params![
             machine_id,
             &details.local_ip,
@@ -1050,12 +3741,467 @@ pub fn update_network_details(
             &details.wifi_ssid,
             &details.wifi_security,
             chrono::Utc::now().to_rfc3339(),
+            details.dns_servers.join(","),
         ],
     )?;
 
     Ok(())
 }
 
+/// Listas guardadas separadas por vírgulas (gateways, servidores DNS)
+fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Substitui as interfaces e endereços da máquina pelos do último relatório
+pub fn update_network_adapters(pool: &DbPool, machine_id: &str, adapters: &[NetworkAdapterEntry]) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM network_adapters WHERE machine_id = ?1", params![machine_id])?;
+    tx.execute("DELETE FROM network_addresses WHERE machine_id = ?1", params![machine_id])?;
+
+    for a in adapters {
+        tx.execute(
+            "INSERT INTO network_adapters
+                (machine_id, name, description, kind, mac_address, gateways, dns_servers,
+                 dhcp_enabled, speed_mbps, is_up, is_wireless, is_primary, gateway_mac)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                machine_id,
+                a.name,
+                a.description,
+                a.kind,
+                a.mac_address,
+                a.gateways.join(","),
+                a.dns_servers.join(","),
+                a.dhcp_enabled,
+                a.speed_mbps,
+                a.is_up,
+                a.is_wireless,
+                a.is_primary,
+                a.gateway_mac,
+            ],
+        )?;
+        for addr in &a.addresses {
+            tx.execute(
+                "INSERT INTO network_addresses (machine_id, adapter_name, family, address, prefix_length)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![machine_id, a.name, addr.family, addr.address, addr.prefix_length],
+            )?;
+        }
+    }
+    tx.commit()
+}
+
+pub fn get_network_adapters(pool: &DbPool, machine_id: &str) -> Result<Vec<NetworkAdapterEntry>> {
+    let conn = pool.lock().unwrap();
+
+    let mut stmt = conn.prepare(
+        "SELECT adapter_name, family, address, prefix_length
+         FROM network_addresses WHERE machine_id = ?1 ORDER BY id",
+    )?;
+    let mut addresses: HashMap<String, Vec<NetworkAddressEntry>> = HashMap::new();
+    let rows = stmt.query_map(params![machine_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            NetworkAddressEntry {
+                family: row.get(1)?,
+                address: row.get(2)?,
+                prefix_length: row.get(3)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (adapter, address) = row?;
+        addresses.entry(adapter).or_default().push(address);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT name, description, kind, mac_address, gateways, dns_servers,
+                dhcp_enabled, speed_mbps, is_up, is_wireless, is_primary, gateway_mac
+         FROM network_adapters WHERE machine_id = ?1
+         ORDER BY is_primary DESC, is_up DESC, name",
+    )?;
+    let adapters = stmt
+        .query_map(params![machine_id], |row| {
+            let name: String = row.get(0)?;
+            Ok(NetworkAdapterEntry {
+                addresses: addresses.remove(&name).unwrap_or_default(),
+                name,
+                description: row.get(1)?,
+                kind: row.get(2)?,
+                mac_address: row.get(3)?,
+                gateways: split_list(row.get(4)?),
+                dns_servers: split_list(row.get(5)?),
+                dhcp_enabled: row.get(6)?,
+                speed_mbps: row.get(7)?,
+                is_up: row.get(8)?,
+                is_wireless: row.get(9)?,
+                is_primary: row.get(10)?,
+                gateway_mac: row.get(11)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(adapters)
+}
+
+pub fn update_network_routes(pool: &DbPool, machine_id: &str, routes: &[NetworkRoute]) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM network_routes WHERE machine_id = ?1", params![machine_id])?;
+
+    for r in routes {
+        tx.execute(
+            "INSERT INTO network_routes (machine_id, interface, destination, prefix_length, gateway, metric)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![machine_id, r.interface, r.destination, r.prefix_length, r.gateway, r.metric],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn get_network_routes(pool: &DbPool, machine_id: &str) -> Result<Vec<NetworkRoute>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT interface, destination, prefix_length, gateway, metric
+         FROM network_routes WHERE machine_id = ?1
+         ORDER BY prefix_length, metric, interface",
+    )?;
+
+    let routes = stmt
+        .query_map(params![machine_id], |row| {
+            Ok(NetworkRoute {
+                interface: row.get(0)?,
+                destination: row.get(1)?,
+                prefix_length: row.get(2)?,
+                gateway: row.get(3)?,
+                metric: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(routes)
+}
+
+// -------------------------------------------------
+// Mapa de rede e histórico de IP/MAC
+// -------------------------------------------------
+
+/// Endereços IPv4 de todas as máquinas; as que só enviaram o adaptador principal
+/// (agentes antigos) entram com os dados de network_details
+pub fn network_address_rows(pool: &DbPool) -> Result<Vec<NetworkAddressRow>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT a.machine_id, m.hostname, m.last_seen, a.adapter_name, ad.kind,
+                a.address, a.prefix_length, ad.mac_address, ad.gateways, ad.gateway_mac
+         FROM network_addresses a
+         JOIN network_adapters ad ON ad.machine_id = a.machine_id AND ad.name = a.adapter_name
+         JOIN machines m ON m.machine_id = a.machine_id
+         WHERE a.family = 'ipv4'",
+    )?;
+    let mut rows = stmt
+        .query_map([], |row| {
+            Ok(NetworkAddressRow {
+                machine_id: row.get(0)?,
+                hostname: row.get(1)?,
+                last_seen: row.get(2)?,
+                adapter_name: row.get(3)?,
+                kind: row.get(4)?,
+                ip_address: row.get(5)?,
+                prefix_length: row.get(6)?,
+                mac_address: row.get(7)?,
+                gateway: split_list(row.get(8)?).into_iter().next(),
+                gateway_mac: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT nd.machine_id, m.hostname, m.last_seen, COALESCE(nd.adapter_name, ''),
+                nd.local_ip, nd.subnet_mask, nd.mac_address, nd.gateway
+         FROM network_details nd
+         JOIN machines m ON m.machine_id = nd.machine_id
+         WHERE COALESCE(nd.local_ip, '') != ''
+           AND NOT EXISTS (SELECT 1 FROM network_adapters ad WHERE ad.machine_id = nd.machine_id)",
+    )?;
+    let legacy = stmt
+        .query_map([], |row| {
+            let mask: Option<String> = row.get(5)?;
+            Ok(NetworkAddressRow {
+                machine_id: row.get(0)?,
+                hostname: row.get(1)?,
+                last_seen: row.get(2)?,
+                adapter_name: row.get(3)?,
+                kind: "physical".to_string(),
+                ip_address: row.get(4)?,
+                prefix_length: mask.as_deref().and_then(crate::network_map::mask_prefix).unwrap_or(24),
+                mac_address: row.get(6)?,
+                gateway: row.get::<_, Option<String>>(7)?.filter(|g| !g.is_empty()),
+                gateway_mac: None,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    rows.extend(legacy);
+
+    Ok(rows)
+}
+
+const IP_ASSIGNMENT_COLUMNS: &str = "a.id, a.machine_id, m.hostname, a.adapter_name, a.ip_address, a.mac_address,
+     a.first_seen, a.last_seen, a.ended_at";
+
+fn ip_assignment_from_row(row: &rusqlite::Row) -> Result<IpAssignment> {
+    Ok(IpAssignment {
+        id: row.get(0)?,
+        machine_id: row.get(1)?,
+        hostname: row.get(2)?,
+        adapter_name: row.get(3)?,
+        ip_address: row.get(4)?,
+        mac_address: row.get(5)?,
+        first_seen: row.get(6)?,
+        last_seen: row.get(7)?,
+        ended_at: row.get(8)?,
+    })
+}
+
+pub fn open_ip_assignments(pool: &DbPool, machine_id: &str) -> Result<Vec<IpAssignment>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ip_assignments a
+         LEFT JOIN machines m ON m.machine_id = a.machine_id
+         WHERE a.machine_id = ?1 AND a.ended_at IS NULL",
+        IP_ASSIGNMENT_COLUMNS
+    ))?;
+
+    let assignments = stmt
+        .query_map(params![machine_id], ip_assignment_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(assignments)
+}
+
+/// Prolonga as atribuições que se mantêm, fecha as que desapareceram e abre as novas
+pub fn apply_ip_assignments(
+    pool: &DbPool,
+    kept: &[i64],
+    ended: &[i64],
+    added: &[IpAssignment],
+    now: &str,
+) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+
+    for id in kept {
+        tx.execute("UPDATE ip_assignments SET last_seen = ?1 WHERE id = ?2", params![now, id])?;
+    }
+    for id in ended {
+        tx.execute("UPDATE ip_assignments SET ended_at = ?1 WHERE id = ?2", params![now, id])?;
+    }
+    for a in added {
+        tx.execute(
+            "INSERT INTO ip_assignments (machine_id, adapter_name, ip_address, mac_address, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![a.machine_id, a.adapter_name, a.ip_address, a.mac_address, a.first_seen, a.last_seen],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Histórico de IP/MAC de uma máquina, mais recente primeiro
+pub fn get_ip_history(pool: &DbPool, machine_id: &str) -> Result<Vec<IpAssignment>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ip_assignments a
+         LEFT JOIN machines m ON m.machine_id = a.machine_id
+         WHERE a.machine_id = ?1
+         ORDER BY a.ended_at IS NOT NULL, a.first_seen DESC",
+        IP_ASSIGNMENT_COLUMNS
+    ))?;
+
+    let history = stmt
+        .query_map(params![machine_id], ip_assignment_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(history)
+}
+
+/// Que máquinas tiveram um IP ou MAC; com `at`, só as atribuições activas nesse instante
+pub fn find_ip_assignments(
+    pool: &DbPool,
+    ip_address: Option<&str>,
+    mac_address: Option<&str>,
+    at: Option<&str>,
+) -> Result<Vec<IpAssignment>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ip_assignments a
+         LEFT JOIN machines m ON m.machine_id = a.machine_id
+         WHERE (?1 IS NULL OR a.ip_address = ?1)
+           AND (?2 IS NULL OR a.mac_address = ?2)
+           AND (?3 IS NULL OR (a.first_seen <= ?3 AND COALESCE(a.ended_at, a.last_seen) >= ?3))
+         ORDER BY a.first_seen DESC
+         LIMIT 200",
+        IP_ASSIGNMENT_COLUMNS
+    ))?;
+
+    let assignments = stmt
+        .query_map(params![ip_address, mac_address, at], ip_assignment_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(assignments)
+}
+
+pub fn count_ip_changes_since(pool: &DbPool, machine_id: &str, adapter_name: &str, since: &str) -> Result<i64> {
+    let conn = pool.lock().unwrap();
+    conn.query_row(
+        "SELECT COUNT(*) FROM ip_change_events
+         WHERE machine_id = ?1 AND adapter_name = ?2 AND detected_at >= ?3",
+        params![machine_id, adapter_name, since],
+        |row| row.get(0),
+    )
+}
+
+pub fn insert_ip_change(pool: &DbPool, event: &IpChangeEvent) -> Result<i64> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO ip_change_events
+            (machine_id, adapter_name, old_ip, new_ip, mac_address, reason, unexpected, detected_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            event.machine_id,
+            event.adapter_name,
+            event.old_ip,
+            event.new_ip,
+            event.mac_address,
+            event.reason,
+            event.unexpected,
+            event.detected_at,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn list_ip_changes(
+    pool: &DbPool,
+    machine_id: Option<&str>,
+    unexpected_only: bool,
+    limit: i64,
+) -> Result<Vec<IpChangeEvent>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.machine_id, m.hostname, e.adapter_name, e.old_ip, e.new_ip,
+                e.mac_address, e.reason, e.unexpected, e.detected_at
+         FROM ip_change_events e
+         LEFT JOIN machines m ON m.machine_id = e.machine_id
+         WHERE (?1 IS NULL OR e.machine_id = ?1) AND (?2 = 0 OR e.unexpected = 1)
+         ORDER BY e.detected_at DESC
+         LIMIT ?3",
+    )?;
+
+    let events = stmt
+        .query_map(params![machine_id, unexpected_only, limit], |row| {
+            Ok(IpChangeEvent {
+                id: row.get(0)?,
+                machine_id: row.get(1)?,
+                hostname: row.get(2)?,
+                adapter_name: row.get(3)?,
+                old_ip: row.get(4)?,
+                new_ip: row.get(5)?,
+                mac_address: row.get(6)?,
+                reason: row.get(7)?,
+                unexpected: row.get(8)?,
+                detected_at: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(events)
+}
+
+pub fn machines_with_unexpected_ip_changes(pool: &DbPool, since: &str) -> Result<HashSet<String>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT machine_id FROM ip_change_events WHERE unexpected = 1 AND detected_at >= ?1",
+    )?;
+    let machines = stmt
+        .query_map(params![since], |row| row.get(0))?
+        .collect::<Result<HashSet<String>>>()?;
+    Ok(machines)
+}
+
+pub fn upsert_discovered_devices(pool: &DbPool, devices: &[DiscoveredDevice]) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO discovered_devices
+                (mac_address, ip_address, vendor, random_mac, interface, reported_by, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+             ON CONFLICT(mac_address) DO UPDATE SET
+                ip_address = excluded.ip_address,
+                vendor = excluded.vendor,
+                random_mac = excluded.random_mac,
+                interface = excluded.interface,
+                reported_by = excluded.reported_by,
+                last_seen = excluded.last_seen",
+        )?;
+        for d in devices {
+            stmt.execute(params![
+                d.mac_address,
+                d.ip_address,
+                d.vendor,
+                d.random_mac,
+                d.interface,
+                d.reported_by,
+                d.last_seen,
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Inventário de equipamentos descobertos, com a máquina gerida que tem o mesmo MAC (se houver)
+pub fn list_discovered_devices(pool: &DbPool, unmanaged_only: bool) -> Result<Vec<DiscoveredDevice>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT d.id, d.mac_address, d.ip_address, d.vendor, d.random_mac, d.interface,
+                d.reported_by, r.hostname, d.first_seen, d.last_seen, g.machine_id, g.hostname
+         FROM discovered_devices d
+         LEFT JOIN machines r ON r.machine_id = d.reported_by
+         LEFT JOIN machines g ON g.machine_id = (
+             SELECT ad.machine_id FROM network_adapters ad
+             WHERE UPPER(REPLACE(ad.mac_address, '-', ':')) = d.mac_address
+             UNION ALL
+             SELECT m.machine_id FROM machines m
+             WHERE UPPER(REPLACE(m.mac_address, '-', ':')) = d.mac_address
+             LIMIT 1
+         )
+         WHERE ?1 = 0 OR g.machine_id IS NULL
+         ORDER BY d.last_seen DESC",
+    )?;
+
+    let devices = stmt
+        .query_map(params![unmanaged_only], |row| {
+            Ok(DiscoveredDevice {
+                id: row.get(0)?,
+                mac_address: row.get(1)?,
+                ip_address: row.get(2)?,
+                vendor: row.get(3)?,
+                random_mac: row.get(4)?,
+                interface: row.get(5)?,
+                reported_by: row.get(6)?,
+                reported_by_hostname: row.get(7)?,
+                first_seen: row.get(8)?,
+                last_seen: row.get(9)?,
+                managed_machine_id: row.get(10)?,
+                managed_hostname: row.get(11)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(devices)
+}
+
 pub fn get_security_status(
     pool: &DbPool,
     machine_id: &str,
@@ -1126,22 +4272,49 @@ pub fn update_security_status(
 // ============================================================
 
 // Screen Time
-pub fn insert_screen_time(
-    pool: &DbPool,
-    machine_id: &str,
-    app_name: &str,
-    total_seconds: u64,
-    date: &str,
-) -> Result<()> {
-    let conn = pool.lock().unwrap();
+const SCREEN_TIME_COLUMNS: &str = "machine_id, app_name, total_seconds, date, category, hourly_seconds, user_name";
 
-    conn.execute(
-        "INSERT OR REPLACE INTO screen_time (machine_id, app_name, total_seconds, date)
-         VALUES (?1, ?2, ?3, ?4)",
-        params![machine_id, app_name, total_seconds as i64, date],
-    )?;
+fn map_screen_time_row(row: &rusqlite::Row) -> Result<ScreenTimeEntry> {
+    let hourly: String = row.get(5)?;
+    Ok(ScreenTimeEntry {
+        machine_id: row.get(0)?,
+        app_name: row.get(1)?,
+        total_seconds: row.get::<_, i64>(2)? as u64,
+        date: row.get(3)?,
+        category: row.get(4)?,
+        hourly_seconds: serde_json::from_str(&hourly).unwrap_or_default(),
+        user_name: row.get(6)?,
+    })
+}
 
-    Ok(())
+/// Guarda os totais do dia enviados pelo agente (acumulados: substituem os anteriores)
+pub fn insert_screen_time(pool: &DbPool, entries: &[ScreenTimeEntry]) -> Result<()> {
+    let mut conn = pool.lock().unwrap();
+    let tx = conn.transaction()?;
+
+    for entry in entries {
+        let hourly = serde_json::to_string(&entry.hourly_seconds).unwrap_or_else(|_| "[]".to_string());
+        tx.execute(
+            "INSERT INTO screen_time (machine_id, app_name, total_seconds, date, category, hourly_seconds, user_name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(machine_id, app_name, date) DO UPDATE SET
+                total_seconds = excluded.total_seconds,
+                category = excluded.category,
+                hourly_seconds = excluded.hourly_seconds,
+                user_name = CASE WHEN excluded.user_name = '' THEN screen_time.user_name ELSE excluded.user_name END",
+            params![
+                entry.machine_id,
+                entry.app_name,
+                entry.total_seconds as i64,
+                entry.date,
+                entry.category,
+                hourly,
+                entry.user_name,
+            ],
+        )?;
+    }
+
+    tx.commit()
 }
 
 pub fn get_screen_time(
@@ -1150,44 +4323,177 @@ pub fn get_screen_time(
     date: Option<&str>,
 ) -> Result<Vec<ScreenTimeEntry>> {
     let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM screen_time
+         WHERE machine_id = ?1 AND (?2 IS NULL OR date = ?2)
+         ORDER BY total_seconds DESC",
+        SCREEN_TIME_COLUMNS
+    ))?;
 
-    // Cria query e params baseado em date
-    let mut query = String::from(
-        "SELECT machine_id, app_name, total_seconds, date FROM screen_time WHERE machine_id = ?1",
-    );
+    let entries = stmt
+        .query_map(params![machine_id, date], map_screen_time_row)?
+        .collect::<Result<Vec<ScreenTimeEntry>>>()?;
 
-    if date.is_some() {
-        query.push_str(" AND date = ?2");
-    }
+    Ok(entries)
+}
 
-    query.push_str(" ORDER BY total_seconds DESC");
+pub fn list_screen_time_categories(pool: &DbPool) -> Result<Vec<ScreenTimeCategory>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT category, productivity FROM screen_time_categories
+         UNION
+         SELECT DISTINCT category, 'neutral' FROM screen_time
+         WHERE category NOT IN (SELECT category FROM screen_time_categories)
+         ORDER BY category",
+    )?;
+    let categories = stmt
+        .query_map([], |row| {
+            Ok(ScreenTimeCategory {
+                category: row.get(0)?,
+                productivity: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<ScreenTimeCategory>>>()?;
 
-    let mut stmt = conn.prepare(&query)?;
+    Ok(categories)
+}
 
-    // Executa query com params dinâmicos
-    let entries: Result<Vec<ScreenTimeEntry>> = if let Some(d) = date {
-        stmt.query_map(params![machine_id, d], |row| {
-            Ok(ScreenTimeEntry {
-                machine_id: row.get(0)?,
+pub fn set_screen_time_category(pool: &DbPool, category: &str, productivity: &str) -> Result<()> {
+    let conn = pool.lock().unwrap();
+    conn.execute(
+        "INSERT INTO screen_time_categories (category, productivity) VALUES (?1, ?2)
+         ON CONFLICT(category) DO UPDATE SET productivity = excluded.productivity",
+        params![category, productivity],
+    )?;
+    Ok(())
+}
+
+/// Expressão SQL do grupo de análise ("user" ou "department"); as linhas vêm de `screen_time s`
+/// com `machines m` em LEFT JOIN
+fn usage_group_expr(group_by: &str) -> &'static str {
+    match group_by {
+        "department" => "COALESCE(NULLIF(m.department, ''), 'Sem departamento')",
+        _ => "COALESCE(NULLIF(s.user_name, ''), 'Desconhecido')",
+    }
+}
+
+/// As `limit` apps com mais tempo em cada grupo, entre duas datas (inclusive)
+pub fn screen_time_top_apps(
+    pool: &DbPool,
+    group_by: &str,
+    from: &str,
+    to: &str,
+    limit: i64,
+) -> Result<Vec<UsageTopApp>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT group_name, app_name, category, total FROM (
+            SELECT {group} AS group_name, s.app_name, MAX(s.category) AS category,
+                   SUM(s.total_seconds) AS total,
+                   ROW_NUMBER() OVER (PARTITION BY {group} ORDER BY SUM(s.total_seconds) DESC) AS rank
+            FROM screen_time s
+            LEFT JOIN machines m ON m.machine_id = s.machine_id
+            WHERE s.date BETWEEN ?1 AND ?2
+            GROUP BY group_name, s.app_name
+         )
+         WHERE rank <= ?3
+         ORDER BY group_name, total DESC",
+        group = usage_group_expr(group_by)
+    ))?;
+
+    let apps = stmt
+        .query_map(params![from, to, limit], |row| {
+            Ok(UsageTopApp {
+                group_name: row.get(0)?,
                 app_name: row.get(1)?,
-                total_seconds: row.get::<_, i64>(2)? as u64,
-                date: row.get(3)?,
+                category: row.get(2)?,
+                total_seconds: row.get::<_, i64>(3)? as u64,
             })
         })?
-        .collect()
-    } else {
-        stmt.query_map(params![machine_id], |row| {
-            Ok(ScreenTimeEntry {
-                machine_id: row.get(0)?,
-                app_name: row.get(1)?,
-                total_seconds: row.get::<_, i64>(2)? as u64,
-                date: row.get(3)?,
+        .collect::<Result<Vec<UsageTopApp>>>()?;
+
+    Ok(apps)
+}
+
+/// Totais por categoria e grupo, com a classificação produtivo/improdutivo
+pub fn screen_time_category_totals(
+    pool: &DbPool,
+    group_by: &str,
+    from: &str,
+    to: &str,
+) -> Result<Vec<UsageCategoryTotal>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {group} AS group_name, s.category, COALESCE(c.productivity, 'neutral'),
+                SUM(s.total_seconds) AS total
+         FROM screen_time s
+         LEFT JOIN machines m ON m.machine_id = s.machine_id
+         LEFT JOIN screen_time_categories c ON c.category = s.category
+         WHERE s.date BETWEEN ?1 AND ?2
+         GROUP BY group_name, s.category
+         ORDER BY group_name, total DESC",
+        group = usage_group_expr(group_by)
+    ))?;
+
+    let totals = stmt
+        .query_map(params![from, to], |row| {
+            Ok(UsageCategoryTotal {
+                group_name: row.get(0)?,
+                category: row.get(1)?,
+                productivity: row.get(2)?,
+                total_seconds: row.get::<_, i64>(3)? as u64,
             })
         })?
-        .collect()
-    };
+        .collect::<Result<Vec<UsageCategoryTotal>>>()?;
+
+    Ok(totals)
+}
+
+/// Totais semanais (a começar à segunda-feira) desde `from`, opcionalmente de uma máquina
+pub fn screen_time_weekly_trend(
+    pool: &DbPool,
+    machine_id: Option<&str>,
+    from: &str,
+) -> Result<Vec<UsageWeeklyTrend>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT date(s.date, '-6 days', 'weekday 1') AS week_start,
+                SUM(CASE WHEN c.productivity = 'productive' THEN s.total_seconds ELSE 0 END),
+                SUM(CASE WHEN c.productivity = 'unproductive' THEN s.total_seconds ELSE 0 END),
+                SUM(CASE WHEN COALESCE(c.productivity, 'neutral') NOT IN ('productive', 'unproductive')
+                         THEN s.total_seconds ELSE 0 END),
+                SUM(s.total_seconds)
+         FROM screen_time s
+         LEFT JOIN screen_time_categories c ON c.category = s.category
+         WHERE s.date >= ?1 AND (?2 IS NULL OR s.machine_id = ?2)
+         GROUP BY week_start
+         ORDER BY week_start",
+    )?;
+
+    let weeks = stmt
+        .query_map(params![from, machine_id], |row| {
+            Ok(UsageWeeklyTrend {
+                week_start: row.get(0)?,
+                productive_seconds: row.get::<_, i64>(1)? as u64,
+                unproductive_seconds: row.get::<_, i64>(2)? as u64,
+                neutral_seconds: row.get::<_, i64>(3)? as u64,
+                total_seconds: row.get::<_, i64>(4)? as u64,
+            })
+        })?
+        .collect::<Result<Vec<UsageWeeklyTrend>>>()?;
+
+    Ok(weeks)
+}
 
-    entries
+/// Screen time de todas as máquinas num dia (para comparar com quotas)
+pub fn get_screen_time_for_date(pool: &DbPool, date: &str) -> Result<Vec<ScreenTimeEntry>> {
+    let conn = pool.lock().unwrap();
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM screen_time WHERE date = ?1", SCREEN_TIME_COLUMNS))?;
+    let entries = stmt
+        .query_map(params![date], map_screen_time_row)?
+        .collect::<Result<Vec<ScreenTimeEntry>>>()?;
+
+    Ok(entries)
 }
 
 // Actualiza máquina com IP
@@ -1210,21 +4516,18 @@ pub fn create_ip_policy(
     action: &str,
     reason: &str,
 ) -> Result<String> {
-    let conn = pool.lock().unwrap();
-    let id = uuid::Uuid::new_v4().to_string();
-
-    conn.execute(
-        "INSERT INTO policies (id, machine_id, policy_type, target, action, reason, created_by, created_at, enabled)
-         VALUES (?1, ?2, 'ip', ?3, ?4, ?5, 'admin', ?6, 1)",
-        params![
-            &id,
-            machine_id,
-            ip_address,
-            action,
-            reason,
-            chrono::Utc::now().to_rfc3339()
-        ],
-    )?;
-
-    Ok(id)
+    // Passa pelo fluxo normal para ficar com nome, revisão e config aplicáveis pelo agente
+    create_policy(
+        pool,
+        machine_id,
+        &format!("Regra de IP: {}", ip_address),
+        "Criado via Dashboard",
+        "ip",
+        1,
+        ip_address,
+        action,
+        "{}",
+        reason,
+        "admin",
+    )
 }
@@ -0,0 +1,93 @@
+// ============================================================
+// discovery.rs — Equipamentos descobertos passivamente
+// ============================================================
+//
+// Os agentes com `report_neighbors` enviam a tabela ARP/NDP. Cada
+// MAC vira um equipamento no inventário, com o fabricante (OUI) e
+// a primeira/última vez que foi visto. Quem não corresponde a uma
+// máquina com agente aparece como não gerido (impressoras, IoT,
+// portáteis por registar ou equipamentos estranhos à rede).
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use crate::database::{self, DbPool};
+use crate::models::DiscoveredDevice;
+use crate::network_map::normalize_mac;
+
+const OUI_TABLE: &str = include_str!("../data/oui.tsv");
+
+/// Vizinho reportado por um agente
+pub struct ReportedNeighbor {
+    pub ip: String,
+    pub mac_address: String,
+    pub interface: String,
+}
+
+fn oui_table() -> &'static HashMap<&'static str, &'static str> {
+    static TABLE: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        OUI_TABLE
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('\t'))
+            .collect()
+    })
+}
+
+/// Fabricante pelos três primeiros octetos de um MAC normalizado ("AA:BB:CC:...")
+pub fn vendor_for(mac: &str) -> Option<String> {
+    let prefix = mac.get(0..8)?.replace(':', "");
+    oui_table().get(prefix.as_str()).map(|v| v.to_string())
+}
+
+/// Bit "localmente administrado" do primeiro octeto: MACs aleatórios e de VMs
+pub fn is_random_mac(mac: &str) -> bool {
+    mac.get(0..2)
+        .and_then(|o| u8::from_str_radix(o, 16).ok())
+        .is_some_and(|o| o & 0x02 != 0)
+}
+
+/// Junta a tabela de vizinhos de um agente ao inventário; um equipamento por MAC,
+/// com o IPv4 quando o agente vê os dois
+pub fn merge_neighbors(
+    pool: &DbPool,
+    reporter: &str,
+    neighbors: &[ReportedNeighbor],
+    now: &str,
+) -> rusqlite::Result<usize> {
+    let mut by_mac: BTreeMap<String, &ReportedNeighbor> = BTreeMap::new();
+    for neighbor in neighbors {
+        let Some(mac) = normalize_mac(&neighbor.mac_address) else {
+            continue;
+        };
+        let has_ipv4 = by_mac
+            .get(&mac)
+            .is_some_and(|n| matches!(n.ip.parse::<IpAddr>(), Ok(IpAddr::V4(_))));
+        if !has_ipv4 {
+            by_mac.insert(mac, neighbor);
+        }
+    }
+
+    let devices: Vec<DiscoveredDevice> = by_mac
+        .into_iter()
+        .map(|(mac, neighbor)| DiscoveredDevice {
+            id: 0,
+            vendor: vendor_for(&mac),
+            random_mac: is_random_mac(&mac),
+            mac_address: mac,
+            ip_address: neighbor.ip.clone(),
+            interface: neighbor.interface.clone(),
+            reported_by: reporter.to_string(),
+            reported_by_hostname: None,
+            first_seen: now.to_string(),
+            last_seen: now.to_string(),
+            managed_machine_id: None,
+            managed_hostname: None,
+        })
+        .collect();
+
+    database::upsert_discovered_devices(pool, &devices)?;
+    Ok(devices.len())
+}
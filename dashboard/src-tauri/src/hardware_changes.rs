@@ -0,0 +1,216 @@
+// ============================================================
+// hardware_changes.rs — Alterações de hardware e alertas de adulteração
+// ============================================================
+//
+// Antes de o relatório substituir o estado guardado, compara a
+// impressão digital do hardware (número de série, motherboard,
+// BIOS, discos, módulos de memória e placas gráficas) com a que
+// já estava na base de dados. Cada diferença fica no histórico e
+// gera um evento `hardware.changed` com os valores antes e depois.
+//
+// As regras (hardware_change_rules) dizem que componentes tornam a
+// mudança suspeita: por omissão, outro número de série ou outra
+// motherboard com o mesmo machine_id.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::Error::QueryReturnedNoRows;
+
+use crate::database::{self, DbPool};
+use crate::hardware_inventory::{self, ReportedHardware};
+use crate::models::*;
+
+/// Componentes comparados entre relatórios (e com regra em hardware_change_rules)
+pub const COMPONENTS: [&str; 6] = ["serial_number", "motherboard", "bios", "disk", "memory", "gpu"];
+
+/// Valores que os agentes enviam quando não conseguiram ler o campo
+const PLACEHOLDERS: [&str; 3] = ["", "Unknown", "Not Available"];
+
+/// Impressão digital do hardware. `None` = desconhecido (não entra na comparação);
+/// os componentes são chave estável → descrição
+#[derive(Debug, Default)]
+pub struct HardwareFingerprint {
+    pub serial_number: Option<String>,
+    pub motherboard: Option<String>,
+    pub bios: Option<String>,
+    pub disks: Option<BTreeMap<String, String>>,
+    pub memory: Option<BTreeMap<String, String>>,
+    pub gpus: Option<BTreeMap<String, String>>,
+}
+
+/// (componente, tipo, antes, depois)
+type Difference = (&'static str, &'static str, Option<String>, Option<String>);
+
+fn known(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!PLACEHOLDERS.contains(&value)).then(|| value.to_string())
+}
+
+fn motherboard(details: &HardwareDetails) -> Option<String> {
+    let parts: Vec<String> = [&details.motherboard_manufacturer, &details.motherboard_model]
+        .into_iter()
+        .filter_map(|v| known(v))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+fn components<T>(items: &[T], key: fn(&T) -> String, describe: fn(&T) -> String) -> BTreeMap<String, String> {
+    hardware_inventory::unique_keys(items.iter().map(key).collect())
+        .into_iter()
+        .zip(items.iter().map(describe))
+        .collect()
+}
+
+fn describe_disk(d: &PhysicalDisk) -> String {
+    let mut text = format!("{} ({:.0} GB)", d.model, d.size_gb);
+    if let Some(serial) = &d.serial_number {
+        text.push_str(&format!(" S/N {}", serial));
+    }
+    text
+}
+
+fn describe_memory(m: &MemoryModule) -> String {
+    let mut text = format!("{}: {} MB", m.slot, m.size_mb);
+    for part in [&m.memory_type, &m.part_number].into_iter().flatten() {
+        text.push_str(&format!(" {}", part));
+    }
+    if let Some(serial) = &m.serial_number {
+        text.push_str(&format!(" S/N {}", serial));
+    }
+    text
+}
+
+fn describe_gpu(g: &GpuInfo) -> String {
+    g.name.clone()
+}
+
+fn fingerprint(
+    details: &HardwareDetails,
+    disks: Option<&[PhysicalDisk]>,
+    memory: Option<&[MemoryModule]>,
+    gpus: Option<&[GpuInfo]>,
+) -> HardwareFingerprint {
+    HardwareFingerprint {
+        serial_number: known(&details.serial_number),
+        motherboard: motherboard(details),
+        bios: known(&details.bios_version),
+        disks: disks.map(|d| components(d, hardware_inventory::disk_key, describe_disk)),
+        memory: memory.map(|m| components(m, hardware_inventory::memory_key, describe_memory)),
+        gpus: gpus.map(|g| components(g, hardware_inventory::gpu_key, describe_gpu)),
+    }
+}
+
+/// Impressão digital do relatório que acabou de chegar
+pub fn reported_fingerprint(details: &HardwareDetails, reported: &ReportedHardware) -> HardwareFingerprint {
+    fingerprint(
+        details,
+        reported.physical_disks.as_deref(),
+        reported.memory_modules.as_deref(),
+        reported.gpus.as_deref(),
+    )
+}
+
+/// Componentes ainda instalados; `None` se o tipo nunca foi reportado (nem há linhas removidas)
+fn installed<T>(items: Vec<T>, is_installed: impl Fn(&T) -> bool) -> Option<Vec<T>> {
+    (!items.is_empty()).then(|| items.into_iter().filter(|i| is_installed(i)).collect())
+}
+
+/// Impressão digital guardada; `None` se a máquina ainda não tinha hardware registado
+pub fn stored_fingerprint(pool: &DbPool, machine_id: &str) -> rusqlite::Result<Option<HardwareFingerprint>> {
+    let details = match database::get_hardware_details(pool, machine_id) {
+        Ok(details) => details,
+        Err(QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let inventory = hardware_inventory::load(pool, machine_id, true)?;
+    let disks = installed(inventory.physical_disks, |d| d.removed_at.is_none());
+    let memory = installed(inventory.memory_modules, |m| m.removed_at.is_none());
+    let gpus = installed(inventory.gpus, |g| g.removed_at.is_none());
+
+    Ok(Some(fingerprint(&details, disks.as_deref(), memory.as_deref(), gpus.as_deref())))
+}
+
+fn diff_value(component: &'static str, before: &Option<String>, after: &Option<String>, out: &mut Vec<Difference>) {
+    if let (Some(b), Some(a)) = (before, after) {
+        if b != a {
+            out.push((component, "changed", Some(b.clone()), Some(a.clone())));
+        }
+    }
+}
+
+/// Componentes que saíram e entraram no mesmo relatório contam como substituições
+fn diff_components(
+    component: &'static str,
+    before: &Option<BTreeMap<String, String>>,
+    after: &Option<BTreeMap<String, String>>,
+    out: &mut Vec<Difference>,
+) {
+    let (Some(before), Some(after)) = (before, after) else {
+        return;
+    };
+    let mut removed = before.iter().filter(|(k, _)| !after.contains_key(*k)).map(|(_, v)| v.clone());
+    let mut added = after.iter().filter(|(k, _)| !before.contains_key(*k)).map(|(_, v)| v.clone());
+
+    loop {
+        match (removed.next(), added.next()) {
+            (Some(old), Some(new)) => out.push((component, "changed", Some(old), Some(new))),
+            (Some(old), None) => out.push((component, "removed", Some(old), None)),
+            (None, Some(new)) => out.push((component, "added", None, Some(new))),
+            (None, None) => break,
+        }
+    }
+}
+
+/// Diferenças entre duas impressões digitais; o que for desconhecido num dos lados fica de fora
+pub fn diff(before: &HardwareFingerprint, after: &HardwareFingerprint) -> Vec<Difference> {
+    let mut out = Vec::new();
+    diff_value("serial_number", &before.serial_number, &after.serial_number, &mut out);
+    diff_value("motherboard", &before.motherboard, &after.motherboard, &mut out);
+    diff_value("bios", &before.bios, &after.bios, &mut out);
+    diff_components("disk", &before.disks, &after.disks, &mut out);
+    diff_components("memory", &before.memory, &after.memory, &mut out);
+    diff_components("gpu", &before.gpus, &after.gpus, &mut out);
+    out
+}
+
+/// Compara com o estado guardado e regista as mudanças. Tem de correr antes de o
+/// relatório actualizar hardware_details e o inventário; o primeiro relatório não gera nada
+pub fn detect(
+    pool: &DbPool,
+    machine_id: &str,
+    hostname: &str,
+    reported: &HardwareFingerprint,
+    now: &str,
+) -> rusqlite::Result<Vec<HardwareChange>> {
+    let Some(stored) = stored_fingerprint(pool, machine_id)? else {
+        return Ok(Vec::new());
+    };
+    let differences = diff(&stored, reported);
+    if differences.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rules: HashMap<String, bool> = database::list_hardware_change_rules(pool)?
+        .into_iter()
+        .map(|r| (r.component, r.suspicious))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (component, change_type, old_value, new_value) in differences {
+        let mut change = HardwareChange {
+            id: 0,
+            machine_id: machine_id.to_string(),
+            hostname: Some(hostname.to_string()),
+            component: component.to_string(),
+            change_type: change_type.to_string(),
+            old_value,
+            new_value,
+            suspicious: rules.get(component).copied().unwrap_or(false),
+            detected_at: now.to_string(),
+        };
+        change.id = database::insert_hardware_change(pool, &change)?;
+        changes.push(change);
+    }
+    Ok(changes)
+}
@@ -0,0 +1,225 @@
+// ============================================================
+// hardware_inventory.rs — Inventário de hardware por componente
+// ============================================================
+//
+// Cada relatório traz os módulos de memória, placas gráficas,
+// monitores, baterias e discos físicos. Cada componente tem uma
+// chave estável (número de série quando existe) para se saber
+// quando foi instalado e quando deixou de aparecer; a capacidade
+// das baterias e o estado SMART dos discos ficam com histórico.
+
+use std::collections::HashMap;
+
+use rusqlite::types::Value;
+
+use crate::database::{self, ComponentRow, DbPool};
+use crate::models::*;
+
+/// Inventário reportado pelo agente; `None` quando o agente não o conseguiu ler
+/// (o inventário guardado fica como estava)
+pub struct ReportedHardware {
+    pub memory_modules: Option<Vec<MemoryModule>>,
+    pub gpus: Option<Vec<GpuInfo>>,
+    pub monitors: Option<Vec<MonitorInfo>>,
+    pub batteries: Option<Vec<BatteryInfo>>,
+    pub physical_disks: Option<Vec<PhysicalDisk>>,
+}
+
+/// Componentes iguais sem número de série (dois monitores do mesmo modelo) ficam "#2", "#3", ...
+pub fn unique_keys(keys: Vec<String>) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    keys.into_iter()
+        .map(|key| {
+            let count = seen.entry(key.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                key
+            } else {
+                format!("{}#{}", key, count)
+            }
+        })
+        .collect()
+}
+
+fn serial_or(serial: &Option<String>, fallback: impl FnOnce() -> String) -> String {
+    match serial {
+        Some(serial) => format!("sn:{}", serial),
+        None => fallback(),
+    }
+}
+
+pub fn memory_key(m: &MemoryModule) -> String {
+    serial_or(&m.serial_number, || {
+        format!("slot:{}|{}|{}", m.slot, m.part_number.as_deref().unwrap_or(""), m.size_mb)
+    })
+}
+
+pub fn gpu_key(g: &GpuInfo) -> String {
+    format!("name:{}", g.name)
+}
+
+pub fn monitor_key(m: &MonitorInfo) -> String {
+    let manufacturer = m.manufacturer.as_deref().unwrap_or("");
+    match &m.serial_number {
+        Some(serial) => format!("sn:{}|{}", manufacturer, serial),
+        None => format!("model:{}|{}", manufacturer, m.model.as_deref().unwrap_or("")),
+    }
+}
+
+pub fn battery_key(b: &BatteryInfo) -> String {
+    serial_or(&b.serial_number, || format!("name:{}", b.name.as_deref().unwrap_or("")))
+}
+
+pub fn disk_key(d: &PhysicalDisk) -> String {
+    serial_or(&d.serial_number, || format!("model:{}|{:.0}", d.model, d.size_gb))
+}
+
+fn component_rows<T>(items: &[T], key: fn(&T) -> String, values: fn(&T) -> Vec<Value>) -> Vec<ComponentRow> {
+    unique_keys(items.iter().map(key).collect())
+        .into_iter()
+        .zip(items)
+        .map(|(key, item)| ComponentRow { key, values: values(item) })
+        .collect()
+}
+
+/// Guarda o inventário reportado e o histórico de baterias e discos
+pub fn record(pool: &DbPool, machine_id: &str, reported: &ReportedHardware, now: &str) -> rusqlite::Result<()> {
+    if let Some(modules) = &reported.memory_modules {
+        let rows = component_rows(modules, memory_key, |m| {
+            vec![
+                m.slot.clone().into(),
+                m.size_mb.into(),
+                m.speed_mts.into(),
+                m.memory_type.clone().into(),
+                m.manufacturer.clone().into(),
+                m.part_number.clone().into(),
+                m.serial_number.clone().into(),
+            ]
+        });
+        database::sync_components(
+            pool,
+            "memory_modules",
+            &["slot", "size_mb", "speed_mts", "memory_type", "manufacturer", "part_number", "serial_number"],
+            machine_id,
+            &rows,
+            now,
+        )?;
+    }
+
+    if let Some(gpus) = &reported.gpus {
+        let rows = component_rows(gpus, gpu_key, |g| {
+            vec![
+                g.name.clone().into(),
+                g.vendor.clone().into(),
+                g.vram_mb.into(),
+                g.driver_version.clone().into(),
+            ]
+        });
+        database::sync_components(pool, "gpus", &["name", "vendor", "vram_mb", "driver_version"], machine_id, &rows, now)?;
+    }
+
+    if let Some(monitors) = &reported.monitors {
+        let rows = component_rows(monitors, monitor_key, |m| {
+            vec![
+                m.manufacturer.clone().into(),
+                m.model.clone().into(),
+                m.serial_number.clone().into(),
+                m.width.into(),
+                m.height.into(),
+            ]
+        });
+        database::sync_components(
+            pool,
+            "monitors",
+            &["manufacturer", "model", "serial_number", "width", "height"],
+            machine_id,
+            &rows,
+            now,
+        )?;
+    }
+
+    if let Some(batteries) = &reported.batteries {
+        let rows = component_rows(batteries, battery_key, |b| {
+            vec![
+                b.name.clone().into(),
+                b.manufacturer.clone().into(),
+                b.serial_number.clone().into(),
+                b.chemistry.clone().into(),
+                b.design_capacity_mwh.into(),
+                b.full_charge_capacity_mwh.into(),
+                b.cycle_count.into(),
+            ]
+        });
+        let ids = database::sync_components(
+            pool,
+            "batteries",
+            &[
+                "name",
+                "manufacturer",
+                "serial_number",
+                "chemistry",
+                "design_capacity_mwh",
+                "full_charge_capacity_mwh",
+                "cycle_count",
+            ],
+            machine_id,
+            &rows,
+            now,
+        )?;
+        for (row, battery) in rows.iter().zip(batteries) {
+            database::record_battery_reading(
+                pool,
+                &BatteryReading {
+                    battery_id: ids[&row.key],
+                    design_capacity_mwh: battery.design_capacity_mwh,
+                    full_charge_capacity_mwh: battery.full_charge_capacity_mwh,
+                    cycle_count: battery.cycle_count,
+                    recorded_at: now.to_string(),
+                },
+            )?;
+        }
+    }
+
+    if let Some(disks) = &reported.physical_disks {
+        let rows = component_rows(disks, disk_key, |d| {
+            vec![
+                d.model.clone().into(),
+                d.serial_number.clone().into(),
+                d.media_type.clone().into(),
+                d.bus_type.clone().into(),
+                d.size_gb.into(),
+                d.health.clone().into(),
+            ]
+        });
+        let ids = database::sync_components(
+            pool,
+            "physical_disks",
+            &["model", "serial_number", "media_type", "bus_type", "size_gb", "health"],
+            machine_id,
+            &rows,
+            now,
+        )?;
+        for (row, disk) in rows.iter().zip(disks) {
+            database::record_disk_health(
+                pool,
+                &DiskHealthChange {
+                    disk_id: ids[&row.key],
+                    health: disk.health.clone(),
+                    recorded_at: now.to_string(),
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn load(pool: &DbPool, machine_id: &str, include_removed: bool) -> rusqlite::Result<HardwareInventory> {
+    Ok(HardwareInventory {
+        memory_modules: database::get_memory_modules(pool, machine_id, include_removed)?,
+        gpus: database::get_gpus(pool, machine_id, include_removed)?,
+        monitors: database::get_monitors(pool, machine_id, include_removed)?,
+        batteries: database::get_batteries(pool, machine_id, include_removed)?,
+        physical_disks: database::get_physical_disks(pool, machine_id, include_removed)?,
+    })
+}
@@ -4,6 +4,9 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 
+use crate::database::{self, DbPool};
+use crate::models::{HardwareChange, IdentityConflict};
+
 type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -88,3 +91,58 @@ pub fn create_vulnerability_found_event(
         }),
     }
 }
+
+pub fn create_hardware_changed_event(change: &HardwareChange) -> WebhookEvent {
+    WebhookEvent {
+        event_type: "hardware.changed".to_string(),
+        timestamp: change.detected_at.clone(),
+        data: serde_json::json!({
+            "machine_id": change.machine_id,
+            "hostname": change.hostname,
+            "component": change.component,
+            "change_type": change.change_type,
+            "before": change.old_value,
+            "after": change.new_value,
+            "suspicious": change.suspicious,
+        }),
+    }
+}
+
+pub fn create_identity_conflict_event(conflict: &IdentityConflict) -> WebhookEvent {
+    WebhookEvent {
+        event_type: "machine.identity_conflict".to_string(),
+        timestamp: conflict.detected_at.clone(),
+        data: serde_json::json!({
+            "kind": conflict.kind,
+            "machine_id": conflict.machine_id,
+            "hostname": conflict.hostname,
+            "other_machine_id": conflict.other_machine_id,
+            "other_hostname": conflict.other_hostname,
+        }),
+    }
+}
+
+/// Envia o evento aos webhooks activos que o subscrevem; cada envio fica em webhook_logs
+pub fn dispatch(pool: &DbPool, event: WebhookEvent) {
+    let targets = match database::list_webhooks_for_event(pool, &event.event_type) {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("[Webhooks] Erro ao ler webhooks de '{}': {}", event.event_type, e);
+            return;
+        }
+    };
+
+    for target in targets {
+        let pool = pool.clone();
+        let event = event.clone();
+        tokio::spawn(async move {
+            let payload = serde_json::to_string(&event).unwrap_or_default();
+            let error = trigger_webhook(&target.url, event.clone(), target.secret.as_deref())
+                .await
+                .err()
+                .map(|e| e.to_string());
+            database::log_webhook_delivery(&pool, &target.id, &event.event_type, &payload, error.as_deref())
+                .ok();
+        });
+    }
+}
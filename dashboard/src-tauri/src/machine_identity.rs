@@ -0,0 +1,145 @@
+// ============================================================
+// machine_identity.rs — Clones, duplicados e união de registos
+// ============================================================
+//
+// O agente envia, com o machine_id, as âncoras de hardware (UUID do
+// SMBIOS, números de série da BIOS e da motherboard, MAC principal)
+// e a impressão digital delas. Cada par (machine_id, impressão) fica
+// em machine_fingerprints, o que permite encontrar:
+//
+// - clones: o mesmo machine_id com hardware diferente a reportar em
+//   paralelo (VM clonada com o ID no Registry ou em /var/lib);
+// - duplicados: o mesmo hardware com outro machine_id (SO reinstalado
+//   ou agente reinstalado sem o ID antigo).
+//
+// Uma troca de motherboard também muda a impressão, mas a antiga deixa
+// de reportar: só conta como clone se as duas se alternarem, ou se a
+// nova chegar com outro hostname enquanto a antiga ainda está activa.
+//
+// Unir dois registos passa o histórico para o que fica e guarda o ID do
+// outro como alias, para onde os relatórios e comandos são encaminhados.
+
+use chrono::{DateTime, Duration};
+
+use crate::database::{self, DbPool};
+use crate::models::{IdentityConflict, MachineFingerprint};
+
+/// Uma impressão digital que não reporta há mais do que isto é hardware antigo, não um clone
+const CLONE_WINDOW_HOURS: i64 = 24;
+
+/// Reportou dentro da janela de clones antes de `now`
+fn recent(last_seen: &str, now: &str) -> bool {
+    match (DateTime::parse_from_rfc3339(last_seen), DateTime::parse_from_rfc3339(now)) {
+        (Ok(last_seen), Ok(now)) => now - last_seen <= Duration::hours(CLONE_WINDOW_HOURS),
+        _ => false,
+    }
+}
+
+/// Duas impressões são de máquinas diferentes se uma âncora de firmware conhecida dos dois
+/// lados difere. O MAC só decide quando não há nenhuma âncora de firmware em comum
+/// (trocar de placa de rede não faz de uma máquina outra)
+pub fn anchors_conflict(a: &MachineFingerprint, b: &MachineFingerprint) -> bool {
+    let firmware = [
+        (&a.system_uuid, &b.system_uuid),
+        (&a.bios_serial, &b.bios_serial),
+        (&a.board_serial, &b.board_serial),
+    ];
+    let mut compared = false;
+    for (x, y) in firmware {
+        if let (Some(x), Some(y)) = (x, y) {
+            if !x.eq_ignore_ascii_case(y) {
+                return true;
+            }
+            compared = true;
+        }
+    }
+    if compared {
+        return false;
+    }
+    match (&a.primary_mac, &b.primary_mac) {
+        (Some(x), Some(y)) => !x.eq_ignore_ascii_case(y),
+        _ => false,
+    }
+}
+
+/// machine_id efectivo: o do registo a que este foi unido, se for o caso
+pub fn resolve(pool: &DbPool, machine_id: &str) -> String {
+    match database::resolve_machine_alias(pool, machine_id) {
+        Ok(Some(target)) => target,
+        Ok(None) => machine_id.to_string(),
+        Err(e) => {
+            eprintln!("[Identity] Erro ao resolver alias de {}: {}", machine_id, e);
+            machine_id.to_string()
+        }
+    }
+}
+
+fn conflict(kind: &str, reported: &MachineFingerprint, other: &MachineFingerprint, now: &str) -> IdentityConflict {
+    IdentityConflict {
+        id: 0,
+        kind: kind.to_string(),
+        machine_id: reported.machine_id.clone(),
+        hostname: reported.hostname.clone(),
+        fingerprint: reported.fingerprint.clone(),
+        other_machine_id: other.machine_id.clone(),
+        other_hostname: other.hostname.clone(),
+        other_fingerprint: other.fingerprint.clone(),
+        detected_at: now.to_string(),
+        last_seen: now.to_string(),
+        resolved_at: None,
+        resolution: None,
+    }
+}
+
+/// Compara a impressão reportada com as conhecidas e regista-a. Devolve só os
+/// conflitos novos (os já conhecidos apenas actualizam last_seen)
+pub fn record(pool: &DbPool, reported: &MachineFingerprint, now: &str) -> rusqlite::Result<Vec<IdentityConflict>> {
+    let known = database::list_machine_fingerprints(pool, &reported.machine_id)?;
+    let previous = known.iter().find(|f| f.fingerprint == reported.fingerprint);
+
+    let mut found = Vec::new();
+    for other in known.iter().filter(|f| f.fingerprint != reported.fingerprint) {
+        if !recent(&other.last_seen, now) || !anchors_conflict(reported, other) {
+            continue;
+        }
+        let is_clone = match previous {
+            // As duas continuam a reportar alternadamente
+            Some(previous) => other.last_seen > previous.last_seen,
+            // Hardware novo com outro nome enquanto o anterior está activo
+            None => other.hostname.is_some() && other.hostname != reported.hostname,
+        };
+        if is_clone {
+            found.push(conflict("clone", reported, other, now));
+        }
+    }
+
+    for other in database::find_fingerprint_elsewhere(pool, &reported.fingerprint, &reported.machine_id)? {
+        found.push(conflict("duplicate", reported, &other, now));
+    }
+
+    database::record_machine_fingerprint(pool, reported)?;
+
+    let mut new_conflicts = Vec::new();
+    for mut c in found {
+        if let Some(id) = database::upsert_identity_conflict(pool, &c)? {
+            c.id = id;
+            new_conflicts.push(c);
+        }
+    }
+    Ok(new_conflicts)
+}
+
+/// Une `source` a `target` (que fica com o histórico dos dois) e regista na auditoria.
+/// Devolve quantas linhas de histórico da origem foram somadas a linhas do destino
+pub fn merge(pool: &DbPool, source: &str, target: &str, user: &str) -> Result<usize, String> {
+    if source == target {
+        return Err("Não é possível unir uma máquina a si própria".to_string());
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    match database::merge_machines(pool, source, target, user, &now).map_err(|e| e.to_string())? {
+        database::MergeOutcome::Merged { combined_rows } => Ok(combined_rows),
+        database::MergeOutcome::Refused(reason) => Err(reason),
+    }
+}
+
@@ -1,15 +1,22 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod agent_hub;
 mod aggregator;
 mod api;
 mod auth;
 mod commands;
 mod compliance;
 mod database;
+mod discovery;
 mod email_sender;
+mod hardware_changes;
+mod hardware_inventory;
 mod integrations;
 mod intelligence;
+mod machine_identity;
 mod models;
+mod network_map;
+mod policy_engine;
 mod scheduler;
 mod server;
 mod vulnerability_scanner;
@@ -41,6 +48,17 @@ fn main() {
         }
     }
 
+    // Chave de assinatura dos scripts: os agentes fixam-na para validar o que executam
+    match auth::script_public_key(&pool) {
+        Ok(key) => println!("[Setup] Chave pública de scripts: {}\n", key),
+        Err(e) => eprintln!("[Setup] Erro na chave de assinatura de scripts: {}", e),
+    }
+    match auth::sign_unsigned_scripts(&pool) {
+        Ok(0) => {}
+        Ok(n) => println!("[Setup] {} script(s) aprovado(s) assinado(s)", n),
+        Err(e) => eprintln!("[Setup] Erro ao assinar scripts aprovados: {}", e),
+    }
+
     let pool_for_server = Arc::clone(&pool);
     let pool_sched = Arc::clone(&pool);
     let pool_agg = Arc::clone(&pool);
@@ -74,6 +92,7 @@ fn main() {
             commands::list_machines,
             commands::get_disks,
             commands::get_software,
+            commands::get_services,
             commands::rename_machine,
             commands::get_dashboard_stats,
             commands::compare_machines,
@@ -84,8 +103,46 @@ fn main() {
             commands::create_policy,
             commands::list_policies,
             commands::delete_policy,
+            commands::check_policy_conflicts,
+            commands::preview_effective_policies,
+            commands::update_policy,
+            commands::promote_policy_revision,
+            commands::rollback_policy,
+            commands::list_policy_revisions,
+            commands::get_policy_rollout_status,
+            commands::get_machine_enforcement,
+            commands::get_policy_compliance,
+            commands::list_app_violations,
+            commands::get_allowlist_candidates,
+            commands::list_policy_matches,
+            commands::get_policy_impact,
+            commands::get_dns_stats,
             commands::get_hardware_details,
+            commands::get_hardware_inventory,
+            commands::get_battery_history,
+            commands::get_disk_health_history,
+            commands::get_hardware_changes,
+            commands::get_hardware_change_rules,
+            commands::set_hardware_change_rule,
+            commands::get_machine_fingerprints,
+            commands::list_identity_conflicts,
+            commands::merge_machines,
+            commands::dismiss_identity_conflict,
+            commands::get_usb_devices,
+            commands::get_network_connections,
+            commands::find_connections_to,
+            commands::find_listeners,
+            commands::get_device_events,
             commands::get_network_details,
+            commands::get_network_adapters,
+            commands::get_network_routes,
+            // Mapa de rede
+            commands::get_network_map,
+            commands::get_network_conflicts,
+            commands::list_ip_changes,
+            commands::get_ip_history,
+            commands::find_ip_assignments,
+            commands::list_discovered_devices,
             commands::get_security_status,
             commands::kill_process_remote,
             commands::block_software,
@@ -98,10 +155,27 @@ fn main() {
             commands::chatbot_query,
             // Screenshots
             commands::request_screenshot,
+            commands::list_screenshots,
+            commands::get_screenshot_image,
+            commands::delete_screenshot,
             commands::get_screen_time,
+            commands::get_top_apps,
+            commands::get_category_totals,
+            commands::get_weekly_usage_trend,
+            commands::get_quota_usage,
+            commands::list_screen_time_categories,
+            commands::set_screen_time_category,
             commands::create_ip_policy,
             commands::block_software_for_machine,
             commands::kill_process,
+            // Remote commands
+            commands::enqueue_agent_command,
+            commands::list_agent_commands,
+            commands::cancel_agent_command,
+            commands::create_approved_script,
+            commands::list_approved_scripts,
+            commands::get_script_signing_key,
+            commands::reset_machine_credential,
         ])
         .run(tauri::generate_context!())
         .expect("Erro ao iniciar AssetScan Dashboard");
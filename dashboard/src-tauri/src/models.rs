@@ -60,21 +60,299 @@ pub struct HardwareDetails {
     pub ram_type: String,
 }
 
+// Inventário por componente: cada linha vive de first_seen a removed_at (NULL = instalado)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MemoryModule {
+    pub id: i64,
+    pub slot: String,
+    pub size_mb: i64,
+    pub speed_mts: Option<i64>,
+    pub memory_type: Option<String>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+    pub serial_number: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub removed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GpuInfo {
+    pub id: i64,
+    pub name: String,
+    pub vendor: Option<String>,
+    pub vram_mb: Option<i64>,
+    pub driver_version: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub removed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MonitorInfo {
+    pub id: i64,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub removed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatteryInfo {
+    pub id: i64,
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub serial_number: Option<String>,
+    pub chemistry: Option<String>,
+    pub design_capacity_mwh: Option<i64>,
+    pub full_charge_capacity_mwh: Option<i64>,
+    pub cycle_count: Option<i64>,
+    pub wear_percent: Option<f64>, // Capacidade perdida face à de fábrica
+    pub first_seen: String,
+    pub last_seen: String,
+    pub removed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhysicalDisk {
+    pub id: i64,
+    pub model: String,
+    pub serial_number: Option<String>,
+    pub media_type: String, // "ssd" | "hdd" | "unknown"
+    pub bus_type: Option<String>,
+    pub size_gb: f64,
+    pub health: Option<String>, // "healthy" | "warning" | "failing" (SMART)
+    pub first_seen: String,
+    pub last_seen: String,
+    pub removed_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HardwareInventory {
+    pub memory_modules: Vec<MemoryModule>,
+    pub gpus: Vec<GpuInfo>,
+    pub monitors: Vec<MonitorInfo>,
+    pub batteries: Vec<BatteryInfo>,
+    pub physical_disks: Vec<PhysicalDisk>,
+}
+
+/// Leitura da capacidade de uma bateria; só se grava quando muda
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatteryReading {
+    pub battery_id: i64,
+    pub design_capacity_mwh: Option<i64>,
+    pub full_charge_capacity_mwh: Option<i64>,
+    pub cycle_count: Option<i64>,
+    pub recorded_at: String,
+}
+
+/// Mudança do estado SMART de um disco
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiskHealthChange {
+    pub disk_id: i64,
+    pub health: Option<String>,
+    pub recorded_at: String,
+}
+
+/// Diferença no hardware de uma máquina entre o estado guardado e o relatório que chegou
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HardwareChange {
+    pub id: i64,
+    pub machine_id: String,
+    pub hostname: Option<String>,
+    pub component: String,   // "serial_number" | "motherboard" | "bios" | "disk" | "memory" | "gpu"
+    pub change_type: String, // "changed" | "added" | "removed"
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub suspicious: bool,
+    pub detected_at: String,
+}
+
+/// Regra configurável: mudanças neste componente são suspeitas (possível adulteração)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HardwareChangeRule {
+    pub component: String,
+    pub suspicious: bool,
+}
+
+/// Impressão digital de hardware vista num machine_id (âncoras reportadas pelo agente)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MachineFingerprint {
+    pub machine_id: String,
+    pub fingerprint: String,
+    pub system_uuid: Option<String>,
+    pub bios_serial: Option<String>,
+    pub board_serial: Option<String>,
+    pub primary_mac: Option<String>,
+    pub hostname: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub report_count: i64,
+}
+
+/// Conflito de identidade: clone (o mesmo machine_id com hardware diferente) ou
+/// duplicado (o mesmo hardware com outro machine_id, por exemplo após reinstalação)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IdentityConflict {
+    pub id: i64,
+    pub kind: String, // "clone" | "duplicate"
+    pub machine_id: String,
+    pub hostname: Option<String>,
+    pub fingerprint: String,
+    pub other_machine_id: String,
+    pub other_hostname: Option<String>,
+    pub other_fingerprint: String,
+    pub detected_at: String,
+    pub last_seen: String,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>, // "merged" | "dismissed"
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkDetails {
-    pub local_ip: String,
-    pub subnet_mask: String,
-    pub gateway: String,
-    pub dns_primary: String,
+    pub local_ip: Option<String>,
+    pub subnet_mask: Option<String>,
+    pub gateway: Option<String>,
+    pub dns_primary: Option<String>,
     pub dns_secondary: Option<String>,
-    pub dhcp_enabled: bool,
-    pub domain_name: String,
+    pub dhcp_enabled: Option<bool>, // None: o agente não conseguiu saber
+    pub domain_name: Option<String>,
     pub is_domain_joined: bool,
-    pub mac_address: String,
-    pub adapter_name: String,
-    pub connection_speed_mbps: i64,
+    pub mac_address: Option<String>,
+    pub adapter_name: Option<String>,
+    pub connection_speed_mbps: Option<i64>,
     pub wifi_ssid: Option<String>,
     pub wifi_security: Option<String>,
+    pub dns_servers: Vec<String>,
+}
+
+/// Interface de rede (física, virtual ou VPN) com os endereços do último relatório
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkAdapterEntry {
+    pub name: String,
+    pub description: String,
+    pub kind: String, // "physical" | "virtual" | "vpn"
+    pub mac_address: Option<String>,
+    pub addresses: Vec<NetworkAddressEntry>,
+    pub gateways: Vec<String>,
+    /// MAC do primeiro gateway, quando o agente o viu na tabela ARP/NDP
+    pub gateway_mac: Option<String>,
+    pub dns_servers: Vec<String>,
+    pub dhcp_enabled: Option<bool>,
+    pub speed_mbps: Option<i64>,
+    pub is_up: bool,
+    pub is_wireless: bool,
+    pub is_primary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkAddressEntry {
+    pub family: String, // "ipv4" | "ipv6"
+    pub address: String,
+    pub prefix_length: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkRoute {
+    pub interface: String,
+    pub destination: String,
+    pub prefix_length: u8,
+    pub gateway: Option<String>,
+    pub metric: u32,
+}
+
+/// Período em que uma máquina teve um IP com um MAC (histórico para investigação)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IpAssignment {
+    pub id: i64,
+    pub machine_id: String,
+    pub hostname: Option<String>, // Preenchido nas pesquisas entre máquinas
+    pub adapter_name: String,
+    pub ip_address: String,
+    pub mac_address: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub ended_at: Option<String>, // None: atribuição actual
+}
+
+/// Mudança do IP de um adaptador físico entre dois relatórios
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IpChangeEvent {
+    pub id: i64,
+    pub machine_id: String,
+    pub hostname: Option<String>,
+    pub adapter_name: String,
+    pub old_ip: String,
+    pub new_ip: String,
+    pub mac_address: Option<String>,
+    pub reason: String, // "static_ip_changed" | "frequent_changes" | "address_changed"
+    pub unexpected: bool,
+    pub detected_at: String,
+}
+
+/// Endereço IPv4 de um adaptador, com a máquina, para o mapa de rede (não vai para o frontend)
+#[derive(Debug, Clone)]
+pub struct NetworkAddressRow {
+    pub machine_id: String,
+    pub hostname: String,
+    pub last_seen: String,
+    pub adapter_name: String,
+    pub kind: String,
+    pub ip_address: String,
+    pub prefix_length: u8,
+    pub mac_address: Option<String>,
+    pub gateway: Option<String>,
+    pub gateway_mac: Option<String>,
+}
+
+/// Subrede IPv4 com as máquinas que lá têm endereço
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkSubnet {
+    pub subnet: String, // "192.168.1.0/24"
+    pub gateway: Option<String>,
+    pub hosts: Vec<NetworkHost>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkHost {
+    pub machine_id: String,
+    pub hostname: String,
+    pub adapter_name: String,
+    pub kind: String,
+    pub ip_address: String,
+    pub mac_address: Option<String>,
+    pub is_online: bool,
+    pub unexpected_ip_change: bool, // Mudança inesperada nos últimos dias
+}
+
+/// Equipamento visto na tabela ARP/NDP de um agente, identificado pelo MAC
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiscoveredDevice {
+    pub id: i64,
+    pub mac_address: String, // "AA:BB:CC:DD:EE:FF"
+    pub ip_address: String,
+    pub vendor: Option<String>,
+    pub random_mac: bool, // MAC administrado localmente (telemóveis, VMs): sem fabricante
+    pub interface: String,
+    pub reported_by: String,
+    pub reported_by_hostname: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub managed_machine_id: Option<String>, // Máquina com agente que tem este MAC
+    pub managed_hostname: Option<String>,
+}
+
+/// O mesmo IP (na mesma rede) ou o mesmo MAC em máquinas diferentes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkConflict {
+    pub conflict_type: String, // "duplicate_ip" | "duplicate_mac"
+    pub value: String,
+    pub gateway: Option<String>,
+    pub hosts: Vec<NetworkHost>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +382,15 @@ pub struct SoftwareEntry {
     pub install_date: String,
 }
 
+/// Serviço do sistema (serviço Windows ou unidade systemd)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceEntry {
+    pub name: String,
+    pub display_name: String,
+    pub state: String,      // "running" | "stopped" | "failed" | ...
+    pub start_mode: String, // "auto" | "manual" | "disabled"
+}
+
 // -------------------------------------------------
 // Processos (NOVO)
 // -------------------------------------------------
@@ -139,8 +426,242 @@ pub struct Policy {
     pub created_by: String,
     pub created_at: String,
     pub enabled: bool,
+    pub revision: i64,            // Revisão imutável actualmente activa
+}
+
+/// Campos editáveis de uma política; cada alteração cria uma nova revisão
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyUpdate {
+    pub name: String,
+    pub description: String,
+    pub priority: i32,
+    pub target: String,
+    pub action: String,
+    pub config_json: String,
+    pub reason: String,
+    pub enabled: bool,
+    pub canary_group: Option<String>, // Só as máquinas com esta tag até à promoção
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyRevision {
+    pub id: i64,
+    pub policy_id: String,
+    pub revision: i64,
+    pub policy: Option<Policy>, // Snapshot completo da política nesta revisão
+    pub state: String,          // "active", "canary", "superseded", "rolled_back", "deleted"
+    pub canary_group: Option<String>,
+    pub note: String,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MachinePolicyRevision {
+    pub machine_id: String,
+    pub policy_id: String,
+    pub revision: i64,
+    pub reported_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyEnforcementStatus {
+    pub machine_id: String,
+    pub policy_id: String,
+    pub revision: i64,
+    pub status: String, // "applied" | "failed"
+    pub error: Option<String>,
+    pub kill_count: i64, // Total acumulado de processos terminados
+    pub last_hit: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyComplianceSummary {
+    pub policy_id: String,
+    pub targeted_machines: i64,
+    pub applied: i64,
+    pub failed: i64,
+    pub pending: i64, // Máquinas abrangidas que ainda não reportaram
+    pub total_kills: i64,
+    pub last_hit: Option<String>,
+    pub results: Vec<PolicyEnforcementStatus>,
 }
 
+/// Executável fora da allowlist reportado por um agente
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppViolation {
+    pub machine_id: String,
+    pub policy_id: String,
+    pub process_name: String,
+    pub exe_path: String,
+    pub sha256: Option<String>,
+    pub publisher: Option<String>,
+    pub original_filename: Option<String>,
+    pub action: String, // "blocked" | "audited"
+    pub hit_count: i64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// Correspondência de uma política em modo "alert" (nada foi bloqueado)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyMatch {
+    pub machine_id: String,
+    pub policy_id: String,
+    pub policy_type: String,
+    pub subject: String,
+    pub process_name: Option<String>,
+    pub pid: Option<i64>,
+    pub user: Option<String>,
+    pub domain: Option<String>,
+    pub remote_ip: Option<String>,
+    pub hit_count: i64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// "Esta regra teria afectado N máquinas e M processos nos últimos X dias"
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyImpact {
+    pub policy_id: String,
+    pub days: i64,
+    pub affected_machines: i64,
+    pub affected_processes: i64,
+    pub affected_users: i64,
+    pub total_matches: i64,
+    pub top_subjects: Vec<(String, i64)>,
+}
+
+/// Consultas DNS por domínio, dia e máquina (filtro DNS do agente)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DnsQueryStat {
+    pub machine_id: String,
+    pub domain: String,
+    pub date: String, // YYYY-MM-DD
+    pub queries: i64,
+    pub blocked: i64,
+    pub policy_id: Option<String>,
+    pub last_query: String,
+}
+
+/// Totais por domínio para o dashboard ("que sites foram bloqueados e quantas vezes")
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DnsDomainSummary {
+    pub domain: String,
+    pub queries: i64,
+    pub blocked: i64,
+    pub machines: i64,
+    pub policy_id: Option<String>,
+    pub last_query: String,
+}
+
+/// Dispositivo USB actualmente ligado a uma máquina
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UsbDevice {
+    pub machine_id: String,
+    pub device_id: String,
+    pub name: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    pub serial: Option<String>,
+    pub device_class: String,
+    pub manufacturer: Option<String>,
+    pub is_mass_storage: bool,
+    pub status: String, // "allowed" | "blocked" | "read_only"
+    pub updated_at: String,
+}
+
+/// Socket TCP/UDP aberto numa máquina no último relatório
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkConnection {
+    pub machine_id: String,
+    pub hostname: Option<String>, // Preenchido nas pesquisas entre máquinas
+    pub protocol: String,         // "tcp" | "udp"
+    pub pid: u32,
+    pub process_name: Option<String>,
+    pub local_ip: String,
+    pub local_port: u16,
+    pub remote_ip: String,
+    pub remote_port: u16,
+    pub remote_host: Option<String>,
+    pub state: String,
+    pub updated_at: String,
+}
+
+/// Ligação ou desligação de um dispositivo USB
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeviceEvent {
+    pub id: i64,
+    pub machine_id: String,
+    pub device_id: String,
+    pub name: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    pub serial: Option<String>,
+    pub is_mass_storage: bool,
+    pub event: String, // "connected" | "disconnected"
+    pub timestamp: String,
+}
+
+/// Comando enviado do dashboard para um agente
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentCommand {
+    pub id: String,
+    pub machine_id: String,
+    pub command_type: String, // "kill_process" | "restart_service" | "collect_now" | "run_script" | "screenshot" | "uninstall_app"
+    pub payload: String,      // JSON com os parâmetros do comando
+    pub status: String,       // "pending" | "delivered" | "succeeded" | "failed" | "expired" | "cancelled"
+    pub created_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub delivered_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Script que pode ser executado remotamente com o comando "run_script"
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApprovedScript {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub interpreter: String, // "powershell" | "cmd" | "sh"
+    pub content: String,
+    pub sha256: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub signature: String, // Ed25519 (hex) sobre id, interpretador e sha256
+}
+
+/// Captura de ecrã recebida de um agente (a imagem fica em ficheiro)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Screenshot {
+    pub id: String,
+    pub machine_id: String,
+    pub command_id: Option<String>,
+    pub captured_at: String,
+    pub received_at: String,
+    pub width: i64,
+    pub height: i64,
+    pub size_bytes: i64,
+}
+
+/// Executável agregado por hash/caminho, candidato a regra "allow"
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AllowlistCandidate {
+    pub process_name: String,
+    pub exe_path: String,
+    pub sha256: Option<String>,
+    pub publisher: Option<String>,
+    pub original_filename: Option<String>,
+    pub machine_count: i64,
+    pub hit_count: i64,
+    pub last_seen: String,
+    pub suggested_match_type: String, // "publisher" | "hash" | "path"
+    pub suggested_target: String,
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -236,6 +757,57 @@ pub struct ScreenTimeEntry {
     pub app_name: String,
     pub total_seconds: u64,
     pub date: String,
+    pub category: String,
+    pub hourly_seconds: Vec<u64>, // 24 posições, hora local do agente
+    pub user_name: String,        // Utilizador com sessão quando o relatório chegou
+}
+
+/// Classificação de uma categoria de apps ("productive" | "unproductive" | "neutral")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenTimeCategory {
+    pub category: String,
+    pub productivity: String,
+}
+
+/// App mais usada num grupo (utilizador ou departamento)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageTopApp {
+    pub group_name: String,
+    pub app_name: String,
+    pub category: String,
+    pub total_seconds: u64,
+}
+
+/// Total por categoria num grupo, com a produtividade configurada
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageCategoryTotal {
+    pub group_name: String,
+    pub category: String,
+    pub productivity: String,
+    pub total_seconds: u64,
+}
+
+/// Totais de uma semana (segunda a domingo)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageWeeklyTrend {
+    pub week_start: String,
+    pub productive_seconds: u64,
+    pub unproductive_seconds: u64,
+    pub neutral_seconds: u64,
+    pub total_seconds: u64,
+}
+
+/// Utilização de hoje face à quota diária de uma política
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub policy_id: String,
+    pub policy_name: String,
+    pub machine_id: String,
+    pub hostname: String,
+    pub target: String,
+    pub quota_seconds: u64,
+    pub used_seconds: u64,
+    pub exceeded: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
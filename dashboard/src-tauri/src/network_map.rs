@@ -0,0 +1,345 @@
+// ============================================================
+// network_map.rs — Mapa de rede, conflitos e histórico de IPs
+// ============================================================
+//
+// Agrupa as máquinas por subrede IPv4 e gateway, detecta IPs e
+// MACs repetidos entre máquinas e mantém o histórico de IP/MAC de
+// cada máquina (quem tinha o IP X a uma dada hora).
+//
+// Um IP repetido só é conflito na mesma rede física: portáteis em
+// casa (192.168.1.x atrás de routers diferentes) ou filiais com o
+// mesmo endereçamento partilham IP e gateway sem se verem. A rede
+// é identificada pelo MAC do gateway; sem ele (agentes antigos),
+// as máquinas têm de ter reportado quase ao mesmo tempo.
+//
+// Os adaptadores virtuais (Docker, WSL, Hyper-V, VirtualBox) ficam
+// de fora: repetem as mesmas redes internas e MACs em muitas máquinas.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::database::{self, DbPool};
+use crate::models::*;
+
+/// Mudanças de IP do mesmo adaptador em 24 h a partir das quais deixa de parecer DHCP normal
+const FREQUENT_CHANGES_PER_DAY: i64 = 3;
+/// Durante quanto tempo uma mudança inesperada continua a marcar a máquina no mapa
+const UNEXPECTED_CHANGE_DAYS: i64 = 7;
+/// Máquinas sem relatório há mais tempo não entram nos conflitos (o IP pode já ser de outra)
+const CONFLICT_WINDOW_HOURS: i64 = 24;
+/// Sem MAC do gateway, distância máxima entre relatórios para o IP repetido contar como conflito
+const SAME_REPORT_WINDOW_MINUTES: i64 = 15;
+
+/// Endereço de um adaptador no relatório que acabou de chegar
+pub struct ReportedAddress {
+    pub adapter_name: String,
+    pub kind: String,
+    pub ip_address: String,
+    pub mac_address: Option<String>,
+    pub dhcp_enabled: Option<bool>,
+}
+
+/// "aa-bb-cc-dd-ee-ff" ou "AA:BB:..." → "AA:BB:CC:DD:EE:FF"; vazio ou só zeros não é um MAC
+pub fn normalize_mac(mac: &str) -> Option<String> {
+    let hex: String = mac
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_uppercase();
+    if hex.len() != 12 || hex.chars().all(|c| c == '0') {
+        return None;
+    }
+
+    let octets: Vec<&str> = (0..12).step_by(2).map(|i| &hex[i..i + 2]).collect();
+    Some(octets.join(":"))
+}
+
+/// Endereços a guardar no histórico: todos menos loopback, link-local e adaptadores virtuais
+pub fn reported_addresses(adapters: &[NetworkAdapterEntry]) -> Vec<ReportedAddress> {
+    adapters
+        .iter()
+        .filter(|a| a.kind != "virtual")
+        .flat_map(|a| {
+            a.addresses
+                .iter()
+                .filter(|addr| is_assignable(&addr.address))
+                .map(move |addr| ReportedAddress {
+                    adapter_name: a.name.clone(),
+                    kind: a.kind.clone(),
+                    ip_address: addr.address.clone(),
+                    mac_address: a.mac_address.as_deref().and_then(normalize_mac),
+                    dhcp_enabled: a.dhcp_enabled,
+                })
+        })
+        .collect()
+}
+
+/// Agentes antigos só enviam o adaptador principal
+pub fn reported_primary_address(details: &NetworkDetails) -> Vec<ReportedAddress> {
+    details
+        .local_ip
+        .iter()
+        .filter(|ip| is_assignable(ip))
+        .map(|ip| ReportedAddress {
+            adapter_name: details.adapter_name.clone().unwrap_or_default(),
+            kind: "physical".to_string(),
+            ip_address: ip.clone(),
+            mac_address: details.mac_address.as_deref().and_then(normalize_mac),
+            dhcp_enabled: details.dhcp_enabled,
+        })
+        .collect()
+}
+
+fn is_assignable(ip: &str) -> bool {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => !ip.is_loopback() && !ip.is_link_local() && !ip.is_unspecified(),
+        Ok(IpAddr::V6(ip)) => !ip.is_loopback() && !ip.is_unspecified() && (ip.segments()[0] & 0xffc0) != 0xfe80,
+        Err(_) => false,
+    }
+}
+
+/// Actualiza o histórico de IP/MAC da máquina e regista as mudanças de IPv4 dos adaptadores
+/// físicos. O primeiro relatório só abre atribuições, não conta como mudança
+pub fn track_assignments(
+    pool: &DbPool,
+    machine_id: &str,
+    reported: &[ReportedAddress],
+    now: &str,
+) -> rusqlite::Result<Vec<IpChangeEvent>> {
+    let open = database::open_ip_assignments(pool, machine_id)?;
+    let key = |adapter: &str, ip: &str, mac: &Option<String>| (adapter.to_string(), ip.to_string(), mac.clone());
+
+    let reported_keys: HashSet<_> = reported
+        .iter()
+        .map(|r| key(&r.adapter_name, &r.ip_address, &r.mac_address))
+        .collect();
+    let open_keys: HashSet<_> = open
+        .iter()
+        .map(|a| key(&a.adapter_name, &a.ip_address, &a.mac_address))
+        .collect();
+
+    let (kept, ended): (Vec<&IpAssignment>, Vec<&IpAssignment>) = open
+        .iter()
+        .partition(|a| reported_keys.contains(&key(&a.adapter_name, &a.ip_address, &a.mac_address)));
+    let added: Vec<&ReportedAddress> = reported
+        .iter()
+        .filter(|r| !open_keys.contains(&key(&r.adapter_name, &r.ip_address, &r.mac_address)))
+        .collect();
+
+    let new_assignments: Vec<IpAssignment> = added
+        .iter()
+        .map(|r| IpAssignment {
+            id: 0,
+            machine_id: machine_id.to_string(),
+            hostname: None,
+            adapter_name: r.adapter_name.clone(),
+            ip_address: r.ip_address.clone(),
+            mac_address: r.mac_address.clone(),
+            first_seen: now.to_string(),
+            last_seen: now.to_string(),
+            ended_at: None,
+        })
+        .collect();
+    database::apply_ip_assignments(
+        pool,
+        &kept.iter().map(|a| a.id).collect::<Vec<_>>(),
+        &ended.iter().map(|a| a.id).collect::<Vec<_>>(),
+        &new_assignments,
+        now,
+    )?;
+
+    // Mudança = o adaptador físico perdeu um IPv4 e ganhou outro no mesmo relatório
+    let since = (Utc::now() - Duration::hours(24)).to_rfc3339();
+    let mut events = Vec::new();
+    for new in added.iter().filter(|r| r.kind == "physical" && is_ipv4(&r.ip_address)) {
+        let Some(old) = ended
+            .iter()
+            .find(|a| a.adapter_name == new.adapter_name && is_ipv4(&a.ip_address))
+        else {
+            continue;
+        };
+
+        let changes_last_day = database::count_ip_changes_since(pool, machine_id, &new.adapter_name, &since)? + 1;
+        let (reason, unexpected) = classify_change(new.dhcp_enabled, changes_last_day);
+        let mut event = IpChangeEvent {
+            id: 0,
+            machine_id: machine_id.to_string(),
+            hostname: None,
+            adapter_name: new.adapter_name.clone(),
+            old_ip: old.ip_address.clone(),
+            new_ip: new.ip_address.clone(),
+            mac_address: new.mac_address.clone(),
+            reason: reason.to_string(),
+            unexpected,
+            detected_at: now.to_string(),
+        };
+        event.id = database::insert_ip_change(pool, &event)?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+fn is_ipv4(ip: &str) -> bool {
+    ip.parse::<Ipv4Addr>().is_ok()
+}
+
+/// IP fixo que muda foi mexido à mão ou a máquina foi levada para outra rede;
+/// com DHCP só é estranho se acontecer muitas vezes no mesmo dia
+fn classify_change(dhcp_enabled: Option<bool>, changes_last_day: i64) -> (&'static str, bool) {
+    if dhcp_enabled == Some(false) {
+        ("static_ip_changed", true)
+    } else if changes_last_day >= FREQUENT_CHANGES_PER_DAY {
+        ("frequent_changes", true)
+    } else {
+        ("address_changed", false)
+    }
+}
+
+/// Máscara em notação decimal → comprimento do prefixo ("255.255.255.0" → 24)
+pub fn mask_prefix(mask: &str) -> Option<u8> {
+    let mask = u32::from(mask.parse::<Ipv4Addr>().ok()?);
+    (mask.leading_ones() + mask.trailing_zeros() == 32).then(|| mask.leading_ones() as u8)
+}
+
+/// Endereço e prefixo → rede ("10.0.0.15", 24 → "10.0.0.0/24")
+fn subnet_of(ip: &str, prefix: u8) -> Option<String> {
+    let ip: Ipv4Addr = ip.parse().ok()?;
+    if ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() {
+        return None;
+    }
+
+    let prefix = prefix.min(32);
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix as u32) };
+    Some(format!("{}/{}", Ipv4Addr::from(u32::from(ip) & mask), prefix))
+}
+
+fn seen_within(last_seen: &str, hours: i64) -> bool {
+    DateTime::parse_from_rfc3339(last_seen)
+        .map(|t| Utc::now().signed_duration_since(t) < Duration::hours(hours))
+        .unwrap_or(false)
+}
+
+/// Linhas com relatório a menos de SAME_REPORT_WINDOW_MINUTES do de outra máquina
+fn reported_together(rows: Vec<&NetworkAddressRow>) -> Vec<&NetworkAddressRow> {
+    let seen_at = |r: &NetworkAddressRow| DateTime::parse_from_rfc3339(&r.last_seen).ok();
+    let window = Duration::minutes(SAME_REPORT_WINDOW_MINUTES);
+    rows.iter()
+        .filter(|r| {
+            let Some(t) = seen_at(r) else { return false };
+            rows.iter().any(|other| {
+                other.machine_id != r.machine_id
+                    && seen_at(other).is_some_and(|o| (o - t).abs() <= window)
+            })
+        })
+        .copied()
+        .collect()
+}
+
+fn host(row: &NetworkAddressRow, flagged: &HashSet<String>) -> NetworkHost {
+    NetworkHost {
+        machine_id: row.machine_id.clone(),
+        hostname: row.hostname.clone(),
+        adapter_name: row.adapter_name.clone(),
+        kind: row.kind.clone(),
+        ip_address: row.ip_address.clone(),
+        mac_address: row.mac_address.as_deref().and_then(normalize_mac),
+        is_online: seen_within(&row.last_seen, 2),
+        unexpected_ip_change: flagged.contains(&row.machine_id),
+    }
+}
+
+fn ip_order(host: &NetworkHost) -> u32 {
+    host.ip_address.parse::<Ipv4Addr>().map(u32::from).unwrap_or(u32::MAX)
+}
+
+/// Máquinas agrupadas por subrede IPv4 e gateway
+pub fn build_map(pool: &DbPool) -> rusqlite::Result<Vec<NetworkSubnet>> {
+    let rows = database::network_address_rows(pool)?;
+    let since = (Utc::now() - Duration::days(UNEXPECTED_CHANGE_DAYS)).to_rfc3339();
+    let flagged = database::machines_with_unexpected_ip_changes(pool, &since)?;
+
+    let mut groups: BTreeMap<(String, Option<String>), Vec<NetworkHost>> = BTreeMap::new();
+    for row in rows.iter().filter(|r| r.kind != "virtual") {
+        if let Some(subnet) = subnet_of(&row.ip_address, row.prefix_length) {
+            groups
+                .entry((subnet, row.gateway.clone()))
+                .or_default()
+                .push(host(row, &flagged));
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|((subnet, gateway), mut hosts)| {
+            hosts.sort_by_key(ip_order);
+            NetworkSubnet { subnet, gateway, hosts }
+        })
+        .collect())
+}
+
+/// IPs repetidos na mesma rede (mesmo gateway e MAC do gateway) e MACs repetidos em
+/// máquinas diferentes. Só entram máquinas com relatório recente
+pub fn find_conflicts(pool: &DbPool) -> rusqlite::Result<Vec<NetworkConflict>> {
+    let rows = database::network_address_rows(pool)?;
+    let since = (Utc::now() - Duration::days(UNEXPECTED_CHANGE_DAYS)).to_rfc3339();
+    let flagged = database::machines_with_unexpected_ip_changes(pool, &since)?;
+    let recent: Vec<&NetworkAddressRow> = rows
+        .iter()
+        .filter(|r| r.kind != "virtual" && seen_within(&r.last_seen, CONFLICT_WINDOW_HOURS))
+        .collect();
+
+    let mut by_ip: HashMap<(String, Option<String>, Option<String>), Vec<&NetworkAddressRow>> = HashMap::new();
+    let mut by_mac: HashMap<String, Vec<&NetworkAddressRow>> = HashMap::new();
+    for row in &recent {
+        by_ip
+            .entry((
+                row.ip_address.clone(),
+                row.gateway.clone(),
+                row.gateway_mac.as_deref().and_then(normalize_mac),
+            ))
+            .or_default()
+            .push(row);
+        // MACs de VPN são gerados pelo cliente e repetem-se entre máquinas
+        if let Some(mac) = row.mac_address.as_deref().and_then(normalize_mac).filter(|_| row.kind == "physical") {
+            by_mac.entry(mac).or_default().push(row);
+        }
+    }
+
+    let conflict = |conflict_type: &str, value: String, gateway: Option<String>, rows: Vec<&NetworkAddressRow>| {
+        let machines: HashSet<&str> = rows.iter().map(|r| r.machine_id.as_str()).collect();
+        if machines.len() < 2 {
+            return None;
+        }
+
+        let mut seen = HashSet::new();
+        let hosts = rows
+            .into_iter()
+            .filter(|r| seen.insert((r.machine_id.clone(), r.adapter_name.clone())))
+            .map(|r| host(r, &flagged))
+            .collect();
+        Some(NetworkConflict {
+            conflict_type: conflict_type.to_string(),
+            value,
+            gateway,
+            hosts,
+        })
+    };
+
+    let mut conflicts: Vec<NetworkConflict> = by_ip
+        .into_iter()
+        .filter_map(|((ip, gateway, gateway_mac), rows)| {
+            let rows = if gateway_mac.is_some() { rows } else { reported_together(rows) };
+            conflict("duplicate_ip", ip, gateway, rows)
+        })
+        .chain(
+            by_mac
+                .into_iter()
+                .filter_map(|(mac, rows)| conflict("duplicate_mac", mac, None, rows)),
+        )
+        .collect();
+    conflicts.sort_by(|a, b| (&a.conflict_type, &a.value).cmp(&(&b.conflict_type, &b.value)));
+
+    Ok(conflicts)
+}
@@ -0,0 +1,570 @@
+// ============================================================
+// policy_engine.rs — Precedência e resolução de políticas
+// ============================================================
+//
+// Regras de precedência (por ordem de avaliação):
+//   1. Prioridade: número mais baixo ganha (1 = mais importante)
+//   2. Âmbito: política da máquina prevalece sobre política global
+//   3. Especificidade do alvo: alvo exacto/mais longo prevalece
+//   4. Acção: em empate total, "allow" prevalece sobre "block"
+//   5. Desempate final: a política mais recente ganha
+//
+// O agente ordena as políticas pelas mesmas regras (ver enforcer.rs).
+// Nas políticas web decide domínio a domínio (ficheiro hosts e filtro
+// DNS): o preview faz o mesmo, e uma política só com parte dos domínios
+// cobertos por outra de maior precedência fica "partial". Whitelists,
+// categorias e os restantes tipos são comparados como um todo.
+
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+use crate::models::Policy;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyConflict {
+    pub policy_id: String,
+    pub policy_name: String,
+    pub action: String,
+    pub winner_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicyCreationResult {
+    pub id: String,
+    pub conflicts: Vec<PolicyConflict>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EffectivePolicy {
+    pub policy: Policy,
+    pub status: String, // "effective" | "partial" | "overridden" | "disabled"
+    pub overridden_by: Option<String>,
+    /// Domínios em que outra política prevalece (políticas web)
+    pub overridden_domains: Vec<String>,
+    pub explanation: String,
+}
+
+/// Compara duas políticas; `Ordering::Greater` significa que `a` prevalece.
+/// Devolve também a razão legível da decisão.
+pub fn compare_precedence(a: &Policy, b: &Policy) -> (Ordering, String) {
+    if a.priority != b.priority {
+        return (
+            b.priority.cmp(&a.priority),
+            format!("Prioridade {} prevalece sobre {}", a.priority.min(b.priority), a.priority.max(b.priority)),
+        );
+    }
+
+    if a.machine_id.is_some() != b.machine_id.is_some() {
+        let ord = if a.machine_id.is_some() { Ordering::Greater } else { Ordering::Less };
+        return (ord, "Política da máquina prevalece sobre política global".to_string());
+    }
+
+    let spec_a = target_specificity(a);
+    let spec_b = target_specificity(b);
+    if spec_a != spec_b {
+        return (spec_a.cmp(&spec_b), "Alvo mais específico prevalece".to_string());
+    }
+
+    let allow_a = a.action == "allow";
+    let allow_b = b.action == "allow";
+    if allow_a != allow_b {
+        let ord = if allow_a { Ordering::Greater } else { Ordering::Less };
+        return (ord, "Em empate, 'allow' prevalece sobre 'block'".to_string());
+    }
+
+    (a.created_at.cmp(&b.created_at), "Em empate, a política mais recente prevalece".to_string())
+}
+
+fn target_specificity(policy: &Policy) -> usize {
+    let target = policy.target.trim();
+    if target == "*" || target.is_empty() {
+        0
+    } else {
+        target.trim_start_matches("*.").len()
+    }
+}
+
+/// Domínios abrangidos por uma política web (config_json.domains ou o próprio target)
+fn web_domains(policy: &Policy) -> Vec<String> {
+    let domains = serde_json::from_str::<serde_json::Value>(&policy.config_json)
+        .ok()
+        .and_then(|v| v.get("domains").cloned())
+        .and_then(|d| serde_json::from_value::<Vec<String>>(d).ok())
+        .unwrap_or_default();
+
+    let mut list: Vec<String> = domains
+        .iter()
+        .map(|d| d.trim().to_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect();
+
+    if list.is_empty() {
+        list.push(policy.target.trim().to_lowercase());
+    }
+    list
+}
+
+fn domain_covers(rule: &str, domain: &str) -> bool {
+    let rule = rule.trim_start_matches("*.");
+    domain == rule || domain.ends_with(&format!(".{}", rule))
+}
+
+/// Indica se duas políticas podem aplicar-se ao mesmo alvo
+pub fn targets_overlap(a: &Policy, b: &Policy) -> bool {
+    if a.policy_type != b.policy_type {
+        return false;
+    }
+
+    match a.policy_type.as_str() {
+        "web" => {
+            let da = web_domains(a);
+            let db = web_domains(b);
+            da.iter().any(|x| db.iter().any(|y| domain_covers(x, y) || domain_covers(y, x)))
+        }
+        "app" => {
+            // A política de allowlist complementa as regras "allow", não entra em conflito
+            if is_allowlist(a) || is_allowlist(b) {
+                return false;
+            }
+            let (ma, ta) = app_identity(a);
+            let (mb, tb) = app_identity(b);
+            // Regras com tipos de identificação diferentes (ex: hash vs publisher)
+            // podem coincidir no mesmo executável; assumimos sobreposição
+            ta == "*" || tb == "*" || ma != mb || ta == tb || ma.starts_with("path")
+        }
+        _ => {
+            let ta = a.target.trim().to_lowercase();
+            let tb = b.target.trim().to_lowercase();
+            ta == "*" || tb == "*" || ta.contains(&tb) || tb.contains(&ta)
+        }
+    }
+}
+
+fn is_allowlist(policy: &Policy) -> bool {
+    serde_json::from_str::<serde_json::Value>(&policy.config_json)
+        .ok()
+        .and_then(|c| c.get("mode").and_then(|m| m.as_str()).map(|m| m == "allowlist"))
+        .unwrap_or(false)
+}
+
+/// Tipo de identificação e valor normalizado de uma regra de aplicação
+fn app_identity(policy: &Policy) -> (String, String) {
+    let config = serde_json::from_str::<serde_json::Value>(&policy.config_json).unwrap_or_default();
+    let match_type = config
+        .get("match_type")
+        .and_then(|m| m.as_str())
+        .unwrap_or("name")
+        .to_string();
+
+    let value = match match_type.as_str() {
+        "hash" => config
+            .get("hash_value")
+            .and_then(|h| h.as_str())
+            .unwrap_or(&policy.target)
+            .trim()
+            .to_lowercase(),
+        "name" | "original_filename" => {
+            let target = policy.target.trim().to_lowercase();
+            target.strip_suffix(".exe").map(str::to_string).unwrap_or(target)
+        }
+        _ => policy.target.trim().to_lowercase(),
+    };
+
+    (match_type, value)
+}
+
+/// O agente só mede o tempo de utilização por executável: uma quota diária noutro
+/// tipo de alvo nunca se esgotaria, por isso é recusada
+pub fn validate_quota(policy_type: &str, config_json: &str) -> Result<(), String> {
+    let config = serde_json::from_str::<serde_json::Value>(config_json).unwrap_or_default();
+    if config.get("daily_quota_minutes").is_none() {
+        return Ok(());
+    }
+
+    let match_type = config.get("match_type").and_then(|m| m.as_str()).unwrap_or("name");
+    if policy_type == "app" && match_type == "name" {
+        Ok(())
+    } else {
+        Err("Quotas diárias só se aplicam a regras de aplicação por nome do executável".to_string())
+    }
+}
+
+/// Recusa horários que o agente não consegue interpretar (ver schedule.rs do
+/// agente): em vez de falhar, a política ficaria sempre activa
+pub fn validate_schedule(config_json: &str) -> Result<(), String> {
+    let config = serde_json::from_str::<Value>(config_json).unwrap_or_default();
+    let schedule = match config.get("schedule") {
+        None | Some(Value::Null) => return Ok(()),
+        Some(schedule) => schedule,
+    };
+
+    let custom = match schedule {
+        Value::String(preset) if preset == "always" || preset == "work_hours" => return Ok(()),
+        Value::String(preset) => return Err(format!("Horário desconhecido '{}'", preset)),
+        Value::Object(custom) => custom,
+        _ => return Err("Horário inválido".to_string()),
+    };
+
+    match custom.get("timezone") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(tz)) if tz.parse::<Tz>().is_ok() => {}
+        Some(tz) => return Err(format!("Fuso horário desconhecido: {}", tz)),
+    }
+
+    for window in json_array(custom.get("windows"), "windows")? {
+        for key in ["start", "end"] {
+            let time = window.get(key).and_then(|t| t.as_str()).unwrap_or_default();
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| format!("Hora inválida '{}' no horário (esperado HH:MM)", time))?;
+        }
+        for day in json_array(window.get("days"), "days")? {
+            if !day.as_str().is_some_and(is_weekday_name) {
+                return Err(format!("Dia da semana desconhecido no horário: {}", day));
+            }
+        }
+    }
+
+    for holiday in json_array(custom.get("holidays"), "holidays")? {
+        let valid = holiday.as_str().map(str::trim).is_some_and(|h| {
+            NaiveDate::parse_from_str(h, "%Y-%m-%d").is_ok()
+                || NaiveDate::parse_from_str(&format!("2000-{}", h), "%Y-%m-%d").is_ok()
+        });
+        if !valid {
+            return Err(format!("Feriado inválido {} (esperado AAAA-MM-DD ou MM-DD)", holiday));
+        }
+    }
+    Ok(())
+}
+
+fn json_array<'a>(value: Option<&'a Value>, field: &str) -> Result<&'a [Value], String> {
+    match value {
+        None | Some(Value::Null) => Ok(&[]),
+        Some(Value::Array(items)) => Ok(items),
+        Some(_) => Err(format!("Campo '{}' do horário tem de ser uma lista", field)),
+    }
+}
+
+/// Mesmos nomes que o agente aceita: inglês ou português, pelas três primeiras letras
+fn is_weekday_name(day: &str) -> bool {
+    let prefix: String = day.trim().to_lowercase().chars().take(3).collect();
+    matches!(
+        prefix.as_str(),
+        "mon" | "seg" | "tue" | "ter" | "wed" | "qua" | "thu" | "qui" | "fri" | "sex" | "sat" | "sáb" | "sab" | "sun" | "dom"
+    )
+}
+
+/// Duas políticas partilham âmbito se uma é global ou se visam a mesma máquina
+fn scopes_overlap(a: &Policy, b: &Policy) -> bool {
+    match (&a.machine_id, &b.machine_id) {
+        (Some(x), Some(y)) => x == y,
+        _ => true,
+    }
+}
+
+/// Detecta conflitos entre uma política candidata e as existentes
+pub fn find_conflicts(candidate: &Policy, existing: &[Policy]) -> Vec<PolicyConflict> {
+    existing
+        .iter()
+        .filter(|p| p.enabled && p.id != candidate.id)
+        .filter(|p| p.action != candidate.action)
+        .filter(|p| scopes_overlap(candidate, p) && targets_overlap(candidate, p))
+        .map(|p| {
+            let (ord, reason) = compare_precedence(candidate, p);
+            let winner_id = if ord == Ordering::Less { p.id.clone() } else { candidate.id.clone() };
+            PolicyConflict {
+                policy_id: p.id.clone(),
+                policy_name: p.name.clone(),
+                action: p.action.clone(),
+                winner_id,
+                reason,
+            }
+        })
+        .collect()
+}
+
+/// Calcula o estado efectivo de cada política para uma máquina
+pub fn resolve_effective(policies: &[Policy]) -> Vec<EffectivePolicy> {
+    policies
+        .iter()
+        .map(|policy| {
+            if !policy.enabled {
+                return effective(policy, "disabled", None, vec![], "Política desactivada".to_string());
+            }
+            if is_domain_list(policy) {
+                return resolve_web_domains(policy, policies);
+            }
+
+            let winner = policies
+                .iter()
+                .filter(|other| other.enabled && other.id != policy.id)
+                .filter(|other| other.action != policy.action && targets_overlap(policy, other))
+                .filter_map(|other| {
+                    let (ord, reason) = compare_precedence(other, policy);
+                    (ord == Ordering::Greater).then_some((other, reason))
+                })
+                .max_by(|(x, _), (y, _)| compare_precedence(x, y).0);
+
+            match winner {
+                Some((other, reason)) => effective(
+                    policy,
+                    "overridden",
+                    Some(other),
+                    vec![],
+                    format!("Substituída por '{}' ({}): {}", other.name, other.action, reason),
+                ),
+                None => effective(policy, "effective", None, vec![], "Sem conflitos com precedência superior".to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Política web com lista de domínios (sem whitelist nem categorias)
+fn is_domain_list(policy: &Policy) -> bool {
+    let target_type = serde_json::from_str::<serde_json::Value>(&policy.config_json)
+        .ok()
+        .and_then(|c| c.get("target_type").and_then(|t| t.as_str()).map(str::to_string));
+    policy.policy_type == "web" && !matches!(target_type.as_deref(), Some("whitelist" | "category"))
+}
+
+/// Como o enforcer: em cada domínio vale a política de maior precedência com uma regra
+/// que o cubra; o domínio só fica substituído se essa política tiver outra acção
+fn resolve_web_domains(policy: &Policy, policies: &[Policy]) -> EffectivePolicy {
+    let rivals: Vec<&Policy> = policies.iter().filter(|p| p.enabled && is_domain_list(p)).collect();
+    let domains = web_domains(policy);
+
+    let mut overridden: Vec<(String, &Policy)> = Vec::new();
+    for domain in &domains {
+        let winner = rivals
+            .iter()
+            .filter(|p| web_domains(p).iter().any(|rule| domain_covers(rule, domain)))
+            .max_by(|x, y| compare_precedence(x, y).0);
+        if let Some(winner) = winner.filter(|w| w.id != policy.id && w.action != policy.action) {
+            overridden.push((domain.clone(), winner));
+        }
+    }
+
+    let Some((_, first)) = overridden.first() else {
+        return effective(policy, "effective", None, vec![], "Sem conflitos com precedência superior".to_string());
+    };
+    let first = *first;
+    let status = if overridden.len() == domains.len() { "overridden" } else { "partial" };
+    let explanation = if overridden.iter().all(|(_, w)| w.id == first.id) {
+        let (_, reason) = compare_precedence(first, policy);
+        let scope = if status == "partial" {
+            format!("{} de {} domínios substituídos", overridden.len(), domains.len())
+        } else {
+            "Substituída".to_string()
+        };
+        format!("{} por '{}' ({}): {}", scope, first.name, first.action, reason)
+    } else {
+        let list: Vec<String> = overridden
+            .iter()
+            .map(|(domain, w)| format!("{} por '{}' ({})", domain, w.name, w.action))
+            .collect();
+        format!("Domínios substituídos: {}", list.join(", "))
+    };
+    let domains = overridden.iter().map(|(d, _)| d.clone()).collect();
+    effective(policy, status, Some(first), domains, explanation)
+}
+
+fn effective(
+    policy: &Policy,
+    status: &str,
+    overridden_by: Option<&Policy>,
+    overridden_domains: Vec<String>,
+    explanation: String,
+) -> EffectivePolicy {
+    EffectivePolicy {
+        policy: policy.clone(),
+        status: status.to_string(),
+        overridden_by: overridden_by.map(|p| p.id.clone()),
+        overridden_domains,
+        explanation,
+    }
+}
+
+/// Políticas enviadas ao agente: apenas activas, ordenadas por precedência
+pub fn policies_for_agent(policies: Vec<Policy>) -> Vec<Policy> {
+    let mut active: Vec<Policy> = policies.into_iter().filter(|p| p.enabled).collect();
+    active.sort_by(|a, b| compare_precedence(b, a).0);
+    active
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(id: &str, policy_type: &str, target: &str, action: &str, config_json: &str) -> Policy {
+        Policy {
+            id: id.to_string(),
+            machine_id: None,
+            name: id.to_string(),
+            description: String::new(),
+            policy_type: policy_type.to_string(),
+            priority: 1,
+            target: target.to_string(),
+            action: action.to_string(),
+            config_json: config_json.to_string(),
+            reason: String::new(),
+            created_by: "admin".to_string(),
+            created_at: "2026-01-01 10:00:00".to_string(),
+            enabled: true,
+            revision: 1,
+        }
+    }
+
+    fn web(id: &str, action: &str, domains: &[&str]) -> Policy {
+        let config = serde_json::json!({ "domains": domains }).to_string();
+        policy(id, "web", domains.first().copied().unwrap_or("*"), action, &config)
+    }
+
+    #[test]
+    fn lower_priority_number_wins() {
+        let mut a = web("a", "block", &["example.com"]);
+        let b = web("b", "allow", &["example.com"]);
+        a.priority = 2;
+
+        let (ord, reason) = compare_precedence(&b, &a);
+        assert_eq!(ord, Ordering::Greater);
+        assert_eq!(reason, "Prioridade 1 prevalece sobre 2");
+        assert_eq!(compare_precedence(&a, &b).0, Ordering::Less);
+    }
+
+    #[test]
+    fn machine_scope_beats_global() {
+        let global = web("global", "allow", &["example.com"]);
+        let mut local = web("local", "block", &["example.com"]);
+        local.machine_id = Some("m1".to_string());
+
+        assert_eq!(compare_precedence(&local, &global).0, Ordering::Greater);
+        assert_eq!(compare_precedence(&global, &local).0, Ordering::Less);
+    }
+
+    #[test]
+    fn more_specific_target_wins() {
+        let broad = policy("broad", "web", "*.example.com", "allow", "{}");
+        let exact = policy("exact", "web", "ads.example.com", "block", "{}");
+        let any = policy("any", "web", "*", "allow", "{}");
+
+        assert_eq!(compare_precedence(&exact, &broad).0, Ordering::Greater);
+        assert_eq!(compare_precedence(&broad, &any).0, Ordering::Greater);
+    }
+
+    #[test]
+    fn allow_wins_a_full_tie() {
+        let allow = web("allow", "allow", &["example.com"]);
+        let block = web("block", "block", &["example.com"]);
+
+        let (ord, reason) = compare_precedence(&allow, &block);
+        assert_eq!(ord, Ordering::Greater);
+        assert!(reason.contains("'allow'"));
+    }
+
+    #[test]
+    fn newest_breaks_the_last_tie() {
+        let older = web("older", "block", &["example.com"]);
+        let mut newer = web("newer", "block", &["example.com"]);
+        newer.created_at = "2026-02-01 10:00:00".to_string();
+
+        assert_eq!(compare_precedence(&newer, &older).0, Ordering::Greater);
+        assert_eq!(compare_precedence(&older, &newer).0, Ordering::Less);
+    }
+
+    #[test]
+    fn overlapping_targets() {
+        let parent = web("parent", "block", &["example.com"]);
+        let child = web("child", "allow", &["ads.example.com"]);
+        let other = web("other", "allow", &["other.org"]);
+        assert!(targets_overlap(&parent, &child));
+        assert!(targets_overlap(&child, &parent));
+        assert!(!targets_overlap(&parent, &other));
+
+        // Tipos diferentes nunca se sobrepõem
+        let app = policy("app", "app", "example.com", "block", "{}");
+        assert!(!targets_overlap(&parent, &app));
+
+        let by_name = policy("name", "app", "chrome.exe", "block", r#"{"match_type": "name"}"#);
+        let same_name = policy("name2", "app", "CHROME", "allow", r#"{"match_type": "name"}"#);
+        let by_hash = policy("hash", "app", "x", "allow", r#"{"match_type": "hash", "hash_value": "ab"}"#);
+        let allowlist = policy("list", "app", "*", "allow", r#"{"mode": "allowlist"}"#);
+        assert!(targets_overlap(&by_name, &same_name));
+        assert!(targets_overlap(&by_name, &by_hash)); // Podem ser o mesmo executável
+        assert!(!targets_overlap(&by_name, &allowlist));
+    }
+
+    #[test]
+    fn conflicts_report_the_winner() {
+        let candidate = web("new", "block", &["example.com"]);
+        let mut allow = web("allow", "allow", &["example.com"]);
+        allow.priority = 5;
+        let same_action = web("same", "block", &["example.com"]);
+        let mut other_machine = web("other", "allow", &["example.com"]);
+        other_machine.machine_id = Some("m2".to_string());
+        let mut disabled = web("off", "allow", &["example.com"]);
+        disabled.enabled = false;
+
+        let mut scoped = candidate.clone();
+        scoped.machine_id = Some("m1".to_string());
+        let conflicts = find_conflicts(&scoped, &[allow, same_action, other_machine, disabled]);
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].policy_id, "allow");
+        assert_eq!(conflicts[0].winner_id, "new");
+    }
+
+    #[test]
+    fn effective_state_per_web_domain() {
+        let block = web("block", "block", &["example.com", "ads.example.com", "tracker.net"]);
+        let mut allow = web("allow", "allow", &["ads.example.com"]);
+        allow.machine_id = Some("m1".to_string());
+        let mut full = web("full", "allow", &["tracker.net"]);
+        full.priority = 3;
+        let mut disabled = web("off", "allow", &["example.com"]);
+        disabled.enabled = false;
+
+        let resolved = resolve_effective(&[block, allow, full, disabled]);
+        let state = |id: &str| resolved.iter().find(|e| e.policy.id == id).unwrap();
+
+        // Só ads.example.com é substituído: o resto do bloqueio continua em vigor
+        assert_eq!(state("block").status, "partial");
+        assert_eq!(state("block").overridden_by.as_deref(), Some("allow"));
+        assert_eq!(state("block").overridden_domains, vec!["ads.example.com"]);
+        assert_eq!(state("allow").status, "effective");
+        assert_eq!(state("full").status, "overridden");
+        assert_eq!(state("full").overridden_by.as_deref(), Some("block"));
+        assert_eq!(state("off").status, "disabled");
+    }
+
+    #[test]
+    fn effective_state_of_whole_policies() {
+        let block = policy("block", "app", "game.exe", "block", "{}");
+        let mut allow = policy("allow", "app", "game.exe", "allow", "{}");
+        allow.priority = 0;
+
+        let resolved = resolve_effective(&[block, allow]);
+        assert_eq!(resolved[0].status, "overridden");
+        assert_eq!(resolved[0].overridden_by.as_deref(), Some("allow"));
+        assert_eq!(resolved[1].status, "effective");
+    }
+
+    #[test]
+    fn schedules_the_agent_cannot_read_are_rejected() {
+        assert!(validate_schedule("{}").is_ok());
+        assert!(validate_schedule(r#"{"schedule": "work_hours"}"#).is_ok());
+        assert!(validate_schedule(
+            r#"{"schedule": {"timezone": "Europe/Lisbon", "windows": [{"days": ["sex"], "start": "22:00", "end": "06:00"}], "holidays": ["12-25"]}}"#
+        )
+        .is_ok());
+
+        assert!(validate_schedule(r#"{"schedule": "office"}"#).is_err());
+        assert!(validate_schedule(r#"{"schedule": {"timezone": "Europe/Lisboa"}}"#).is_err());
+        assert!(validate_schedule(r#"{"schedule": {"windows": [{"start": "9h", "end": "18:00"}]}}"#).is_err());
+        assert!(validate_schedule(r#"{"schedule": {"windows": [{"start": "09:00"}]}}"#).is_err());
+        assert!(validate_schedule(r#"{"schedule": {"windows": [{"days": ["xyz"], "start": "09:00", "end": "18:00"}]}}"#).is_err());
+        assert!(validate_schedule(r#"{"schedule": {"holidays": ["25/12"]}}"#).is_err());
+    }
+}
@@ -2,21 +2,31 @@
 // server.rs — Servidor HTTP Axum v3.0 (Enterprise)
 // ============================================================
 
+use crate::agent_hub;
 use crate::api;
 use axum::{
-    extract::{Request, State},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Query, Request, State},
     http::{HeaderMap, StatusCode},
     middleware::{self, Next},
     response::Response,
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::auth;
+use crate::commands;
 use crate::database::{self, DbPool};
+use crate::discovery;
+use crate::hardware_changes;
+use crate::hardware_inventory;
+use crate::integrations::webhooks;
+use crate::machine_identity;
 use crate::models::*;
+use crate::network_map;
+use crate::policy_engine;
 use axum::response::sse::{Event, Sse};
 use futures::stream::Stream;
 use std::convert::Infallible;
@@ -30,18 +40,127 @@ pub struct AgentReport {
     pub agent_version: String,
     pub hostname: String,
     pub machine_id: String,
-    pub local_ip: String,
+    #[serde(default)]
+    pub local_ip: Option<String>,
     pub collected_at: String,
     pub hardware: HardwarePayload,
     pub hardware_details: HardwareDetailsPayload, // NOVO!
     pub network_details: NetworkDetailsPayload,   // NOVO!
     pub security_status: SecurityStatusPayload,   // NOVO!
     pub software: Vec<SoftwarePayload>,
+    pub services: Option<Vec<ServicePayload>>,
     pub processes: Vec<ProcessPayload>,
     pub network_connections: Option<Vec<NetworkConnectionPayload>>,
     pub screen_time: Vec<ScreenTimePayload>,
     pub current_user: Option<String>,
     pub os: OsPayload,
+    #[serde(default)]
+    pub policy_revisions: Vec<PolicyRevisionPayload>,
+    #[serde(default)]
+    pub enforcement: Vec<EnforcementPayload>,
+    #[serde(default)]
+    pub app_violations: Vec<AppViolationPayload>,
+    #[serde(default)]
+    pub policy_matches: Vec<PolicyMatchPayload>,
+    #[serde(default)]
+    pub dns_stats: Vec<DnsStatPayload>,
+    #[serde(default)]
+    pub usb_devices: Vec<UsbDevicePayload>,
+    #[serde(default)]
+    pub device_events: Vec<DeviceEventPayload>,
+    #[serde(default)]
+    pub neighbors: Option<Vec<NeighborPayload>>, // só com report_neighbors activo
+    #[serde(default)]
+    pub machine_identity: Option<MachineIdentityPayload>, // agentes antigos não enviam
+}
+
+/// Âncoras de hardware e a impressão digital calculada pelo agente
+#[derive(Deserialize, Debug)]
+pub struct MachineIdentityPayload {
+    pub system_uuid: Option<String>,
+    pub bios_serial: Option<String>,
+    pub board_serial: Option<String>,
+    pub primary_mac: Option<String>,
+    pub fingerprint: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UsbDevicePayload {
+    pub name: String,
+    pub device_id: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    pub serial: Option<String>,
+    pub device_class: String,
+    pub manufacturer: Option<String>,
+    pub is_mass_storage: bool,
+    pub status: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeviceEventPayload {
+    pub device_id: String,
+    pub name: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    pub serial: Option<String>,
+    pub is_mass_storage: bool,
+    pub event: String,
+    pub timestamp: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DnsStatPayload {
+    pub domain: String,
+    pub queries: u64,
+    pub blocked: u64,
+    pub policy_id: Option<String>,
+    pub last_query: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PolicyMatchPayload {
+    pub policy_id: String,
+    pub policy_type: String,
+    pub subject: String,
+    pub process_name: Option<String>,
+    pub pid: Option<u32>,
+    pub user: Option<String>,
+    pub domain: Option<String>,
+    pub remote_ip: Option<String>,
+    pub count: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AppViolationPayload {
+    pub policy_id: String,
+    pub process_name: String,
+    pub exe_path: String,
+    pub sha256: Option<String>,
+    pub publisher: Option<String>,
+    pub original_filename: Option<String>,
+    pub action: String,
+    pub count: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EnforcementPayload {
+    pub policy_id: String,
+    pub revision: i64,
+    pub status: String,
+    pub error: Option<String>,
+    pub kill_count: u64,
+    pub last_hit: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PolicyRevisionPayload {
+    pub policy_id: String,
+    pub revision: i64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -55,23 +174,143 @@ pub struct HardwareDetailsPayload {
     pub total_ram_slots: i64,
     pub used_ram_slots: i64,
     pub ram_type: String,
+    // Agentes antigos não enviam o inventário por componente
+    #[serde(default)]
+    pub memory_modules: Option<Vec<MemoryModulePayload>>,
+    #[serde(default)]
+    pub gpus: Option<Vec<GpuPayload>>,
+    #[serde(default)]
+    pub monitors: Option<Vec<MonitorPayload>>,
+    #[serde(default)]
+    pub batteries: Option<Vec<BatteryPayload>>,
+    #[serde(default)]
+    pub physical_disks: Option<Vec<PhysicalDiskPayload>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MemoryModulePayload {
+    pub slot: String,
+    pub size_mb: i64,
+    pub speed_mts: Option<i64>,
+    pub memory_type: Option<String>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GpuPayload {
+    pub name: String,
+    pub vendor: Option<String>,
+    pub vram_mb: Option<i64>,
+    pub driver_version: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MonitorPayload {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BatteryPayload {
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub serial_number: Option<String>,
+    pub chemistry: Option<String>,
+    pub design_capacity_mwh: Option<i64>,
+    pub full_charge_capacity_mwh: Option<i64>,
+    pub cycle_count: Option<i64>,
 }
 
 #[derive(Deserialize, Debug)]
+pub struct PhysicalDiskPayload {
+    pub model: String,
+    pub serial_number: Option<String>,
+    pub media_type: String,
+    pub bus_type: Option<String>,
+    pub size_gb: f64,
+    pub health: Option<String>,
+}
+
+/// Agentes antigos enviam texto vazio ou valores por omissão; os novos omitem o que não leram
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct NetworkDetailsPayload {
-    pub local_ip: String,
-    pub subnet_mask: String,
-    pub gateway: String,
-    pub dns_primary: String,
+    pub local_ip: Option<String>,
+    pub subnet_mask: Option<String>,
+    pub gateway: Option<String>,
+    pub dns_primary: Option<String>,
     pub dns_secondary: Option<String>,
-    pub dhcp_enabled: bool,
-    pub domain_name: String,
+    pub dhcp_enabled: Option<bool>,
+    pub domain_name: Option<String>,
     pub is_domain_joined: bool,
-    pub mac_address: String,
-    pub adapter_name: String,
-    pub connection_speed_mbps: i64,
+    pub mac_address: Option<String>,
+    pub adapter_name: Option<String>,
+    pub connection_speed_mbps: Option<i64>,
     pub wifi_ssid: Option<String>,
     pub wifi_security: Option<String>,
+    pub dns_servers: Vec<String>,
+    pub adapters: Option<Vec<NetworkAdapterPayload>>,
+    pub routes: Option<Vec<NetworkRoutePayload>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NetworkAdapterPayload {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub mac_address: Option<String>,
+    #[serde(default)]
+    pub addresses: Vec<NetworkAddressPayload>,
+    #[serde(default)]
+    pub gateways: Vec<String>,
+    #[serde(default)]
+    pub gateway_mac: Option<String>,
+    #[serde(default)]
+    pub dns_servers: Vec<String>,
+    #[serde(default)]
+    pub dhcp_enabled: Option<bool>,
+    #[serde(default)]
+    pub speed_mbps: Option<i64>,
+    #[serde(default)]
+    pub is_up: bool,
+    #[serde(default)]
+    pub is_wireless: bool,
+    #[serde(default)]
+    pub is_primary: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NetworkAddressPayload {
+    pub family: String,
+    pub address: String,
+    pub prefix_length: u8,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NetworkRoutePayload {
+    pub interface: String,
+    pub destination: String,
+    pub prefix_length: u8,
+    #[serde(default)]
+    pub gateway: Option<String>,
+    #[serde(default)]
+    pub metric: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NeighborPayload {
+    pub ip: String,
+    pub mac_address: String,
+    #[serde(default)]
+    pub interface: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -86,12 +325,26 @@ pub struct SecurityStatusPayload {
 
 #[derive(Deserialize, Debug)]
 pub struct NetworkConnectionPayload {
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
     pub pid: u32,
+    #[serde(default)]
+    pub process_name: Option<String>,
     pub local_ip: String,
+    #[serde(default)]
+    pub local_port: u16,
     pub remote_ip: String,
+    #[serde(default)]
+    pub remote_port: u16,
+    #[serde(default)]
+    pub remote_host: Option<String>,
     pub state: String,
 }
 
+fn default_protocol() -> String {
+    "tcp".to_string()
+}
+
 #[derive(Deserialize, Debug)]
 pub struct HardwarePayload {
     pub cpu_name: String,
@@ -107,6 +360,12 @@ pub struct HardwarePayload {
 pub struct ScreenTimePayload {
     pub app_name: String,
     pub total_seconds: u64,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>, // Agentes antigos só enviam o dia corrente
+    #[serde(default)]
+    pub hourly_seconds: Vec<u64>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -126,6 +385,17 @@ pub struct SoftwarePayload {
     pub install_date: String,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ServicePayload {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub start_mode: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ProcessPayload {
     pub pid: u32,
@@ -148,6 +418,59 @@ struct ApiResponse {
     status: String,
     message: String,
     policies: Vec<Policy>,
+    commands: Vec<AgentCommandDelivery>,
+    /// Credencial da máquina, enviada só no relatório em que é emitida
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent_token: Option<String>,
+}
+
+/// Comando no formato que o agente executa (parâmetros já resolvidos)
+#[derive(Serialize, Debug)]
+pub struct AgentCommandDelivery {
+    pub id: String,
+    pub command_type: String,
+    pub payload: serde_json::Value,
+    pub expires_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommandResultPayload {
+    pub command_id: String,
+    pub machine_id: String,
+    pub status: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub completed_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommandsQuery {
+    pub machine_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScreenshotUploadQuery {
+    pub machine_id: String,
+    pub command_id: String,
+    pub width: i64,
+    pub height: i64,
+    pub captured_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AgentEventsQuery {
+    pub machine_id: String,
+    pub wait: Option<u64>, // segundos
+}
+
+#[derive(Serialize, Debug)]
+pub struct AgentEventsResponse {
+    pub events: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScriptKeyResponse {
+    pub public_key: String,
 }
 
 // -------------------------------------------------
@@ -175,6 +498,20 @@ async fn auth_middleware(
     Ok(next.run(req).await)
 }
 
+/// Pedidos em nome de uma máquina: além da API Key partilhada, exige o token emitido
+/// a essa máquina (o ID enviado pelo agente, antes de resolver uniões de registos)
+fn authorize_machine(pool: &DbPool, headers: &HeaderMap, machine_id: &str) -> Result<(), StatusCode> {
+    let token = headers.get("X-Agent-Token").and_then(|h| h.to_str().ok());
+    match auth::verify_machine_token(pool, machine_id, token) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            eprintln!("[Server] Erro ao validar credencial da máquina: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 // -------------------------------------------------
 // Server-Sent Events (SSE) v3.0
 // -------------------------------------------------
@@ -191,13 +528,31 @@ pub async fn events_stream(
 
 async fn receive_report(
     State(pool): State<DbPool>,
-    Json(report): Json<AgentReport>,
+    headers: HeaderMap,
+    Json(mut report): Json<AgentReport>,
 ) -> Result<Json<ApiResponse>, StatusCode> {
     println!(
         "[Server] Relatório v{} recebido de: {} (ID: {})",
         report.agent_version, report.hostname, report.machine_id
     );
 
+    // Primeiro relatório da máquina: emite a credencial do agente
+    let agent_token = auth::issue_machine_token(&pool, &report.machine_id).unwrap_or_else(|e| {
+        eprintln!("[Server] Erro ao emitir credencial da máquina: {}", e);
+        None
+    });
+    // Com a credencial já emitida, só o agente que a tem pode enviar dados e receber
+    // políticas (reposta no dashboard se a máquina for reinstalada)
+    if agent_token.is_none() {
+        if let Err(status) = authorize_machine(&pool, &headers, &report.machine_id) {
+            eprintln!("[Server] Relatório de '{}' recusado: credencial da máquina inválida", report.machine_id);
+            return Err(status);
+        }
+    }
+
+    // Registo unido a outro: tudo o que chega passa a ser do destino
+    report.machine_id = machine_identity::resolve(&pool, &report.machine_id);
+
     // 1. Upsert da máquina (Com a ordem correcta de argumentos)
     let _db_machine_id = database::upsert_machine(
         &pool,
@@ -214,13 +569,13 @@ async fn receive_report(
         &report.os.kernel_version,
         report.os.uptime_hours as i64,
         // Enterprise Fields - CORRECTED PATHS
-        &report.local_ip,
-        &report.network_details.mac_address,
+        report.local_ip.as_deref().or(report.network_details.local_ip.as_deref()).unwrap_or(""),
+        report.network_details.mac_address.as_deref().unwrap_or(""),
         &report.hardware_details.serial_number,
         &report.hardware_details.motherboard_model,
         &report.hardware_details.gpu_name,
         report.security_status.bitlocker_active,
-        &report.network_details.domain_name,
+        report.network_details.domain_name.as_deref().unwrap_or(""),
         report.current_user.as_deref().unwrap_or(""), // Handles missing user gracefully
     )
     .map_err(|e| {
@@ -228,6 +583,41 @@ async fn receive_report(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    // Clones (mesmo ID, outro hardware) e duplicados (mesmo hardware, outro ID)
+    if let Some(identity) = &report.machine_identity {
+        if let Some(fingerprint) = &identity.fingerprint {
+            let now = chrono::Utc::now().to_rfc3339();
+            let reported = MachineFingerprint {
+                machine_id: report.machine_id.clone(),
+                fingerprint: fingerprint.clone(),
+                system_uuid: identity.system_uuid.clone(),
+                bios_serial: identity.bios_serial.clone(),
+                board_serial: identity.board_serial.clone(),
+                primary_mac: identity.primary_mac.clone(),
+                hostname: Some(report.hostname.clone()),
+                first_seen: now.clone(),
+                last_seen: now.clone(),
+                report_count: 1,
+            };
+            match machine_identity::record(&pool, &reported, &now) {
+                Ok(conflicts) => {
+                    for conflict in conflicts {
+                        println!(
+                            "[Server] Conflito de identidade ({}): '{}' ({}) e '{}' ({})",
+                            conflict.kind,
+                            report.hostname,
+                            conflict.machine_id,
+                            conflict.other_hostname.as_deref().unwrap_or("?"),
+                            conflict.other_machine_id,
+                        );
+                        webhooks::dispatch(&pool, webhooks::create_identity_conflict_event(&conflict));
+                    }
+                }
+                Err(e) => eprintln!("[Server] Erro ao verificar identidade: {}", e),
+            }
+        }
+    }
+
     let hardware_details = crate::models::HardwareDetails {
         serial_number: report.hardware_details.serial_number.clone(),
         motherboard_manufacturer: report.hardware_details.motherboard_manufacturer.clone(),
@@ -239,7 +629,118 @@ async fn receive_report(
         used_ram_slots: report.hardware_details.used_ram_slots,
         ram_type: report.hardware_details.ram_type.clone(),
     };
+
+    // Inventário por componente (memória, gráficas, monitores, baterias, discos)
+    let hw = &report.hardware_details;
+    let reported_hardware = hardware_inventory::ReportedHardware {
+        memory_modules: hw.memory_modules.as_ref().map(|list| {
+            list.iter()
+                .map(|m| MemoryModule {
+                    id: 0,
+                    slot: m.slot.clone(),
+                    size_mb: m.size_mb,
+                    speed_mts: m.speed_mts,
+                    memory_type: m.memory_type.clone(),
+                    manufacturer: m.manufacturer.clone(),
+                    part_number: m.part_number.clone(),
+                    serial_number: m.serial_number.clone(),
+                    first_seen: String::new(),
+                    last_seen: String::new(),
+                    removed_at: None,
+                })
+                .collect()
+        }),
+        gpus: hw.gpus.as_ref().map(|list| {
+            list.iter()
+                .map(|g| GpuInfo {
+                    id: 0,
+                    name: g.name.clone(),
+                    vendor: g.vendor.clone(),
+                    vram_mb: g.vram_mb,
+                    driver_version: g.driver_version.clone(),
+                    first_seen: String::new(),
+                    last_seen: String::new(),
+                    removed_at: None,
+                })
+                .collect()
+        }),
+        monitors: hw.monitors.as_ref().map(|list| {
+            list.iter()
+                .map(|m| MonitorInfo {
+                    id: 0,
+                    manufacturer: m.manufacturer.clone(),
+                    model: m.model.clone(),
+                    serial_number: m.serial_number.clone(),
+                    width: m.width,
+                    height: m.height,
+                    first_seen: String::new(),
+                    last_seen: String::new(),
+                    removed_at: None,
+                })
+                .collect()
+        }),
+        batteries: hw.batteries.as_ref().map(|list| {
+            list.iter()
+                .map(|b| BatteryInfo {
+                    id: 0,
+                    name: b.name.clone(),
+                    manufacturer: b.manufacturer.clone(),
+                    serial_number: b.serial_number.clone(),
+                    chemistry: b.chemistry.clone(),
+                    design_capacity_mwh: b.design_capacity_mwh,
+                    full_charge_capacity_mwh: b.full_charge_capacity_mwh,
+                    cycle_count: b.cycle_count,
+                    wear_percent: None,
+                    first_seen: String::new(),
+                    last_seen: String::new(),
+                    removed_at: None,
+                })
+                .collect()
+        }),
+        physical_disks: hw.physical_disks.as_ref().map(|list| {
+            list.iter()
+                .map(|d| PhysicalDisk {
+                    id: 0,
+                    model: d.model.clone(),
+                    serial_number: d.serial_number.clone(),
+                    media_type: d.media_type.clone(),
+                    bus_type: d.bus_type.clone(),
+                    size_gb: d.size_gb,
+                    health: d.health.clone(),
+                    first_seen: String::new(),
+                    last_seen: String::new(),
+                    removed_at: None,
+                })
+                .collect()
+        }),
+    };
+    let hardware_seen_at = chrono::Utc::now().to_rfc3339();
+
+    // Mudanças face ao hardware guardado, antes de o relatório o substituir
+    let fingerprint = hardware_changes::reported_fingerprint(&hardware_details, &reported_hardware);
+    match hardware_changes::detect(&pool, &report.machine_id, &report.hostname, &fingerprint, &hardware_seen_at) {
+        Ok(changes) => {
+            for change in changes {
+                if change.suspicious {
+                    println!(
+                        "[Server] Hardware suspeito em '{}': {} {} ({} → {})",
+                        report.hostname,
+                        change.component,
+                        change.change_type,
+                        change.old_value.as_deref().unwrap_or("-"),
+                        change.new_value.as_deref().unwrap_or("-"),
+                    );
+                }
+                webhooks::dispatch(&pool, webhooks::create_hardware_changed_event(&change));
+            }
+        }
+        Err(e) => eprintln!("[Server] Erro ao comparar hardware: {}", e),
+    }
+
     database::update_hardware_details(&pool, &report.machine_id, &hardware_details).ok();
+    if let Err(e) = hardware_inventory::record(&pool, &report.machine_id, &reported_hardware, &hardware_seen_at) {
+        eprintln!("[Server] Erro ao guardar inventário de hardware: {}", e);
+    }
 
     // NOVO: Guarda network details
     let network_details = crate::models::NetworkDetails {
@@ -256,9 +757,90 @@ async fn receive_report(
         connection_speed_mbps: report.network_details.connection_speed_mbps,
         wifi_ssid: report.network_details.wifi_ssid.clone(),
         wifi_security: report.network_details.wifi_security.clone(),
+        dns_servers: report.network_details.dns_servers.clone(),
     };
     database::update_network_details(&pool, &report.machine_id, &network_details).ok();
 
+    // Todas as interfaces e rotas; agentes antigos só enviam o adaptador principal
+    let reported_addresses = if let Some(payload) = &report.network_details.adapters {
+        let adapters: Vec<NetworkAdapterEntry> = payload
+            .iter()
+            .map(|a| NetworkAdapterEntry {
+                name: a.name.clone(),
+                description: a.description.clone(),
+                kind: a.kind.clone(),
+                mac_address: a.mac_address.clone(),
+                addresses: a
+                    .addresses
+                    .iter()
+                    .map(|addr| NetworkAddressEntry {
+                        family: addr.family.clone(),
+                        address: addr.address.clone(),
+                        prefix_length: addr.prefix_length,
+                    })
+                    .collect(),
+                gateways: a.gateways.clone(),
+                gateway_mac: a.gateway_mac.clone(),
+                dns_servers: a.dns_servers.clone(),
+                dhcp_enabled: a.dhcp_enabled,
+                speed_mbps: a.speed_mbps,
+                is_up: a.is_up,
+                is_wireless: a.is_wireless,
+                is_primary: a.is_primary,
+            })
+            .collect();
+        if let Err(e) = database::update_network_adapters(&pool, &report.machine_id, &adapters) {
+            eprintln!("[Server] Erro ao guardar interfaces de rede: {}", e);
+        }
+        network_map::reported_addresses(&adapters)
+    } else {
+        network_map::reported_primary_address(&network_details)
+    };
+
+    // Histórico de IP/MAC e mudanças de IP inesperadas
+    let now = chrono::Utc::now().to_rfc3339();
+    match network_map::track_assignments(&pool, &report.machine_id, &reported_addresses, &now) {
+        Ok(changes) => {
+            for change in changes.iter().filter(|c| c.unexpected) {
+                println!(
+                    "[Server] IP de '{}' ({}) mudou de {} para {}: {}",
+                    report.hostname, change.adapter_name, change.old_ip, change.new_ip, change.reason
+                );
+            }
+        }
+        Err(e) => eprintln!("[Server] Erro ao actualizar histórico de IPs: {}", e),
+    }
+    if let Some(payload) = &report.network_details.routes {
+        let routes: Vec<NetworkRoute> = payload
+            .iter()
+            .map(|r| NetworkRoute {
+                interface: r.interface.clone(),
+                destination: r.destination.clone(),
+                prefix_length: r.prefix_length,
+                gateway: r.gateway.clone(),
+                metric: r.metric,
+            })
+            .collect();
+        if let Err(e) = database::update_network_routes(&pool, &report.machine_id, &routes) {
+            eprintln!("[Server] Erro ao guardar rotas: {}", e);
+        }
+    }
+
+    // Descoberta passiva: vizinhos ARP/NDP deste agente
+    if let Some(payload) = &report.neighbors {
+        let neighbors: Vec<discovery::ReportedNeighbor> = payload
+            .iter()
+            .map(|n| discovery::ReportedNeighbor {
+                ip: n.ip.clone(),
+                mac_address: n.mac_address.clone(),
+                interface: n.interface.clone(),
+            })
+            .collect();
+        if let Err(e) = discovery::merge_neighbors(&pool, &report.machine_id, &neighbors, &now) {
+            eprintln!("[Server] Erro ao guardar equipamentos descobertos: {}", e);
+        }
+    }
+
     // NOVO: Guarda security status
     let security_status = crate::models::SecurityStatus {
         windows_defender_enabled: report.security_status.windows_defender_enabled,
@@ -300,6 +882,22 @@ async fn receive_report(
 
     let _ = database::update_software(&pool, &report.machine_id, &software);
 
+    // Agentes antigos não enviam serviços: mantém a última lista conhecida
+    if let Some(payload) = &report.services {
+        let services: Vec<ServiceEntry> = payload
+            .iter()
+            .map(|s| ServiceEntry {
+                name: s.name.clone(),
+                display_name: s.display_name.clone(),
+                state: s.state.clone(),
+                start_mode: s.start_mode.clone(),
+            })
+            .collect();
+        if let Err(e) = database::update_services(&pool, &report.machine_id, &services) {
+            eprintln!("[Server] Erro ao guardar serviços: {}", e);
+        }
+    }
+
     // 4. Atualiza processos
     let processes: Vec<ProcessInfo> = report
         .processes
@@ -318,17 +916,403 @@ async fn receive_report(
 
     let _ = database::update_processes(&pool, &report.machine_id, &processes);
 
-    // 5. Busca políticas aplicáveis a esta máquina
-    let policies = database::list_policies(&pool, Some(&report.machine_id))
+    // Tempo de utilização: totais acumulados do dia, por app
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let screen_time: Vec<ScreenTimeEntry> = report
+        .screen_time
+        .iter()
+        .map(|s| ScreenTimeEntry {
+            machine_id: report.machine_id.clone(),
+            app_name: s.app_name.clone(),
+            total_seconds: s.total_seconds,
+            date: s.date.clone().unwrap_or_else(|| today.clone()),
+            category: s.category.clone().unwrap_or_else(|| "other".to_string()),
+            hourly_seconds: s.hourly_seconds.clone(),
+            user_name: report.current_user.clone().unwrap_or_default(),
+        })
+        .collect();
+    if let Err(e) = database::insert_screen_time(&pool, &screen_time) {
+        eprintln!("[Server] Erro ao guardar screen time: {}", e);
+    }
+
+    // Revisões de políticas que o agente está a aplicar
+    let revisions: Vec<(String, i64)> = report
+        .policy_revisions
+        .iter()
+        .map(|r| (r.policy_id.clone(), r.revision))
+        .collect();
+    let _ = database::update_machine_policy_revisions(&pool, &report.machine_id, &revisions);
+
+    // Resultado da aplicação de cada política no endpoint
+    let enforcement: Vec<PolicyEnforcementStatus> = report
+        .enforcement
+        .iter()
+        .map(|e| PolicyEnforcementStatus {
+            machine_id: report.machine_id.clone(),
+            policy_id: e.policy_id.clone(),
+            revision: e.revision,
+            status: e.status.clone(),
+            error: e.error.clone(),
+            kill_count: e.kill_count as i64,
+            last_hit: e.last_hit.clone(),
+            updated_at: report.collected_at.clone(),
+        })
+        .collect();
+    if let Err(e) = database::update_policy_enforcement(&pool, &report.machine_id, &enforcement) {
+        eprintln!("[Server] Erro ao guardar resultados de enforcement: {}", e);
+    }
+
+    // Executáveis fora da allowlist (modo enforce ou aprendizagem)
+    let violations: Vec<AppViolation> = report
+        .app_violations
+        .iter()
+        .map(|v| AppViolation {
+            machine_id: report.machine_id.clone(),
+            policy_id: v.policy_id.clone(),
+            process_name: v.process_name.clone(),
+            exe_path: v.exe_path.clone(),
+            sha256: v.sha256.clone(),
+            publisher: v.publisher.clone(),
+            original_filename: v.original_filename.clone(),
+            action: v.action.clone(),
+            hit_count: v.count as i64,
+            first_seen: v.first_seen.clone(),
+            last_seen: v.last_seen.clone(),
+        })
+        .collect();
+    if let Err(e) = database::insert_app_violations(&pool, &report.machine_id, &violations) {
+        eprintln!("[Server] Erro ao guardar violações da allowlist: {}", e);
+    }
+
+    // Correspondências de políticas em modo "alert"
+    let matches: Vec<PolicyMatch> = report
+        .policy_matches
+        .iter()
+        .map(|m| PolicyMatch {
+            machine_id: report.machine_id.clone(),
+            policy_id: m.policy_id.clone(),
+            policy_type: m.policy_type.clone(),
+            subject: m.subject.clone(),
+            process_name: m.process_name.clone(),
+            pid: m.pid.map(|p| p as i64),
+            user: m.user.clone(),
+            domain: m.domain.clone(),
+            remote_ip: m.remote_ip.clone(),
+            hit_count: m.count as i64,
+            first_seen: m.first_seen.clone(),
+            last_seen: m.last_seen.clone(),
+        })
+        .collect();
+    if let Err(e) = database::insert_policy_matches(&pool, &report.machine_id, &matches) {
+        eprintln!("[Server] Erro ao guardar correspondências de políticas: {}", e);
+    }
+
+    // Consultas DNS do filtro local do agente
+    let dns_stats: Vec<DnsQueryStat> = report
+        .dns_stats
+        .iter()
+        .map(|d| DnsQueryStat {
+            machine_id: report.machine_id.clone(),
+            domain: d.domain.clone(),
+            date: d.last_query.get(..10).unwrap_or_default().to_string(),
+            queries: d.queries as i64,
+            blocked: d.blocked as i64,
+            policy_id: d.policy_id.clone(),
+            last_query: d.last_query.clone(),
+        })
+        .collect();
+    if let Err(e) = database::insert_dns_stats(&pool, &report.machine_id, &dns_stats) {
+        eprintln!("[Server] Erro ao guardar estatísticas DNS: {}", e);
+    }
+
+    // Dispositivos USB ligados e eventos de ligação/desligação
+    let usb_devices: Vec<UsbDevice> = report
+        .usb_devices
+        .iter()
+        .map(|d| UsbDevice {
+            machine_id: report.machine_id.clone(),
+            device_id: d.device_id.clone(),
+            name: d.name.clone(),
+            vendor_id: d.vendor_id.clone(),
+            product_id: d.product_id.clone(),
+            serial: d.serial.clone(),
+            device_class: d.device_class.clone(),
+            manufacturer: d.manufacturer.clone(),
+            is_mass_storage: d.is_mass_storage,
+            status: d.status.clone(),
+            updated_at: report.collected_at.clone(),
+        })
+        .collect();
+    if let Err(e) = database::update_usb_devices(&pool, &report.machine_id, &usb_devices) {
+        eprintln!("[Server] Erro ao guardar dispositivos USB: {}", e);
+    }
+
+    let device_events: Vec<DeviceEvent> = report
+        .device_events
+        .iter()
+        .map(|e| DeviceEvent {
+            id: 0,
+            machine_id: report.machine_id.clone(),
+            device_id: e.device_id.clone(),
+            name: e.name.clone(),
+            vendor_id: e.vendor_id.clone(),
+            product_id: e.product_id.clone(),
+            serial: e.serial.clone(),
+            is_mass_storage: e.is_mass_storage,
+            event: e.event.clone(),
+            timestamp: e.timestamp.clone(),
+        })
+        .collect();
+    if let Err(e) = database::insert_device_events(&pool, &report.machine_id, &device_events) {
+        eprintln!("[Server] Erro ao guardar eventos de dispositivos: {}", e);
+    }
+
+    // Agentes antigos não enviam o campo: mantém o último inventário conhecido
+    if let Some(payload) = &report.network_connections {
+        let connections: Vec<NetworkConnection> = payload
+            .iter()
+            .map(|c| NetworkConnection {
+                machine_id: report.machine_id.clone(),
+                hostname: None,
+                protocol: c.protocol.clone(),
+                pid: c.pid,
+                process_name: c.process_name.clone(),
+                local_ip: c.local_ip.clone(),
+                local_port: c.local_port,
+                remote_ip: c.remote_ip.clone(),
+                remote_port: c.remote_port,
+                remote_host: c.remote_host.clone(),
+                state: c.state.clone(),
+                updated_at: report.collected_at.clone(),
+            })
+            .collect();
+        if let Err(e) = database::update_network_connections(&pool, &report.machine_id, &connections) {
+            eprintln!("[Server] Erro ao guardar ligações de rede: {}", e);
+        }
+    }
+
+    // 5. Busca políticas aplicáveis a esta máquina (activas, por precedência, com canary)
+    let policies = database::list_policies_for_machine(&pool, &report.machine_id)
+        .map(policy_engine::policies_for_agent)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let commands = commands_for_agent(&pool, &report.machine_id);
+
     Ok(Json(ApiResponse {
         status: "ok".to_string(),
         message: format!("Dados de '{}' processados com sucesso.", report.hostname),
         policies,
+        commands,
+        agent_token,
     }))
 }
 
+// -------------------------------------------------
+// Comandos remotos: GET /api/v2/commands e POST /api/v2/commands/result
+// -------------------------------------------------
+
+/// Entrega os comandos pendentes; "run_script" leva o conteúdo do script aprovado e a
+/// assinatura feita na aprovação, que o agente valida com a chave pública fixada
+fn commands_for_agent(pool: &DbPool, machine_id: &str) -> Vec<AgentCommandDelivery> {
+    let commands = match database::take_pending_commands(pool, machine_id) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("[Server] Erro ao ler comandos pendentes: {}", e);
+            return vec![];
+        }
+    };
+
+    let mut deliveries = Vec::new();
+    for command in commands {
+        let mut payload: serde_json::Value =
+            serde_json::from_str(&command.payload).unwrap_or_else(|_| serde_json::json!({}));
+
+        if command.command_type == "run_script" {
+            let script_id = payload.get("script_id").and_then(|v| v.as_str()).unwrap_or_default();
+            let error = match database::get_approved_script(pool, script_id) {
+                Ok(Some(script)) if !script.signature.is_empty() => {
+                    payload = serde_json::json!({
+                        "script_id": script.id,
+                        "name": script.name,
+                        "interpreter": script.interpreter,
+                        "content": script.content,
+                        "signature": script.signature,
+                    });
+                    None
+                }
+                Ok(Some(_)) => Some("Script aprovado sem assinatura"),
+                _ => Some("Script aprovado já não existe"),
+            };
+            if let Some(error) = error {
+                let _ = database::complete_agent_command(
+                    pool,
+                    &command.id,
+                    machine_id,
+                    "failed",
+                    None,
+                    Some(error),
+                    &chrono::Utc::now().to_rfc3339(),
+                );
+                continue;
+            }
+        }
+
+        deliveries.push(AgentCommandDelivery {
+            id: command.id,
+            command_type: command.command_type,
+            payload,
+            expires_at: command.expires_at,
+        });
+    }
+    deliveries
+}
+
+async fn poll_commands(
+    State(pool): State<DbPool>,
+    headers: HeaderMap,
+    Query(query): Query<CommandsQuery>,
+) -> Result<Json<Vec<AgentCommandDelivery>>, StatusCode> {
+    authorize_machine(&pool, &headers, &query.machine_id)?;
+    let machine_id = machine_identity::resolve(&pool, &query.machine_id);
+    Ok(Json(commands_for_agent(&pool, &machine_id)))
+}
+
+/// Long-poll do agente: responde assim que houver eventos ou ao fim de `wait`
+async fn agent_events(
+    State(pool): State<DbPool>,
+    headers: HeaderMap,
+    Query(query): Query<AgentEventsQuery>,
+) -> Result<Json<AgentEventsResponse>, StatusCode> {
+    authorize_machine(&pool, &headers, &query.machine_id)?;
+    let wait = std::time::Duration::from_secs(query.wait.unwrap_or(50).clamp(1, 120));
+    let machine_id = machine_identity::resolve(&pool, &query.machine_id);
+    let events = agent_hub::wait(&machine_id, wait).await;
+    Ok(Json(AgentEventsResponse { events }))
+}
+
+/// Políticas da máquina, pedidas pelo agente quando recebe "policy_changed" (sem relatório completo)
+async fn agent_policies(
+    State(pool): State<DbPool>,
+    headers: HeaderMap,
+    Query(query): Query<CommandsQuery>,
+) -> Result<Json<Vec<Policy>>, StatusCode> {
+    authorize_machine(&pool, &headers, &query.machine_id)?;
+    let machine_id = machine_identity::resolve(&pool, &query.machine_id);
+    let policies = database::list_policies_for_machine(&pool, &machine_id)
+        .map(policy_engine::policies_for_agent)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(policies))
+}
+
+/// Chave pública de assinatura de scripts (fixada pelo agente no primeiro contacto)
+async fn script_key(State(pool): State<DbPool>) -> Result<Json<ScriptKeyResponse>, StatusCode> {
+    let public_key = auth::script_public_key(&pool).map_err(|e| {
+        eprintln!("[Server] Erro ao ler chave de assinatura de scripts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(ScriptKeyResponse { public_key }))
+}
+
+async fn receive_command_results(
+    State(pool): State<DbPool>,
+    headers: HeaderMap,
+    Json(results): Json<Vec<CommandResultPayload>>,
+) -> StatusCode {
+    for result in &results {
+        if let Err(status) = authorize_machine(&pool, &headers, &result.machine_id) {
+            return status;
+        }
+    }
+
+    for result in &results {
+        let machine_id = machine_identity::resolve(&pool, &result.machine_id);
+        match database::complete_agent_command(
+            &pool,
+            &result.command_id,
+            &machine_id,
+            &result.status,
+            result.output.as_deref(),
+            result.error.as_deref(),
+            &result.completed_at,
+        ) {
+            Ok(true) => {
+                let details = match &result.error {
+                    Some(error) => format!("Machine: {} | {}: {}", machine_id, result.status, error),
+                    None => format!("Machine: {} | {}", machine_id, result.status),
+                };
+                database::log_audit(&pool, "command_result", "agent_command", &result.command_id, "agent", &details).ok();
+            }
+            Ok(false) => eprintln!("[Server] Resultado ignorado para o comando {}", result.command_id),
+            Err(e) => {
+                eprintln!("[Server] Erro ao guardar resultado de comando: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        }
+    }
+    StatusCode::OK
+}
+
+// -------------------------------------------------
+// Capturas de ecrã: POST /api/v2/screenshots (corpo em image/jpeg)
+// -------------------------------------------------
+
+// Limite do corpo do upload (vários monitores em JPEG ficam bem abaixo)
+const MAX_SCREENSHOT_BYTES: usize = 10 * 1024 * 1024;
+
+async fn receive_screenshot(
+    State(pool): State<DbPool>,
+    headers: HeaderMap,
+    Query(query): Query<ScreenshotUploadQuery>,
+    body: Bytes,
+) -> StatusCode {
+    if let Err(status) = authorize_machine(&pool, &headers, &query.machine_id) {
+        return status;
+    }
+
+    if !body.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+    }
+
+    // Só aceita capturas pedidas pelo dashboard e ainda em curso
+    let machine_id = machine_identity::resolve(&pool, &query.machine_id);
+    match database::is_command_open(&pool, &query.command_id, &machine_id, "screenshot") {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN,
+        Err(e) => {
+            eprintln!("[Server] Erro ao validar captura: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let shot = Screenshot {
+        id: uuid::Uuid::new_v4().to_string(),
+        machine_id,
+        command_id: Some(query.command_id.clone()),
+        captured_at: query.captured_at.clone().unwrap_or_else(|| now.clone()),
+        received_at: now,
+        width: query.width,
+        height: query.height,
+        size_bytes: body.len() as i64,
+    };
+
+    if let Err(e) = commands::screenshots::store(&pool, &shot, &body) {
+        eprintln!("[Server] Erro ao guardar captura de ecrã: {}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    database::log_audit(
+        &pool,
+        "screenshot_captured",
+        "screenshot",
+        &shot.id,
+        "agent",
+        &format!("Machine: {} | {}x{}", shot.machine_id, shot.width, shot.height),
+    )
+    .ok();
+    StatusCode::OK
+}
+
 // -------------------------------------------------
 // Inicia o servidor HTTP
 // -------------------------------------------------
@@ -341,6 +1325,15 @@ pub async fn start_server(pool: DbPool) {
 
     let app = Router::new()
         .route("/api/v2/report", post(receive_report))
+        .route("/api/v2/commands", get(poll_commands))
+        .route("/api/v2/commands/result", post(receive_command_results))
+        .route("/api/v2/agent/events", get(agent_events))
+        .route("/api/v2/policies", get(agent_policies))
+        .route("/api/v2/script-key", get(script_key))
+        .route(
+            "/api/v2/screenshots",
+            post(receive_screenshot).layer(DefaultBodyLimit::max(MAX_SCREENSHOT_BYTES)),
+        )
         .route("/api/v3/events", axum::routing::get(events_stream))
         .merge(api::create_api_router())
         .layer(middleware::from_fn_with_state(
@@ -8,6 +8,7 @@ import Dashboard from "./pages/Dashboard";
 import Machines from "./pages/Machines";
 import MachineDetail from "./pages/MachineDetail";
 import ScreenTime from "./pages/ScreenTime";
+import NetworkMap from "./pages/NetworkMap";
 import Policies from "./pages/Policies";
 import Vulnerabilities from "./pages/Vulnerabilities";
 import Audit from "./pages/Audit";
@@ -32,6 +33,7 @@ export default function App() {
                                 <Route path="/machines" element={<Machines />} />
                                 <Route path="/machines/:id" element={<MachineDetail />} />
                                 <Route path="/screen-time" element={<ScreenTime />} />
+                                <Route path="/network" element={<NetworkMap />} />
                                 <Route path="/policies" element={<Policies />} />
                                 <Route path="/vulnerabilities" element={<Vulnerabilities />} />
                                 <Route path="/audit" element={<Audit />} />
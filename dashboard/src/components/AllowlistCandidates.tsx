@@ -0,0 +1,114 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import toast from "react-hot-toast";
+import { CheckCircle2 } from "lucide-react";
+import type { Policy } from "../hooks/usePolicies";
+
+interface AllowlistCandidate {
+    process_name: string;
+    exe_path: string;
+    sha256: string | null;
+    publisher: string | null;
+    original_filename: string | null;
+    machine_count: number;
+    hit_count: number;
+    last_seen: string;
+    suggested_match_type: "publisher" | "hash" | "path";
+    suggested_target: string;
+}
+
+const MATCH_LABELS: Record<string, string> = {
+    publisher: "Publisher",
+    hash: "Hash",
+    path: "Caminho",
+};
+
+// Executáveis vistos pela allowlist (em aprendizagem ou já bloqueados), com a regra
+// "allow" sugerida para cada um
+export default function AllowlistCandidates({ policy, onClose, onAllow }: {
+    policy: Policy;
+    onClose: () => void;
+    onAllow: (policyData: any) => Promise<unknown>;
+}) {
+    const [candidates, setCandidates] = useState<AllowlistCandidate[]>([]);
+    const [allowed, setAllowed] = useState<string[]>([]);
+
+    useEffect(() => {
+        invoke<AllowlistCandidate[]>("get_allowlist_candidates", { policyId: policy.id })
+            .then(setCandidates)
+            .catch((err) => console.error("Erro ao carregar candidatos da allowlist:", err));
+    }, [policy.id]);
+
+    const allow = async (c: AllowlistCandidate) => {
+        try {
+            await onAllow({
+                name: `Permitir ${c.process_name}`,
+                machineId: policy.machine_id ?? "all",
+                priority: String(policy.priority),
+                policyType: "app",
+                target: c.suggested_target,
+                action: "allow",
+                configJson: JSON.stringify({ match_type: c.suggested_match_type }),
+                reason: `Allowlist '${policy.name}': visto em ${c.machine_count} máquina(s)`,
+            });
+            setAllowed((prev) => [...prev, c.suggested_target]);
+            toast.success(`${c.process_name} permitido`);
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-5xl max-h-[85vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5">
+                    <div>
+                        <h2 className="text-xl font-bold text-white">Candidatos à allowlist '{policy.name}'</h2>
+                        <p className="text-xs text-slate-500">Executáveis que a allowlist bloqueou ou teria bloqueado</p>
+                    </div>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto">
+                    <table className="w-full text-left">
+                        <thead className="bg-white/5 text-slate-400 text-sm">
+                            <tr>
+                                <th className="p-3">Executável</th><th className="p-3">Máquinas</th>
+                                <th className="p-3">Ocorrências</th><th className="p-3">Última vez</th>
+                                <th className="p-3">Regra sugerida</th><th className="p-3 text-right"></th>
+                            </tr>
+                        </thead>
+                        <tbody className="divide-y divide-white/5 text-sm">
+                            {candidates.map((c) => (
+                                <tr key={`${c.suggested_match_type}:${c.suggested_target}:${c.exe_path}`}>
+                                    <td className="p-3">
+                                        <p className="text-white">{c.process_name}</p>
+                                        <p className="text-xs text-slate-500 font-mono truncate max-w-xs">{c.exe_path}</p>
+                                    </td>
+                                    <td className="p-3 text-slate-400">{c.machine_count}</td>
+                                    <td className="p-3 text-slate-400">{c.hit_count}</td>
+                                    <td className="p-3 text-slate-400">{new Date(c.last_seen).toLocaleString("pt-PT")}</td>
+                                    <td className="p-3">
+                                        <p className="text-xs text-slate-400">{MATCH_LABELS[c.suggested_match_type]}</p>
+                                        <p className="text-xs text-slate-300 font-mono truncate max-w-xs">{c.suggested_target}</p>
+                                    </td>
+                                    <td className="p-3 text-right">
+                                        {allowed.includes(c.suggested_target) ? (
+                                            <span className="text-emerald-400 text-xs">Permitido</span>
+                                        ) : (
+                                            <button onClick={() => allow(c)} className="flex items-center gap-2 px-3 py-1.5 bg-emerald-600 text-white rounded-lg hover:bg-emerald-700 transition-colors text-sm ml-auto">
+                                                <CheckCircle2 className="w-4 h-4" /> Permitir
+                                            </button>
+                                        )}
+                                    </td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                    {candidates.length === 0 && (
+                        <p className="text-center text-slate-500 text-sm mt-4">Ainda não há executáveis registados por esta allowlist.</p>
+                    )}
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,133 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import toast from "react-hot-toast";
+import { Plus } from "lucide-react";
+
+export interface ApprovedScript {
+    id: string;
+    name: string;
+    description: string;
+    interpreter: string; // powershell | cmd | sh
+    content: string;
+    sha256: string;
+    created_by: string;
+    created_at: string;
+}
+
+// Scripts que o dashboard pode mandar executar; ficam assinados pela chave do servidor
+export default function ApprovedScriptsModal({ scripts, onClose, onChanged }: {
+    scripts: ApprovedScript[];
+    onClose: () => void;
+    onChanged: () => void;
+}) {
+    const [publicKey, setPublicKey] = useState("");
+    const [name, setName] = useState("");
+    const [description, setDescription] = useState("");
+    const [interpreter, setInterpreter] = useState("powershell");
+    const [content, setContent] = useState("");
+    const [saving, setSaving] = useState(false);
+
+    useEffect(() => {
+        invoke<string>("get_script_signing_key")
+            .then(setPublicKey)
+            .catch((err) => console.error("Erro ao carregar chave de assinatura:", err));
+    }, []);
+
+    const approve = async () => {
+        if (!name.trim() || !content.trim()) return;
+
+        try {
+            setSaving(true);
+            await invoke("create_approved_script", { name: name.trim(), description, interpreter, content });
+            toast.success(`Script '${name.trim()}' aprovado`);
+            setName("");
+            setDescription("");
+            setContent("");
+            onChanged();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        } finally {
+            setSaving(false);
+        }
+    };
+
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-4xl max-h-[85vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5">
+                    <div>
+                        <h2 className="text-xl font-bold text-white">Scripts aprovados</h2>
+                        <p className="text-xs text-slate-500 font-mono break-all">Chave pública: {publicKey}</p>
+                    </div>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto space-y-6">
+                    <div className="space-y-3">
+                        <div className="grid grid-cols-3 gap-3">
+                            <input
+                                value={name}
+                                onChange={(e) => setName(e.target.value)}
+                                placeholder="Nome"
+                                className="col-span-2 p-2.5 bg-black/50 border border-white/10 rounded-lg text-white text-sm outline-none"
+                            />
+                            <select
+                                value={interpreter}
+                                onChange={(e) => setInterpreter(e.target.value)}
+                                className="p-2.5 bg-black/50 border border-white/10 rounded-lg text-white text-sm outline-none"
+                            >
+                                <option value="powershell">PowerShell</option>
+                                <option value="cmd">cmd</option>
+                                <option value="sh">sh (Linux)</option>
+                            </select>
+                        </div>
+                        <input
+                            value={description}
+                            onChange={(e) => setDescription(e.target.value)}
+                            placeholder="Descrição"
+                            className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white text-sm outline-none"
+                        />
+                        <textarea
+                            value={content}
+                            onChange={(e) => setContent(e.target.value)}
+                            placeholder="Conteúdo do script"
+                            rows={8}
+                            className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white text-sm font-mono outline-none"
+                        />
+                        <button
+                            onClick={approve}
+                            disabled={saving || !name.trim() || !content.trim()}
+                            className="flex items-center gap-2 px-4 py-2 bg-emerald-600 text-white rounded-lg hover:bg-emerald-700 transition-colors text-sm disabled:opacity-50"
+                        >
+                            <Plus className="w-4 h-4" /> Aprovar script
+                        </button>
+                    </div>
+
+                    <table className="w-full text-left">
+                        <thead className="bg-white/5 text-slate-400 text-sm">
+                            <tr>
+                                <th className="p-3">Nome</th><th className="p-3">Interpretador</th>
+                                <th className="p-3">SHA-256</th><th className="p-3">Aprovado em</th>
+                            </tr>
+                        </thead>
+                        <tbody className="divide-y divide-white/5 text-sm">
+                            {scripts.map((s) => (
+                                <tr key={s.id}>
+                                    <td className="p-3">
+                                        <p className="text-white">{s.name}</p>
+                                        {s.description && <p className="text-xs text-slate-500">{s.description}</p>}
+                                    </td>
+                                    <td className="p-3 text-slate-400">{s.interpreter}</td>
+                                    <td className="p-3 text-slate-500 font-mono text-xs">{s.sha256.slice(0, 16)}…</td>
+                                    <td className="p-3 text-slate-400">{new Date(s.created_at).toLocaleString("pt-PT")}</td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                    {scripts.length === 0 && (
+                        <p className="text-center text-slate-500 text-sm">Ainda não há scripts aprovados.</p>
+                    )}
+                </div>
+            </div>
+        </div>
+    );
+}
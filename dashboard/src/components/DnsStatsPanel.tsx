@@ -0,0 +1,69 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Globe } from "lucide-react";
+
+interface DnsDomainSummary {
+    domain: string;
+    queries: number;
+    blocked: number;
+    machines: number;
+    policy_id: string | null;
+    last_query: string;
+}
+
+// Domínios consultados através do filtro DNS local dos agentes
+export default function DnsStatsPanel({ machineId }: { machineId?: string }) {
+    const [stats, setStats] = useState<DnsDomainSummary[]>([]);
+    const [days, setDays] = useState(7);
+    const [blockedOnly, setBlockedOnly] = useState(true);
+
+    useEffect(() => {
+        invoke<DnsDomainSummary[]>("get_dns_stats", { machineId: machineId ?? null, days, blockedOnly, limit: 50 })
+            .then(setStats)
+            .catch((err) => console.error("Erro ao carregar estatísticas DNS:", err));
+    }, [machineId, days, blockedOnly]);
+
+    return (
+        <div className="bg-[#0a0a0a] border border-white/10 rounded-xl overflow-hidden">
+            <div className="p-4 border-b border-white/10 flex items-center justify-between">
+                <h3 className="font-bold text-white flex items-center gap-2">
+                    <Globe className="w-4 h-4 text-emerald-400" /> Filtro DNS
+                </h3>
+                <div className="flex items-center gap-4 text-sm">
+                    <label className="flex items-center gap-2 text-slate-400">
+                        <input type="checkbox" checked={blockedOnly} onChange={(e) => setBlockedOnly(e.target.checked)} className="rounded bg-black/50 border-white/10" />
+                        Só bloqueados
+                    </label>
+                    <select value={days} onChange={(e) => setDays(Number(e.target.value))} className="p-1.5 bg-black/50 border border-white/10 rounded-lg text-white outline-none">
+                        <option value={7}>7 dias</option>
+                        <option value={30}>30 dias</option>
+                        <option value={90}>90 dias</option>
+                    </select>
+                </div>
+            </div>
+            <table className="w-full text-left">
+                <thead className="bg-white/5 text-slate-400 text-sm">
+                    <tr>
+                        <th className="p-3">Domínio</th><th className="p-3">Consultas</th>
+                        <th className="p-3">Bloqueadas</th><th className="p-3">Máquinas</th>
+                        <th className="p-3">Última consulta</th>
+                    </tr>
+                </thead>
+                <tbody className="divide-y divide-white/5 text-sm">
+                    {stats.map((s) => (
+                        <tr key={s.domain}>
+                            <td className="p-3 text-white font-mono">{s.domain}</td>
+                            <td className="p-3 text-slate-400">{s.queries}</td>
+                            <td className={`p-3 ${s.blocked > 0 ? "text-red-400" : "text-slate-400"}`}>{s.blocked}</td>
+                            <td className="p-3 text-slate-400">{s.machines}</td>
+                            <td className="p-3 text-slate-500">{new Date(s.last_query).toLocaleString("pt-PT")}</td>
+                        </tr>
+                    ))}
+                </tbody>
+            </table>
+            {stats.length === 0 && (
+                <p className="text-center text-slate-500 text-sm p-4">Sem consultas registadas pelo filtro DNS neste período.</p>
+            )}
+        </div>
+    );
+}
@@ -0,0 +1,83 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { Policy } from "../hooks/usePolicies";
+import type { Machine } from "../hooks/useMachines";
+
+interface EffectivePolicy {
+    policy: Policy;
+    status: string;
+    overridden_by: string | null;
+    overridden_domains: string[];
+    explanation: string;
+}
+
+const STATUS_STYLES: Record<string, string> = {
+    effective: "bg-emerald-500/20 text-emerald-400",
+    partial: "bg-amber-500/20 text-amber-400",
+    overridden: "bg-red-500/20 text-red-400",
+    disabled: "bg-white/5 text-slate-400",
+};
+
+const STATUS_LABELS: Record<string, string> = {
+    effective: "em vigor",
+    partial: "parcial",
+    overridden: "substituída",
+    disabled: "desactivada",
+};
+
+// Que regra ganha numa máquina e porquê, com as mesmas regras de precedência do agente
+export default function EffectivePolicies({ machines, onClose }: {
+    machines: Machine[];
+    onClose: () => void;
+}) {
+    const [machineId, setMachineId] = useState(machines[0]?.machine_id ?? "");
+    const [effective, setEffective] = useState<EffectivePolicy[]>([]);
+
+    useEffect(() => {
+        if (!machineId) return;
+        invoke<EffectivePolicy[]>("preview_effective_policies", { machineId })
+            .then(setEffective)
+            .catch((err) => console.error("Erro ao calcular políticas efectivas:", err));
+    }, [machineId]);
+
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-4xl max-h-[80vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5 gap-4">
+                    <h2 className="text-xl font-bold text-white">Políticas efectivas</h2>
+                    <select
+                        value={machineId}
+                        onChange={(e) => setMachineId(e.target.value)}
+                        className="ml-auto bg-black border border-white/10 rounded-lg px-3 py-2 text-white text-sm"
+                    >
+                        {machines.map((m) => (
+                            <option key={m.id} value={m.machine_id}>{m.custom_name || m.hostname}</option>
+                        ))}
+                    </select>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto divide-y divide-white/5">
+                    {effective.length === 0 && <p className="text-sm text-slate-500">Nenhuma política se aplica a esta máquina.</p>}
+                    {effective.map((e) => (
+                        <div key={e.policy.id} className="py-3">
+                            <p className="text-white text-sm font-medium">
+                                {e.policy.name}
+                                <span className={`ml-2 px-2 py-0.5 rounded-full text-xs ${STATUS_STYLES[e.status] ?? ""}`}>
+                                    {STATUS_LABELS[e.status] ?? e.status}
+                                </span>
+                            </p>
+                            <p className="text-xs text-slate-400 font-mono truncate">
+                                {e.policy.policy_type} · {e.policy.action} {e.policy.target} (prioridade {e.policy.priority}
+                                {e.policy.machine_id ? ", desta máquina" : ", global"})
+                            </p>
+                            <p className="text-xs text-slate-500">{e.explanation}</p>
+                            {e.overridden_domains.length > 0 && (
+                                <p className="text-xs text-amber-400/80 font-mono">{e.overridden_domains.join(", ")}</p>
+                            )}
+                        </div>
+                    ))}
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -1,6 +1,6 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Cpu, HardDrive, MemoryStick, Monitor, Server, Wifi } from "lucide-react";
+import { BatteryMedium, Cpu, HardDrive, History, MemoryStick, Monitor, Server, Usb, Wifi } from "lucide-react";
 import { Machine } from "../hooks/useMachines";
 
 interface DiskInfo {
@@ -32,6 +32,112 @@ interface NetworkAdapter {
     status: string;
 }
 
+// Inventário por componente (get_hardware_inventory)
+interface MemoryModule {
+    id: number;
+    slot: string;
+    size_mb: number;
+    speed_mts?: number;
+    memory_type?: string;
+    manufacturer?: string;
+    part_number?: string;
+    serial_number?: string;
+}
+
+interface GpuInfo {
+    id: number;
+    name: string;
+    vendor?: string;
+    vram_mb?: number;
+    driver_version?: string;
+}
+
+interface MonitorInfo {
+    id: number;
+    manufacturer?: string;
+    model?: string;
+    serial_number?: string;
+    width?: number;
+    height?: number;
+}
+
+interface BatteryInfo {
+    id: number;
+    name?: string;
+    manufacturer?: string;
+    chemistry?: string;
+    design_capacity_mwh?: number;
+    full_charge_capacity_mwh?: number;
+    cycle_count?: number;
+    wear_percent?: number;
+}
+
+interface PhysicalDisk {
+    id: number;
+    model: string;
+    serial_number?: string;
+    media_type: string; // "ssd" | "hdd" | "unknown"
+    bus_type?: string;
+    size_gb: number;
+    health?: string; // "healthy" | "warning" | "failing"
+}
+
+interface HardwareInventory {
+    memory_modules: MemoryModule[];
+    gpus: GpuInfo[];
+    monitors: MonitorInfo[];
+    batteries: BatteryInfo[];
+    physical_disks: PhysicalDisk[];
+}
+
+interface HardwareChange {
+    id: number;
+    component: string;
+    change_type: string; // "changed" | "added" | "removed"
+    old_value?: string;
+    new_value?: string;
+    suspicious: boolean;
+    detected_at: string;
+}
+
+const CHANGE_LABELS: Record<string, string> = {
+    changed: "Alterado",
+    added: "Adicionado",
+    removed: "Removido",
+};
+
+const DISK_HEALTH: Record<string, { label: string; color: string }> = {
+    healthy: { label: "Saudável", color: "text-emerald-400" },
+    warning: { label: "Aviso", color: "text-amber-400" },
+    failing: { label: "A falhar", color: "text-red-400" },
+};
+
+interface UsbDevice {
+    device_id: string;
+    name: string;
+    vendor_id: string;
+    product_id: string;
+    serial?: string;
+    device_class: string;
+    manufacturer?: string;
+    is_mass_storage: boolean;
+    status: string; // "allowed" | "blocked" | "read_only"
+}
+
+interface DeviceEvent {
+    id: number;
+    name: string;
+    serial?: string;
+    event: string; // "connected" | "disconnected"
+    timestamp: string;
+}
+
+const USB_STATUS: Record<string, { label: string; color: string }> = {
+    allowed: { label: "Permitido", color: "text-emerald-400" },
+    blocked: { label: "Bloqueado", color: "text-red-400" },
+    read_only: { label: "Só Leitura", color: "text-amber-400" },
+};
+
 interface Props {
     machine: Machine;
 }
@@ -48,6 +154,10 @@ const InfoRow = ({ label, value }: { label: string; value: React.ReactNode }) =>
 export default function HardwareTab({ machine }: Props) {
     const [disks, setDisks] = useState<DiskInfo[]>([]);
     const [hardware, setHardware] = useState<HardwareDetails | null>(null);
+    const [inventory, setInventory] = useState<HardwareInventory | null>(null);
+    const [changes, setChanges] = useState<HardwareChange[]>([]);
+    const [usbDevices, setUsbDevices] = useState<UsbDevice[]>([]);
+    const [deviceEvents, setDeviceEvents] = useState<DeviceEvent[]>([]);
     const [loading, setLoading] = useState(true);
 
     useEffect(() => {
@@ -66,6 +176,12 @@ export default function HardwareTab({ machine }: Props) {
             // Carrega hardware details
             const hardwareData = await invoke<HardwareDetails>("get_hardware_details", { machineId });
             setHardware(hardwareData);
+            setInventory(await invoke<HardwareInventory>("get_hardware_inventory", { machineId }));
+            setChanges(await invoke<HardwareChange[]>("get_hardware_changes", { machineId, limit: 20 }));
+
+            // Dispositivos USB ligados e histórico recente
+            setUsbDevices(await invoke<UsbDevice[]>("get_usb_devices", { machineId }));
+            setDeviceEvents(await invoke<DeviceEvent[]>("get_device_events", { machineId, limit: 20 }));
         } catch (err) {
             console.error("Erro ao carregar hardware:", err);
         } finally {
@@ -178,6 +294,168 @@ export default function HardwareTab({ machine }: Props) {
                 </div>
             </div>
 
+            {/* MÓDULOS DE MEMÓRIA E PLACAS GRÁFICAS */}
+            {inventory && (inventory.memory_modules.length > 0 || inventory.gpus.length > 1) && (
+                <div className="grid grid-cols-1 md:grid-cols-2 gap-6">
+                    <div className="liquid-glass rounded-2xl p-6">
+                        <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                            <MemoryStick className="w-5 h-5 text-emerald-400" />
+                            Módulos de Memória
+                        </h3>
+                        <div className="bg-white/5 border border-white/10 rounded-xl p-4">
+                            {inventory.memory_modules.length === 0 ? (
+                                <p className="text-slate-500 text-sm italic">Não reportado</p>
+                            ) : inventory.memory_modules.map((m) => (
+                                <InfoRow
+                                    key={m.id}
+                                    label={m.slot || "—"}
+                                    value={
+                                        <span className="text-xs">
+                                            {(m.size_mb / 1024).toFixed(0)} GB {m.memory_type ?? ""}
+                                            {m.speed_mts ? ` ${m.speed_mts} MT/s` : ""}
+                                            {m.part_number && <span className="text-slate-400 font-mono"> · {m.part_number}</span>}
+                                        </span>
+                                    }
+                                />
+                            ))}
+                        </div>
+                    </div>
+
+                    <div className="liquid-glass rounded-2xl p-6">
+                        <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                            <Monitor className="w-5 h-5 text-emerald-400" />
+                            Placas Gráficas
+                        </h3>
+                        <div className="bg-white/5 border border-white/10 rounded-xl p-4">
+                            {inventory.gpus.map((g) => (
+                                <InfoRow
+                                    key={g.id}
+                                    label={g.vendor ?? "GPU"}
+                                    value={
+                                        <span className="text-xs">
+                                            {g.name}
+                                            {g.vram_mb ? ` · ${g.vram_mb} MB` : ""}
+                                            {g.driver_version && <span className="text-slate-400"> · {g.driver_version}</span>}
+                                        </span>
+                                    }
+                                />
+                            ))}
+                        </div>
+                    </div>
+                </div>
+            )}
+
+            {/* MONITORES E BATERIAS */}
+            {inventory && (inventory.monitors.length > 0 || inventory.batteries.length > 0) && (
+                <div className="grid grid-cols-1 md:grid-cols-2 gap-6">
+                    <div className="liquid-glass rounded-2xl p-6">
+                        <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                            <Monitor className="w-5 h-5 text-emerald-400" />
+                            Monitores
+                        </h3>
+                        <div className="bg-white/5 border border-white/10 rounded-xl p-4">
+                            {inventory.monitors.length === 0 ? (
+                                <p className="text-slate-500 text-sm italic">Nenhum monitor externo reportado</p>
+                            ) : inventory.monitors.map((m) => (
+                                <InfoRow
+                                    key={m.id}
+                                    label={[m.manufacturer, m.model].filter(Boolean).join(" ") || "Monitor"}
+                                    value={
+                                        <span className="text-xs">
+                                            {m.width && m.height ? `${m.width}×${m.height}` : "—"}
+                                            {m.serial_number && <span className="text-slate-400 font-mono"> · {m.serial_number}</span>}
+                                        </span>
+                                    }
+                                />
+                            ))}
+                        </div>
+                    </div>
+
+                    <div className="liquid-glass rounded-2xl p-6">
+                        <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                            <BatteryMedium className="w-5 h-5 text-emerald-400" />
+                            Bateria
+                        </h3>
+                        <div className="bg-white/5 border border-white/10 rounded-xl p-4">
+                            {inventory.batteries.length === 0 ? (
+                                <p className="text-slate-500 text-sm italic">Sem bateria</p>
+                            ) : inventory.batteries.map((b) => (
+                                <div key={b.id}>
+                                    <InfoRow label="Modelo" value={[b.manufacturer, b.name].filter(Boolean).join(" ")} />
+                                    <InfoRow
+                                        label="Capacidade"
+                                        value={b.full_charge_capacity_mwh && b.design_capacity_mwh
+                                            ? `${(b.full_charge_capacity_mwh / 1000).toFixed(1)} / ${(b.design_capacity_mwh / 1000).toFixed(1)} Wh`
+                                            : undefined}
+                                    />
+                                    <InfoRow
+                                        label="Desgaste"
+                                        value={b.wear_percent != null ? (
+                                            <span className={b.wear_percent > 30 ? "text-red-400" : "text-emerald-400"}>
+                                                {b.wear_percent.toFixed(0)}%
+                                            </span>
+                                        ) : undefined}
+                                    />
+                                    <InfoRow label="Ciclos" value={b.cycle_count} />
+                                </div>
+                            ))}
+                        </div>
+                    </div>
+                </div>
+            )}
+
+            {/* DISCOS FÍSICOS */}
+            {inventory && inventory.physical_disks.length > 0 && (
+                <div className="liquid-glass rounded-2xl p-6">
+                    <div className="flex items-center gap-3 mb-6">
+                        <HardDrive className="w-6 h-6 text-emerald-400" />
+                        <h3 className="text-xl font-bold text-white">Discos Físicos</h3>
+                    </div>
+                    <div className="bg-white/5 border border-white/10 rounded-xl p-4">
+                        {inventory.physical_disks.map((d) => {
+                            const health = d.health ? DISK_HEALTH[d.health] : undefined;
+                            return (
+                                <InfoRow
+                                    key={d.id}
+                                    label={d.model}
+                                    value={
+                                        <span className="text-xs">
+                                            {d.size_gb.toFixed(0)} GB · {d.media_type.toUpperCase()}
+                                            {d.bus_type ? ` · ${d.bus_type}` : ""}
+                                            {d.serial_number && <span className="text-slate-400 font-mono"> · {d.serial_number}</span>}
+                                            {health && <span className={`${health.color} font-bold`}> · {health.label}</span>}
+                                        </span>
+                                    }
+                                />
+                            );
+                        })}
+                    </div>
+                </div>
+            )}
+
+            {/* ALTERAÇÕES DE HARDWARE */}
+            {changes.length > 0 && (
+                <div className="liquid-glass rounded-2xl p-6">
+                    <div className="flex items-center gap-3 mb-6">
+                        <History className="w-6 h-6 text-emerald-400" />
+                        <h3 className="text-xl font-bold text-white">Alterações de Hardware</h3>
+                    </div>
+                    <div className="bg-white/5 border border-white/10 rounded-xl p-4">
+                        {changes.map((c) => (
+                            <InfoRow
+                                key={c.id}
+                                label={new Date(c.detected_at).toLocaleString('pt-PT')}
+                                value={
+                                    <span className={`text-xs ${c.suspicious ? "text-red-400" : "text-white"}`}>
+                                        {c.component} · {CHANGE_LABELS[c.change_type] ?? c.change_type}: {c.old_value ?? "—"} → {c.new_value ?? "—"}
+                                    </span>
+                                }
+                            />
+                        ))}
+                    </div>
+                </div>
+            )}
+
             {/* ARMAZENAMENTO */}
             <div className="liquid-glass rounded-2xl p-6">
                 <div className="flex items-center gap-3 mb-6">
@@ -234,6 +512,63 @@ export default function HardwareTab({ machine }: Props) {
                 </div>
             </div>
 
+            {/* DISPOSITIVOS USB */}
+            <div className="liquid-glass rounded-2xl p-6">
+                <div className="flex items-center gap-3 mb-6">
+                    <Usb className="w-6 h-6 text-emerald-400" />
+                    <h3 className="text-xl font-bold text-white">Dispositivos USB</h3>
+                </div>
+                {usbDevices.length === 0 ? (
+                    <p className="text-slate-500 text-sm italic">Nenhum dispositivo USB reportado</p>
+                ) : (
+                    <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+                        {usbDevices.map((device) => {
+                            const status = USB_STATUS[device.status] ?? USB_STATUS.allowed;
+                            return (
+                                <div key={device.device_id} className="bg-white/5 border border-white/10 rounded-xl p-4">
+                                    <div className="flex items-center justify-between mb-3">
+                                        <span className="font-medium text-white">{device.name}</span>
+                                        <span className={`badge-apple ${status.color}`}>{status.label}</span>
+                                    </div>
+                                    <div className="space-y-1 text-sm">
+                                        <div className="flex justify-between">
+                                            <span className="text-slate-400">VID:PID</span>
+                                            <span className="text-white font-mono">{device.vendor_id}:{device.product_id}</span>
+                                        </div>
+                                        <div className="flex justify-between">
+                                            <span className="text-slate-400">Série</span>
+                                            <span className="text-white font-mono">{device.serial || "—"}</span>
+                                        </div>
+                                        <div className="flex justify-between">
+                                            <span className="text-slate-400">Classe</span>
+                                            <span className="text-white">
+                                                {device.is_mass_storage ? "Armazenamento" : device.device_class}
+                                            </span>
+                                        </div>
+                                    </div>
+                                </div>
+                            );
+                        })}
+                    </div>
+                )}
+
+                {deviceEvents.length > 0 && (
+                    <div className="mt-6 bg-white/5 border border-white/10 rounded-xl p-4">
+                        {deviceEvents.map((e) => (
+                            <InfoRow
+                                key={e.id}
+                                label={new Date(e.timestamp).toLocaleString('pt-PT')}
+                                value={
+                                    <span className={e.event === "connected" ? "text-emerald-400" : "text-slate-400"}>
+                                        {e.event === "connected" ? "Ligado" : "Desligado"}: {e.name}
+                                    </span>
+                                }
+                            />
+                        ))}
+                    </div>
+                )}
+            </div>
+
             {/* ADAPTADORES DE REDE */}
             {hardware?.network_adapters && hardware.network_adapters.length > 0 && (
                 <div className="liquid-glass rounded-2xl p-6">
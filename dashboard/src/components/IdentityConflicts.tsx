@@ -0,0 +1,105 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import toast from "react-hot-toast";
+import { Copy, GitMerge, X } from "lucide-react";
+
+interface IdentityConflict {
+    id: number;
+    kind: "clone" | "duplicate";
+    machine_id: string;
+    hostname?: string;
+    fingerprint: string;
+    other_machine_id: string;
+    other_hostname?: string;
+    other_fingerprint: string;
+    detected_at: string;
+    last_seen: string;
+}
+
+const KIND_LABELS: Record<string, string> = {
+    clone: "Clone: o mesmo ID em hardware diferente",
+    duplicate: "Duplicado: o mesmo hardware com outro ID",
+};
+
+export default function IdentityConflicts({ onMerged }: { onMerged: () => void }) {
+    const [conflicts, setConflicts] = useState<IdentityConflict[]>([]);
+
+    const load = () => {
+        invoke<IdentityConflict[]>("list_identity_conflicts")
+            .then(setConflicts)
+            .catch((err) => console.error("Erro ao carregar conflitos de identidade:", err));
+    };
+
+    useEffect(() => {
+        load();
+    }, []);
+
+    // O registo mais antigo (o que já existia) fica com o histórico dos dois
+    const merge = async (c: IdentityConflict) => {
+        try {
+            const combined = await invoke<number>("merge_machines", { sourceId: c.machine_id, targetId: c.other_machine_id });
+            const summary = combined > 0 ? ` (${combined} registos de histórico somados aos do destino)` : "";
+            toast.success(`${c.hostname ?? c.machine_id} unida a ${c.other_hostname ?? c.other_machine_id}${summary}`);
+            load();
+            onMerged();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    const dismiss = async (c: IdentityConflict) => {
+        try {
+            await invoke("dismiss_identity_conflict", { id: c.id });
+            setConflicts((prev) => prev.filter((x) => x.id !== c.id));
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    if (conflicts.length === 0) {
+        return null;
+    }
+
+    return (
+        <div className="liquid-glass rounded-xl p-4 border-l-4 border-amber-500 space-y-3">
+            <h3 className="font-bold text-white flex items-center gap-2">
+                <Copy className="w-4 h-4 text-amber-400" />
+                Conflitos de identidade
+                <span className="badge-apple text-amber-500">{conflicts.length}</span>
+            </h3>
+            {conflicts.map((c) => (
+                <div key={c.id} className="flex items-center justify-between gap-4 bg-black/20 rounded-lg px-4 py-3">
+                    <div className="min-w-0">
+                        <p className="text-sm text-amber-400">{KIND_LABELS[c.kind] ?? c.kind}</p>
+                        <p className="text-white text-sm">
+                            {c.hostname ?? "?"} <span className="text-gray-500">e</span> {c.other_hostname ?? "?"}
+                        </p>
+                        <p className="text-xs text-gray-500 font-mono truncate">
+                            {c.machine_id}
+                            {c.kind === "duplicate" && ` → ${c.other_machine_id}`}
+                        </p>
+                        <p className="text-xs text-gray-600">Visto em {new Date(c.last_seen).toLocaleString("pt-PT")}</p>
+                    </div>
+                    <div className="flex items-center gap-2 shrink-0">
+                        {c.kind === "duplicate" && (
+                            <button
+                                onClick={() => merge(c)}
+                                className="btn-apple-primary ripple-container flex items-center gap-2"
+                            >
+                                <GitMerge className="w-4 h-4" />
+                                Unir registos
+                            </button>
+                        )}
+                        <button
+                            onClick={() => dismiss(c)}
+                            className="btn-apple-secondary ripple-container flex items-center gap-2"
+                        >
+                            <X className="w-4 h-4" />
+                            Ignorar
+                        </button>
+                    </div>
+                </div>
+            ))}
+        </div>
+    );
+}
@@ -1,24 +1,68 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Globe, Wifi, ShieldCheck, Lock, User, MapPin, Activity } from "lucide-react";
+import { Globe, Wifi, ShieldCheck, Lock, User, MapPin, Activity, Network, Cable, Route, History } from "lucide-react";
 import { Machine } from "../hooks/useMachines";
 
 interface NetworkDetails {
-    local_ip: string;
-    subnet_mask: string;
-    gateway: string;
-    dns_primary: string;
-    dns_secondary?: string;
-    dhcp_enabled: boolean;
-    domain_name: string;
+    local_ip: string | null;
+    subnet_mask: string | null;
+    gateway: string | null;
+    dns_primary: string | null;
+    dns_secondary: string | null;
+    dhcp_enabled: boolean | null;
+    domain_name: string | null;
     is_domain_joined: boolean;
-    mac_address: string;
+    mac_address: string | null;
+    adapter_name: string | null;
+    connection_speed_mbps: number | null;
+    wifi_ssid: string | null;
+    wifi_security: string | null;
+    dns_servers: string[];
+}
+
+interface NetworkAdapter {
+    name: string;
+    description: string;
+    kind: "physical" | "virtual" | "vpn";
+    mac_address: string | null;
+    addresses: { family: "ipv4" | "ipv6"; address: string; prefix_length: number }[];
+    gateways: string[];
+    gateway_mac: string | null;
+    dns_servers: string[];
+    dhcp_enabled: boolean | null;
+    speed_mbps: number | null;
+    is_up: boolean;
+    is_wireless: boolean;
+    is_primary: boolean;
+}
+
+interface NetworkRoute {
+    interface: string;
+    destination: string;
+    prefix_length: number;
+    gateway: string | null;
+    metric: number;
+}
+
+interface IpAssignment {
+    id: number;
     adapter_name: string;
-    connection_speed_mbps: number;
-    wifi_ssid?: string;
-    wifi_security?: string;
+    ip_address: string;
+    mac_address: string | null;
+    first_seen: string;
+    last_seen: string;
+    ended_at: string | null;
 }
 
+const KIND_LABELS: Record<string, { label: string; color: string }> = {
+    physical: { label: "Física", color: "text-emerald-400" },
+    virtual: { label: "Virtual", color: "text-slate-400" },
+    vpn: { label: "VPN", color: "text-violet-400" },
+};
+
+const dhcpLabel = (dhcp: boolean | null | undefined) =>
+    dhcp == null ? "Desconhecido" : dhcp ? "DHCP Dinâmico" : "IP Estático";
+
 interface SecurityStatus {
     windows_defender_enabled: boolean;
     windows_defender_updated: boolean;
@@ -35,6 +79,18 @@ interface UserInfo {
     location?: string;
 }
 
+interface NetworkConnection {
+    protocol: string;
+    pid: number;
+    process_name?: string;
+    local_ip: string;
+    local_port: number;
+    remote_ip: string;
+    remote_port: number;
+    remote_host?: string;
+    state: string;
+}
+
 interface Props {
     machine: Machine;
 }
@@ -52,6 +108,11 @@ export default function NetworkTab({ machine }: Props) {
     const [network, setNetwork] = useState<NetworkDetails | null>(null);
     const [security, setSecurity] = useState<SecurityStatus | null>(null);
     const [userInfo, setUserInfo] = useState<UserInfo | null>(null);
+    const [connections, setConnections] = useState<NetworkConnection[]>([]);
+    const [adapters, setAdapters] = useState<NetworkAdapter[]>([]);
+    const [routes, setRoutes] = useState<NetworkRoute[]>([]);
+    const [ipHistory, setIpHistory] = useState<IpAssignment[]>([]);
+    const [onlyListening, setOnlyListening] = useState(false);
     const [loading, setLoading] = useState(true);
 
     useEffect(() => {
@@ -67,10 +128,18 @@ export default function NetworkTab({ machine }: Props) {
             const networkData = await invoke<NetworkDetails>("get_network_details", { machineId });
             setNetwork(networkData);
 
+            // Todas as interfaces e a tabela de rotas
+            setAdapters(await invoke<NetworkAdapter[]>("get_network_adapters", { machineId }));
+            setRoutes(await invoke<NetworkRoute[]>("get_network_routes", { machineId }));
+            setIpHistory(await invoke<IpAssignment[]>("get_ip_history", { machineId }));
+
             // Carrega security status
             const securityData = await invoke<SecurityStatus>("get_security_status", { machineId });
             setSecurity(securityData);
 
+            // Sockets abertos no último relatório
+            setConnections(await invoke<NetworkConnection[]>("get_network_connections", { machineId }));
+
             // User info (pode vir no machine ou separado)
             setUserInfo({
                 current_user: machine.current_user || "Sistema",
@@ -85,6 +154,10 @@ export default function NetworkTab({ machine }: Props) {
         }
     };
 
+    const visibleConnections = onlyListening
+        ? connections.filter((c) => c.state === "LISTEN" || c.protocol === "udp")
+        : connections;
+
     if (loading) {
         return (
             <div className="flex items-center justify-center py-12">
@@ -117,7 +190,7 @@ export default function NetworkTab({ machine }: Props) {
                         )}
                         <InfoRow
                             label="Atribuição"
-                            value={network?.dhcp_enabled ? "DHCP Dinâmico" : "IP Estático"}
+                            value={network && dhcpLabel(network.dhcp_enabled)}
                         />
                     </div>
                 </div>
@@ -140,7 +213,7 @@ export default function NetworkTab({ machine }: Props) {
                         />
                         <InfoRow
                             label="Domínio/Workgroup"
-                            value={network?.domain_name || "WORKGROUP"}
+                            value={network?.domain_name}
                         />
                         <InfoRow
                             label="Utilizador Actual"
@@ -175,7 +248,7 @@ export default function NetworkTab({ machine }: Props) {
                         />
                         <InfoRow
                             label="Velocidade"
-                            value={network?.connection_speed_mbps ? `${network.connection_speed_mbps} Mbps` : undefined}
+                            value={network?.connection_speed_mbps != null ? `${network.connection_speed_mbps} Mbps` : undefined}
                         />
                         <div className="h-px bg-white/10 my-3" />
                         {network?.wifi_ssid && (
@@ -238,6 +311,211 @@ export default function NetworkTab({ machine }: Props) {
                 </div>
             </div>
 
+            {/* Todas as interfaces */}
+            <div className="liquid-glass rounded-2xl p-6">
+                <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                    <Cable className="w-5 h-5 text-emerald-400" />
+                    Interfaces ({adapters.length})
+                </h3>
+                {adapters.length === 0 ? (
+                    <p className="text-slate-500 text-sm italic">Sem interfaces reportadas</p>
+                ) : (
+                    <div className="bg-white/5 border border-white/10 rounded-xl overflow-auto">
+                        <table className="w-full text-sm">
+                            <thead className="text-slate-400 text-left">
+                                <tr>
+                                    <th className="px-3 py-2">Interface</th>
+                                    <th className="px-3 py-2">Tipo</th>
+                                    <th className="px-3 py-2">Endereços</th>
+                                    <th className="px-3 py-2">Gateway</th>
+                                    <th className="px-3 py-2">DNS</th>
+                                    <th className="px-3 py-2">MAC</th>
+                                    <th className="px-3 py-2">Atribuição</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {adapters.map((a) => {
+                                    const kind = KIND_LABELS[a.kind] ?? KIND_LABELS.physical;
+                                    return (
+                                        <tr
+                                            key={a.name}
+                                            className={`border-t border-white/5 align-top ${a.is_up ? "text-white" : "text-slate-500"}`}
+                                        >
+                                            <td className="px-3 py-2">
+                                                <div className="font-medium">
+                                                    {a.name}
+                                                    {a.is_primary && (
+                                                        <span className="ml-2 text-xs text-emerald-400">principal</span>
+                                                    )}
+                                                </div>
+                                                {a.description !== a.name && (
+                                                    <div className="text-xs text-slate-500">{a.description}</div>
+                                                )}
+                                            </td>
+                                            <td className={`px-3 py-2 ${kind.color}`}>
+                                                {kind.label}
+                                                {a.is_wireless && <Wifi className="inline w-3 h-3 ml-1" />}
+                                            </td>
+                                            <td className="px-3 py-2 font-mono text-xs">
+                                                {a.addresses.length === 0 ? (
+                                                    <span className="text-slate-600">—</span>
+                                                ) : (
+                                                    a.addresses.map((addr) => (
+                                                        <div key={addr.address}>
+                                                            {addr.address}/{addr.prefix_length}
+                                                        </div>
+                                                    ))
+                                                )}
+                                            </td>
+                                            <td className="px-3 py-2 font-mono text-xs" title={a.gateway_mac ? `MAC do gateway: ${a.gateway_mac}` : undefined}>{a.gateways.join(", ") || "—"}</td>
+                                            <td className="px-3 py-2 font-mono text-xs">{a.dns_servers.join(", ") || "—"}</td>
+                                            <td className="px-3 py-2 font-mono text-xs">{a.mac_address || "—"}</td>
+                                            <td className="px-3 py-2 text-xs">{dhcpLabel(a.dhcp_enabled)}</td>
+                                        </tr>
+                                    );
+                                })}
+                            </tbody>
+                        </table>
+                    </div>
+                )}
+            </div>
+
+            {/* Tabela de rotas */}
+            {routes.length > 0 && (
+                <div className="liquid-glass rounded-2xl p-6">
+                    <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                        <Route className="w-5 h-5 text-emerald-400" />
+                        Rotas ({routes.length})
+                    </h3>
+                    <div className="bg-white/5 border border-white/10 rounded-xl overflow-auto max-h-80">
+                        <table className="w-full text-sm">
+                            <thead className="text-slate-400 text-left sticky top-0 bg-slate-900/90">
+                                <tr>
+                                    <th className="px-3 py-2">Destino</th>
+                                    <th className="px-3 py-2">Gateway</th>
+                                    <th className="px-3 py-2">Interface</th>
+                                    <th className="px-3 py-2">Métrica</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {routes.map((r, i) => (
+                                    <tr key={i} className="border-t border-white/5 text-white">
+                                        <td className="px-3 py-2 font-mono">
+                                            {r.prefix_length === 0 ? "predefinida" : `${r.destination}/${r.prefix_length}`}
+                                        </td>
+                                        <td className="px-3 py-2 font-mono">
+                                            {r.gateway || <span className="text-slate-600">ligada</span>}
+                                        </td>
+                                        <td className="px-3 py-2">{r.interface}</td>
+                                        <td className="px-3 py-2 text-slate-400">{r.metric}</td>
+                                    </tr>
+                                ))}
+                            </tbody>
+                        </table>
+                    </div>
+                </div>
+            )}
+
+            {/* Histórico de IP/MAC */}
+            {ipHistory.length > 0 && (
+                <div className="liquid-glass rounded-2xl p-6">
+                    <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                        <History className="w-5 h-5 text-emerald-400" />
+                        Histórico de Endereços
+                    </h3>
+                    <div className="bg-white/5 border border-white/10 rounded-xl overflow-auto max-h-80">
+                        <table className="w-full text-sm">
+                            <thead className="text-slate-400 text-left sticky top-0 bg-slate-900/90">
+                                <tr>
+                                    <th className="px-3 py-2">IP</th>
+                                    <th className="px-3 py-2">MAC</th>
+                                    <th className="px-3 py-2">Interface</th>
+                                    <th className="px-3 py-2">Desde</th>
+                                    <th className="px-3 py-2">Até</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {ipHistory.map((a) => (
+                                    <tr key={a.id} className="border-t border-white/5 text-white">
+                                        <td className="px-3 py-2 font-mono">{a.ip_address}</td>
+                                        <td className="px-3 py-2 font-mono text-slate-400">{a.mac_address || "—"}</td>
+                                        <td className="px-3 py-2">{a.adapter_name}</td>
+                                        <td className="px-3 py-2 text-slate-400">{new Date(a.first_seen).toLocaleString("pt-PT")}</td>
+                                        <td className="px-3 py-2 text-slate-400">
+                                            {a.ended_at ? (
+                                                new Date(a.ended_at).toLocaleString("pt-PT")
+                                            ) : (
+                                                <span className="text-emerald-400">actual</span>
+                                            )}
+                                        </td>
+                                    </tr>
+                                ))}
+                            </tbody>
+                        </table>
+                    </div>
+                </div>
+            )}
+
+            {/* Sockets abertos */}
+            <div className="liquid-glass rounded-2xl p-6">
+                <div className="flex items-center justify-between mb-4">
+                    <h3 className="text-lg font-bold text-white flex items-center gap-2">
+                        <Network className="w-5 h-5 text-emerald-400" />
+                        Ligações Abertas ({visibleConnections.length})
+                    </h3>
+                    <label className="flex items-center gap-2 text-sm text-slate-400 cursor-pointer">
+                        <input
+                            type="checkbox"
+                            checked={onlyListening}
+                            onChange={(e) => setOnlyListening(e.target.checked)}
+                        />
+                        Só portas à escuta
+                    </label>
+                </div>
+                {visibleConnections.length === 0 ? (
+                    <p className="text-slate-500 text-sm italic">Sem ligações reportadas</p>
+                ) : (
+                    <div className="bg-white/5 border border-white/10 rounded-xl overflow-auto max-h-96">
+                        <table className="w-full text-sm">
+                            <thead className="text-slate-400 text-left sticky top-0 bg-slate-900/90">
+                                <tr>
+                                    <th className="px-3 py-2">Proto</th>
+                                    <th className="px-3 py-2">Processo</th>
+                                    <th className="px-3 py-2">Local</th>
+                                    <th className="px-3 py-2">Remoto</th>
+                                    <th className="px-3 py-2">Estado</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {visibleConnections.map((c, i) => (
+                                    <tr key={i} className="border-t border-white/5 text-white">
+                                        <td className="px-3 py-2 uppercase text-slate-400">{c.protocol}</td>
+                                        <td className="px-3 py-2">
+                                            {c.process_name || "?"} <span className="text-slate-500">({c.pid})</span>
+                                        </td>
+                                        <td className="px-3 py-2 font-mono">{c.local_ip}:{c.local_port}</td>
+                                        <td className="px-3 py-2 font-mono">
+                                            {c.remote_ip ? (
+                                                <span title={c.remote_host}>
+                                                    {c.remote_host || c.remote_ip}:{c.remote_port}
+                                                </span>
+                                            ) : (
+                                                <span className="text-slate-600">—</span>
+                                            )}
+                                        </td>
+                                        <td className="px-3 py-2">
+                                            <span className={c.state === "LISTEN" ? "text-amber-400" : "text-slate-300"}>
+                                                {c.state}
+                                            </span>
+                                        </td>
+                                    </tr>
+                                ))}
+                            </tbody>
+                        </table>
+                    </div>
+                )}
+            </div>
+
             {/* Informação de Contexto */}
             {userInfo && (userInfo.department || userInfo.location) && (
                 <div className="liquid-glass rounded-2xl p-6">
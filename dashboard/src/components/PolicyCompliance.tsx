@@ -0,0 +1,112 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { Policy } from "../hooks/usePolicies";
+import type { Machine } from "../hooks/useMachines";
+
+interface PolicyEnforcementStatus {
+    machine_id: string;
+    policy_id: string;
+    revision: number;
+    status: "applied" | "failed";
+    error: string | null;
+    kill_count: number;
+    last_hit: string | null;
+    updated_at: string;
+}
+
+interface PolicyComplianceSummary {
+    policy_id: string;
+    targeted_machines: number;
+    applied: number;
+    failed: number;
+    pending: number;
+    total_kills: number;
+    last_hit: string | null;
+    results: PolicyEnforcementStatus[];
+}
+
+const formatDate = (value: string | null) => (value ? new Date(value).toLocaleString("pt-PT") : "—");
+
+export default function PolicyCompliance({ policy, machines, onClose }: {
+    policy: Policy;
+    machines: Machine[];
+    onClose: () => void;
+}) {
+    const [summary, setSummary] = useState<PolicyComplianceSummary | null>(null);
+
+    useEffect(() => {
+        invoke<PolicyComplianceSummary>("get_policy_compliance", { policyId: policy.id })
+            .then(setSummary)
+            .catch((err) => console.error("Erro ao carregar conformidade:", err));
+    }, [policy.id]);
+
+    const hostname = (machineId: string) =>
+        machines.find((m) => m.machine_id === machineId)?.hostname ?? machineId;
+
+    const cards = summary
+        ? [
+            { label: "Máquinas abrangidas", value: summary.targeted_machines, color: "text-white" },
+            { label: "Aplicada", value: summary.applied, color: "text-emerald-400" },
+            { label: "Falhou", value: summary.failed, color: "text-red-400" },
+            { label: "Por reportar", value: summary.pending, color: "text-amber-400" },
+            { label: "Processos terminados", value: summary.total_kills, color: "text-white" },
+        ]
+        : [];
+
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-4xl max-h-[85vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5">
+                    <div>
+                        <h2 className="text-xl font-bold text-white">Conformidade de '{policy.name}'</h2>
+                        {summary && <p className="text-xs text-slate-500">Última ocorrência: {formatDate(summary.last_hit)}</p>}
+                    </div>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto space-y-6">
+                    <div className="grid grid-cols-5 gap-3">
+                        {cards.map((c) => (
+                            <div key={c.label} className="bg-white/5 border border-white/10 rounded-xl p-4">
+                                <p className="text-xs text-slate-400">{c.label}</p>
+                                <p className={`text-2xl font-bold ${c.color}`}>{c.value}</p>
+                            </div>
+                        ))}
+                    </div>
+
+                    <table className="w-full text-left">
+                        <thead className="bg-white/5 text-slate-400 text-sm">
+                            <tr>
+                                <th className="p-3">Máquina</th><th className="p-3">Estado</th>
+                                <th className="p-3">Revisão</th><th className="p-3">Terminados</th>
+                                <th className="p-3">Última ocorrência</th><th className="p-3">Reportado</th>
+                            </tr>
+                        </thead>
+                        <tbody className="divide-y divide-white/5 text-sm">
+                            {summary?.results.map((r) => (
+                                <tr key={r.machine_id}>
+                                    <td className="p-3 text-white">{hostname(r.machine_id)}</td>
+                                    <td className="p-3">
+                                        <span className={`px-2 py-1 rounded-full text-xs ${r.status === "applied" ? "bg-emerald-500/20 text-emerald-400" : "bg-red-500/20 text-red-400"}`}>
+                                            {r.status === "applied" ? "Aplicada" : "Falhou"}
+                                        </span>
+                                        {r.error && <p className="text-xs text-red-400/80 mt-1">{r.error}</p>}
+                                    </td>
+                                    <td className="p-3 text-slate-400">
+                                        {r.revision}
+                                        {r.revision !== policy.revision && <span className="text-amber-400"> (actual {policy.revision})</span>}
+                                    </td>
+                                    <td className="p-3 text-slate-400">{r.kill_count}</td>
+                                    <td className="p-3 text-slate-400">{formatDate(r.last_hit)}</td>
+                                    <td className="p-3 text-slate-500">{formatDate(r.updated_at)}</td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                    {summary && summary.results.length === 0 && (
+                        <p className="text-center text-slate-500 text-sm">Nenhuma máquina reportou esta política ainda.</p>
+                    )}
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,41 @@
+import { AlertTriangle } from "lucide-react";
+import type { PolicyConflict } from "../hooks/usePolicies";
+
+// Conflitos detectados ao criar uma política: quem prevalece em cada um e porquê
+export default function PolicyConflicts({ policyId, policyName, conflicts, onClose }: {
+    policyId: string;
+    policyName: string;
+    conflicts: PolicyConflict[];
+    onClose: () => void;
+}) {
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-3xl max-h-[80vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5">
+                    <h2 className="text-xl font-bold text-white flex items-center gap-2">
+                        <AlertTriangle className="w-5 h-5 text-amber-400" />
+                        '{policyName}' criada com {conflicts.length} conflito(s)
+                    </h2>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto divide-y divide-white/5">
+                    {conflicts.map((c) => {
+                        const newWins = c.winner_id === policyId;
+                        return (
+                            <div key={c.policy_id} className="py-3">
+                                <p className="text-white text-sm font-medium">
+                                    {c.policy_name}
+                                    <span className="ml-2 px-2 py-0.5 rounded-full text-xs bg-white/5 text-slate-400">{c.action}</span>
+                                    <span className={`ml-2 px-2 py-0.5 rounded-full text-xs ${newWins ? "bg-emerald-500/20 text-emerald-400" : "bg-amber-500/20 text-amber-400"}`}>
+                                        {newWins ? "a nova política prevalece" : "a política existente prevalece"}
+                                    </span>
+                                </p>
+                                <p className="text-xs text-slate-500">{c.reason}</p>
+                            </div>
+                        );
+                    })}
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,118 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import toast from "react-hot-toast";
+import type { Policy } from "../hooks/usePolicies";
+
+// Edita uma política existente; com um grupo canary a nova revisão só chega
+// às máquinas com essa tag até ser promovida no histórico
+export default function PolicyEditor({ policy, onClose, onSaved }: {
+    policy: Policy;
+    onClose: () => void;
+    onSaved: () => void;
+}) {
+    const [form, setForm] = useState({
+        name: policy.name,
+        description: policy.description,
+        priority: policy.priority,
+        target: policy.target,
+        action: policy.action,
+        configJson: policy.config_json,
+        reason: policy.reason,
+        enabled: policy.enabled,
+        canaryGroup: "",
+    });
+
+    const save = async () => {
+        try {
+            JSON.parse(form.configJson);
+        } catch {
+            toast.error("Configuração JSON inválida");
+            return;
+        }
+
+        const canaryGroup = form.canaryGroup.trim() || null;
+        try {
+            const revision = await invoke<number>("update_policy", {
+                id: policy.id,
+                update: {
+                    name: form.name,
+                    description: form.description,
+                    priority: Number(form.priority) || 1,
+                    target: form.target,
+                    action: form.action,
+                    config_json: form.configJson,
+                    reason: form.reason,
+                    enabled: form.enabled,
+                    canary_group: canaryGroup,
+                },
+            });
+            toast.success(canaryGroup
+                ? `Revisão ${revision} em canary no grupo '${canaryGroup}'`
+                : `Revisão ${revision} aplicada a todas as máquinas`);
+            onSaved();
+            onClose();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    const input = "w-full bg-black border border-white/10 rounded-lg px-3 py-2 text-white text-sm";
+
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-2xl max-h-[90vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5">
+                    <h2 className="text-xl font-bold text-white">Editar '{policy.name}' (revisão {policy.revision})</h2>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto space-y-4">
+                    <div className="grid grid-cols-2 gap-4">
+                        <label className="block text-sm text-slate-400">Nome
+                            <input value={form.name} onChange={(e) => setForm({ ...form, name: e.target.value })} className={input} />
+                        </label>
+                        <label className="block text-sm text-slate-400">Prioridade
+                            <input type="number" value={form.priority} onChange={(e) => setForm({ ...form, priority: parseInt(e.target.value) })} className={input} />
+                        </label>
+                        <label className="block text-sm text-slate-400">Alvo
+                            <input value={form.target} onChange={(e) => setForm({ ...form, target: e.target.value })} className={`${input} font-mono`} />
+                        </label>
+                        <label className="block text-sm text-slate-400">Ação
+                            <select value={form.action} onChange={(e) => setForm({ ...form, action: e.target.value })} className={input}>
+                                <option value="block">block</option>
+                                <option value="allow">allow</option>
+                                <option value="alert">alert</option>
+                            </select>
+                        </label>
+                    </div>
+                    <label className="block text-sm text-slate-400">Descrição
+                        <input value={form.description} onChange={(e) => setForm({ ...form, description: e.target.value })} className={input} />
+                    </label>
+                    <label className="block text-sm text-slate-400">Configuração (JSON)
+                        <textarea rows={6} value={form.configJson} onChange={(e) => setForm({ ...form, configJson: e.target.value })} className={`${input} font-mono`} />
+                    </label>
+                    <label className="block text-sm text-slate-400">Motivo
+                        <input value={form.reason} onChange={(e) => setForm({ ...form, reason: e.target.value })} className={input} />
+                    </label>
+                    <label className="flex items-center gap-2 text-sm text-slate-300">
+                        <input type="checkbox" checked={form.enabled} onChange={(e) => setForm({ ...form, enabled: e.target.checked })} />
+                        Política activa
+                    </label>
+                    <label className="block text-sm text-slate-400">Grupo canary (tag das máquinas)
+                        <input
+                            value={form.canaryGroup}
+                            onChange={(e) => setForm({ ...form, canaryGroup: e.target.value })}
+                            placeholder="Vazio: aplicar já a todas as máquinas"
+                            className={input}
+                        />
+                    </label>
+                </div>
+                <div className="p-6 border-t border-white/10 flex justify-end gap-3 bg-white/5">
+                    <button onClick={onClose} className="px-6 py-2.5 bg-transparent border border-white/10 text-white rounded-lg hover:bg-white/5 transition-colors">Cancelar</button>
+                    <button onClick={save} className="px-6 py-2.5 bg-emerald-600 text-white rounded-lg hover:bg-emerald-700 transition-colors">
+                        {form.canaryGroup.trim() ? "Iniciar canary" : "Guardar"}
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
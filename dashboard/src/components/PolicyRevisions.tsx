@@ -0,0 +1,108 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import toast from "react-hot-toast";
+import { RotateCcw, Rocket } from "lucide-react";
+import type { Policy } from "../hooks/usePolicies";
+
+interface PolicyRevision {
+    id: number;
+    policy_id: string;
+    revision: number;
+    policy: Policy | null;
+    state: string;
+    canary_group: string | null;
+    note: string;
+    created_by: string;
+    created_at: string;
+}
+
+const STATE_STYLES: Record<string, string> = {
+    active: "bg-emerald-500/20 text-emerald-400",
+    canary: "bg-amber-500/20 text-amber-400",
+    superseded: "bg-white/5 text-slate-400",
+    rolled_back: "bg-red-500/20 text-red-400",
+    deleted: "bg-red-500/20 text-red-400",
+};
+
+export default function PolicyRevisions({ policy, onClose, onChanged }: {
+    policy: Policy;
+    onClose: () => void;
+    onChanged: () => void;
+}) {
+    const [revisions, setRevisions] = useState<PolicyRevision[]>([]);
+
+    const load = () => {
+        invoke<PolicyRevision[]>("list_policy_revisions", { id: policy.id })
+            .then(setRevisions)
+            .catch((err) => console.error("Erro ao carregar revisões:", err));
+    };
+
+    useEffect(() => {
+        load();
+    }, [policy.id]);
+
+    const rollback = async (revision: number) => {
+        try {
+            const created = await invoke<number>("rollback_policy", { id: policy.id, revision });
+            toast.success(`Revisão ${revision} reposta (nova revisão ${created})`);
+            load();
+            onChanged();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    const promote = async () => {
+        try {
+            const promoted = await invoke<number>("promote_policy_revision", { id: policy.id });
+            toast.success(`Revisão ${promoted} aplicada a todas as máquinas`);
+            load();
+            onChanged();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    return (
+        <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
+            <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-3xl max-h-[80vh] flex flex-col shadow-2xl">
+                <div className="p-6 border-b border-white/10 flex justify-between items-center bg-white/5">
+                    <h2 className="text-xl font-bold text-white">Histórico de '{policy.name}'</h2>
+                    <button onClick={onClose} className="text-slate-400 hover:text-white">✕</button>
+                </div>
+                <div className="p-6 overflow-y-auto divide-y divide-white/5">
+                    {revisions.map((r) => (
+                        <div key={r.id} className="py-3 flex items-center justify-between gap-4">
+                            <div className="min-w-0">
+                                <p className="text-white text-sm font-medium">
+                                    Revisão {r.revision}
+                                    <span className={`ml-2 px-2 py-0.5 rounded-full text-xs ${STATE_STYLES[r.state] ?? ""}`}>
+                                        {r.state}{r.canary_group ? ` · ${r.canary_group}` : ""}
+                                    </span>
+                                </p>
+                                <p className="text-xs text-slate-500">
+                                    {r.note} · {r.created_by} · {new Date(r.created_at).toLocaleString("pt-PT")}
+                                </p>
+                                {r.policy && (
+                                    <p className="text-xs text-slate-400 font-mono truncate">
+                                        {r.policy.action} {r.policy.target} (prioridade {r.policy.priority})
+                                    </p>
+                                )}
+                            </div>
+                            {r.state === "canary" && (
+                                <button onClick={promote} className="flex items-center gap-2 px-3 py-1.5 bg-amber-600 text-white rounded-lg hover:bg-amber-700 transition-colors text-sm">
+                                    <Rocket className="w-4 h-4" /> Promover
+                                </button>
+                            )}
+                            {r.policy && r.state !== "active" && r.state !== "canary" && r.state !== "deleted" && (
+                                <button onClick={() => rollback(r.revision)} className="flex items-center gap-2 px-3 py-1.5 border border-white/10 text-white rounded-lg hover:bg-white/5 transition-colors text-sm">
+                                    <RotateCcw className="w-4 h-4" /> Repor
+                                </button>
+                            )}
+                        </div>
+                    ))}
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -1,32 +1,86 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Power, RefreshCw, Lock, Terminal, Ban, LogOut, Moon, Download } from "lucide-react";
+import { RefreshCw, Terminal, Camera, X, RotateCcw, Trash2, FileCode, KeyRound } from "lucide-react";
 import toast from "react-hot-toast";
+import ApprovedScriptsModal, { type ApprovedScript } from "./ApprovedScriptsModal";
 
 interface Props {
     machineId: string;
     machineName: string;
 }
 
-const actions = [
-    { id: "restart", label: "Reiniciar", icon: RefreshCw, color: "blue" },
-    { id: "shutdown", label: "Desligar", icon: Power, color: "red" },
-    { id: "lock", label: "Bloquear", icon: Lock, color: "amber" },
-    { id: "logoff", label: "Logout", icon: LogOut, color: "gray" },
-    { id: "sleep", label: "Suspender", icon: Moon, color: "purple" },
-    { id: "update", label: "Windows Update", icon: Download, color: "emerald" },
-];
+interface AgentCommand {
+    id: string;
+    command_type: string;
+    payload: string;
+    status: string; // pending | delivered | succeeded | failed | expired | cancelled
+    created_at: string;
+    completed_at?: string;
+    output?: string;
+    error?: string;
+}
+
+const STATUS_COLORS: Record<string, string> = {
+    pending: "text-slate-400",
+    delivered: "text-blue-400",
+    succeeded: "text-emerald-400",
+    failed: "text-red-400",
+    expired: "text-amber-400",
+    cancelled: "text-gray-500",
+};
+
+const STATUS_LABELS: Record<string, string> = {
+    pending: "Pendente",
+    delivered: "Entregue",
+    succeeded: "Concluído",
+    failed: "Falhou",
+    expired: "Expirado",
+    cancelled: "Cancelado",
+};
 
 export default function RemoteActionsPanel({ machineId, machineName }: Props) {
     const [loading, setLoading] = useState(false);
+    const [commands, setCommands] = useState<AgentCommand[]>([]);
+    const [scripts, setScripts] = useState<ApprovedScript[]>([]);
+    const [scriptId, setScriptId] = useState("");
+    const [showScripts, setShowScripts] = useState(false);
+
+    const loadScripts = async () => {
+        try {
+            const list = await invoke<ApprovedScript[]>("list_approved_scripts");
+            setScripts(list);
+            setScriptId((current) => current || list[0]?.id || "");
+        } catch (err) {
+            console.error("Erro ao carregar scripts aprovados:", err);
+        }
+    };
+
+    const loadCommands = async () => {
+        try {
+            setCommands(await invoke<AgentCommand[]>("list_agent_commands", { machineId, limit: 10 }));
+        } catch (err) {
+            console.error("Erro ao carregar comandos:", err);
+        }
+    };
+
+    useEffect(() => {
+        loadScripts();
+    }, []);
 
-    const executeAction = async (action: string, displayName: string) => {
+    useEffect(() => {
+        loadCommands();
+        const timer = setInterval(loadCommands, 10000);
+        return () => clearInterval(timer);
+    }, [machineId]);
+
+    const enqueue = async (commandType: string, displayName: string, payload?: Record<string, unknown>) => {
         if (!confirm(`${displayName} em ${machineName}?`)) return;
 
         try {
             setLoading(true);
-            await invoke("execute_remote_action", { machineId, action });
-            toast.success(`${displayName} executado com sucesso!`);
+            await invoke("enqueue_agent_command", { machineId, commandType, payload: payload ?? null });
+            toast.success(`${displayName}: comando enviado para a fila`);
+            loadCommands();
         } catch (err) {
             toast.error(`Erro: ${err}`);
         } finally {
@@ -34,6 +88,41 @@ export default function RemoteActionsPanel({ machineId, machineName }: Props) {
         }
     };
 
+    const restartService = () => {
+        const serviceName = prompt("Nome do serviço a reiniciar:")?.trim();
+        if (serviceName) enqueue("restart_service", `Reiniciar o serviço ${serviceName}`, { service_name: serviceName });
+    };
+
+    const uninstallApp = () => {
+        const name = prompt("Nome da aplicação (como aparece em Programas e Funcionalidades):")?.trim();
+        if (name) enqueue("uninstall_app", `Desinstalar ${name}`, { name });
+    };
+
+    const runScript = () => {
+        const script = scripts.find((s) => s.id === scriptId);
+        if (script) enqueue("run_script", `Executar o script '${script.name}'`, { script_id: script.id });
+    };
+
+    const resetCredential = async () => {
+        if (!confirm(`Revogar a credencial do agente de ${machineName}? O próximo relatório da máquina recebe uma nova.`)) return;
+
+        try {
+            await invoke("reset_machine_credential", { machineId });
+            toast.success("Credencial revogada");
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    const cancel = async (commandId: string) => {
+        try {
+            await invoke("cancel_agent_command", { commandId });
+            loadCommands();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
     return (
         <div className="liquid-glass rounded-2xl p-6">
             <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
@@ -43,41 +132,108 @@ export default function RemoteActionsPanel({ machineId, machineName }: Props) {
 
             <div className="grid grid-cols-2 gap-3">
                 <button
-                    onClick={() => executeAction("restart", "Reiniciar")}
+                    onClick={() => enqueue("collect_now", "Recolher agora")}
                     disabled={loading}
-                    className="flex items-center justify-center gap-2 px-4 py-3 bg-blue-500/10 hover:bg-blue-500/20 border border-blue-500/30 rounded-xl text-blue-400 font-medium transition-colors disabled:opacity-50"
+                    className="flex items-center justify-center gap-2 px-4 py-3 bg-emerald-500/10 hover:bg-emerald-500/20 border border-emerald-500/30 rounded-xl text-emerald-400 font-medium transition-colors disabled:opacity-50"
                 >
                     <RefreshCw className="w-4 h-4" />
-                    Reiniciar
+                    Recolher agora
                 </button>
 
                 <button
-                    onClick={() => executeAction("shutdown", "Desligar")}
+                    onClick={() => enqueue("screenshot", "Captura de ecrã")}
                     disabled={loading}
-                    className="flex items-center justify-center gap-2 px-4 py-3 bg-red-500/10 hover:bg-red-500/20 border border-red-500/30 rounded-xl text-red-400 font-medium transition-colors disabled:opacity-50"
+                    className="flex items-center justify-center gap-2 px-4 py-3 bg-blue-500/10 hover:bg-blue-500/20 border border-blue-500/30 rounded-xl text-blue-400 font-medium transition-colors disabled:opacity-50"
                 >
-                    <Power className="w-4 h-4" />
-                    Desligar
+                    <Camera className="w-4 h-4" />
+                    Captura de ecrã
                 </button>
 
                 <button
-                    onClick={() => executeAction("lock", "Bloquear ecrã")}
+                    onClick={restartService}
                     disabled={loading}
                     className="flex items-center justify-center gap-2 px-4 py-3 bg-amber-500/10 hover:bg-amber-500/20 border border-amber-500/30 rounded-xl text-amber-400 font-medium transition-colors disabled:opacity-50"
                 >
-                    <Lock className="w-4 h-4" />
-                    Bloquear
+                    <RotateCcw className="w-4 h-4" />
+                    Reiniciar serviço
                 </button>
 
                 <button
-                    onClick={() => executeAction("force_update", "Forçar actualização")}
+                    onClick={uninstallApp}
                     disabled={loading}
-                    className="flex items-center justify-center gap-2 px-4 py-3 bg-emerald-500/10 hover:bg-emerald-500/20 border border-emerald-500/30 rounded-xl text-emerald-400 font-medium transition-colors disabled:opacity-50"
+                    className="flex items-center justify-center gap-2 px-4 py-3 bg-red-500/10 hover:bg-red-500/20 border border-red-500/30 rounded-xl text-red-400 font-medium transition-colors disabled:opacity-50"
                 >
-                    <RefreshCw className="w-4 h-4" />
-                    Actualizar
+                    <Trash2 className="w-4 h-4" />
+                    Desinstalar aplicação
+                </button>
+            </div>
+
+            <div className="mt-3 flex items-center gap-2">
+                <select
+                    value={scriptId}
+                    onChange={(e) => setScriptId(e.target.value)}
+                    className="flex-1 p-2.5 bg-black/50 border border-white/10 rounded-xl text-white text-sm outline-none"
+                >
+                    {scripts.length === 0 && <option value="">Sem scripts aprovados</option>}
+                    {scripts.map((s) => (
+                        <option key={s.id} value={s.id}>{s.name} ({s.interpreter})</option>
+                    ))}
+                </select>
+                <button
+                    onClick={runScript}
+                    disabled={loading || !scriptId}
+                    className="flex items-center gap-2 px-4 py-2.5 bg-purple-500/10 hover:bg-purple-500/20 border border-purple-500/30 rounded-xl text-purple-400 text-sm font-medium transition-colors disabled:opacity-50"
+                >
+                    <Terminal className="w-4 h-4" />
+                    Executar
+                </button>
+                <button
+                    onClick={() => setShowScripts(true)}
+                    className="p-2.5 bg-white/5 hover:bg-white/10 border border-white/10 rounded-xl text-slate-400 hover:text-white transition-colors"
+                    title="Scripts aprovados"
+                >
+                    <FileCode className="w-4 h-4" />
+                </button>
+                <button
+                    onClick={resetCredential}
+                    className="p-2.5 bg-white/5 hover:bg-white/10 border border-white/10 rounded-xl text-slate-400 hover:text-white transition-colors"
+                    title="Revogar credencial do agente"
+                >
+                    <KeyRound className="w-4 h-4" />
                 </button>
             </div>
+
+            {commands.length > 0 && (
+                <div className="mt-6 space-y-2">
+                    {commands.map((cmd) => (
+                        <div key={cmd.id} className="flex items-center justify-between bg-white/5 border border-white/10 rounded-xl px-4 py-2 text-sm">
+                            <div>
+                                <span className="text-white font-mono">{cmd.command_type}</span>
+                                <span className="text-slate-500 ml-3">{new Date(cmd.created_at).toLocaleString('pt-PT')}</span>
+                                {cmd.error && <p className="text-red-400/80 text-xs mt-1">{cmd.error}</p>}
+                            </div>
+                            <div className="flex items-center gap-3">
+                                <span className={STATUS_COLORS[cmd.status] ?? "text-slate-400"}>
+                                    {STATUS_LABELS[cmd.status] ?? cmd.status}
+                                </span>
+                                {cmd.status === "pending" && (
+                                    <button onClick={() => cancel(cmd.id)} className="text-slate-500 hover:text-red-400" title="Cancelar">
+                                        <X className="w-4 h-4" />
+                                    </button>
+                                )}
+                            </div>
+                        </div>
+                    ))}
+                </div>
+            )}
+
+            {showScripts && (
+                <ApprovedScriptsModal
+                    scripts={scripts}
+                    onClose={() => setShowScripts(false)}
+                    onChanged={loadScripts}
+                />
+            )}
         </div>
     );
-}
\ No newline at end of file
+}
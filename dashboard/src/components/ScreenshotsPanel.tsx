@@ -0,0 +1,103 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Camera, Eye, Trash2, X } from "lucide-react";
+import toast from "react-hot-toast";
+
+interface Props {
+    machineId: string;
+}
+
+interface Screenshot {
+    id: string;
+    machine_id: string;
+    command_id?: string;
+    captured_at: string;
+    received_at: string;
+    width: number;
+    height: number;
+    size_bytes: number;
+}
+
+export default function ScreenshotsPanel({ machineId }: Props) {
+    const [screenshots, setScreenshots] = useState<Screenshot[]>([]);
+    const [viewing, setViewing] = useState<{ shot: Screenshot; src: string } | null>(null);
+
+    const load = async () => {
+        try {
+            setScreenshots(await invoke<Screenshot[]>("list_screenshots", { machineId }));
+        } catch (err) {
+            console.error("Erro ao carregar capturas:", err);
+        }
+    };
+
+    useEffect(() => {
+        load();
+        const timer = setInterval(load, 15000);
+        return () => clearInterval(timer);
+    }, [machineId]);
+
+    // A imagem só é pedida ao abrir: cada visualização fica registada na auditoria
+    const view = async (shot: Screenshot) => {
+        try {
+            const src = await invoke<string>("get_screenshot_image", { id: shot.id });
+            setViewing({ shot, src });
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    const remove = async (id: string) => {
+        if (!confirm("Apagar esta captura?")) return;
+        try {
+            await invoke("delete_screenshot", { id });
+            if (viewing?.shot.id === id) setViewing(null);
+            load();
+        } catch (err) {
+            toast.error(`Erro: ${err}`);
+        }
+    };
+
+    return (
+        <div className="liquid-glass rounded-2xl p-6">
+            <h3 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                <Camera className="w-5 h-5 text-blue-400" />
+                Capturas de Ecrã
+            </h3>
+
+            {screenshots.length === 0 ? (
+                <p className="text-slate-500 text-sm">Sem capturas para esta máquina.</p>
+            ) : (
+                <div className="space-y-2">
+                    {screenshots.map((shot) => (
+                        <div key={shot.id} className="flex items-center justify-between bg-white/5 border border-white/10 rounded-xl px-4 py-2 text-sm">
+                            <div>
+                                <span className="text-white">{new Date(shot.captured_at).toLocaleString('pt-PT')}</span>
+                                <span className="text-slate-500 ml-3">{shot.width}×{shot.height} · {Math.round(shot.size_bytes / 1024)} KB</span>
+                            </div>
+                            <div className="flex items-center gap-3">
+                                <button onClick={() => view(shot)} className="text-slate-400 hover:text-blue-400" title="Ver">
+                                    <Eye className="w-4 h-4" />
+                                </button>
+                                <button onClick={() => remove(shot.id)} className="text-slate-500 hover:text-red-400" title="Apagar">
+                                    <Trash2 className="w-4 h-4" />
+                                </button>
+                            </div>
+                        </div>
+                    ))}
+                </div>
+            )}
+
+            {viewing && (
+                <div className="fixed inset-0 bg-black/80 flex items-center justify-center z-50 p-8" onClick={() => setViewing(null)}>
+                    <div className="relative max-w-full max-h-full" onClick={(e) => e.stopPropagation()}>
+                        <button onClick={() => setViewing(null)} className="absolute -top-10 right-0 text-slate-300 hover:text-white">
+                            <X className="w-6 h-6" />
+                        </button>
+                        <img src={viewing.src} alt="Captura de ecrã" className="max-w-full max-h-[85vh] rounded-xl border border-white/10" />
+                        <p className="text-slate-400 text-sm mt-2">{new Date(viewing.shot.captured_at).toLocaleString('pt-PT')}</p>
+                    </div>
+                </div>
+            )}
+        </div>
+    );
+}
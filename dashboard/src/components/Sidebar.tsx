@@ -1,7 +1,7 @@
 import { Link, useLocation } from "react-router-dom";
 import {
     LayoutDashboard, MonitorSmartphone, Clock, ShieldAlert,
-    Lock, Scale, Webhook, FileText, Settings, ChevronRight, ShieldCheck
+    Lock, Scale, Webhook, FileText, Settings, ChevronRight, ShieldCheck, Network
 } from "lucide-react";
 import { useState } from "react";
 
@@ -14,6 +14,7 @@ export default function Sidebar() {
         { to: "/", icon: LayoutDashboard, label: "Dashboard", badge: null },
         { to: "/machines", icon: MonitorSmartphone, label: "Máquinas", badge: null },
         { to: "/screen-time", icon: Clock, label: "Screen Time", badge: null },
+        { to: "/network", icon: Network, label: "Mapa de Rede", badge: null },
         { to: "/vulnerabilities", icon: ShieldAlert, label: "Vulnerabilities", badge: null },
         { to: "/policies", icon: Lock, label: "Políticas", badge: null },
         { to: "/compliance", icon: Scale, label: "Compliance", badge: null },
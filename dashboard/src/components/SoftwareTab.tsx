@@ -1,6 +1,6 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Package, Ban, Search, Filter } from "lucide-react";
+import { Package, Ban, Search, Filter, Cog } from "lucide-react";
 import toast from "react-hot-toast";
 
 interface SoftwareEntry {
@@ -10,12 +10,26 @@ interface SoftwareEntry {
     install_date: string;
 }
 
+interface ServiceEntry {
+    name: string;
+    display_name: string;
+    state: string;
+    start_mode: string;
+}
+
+const STATE_COLORS: Record<string, string> = {
+    running: "text-emerald-400",
+    failed: "text-red-400",
+    stopped: "text-gray-500",
+};
+
 interface Props {
     machineId: string;
 }
 
 export default function SoftwareTab({ machineId }: Props) {
     const [software, setSoftware] = useState<SoftwareEntry[]>([]);
+    const [services, setServices] = useState<ServiceEntry[]>([]);
     const [loading, setLoading] = useState(true);
     const [search, setSearch] = useState(""); // ✅ NOVO
 
@@ -25,8 +39,12 @@ export default function SoftwareTab({ machineId }: Props) {
 
     const loadSoftware = async () => {
         try {
-            const result = await invoke<SoftwareEntry[]>("get_software", { machineId });
+            const [result, serviceList] = await Promise.all([
+                invoke<SoftwareEntry[]>("get_software", { machineId }),
+                invoke<ServiceEntry[]>("get_services", { machineId }),
+            ]);
             setSoftware(result);
+            setServices(serviceList);
         } catch (err) {
             console.error(err);
         } finally {
@@ -56,6 +74,11 @@ export default function SoftwareTab({ machineId }: Props) {
         (sw.version || '').toLowerCase().includes(search.toLowerCase())
     );
 
+    const filteredServices = services.filter(s =>
+        s.name.toLowerCase().includes(search.toLowerCase()) ||
+        s.display_name.toLowerCase().includes(search.toLowerCase())
+    );
+
     if (loading) {
         return (
             <div className="flex items-center justify-center py-12">
@@ -147,6 +170,39 @@ export default function SoftwareTab({ machineId }: Props) {
                     </tbody>
                 </table>
             </div>
+
+            {/* Serviços (Windows) / unidades systemd (Linux) */}
+            {services.length > 0 && (
+                <div className="liquid-glass rounded-xl overflow-hidden">
+                    <div className="flex items-center gap-2 p-4 text-sm text-gray-400 border-b border-white/10">
+                        <Cog className="w-4 h-4" />
+                        <span>{filteredServices.length} de {services.length} serviços</span>
+                    </div>
+                    <table className="w-full">
+                        <thead className="bg-black/30 border-b border-white/10">
+                            <tr>
+                                <th className="text-left p-4 text-sm font-semibold text-gray-400">Serviço</th>
+                                <th className="text-left p-4 text-sm font-semibold text-gray-400">Estado</th>
+                                <th className="text-left p-4 text-sm font-semibold text-gray-400">Arranque</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {filteredServices.map((s) => (
+                                <tr key={s.name} className="border-b border-white/5 hover:bg-white/5 transition-colors">
+                                    <td className="p-4">
+                                        <span className="font-medium text-white">{s.display_name}</span>
+                                        {s.display_name !== s.name && (
+                                            <span className="text-gray-500 font-mono text-xs ml-2">{s.name}</span>
+                                        )}
+                                    </td>
+                                    <td className={`p-4 text-sm ${STATE_COLORS[s.state] ?? "text-gray-400"}`}>{s.state}</td>
+                                    <td className="p-4 text-gray-400 text-sm">{s.start_mode}</td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                </div>
+            )}
         </div>
     );
 }
\ No newline at end of file
@@ -68,6 +68,6 @@ export default function TopBar() {
 
 function getPageTitle() {
     const path = window.location.pathname;
-    const titles: Record<string, string> = { '/': 'Dashboard', '/machines': 'Máquinas', '/compliance': 'Compliance', '/webhooks': 'Webhooks', '/settings': 'Configurações', '/audit': 'Auditoria', '/policies': 'Políticas', '/vulnerabilities': 'Vulnerabilidades', '/screen-time': 'Screen Time' };
+    const titles: Record<string, string> = { '/': 'Dashboard', '/machines': 'Máquinas', '/compliance': 'Compliance', '/webhooks': 'Webhooks', '/settings': 'Configurações', '/audit': 'Auditoria', '/policies': 'Políticas', '/vulnerabilities': 'Vulnerabilidades', '/screen-time': 'Screen Time', '/network': 'Mapa de Rede' };
     return titles[path] || 'AssetScan';
 }
\ No newline at end of file
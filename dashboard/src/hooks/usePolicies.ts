@@ -14,6 +14,20 @@ export interface Policy {
     reason: string;
     created_at: string;
     enabled: boolean;
+    revision: number;
+}
+
+export interface PolicyConflict {
+    policy_id: string;
+    policy_name: string;
+    action: string;
+    winner_id: string;
+    reason: string;
+}
+
+export interface PolicyCreationResult {
+    id: string;
+    conflicts: PolicyConflict[];
 }
 
 export function usePolicies(machineId?: string) {
@@ -36,7 +50,7 @@ export function usePolicies(machineId?: string) {
 
     const createPolicy = async (policyData: any) => {
         try {
-            await invoke("create_policy", {
+            const result = await invoke<PolicyCreationResult>("create_policy", {
                 machineId: policyData.machineId === "all" ? null : policyData.machineId,
                 name: policyData.name || "Nova Política",
                 description: policyData.description || "",
@@ -48,6 +62,7 @@ export function usePolicies(machineId?: string) {
                 reason: policyData.reason || "Criado via Dashboard Enterprise"
             });
             await loadPolicies();
+            return result;
         } catch (error) {
             console.error("Failed to create policy", error);
             throw error;
@@ -63,5 +78,5 @@ export function usePolicies(machineId?: string) {
         }
     };
 
-    return { policies, loading, createPolicy, deletePolicy };
+    return { policies, loading, createPolicy, deletePolicy, refresh: loadPolicies };
 }
\ No newline at end of file
@@ -7,6 +7,8 @@ import { useMachines } from "../hooks/useMachines";
 import HardwareTab from "../components/HardwareTab";
 import SoftwareTab from "../components/SoftwareTab";
 import NetworkTab from "../components/NetworkTab"; // NOVA ABA
+import RemoteActionsPanel from "../components/RemoteActionsPanel";
+import ScreenshotsPanel from "../components/ScreenshotsPanel";
 
 interface ProcessInfo { id: number; pid: number; name: string; exe_path: string; memory_mb: number; cpu_percent: number; }
 
@@ -62,6 +64,9 @@ export default function MachineDetail() {
                 </div>
             </div>
 
+            <RemoteActionsPanel machineId={actualId} machineName={machine.custom_name || machine.hostname} />
+            <ScreenshotsPanel machineId={actualId} />
+
             {/* Abas Liquid Glass */}
             <div className="flex gap-2 border-b border-white/10 pb-px overflow-x-auto">
                 <button onClick={() => setTab("hardware")} className={`px-6 py-3 font-medium transition-all rounded-t-xl flex items-center gap-2 whitespace-nowrap ${tab === 'hardware' ? 'bg-white/5 border-t border-l border-r border-white/10 text-emerald-400' : 'text-slate-400 hover:text-white hover:bg-white/5'}`}>
@@ -5,9 +5,10 @@ import { exportMachinesPDF } from "../utils/exportPDF";
 import MachineCard from "../components/MachineCard";
 import ViewModeToggle, { ViewMode } from "../components/ViewModeToggle";
 import AdvancedFilter from "../components/AdvancedFilter";
+import IdentityConflicts from "../components/IdentityConflicts";
 
 export default function Machines() {
-    const { machines, loading } = useMachines();
+    const { machines, loading, refresh } = useMachines();
     const [viewMode, setViewMode] = useState<ViewMode>('grid');
     const [search, setSearch] = useState("");
     const [statusFilter, setStatusFilter] = useState<'all' | 'online' | 'offline'>('all');
@@ -51,6 +52,8 @@ export default function Machines() {
                 </div>
             </div>
 
+            <IdentityConflicts onMerged={refresh} />
+
             {/* Filters Bar */}
             <div className="flex items-center gap-4">
                 {/* Search */}
@@ -0,0 +1,344 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Link } from "react-router-dom";
+import { Network, AlertTriangle, History, Search, Shuffle, Radar } from "lucide-react";
+
+interface NetworkHost {
+    machine_id: string;
+    hostname: string;
+    adapter_name: string;
+    kind: string;
+    ip_address: string;
+    mac_address: string | null;
+    is_online: boolean;
+    unexpected_ip_change: boolean;
+}
+
+interface NetworkSubnet {
+    subnet: string;
+    gateway: string | null;
+    hosts: NetworkHost[];
+}
+
+interface NetworkConflict {
+    conflict_type: "duplicate_ip" | "duplicate_mac";
+    value: string;
+    gateway: string | null;
+    hosts: NetworkHost[];
+}
+
+interface IpChangeEvent {
+    id: number;
+    machine_id: string;
+    hostname: string | null;
+    adapter_name: string;
+    old_ip: string;
+    new_ip: string;
+    mac_address: string | null;
+    reason: string;
+    unexpected: boolean;
+    detected_at: string;
+}
+
+interface IpAssignment {
+    id: number;
+    machine_id: string;
+    hostname: string | null;
+    adapter_name: string;
+    ip_address: string;
+    mac_address: string | null;
+    first_seen: string;
+    last_seen: string;
+    ended_at: string | null;
+}
+
+interface DiscoveredDevice {
+    id: number;
+    mac_address: string;
+    ip_address: string;
+    vendor: string | null;
+    random_mac: boolean;
+    interface: string;
+    reported_by: string;
+    reported_by_hostname: string | null;
+    first_seen: string;
+    last_seen: string;
+    managed_machine_id: string | null;
+    managed_hostname: string | null;
+}
+
+const REASON_LABELS: Record<string, string> = {
+    static_ip_changed: "IP fixo alterado",
+    frequent_changes: "Muitas mudanças em 24 h",
+    address_changed: "Renovação DHCP",
+};
+
+const formatDate = (value: string | null) => (value ? new Date(value).toLocaleString("pt-PT") : "actual");
+
+export default function NetworkMap() {
+    const [subnets, setSubnets] = useState<NetworkSubnet[]>([]);
+    const [conflicts, setConflicts] = useState<NetworkConflict[]>([]);
+    const [changes, setChanges] = useState<IpChangeEvent[]>([]);
+    const [query, setQuery] = useState("");
+    const [queryAt, setQueryAt] = useState("");
+    const [history, setHistory] = useState<IpAssignment[] | null>(null);
+    const [queryError, setQueryError] = useState<string | null>(null);
+    const [devices, setDevices] = useState<DiscoveredDevice[]>([]);
+    const [unmanagedOnly, setUnmanagedOnly] = useState(true);
+
+    useEffect(() => {
+        invoke<NetworkSubnet[]>("get_network_map").then(setSubnets).catch(console.error);
+        invoke<NetworkConflict[]>("get_network_conflicts").then(setConflicts).catch(console.error);
+        invoke<IpChangeEvent[]>("list_ip_changes", { unexpectedOnly: true, limit: 50 }).then(setChanges).catch(console.error);
+    }, []);
+
+    useEffect(() => {
+        invoke<DiscoveredDevice[]>("list_discovered_devices", { unmanagedOnly }).then(setDevices).catch(console.error);
+    }, [unmanagedOnly]);
+
+    const searchAssignments = async () => {
+        if (!query.trim()) return;
+        try {
+            setQueryError(null);
+            const at = queryAt ? new Date(queryAt).toISOString() : null;
+            setHistory(await invoke<IpAssignment[]>("find_ip_assignments", { value: query, at }));
+        } catch (err) {
+            setHistory(null);
+            setQueryError(String(err));
+        }
+    };
+
+    return (
+        <div className="space-y-6 animate-fade-in">
+            <div>
+                <h1 className="text-3xl font-bold text-white mb-2 tracking-tight">Mapa de Rede</h1>
+                <p className="text-emerald-400/80 font-medium">Máquinas por subrede, conflitos de IP/MAC e histórico de endereços</p>
+            </div>
+
+            {/* Conflitos */}
+            <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                <h2 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                    <AlertTriangle className="w-5 h-5 text-amber-400" /> Conflitos ({conflicts.length})
+                </h2>
+                {conflicts.length === 0 ? (
+                    <p className="text-slate-500 text-sm">Sem IPs ou MACs repetidos entre máquinas activas.</p>
+                ) : (
+                    <div className="space-y-3">
+                        {conflicts.map((c) => (
+                            <div key={`${c.conflict_type}-${c.value}-${c.gateway}`} className="bg-red-500/5 border border-red-500/20 rounded-xl p-4">
+                                <div className="text-sm mb-2">
+                                    <span className="text-red-400 font-medium">
+                                        {c.conflict_type === "duplicate_ip" ? "IP repetido" : "MAC repetido"}
+                                    </span>{" "}
+                                    <span className="font-mono text-white">{c.value}</span>
+                                    {c.gateway && <span className="text-slate-500"> (gateway {c.gateway})</span>}
+                                </div>
+                                <div className="flex flex-wrap gap-2">
+                                    {c.hosts.map((h) => (
+                                        <Link
+                                            key={`${h.machine_id}-${h.adapter_name}`}
+                                            to={`/machines/${h.machine_id}`}
+                                            className="px-3 py-1 bg-white/5 border border-white/10 rounded-lg text-sm text-white hover:border-emerald-500/50"
+                                        >
+                                            {h.hostname} <span className="text-slate-500 font-mono">{h.ip_address}</span>
+                                        </Link>
+                                    ))}
+                                </div>
+                            </div>
+                        ))}
+                    </div>
+                )}
+            </div>
+
+            {/* Mudanças inesperadas */}
+            <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                <h2 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                    <Shuffle className="w-5 h-5 text-amber-400" /> Mudanças de IP inesperadas
+                </h2>
+                {changes.length === 0 ? (
+                    <p className="text-slate-500 text-sm">Nenhuma mudança inesperada registada.</p>
+                ) : (
+                    <table className="w-full text-sm">
+                        <thead className="text-slate-400 text-left">
+                            <tr>
+                                <th className="py-2">Máquina</th>
+                                <th className="py-2">Adaptador</th>
+                                <th className="py-2">Mudança</th>
+                                <th className="py-2">Motivo</th>
+                                <th className="py-2">Quando</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {changes.map((c) => (
+                                <tr key={c.id} className="border-t border-white/5 text-white">
+                                    <td className="py-2">
+                                        <Link to={`/machines/${c.machine_id}`} className="hover:text-emerald-400">
+                                            {c.hostname || c.machine_id}
+                                        </Link>
+                                    </td>
+                                    <td className="py-2 text-slate-400">{c.adapter_name}</td>
+                                    <td className="py-2 font-mono">{c.old_ip} → {c.new_ip}</td>
+                                    <td className="py-2 text-amber-400">{REASON_LABELS[c.reason] || c.reason}</td>
+                                    <td className="py-2 text-slate-400">{formatDate(c.detected_at)}</td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                )}
+            </div>
+
+            {/* Subredes */}
+            <div className="grid grid-cols-1 lg:grid-cols-2 gap-6">
+                {subnets.map((s) => (
+                    <div key={`${s.subnet}-${s.gateway}`} className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                        <h2 className="text-lg font-bold text-white mb-1 flex items-center gap-2">
+                            <Network className="w-5 h-5 text-emerald-400" />
+                            <span className="font-mono">{s.subnet}</span>
+                        </h2>
+                        <p className="text-sm text-slate-500 mb-4">
+                            Gateway {s.gateway || "desconhecido"} · {s.hosts.length} {s.hosts.length === 1 ? "máquina" : "máquinas"}
+                        </p>
+                        <div className="space-y-1">
+                            {s.hosts.map((h) => (
+                                <Link
+                                    key={`${h.machine_id}-${h.adapter_name}-${h.ip_address}`}
+                                    to={`/machines/${h.machine_id}`}
+                                    className="flex items-center justify-between px-3 py-2 rounded-lg hover:bg-white/5 text-sm"
+                                >
+                                    <span className="flex items-center gap-2 text-white">
+                                        <span className={`w-2 h-2 rounded-full ${h.is_online ? "bg-emerald-400" : "bg-slate-600"}`} />
+                                        {h.hostname}
+                                        {h.kind === "vpn" && <span className="text-xs text-violet-400">VPN</span>}
+                                        {h.unexpected_ip_change && (
+                                            <span title="Mudança de IP inesperada nos últimos 7 dias">
+                                                <AlertTriangle className="w-3 h-3 text-amber-400" />
+                                            </span>
+                                        )}
+                                    </span>
+                                    <span className="font-mono text-slate-400">
+                                        {h.ip_address} <span className="text-slate-600">{h.mac_address}</span>
+                                    </span>
+                                </Link>
+                            ))}
+                        </div>
+                    </div>
+                ))}
+            </div>
+
+            {/* Equipamentos descobertos */}
+            <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                <div className="flex items-center justify-between mb-4">
+                    <h2 className="text-lg font-bold text-white flex items-center gap-2">
+                        <Radar className="w-5 h-5 text-emerald-400" /> Equipamentos descobertos ({devices.length})
+                    </h2>
+                    <label className="flex items-center gap-2 text-sm text-slate-400">
+                        <input type="checkbox" checked={unmanagedOnly} onChange={(e) => setUnmanagedOnly(e.target.checked)} />
+                        Só não geridos
+                    </label>
+                </div>
+                {devices.length === 0 ? (
+                    <p className="text-slate-500 text-sm">
+                        Nenhum equipamento{unmanagedOnly ? " não gerido" : ""}. Active <span className="font-mono">report_neighbors</span> nos agentes para recolher as tabelas ARP.
+                    </p>
+                ) : (
+                    <table className="w-full text-sm">
+                        <thead className="text-slate-400 text-left">
+                            <tr>
+                                <th className="py-2">IP</th>
+                                <th className="py-2">MAC</th>
+                                <th className="py-2">Fabricante</th>
+                                <th className="py-2">Estado</th>
+                                <th className="py-2">Visto por</th>
+                                <th className="py-2">Primeira vez</th>
+                                <th className="py-2">Última vez</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {devices.map((d) => (
+                                <tr key={d.id} className="border-t border-white/5 text-white">
+                                    <td className="py-2 font-mono">{d.ip_address}</td>
+                                    <td className="py-2 font-mono text-slate-400">{d.mac_address}</td>
+                                    <td className="py-2 text-slate-400">{d.vendor || (d.random_mac ? "MAC aleatório" : "Desconhecido")}</td>
+                                    <td className="py-2">
+                                        {d.managed_machine_id ? (
+                                            <Link to={`/machines/${d.managed_machine_id}`} className="text-emerald-400 hover:underline">
+                                                Gerido ({d.managed_hostname})
+                                            </Link>
+                                        ) : (
+                                            <span className="px-2 py-0.5 bg-amber-500/10 border border-amber-500/30 rounded text-amber-400 text-xs">Não gerido</span>
+                                        )}
+                                    </td>
+                                    <td className="py-2 text-slate-400">{d.reported_by_hostname || d.reported_by}</td>
+                                    <td className="py-2 text-slate-400">{formatDate(d.first_seen)}</td>
+                                    <td className="py-2 text-slate-400">{formatDate(d.last_seen)}</td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                )}
+            </div>
+
+            {/* Histórico de atribuições */}
+            <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                <h2 className="text-lg font-bold text-white mb-4 flex items-center gap-2">
+                    <History className="w-5 h-5 text-emerald-400" /> Quem tinha este IP/MAC?
+                </h2>
+                <div className="flex gap-3 mb-4">
+                    <div className="relative flex-1">
+                        <Search className="absolute left-3 top-3.5 w-4 h-4 text-slate-500" />
+                        <input
+                            type="text"
+                            placeholder="ex: 10.0.0.15 ou AA:BB:CC:DD:EE:FF"
+                            value={query}
+                            onChange={(e) => setQuery(e.target.value)}
+                            onKeyDown={(e) => e.key === "Enter" && searchAssignments()}
+                            className="w-full pl-10 p-3 bg-white/5 border border-white/10 rounded-xl text-white outline-none focus:border-emerald-500/50"
+                        />
+                    </div>
+                    <input
+                        type="datetime-local"
+                        value={queryAt}
+                        onChange={(e) => setQueryAt(e.target.value)}
+                        className="p-3 bg-white/5 border border-white/10 rounded-xl text-white outline-none"
+                    />
+                    <button onClick={searchAssignments} className="px-5 bg-emerald-600 hover:bg-emerald-500 rounded-xl text-white font-medium">
+                        Procurar
+                    </button>
+                </div>
+                {queryError && <p className="text-red-400 text-sm">{queryError}</p>}
+                {history && (history.length === 0 ? (
+                    <p className="text-slate-500 text-sm">Nenhuma máquina teve este endereço{queryAt ? " nesse instante" : ""}.</p>
+                ) : (
+                    <table className="w-full text-sm">
+                        <thead className="text-slate-400 text-left">
+                            <tr>
+                                <th className="py-2">Máquina</th>
+                                <th className="py-2">Adaptador</th>
+                                <th className="py-2">IP</th>
+                                <th className="py-2">MAC</th>
+                                <th className="py-2">Desde</th>
+                                <th className="py-2">Até</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {history.map((a) => (
+                                <tr key={a.id} className="border-t border-white/5 text-white">
+                                    <td className="py-2">
+                                        <Link to={`/machines/${a.machine_id}`} className="hover:text-emerald-400">
+                                            {a.hostname || a.machine_id}
+                                        </Link>
+                                    </td>
+                                    <td className="py-2 text-slate-400">{a.adapter_name}</td>
+                                    <td className="py-2 font-mono">{a.ip_address}</td>
+                                    <td className="py-2 font-mono text-slate-400">{a.mac_address || "—"}</td>
+                                    <td className="py-2 text-slate-400">{formatDate(a.first_seen)}</td>
+                                    <td className="py-2 text-slate-400">{a.ended_at ? formatDate(a.ended_at) : `actual (visto ${formatDate(a.last_seen)})`}</td>
+                                </tr>
+                            ))}
+                        </tbody>
+                    </table>
+                ))}
+            </div>
+        </div>
+    );
+}
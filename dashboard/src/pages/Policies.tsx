@@ -1,28 +1,65 @@
-import { useState } from "react";
-import { Plus, Trash2, Globe, MonitorPlay, Usb, FolderLock, Network, Clock, ShieldAlert } from "lucide-react";
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Plus, Trash2, History, ShieldCheck, Globe, MonitorPlay, Usb, FolderLock, Network, Clock, ShieldAlert, Camera, ListChecks, Layers, Pencil } from "lucide-react";
 import { useMachines } from "../hooks/useMachines";
-import { usePolicies } from "../hooks/usePolicies";
+import { usePolicies, Policy, PolicyConflict } from "../hooks/usePolicies";
+import PolicyRevisions from "../components/PolicyRevisions";
+import PolicyCompliance from "../components/PolicyCompliance";
+import AllowlistCandidates from "../components/AllowlistCandidates";
+import DnsStatsPanel from "../components/DnsStatsPanel";
+import PolicyConflicts from "../components/PolicyConflicts";
+import EffectivePolicies from "../components/EffectivePolicies";
+import PolicyEditor from "../components/PolicyEditor";
+
+const isAllowlist = (policy: Policy) => {
+    try {
+        return JSON.parse(policy.config_json).mode === "allowlist";
+    } catch {
+        return false;
+    }
+};
 
 export default function Policies() {
     const { machines } = useMachines();
-    const { policies, createPolicy, deletePolicy } = usePolicies();
+    const { policies, createPolicy, deletePolicy, refresh } = usePolicies();
 
     const [showModal, setShowModal] = useState(false);
+    const [historyFor, setHistoryFor] = useState<Policy | null>(null);
+    const [editing, setEditing] = useState<Policy | null>(null);
+    const [complianceFor, setComplianceFor] = useState<Policy | null>(null);
+    const [candidatesFor, setCandidatesFor] = useState<Policy | null>(null);
+    const [showEffective, setShowEffective] = useState(false);
+    const [createdConflicts, setCreatedConflicts] = useState<{ id: string; name: string; conflicts: PolicyConflict[] } | null>(null);
+    const [impacts, setImpacts] = useState<Record<string, { affected_machines: number; affected_processes: number; total_matches: number }>>({});
+
+    // Regras em modo "alert": quantas máquinas/processos teriam sido afectados nos últimos 7 dias
+    useEffect(() => {
+        policies.filter(p => p.action === "alert").forEach(async (p) => {
+            try {
+                const impact = await invoke<any>("get_policy_impact", { policyId: p.id, days: 7 });
+                setImpacts(prev => ({ ...prev, [p.id]: impact }));
+            } catch (error) {
+                console.error("Failed to load policy impact", error);
+            }
+        });
+    }, [policies]);
     const [activeTab, setActiveTab] = useState("app");
 
     const [formData, setFormData] = useState({
         name: "", machineId: "all", priority: "1", action: "block", reason: "",
         schedule: "always", allowAdmin: false,
         // Web
-        webTargetType: "domain", blockMethod: "firewall", domains: "",
+        webTargetType: "domain", blockMethod: "firewall", domains: "", blockDoh: false,
         // App
-        matchType: "name", target: "", hashValue: "",
+        matchType: "name", target: "", hashValue: "", appMode: "rules",
         // Device
-        deviceAction: "block_usb", allowedSerials: "",
+        deviceAction: "block_all", allowedSerials: "",
         // Folder
         restrictedFolders: [] as string[],
         // Network
         blockedPorts: "",
+        // Monitoring
+        screenshotsAllowed: true, screenshotNotify: true,
     });
 
     const toggleFolder = (folder: string) => {
@@ -42,29 +79,48 @@ export default function Policies() {
             configJson.domains = domainsList;
             configJson.block_method = formData.blockMethod;
             configJson.target_type = formData.webTargetType;
+            configJson.block_doh = formData.blockDoh;
             targetValue = domainsList.length > 0 ? domainsList[0] : "Multi-domínios";
         } else if (activeTab === "app") {
             configJson.match_type = formData.matchType;
             configJson.hash_value = formData.hashValue || null;
+            if (formData.appMode !== "rules") {
+                // Allowlist: só correm executáveis que coincidam com regras "allow"
+                configJson.mode = "allowlist";
+                configJson.learning = formData.appMode === "allowlist_learning";
+                targetValue = "*";
+            }
         } else if (activeTab === "device") {
             configJson.device_action = formData.deviceAction;
-            configJson.allowed_serials = formData.allowedSerials.split("\n").map(s => s.trim());
-            targetValue = "Regras de Dispositivos USB";
+            configJson.access = formData.deviceAction === "read_only" ? "read_only" : "block";
+            configJson.allowed_serials = formData.deviceAction === "whitelist_only"
+                ? formData.allowedSerials.split("\n").map(s => s.trim()).filter(s => s !== "") : [];
+            targetValue = "mass_storage";
         } else if (activeTab === "folder") {
             configJson.restricted_folders = formData.restrictedFolders;
             targetValue = "Restrições de Sistema";
         } else if (activeTab === "network") {
             configJson.blocked_ports = formData.blockedPorts;
             targetValue = "Portas de Rede Bloqueadas";
+        } else if (activeTab === "monitoring") {
+            configJson.notify_user = formData.screenshotNotify;
+            targetValue = "screenshot";
         }
 
-        await createPolicy({
+        // Monitorização: a regra permite ou proíbe capturas de ecrã
+        const action = activeTab === "monitoring"
+            ? (formData.screenshotsAllowed ? "allow" : "block") : formData.action;
+
+        const result = await createPolicy({
             name: formData.name, machineId: formData.machineId, priority: formData.priority,
-            policyType: activeTab, target: targetValue || "*", action: formData.action,
+            policyType: activeTab, target: targetValue || "*", action,
             configJson: JSON.stringify(configJson), reason: formData.reason
         });
 
         setShowModal(false);
+        if (result.conflicts.length > 0) {
+            setCreatedConflicts({ id: result.id, name: formData.name || "Nova Política", conflicts: result.conflicts });
+        }
     };
 
     return (
@@ -74,9 +130,14 @@ export default function Policies() {
                     <h1 className="text-2xl font-bold text-white mb-2">Políticas de Segurança Enterprise</h1>
                     <p className="text-slate-400">{policies.length} regra(s) ativa(s) na rede</p>
                 </div>
-                <button onClick={() => setShowModal(true)} className="flex items-center gap-2 px-4 py-2 bg-emerald-600 text-white rounded-lg hover:bg-emerald-700 transition-colors">
-                    <Plus className="w-5 h-5" /><span>Nova Regra</span>
-                </button>
+                <div className="flex items-center gap-2">
+                    <button onClick={() => setShowEffective(true)} className="flex items-center gap-2 px-4 py-2 border border-white/10 text-white rounded-lg hover:bg-white/5 transition-colors">
+                        <Layers className="w-5 h-5" /><span>Políticas por Máquina</span>
+                    </button>
+                    <button onClick={() => setShowModal(true)} className="flex items-center gap-2 px-4 py-2 bg-emerald-600 text-white rounded-lg hover:bg-emerald-700 transition-colors">
+                        <Plus className="w-5 h-5" /><span>Nova Regra</span>
+                    </button>
+                </div>
             </div>
 
             <div className="bg-[#0a0a0a] border border-white/10 rounded-xl overflow-hidden">
@@ -99,8 +160,27 @@ export default function Policies() {
                                     <span className={`px-2 py-1 rounded-full text-xs ${policy.action === "block" ? "bg-red-500/20 text-red-400" : "bg-emerald-500/20 text-emerald-400"}`}>
                                         {policy.action}
                                     </span>
+                                    {impacts[policy.id] && (
+                                        <p className="text-xs text-slate-500 mt-1">
+                                            Teria afectado {impacts[policy.id].affected_machines} máquina(s) e {impacts[policy.id].affected_processes} processo(s) nos últimos 7 dias ({impacts[policy.id].total_matches} ocorrências)
+                                        </p>
+                                    )}
                                 </td>
                                 <td className="p-4 text-right">
+                                    {isAllowlist(policy) && (
+                                        <button onClick={() => setCandidatesFor(policy)} title="Candidatos à allowlist" className="p-2 text-slate-400 hover:bg-white/10 rounded-lg transition-colors">
+                                            <ListChecks className="w-4 h-4" />
+                                        </button>
+                                    )}
+                                    <button onClick={() => setComplianceFor(policy)} title="Conformidade por máquina" className="p-2 text-slate-400 hover:bg-white/10 rounded-lg transition-colors">
+                                        <ShieldCheck className="w-4 h-4" />
+                                    </button>
+                                    <button onClick={() => setEditing(policy)} title="Editar (com rollout canary opcional)" className="p-2 text-slate-400 hover:bg-white/10 rounded-lg transition-colors">
+                                        <Pencil className="w-4 h-4" />
+                                    </button>
+                                    <button onClick={() => setHistoryFor(policy)} title={`Revisão ${policy.revision} — histórico e rollback`} className="p-2 text-slate-400 hover:bg-white/10 rounded-lg transition-colors">
+                                        <History className="w-4 h-4" />
+                                    </button>
                                     <button onClick={() => deletePolicy(policy.id)} className="p-2 text-red-400 hover:bg-red-500/20 rounded-lg transition-colors">
                                         <Trash2 className="w-4 h-4" />
                                     </button>
@@ -111,6 +191,37 @@ export default function Policies() {
                 </table>
             </div>
 
+            <DnsStatsPanel />
+
+            {candidatesFor && (
+                <AllowlistCandidates policy={candidatesFor} onClose={() => setCandidatesFor(null)} onAllow={createPolicy} />
+            )}
+
+            {complianceFor && (
+                <PolicyCompliance policy={complianceFor} machines={machines} onClose={() => setComplianceFor(null)} />
+            )}
+
+            {createdConflicts && (
+                <PolicyConflicts
+                    policyId={createdConflicts.id}
+                    policyName={createdConflicts.name}
+                    conflicts={createdConflicts.conflicts}
+                    onClose={() => setCreatedConflicts(null)}
+                />
+            )}
+
+            {showEffective && (
+                <EffectivePolicies machines={machines} onClose={() => setShowEffective(false)} />
+            )}
+
+            {editing && (
+                <PolicyEditor policy={editing} onClose={() => setEditing(null)} onSaved={refresh} />
+            )}
+
+            {historyFor && (
+                <PolicyRevisions policy={historyFor} onClose={() => setHistoryFor(null)} onChanged={refresh} />
+            )}
+
             {showModal && (
                 <div className="fixed inset-0 bg-black/80 backdrop-blur-sm flex items-center justify-center z-50 p-4">
                     <div className="bg-[#0a0a0a] border border-white/10 rounded-2xl w-full max-w-5xl max-h-[90vh] flex flex-col shadow-2xl">
@@ -125,6 +236,7 @@ export default function Policies() {
                             <button onClick={() => setActiveTab("device")} className={`px-4 py-4 flex items-center gap-2 font-medium ${activeTab === "device" ? "text-emerald-400 border-b-2 border-emerald-400" : "text-slate-400 hover:text-white"}`}><Usb className="w-4 h-4" /> Device Control</button>
                             <button onClick={() => setActiveTab("folder")} className={`px-4 py-4 flex items-center gap-2 font-medium ${activeTab === "folder" ? "text-emerald-400 border-b-2 border-emerald-400" : "text-slate-400 hover:text-white"}`}><FolderLock className="w-4 h-4" /> Folder Restrictions</button>
                             <button onClick={() => setActiveTab("network")} className={`px-4 py-4 flex items-center gap-2 font-medium ${activeTab === "network" ? "text-emerald-400 border-b-2 border-emerald-400" : "text-slate-400 hover:text-white"}`}><Network className="w-4 h-4" /> Network Rules</button>
+                            <button onClick={() => setActiveTab("monitoring")} className={`px-4 py-4 flex items-center gap-2 font-medium ${activeTab === "monitoring" ? "text-emerald-400 border-b-2 border-emerald-400" : "text-slate-400 hover:text-white"}`}><Camera className="w-4 h-4" /> Monitorização</button>
                         </div>
 
                         <div className="p-6 overflow-y-auto flex-1 space-y-8">
@@ -145,6 +257,7 @@ export default function Policies() {
                                     <label className="block text-sm text-slate-400 mb-1">Ação</label>
                                     <select value={formData.action} onChange={e => setFormData({ ...formData, action: e.target.value })} className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white outline-none">
                                         <option value="block">Bloquear / Terminar</option>
+                                        <option value="allow">Permitir (excepção / allowlist)</option>
                                         <option value="alert">Apenas Alertar</option>
                                         <option value="ask">Pedir Autorização Admin</option>
                                     </select>
@@ -172,13 +285,22 @@ export default function Policies() {
                             {/* ABAS ESPECÍFICAS */}
                             {activeTab === "app" && (
                                 <div className="space-y-4 animate-fade-in">
+                                    <div>
+                                        <label className="block text-sm text-slate-400 mb-1">Modo</label>
+                                        <select value={formData.appMode} onChange={e => setFormData({ ...formData, appMode: e.target.value })} className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white outline-none">
+                                            <option value="rules">Regra individual (bloquear / permitir)</option>
+                                            <option value="allowlist">Allowlist — bloquear tudo o que não estiver permitido</option>
+                                            <option value="allowlist_learning">Allowlist em aprendizagem — apenas registar</option>
+                                        </select>
+                                    </div>
                                     <div className="grid grid-cols-2 gap-4">
                                         <div>
                                             <label className="block text-sm text-slate-400 mb-1">Tipo de Identificação</label>
                                             <select value={formData.matchType} onChange={e => setFormData({ ...formData, matchType: e.target.value })} className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white outline-none">
                                                 <option value="name">Nome do Executável (ex: fraps.exe)</option>
-                                                <option value="path">Caminho Completo</option>
+                                                <option value="path">Caminho Completo (aceita * e ?)</option>
                                                 <option value="hash">Por Hash do ficheiro (Mais Seguro)</option>
+                                                <option value="original_filename">Nome Original (Recurso de Versão)</option>
                                                 <option value="publisher">Por Publisher (Assinatura)</option>
                                             </select>
                                         </div>
@@ -220,6 +342,12 @@ export default function Policies() {
                                         <label className="block text-sm text-slate-400 mb-1">Lista de Domínios (Um por linha)</label>
                                         <textarea value={formData.domains} onChange={e => setFormData({ ...formData, domains: e.target.value })} rows={3} placeholder="facebook.com&#10;youtube.com" className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white font-mono text-sm outline-none"></textarea>
                                     </div>
+                                    {formData.blockMethod === "dns" && (
+                                        <label className="flex items-center gap-2 text-sm text-slate-400">
+                                            <input type="checkbox" checked={formData.blockDoh} onChange={e => setFormData({ ...formData, blockDoh: e.target.checked })} className="rounded bg-black/50 border-white/10" />
+                                            Bloquear DNS-over-HTTPS (os browsers voltam a usar o DNS do sistema)
+                                        </label>
+                                    )}
                                 </div>
                             )}
 
@@ -229,6 +357,7 @@ export default function Policies() {
                                     <select value={formData.deviceAction} onChange={e => setFormData({ ...formData, deviceAction: e.target.value })} className="w-full p-2.5 bg-black/50 border border-white/10 rounded-lg text-white outline-none mb-4">
                                         <option value="block_all">Bloquear todos os USBs de Armazenamento</option>
                                         <option value="whitelist_only">Permitir apenas USBs Autorizados</option>
+                                        <option value="read_only">Apenas Leitura (discos removíveis)</option>
                                     </select>
                                     <div>
                                         <label className="block text-sm text-slate-400 mb-1">Whitelists (Serials Autorizados)</label>
@@ -260,6 +389,20 @@ export default function Policies() {
                                     <p className="text-xs text-amber-500/80 mt-2">* Funcionalidade avançada baseada no Windows Defender Firewall</p>
                                 </div>
                             )}
+
+                            {activeTab === "monitoring" && (
+                                <div className="space-y-4 animate-fade-in">
+                                    <label className="flex items-center gap-3 p-3 bg-black/30 border border-white/5 rounded-lg cursor-pointer hover:bg-white/5 transition-colors">
+                                        <input type="checkbox" checked={formData.screenshotsAllowed} onChange={e => setFormData({ ...formData, screenshotsAllowed: e.target.checked })} className="w-4 h-4 accent-emerald-500" />
+                                        <span className="text-sm text-white">Permitir capturas de ecrã a pedido</span>
+                                    </label>
+                                    <label className="flex items-center gap-3 p-3 bg-black/30 border border-white/5 rounded-lg cursor-pointer hover:bg-white/5 transition-colors">
+                                        <input type="checkbox" checked={formData.screenshotNotify} onChange={e => setFormData({ ...formData, screenshotNotify: e.target.checked })} className="w-4 h-4 accent-emerald-500" />
+                                        <span className="text-sm text-white">Avisar o utilizador em cada captura</span>
+                                    </label>
+                                    <p className="text-xs text-amber-500/80 mt-2">* Sem uma regra a permitir, as capturas estão desactivadas. Cada visualização fica registada na auditoria.</p>
+                                </div>
+                            )}
                         </div>
 
                         <div className="p-6 border-t border-white/10 flex justify-end gap-3 bg-black/20">
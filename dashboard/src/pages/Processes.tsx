@@ -29,7 +29,7 @@ export default function ProcessesTab({ machineId }: Props) {
     const killProcess = async (proc: ProcessInfo) => {
         if (!confirm(`Terminar o processo ${proc.name}?`)) return;
         try {
-            await invoke("kill_process", { machine_id: machineId, pid: proc.pid });
+            await invoke("kill_process", { machineId, pid: proc.pid, processName: proc.name });
             toast.success(`Sinal enviado para ${proc.name}`); loadProcesses();
         } catch (err) { toast.error(`Erro: ${err}`); }
     };
@@ -1,6 +1,6 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Clock, MonitorSmartphone, Search } from "lucide-react";
+import { Clock, MonitorSmartphone, Search, TrendingUp, Users, Timer } from "lucide-react";
 import { useMachines } from "../hooks/useMachines";
 
 interface ScreenTimeEntry {
@@ -8,6 +8,42 @@ interface ScreenTimeEntry {
     app_name: string;
     total_seconds: number;
     date: string;
+    category: string;
+    hourly_seconds: number[];
+    user_name: string;
+}
+
+interface UsageTopApp {
+    group_name: string;
+    app_name: string;
+    category: string;
+    total_seconds: number;
+}
+
+interface UsageCategoryTotal {
+    group_name: string;
+    category: string;
+    productivity: string; // productive | unproductive | neutral
+    total_seconds: number;
+}
+
+interface UsageWeeklyTrend {
+    week_start: string;
+    productive_seconds: number;
+    unproductive_seconds: number;
+    neutral_seconds: number;
+    total_seconds: number;
+}
+
+interface QuotaUsage {
+    policy_id: string;
+    policy_name: string;
+    machine_id: string;
+    hostname: string;
+    target: string;
+    quota_seconds: number;
+    used_seconds: number;
+    exceeded: boolean;
 }
 
 export default function ScreenTime() {
@@ -15,6 +51,29 @@ export default function ScreenTime() {
     const [selectedMachine, setSelectedMachine] = useState<string>("all");
     const [entries, setEntries] = useState<ScreenTimeEntry[]>([]);
     const [searchFilter, setSearchFilter] = useState("");
+    const [groupBy, setGroupBy] = useState<"user" | "department">("user");
+    const [topApps, setTopApps] = useState<UsageTopApp[]>([]);
+    const [categoryTotals, setCategoryTotals] = useState<UsageCategoryTotal[]>([]);
+    const [weekly, setWeekly] = useState<UsageWeeklyTrend[]>([]);
+    const [quotas, setQuotas] = useState<QuotaUsage[]>([]);
+
+    useEffect(() => {
+        const load = async () => {
+            try {
+                setTopApps(await invoke<UsageTopApp[]>("get_top_apps", { groupBy, limit: 5 }));
+                setCategoryTotals(await invoke<UsageCategoryTotal[]>("get_category_totals", { groupBy }));
+            } catch (err) {
+                console.error(err);
+            }
+        };
+        load();
+    }, [groupBy]);
+
+    useEffect(() => {
+        const machineId = selectedMachine === "all" ? null : selectedMachine;
+        invoke<UsageWeeklyTrend[]>("get_weekly_usage_trend", { machineId, weeks: 8 }).then(setWeekly).catch(console.error);
+        invoke<QuotaUsage[]>("get_quota_usage").then(setQuotas).catch(console.error);
+    }, [selectedMachine]);
 
     useEffect(() => {
         // Num cenário real, "all" iria buscar o agregado ao backend.
@@ -27,7 +86,8 @@ export default function ScreenTime() {
 
     const loadScreenTime = async (machineId: string) => {
         try {
-            const today = new Date().toISOString().split('T')[0];
+            const now = new Date();
+            const today = `${now.getFullYear()}-${String(now.getMonth() + 1).padStart(2, '0')}-${String(now.getDate()).padStart(2, '0')}`;
             const result = await invoke<ScreenTimeEntry[]>("get_screen_time", {
                 machineId: machineId,
                 date: today,
@@ -45,6 +105,13 @@ export default function ScreenTime() {
     };
 
     const maxTime = entries.length > 0 ? entries[0].total_seconds : 1;
+    const maxWeek = Math.max(1, ...weekly.map(w => w.total_seconds));
+    const groups = Array.from(new Set(topApps.map(a => a.group_name)));
+    const productivityTotals = (group: string) => {
+        const rows = categoryTotals.filter(c => c.group_name === group);
+        const sum = (p: string) => rows.filter(r => r.productivity === p).reduce((acc, r) => acc + r.total_seconds, 0);
+        return { productive: sum("productive"), unproductive: sum("unproductive"), neutral: sum("neutral") };
+    };
     const filteredEntries = entries.filter(e => e.app_name.toLowerCase().includes(searchFilter.toLowerCase()));
 
     return (
@@ -111,6 +178,84 @@ export default function ScreenTime() {
                     )}
                 </div>
             </div>
+
+            <div className="grid grid-cols-1 lg:grid-cols-2 gap-6">
+                <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                    <div className="flex items-center justify-between mb-4">
+                        <h2 className="text-lg font-bold text-white flex items-center gap-2"><Users className="w-5 h-5 text-emerald-400" /> Top apps (7 dias)</h2>
+                        <select value={groupBy} onChange={(e) => setGroupBy(e.target.value as "user" | "department")} className="p-2 bg-white/5 border border-white/10 rounded-lg text-white text-sm outline-none">
+                            <option value="user">Por utilizador</option>
+                            <option value="department">Por departamento</option>
+                        </select>
+                    </div>
+                    {groups.length === 0 ? (
+                        <p className="text-slate-500 text-sm">Sem dados no período.</p>
+                    ) : (
+                        <div className="space-y-4">
+                            {groups.map(group => {
+                                const totals = productivityTotals(group);
+                                return (
+                                    <div key={group} className="bg-white/5 border border-white/10 rounded-xl p-4">
+                                        <div className="flex justify-between text-sm mb-2">
+                                            <span className="text-white font-medium">{group}</span>
+                                            <span>
+                                                <span className="text-emerald-400">{formatTime(totals.productive)} produtivo</span>
+                                                <span className="text-slate-500 mx-2">·</span>
+                                                <span className="text-rose-400">{formatTime(totals.unproductive)} improdutivo</span>
+                                            </span>
+                                        </div>
+                                        {topApps.filter(a => a.group_name === group).map(app => (
+                                            <div key={app.app_name} className="flex justify-between text-xs text-slate-400 py-0.5">
+                                                <span>{app.app_name} <span className="text-slate-600">({app.category})</span></span>
+                                                <span>{formatTime(app.total_seconds)}</span>
+                                            </div>
+                                        ))}
+                                    </div>
+                                );
+                            })}
+                        </div>
+                    )}
+                </div>
+
+                <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                    <h2 className="text-lg font-bold text-white mb-4 flex items-center gap-2"><TrendingUp className="w-5 h-5 text-emerald-400" /> Tendência semanal</h2>
+                    {weekly.length === 0 ? (
+                        <p className="text-slate-500 text-sm">Sem dados no período.</p>
+                    ) : (
+                        <div className="space-y-3">
+                            {weekly.map(week => (
+                                <div key={week.week_start} className="flex items-center gap-4 text-sm">
+                                    <span className="w-24 text-slate-400 shrink-0">{new Date(week.week_start).toLocaleDateString('pt-PT')}</span>
+                                    <div className="flex-1">
+                                        <div className="flex h-3 rounded-full overflow-hidden bg-white/5" style={{ width: `${(week.total_seconds / maxWeek) * 100}%` }}>
+                                            <div className="bg-emerald-500" style={{ width: `${(week.productive_seconds / Math.max(1, week.total_seconds)) * 100}%` }} />
+                                            <div className="bg-slate-500" style={{ width: `${(week.neutral_seconds / Math.max(1, week.total_seconds)) * 100}%` }} />
+                                            <div className="bg-rose-500" style={{ width: `${(week.unproductive_seconds / Math.max(1, week.total_seconds)) * 100}%` }} />
+                                        </div>
+                                    </div>
+                                    <span className="w-16 text-right text-white shrink-0">{formatTime(week.total_seconds)}</span>
+                                </div>
+                            ))}
+                        </div>
+                    )}
+                </div>
+            </div>
+
+            {quotas.length > 0 && (
+                <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-6 shadow-2xl">
+                    <h2 className="text-lg font-bold text-white mb-4 flex items-center gap-2"><Timer className="w-5 h-5 text-amber-400" /> Quotas diárias (hoje)</h2>
+                    <div className="space-y-2">
+                        {quotas.map(q => (
+                            <div key={`${q.policy_id}-${q.machine_id}`} className="flex items-center justify-between bg-white/5 border border-white/10 rounded-xl px-4 py-2 text-sm">
+                                <span className="text-white">{q.hostname} <span className="text-slate-500">· {q.target} ({q.policy_name})</span></span>
+                                <span className={q.exceeded ? "text-rose-400" : "text-emerald-400"}>
+                                    {formatTime(q.used_seconds)} / {formatTime(q.quota_seconds)}
+                                </span>
+                            </div>
+                        ))}
+                    </div>
+                </div>
+            )}
         </div>
     );
 }
\ No newline at end of file
@@ -1,6 +1,41 @@
-import { Key } from "lucide-react";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Key, ShieldAlert } from "lucide-react";
+
+interface HardwareChangeRule {
+    component: string;
+    suspicious: boolean;
+}
+
+const COMPONENT_LABELS: Record<string, string> = {
+    serial_number: "Número de série",
+    motherboard: "Motherboard",
+    bios: "BIOS",
+    disk: "Discos",
+    memory: "Módulos de memória",
+    gpu: "Placas gráficas",
+};
 
 export default function Settings() {
+    const [rules, setRules] = useState<HardwareChangeRule[]>([]);
+
+    useEffect(() => {
+        invoke<HardwareChangeRule[]>("get_hardware_change_rules")
+            .then(setRules)
+            .catch((err) => console.error("Erro ao carregar regras de hardware:", err));
+    }, []);
+
+    const toggleRule = async (rule: HardwareChangeRule) => {
+        try {
+            await invoke("set_hardware_change_rule", { component: rule.component, suspicious: !rule.suspicious });
+            setRules((prev) =>
+                prev.map((r) => (r.component === rule.component ? { ...r, suspicious: !r.suspicious } : r))
+            );
+        } catch (err) {
+            console.error("Erro ao guardar regra:", err);
+        }
+    };
+
     return (
         <div className="space-y-6">
             <div>
@@ -23,6 +58,34 @@ export default function Settings() {
                 </div>
             </div>
 
+            <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-8 shadow-2xl">
+                <div className="flex items-center gap-4 mb-4">
+                    <div className="p-3 bg-amber-500/10 rounded-2xl border border-amber-500/20">
+                        <ShieldAlert className="w-6 h-6 text-amber-400" />
+                    </div>
+                    <h3 className="text-xl font-bold text-white">Alterações de Hardware Suspeitas</h3>
+                </div>
+                <p className="text-slate-400 mb-6 max-w-2xl">
+                    Todas as alterações ficam no histórico e geram o evento hardware.changed; as dos componentes marcados são assinaladas como possível adulteração.
+                </p>
+                <div className="grid grid-cols-2 md:grid-cols-3 gap-3">
+                    {rules.map((rule) => (
+                        <label
+                            key={rule.component}
+                            className="flex items-center justify-between bg-white/5 border border-white/10 rounded-2xl px-5 py-4 cursor-pointer"
+                        >
+                            <span className="text-white text-sm">{COMPONENT_LABELS[rule.component] ?? rule.component}</span>
+                            <input
+                                type="checkbox"
+                                checked={rule.suspicious}
+                                onChange={() => toggleRule(rule)}
+                                className="w-4 h-4 accent-amber-500"
+                            />
+                        </label>
+                    ))}
+                </div>
+            </div>
+
             <div className="bg-[#0a0a0a]/40 backdrop-blur-xl border border-white/10 rounded-3xl p-8 shadow-2xl">
                 <h3 className="text-xl font-bold text-white mb-6">Informação do Sistema</h3>
                 <div className="grid grid-cols-3 gap-4">